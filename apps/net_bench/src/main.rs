@@ -92,7 +92,7 @@ fn sockaddr(ip: [u8; 4], port: u16) -> SockAddrIn {
     SockAddrIn {
         sin_family: AF_INET,
         sin_port: port.to_be(),
-        sin_addr: u32::from_be_bytes(ip),
+        sin_addr: u32::from_ne_bytes(ip),
         sin_zero: [0; 8],
     }
 }
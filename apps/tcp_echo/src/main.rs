@@ -38,7 +38,7 @@ const ECONNREFUSED: isize = -111;
 const ENETUNREACH: isize = -101;
 const EISCONN: isize = -106;
 
-const LOCAL_IP: [u8; 4] = [10, 0, 2, 15];
+const LOCAL_IP: [u8; 4] = [127, 0, 0, 1];
 const SERVER_PORT: u16 = 22345;
 const CLIENT_PORT: u16 = 22346;
 const FAIL_PORT: u16 = 22347;
@@ -332,7 +332,7 @@ fn sockaddr(ip: [u8; 4], port: u16) -> SockAddrIn {
     SockAddrIn {
         sin_family: AF_INET,
         sin_port: port.to_be(),
-        sin_addr: u32::from_be_bytes(ip),
+        sin_addr: u32::from_ne_bytes(ip),
         sin_zero: [0; 8],
     }
 }
@@ -362,7 +362,7 @@ fn iov_matches(a: &[u8], b: &[u8], expected: &[u8]) -> bool {
 fn sockaddr_matches(addr: &SockAddrIn, ip: [u8; 4], port: u16) -> bool {
     addr.sin_family == AF_INET
         && addr.sin_port == port.to_be()
-        && addr.sin_addr == u32::from_be_bytes(ip)
+        && addr.sin_addr == u32::from_ne_bytes(ip)
 }
 
 #[no_mangle]
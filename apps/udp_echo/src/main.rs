@@ -27,7 +27,7 @@ const MSG_DONTWAIT: usize = 0x40;
 const ETIMEDOUT: isize = -110;
 const EAGAIN: isize = -11;
 
-const LOCAL_IP: [u8; 4] = [10, 0, 2, 15];
+const LOCAL_IP: [u8; 4] = [127, 0, 0, 1];
 const SERVER_PORT: u16 = 22445;
 const CLIENT_PORT: u16 = 22446;
 
@@ -275,7 +275,7 @@ fn sockaddr(ip: [u8; 4], port: u16) -> SockAddrIn {
     SockAddrIn {
         sin_family: AF_INET,
         sin_port: port.to_be(),
-        sin_addr: u32::from_be_bytes(ip),
+        sin_addr: u32::from_ne_bytes(ip),
         sin_zero: [0; 8],
     }
 }
@@ -295,7 +295,7 @@ fn slices_equal(a: &[u8], b: &[u8]) -> bool {
 fn check_addr(addr: &SockAddrIn, port: u16) {
     if addr.sin_family != AF_INET
         || addr.sin_port != port.to_be()
-        || addr.sin_addr != u32::from_be_bytes(LOCAL_IP)
+        || addr.sin_addr != u32::from_ne_bytes(LOCAL_IP)
     {
         fail();
     }
@@ -18,6 +18,7 @@ pub enum VfsError {
     Io,
     Permission,
     Busy,
+    NoSpace,
     Unknown,
 }
 
@@ -89,15 +90,200 @@ pub struct Metadata {
     pub size: u64,
     /// Mode bits (permission + type).
     pub mode: u16,
+    /// Device number for character and block nodes (see `make_dev`).
+    pub rdev: u64,
+    /// Owning user id.
+    pub uid: u32,
+    /// Owning group id.
+    pub gid: u32,
 }
 
 impl Metadata {
     /// Construct metadata with the provided fields.
     pub const fn new(file_type: FileType, size: u64, mode: u16) -> Self {
-        Self { file_type, size, mode }
+        Self {
+            file_type,
+            size,
+            mode,
+            rdev: 0,
+            uid: 0,
+            gid: 0,
+        }
+    }
+
+    /// Attach a device number to the metadata.
+    pub const fn with_rdev(mut self, rdev: u64) -> Self {
+        self.rdev = rdev;
+        self
+    }
+
+    /// Attach an owner to the metadata.
+    pub const fn with_owner(mut self, uid: u32, gid: u32) -> Self {
+        self.uid = uid;
+        self.gid = gid;
+        self
     }
 }
 
+/// Read permission requested from `Credentials::may_access`.
+pub const ACCESS_READ: u8 = 4;
+/// Write permission requested from `Credentials::may_access`.
+pub const ACCESS_WRITE: u8 = 2;
+/// Execute (or directory search) permission requested from `Credentials::may_access`.
+pub const ACCESS_EXEC: u8 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Caller identity used for discretionary access checks.
+pub struct Credentials<'a> {
+    /// Filesystem user id.
+    pub uid: u32,
+    /// Filesystem group id.
+    pub gid: u32,
+    /// Supplementary group ids.
+    pub groups: &'a [u32],
+}
+
+impl<'a> Credentials<'a> {
+    /// Construct credentials from a filesystem uid/gid and supplementary groups.
+    pub const fn new(uid: u32, gid: u32, groups: &'a [u32]) -> Self {
+        Self { uid, gid, groups }
+    }
+
+    /// Return true when `gid` is the caller's group or one of its supplementary groups.
+    pub fn in_group(&self, gid: u32) -> bool {
+        self.gid == gid || self.groups.contains(&gid)
+    }
+
+    /// Check `want` (a mask of `ACCESS_*` bits) against the owner, group and other mode bits.
+    ///
+    /// uid 0 bypasses read/write checks and gets execute when any execute bit is set or the
+    /// inode is a directory, matching Linux `CAP_DAC_OVERRIDE`.
+    pub fn may_access(&self, meta: &Metadata, want: u8) -> bool {
+        let want = (want & 0o7) as u16;
+        if self.uid == 0 {
+            return want & ACCESS_EXEC as u16 == 0 || meta.file_type == FileType::Dir || meta.mode & 0o111 != 0;
+        }
+        let granted = if self.uid == meta.uid {
+            (meta.mode >> 6) & 0o7
+        } else if self.in_group(meta.gid) {
+            (meta.mode >> 3) & 0o7
+        } else {
+            meta.mode & 0o7
+        };
+        granted & want == want
+    }
+}
+
+/// Encode a major/minor pair using the Linux userspace `dev_t` layout.
+pub const fn make_dev(major: u32, minor: u32) -> u64 {
+    let major = major as u64;
+    let minor = minor as u64;
+    ((major & 0xffff_f000) << 32) | ((major & 0xfff) << 8) | ((minor & 0xffff_ff00) << 12) | (minor & 0xff)
+}
+
+/// Extract the major number from a `dev_t` value.
+pub const fn dev_major(dev: u64) -> u32 {
+    (((dev >> 32) & 0xffff_f000) | ((dev >> 8) & 0xfff)) as u32
+}
+
+/// Extract the minor number from a `dev_t` value.
+pub const fn dev_minor(dev: u64) -> u32 {
+    (((dev >> 12) & 0xffff_ff00) | (dev & 0xff)) as u32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Space operations accepted by `VfsOps::fallocate`.
+pub enum FallocMode {
+    /// Reserve blocks; new blocks read as zeros.
+    Allocate,
+    /// Deallocate the range, leaving a hole.
+    PunchHole,
+    /// Make the range read as zeros while keeping it allocated.
+    ZeroRange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A mapped byte range of a file reported by `VfsOps::fiemap`.
+pub struct FileExtent {
+    /// Byte offset of the extent within the file.
+    pub logical: u64,
+    /// Byte offset of the extent on the backing device.
+    pub physical: u64,
+    /// Length of the extent in bytes.
+    pub length: u64,
+    /// Blocks are allocated but not yet written (read as zeros).
+    pub unwritten: bool,
+}
+
+impl FileExtent {
+    /// Construct an empty extent record.
+    pub const fn empty() -> Self {
+        Self {
+            logical: 0,
+            physical: 0,
+            length: 0,
+            unwritten: false,
+        }
+    }
+}
+
+/// `f_type` reported by ext2/3/4.
+pub const EXT4_SUPER_MAGIC: u64 = 0xef53;
+/// `f_type` reported by FAT filesystems.
+pub const MSDOS_SUPER_MAGIC: u64 = 0x4d44;
+/// `f_type` reported by tmpfs and devtmpfs.
+pub const TMPFS_MAGIC: u64 = 0x0102_1994;
+/// `f_type` reported by procfs.
+pub const PROC_SUPER_MAGIC: u64 = 0x9fa0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Filesystem-wide usage reported by `VfsOps::statfs`.
+pub struct StatFs {
+    /// Filesystem magic (`f_type`).
+    pub magic: u64,
+    /// Allocation unit in bytes.
+    pub block_size: u32,
+    /// Total data blocks.
+    pub blocks: u64,
+    /// Free blocks.
+    pub blocks_free: u64,
+    /// Free blocks available to unprivileged users.
+    pub blocks_avail: u64,
+    /// Total inodes (0 when the filesystem has no fixed inode table).
+    pub files: u64,
+    /// Free inodes.
+    pub files_free: u64,
+    /// Maximum filename length.
+    pub name_max: u32,
+}
+
+impl StatFs {
+    /// Usage for a filesystem that holds no data blocks.
+    pub const fn empty(magic: u64) -> Self {
+        Self {
+            magic,
+            block_size: 4096,
+            blocks: 0,
+            blocks_free: 0,
+            blocks_avail: 0,
+            files: 0,
+            files_free: 0,
+            name_max: MAX_NAME_LEN as u32,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Existence requirement for `VfsOps::setxattr`.
+pub enum XattrMode {
+    /// Create the attribute or replace its value.
+    Any,
+    /// Fail with `AlreadyExists` if the attribute exists.
+    Create,
+    /// Fail with `NotFound` if the attribute does not exist.
+    Replace,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// Seek origin used by file operations.
 pub enum SeekWhence {
@@ -128,10 +314,120 @@ pub trait VfsOps {
     fn flush(&self) -> VfsResult<()> {
         Ok(())
     }
+    /// Report block and inode usage for the whole filesystem.
+    fn statfs(&self) -> VfsResult<StatFs> {
+        Err(VfsError::NotSupported)
+    }
+    /// Create a special node (FIFO, character or block device) under the parent.
+    fn mknod(&self, parent: InodeId, name: &str, kind: FileType, mode: u16, _rdev: u64) -> VfsResult<InodeId> {
+        match kind {
+            FileType::File => self.create(parent, name, kind, mode),
+            _ => Err(VfsError::NotSupported),
+        }
+    }
+    /// Move an entry, replacing an existing non-directory target.
+    fn rename(&self, _old_parent: InodeId, _old_name: &str, _new_parent: InodeId, _new_name: &str) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
     /// Truncate a file to the given size.
     fn truncate(&self, _inode: InodeId, _size: u64) -> VfsResult<()> {
         Err(VfsError::NotSupported)
     }
+    /// Allocate, punch or zero a byte range; `keep_size` leaves the file size unchanged.
+    fn fallocate(
+        &self,
+        _inode: InodeId,
+        _mode: FallocMode,
+        _offset: u64,
+        _len: u64,
+        _keep_size: bool,
+    ) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+    /// Copy up to `len` bytes between two files of this filesystem, stopping at the source EOF.
+    ///
+    /// Data moves in 4 KiB reads. Zero runs of 512 bytes that land beyond the destination's
+    /// end are skipped and the size is extended afterwards, so sparse sources stay sparse on
+    /// filesystems with holes.
+    fn copy_range(&self, src: InodeId, src_offset: u64, dst: InodeId, dst_offset: u64, len: u64) -> VfsResult<u64> {
+        const GRAIN: usize = 512;
+        let dst_size = self.metadata(dst)?.size;
+        let mut buf = [0u8; 4096];
+        let mut copied = 0u64;
+        let mut skipped_end = 0u64;
+        'copy: while copied < len {
+            let chunk = core::cmp::min(len - copied, buf.len() as u64) as usize;
+            let read = self.read_at(src, src_offset + copied, &mut buf[..chunk])?;
+            if read == 0 {
+                break;
+            }
+            let base = dst_offset + copied;
+            // 目标末尾之外的全零段可以跳过，其余相邻段合并成一次写入。
+            let hole = |start: usize| {
+                let end = core::cmp::min(start + GRAIN, read);
+                base + start as u64 >= dst_size && buf[start..end].iter().all(|&b| b == 0)
+            };
+            let mut start = 0usize;
+            while start < read {
+                if hole(start) {
+                    let end = core::cmp::min(start + GRAIN, read);
+                    skipped_end = base + end as u64;
+                    copied += (end - start) as u64;
+                    start = end;
+                    continue;
+                }
+                let mut end = core::cmp::min(start + GRAIN, read);
+                while end < read && !hole(end) {
+                    end = core::cmp::min(end + GRAIN, read);
+                }
+                let written = self.write_at(dst, base + start as u64, &buf[start..end])?;
+                copied += written as u64;
+                if written < end - start {
+                    break 'copy;
+                }
+                start = end;
+            }
+        }
+        if skipped_end > self.metadata(dst)?.size {
+            match self.truncate(dst, skipped_end) {
+                Err(VfsError::NotSupported) => {
+                    self.write_at(dst, skipped_end - 1, &[0])?;
+                }
+                result => result?,
+            }
+        }
+        Ok(copied)
+    }
+    /// Report mapped extents at or after `offset`, in file order.
+    fn fiemap(&self, _inode: InodeId, _offset: u64, _extents: &mut [FileExtent]) -> VfsResult<usize> {
+        Err(VfsError::NotSupported)
+    }
+    /// Copy the value of extended attribute `name` into `buf`, returning its full length.
+    ///
+    /// A missing attribute is reported as `NotFound`; a short `buf` receives a truncated copy.
+    fn getxattr(&self, _inode: InodeId, _name: &str, _buf: &mut [u8]) -> VfsResult<usize> {
+        Err(VfsError::NotSupported)
+    }
+    /// Set extended attribute `name` to `value`.
+    fn setxattr(&self, _inode: InodeId, _name: &str, _value: &[u8], _mode: XattrMode) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+    /// Write NUL-terminated attribute names into `buf`, returning the full list length.
+    fn listxattr(&self, _inode: InodeId, _buf: &mut [u8]) -> VfsResult<usize> {
+        Err(VfsError::NotSupported)
+    }
+    /// Remove extended attribute `name`.
+    fn removexattr(&self, _inode: InodeId, _name: &str) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+    /// Replace the permission bits (including setuid/setgid/sticky) of an inode.
+    fn set_mode(&self, _inode: InodeId, _mode: u16) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
+    /// Change the owning user and group of an inode.
+    fn set_owner(&self, _inode: InodeId, _uid: u32, _gid: u32) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
 }
 
 /// Optional file-oriented operations for file-like handles.
@@ -18,6 +18,37 @@ pub trait BlockDevice {
     fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()>;
     /// Flush any buffered writes to the device.
     fn flush(&self) -> VfsResult<()>;
+
+    /// Read consecutive blocks starting at `block_id`; `buf` holds whole blocks.
+    ///
+    /// Devices that can batch or scatter-gather should override this.
+    fn read_blocks(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        let block_size = self.block_size();
+        if block_size == 0 || buf.len() % block_size != 0 {
+            return Err(VfsError::Invalid);
+        }
+        for (idx, chunk) in buf.chunks_exact_mut(block_size).enumerate() {
+            self.read_block(block_id + idx as BlockId, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Write consecutive blocks starting at `block_id`; `buf` holds whole blocks.
+    fn write_blocks(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+        let block_size = self.block_size();
+        if block_size == 0 || buf.len() % block_size != 0 {
+            return Err(VfsError::Invalid);
+        }
+        for (idx, chunk) in buf.chunks_exact(block_size).enumerate() {
+            self.write_block(block_id + idx as BlockId, chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Tell the device that `count` blocks starting at `block_id` are unused.
+    fn discard_blocks(&self, _block_id: BlockId, _count: u64) -> VfsResult<()> {
+        Err(VfsError::NotSupported)
+    }
 }
 
 const BLOCK_CACHE_LINES: usize = 32;
@@ -148,6 +179,55 @@ impl<'a> BlockCache<'a> {
         Ok(())
     }
 
+    /// Read consecutive blocks, issuing one device request per run of misses.
+    pub fn read_blocks(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        let block_size = self.block_size;
+        if block_size == 0 || buf.len() % block_size != 0 {
+            return Err(VfsError::Invalid);
+        }
+        if block_size > BLOCK_CACHE_MAX_SIZE {
+            return self.device.read_blocks(block_id, buf);
+        }
+        let count = buf.len() / block_size;
+        let guard = self.cache.lock();
+        let state = guard.state_mut();
+        let cached = |state: &CacheState, id: BlockId| {
+            let entry = &state.entries[(id as usize) % BLOCK_CACHE_LINES];
+            entry.valid && entry.block_id == id
+        };
+        let mut idx = 0usize;
+        while idx < count {
+            let id = block_id + idx as BlockId;
+            if cached(state, id) {
+                let entry = &state.entries[(id as usize) % BLOCK_CACHE_LINES];
+                buf[idx * block_size..(idx + 1) * block_size]
+                    .copy_from_slice(&entry.buf[..block_size]);
+                idx += 1;
+                continue;
+            }
+            let run_start = idx;
+            while idx < count && !cached(state, block_id + idx as BlockId) {
+                idx += 1;
+            }
+            let run = &mut buf[run_start * block_size..idx * block_size];
+            self.device
+                .read_blocks(block_id + run_start as BlockId, run)?;
+            for (offset, chunk) in run.chunks_exact(block_size).enumerate() {
+                let id = block_id + (run_start + offset) as BlockId;
+                let entry = &mut state.entries[(id as usize) % BLOCK_CACHE_LINES];
+                if entry.valid && entry.dirty {
+                    self.device
+                        .write_block(entry.block_id, &entry.buf[..block_size])?;
+                }
+                entry.block_id = id;
+                entry.valid = true;
+                entry.dirty = false;
+                entry.buf[..block_size].copy_from_slice(chunk);
+            }
+        }
+        Ok(())
+    }
+
     /// Write a block with caching.
     pub fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
         if buf.len() < self.block_size {
@@ -260,4 +340,18 @@ mod tests {
         dev.read_block(0, &mut direct).unwrap();
         assert_eq!(direct, buf);
     }
+
+    #[test]
+    fn block_cache_read_blocks_mixes_hits_and_misses() {
+        let dev = TestBlockDevice {
+            block_size: 4,
+            data: RefCell::new([1, 1, 1, 1, 2, 2, 2, 2]),
+        };
+        let cache = BlockCache::new(&dev);
+        cache.write_block(1, &[9u8; 4]).unwrap();
+        let mut read = [0u8; 8];
+        cache.read_blocks(0, &mut read).unwrap();
+        assert_eq!(read, [1, 1, 1, 1, 9, 9, 9, 9]);
+        assert!(cache.read_blocks(0, &mut read[..6]).is_err());
+    }
 }
@@ -0,0 +1,559 @@
+//! Bounded dentry and inode-metadata cache for path resolution.
+//!
+//! `CachedFs` wraps a mounted filesystem and answers `lookup` and `metadata`
+//! from a shared `DentryCache`. Entries are keyed by mount so one cache can
+//! serve every mount; misses fall through to the backend and are remembered,
+//! including negative lookups. Every mutating operation routed through the
+//! wrapper drops the entries it may have made stale.
+
+use core::cell::UnsafeCell;
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axvfs::{DirEntry, FallocMode, FileExtent, FileType, InodeId, Metadata, StatFs, VfsOps, VfsResult, XattrMode};
+
+use crate::mount::MountId;
+
+/// Ways per set in both caches.
+pub const DCACHE_WAYS: usize = 4;
+/// Sets in the dentry cache.
+pub const DCACHE_SETS: usize = 64;
+/// Sets in the inode-metadata cache.
+pub const ICACHE_SETS: usize = 32;
+/// Longest name stored in the dentry cache; longer names always go to the backend.
+pub const DCACHE_NAME_LEN: usize = 32;
+
+#[derive(Clone, Copy)]
+struct Dentry {
+    used: bool,
+    mount: MountId,
+    parent: InodeId,
+    name: [u8; DCACHE_NAME_LEN],
+    name_len: usize,
+    /// `None` records a negative entry.
+    inode: Option<InodeId>,
+    stamp: u64,
+}
+
+impl Dentry {
+    const EMPTY: Self = Self {
+        used: false,
+        mount: MountId::Root,
+        parent: 0,
+        name: [0; DCACHE_NAME_LEN],
+        name_len: 0,
+        inode: None,
+        stamp: 0,
+    };
+
+    fn matches(&self, mount: MountId, parent: InodeId, name: &[u8]) -> bool {
+        self.used && self.mount == mount && self.parent == parent && &self.name[..self.name_len] == name
+    }
+}
+
+#[derive(Clone, Copy)]
+struct InodeSlot {
+    used: bool,
+    mount: MountId,
+    inode: InodeId,
+    meta: Metadata,
+    stamp: u64,
+}
+
+impl InodeSlot {
+    const EMPTY: Self = Self {
+        used: false,
+        mount: MountId::Root,
+        inode: 0,
+        meta: Metadata::new(FileType::File, 0, 0),
+        stamp: 0,
+    };
+
+    fn matches(&self, mount: MountId, inode: InodeId) -> bool {
+        self.used && self.mount == mount && self.inode == inode
+    }
+}
+
+struct Tables {
+    dentries: [[Dentry; DCACHE_WAYS]; DCACHE_SETS],
+    inodes: [[InodeSlot; DCACHE_WAYS]; ICACHE_SETS],
+    clock: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl Tables {
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Hit/miss counters across both caches.
+pub struct DcacheStats {
+    /// Lookups and metadata requests answered from the cache.
+    pub hits: u64,
+    /// Requests that went to the backend.
+    pub misses: u64,
+}
+
+/// Set-associative dentry and inode-metadata cache with per-set LRU replacement.
+pub struct DentryCache {
+    locked: AtomicBool,
+    tables: UnsafeCell<Tables>,
+}
+
+// SAFETY: all access to the tables is serialized by the spin lock.
+unsafe impl Sync for DentryCache {}
+
+struct CacheGuard<'a> {
+    cache: &'a DentryCache,
+}
+
+impl CacheGuard<'_> {
+    fn get_mut(&mut self) -> &mut Tables {
+        // SAFETY: guard ensures exclusive access to the tables.
+        unsafe { &mut *self.cache.tables.get() }
+    }
+}
+
+impl Drop for CacheGuard<'_> {
+    fn drop(&mut self) {
+        self.cache.locked.store(false, Ordering::Release);
+    }
+}
+
+impl DentryCache {
+    /// Create an empty cache.
+    pub const fn new() -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            tables: UnsafeCell::new(Tables {
+                dentries: [[Dentry::EMPTY; DCACHE_WAYS]; DCACHE_SETS],
+                inodes: [[InodeSlot::EMPTY; DCACHE_WAYS]; ICACHE_SETS],
+                clock: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    fn lock(&self) -> CacheGuard<'_> {
+        while self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        CacheGuard { cache: self }
+    }
+
+    /// Return hit/miss counters.
+    pub fn stats(&self) -> DcacheStats {
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        DcacheStats {
+            hits: tables.hits,
+            misses: tables.misses,
+        }
+    }
+
+    /// Cached result of `lookup(parent, name)`: `Some(None)` is a negative entry.
+    pub fn lookup(&self, mount: MountId, parent: InodeId, name: &str) -> Option<Option<InodeId>> {
+        let name = name.as_bytes();
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        let stamp = tables.tick();
+        let set = dentry_set(mount, parent, name);
+        let found = tables.dentries[set].iter_mut().find(|entry| entry.matches(mount, parent, name));
+        let result = found.map(|entry| {
+            entry.stamp = stamp;
+            entry.inode
+        });
+        if result.is_some() {
+            tables.hits += 1;
+        } else {
+            tables.misses += 1;
+        }
+        result
+    }
+
+    /// Remember the result of a backend lookup.
+    pub fn insert_dentry(&self, mount: MountId, parent: InodeId, name: &str, inode: Option<InodeId>) {
+        let name = name.as_bytes();
+        if name.len() > DCACHE_NAME_LEN {
+            return;
+        }
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        let stamp = tables.tick();
+        let ways = &mut tables.dentries[dentry_set(mount, parent, name)];
+        let slot = match ways.iter().position(|entry| entry.matches(mount, parent, name)) {
+            Some(slot) => slot,
+            None => lru_slot(ways.iter().map(|entry| (entry.used, entry.stamp))),
+        };
+        let entry = &mut ways[slot];
+        *entry = Dentry {
+            used: true,
+            mount,
+            parent,
+            name: [0; DCACHE_NAME_LEN],
+            name_len: name.len(),
+            inode,
+            stamp,
+        };
+        entry.name[..name.len()].copy_from_slice(name);
+    }
+
+    /// Drop the entry for `name` in `parent`.
+    pub fn forget_dentry(&self, mount: MountId, parent: InodeId, name: &str) {
+        let name = name.as_bytes();
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        for entry in tables.dentries[dentry_set(mount, parent, name)].iter_mut() {
+            if entry.matches(mount, parent, name) {
+                entry.used = false;
+            }
+        }
+    }
+
+    /// Cached metadata for an inode.
+    pub fn metadata(&self, mount: MountId, inode: InodeId) -> Option<Metadata> {
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        let stamp = tables.tick();
+        let found = tables.inodes[inode_set(mount, inode)].iter_mut().find(|slot| slot.matches(mount, inode));
+        let result = found.map(|slot| {
+            slot.stamp = stamp;
+            slot.meta
+        });
+        if result.is_some() {
+            tables.hits += 1;
+        } else {
+            tables.misses += 1;
+        }
+        result
+    }
+
+    /// Remember metadata fetched from the backend.
+    pub fn insert_metadata(&self, mount: MountId, inode: InodeId, meta: Metadata) {
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        let stamp = tables.tick();
+        let ways = &mut tables.inodes[inode_set(mount, inode)];
+        let slot = match ways.iter().position(|slot| slot.matches(mount, inode)) {
+            Some(slot) => slot,
+            None => lru_slot(ways.iter().map(|slot| (slot.used, slot.stamp))),
+        };
+        ways[slot] = InodeSlot {
+            used: true,
+            mount,
+            inode,
+            meta,
+            stamp,
+        };
+    }
+
+    /// Drop cached metadata for an inode.
+    pub fn forget_metadata(&self, mount: MountId, inode: InodeId) {
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        for slot in tables.inodes[inode_set(mount, inode)].iter_mut() {
+            if slot.matches(mount, inode) {
+                slot.used = false;
+            }
+        }
+    }
+
+    /// Drop an inode's metadata and every dentry that resolves to it.
+    ///
+    /// Backends such as FAT32 derive inode ids from on-disk state, so a write
+    /// can change the id a name resolves to.
+    pub fn forget_inode(&self, mount: MountId, inode: InodeId) {
+        self.forget_metadata(mount, inode);
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        for entry in tables.dentries.iter_mut().flatten() {
+            if entry.used && entry.mount == mount && entry.inode == Some(inode) {
+                entry.used = false;
+            }
+        }
+    }
+
+    /// Drop everything cached for a mount.
+    pub fn forget_mount(&self, mount: MountId) {
+        let mut guard = self.lock();
+        let tables = guard.get_mut();
+        for entry in tables.dentries.iter_mut().flatten() {
+            if entry.mount == mount {
+                entry.used = false;
+            }
+        }
+        for slot in tables.inodes.iter_mut().flatten() {
+            if slot.mount == mount {
+                slot.used = false;
+            }
+        }
+    }
+}
+
+impl Default for DentryCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn lru_slot(ways: impl Iterator<Item = (bool, u64)>) -> usize {
+    let mut best = 0usize;
+    let mut best_stamp = u64::MAX;
+    for (idx, (used, stamp)) in ways.enumerate() {
+        if !used {
+            return idx;
+        }
+        if stamp < best_stamp {
+            best = idx;
+            best_stamp = stamp;
+        }
+    }
+    best
+}
+
+fn mix(hash: u64, value: u64) -> u64 {
+    (hash ^ value).wrapping_mul(0x0000_0100_0000_01b3)
+}
+
+fn dentry_set(mount: MountId, parent: InodeId, name: &[u8]) -> usize {
+    let mut hash = mix(mix(0xcbf2_9ce4_8422_2325, mount as u64), parent);
+    for &byte in name {
+        hash = mix(hash, byte as u64);
+    }
+    (hash % DCACHE_SETS as u64) as usize
+}
+
+fn inode_set(mount: MountId, inode: InodeId) -> usize {
+    let hash = mix(mix(0xcbf2_9ce4_8422_2325, mount as u64), inode);
+    (hash % ICACHE_SETS as u64) as usize
+}
+
+/// Mounted filesystem view that serves `lookup` and `metadata` through a `DentryCache`.
+pub struct CachedFs<'a> {
+    fs: &'a dyn VfsOps,
+    mount: MountId,
+    cache: &'a DentryCache,
+}
+
+impl<'a> CachedFs<'a> {
+    /// Wrap `fs`, mounted as `mount`, with `cache`.
+    pub fn new(fs: &'a dyn VfsOps, mount: MountId, cache: &'a DentryCache) -> Self {
+        Self { fs, mount, cache }
+    }
+
+    fn forget_child(&self, parent: InodeId, name: &str) {
+        if let Ok(Some(inode)) = self.lookup(parent, name) {
+            self.cache.forget_inode(self.mount, inode);
+        }
+        self.cache.forget_dentry(self.mount, parent, name);
+    }
+
+    fn created(&self, parent: InodeId, name: &str, inode: InodeId) {
+        self.cache.forget_metadata(self.mount, parent);
+        self.cache.insert_dentry(self.mount, parent, name, Some(inode));
+    }
+}
+
+impl VfsOps for CachedFs<'_> {
+    fn root(&self) -> VfsResult<InodeId> {
+        self.fs.root()
+    }
+
+    fn lookup(&self, parent: InodeId, name: &str) -> VfsResult<Option<InodeId>> {
+        // ".." moves with directory renames; resolve_path walks it from its own stack anyway.
+        if name == "." || name == ".." {
+            return self.fs.lookup(parent, name);
+        }
+        if let Some(hit) = self.cache.lookup(self.mount, parent, name) {
+            return Ok(hit);
+        }
+        let found = self.fs.lookup(parent, name)?;
+        self.cache.insert_dentry(self.mount, parent, name, found);
+        Ok(found)
+    }
+
+    fn create(&self, parent: InodeId, name: &str, kind: FileType, mode: u16) -> VfsResult<InodeId> {
+        let inode = self.fs.create(parent, name, kind, mode)?;
+        self.created(parent, name, inode);
+        Ok(inode)
+    }
+
+    fn remove(&self, parent: InodeId, name: &str) -> VfsResult<()> {
+        self.forget_child(parent, name);
+        self.cache.forget_metadata(self.mount, parent);
+        self.fs.remove(parent, name)?;
+        self.cache.insert_dentry(self.mount, parent, name, None);
+        Ok(())
+    }
+
+    fn metadata(&self, inode: InodeId) -> VfsResult<Metadata> {
+        if let Some(meta) = self.cache.metadata(self.mount, inode) {
+            return Ok(meta);
+        }
+        let meta = self.fs.metadata(inode)?;
+        self.cache.insert_metadata(self.mount, inode, meta);
+        Ok(meta)
+    }
+
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        self.fs.read_at(inode, offset, buf)
+    }
+
+    fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+        let result = self.fs.write_at(inode, offset, buf);
+        self.cache.forget_inode(self.mount, inode);
+        result
+    }
+
+    fn read_dir(&self, inode: InodeId, offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
+        self.fs.read_dir(inode, offset, entries)
+    }
+
+    fn flush(&self) -> VfsResult<()> {
+        self.fs.flush()
+    }
+
+    fn statfs(&self) -> VfsResult<StatFs> {
+        self.fs.statfs()
+    }
+
+    fn mknod(&self, parent: InodeId, name: &str, kind: FileType, mode: u16, rdev: u64) -> VfsResult<InodeId> {
+        let inode = self.fs.mknod(parent, name, kind, mode, rdev)?;
+        self.created(parent, name, inode);
+        Ok(inode)
+    }
+
+    fn rename(&self, old_parent: InodeId, old_name: &str, new_parent: InodeId, new_name: &str) -> VfsResult<()> {
+        self.forget_child(old_parent, old_name);
+        self.forget_child(new_parent, new_name);
+        self.cache.forget_metadata(self.mount, old_parent);
+        self.cache.forget_metadata(self.mount, new_parent);
+        self.fs.rename(old_parent, old_name, new_parent, new_name)?;
+        self.cache.insert_dentry(self.mount, old_parent, old_name, None);
+        Ok(())
+    }
+
+    fn truncate(&self, inode: InodeId, size: u64) -> VfsResult<()> {
+        let result = self.fs.truncate(inode, size);
+        self.cache.forget_inode(self.mount, inode);
+        result
+    }
+
+    fn fallocate(&self, inode: InodeId, mode: FallocMode, offset: u64, len: u64, keep_size: bool) -> VfsResult<()> {
+        let result = self.fs.fallocate(inode, mode, offset, len, keep_size);
+        self.cache.forget_inode(self.mount, inode);
+        result
+    }
+
+    fn copy_range(&self, src: InodeId, src_offset: u64, dst: InodeId, dst_offset: u64, len: u64) -> VfsResult<u64> {
+        let result = self.fs.copy_range(src, src_offset, dst, dst_offset, len);
+        self.cache.forget_inode(self.mount, dst);
+        result
+    }
+
+    fn fiemap(&self, inode: InodeId, offset: u64, extents: &mut [FileExtent]) -> VfsResult<usize> {
+        self.fs.fiemap(inode, offset, extents)
+    }
+
+    fn getxattr(&self, inode: InodeId, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        self.fs.getxattr(inode, name, buf)
+    }
+
+    fn setxattr(&self, inode: InodeId, name: &str, value: &[u8], mode: XattrMode) -> VfsResult<()> {
+        self.fs.setxattr(inode, name, value, mode)
+    }
+
+    fn listxattr(&self, inode: InodeId, buf: &mut [u8]) -> VfsResult<usize> {
+        self.fs.listxattr(inode, buf)
+    }
+
+    fn removexattr(&self, inode: InodeId, name: &str) -> VfsResult<()> {
+        self.fs.removexattr(inode, name)
+    }
+
+    fn set_mode(&self, inode: InodeId, mode: u16) -> VfsResult<()> {
+        let result = self.fs.set_mode(inode, mode);
+        self.cache.forget_metadata(self.mount, inode);
+        result
+    }
+
+    fn set_owner(&self, inode: InodeId, uid: u32, gid: u32) -> VfsResult<()> {
+        let result = self.fs.set_owner(inode, uid, gid);
+        self.cache.forget_metadata(self.mount, inode);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devfs::{DevFs, HostShmPages, DEV_SHM_ID};
+
+    #[test]
+    fn lookups_and_metadata_hit_after_first_miss() {
+        let devfs = DevFs::new();
+        let cache = DentryCache::new();
+        let fs = CachedFs::new(&devfs, MountId::Dev, &cache);
+        let inode = fs.create(DEV_SHM_ID, "seg", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "seg").unwrap(), Some(inode));
+        assert_eq!(fs.lookup(DEV_SHM_ID, "missing").unwrap(), None);
+        let before = cache.stats();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "seg").unwrap(), Some(inode));
+        assert_eq!(fs.lookup(DEV_SHM_ID, "missing").unwrap(), None);
+        fs.metadata(inode).unwrap();
+        fs.metadata(inode).unwrap();
+        let after = cache.stats();
+        assert_eq!(after.hits - before.hits, 3);
+        assert_eq!(after.misses - before.misses, 1);
+    }
+
+    #[test]
+    fn mutations_invalidate_entries() {
+        let devfs = DevFs::new();
+        devfs.set_shm_memory(&HostShmPages, 1 << 20);
+        let cache = DentryCache::new();
+        let fs = CachedFs::new(&devfs, MountId::Dev, &cache);
+        assert_eq!(fs.lookup(DEV_SHM_ID, "a").unwrap(), None);
+        let a = fs.create(DEV_SHM_ID, "a", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "a").unwrap(), Some(a));
+        assert_eq!(fs.metadata(a).unwrap().size, 0);
+        fs.write_at(a, 0, b"hello").unwrap();
+        assert_eq!(fs.metadata(a).unwrap().size, 5);
+        fs.set_mode(a, 0o640).unwrap();
+        assert_eq!(fs.metadata(a).unwrap().mode, 0o640);
+        assert_eq!(fs.lookup(DEV_SHM_ID, "b").unwrap(), None);
+        fs.rename(DEV_SHM_ID, "a", DEV_SHM_ID, "b").unwrap();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "a").unwrap(), None);
+        let b = fs.lookup(DEV_SHM_ID, "b").unwrap().unwrap();
+        assert_eq!(fs.metadata(b).unwrap().size, 5);
+        fs.remove(DEV_SHM_ID, "b").unwrap();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "b").unwrap(), None);
+        assert_eq!(devfs.lookup(DEV_SHM_ID, "b").unwrap(), None);
+    }
+
+    #[test]
+    fn bounded_cache_evicts_and_stays_correct() {
+        let devfs = DevFs::new();
+        let cache = DentryCache::new();
+        let fs = CachedFs::new(&devfs, MountId::Dev, &cache);
+        for idx in 0..(DCACHE_SETS * DCACHE_WAYS * 2) {
+            let name = std::format!("n{}", idx);
+            assert_eq!(fs.lookup(DEV_SHM_ID, &name).unwrap(), None);
+        }
+        let inode = fs.create(DEV_SHM_ID, "n0", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "n0").unwrap(), Some(inode));
+        cache.forget_mount(MountId::Dev);
+        let before = cache.stats();
+        assert_eq!(fs.lookup(DEV_SHM_ID, "n0").unwrap(), Some(inode));
+        assert_eq!(cache.stats().misses - before.misses, 1);
+    }
+}
@@ -1,6 +1,18 @@
-//! Device filesystem with basic /dev/null and /dev/zero.
+//! Device filesystem with built-in memory devices, `/dev/shm` and registered device nodes.
 
-use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult};
+use core::cell::UnsafeCell;
+use core::cmp::min;
+use core::hint::spin_loop;
+use core::mem::size_of;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use axvfs::{
+    dev_major, dev_minor, make_dev, DirEntry, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult,
+    XattrMode, TMPFS_MAGIC,
+};
+
+use crate::block::BlockDevice;
 
 /// Root inode identifier for devfs.
 pub const ROOT_ID: InodeId = 1;
@@ -8,6 +20,55 @@ pub const ROOT_ID: InodeId = 1;
 pub const DEV_NULL_ID: InodeId = 2;
 /// Inode identifier for /dev/zero.
 pub const DEV_ZERO_ID: InodeId = 3;
+/// Inode identifier for /dev/full.
+pub const DEV_FULL_ID: InodeId = 4;
+/// Inode identifier for the /dev/shm directory.
+pub const DEV_SHM_ID: InodeId = 5;
+/// Maximum number of dynamically registered device nodes.
+pub const MAX_DEVICE_NODES: usize = 32;
+/// Maximum device node name length.
+pub const MAX_DEVICE_NAME_LEN: usize = 15;
+/// Maximum number of files in /dev/shm.
+pub const MAX_SHM_FILES: usize = 64;
+/// Size of the pages backing /dev/shm files.
+pub const SHM_PAGE_SIZE: usize = 4096;
+/// Largest /dev/shm file: one index page of index pages of data pages (1 GiB).
+pub const MAX_SHM_FILE_SIZE: u64 = (SHM_INDEX_ENTRIES * SHM_INDEX_ENTRIES * SHM_PAGE_SIZE) as u64;
+/// Maximum /dev/shm file name length.
+pub const MAX_SHM_NAME_LEN: usize = 31;
+/// Extended attribute space per /dev/shm file, including record headers.
+pub const SHM_XATTR_SIZE: usize = 512;
+
+const DYNAMIC_BASE_ID: InodeId = 16;
+/// First /dev/shm inode; later files count up from here and numbers are never reused.
+const SHM_BASE_ID: InodeId = 64;
+/// Page addresses held by one index page.
+const SHM_INDEX_ENTRIES: usize = SHM_PAGE_SIZE / size_of::<usize>();
+const BLOCK_SCRATCH_SIZE: usize = 4096;
+/// Record header in the shm xattr area: name length (u8) and value length (u16).
+const SHM_XATTR_HEADER: usize = 3;
+/// Namespaces tmpfs accepts; `system.` (ACLs) is not supported here.
+const SHM_XATTR_PREFIXES: [&str; 3] = ["user.", "trusted.", "security."];
+
+/// Page provider backing /dev/shm file contents.
+pub trait ShmMemory: Sync {
+    /// Allocate one zeroed `SHM_PAGE_SIZE` page.
+    fn alloc_page(&self) -> Option<NonNull<u8>>;
+    /// Give back a page obtained from `alloc_page`.
+    fn free_page(&self, page: NonNull<u8>);
+}
+
+/// Character device driver published through devfs.
+pub trait CharDevice: Sync {
+    /// Read available bytes; return `VfsError::Busy` when nothing is ready yet.
+    fn read(&self, buf: &mut [u8]) -> VfsResult<usize>;
+    /// Write bytes to the device.
+    fn write(&self, buf: &[u8]) -> VfsResult<usize>;
+    /// Return true if a read would make progress without blocking.
+    fn poll_read(&self) -> bool {
+        true
+    }
+}
 
 #[derive(Clone, Copy)]
 struct Node {
@@ -16,15 +77,20 @@ struct Node {
     name: &'static str,
     file_type: FileType,
     mode: u16,
+    major: u32,
+    minor: u32,
 }
 
-const NODES: [Node; 3] = [
+// 内存类设备号与 Linux 保持一致：mem 主设备号 1。
+const NODES: [Node; 5] = [
     Node {
         id: ROOT_ID,
         parent: ROOT_ID,
         name: "",
         file_type: FileType::Dir,
         mode: 0o755,
+        major: 0,
+        minor: 0,
     },
     Node {
         id: DEV_NULL_ID,
@@ -32,6 +98,8 @@ const NODES: [Node; 3] = [
         name: "null",
         file_type: FileType::Char,
         mode: 0o666,
+        major: 1,
+        minor: 3,
     },
     Node {
         id: DEV_ZERO_ID,
@@ -39,6 +107,26 @@ const NODES: [Node; 3] = [
         name: "zero",
         file_type: FileType::Char,
         mode: 0o666,
+        major: 1,
+        minor: 5,
+    },
+    Node {
+        id: DEV_FULL_ID,
+        parent: ROOT_ID,
+        name: "full",
+        file_type: FileType::Char,
+        mode: 0o666,
+        major: 1,
+        minor: 7,
+    },
+    Node {
+        id: DEV_SHM_ID,
+        parent: ROOT_ID,
+        name: "shm",
+        file_type: FileType::Dir,
+        mode: 0o1777,
+        major: 0,
+        minor: 0,
     },
 ];
 
@@ -49,7 +137,7 @@ struct DirEntrySpec {
     file_type: FileType,
 }
 
-const DEV_ENTRIES: [DirEntrySpec; 4] = [
+const DEV_ENTRIES: [DirEntrySpec; 6] = [
     DirEntrySpec {
         ino: ROOT_ID,
         name: b".",
@@ -70,21 +158,482 @@ const DEV_ENTRIES: [DirEntrySpec; 4] = [
         name: b"zero",
         file_type: FileType::Char,
     },
+    DirEntrySpec {
+        ino: DEV_FULL_ID,
+        name: b"full",
+        file_type: FileType::Char,
+    },
+    DirEntrySpec {
+        ino: DEV_SHM_ID,
+        name: b"shm",
+        file_type: FileType::Dir,
+    },
+];
+
+const SHM_ENTRIES: [DirEntrySpec; 2] = [
+    DirEntrySpec {
+        ino: DEV_SHM_ID,
+        name: b".",
+        file_type: FileType::Dir,
+    },
+    DirEntrySpec {
+        ino: ROOT_ID,
+        name: b"..",
+        file_type: FileType::Dir,
+    },
 ];
 
-/// Simple devfs implementation with fixed nodes.
-pub struct DevFs;
+#[derive(Clone, Copy)]
+enum DeviceKind {
+    Block {
+        device: &'static dyn BlockDevice,
+        blocks: u64,
+    },
+    Char {
+        device: &'static dyn CharDevice,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct DeviceNode {
+    name: [u8; MAX_DEVICE_NAME_LEN],
+    name_len: usize,
+    major: u32,
+    minor: u32,
+    mode: u16,
+    kind: DeviceKind,
+}
+
+impl DeviceNode {
+    fn name(&self) -> &[u8] {
+        &self.name[..self.name_len]
+    }
+
+    fn file_type(&self) -> FileType {
+        match self.kind {
+            DeviceKind::Block { .. } => FileType::Block,
+            DeviceKind::Char { .. } => FileType::Char,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        match self.kind {
+            DeviceKind::Block { device, blocks } => blocks * device.block_size() as u64,
+            DeviceKind::Char { .. } => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ShmFile {
+    used: bool,
+    ino: InodeId,
+    name: [u8; MAX_SHM_NAME_LEN],
+    name_len: usize,
+    file_type: FileType,
+    mode: u16,
+    uid: u32,
+    gid: u32,
+    rdev: u64,
+    len: u64,
+    /// Index page of index pages of data pages; missing pages read as zeros.
+    index: Option<NonNull<u8>>,
+    xattr_len: usize,
+    xattrs: [u8; SHM_XATTR_SIZE],
+}
+
+impl ShmFile {
+    const EMPTY: Self = Self {
+        used: false,
+        ino: 0,
+        name: [0; MAX_SHM_NAME_LEN],
+        name_len: 0,
+        file_type: FileType::File,
+        mode: 0,
+        uid: 0,
+        gid: 0,
+        rdev: 0,
+        len: 0,
+        index: None,
+        xattr_len: 0,
+        xattrs: [0; SHM_XATTR_SIZE],
+    };
+
+    fn name(&self) -> &[u8] {
+        &self.name[..self.name_len]
+    }
+
+    /// Packed `[name_len, value_len, name, value]` records as (offset, name, value).
+    fn xattr_records(&self) -> impl Iterator<Item = (usize, &[u8], &[u8])> {
+        let area = &self.xattrs[..self.xattr_len];
+        let mut offset = 0;
+        core::iter::from_fn(move || {
+            if offset + SHM_XATTR_HEADER > area.len() {
+                return None;
+            }
+            let name_len = area[offset] as usize;
+            let value_len = u16::from_le_bytes([area[offset + 1], area[offset + 2]]) as usize;
+            let name_at = offset + SHM_XATTR_HEADER;
+            let record = (offset, &area[name_at..name_at + name_len], &area[name_at + name_len..][..value_len]);
+            offset = name_at + name_len + value_len;
+            Some(record)
+        })
+    }
+
+    fn find_xattr(&self, name: &[u8]) -> Option<(usize, &[u8])> {
+        self.xattr_records()
+            .find(|(_, record, _)| *record == name)
+            .map(|(offset, _, value)| (offset, value))
+    }
+
+    fn remove_xattr_at(&mut self, offset: usize) {
+        let name_len = self.xattrs[offset] as usize;
+        let value_len = u16::from_le_bytes([self.xattrs[offset + 1], self.xattrs[offset + 2]]) as usize;
+        let end = offset + SHM_XATTR_HEADER + name_len + value_len;
+        self.xattrs.copy_within(end..self.xattr_len, offset);
+        self.xattr_len -= end - offset;
+    }
+
+    /// Data page `page`, if it was ever written.
+    fn data_page(&self, page: usize) -> Option<NonNull<u8>> {
+        let top = self.index?;
+        // SAFETY: 索引页由 ShmPages 分配并归本文件所有。
+        unsafe {
+            let mid = NonNull::new(*index_slot(top, page / SHM_INDEX_ENTRIES) as *mut u8)?;
+            NonNull::new(*index_slot(mid, page % SHM_INDEX_ENTRIES) as *mut u8)
+        }
+    }
+
+    /// Data page `page`, allocating it and any missing index page.
+    fn data_page_mut(&mut self, pages: &mut ShmPages, page: usize) -> VfsResult<NonNull<u8>> {
+        let top = match self.index {
+            Some(top) => top,
+            None => *self.index.insert(pages.alloc()?),
+        };
+        // SAFETY: 索引页由 ShmPages 分配并归本文件所有。
+        unsafe {
+            let mid = index_slot(top, page / SHM_INDEX_ENTRIES);
+            if *mid == 0 {
+                *mid = pages.alloc()?.as_ptr() as usize;
+            }
+            let data = index_slot(NonNull::new_unchecked(*mid as *mut u8), page % SHM_INDEX_ENTRIES);
+            if *data == 0 {
+                *data = pages.alloc()?.as_ptr() as usize;
+            }
+            Ok(NonNull::new_unchecked(*data as *mut u8))
+        }
+    }
+
+    /// Free data pages from `first` on, and index pages left without data.
+    fn release_pages(&mut self, pages: &mut ShmPages, first: usize) {
+        let Some(top) = self.index else {
+            return;
+        };
+        // SAFETY: 索引页由 ShmPages 分配并归本文件所有。
+        unsafe {
+            for mid_idx in first / SHM_INDEX_ENTRIES..SHM_INDEX_ENTRIES {
+                let mid_slot = index_slot(top, mid_idx);
+                let Some(mid) = NonNull::new(*mid_slot as *mut u8) else {
+                    continue;
+                };
+                let start = first.saturating_sub(mid_idx * SHM_INDEX_ENTRIES);
+                for data_idx in start..SHM_INDEX_ENTRIES {
+                    let data_slot = index_slot(mid, data_idx);
+                    if let Some(data) = NonNull::new(*data_slot as *mut u8) {
+                        pages.free(data);
+                        *data_slot = 0;
+                    }
+                }
+                if start == 0 {
+                    pages.free(mid);
+                    *mid_slot = 0;
+                }
+            }
+        }
+        if first == 0 {
+            pages.free(top);
+            self.index = None;
+        }
+    }
+
+    /// Drop the name and contents so the slot can take a new file.
+    fn clear(&mut self, pages: &mut ShmPages) {
+        self.release_pages(pages, 0);
+        self.used = false;
+        self.name_len = 0;
+        self.len = 0;
+    }
+}
+
+/// Entry `idx` of an index page, holding a page address or 0.
+///
+/// # Safety
+///
+/// `index` must be a live index page and `idx < SHM_INDEX_ENTRIES`.
+unsafe fn index_slot(index: NonNull<u8>, idx: usize) -> *mut usize {
+    index.as_ptr().cast::<usize>().add(idx)
+}
+
+/// Page budget of /dev/shm, shared by data and index pages.
+struct ShmPages {
+    memory: Option<&'static dyn ShmMemory>,
+    used: usize,
+    limit: usize,
+}
+
+impl ShmPages {
+    fn alloc(&mut self) -> VfsResult<NonNull<u8>> {
+        let memory = self.memory.ok_or(VfsError::NoSpace)?;
+        if self.used >= self.limit {
+            return Err(VfsError::NoSpace);
+        }
+        let page = memory.alloc_page().ok_or(VfsError::NoMem)?;
+        self.used += 1;
+        Ok(page)
+    }
+
+    fn free(&mut self, page: NonNull<u8>) {
+        if let Some(memory) = self.memory {
+            memory.free_page(page);
+            self.used -= 1;
+        }
+    }
+}
+
+/// /dev/shm files and the pages behind them.
+struct ShmStore {
+    files: [ShmFile; MAX_SHM_FILES],
+    pages: ShmPages,
+    next_ino: InodeId,
+}
+
+impl ShmStore {
+    fn position(&self, name: &[u8]) -> Option<usize> {
+        self.files.iter().position(|file| file.used && file.name() == name)
+    }
+}
+
+/// Spin-locked table shared by device registration and /dev/shm.
+struct Table<T> {
+    locked: AtomicBool,
+    inner: UnsafeCell<T>,
+}
+
+// SAFETY: 访问通过自旋锁串行化，表项只保存 `'static` 设备引用与 /dev/shm 独占的页。
+unsafe impl<T> Sync for Table<T> {}
+
+impl<T> Table<T> {
+    const fn new(inner: T) -> Self {
+        Self {
+            locked: AtomicBool::new(false),
+            inner: UnsafeCell::new(inner),
+        }
+    }
+
+    fn lock(&self) -> TableGuard<'_, T> {
+        while self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        TableGuard { table: self }
+    }
+}
+
+struct TableGuard<'a, T> {
+    table: &'a Table<T>,
+}
+
+impl<T> TableGuard<'_, T> {
+    fn get_mut(&mut self) -> &mut T {
+        // SAFETY: guard ensures exclusive access to the table.
+        unsafe { &mut *self.table.inner.get() }
+    }
+}
+
+impl<T> Drop for TableGuard<'_, T> {
+    fn drop(&mut self) {
+        self.table.locked.store(false, Ordering::Release);
+    }
+}
+
+/// Devfs implementation with fixed nodes, a page-backed /dev/shm and registered devices.
+pub struct DevFs {
+    devices: Table<[Option<DeviceNode>; MAX_DEVICE_NODES]>,
+    shm: Table<ShmStore>,
+}
 
 impl DevFs {
-    /// Create a new devfs instance.
+    /// Create a new devfs instance; /dev/shm holds no data until `set_shm_memory`.
     pub const fn new() -> Self {
-        Self
+        Self {
+            devices: Table::new([None; MAX_DEVICE_NODES]),
+            shm: Table::new(ShmStore {
+                files: [ShmFile::EMPTY; MAX_SHM_FILES],
+                pages: ShmPages { memory: None, used: 0, limit: 0 },
+                next_ino: SHM_BASE_ID,
+            }),
+        }
+    }
+
+    /// Back /dev/shm with pages from `memory`, at most `limit` bytes including index pages.
+    pub fn set_shm_memory(&self, memory: &'static dyn ShmMemory, limit: usize) {
+        let mut guard = self.shm.lock();
+        let pages = &mut guard.get_mut().pages;
+        pages.memory = Some(memory);
+        pages.limit = limit / SHM_PAGE_SIZE;
+    }
+
+    /// Publish a block device node such as `vda` or `vda1`.
+    pub fn register_block(
+        &self,
+        name: &str,
+        major: u32,
+        minor: u32,
+        device: &'static dyn BlockDevice,
+        blocks: u64,
+    ) -> VfsResult<InodeId> {
+        if device.block_size() > BLOCK_SCRATCH_SIZE {
+            return Err(VfsError::Invalid);
+        }
+        self.register(name, major, minor, 0o660, DeviceKind::Block { device, blocks })
+    }
+
+    /// Publish a character device node such as `console` or `urandom`.
+    pub fn register_char(
+        &self,
+        name: &str,
+        major: u32,
+        minor: u32,
+        mode: u16,
+        device: &'static dyn CharDevice,
+    ) -> VfsResult<InodeId> {
+        self.register(name, major, minor, mode, DeviceKind::Char { device })
+    }
+
+    fn register(
+        &self,
+        name: &str,
+        major: u32,
+        minor: u32,
+        mode: u16,
+        kind: DeviceKind,
+    ) -> VfsResult<InodeId> {
+        let bytes = name.as_bytes();
+        if bytes.is_empty() || bytes.len() > MAX_DEVICE_NAME_LEN || bytes.contains(&b'/') {
+            return Err(VfsError::Invalid);
+        }
+        if NODES.iter().any(|node| node.parent == ROOT_ID && node.name == name) {
+            return Err(VfsError::AlreadyExists);
+        }
+        let mut guard = self.devices.lock();
+        let nodes = guard.get_mut();
+        if nodes.iter().flatten().any(|node| node.name() == bytes) {
+            return Err(VfsError::AlreadyExists);
+        }
+        let slot = nodes.iter().position(|node| node.is_none()).ok_or(VfsError::NoMem)?;
+        let mut node = DeviceNode {
+            name: [0; MAX_DEVICE_NAME_LEN],
+            name_len: bytes.len(),
+            major,
+            minor,
+            mode,
+            kind,
+        };
+        node.name[..bytes.len()].copy_from_slice(bytes);
+        nodes[slot] = Some(node);
+        Ok(DYNAMIC_BASE_ID + slot as InodeId)
+    }
+
+    /// Return the (major, minor) pair of a device node.
+    pub fn device_number(&self, inode: InodeId) -> Option<(u32, u32)> {
+        if let Some(node) = self.node(inode) {
+            return (node.file_type == FileType::Char).then_some((node.major, node.minor));
+        }
+        self.device(inode).map(|dev| (dev.major, dev.minor))
+    }
+
+    /// Find the devfs inode that serves device number `rdev`.
+    pub fn lookup_rdev(&self, rdev: u64) -> Option<InodeId> {
+        let (major, minor) = (dev_major(rdev), dev_minor(rdev));
+        if let Some(node) = NODES
+            .iter()
+            .find(|node| node.file_type == FileType::Char && node.major == major && node.minor == minor)
+        {
+            return Some(node.id);
+        }
+        let mut guard = self.devices.lock();
+        guard
+            .get_mut()
+            .iter()
+            .position(|node| node.map_or(false, |node| node.major == major && node.minor == minor))
+            .map(|slot| DYNAMIC_BASE_ID + slot as InodeId)
+    }
+
+    /// Return true if a read from `inode` would not block.
+    pub fn poll_read(&self, inode: InodeId) -> bool {
+        match self.device(inode).map(|dev| dev.kind) {
+            Some(DeviceKind::Char { device }) => device.poll_read(),
+            _ => true,
+        }
     }
 
     fn node(&self, inode: InodeId) -> Option<&'static Node> {
         NODES.iter().find(|node| node.id == inode)
     }
 
+    fn device(&self, inode: InodeId) -> Option<DeviceNode> {
+        let slot = inode.checked_sub(DYNAMIC_BASE_ID)? as usize;
+        if slot >= MAX_DEVICE_NODES {
+            return None;
+        }
+        let mut guard = self.devices.lock();
+        guard.get_mut()[slot]
+    }
+
+    fn lookup_device(&self, name: &str) -> Option<InodeId> {
+        let mut guard = self.devices.lock();
+        guard
+            .get_mut()
+            .iter()
+            .position(|node| node.map_or(false, |node| node.name() == name.as_bytes()))
+            .map(|slot| DYNAMIC_BASE_ID + slot as InodeId)
+    }
+
+    fn is_shm(inode: InodeId) -> bool {
+        inode >= SHM_BASE_ID
+    }
+
+    fn lookup_shm(&self, name: &str) -> Option<InodeId> {
+        let mut guard = self.shm.lock();
+        let store = guard.get_mut();
+        store.position(name.as_bytes()).map(|slot| store.files[slot].ino)
+    }
+
+    fn with_shm<R>(
+        &self,
+        inode: InodeId,
+        f: impl FnOnce(&mut ShmFile, &mut ShmPages) -> VfsResult<R>,
+    ) -> VfsResult<R> {
+        if !Self::is_shm(inode) {
+            return Err(VfsError::NotFound);
+        }
+        let mut guard = self.shm.lock();
+        let ShmStore { files, pages, .. } = guard.get_mut();
+        let file = files
+            .iter_mut()
+            .find(|file| file.used && file.ino == inode)
+            .ok_or(VfsError::NotFound)?;
+        f(file, pages)
+    }
+}
+
+impl Default for DevFs {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl VfsOps for DevFs {
@@ -95,48 +644,425 @@ impl VfsOps for DevFs {
     fn lookup(&self, parent: InodeId, name: &str) -> VfsResult<Option<InodeId>> {
         if let Some(node) = NODES.iter().find(|node| node.parent == parent && node.name == name) {
             Ok(Some(node.id))
+        } else if parent == ROOT_ID {
+            Ok(self.lookup_device(name))
+        } else if parent == DEV_SHM_ID {
+            match name {
+                "." => Ok(Some(DEV_SHM_ID)),
+                ".." => Ok(Some(ROOT_ID)),
+                _ => Ok(self.lookup_shm(name)),
+            }
         } else {
             Ok(None)
         }
     }
 
-    fn create(&self, _parent: InodeId, _name: &str, _kind: FileType, _mode: u16) -> VfsResult<InodeId> {
-        Err(VfsError::NotSupported)
+    fn create(&self, parent: InodeId, name: &str, kind: FileType, mode: u16) -> VfsResult<InodeId> {
+        match kind {
+            FileType::File | FileType::Fifo => self.mknod(parent, name, kind, mode, 0),
+            _ => Err(VfsError::NotSupported),
+        }
+    }
+
+    fn mknod(&self, parent: InodeId, name: &str, kind: FileType, mode: u16, rdev: u64) -> VfsResult<InodeId> {
+        // 只有 /dev/shm 可创建文件与特殊节点，其余节点由驱动注册。
+        if parent != DEV_SHM_ID {
+            return Err(VfsError::NotSupported);
+        }
+        if !matches!(
+            kind,
+            FileType::File | FileType::Fifo | FileType::Char | FileType::Block | FileType::Socket
+        ) {
+            return Err(VfsError::NotSupported);
+        }
+        let bytes = name.as_bytes();
+        if bytes.is_empty() || bytes.len() > MAX_SHM_NAME_LEN || bytes.contains(&b'/') {
+            return Err(VfsError::Invalid);
+        }
+        let mut guard = self.shm.lock();
+        let store = guard.get_mut();
+        if store.position(bytes).is_some() {
+            return Err(VfsError::AlreadyExists);
+        }
+        let slot = store.files.iter().position(|file| !file.used).ok_or(VfsError::NoSpace)?;
+        let ino = store.next_ino;
+        store.next_ino += 1;
+        let file = &mut store.files[slot];
+        file.used = true;
+        file.ino = ino;
+        file.name[..bytes.len()].copy_from_slice(bytes);
+        file.name_len = bytes.len();
+        file.file_type = kind;
+        file.mode = mode & 0o7777;
+        file.uid = 0;
+        file.gid = 0;
+        file.rdev = rdev;
+        file.len = 0;
+        file.xattr_len = 0;
+        Ok(ino)
+    }
+
+    fn remove(&self, parent: InodeId, name: &str) -> VfsResult<()> {
+        if parent != DEV_SHM_ID {
+            return Err(VfsError::NotSupported);
+        }
+        // 查找与释放在同一次加锁内完成，避免中途被并发的 rename/remove 换掉。
+        let mut guard = self.shm.lock();
+        let store = guard.get_mut();
+        let slot = store.position(name.as_bytes()).ok_or(VfsError::NotFound)?;
+        store.files[slot].clear(&mut store.pages);
+        Ok(())
     }
 
-    fn remove(&self, _parent: InodeId, _name: &str) -> VfsResult<()> {
-        Err(VfsError::NotSupported)
+    fn rename(&self, old_parent: InodeId, old_name: &str, new_parent: InodeId, new_name: &str) -> VfsResult<()> {
+        if old_parent != DEV_SHM_ID || new_parent != DEV_SHM_ID {
+            return Err(VfsError::NotSupported);
+        }
+        let bytes = new_name.as_bytes();
+        if bytes.is_empty() || bytes.len() > MAX_SHM_NAME_LEN || bytes.contains(&b'/') {
+            return Err(VfsError::Invalid);
+        }
+        let mut guard = self.shm.lock();
+        let store = guard.get_mut();
+        let from = store.position(old_name.as_bytes()).ok_or(VfsError::NotFound)?;
+        if let Some(to) = store.position(bytes) {
+            if to == from {
+                return Ok(());
+            }
+            // 目标已存在时按 rename(2) 语义原子替换。
+            store.files[to].clear(&mut store.pages);
+        }
+        let file = &mut store.files[from];
+        file.name[..bytes.len()].copy_from_slice(bytes);
+        file.name_len = bytes.len();
+        Ok(())
     }
 
     fn metadata(&self, inode: InodeId) -> VfsResult<Metadata> {
-        let node = self.node(inode).ok_or(VfsError::NotFound)?;
-        Ok(Metadata::new(node.file_type, 0, node.mode))
+        if let Some(node) = self.node(inode) {
+            let meta = Metadata::new(node.file_type, 0, node.mode);
+            return match node.file_type {
+                FileType::Char => Ok(meta.with_rdev(make_dev(node.major, node.minor))),
+                _ => Ok(meta),
+            };
+        }
+        if Self::is_shm(inode) {
+            return self.with_shm(inode, |file, _| {
+                let meta = Metadata::new(file.file_type, file.len, file.mode);
+                Ok(meta.with_rdev(file.rdev).with_owner(file.uid, file.gid))
+            });
+        }
+        let dev = self.device(inode).ok_or(VfsError::NotFound)?;
+        Ok(Metadata::new(dev.file_type(), dev.size(), dev.mode).with_rdev(make_dev(dev.major, dev.minor)))
     }
 
-    fn read_at(&self, inode: InodeId, _offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
         match inode {
-            DEV_ZERO_ID => {
+            DEV_ZERO_ID | DEV_FULL_ID => {
                 buf.fill(0);
                 Ok(buf.len())
             }
             DEV_NULL_ID => Ok(0),
-            _ => Err(VfsError::NotSupported),
+            _ if Self::is_shm(inode) => self.with_shm(inode, |file, _| {
+                if file.file_type != FileType::File {
+                    return Err(VfsError::Invalid);
+                }
+                let count = min(buf.len() as u64, file.len.saturating_sub(offset)) as usize;
+                let mut done = 0usize;
+                while done < count {
+                    let pos = offset as usize + done;
+                    let in_page = pos % SHM_PAGE_SIZE;
+                    let chunk = min(count - done, SHM_PAGE_SIZE - in_page);
+                    let dst = &mut buf[done..done + chunk];
+                    match file.data_page(pos / SHM_PAGE_SIZE) {
+                        // SAFETY: 数据页归本文件所有，chunk 不越过页尾。
+                        Some(page) => unsafe {
+                            ptr::copy_nonoverlapping(page.as_ptr().add(in_page), dst.as_mut_ptr(), chunk);
+                        },
+                        None => dst.fill(0),
+                    }
+                    done += chunk;
+                }
+                Ok(count)
+            }),
+            _ => match self.device(inode).map(|dev| dev.kind) {
+                Some(DeviceKind::Block { device, blocks }) => block_read(device, blocks, offset, buf),
+                Some(DeviceKind::Char { device }) => device.read(buf),
+                None => Err(VfsError::NotSupported),
+            },
         }
     }
 
-    fn write_at(&self, inode: InodeId, _offset: u64, buf: &[u8]) -> VfsResult<usize> {
+    fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> VfsResult<usize> {
         match inode {
             DEV_NULL_ID | DEV_ZERO_ID => Ok(buf.len()),
-            _ => Err(VfsError::NotSupported),
+            DEV_FULL_ID => Err(VfsError::NoSpace),
+            _ if Self::is_shm(inode) => self.with_shm(inode, |file, pages| {
+                if file.file_type != FileType::File {
+                    return Err(VfsError::Invalid);
+                }
+                if offset >= MAX_SHM_FILE_SIZE {
+                    return Err(VfsError::NoSpace);
+                }
+                let count = min(buf.len() as u64, MAX_SHM_FILE_SIZE - offset) as usize;
+                let mut done = 0usize;
+                while done < count {
+                    let pos = offset as usize + done;
+                    let in_page = pos % SHM_PAGE_SIZE;
+                    let chunk = min(count - done, SHM_PAGE_SIZE - in_page);
+                    let page = match file.data_page_mut(pages, pos / SHM_PAGE_SIZE) {
+                        Ok(page) => page,
+                        Err(_) if done > 0 => break,
+                        Err(err) => return Err(err),
+                    };
+                    // SAFETY: 数据页归本文件所有，chunk 不越过页尾。
+                    unsafe {
+                        ptr::copy_nonoverlapping(buf[done..].as_ptr(), page.as_ptr().add(in_page), chunk);
+                    }
+                    done += chunk;
+                }
+                file.len = file.len.max(offset + done as u64);
+                Ok(done)
+            }),
+            _ => match self.device(inode).map(|dev| dev.kind) {
+                Some(DeviceKind::Block { device, blocks }) => block_write(device, blocks, offset, buf),
+                Some(DeviceKind::Char { device }) => device.write(buf),
+                None => Err(VfsError::NotSupported),
+            },
         }
     }
 
     fn read_dir(&self, inode: InodeId, offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
-        if inode != ROOT_ID {
-            return Err(VfsError::NotDir);
+        let fixed: &[DirEntrySpec] = match inode {
+            ROOT_ID => &DEV_ENTRIES,
+            DEV_SHM_ID => &SHM_ENTRIES,
+            _ => return Err(VfsError::NotDir),
+        };
+        let mut written = fill_dir_entries(fixed, offset, entries)?;
+        let mut index = fixed.len();
+        let mut push = |ino: InodeId, name: &[u8], file_type: FileType| -> VfsResult<bool> {
+            if written >= entries.len() {
+                return Ok(false);
+            }
+            if index >= offset {
+                let mut entry = DirEntry::empty();
+                entry.ino = ino;
+                entry.file_type = file_type;
+                entry.set_name(name)?;
+                entries[written] = entry;
+                written += 1;
+            }
+            index += 1;
+            Ok(true)
+        };
+        if inode == ROOT_ID {
+            let mut guard = self.devices.lock();
+            for (slot, node) in guard.get_mut().iter().enumerate() {
+                let Some(node) = node else {
+                    continue;
+                };
+                if !push(DYNAMIC_BASE_ID + slot as InodeId, node.name(), node.file_type())? {
+                    break;
+                }
+            }
+        } else {
+            let mut guard = self.shm.lock();
+            for file in guard.get_mut().files.iter().filter(|file| file.used) {
+                if !push(file.ino, file.name(), file.file_type)? {
+                    break;
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&self) -> VfsResult<()> {
+        let mut guard = self.devices.lock();
+        for node in guard.get_mut().iter().flatten() {
+            if let DeviceKind::Block { device, .. } = node.kind {
+                device.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn statfs(&self) -> VfsResult<StatFs> {
+        // devtmpfs reports tmpfs values; only /dev/shm contents consume space.
+        let mut stat = StatFs::empty(TMPFS_MAGIC);
+        let block_size = stat.block_size as usize;
+        let free_devices = self.devices.lock().get_mut().iter().filter(|node| node.is_none()).count();
+        let mut guard = self.shm.lock();
+        let shm = guard.get_mut();
+        let free_shm = shm.files.iter().filter(|file| !file.used).count();
+        stat.blocks = (shm.pages.limit * SHM_PAGE_SIZE / block_size) as u64;
+        stat.blocks_free = (shm.pages.limit.saturating_sub(shm.pages.used) * SHM_PAGE_SIZE / block_size) as u64;
+        stat.blocks_avail = stat.blocks_free;
+        stat.files = (NODES.len() + MAX_DEVICE_NODES + MAX_SHM_FILES) as u64;
+        stat.files_free = (free_devices + free_shm) as u64;
+        Ok(stat)
+    }
+
+    fn truncate(&self, inode: InodeId, size: u64) -> VfsResult<()> {
+        if size > MAX_SHM_FILE_SIZE {
+            return Err(VfsError::NoSpace);
+        }
+        self.with_shm(inode, |file, pages| {
+            if file.file_type != FileType::File {
+                return Err(VfsError::Invalid);
+            }
+            if size < file.len {
+                // 截断后的尾部清零，保证之后扩展或空洞读到 0；扩展本身不分配页。
+                let size = size as usize;
+                file.release_pages(pages, (size + SHM_PAGE_SIZE - 1) / SHM_PAGE_SIZE);
+                if let Some(page) = file.data_page(size / SHM_PAGE_SIZE) {
+                    let in_page = size % SHM_PAGE_SIZE;
+                    // SAFETY: 数据页归本文件所有，清零范围不越过页尾。
+                    unsafe { ptr::write_bytes(page.as_ptr().add(in_page), 0, SHM_PAGE_SIZE - in_page) };
+                }
+            }
+            file.len = size;
+            Ok(())
+        })
+    }
+
+    fn getxattr(&self, inode: InodeId, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        check_shm_xattr_name(name)?;
+        self.with_shm(inode, |file, _| {
+            let (_, value) = file.find_xattr(name.as_bytes()).ok_or(VfsError::NotFound)?;
+            let count = min(value.len(), buf.len());
+            buf[..count].copy_from_slice(&value[..count]);
+            Ok(value.len())
+        })
+    }
+
+    fn setxattr(&self, inode: InodeId, name: &str, value: &[u8], mode: XattrMode) -> VfsResult<()> {
+        check_shm_xattr_name(name)?;
+        let name = name.as_bytes();
+        self.with_shm(inode, |file, _| {
+            let existing = file.find_xattr(name).map(|(offset, value)| (offset, value.len()));
+            match (mode, existing) {
+                (XattrMode::Create, Some(_)) => return Err(VfsError::AlreadyExists),
+                (XattrMode::Replace, None) => return Err(VfsError::NotFound),
+                _ => {}
+            }
+            let freed = existing.map_or(0, |(_, len)| SHM_XATTR_HEADER + name.len() + len);
+            let record = SHM_XATTR_HEADER + name.len() + value.len();
+            if file.xattr_len - freed + record > SHM_XATTR_SIZE {
+                return Err(VfsError::NoSpace);
+            }
+            if let Some((offset, _)) = existing {
+                file.remove_xattr_at(offset);
+            }
+            let at = file.xattr_len;
+            file.xattrs[at] = name.len() as u8;
+            file.xattrs[at + 1..at + SHM_XATTR_HEADER].copy_from_slice(&(value.len() as u16).to_le_bytes());
+            file.xattrs[at + SHM_XATTR_HEADER..][..name.len()].copy_from_slice(name);
+            file.xattrs[at + SHM_XATTR_HEADER + name.len()..][..value.len()].copy_from_slice(value);
+            file.xattr_len += record;
+            Ok(())
+        })
+    }
+
+    fn listxattr(&self, inode: InodeId, buf: &mut [u8]) -> VfsResult<usize> {
+        self.with_shm(inode, |file, _| {
+            let mut len = 0;
+            for (_, name, _) in file.xattr_records() {
+                for part in [name, b"\0"] {
+                    if let Some(dst) = buf.get_mut(len..len + part.len()) {
+                        dst.copy_from_slice(part);
+                    }
+                    len += part.len();
+                }
+            }
+            Ok(len)
+        })
+    }
+
+    fn removexattr(&self, inode: InodeId, name: &str) -> VfsResult<()> {
+        check_shm_xattr_name(name)?;
+        self.with_shm(inode, |file, _| {
+            let (offset, _) = file.find_xattr(name.as_bytes()).ok_or(VfsError::NotFound)?;
+            file.remove_xattr_at(offset);
+            Ok(())
+        })
+    }
+
+    fn set_mode(&self, inode: InodeId, mode: u16) -> VfsResult<()> {
+        if !Self::is_shm(inode) {
+            return Err(VfsError::NotSupported);
+        }
+        self.with_shm(inode, |file, _| {
+            file.mode = mode & 0o7777;
+            Ok(())
+        })
+    }
+
+    fn set_owner(&self, inode: InodeId, uid: u32, gid: u32) -> VfsResult<()> {
+        if !Self::is_shm(inode) {
+            return Err(VfsError::NotSupported);
+        }
+        self.with_shm(inode, |file, _| {
+            file.uid = uid;
+            file.gid = gid;
+            Ok(())
+        })
+    }
+}
+
+fn check_shm_xattr_name(name: &str) -> VfsResult<()> {
+    let prefix = SHM_XATTR_PREFIXES
+        .iter()
+        .find(|prefix| name.starts_with(*prefix))
+        .ok_or(VfsError::NotSupported)?;
+    if name.len() == prefix.len() || name.len() > u8::MAX as usize {
+        return Err(VfsError::Invalid);
+    }
+    Ok(())
+}
+
+fn block_read(device: &dyn BlockDevice, blocks: u64, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+    let block_size = device.block_size();
+    let size = blocks * block_size as u64;
+    if offset >= size {
+        return Ok(0);
+    }
+    let total = min(buf.len() as u64, size - offset) as usize;
+    let mut scratch = [0u8; BLOCK_SCRATCH_SIZE];
+    let mut done = 0usize;
+    while done < total {
+        let pos = offset + done as u64;
+        let in_block = (pos % block_size as u64) as usize;
+        let chunk = min(total - done, block_size - in_block);
+        device.read_block(pos / block_size as u64, &mut scratch[..block_size])?;
+        buf[done..done + chunk].copy_from_slice(&scratch[in_block..in_block + chunk]);
+        done += chunk;
+    }
+    Ok(total)
+}
+
+fn block_write(device: &dyn BlockDevice, blocks: u64, offset: u64, buf: &[u8]) -> VfsResult<usize> {
+    let block_size = device.block_size();
+    let size = blocks * block_size as u64;
+    if offset >= size {
+        return Err(VfsError::NoSpace);
+    }
+    let total = min(buf.len() as u64, size - offset) as usize;
+    let mut scratch = [0u8; BLOCK_SCRATCH_SIZE];
+    let mut done = 0usize;
+    while done < total {
+        let pos = offset + done as u64;
+        let block = pos / block_size as u64;
+        let in_block = (pos % block_size as u64) as usize;
+        let chunk = min(total - done, block_size - in_block);
+        if chunk < block_size {
+            // Partial block: read-modify-write.
+            device.read_block(block, &mut scratch[..block_size])?;
         }
-        fill_dir_entries(&DEV_ENTRIES, offset, entries)
+        scratch[in_block..in_block + chunk].copy_from_slice(&buf[done..done + chunk]);
+        device.write_block(block, &scratch[..block_size])?;
+        done += chunk;
     }
+    Ok(total)
 }
 
 fn fill_dir_entries(list: &[DirEntrySpec], offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
@@ -154,3 +1080,341 @@ fn fill_dir_entries(list: &[DirEntrySpec], offset: usize, entries: &mut [DirEntr
     }
     Ok(written)
 }
+
+/// Heap-backed /dev/shm pages for host tests.
+#[cfg(test)]
+pub(crate) struct HostShmPages;
+
+#[cfg(test)]
+impl HostShmPages {
+    const LAYOUT: std::alloc::Layout = match std::alloc::Layout::from_size_align(SHM_PAGE_SIZE, SHM_PAGE_SIZE) {
+        Ok(layout) => layout,
+        Err(_) => panic!("bad shm page layout"),
+    };
+}
+
+#[cfg(test)]
+impl ShmMemory for HostShmPages {
+    fn alloc_page(&self) -> Option<NonNull<u8>> {
+        // SAFETY: 布局大小非零。
+        NonNull::new(unsafe { std::alloc::alloc_zeroed(Self::LAYOUT) })
+    }
+
+    fn free_page(&self, page: NonNull<u8>) {
+        // SAFETY: 页来自同一布局的 alloc_page。
+        unsafe { std::alloc::dealloc(page.as_ptr(), Self::LAYOUT) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::BlockId;
+    use core::cell::RefCell;
+    use std::boxed::Box;
+
+    fn shm_fs(limit: usize) -> DevFs {
+        let fs = DevFs::new();
+        fs.set_shm_memory(&HostShmPages, limit);
+        fs
+    }
+
+    struct TestBlockDevice {
+        data: RefCell<[u8; 2048]>,
+    }
+
+    impl BlockDevice for TestBlockDevice {
+        fn block_size(&self) -> usize {
+            512
+        }
+
+        fn read_block(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+            let offset = block_id as usize * 512;
+            buf[..512].copy_from_slice(&self.data.borrow()[offset..offset + 512]);
+            Ok(())
+        }
+
+        fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+            let offset = block_id as usize * 512;
+            self.data.borrow_mut()[offset..offset + 512].copy_from_slice(&buf[..512]);
+            Ok(())
+        }
+
+        fn flush(&self) -> VfsResult<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn block_node_read_write() {
+        let dev: &'static TestBlockDevice = Box::leak(Box::new(TestBlockDevice {
+            data: RefCell::new([0u8; 2048]),
+        }));
+        let fs = DevFs::new();
+        let inode = fs.register_block("vda", 254, 0, dev, 4).unwrap();
+        assert_eq!(fs.lookup(ROOT_ID, "vda").unwrap(), Some(inode));
+        assert_eq!(fs.register_block("vda", 254, 0, dev, 4), Err(VfsError::AlreadyExists));
+        let meta = fs.metadata(inode).unwrap();
+        assert_eq!(meta.file_type, FileType::Block);
+        assert_eq!(meta.size, 2048);
+        assert_eq!(fs.device_number(inode), Some((254, 0)));
+
+        // Unaligned write spanning two sectors.
+        assert_eq!(fs.write_at(inode, 510, b"abcd").unwrap(), 4);
+        assert_eq!(&dev.data.borrow()[510..514], b"abcd");
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read_at(inode, 510, &mut buf).unwrap(), 4);
+        assert_eq!(&buf, b"abcd");
+        assert_eq!(fs.read_at(inode, 2048, &mut buf).unwrap(), 0);
+
+        let mut entries = [DirEntry::empty(); 8];
+        let count = fs.read_dir(ROOT_ID, 0, &mut entries).unwrap();
+        assert_eq!(count, DEV_ENTRIES.len() + 1);
+        assert_eq!(entries[count - 1].name(), b"vda");
+    }
+
+    struct EchoDevice {
+        last: RefCell<u8>,
+    }
+
+    // SAFETY: tests access the device from a single thread.
+    unsafe impl Sync for EchoDevice {}
+
+    impl CharDevice for EchoDevice {
+        fn read(&self, buf: &mut [u8]) -> VfsResult<usize> {
+            let last = *self.last.borrow();
+            if last == 0 {
+                return Err(VfsError::Busy);
+            }
+            buf[0] = last;
+            *self.last.borrow_mut() = 0;
+            Ok(1)
+        }
+
+        fn write(&self, buf: &[u8]) -> VfsResult<usize> {
+            *self.last.borrow_mut() = *buf.last().unwrap_or(&0);
+            Ok(buf.len())
+        }
+
+        fn poll_read(&self) -> bool {
+            *self.last.borrow() != 0
+        }
+    }
+
+    #[test]
+    fn char_node_and_builtin_devices() {
+        let dev: &'static EchoDevice = Box::leak(Box::new(EchoDevice {
+            last: RefCell::new(0),
+        }));
+        let fs = DevFs::new();
+        let inode = fs.register_char("ttyS0", 4, 64, 0o620, dev).unwrap();
+        assert_eq!(fs.metadata(inode).unwrap().file_type, FileType::Char);
+        assert_eq!(fs.device_number(inode), Some((4, 64)));
+        let mut buf = [0u8; 4];
+        assert_eq!(fs.read_at(inode, 0, &mut buf), Err(VfsError::Busy));
+        assert!(!fs.poll_read(inode));
+        assert_eq!(fs.write_at(inode, 0, b"hi").unwrap(), 2);
+        assert!(fs.poll_read(inode));
+        assert_eq!(fs.read_at(inode, 0, &mut buf).unwrap(), 1);
+        assert_eq!(buf[0], b'i');
+
+        let full = fs.lookup(ROOT_ID, "full").unwrap().unwrap();
+        assert_eq!(fs.device_number(full), Some((1, 7)));
+        assert_eq!(fs.write_at(full, 0, b"x"), Err(VfsError::NoSpace));
+        buf.fill(0xff);
+        assert_eq!(fs.read_at(full, 0, &mut buf).unwrap(), 4);
+        assert_eq!(buf, [0; 4]);
+    }
+
+    #[test]
+    fn shm_files_create_write_truncate_remove() {
+        let fs = shm_fs(1 << 20);
+        let shm = fs.lookup(ROOT_ID, "shm").unwrap().unwrap();
+        assert_eq!(shm, DEV_SHM_ID);
+        assert_eq!(fs.create(ROOT_ID, "foo", FileType::File, 0o600), Err(VfsError::NotSupported));
+        let inode = fs.create(shm, "sem.test", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.lookup(shm, "sem.test").unwrap(), Some(inode));
+        assert_eq!(fs.create(shm, "sem.test", FileType::File, 0o600), Err(VfsError::AlreadyExists));
+
+        assert_eq!(fs.write_at(inode, 4, b"data").unwrap(), 4);
+        let mut buf = [0xffu8; 8];
+        assert_eq!(fs.read_at(inode, 0, &mut buf).unwrap(), 8);
+        assert_eq!(&buf, b"\0\0\0\0data");
+        fs.truncate(inode, 2).unwrap();
+        assert_eq!(fs.metadata(inode).unwrap().size, 2);
+        fs.truncate(inode, 8).unwrap();
+        assert_eq!(fs.read_at(inode, 0, &mut buf).unwrap(), 8);
+        assert_eq!(&buf, b"\0\0\0\0\0\0\0\0");
+        assert_eq!(fs.truncate(inode, MAX_SHM_FILE_SIZE + 1), Err(VfsError::NoSpace));
+
+        let mut entries = [DirEntry::empty(); 4];
+        let count = fs.read_dir(shm, 0, &mut entries).unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(entries[2].name(), b"sem.test");
+
+        let fifo = fs.create(shm, "ctl", FileType::Fifo, 0o600).unwrap();
+        assert_eq!(fs.metadata(fifo).unwrap().file_type, FileType::Fifo);
+        assert_eq!(fs.write_at(fifo, 0, b"x"), Err(VfsError::Invalid));
+        let null = fs.mknod(shm, "null", FileType::Char, 0o666, make_dev(1, 3)).unwrap();
+        let rdev = fs.metadata(null).unwrap().rdev;
+        assert_eq!(fs.lookup_rdev(rdev), fs.lookup(ROOT_ID, "null").unwrap());
+        assert_eq!(fs.lookup_rdev(make_dev(200, 1)), None);
+        fs.remove(shm, "ctl").unwrap();
+        fs.remove(shm, "null").unwrap();
+
+        fs.remove(shm, "sem.test").unwrap();
+        assert_eq!(fs.lookup(shm, "sem.test").unwrap(), None);
+        assert_eq!(fs.metadata(inode), Err(VfsError::NotFound));
+    }
+
+    #[test]
+    fn shm_rename_replaces_target() {
+        let fs = shm_fs(1 << 20);
+        let src = fs.create(DEV_SHM_ID, "new.tmp", FileType::File, 0o600).unwrap();
+        let dst = fs.create(DEV_SHM_ID, "data", FileType::File, 0o600).unwrap();
+        fs.write_at(src, 0, b"fresh").unwrap();
+        fs.write_at(dst, 0, b"stale").unwrap();
+        let before = fs.statfs().unwrap().blocks_free;
+        fs.rename(DEV_SHM_ID, "new.tmp", DEV_SHM_ID, "data").unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, before + 3);
+        assert_eq!(fs.lookup(DEV_SHM_ID, "new.tmp").unwrap(), None);
+        assert_eq!(fs.lookup(DEV_SHM_ID, "data").unwrap(), Some(src));
+        assert_eq!(fs.metadata(dst), Err(VfsError::NotFound));
+        let mut buf = [0u8; 5];
+        fs.read_at(src, 0, &mut buf).unwrap();
+        assert_eq!(&buf, b"fresh");
+        assert_eq!(fs.rename(DEV_SHM_ID, "missing", DEV_SHM_ID, "x"), Err(VfsError::NotFound));
+        assert_eq!(fs.rename(ROOT_ID, "null", DEV_SHM_ID, "x"), Err(VfsError::NotSupported));
+    }
+
+    #[test]
+    fn shm_owner_and_mode() {
+        use axvfs::{Credentials, ACCESS_EXEC, ACCESS_READ, ACCESS_WRITE};
+
+        let fs = DevFs::new();
+        let inode = fs.create(DEV_SHM_ID, "secret", FileType::File, 0o640).unwrap();
+        fs.set_owner(inode, 1000, 100).unwrap();
+        let groups = [100];
+        let owner = Credentials::new(1000, 1000, &[]);
+        let member = Credentials::new(2000, 2000, &groups);
+        let other = Credentials::new(3000, 3000, &[]);
+        let root = Credentials::new(0, 0, &[]);
+        let meta = fs.metadata(inode).unwrap();
+        assert_eq!((meta.uid, meta.gid), (1000, 100));
+        assert!(owner.may_access(&meta, ACCESS_READ | ACCESS_WRITE));
+        assert!(member.may_access(&meta, ACCESS_READ));
+        assert!(!member.may_access(&meta, ACCESS_WRITE));
+        assert!(!other.may_access(&meta, ACCESS_READ));
+        assert!(root.may_access(&meta, ACCESS_READ | ACCESS_WRITE));
+        assert!(!root.may_access(&meta, ACCESS_EXEC));
+        fs.set_mode(inode, 0o4000).unwrap();
+        let meta = fs.metadata(inode).unwrap();
+        assert_eq!(meta.mode, 0o4000);
+        assert!(!owner.may_access(&meta, ACCESS_READ));
+        assert_eq!(fs.set_mode(DEV_SHM_ID, 0o700), Err(VfsError::NotSupported));
+        assert_eq!(fs.set_owner(DEV_NULL_ID, 1000, 1000), Err(VfsError::NotSupported));
+    }
+
+    #[test]
+    fn statfs_tracks_shm_usage() {
+        let fs = shm_fs(1 << 20);
+        let before = fs.statfs().unwrap();
+        assert_eq!(before.magic, TMPFS_MAGIC);
+        assert_eq!(before.blocks, before.blocks_free);
+        assert_eq!(before.blocks * before.block_size as u64, 1 << 20);
+        let inode = fs.create(DEV_SHM_ID, "seg", FileType::File, 0o600).unwrap();
+        fs.write_at(inode, 0, &[1u8; 5000]).unwrap();
+        let after = fs.statfs().unwrap();
+        // 两个数据页加两级索引页。
+        assert_eq!(after.blocks_free, before.blocks_free - 4);
+        assert_eq!(after.files_free, before.files_free - 1);
+        fs.truncate(inode, 100).unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, before.blocks_free - 3);
+        fs.remove(DEV_SHM_ID, "seg").unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, before.blocks_free);
+    }
+
+    #[test]
+    fn shm_files_grow_past_one_page_and_stay_sparse() {
+        let fs = shm_fs(1 << 20);
+        let inode = fs.create(DEV_SHM_ID, "big", FileType::File, 0o600).unwrap();
+        let data: std::vec::Vec<u8> = (0..40_000u32).map(|i| i as u8).collect();
+        assert_eq!(fs.write_at(inode, 3, &data).unwrap(), data.len());
+        let mut back = std::vec![0u8; data.len()];
+        assert_eq!(fs.read_at(inode, 3, &mut back).unwrap(), data.len());
+        assert_eq!(back, data);
+
+        let free = fs.statfs().unwrap().blocks_free;
+        fs.truncate(inode, 512 << 20).unwrap();
+        assert_eq!(fs.metadata(inode).unwrap().size, 512 << 20);
+        assert_eq!(fs.statfs().unwrap().blocks_free, free);
+        let mut buf = [0xffu8; 16];
+        assert_eq!(fs.read_at(inode, 300 << 20, &mut buf).unwrap(), 16);
+        assert_eq!(buf, [0; 16]);
+    }
+
+    #[test]
+    fn shm_limit_and_missing_memory() {
+        let fs = DevFs::new();
+        let inode = fs.create(DEV_SHM_ID, "seg", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.write_at(inode, 0, b"x"), Err(VfsError::NoSpace));
+        fs.truncate(inode, 4096).unwrap();
+
+        let fs = shm_fs(4 * SHM_PAGE_SIZE);
+        let inode = fs.create(DEV_SHM_ID, "seg", FileType::File, 0o600).unwrap();
+        // 索引页占两页，剩余两页装数据。
+        assert_eq!(fs.write_at(inode, 0, &[7u8; 3 * SHM_PAGE_SIZE]).unwrap(), 2 * SHM_PAGE_SIZE);
+        assert_eq!(fs.write_at(inode, 2 * SHM_PAGE_SIZE as u64, b"x"), Err(VfsError::NoSpace));
+        assert_eq!(fs.statfs().unwrap().blocks_free, 0);
+        fs.remove(DEV_SHM_ID, "seg").unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, 4);
+    }
+
+    #[test]
+    fn shm_inodes_are_not_reused() {
+        let fs = shm_fs(1 << 20);
+        let first = fs.create(DEV_SHM_ID, "a", FileType::File, 0o600).unwrap();
+        fs.remove(DEV_SHM_ID, "a").unwrap();
+        let second = fs.create(DEV_SHM_ID, "b", FileType::File, 0o600).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs.metadata(first), Err(VfsError::NotFound));
+        for i in 0..MAX_SHM_FILES - 1 {
+            let name = std::format!("f{i}");
+            fs.create(DEV_SHM_ID, &name, FileType::File, 0o600).unwrap();
+        }
+        assert_eq!(fs.create(DEV_SHM_ID, "full", FileType::File, 0o600), Err(VfsError::NoSpace));
+    }
+
+    #[test]
+    fn shm_xattrs() {
+        let fs = DevFs::new();
+        let inode = fs.create(DEV_SHM_ID, "blob", FileType::File, 0o600).unwrap();
+        let mut buf = [0u8; 64];
+        assert_eq!(fs.getxattr(inode, "user.tag", &mut buf), Err(VfsError::NotFound));
+        assert_eq!(
+            fs.setxattr(inode, "system.posix_acl_access", b"x", XattrMode::Any),
+            Err(VfsError::NotSupported)
+        );
+        fs.setxattr(inode, "user.tag", b"one", XattrMode::Create).unwrap();
+        fs.setxattr(inode, "trusted.k", b"v", XattrMode::Any).unwrap();
+        assert_eq!(
+            fs.setxattr(inode, "user.tag", b"two", XattrMode::Create),
+            Err(VfsError::AlreadyExists)
+        );
+        fs.setxattr(inode, "user.tag", b"three", XattrMode::Replace).unwrap();
+        let len = fs.getxattr(inode, "user.tag", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"three");
+        let len = fs.listxattr(inode, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"trusted.k\0user.tag\0");
+        assert_eq!(
+            fs.setxattr(inode, "user.big", &[0; SHM_XATTR_SIZE], XattrMode::Any),
+            Err(VfsError::NoSpace)
+        );
+        fs.removexattr(inode, "trusted.k").unwrap();
+        assert_eq!(fs.removexattr(inode, "trusted.k"), Err(VfsError::NotFound));
+
+        fs.remove(DEV_SHM_ID, "blob").unwrap();
+        let inode = fs.create(DEV_SHM_ID, "blob", FileType::File, 0o600).unwrap();
+        assert_eq!(fs.listxattr(inode, &mut buf).unwrap(), 0);
+    }
+}
@@ -1,6 +1,9 @@
 //! ext4 filesystem implementation.
 
-use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult};
+use axvfs::{
+    DirEntry, FallocMode, FileExtent, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult, XattrMode,
+    EXT4_SUPER_MAGIC,
+};
 use core::cell::UnsafeCell;
 use core::hint::spin_loop;
 use core::sync::atomic::{AtomicBool, Ordering};
@@ -11,30 +14,61 @@ const EXT4_ROOT_INODE: InodeId = 2;
 const EXT4_MAGIC: u16 = 0xef53;
 const SUPERBLOCK_OFFSET: u64 = 1024;
 const SUPERBLOCK_SIZE: usize = 1024;
+const SUPERBLOCK_INODES_COUNT_OFFSET: usize = 0;
+const SUPERBLOCK_BLOCKS_COUNT_OFFSET: usize = 4;
+const SUPERBLOCK_R_BLOCKS_COUNT_OFFSET: usize = 8;
+const SUPERBLOCK_FREE_BLOCKS_OFFSET: usize = 12;
+const SUPERBLOCK_FREE_INODES_OFFSET: usize = 16;
+const SUPERBLOCK_FIRST_DATA_BLOCK_OFFSET: usize = 20;
 const SUPERBLOCK_LOG_BLOCK_SIZE_OFFSET: usize = 24;
 const SUPERBLOCK_BLOCKS_PER_GROUP_OFFSET: usize = 32;
 const SUPERBLOCK_INODES_PER_GROUP_OFFSET: usize = 40;
 const SUPERBLOCK_MAGIC_OFFSET: usize = 56;
 const SUPERBLOCK_INODE_SIZE_OFFSET: usize = 88;
+const SUPERBLOCK_FEATURE_INCOMPAT_OFFSET: usize = 96;
+const SUPERBLOCK_DESC_SIZE_OFFSET: usize = 254;
+const INCOMPAT_64BIT: u32 = 0x80;
 const GROUP_DESC_SIZE: usize = 32;
 const GROUP_DESC_BLOCK_BITMAP_OFFSET: usize = 0;
 const GROUP_DESC_INODE_BITMAP_OFFSET: usize = 4;
 const GROUP_DESC_INODE_TABLE_OFFSET: usize = 8;
+const GROUP_DESC_FREE_BLOCKS_OFFSET: usize = 12;
+const GROUP_DESC_FREE_INODES_OFFSET: usize = 14;
+const GROUP_DESC_USED_DIRS_OFFSET: usize = 16;
+const GROUP_DESC_FLAGS_OFFSET: usize = 18;
+const BG_INODE_UNINIT: u16 = 0x1;
+const BG_BLOCK_UNINIT: u16 = 0x2;
 const INODE_MODE_OFFSET: usize = 0;
+const INODE_UID_OFFSET: usize = 2;
 const INODE_SIZE_LO_OFFSET: usize = 4;
+const INODE_GID_OFFSET: usize = 24;
+const INODE_LINKS_COUNT_OFFSET: usize = 26;
 const INODE_FLAGS_OFFSET: usize = 32;
 const INODE_BLOCK_OFFSET: usize = 40;
 const INODE_BLOCK_LEN: usize = 60;
+const INODE_FILE_ACL_LO_OFFSET: usize = 104;
 const INODE_SIZE_HIGH_OFFSET: usize = 108;
+const INODE_FILE_ACL_HIGH_OFFSET: usize = 118;
+const INODE_UID_HIGH_OFFSET: usize = 120;
+const INODE_GID_HIGH_OFFSET: usize = 122;
+const EXT4_GOOD_OLD_INODE_SIZE: usize = 128;
+const INODE_EXTRA_ISIZE_OFFSET: usize = 128;
+const EXT4_NEW_EXTRA_ISIZE: u16 = 32;
 const EXT4_EXTENTS_FLAG: u32 = 0x0008_0000;
 const EXTENT_HEADER_MAGIC: u16 = 0xf30a;
 const EXTENT_HEADER_SIZE: usize = 12;
 const EXTENT_ENTRY_SIZE: usize = 12;
-const EXTENT_LEN_MAX: u16 = 0x7fff;
+const EXT_INIT_MAX_LEN: u32 = 0x8000;
+const EXT_UNWRITTEN_MAX_LEN: u32 = 0x7fff;
+const EXTENT_MAX_DEPTH: usize = 5;
 const EXTENT_INODE_CAPACITY: usize = (INODE_BLOCK_LEN - EXTENT_HEADER_SIZE) / EXTENT_ENTRY_SIZE;
 const EXT4_SCRATCH_SIZE: usize = 4096;
 const EXT4_MODE_DIR: u16 = 0x4000;
 const EXT4_MODE_FILE: u16 = 0x8000;
+const EXT4_MODE_FIFO: u16 = 0x1000;
+const EXT4_MODE_CHAR: u16 = 0x2000;
+const EXT4_MODE_BLOCK: u16 = 0x6000;
+const EXT4_MODE_SOCKET: u16 = 0xC000;
 const EXT4_DIR_ENTRY_HEADER: usize = 8;
 const EXT4_DIR_ENTRY_FILE: u8 = 1;
 const EXT4_DIR_ENTRY_DIR: u8 = 2;
@@ -44,6 +78,26 @@ const EXT4_DIR_ENTRY_FIFO: u8 = 5;
 const EXT4_DIR_ENTRY_SOCKET: u8 = 6;
 const EXT4_DIR_ENTRY_SYMLINK: u8 = 7;
 const EXT4_DIRECT_BLOCKS: usize = 12;
+const EXT4_MAX_LOGICAL_BLOCKS: u64 = 1 << 32;
+const EXT4_DELALLOC_SLOTS: usize = 16;
+const BITMAP_CHUNK: usize = 64;
+const XATTR_MAGIC: u32 = 0xea02_0000;
+const XATTR_BLOCK_REFCOUNT_OFFSET: usize = 4;
+const XATTR_BLOCK_BLOCKS_OFFSET: usize = 8;
+const XATTR_BLOCK_HASH_OFFSET: usize = 12;
+const XATTR_BLOCK_HEADER_SIZE: usize = 32;
+const XATTR_IBODY_HEADER_SIZE: usize = 4;
+const XATTR_ENTRY_HEADER_SIZE: usize = 16;
+/// Name prefix for each on-disk `e_name_index`; the POSIX ACL names are
+/// complete names and must be matched before the generic `system.` prefix.
+const XATTR_PREFIXES: [(u8, &str); 6] = [
+    (2, "system.posix_acl_access"),
+    (3, "system.posix_acl_default"),
+    (1, "user."),
+    (4, "trusted."),
+    (6, "security."),
+    (7, "system."),
+];
 
 struct ScratchLock {
     locked: AtomicBool,
@@ -91,9 +145,86 @@ impl Drop for ScratchGuard<'_> {
 
 static EXT4_SCRATCH: ScratchLock = ScratchLock::new();
 
+/// A file block that has been written but has no disk block yet.
+struct DelallocSlot {
+    owner: usize,
+    inode: InodeId,
+    logical: u32,
+    used: bool,
+    data: [u8; EXT4_SCRATCH_SIZE],
+}
+
+impl DelallocSlot {
+    const fn new() -> Self {
+        Self {
+            owner: 0,
+            inode: 0,
+            logical: 0,
+            used: false,
+            data: [0u8; EXT4_SCRATCH_SIZE],
+        }
+    }
+
+    fn is(&self, owner: usize, inode: InodeId, logical: u32) -> bool {
+        self.used && self.owner == owner && self.inode == inode && self.logical == logical
+    }
+}
+
+struct DelallocLock {
+    locked: AtomicBool,
+    slots: UnsafeCell<[DelallocSlot; EXT4_DELALLOC_SLOTS]>,
+}
+
+unsafe impl Sync for DelallocLock {}
+
+impl DelallocLock {
+    const fn new() -> Self {
+        const EMPTY: DelallocSlot = DelallocSlot::new();
+        Self {
+            locked: AtomicBool::new(false),
+            slots: UnsafeCell::new([EMPTY; EXT4_DELALLOC_SLOTS]),
+        }
+    }
+
+    fn lock(&self) -> DelallocGuard<'_> {
+        while self
+            .locked
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            spin_loop();
+        }
+        DelallocGuard { lock: self }
+    }
+}
+
+struct DelallocGuard<'a> {
+    lock: &'a DelallocLock,
+}
+
+impl<'a> DelallocGuard<'a> {
+    fn get_mut(&self) -> &mut [DelallocSlot; EXT4_DELALLOC_SLOTS] {
+        // SAFETY: guard ensures exclusive access to the staging slots.
+        unsafe { &mut *self.lock.slots.get() }
+    }
+}
+
+impl Drop for DelallocGuard<'_> {
+    fn drop(&mut self) {
+        self.lock.locked.store(false, Ordering::Release);
+    }
+}
+
+// 延迟分配暂存区：写入空洞的数据先留在这里，回写时按逻辑连续区间一次分配物理块。
+static EXT4_DELALLOC: DelallocLock = DelallocLock::new();
+
 #[derive(Clone, Copy, Debug)]
 /// ext4 superblock fields required by this implementation.
 pub struct SuperBlock {
+    /// Total block count (0 when the image leaves it unset).
+    pub blocks_count: u64,
+    /// First block covered by group 0's bitmap.
+    pub first_data_block: u32,
     /// log2(block_size / 1024).
     pub log_block_size: u32,
     /// Blocks per group.
@@ -102,6 +233,8 @@ pub struct SuperBlock {
     pub inodes_per_group: u32,
     /// Inode size in bytes.
     pub inode_size: u16,
+    /// Group descriptor size (64 or more with the 64bit feature, else 32).
+    pub desc_size: u16,
     /// ext4 magic value.
     pub magic: u16,
 }
@@ -116,6 +249,8 @@ impl SuperBlock {
         if magic != EXT4_MAGIC {
             return Err(VfsError::Invalid);
         }
+        let blocks_count = read_u32(buf, SUPERBLOCK_BLOCKS_COUNT_OFFSET) as u64;
+        let first_data_block = read_u32(buf, SUPERBLOCK_FIRST_DATA_BLOCK_OFFSET);
         let log_block_size = read_u32(buf, SUPERBLOCK_LOG_BLOCK_SIZE_OFFSET);
         let blocks_per_group = read_u32(buf, SUPERBLOCK_BLOCKS_PER_GROUP_OFFSET);
         let inodes_per_group = read_u32(buf, SUPERBLOCK_INODES_PER_GROUP_OFFSET);
@@ -124,11 +259,19 @@ impl SuperBlock {
         if block_size < 1024 || !block_size.is_power_of_two() || inode_size == 0 {
             return Err(VfsError::Invalid);
         }
+        let desc_size = if read_u32(buf, SUPERBLOCK_FEATURE_INCOMPAT_OFFSET) & INCOMPAT_64BIT != 0 {
+            read_u16(buf, SUPERBLOCK_DESC_SIZE_OFFSET).max(GROUP_DESC_SIZE as u16)
+        } else {
+            GROUP_DESC_SIZE as u16
+        };
         Ok(Self {
+            blocks_count,
+            first_data_block,
             log_block_size,
             blocks_per_group,
             inodes_per_group,
             inode_size,
+            desc_size,
             magic,
         })
     }
@@ -144,6 +287,8 @@ struct GroupDesc {
     block_bitmap: u32,
     inode_bitmap: u32,
     inode_table: u32,
+    free_blocks: u32,
+    flags: u16,
 }
 
 impl GroupDesc {
@@ -161,6 +306,8 @@ impl GroupDesc {
             block_bitmap,
             inode_bitmap,
             inode_table,
+            free_blocks: read_u16(buf, GROUP_DESC_FREE_BLOCKS_OFFSET) as u32,
+            flags: read_u16(buf, GROUP_DESC_FLAGS_OFFSET),
         })
     }
 }
@@ -168,11 +315,26 @@ impl GroupDesc {
 #[derive(Clone, Copy, Debug)]
 struct Ext4Inode {
     mode: u16,
+    uid: u32,
+    gid: u32,
     size: u64,
     flags: u32,
     blocks: [u32; 15],
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Superblock free counters next to the values recounted from the group bitmaps.
+pub struct Ext4Usage {
+    /// Free blocks recorded in the superblock.
+    pub sb_free_blocks: u64,
+    /// Clear bits across all block bitmaps.
+    pub bitmap_free_blocks: u64,
+    /// Free inodes recorded in the superblock.
+    pub sb_free_inodes: u64,
+    /// Clear bits across all inode bitmaps.
+    pub bitmap_free_inodes: u64,
+}
+
 /// ext4 filesystem backed by a block device.
 pub struct Ext4Fs<'a> {
     cache: BlockCache<'a>,
@@ -208,16 +370,60 @@ impl<'a> Ext4Fs<'a> {
         self.cache.read_block(block, buf)
     }
 
-    fn read_group_desc(&self, group: u32) -> VfsResult<GroupDesc> {
+    /// Write back staged blocks and recount free blocks and inodes from the bitmaps.
+    pub fn usage(&self) -> VfsResult<Ext4Usage> {
+        self.writeback(None)?;
+        let block_size = self.fs_block_size() as usize;
+        let mut raw = [0u8; 20];
+        read_bytes(&self.cache, SUPERBLOCK_OFFSET, &mut raw)?;
+        let mut usage = Ext4Usage {
+            sb_free_blocks: read_u32(&raw, SUPERBLOCK_FREE_BLOCKS_OFFSET) as u64,
+            sb_free_inodes: read_u32(&raw, SUPERBLOCK_FREE_INODES_OFFSET) as u64,
+            ..Ext4Usage::default()
+        };
+        let mut scratch = [0u8; EXT4_SCRATCH_SIZE];
+        for group in 0..self.block_groups() {
+            // 未初始化的位图内容无定义：块位图改用组描述符计数，inode 位图视为全空闲。
+            let desc = self.read_group_desc(group)?;
+            if desc.flags & BG_BLOCK_UNINIT != 0 {
+                usage.bitmap_free_blocks += desc.free_blocks as u64;
+            } else {
+                self.read_fs_block(desc.block_bitmap as u64, &mut scratch[..block_size])?;
+                usage.bitmap_free_blocks +=
+                    count_clear_bits(&scratch[..block_size], self.group_block_count(group)) as u64;
+            }
+            if desc.flags & BG_INODE_UNINIT != 0 {
+                usage.bitmap_free_inodes += self.superblock.inodes_per_group as u64;
+            } else {
+                self.read_fs_block(desc.inode_bitmap as u64, &mut scratch[..block_size])?;
+                usage.bitmap_free_inodes +=
+                    count_clear_bits(&scratch[..block_size], self.superblock.inodes_per_group) as u64;
+            }
+        }
+        Ok(usage)
+    }
+
+    fn group_desc_offset(&self, group: u32) -> u64 {
         let block_size = self.fs_block_size();
         let table_block = if block_size == 1024 { 2 } else { 1 };
-        let offset = table_block as u64 * block_size as u64
-            + group as u64 * GROUP_DESC_SIZE as u64;
+        table_block as u64 * block_size as u64 + group as u64 * self.superblock.desc_size as u64
+    }
+
+    fn read_group_desc(&self, group: u32) -> VfsResult<GroupDesc> {
         let mut buf = [0u8; GROUP_DESC_SIZE];
-        read_bytes(&self.cache, offset, &mut buf)?;
+        read_bytes(&self.cache, self.group_desc_offset(group), &mut buf)?;
         GroupDesc::parse(&buf)
     }
 
+    /// Adjust a 16-bit per-group counter (free blocks, free inodes, used dirs).
+    fn adjust_group_count(&self, group: u32, offset: usize, delta: i64) -> VfsResult<()> {
+        let pos = self.group_desc_offset(group) + offset as u64;
+        let mut raw = [0u8; 2];
+        read_bytes(&self.cache, pos, &mut raw)?;
+        let value = (u16::from_le_bytes(raw) as i64 + delta).clamp(0, u16::MAX as i64) as u16;
+        write_bytes(&self.cache, pos, &value.to_le_bytes())
+    }
+
     fn inode_location(&self, inode: InodeId) -> VfsResult<(u64, usize)> {
         if inode == 0 {
             return Err(VfsError::NotFound);
@@ -245,6 +451,8 @@ impl<'a> Ext4Fs<'a> {
         let mut buf = [0u8; 512];
         read_bytes(&self.cache, offset, &mut buf[..inode_size])?;
         let mode = read_u16(&buf, INODE_MODE_OFFSET);
+        let uid = read_u16(&buf, INODE_UID_OFFSET) as u32 | (read_u16(&buf, INODE_UID_HIGH_OFFSET) as u32) << 16;
+        let gid = read_u16(&buf, INODE_GID_OFFSET) as u32 | (read_u16(&buf, INODE_GID_HIGH_OFFSET) as u32) << 16;
         let size_lo = read_u32(&buf, INODE_SIZE_LO_OFFSET) as u64;
         let size_high = if inode_size >= INODE_SIZE_HIGH_OFFSET + 4 {
             read_u32(&buf, INODE_SIZE_HIGH_OFFSET) as u64
@@ -263,6 +471,8 @@ impl<'a> Ext4Fs<'a> {
         }
         Ok(Ext4Inode {
             mode,
+            uid,
+            gid,
             size,
             flags,
             blocks,
@@ -274,6 +484,10 @@ impl<'a> Ext4Fs<'a> {
         let mut buf = [0u8; 512];
         read_bytes(&self.cache, offset, &mut buf[..inode_size])?;
         write_u16(&mut buf, INODE_MODE_OFFSET, inode_meta.mode);
+        write_u16(&mut buf, INODE_UID_OFFSET, inode_meta.uid as u16);
+        write_u16(&mut buf, INODE_UID_HIGH_OFFSET, (inode_meta.uid >> 16) as u16);
+        write_u16(&mut buf, INODE_GID_OFFSET, inode_meta.gid as u16);
+        write_u16(&mut buf, INODE_GID_HIGH_OFFSET, (inode_meta.gid >> 16) as u16);
         write_u32(&mut buf, INODE_SIZE_LO_OFFSET, inode_meta.size as u32);
         if inode_size >= INODE_SIZE_HIGH_OFFSET + 4 {
             write_u32(&mut buf, INODE_SIZE_HIGH_OFFSET, (inode_meta.size >> 32) as u32);
@@ -288,11 +502,11 @@ impl<'a> Ext4Fs<'a> {
         write_bytes(&self.cache, offset, &buf[..inode_size])
     }
 
-    fn map_block(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<u64>> {
+    fn map_block(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<BlockMap>> {
         if (inode.flags & EXT4_EXTENTS_FLAG) != 0 {
             return self.map_extent_tree(inode, logical);
         }
-        self.map_indirect_block(inode, logical)
+        Ok(self.map_indirect_block(inode, logical)?.map(|phys| BlockMap { phys, unwritten: false }))
     }
 
     fn read_from_inode(&self, inode: &Ext4Inode, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
@@ -309,12 +523,12 @@ impl<'a> Ext4Fs<'a> {
             let in_block = (cur_offset % block_size as u64) as usize;
             let to_copy = core::cmp::min(remaining, block_size - in_block);
             match self.map_block(inode, block_index)? {
-                Some(phys) => {
-                    let block_offset = phys * block_size as u64 + in_block as u64;
+                Some(mapping) if !mapping.unwritten => {
+                    let block_offset = mapping.phys * block_size as u64 + in_block as u64;
                     read_bytes(&self.cache, block_offset, &mut buf[total..total + to_copy])?;
                 }
-                None => {
-                    // Sparse hole: zero-fill instead of treating as EOF.
+                _ => {
+                    // Sparse hole or preallocated extent: zero-fill instead of treating as EOF.
                     buf[total..total + to_copy].fill(0);
                 }
             }
@@ -373,34 +587,11 @@ impl<'a> Ext4Fs<'a> {
         Ok(())
     }
 
-    fn map_extent_tree(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<u64>> {
-        let mut raw = [0u8; INODE_BLOCK_LEN];
-        for (idx, block) in inode.blocks.iter().enumerate() {
-            let offset = idx * 4;
-            raw[offset..offset + 4].copy_from_slice(&block.to_le_bytes());
-        }
-        let header = parse_extent_header(&raw)?;
-        if header.depth == 0 {
-            return map_extent_entries(&raw, header.entries, logical);
-        }
-        let mut next = match find_extent_index(&raw, header.entries, logical)? {
-            Some(block) => block,
-            None => return Ok(None),
-        };
-
-        let block_size = self.fs_block_size() as usize;
-        let mut scratch = [0u8; 4096];
-        loop {
-            self.read_fs_block(next, &mut scratch[..block_size])?;
-            let header = parse_extent_header(&scratch)?;
-            if header.depth == 0 {
-                return map_extent_entries(&scratch, header.entries, logical);
-            }
-            match find_extent_index(&scratch, header.entries, logical)? {
-                Some(block) => next = block,
-                None => return Ok(None),
-            }
-        }
+    fn map_extent_tree(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<BlockMap>> {
+        Ok(self.find_extent(inode, logical)?.map(|entry| BlockMap {
+            phys: entry.start + (logical - entry.block) as u64,
+            unwritten: entry.unwritten,
+        }))
     }
 
     fn map_indirect_block(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<u64>> {
@@ -461,7 +652,8 @@ impl<'a> Ext4Fs<'a> {
             return self.allocate_extent_block(inode, block_index);
         }
         if block_index < EXT4_DIRECT_BLOCKS as u32 {
-            let new_block = self.allocate_block()?;
+            let goal = self.allocation_goal(inode, 0, block_index)?;
+            let new_block = self.allocate_block_near(goal)?;
             inode.blocks[block_index as usize] = new_block;
             self.zero_fs_block(new_block)?;
             return Ok(new_block as u64);
@@ -500,522 +692,692 @@ impl<'a> Ext4Fs<'a> {
     }
 
     fn allocate_extent_block(&self, inode: &mut Ext4Inode, block_index: u32) -> VfsResult<u64> {
-        let mut raw = inode_extent_raw(inode);
-        let mut header = match parse_extent_header(&raw) {
-            Ok(header) => header,
-            Err(VfsError::NotSupported) => {
-                if raw.iter().all(|&b| b == 0) {
-                    init_extent_raw(&mut raw);
-                    ExtentHeader { entries: 0, depth: 0 }
-                } else {
-                    return Err(VfsError::NotSupported);
+        let new_block = self.allocate_block()?;
+        self.zero_fs_block(new_block)?;
+        self.insert_extent(
+            inode,
+            ExtentEntry {
+                block: block_index,
+                len: 1,
+                start: new_block as u64,
+                unwritten: false,
+            },
+        )?;
+        Ok(new_block as u64)
+    }
+
+    fn load_extent_node(
+        &self,
+        inode: &Ext4Inode,
+        node: ExtentNode,
+        buf: &mut [u8],
+    ) -> VfsResult<(ExtentHeader, usize)> {
+        match node {
+            ExtentNode::Root => {
+                let mut raw = inode_extent_raw(inode);
+                let header = match parse_extent_header(&raw) {
+                    Ok(header) => header,
+                    Err(VfsError::NotSupported) if raw.iter().all(|&b| b == 0) => {
+                        init_extent_raw(&mut raw);
+                        ExtentHeader { entries: 0, depth: 0 }
+                    }
+                    Err(err) => return Err(err),
+                };
+                buf[..INODE_BLOCK_LEN].copy_from_slice(&raw);
+                if header.entries as usize > EXTENT_INODE_CAPACITY {
+                    return Err(VfsError::Invalid);
                 }
+                Ok((header, EXTENT_INODE_CAPACITY))
+            }
+            ExtentNode::Block(block) => {
+                let block_size = self.fs_block_size() as usize;
+                self.read_fs_block(block, &mut buf[..block_size])?;
+                let header = parse_extent_header(buf)?;
+                let capacity = extent_capacity(block_size);
+                if header.entries as usize > capacity {
+                    return Err(VfsError::Invalid);
+                }
+                Ok((header, capacity))
             }
-            Err(err) => return Err(err),
-        };
-        match header.depth {
-            0 => self.allocate_extent_block_in_inode(inode, &mut raw, &mut header, block_index),
-            1 => self.allocate_extent_block_in_tree(inode, &mut raw, &mut header, block_index, None),
-            _ => Err(VfsError::NotSupported),
         }
     }
 
-    fn allocate_extent_block_in_inode(
-        &self,
-        inode: &mut Ext4Inode,
-        raw: &mut [u8; INODE_BLOCK_LEN],
-        header: &mut ExtentHeader,
-        block_index: u32,
-    ) -> VfsResult<u64> {
-        if header.entries as usize > EXTENT_INODE_CAPACITY {
-            return Err(VfsError::Invalid);
-        }
-        let mut entries = [ExtentEntry::default(); EXTENT_INODE_CAPACITY];
-        let count = header.entries as usize;
-        for idx in 0..count {
-            entries[idx] = read_extent_entry(raw, idx);
+    fn extent_node_capacity(&self, node: ExtentNode) -> usize {
+        match node {
+            ExtentNode::Root => EXTENT_INODE_CAPACITY,
+            ExtentNode::Block(_) => extent_capacity(self.fs_block_size() as usize),
         }
+    }
 
-        for entry in entries.iter().take(count) {
-            if entry.covers(block_index) {
-                let phys = entry.start + (block_index - entry.block) as u64;
-                return Ok(phys);
+    fn store_extent_node(&self, inode: &mut Ext4Inode, node: ExtentNode, buf: &[u8]) -> VfsResult<()> {
+        match node {
+            ExtentNode::Root => {
+                let mut raw = [0u8; INODE_BLOCK_LEN];
+                raw.copy_from_slice(&buf[..INODE_BLOCK_LEN]);
+                store_inode_extents(inode, &raw);
+                Ok(())
             }
+            ExtentNode::Block(block) => self.write_fs_block(block, buf),
         }
+    }
 
-        let mut insert_pos = count;
-        for idx in 0..count {
-            if block_index < entries[idx].block {
-                insert_pos = idx;
-                break;
+    /// Descend from the root to the leaf that should hold `logical`, remembering
+    /// the index slot taken at each level and the first key of the next subtree.
+    fn find_extent_leaf(&self, inode: &Ext4Inode, logical: u64, buf: &mut [u8]) -> VfsResult<ExtentCursor> {
+        let mut cursor = ExtentCursor {
+            path: [(ExtentNode::Root, 0); EXTENT_MAX_DEPTH],
+            levels: 0,
+            leaf: ExtentNode::Root,
+            next_key: None,
+        };
+        let (mut header, _) = self.load_extent_node(inode, ExtentNode::Root, buf)?;
+        while header.depth > 0 {
+            if cursor.levels >= EXTENT_MAX_DEPTH || header.entries == 0 {
+                return Err(VfsError::Invalid);
+            }
+            let idx = extent_slot_for(buf, header.entries as usize, logical);
+            if idx + 1 < header.entries as usize {
+                cursor.next_key = Some(extent_key(buf, idx + 1) as u64);
             }
+            cursor.path[cursor.levels] = (cursor.leaf, idx);
+            cursor.levels += 1;
+            cursor.leaf = ExtentNode::Block(read_extent_index(buf, idx).leaf);
+            header = self.load_extent_node(inode, cursor.leaf, buf)?.0;
         }
+        Ok(cursor)
+    }
 
-        let new_block = self.allocate_block()?;
-        self.zero_fs_block(new_block)?;
-        let new_start = new_block as u64;
-
-        if insert_pos > 0 {
-            let prev = entries[insert_pos - 1];
-            if prev.can_extend(block_index, new_start) {
-                let mut updated = prev;
-                updated.len += 1;
-                entries[insert_pos - 1] = updated;
-                write_extent_header(raw, header.entries, header.depth, EXTENT_INODE_CAPACITY as u16);
-                for idx in 0..count {
-                    write_extent_entry(raw, idx, entries[idx]);
+    fn find_extent(&self, inode: &Ext4Inode, logical: u32) -> VfsResult<Option<ExtentEntry>> {
+        let mut buf = [0u8; EXT4_SCRATCH_SIZE];
+        self.find_extent_leaf(inode, logical as u64, &mut buf)?;
+        let entries = parse_extent_header(&buf)?.entries as usize;
+        Ok((0..entries)
+            .map(|idx| read_extent_entry(&buf, idx))
+            .find(|entry| entry.covers(logical)))
+    }
+
+    /// Visit extents that end after `from` in logical order until `visit` returns true.
+    fn for_each_extent(
+        &self,
+        inode: &Ext4Inode,
+        from: u64,
+        mut visit: impl FnMut(ExtentEntry) -> VfsResult<bool>,
+    ) -> VfsResult<()> {
+        let mut buf = [0u8; EXT4_SCRATCH_SIZE];
+        let mut logical = from;
+        loop {
+            let cursor = self.find_extent_leaf(inode, logical, &mut buf)?;
+            let entries = parse_extent_header(&buf)?.entries as usize;
+            for idx in 0..entries {
+                let entry = read_extent_entry(&buf, idx);
+                if entry.end() > logical && visit(entry)? {
+                    return Ok(());
                 }
-                store_inode_extents(inode, raw);
-                return Ok(new_start);
+            }
+            match cursor.next_key {
+                Some(next) if next > logical => logical = next,
+                _ => return Ok(()),
             }
         }
+    }
 
-        if count < EXTENT_INODE_CAPACITY {
-            for idx in (insert_pos..count).rev() {
-                entries[idx + 1] = entries[idx];
+    /// Insert a new extent over a hole, merging with neighbours and splitting
+    /// full nodes on the way down so the parent always has room.
+    fn insert_extent(&self, inode: &mut Ext4Inode, new: ExtentEntry) -> VfsResult<()> {
+        let block_size = self.fs_block_size() as usize;
+        let mut node_buf = [0u8; EXT4_SCRATCH_SIZE];
+        let mut child_buf = [0u8; EXT4_SCRATCH_SIZE];
+        let logical = new.block as u64;
+        'restart: loop {
+            let mut node = ExtentNode::Root;
+            let (mut header, mut capacity) = self.load_extent_node(inode, node, &mut node_buf)?;
+            if header.entries as usize >= capacity {
+                if header.depth == 0 && leaf_merge(&mut node_buf, &header, new) {
+                    return self.store_extent_node(inode, node, &node_buf);
+                }
+                self.grow_extent_root(inode, &mut node_buf, &header, &mut child_buf)?;
+                continue 'restart;
             }
-            entries[insert_pos] = ExtentEntry {
-                block: block_index,
-                len: 1,
-                start: new_start,
-            };
-            header.entries = (count + 1) as u16;
-            write_extent_header(raw, header.entries, header.depth, EXTENT_INODE_CAPACITY as u16);
-            for idx in 0..(count + 1) {
-                write_extent_entry(raw, idx, entries[idx]);
+            while header.depth > 0 {
+                let count = header.entries as usize;
+                if count == 0 {
+                    return Err(VfsError::Invalid);
+                }
+                let idx = extent_slot_for(&node_buf, count, logical);
+                let mut index = read_extent_index(&node_buf, idx);
+                if logical < index.block as u64 {
+                    // 插入点早于首个键时下调键值，保证查找能到达该叶子。
+                    index.block = new.block;
+                    write_extent_index(&mut node_buf, idx, index);
+                    self.store_extent_node(inode, node, &node_buf)?;
+                }
+                let child = ExtentNode::Block(index.leaf);
+                let (child_header, child_capacity) = self.load_extent_node(inode, child, &mut child_buf)?;
+                if child_header.entries as usize >= child_capacity {
+                    if child_header.depth == 0 && leaf_merge(&mut child_buf, &child_header, new) {
+                        return self.store_extent_node(inode, child, &child_buf);
+                    }
+                    let appending = logical > extent_key(&child_buf, child_header.entries as usize - 1) as u64;
+                    self.split_extent_node(
+                        inode,
+                        node,
+                        &mut node_buf,
+                        &header,
+                        capacity,
+                        idx,
+                        &mut child_buf,
+                        &child_header,
+                        appending,
+                    )?;
+                    continue 'restart;
+                }
+                node_buf[..block_size].copy_from_slice(&child_buf[..block_size]);
+                node = child;
+                header = child_header;
+                capacity = child_capacity;
+            }
+            if !leaf_merge(&mut node_buf, &header, new) {
+                leaf_insert(&mut node_buf, &mut header, capacity, new)?;
             }
-            store_inode_extents(inode, raw);
-            return Ok(new_start);
+            return self.store_extent_node(inode, node, &node_buf);
         }
-
-        self.upgrade_inode_extents(inode, raw, entries, count, block_index, new_start)
     }
 
-    fn allocate_extent_block_in_tree(
+    fn grow_extent_root(
         &self,
         inode: &mut Ext4Inode,
-        raw: &mut [u8; INODE_BLOCK_LEN],
-        header: &mut ExtentHeader,
-        block_index: u32,
-        prealloc: Option<u64>,
-    ) -> VfsResult<u64> {
-        if header.depth == 2 {
-            return self.allocate_extent_block_in_depth2(inode, raw, header, block_index, prealloc);
-        }
-        if header.depth != 1 {
-            return Err(VfsError::NotSupported);
-        }
-        if header.entries as usize > EXTENT_INODE_CAPACITY {
-            return Err(VfsError::Invalid);
-        }
-        let mut indices = [ExtentIndex::default(); EXTENT_INODE_CAPACITY];
-        let index_count = header.entries as usize;
-        for idx in 0..index_count {
-            indices[idx] = read_extent_index(raw, idx);
-        }
-        if index_count == 0 {
-            return Err(VfsError::Invalid);
-        }
-        let mut chosen = 0usize;
-        for idx in 1..index_count {
-            if block_index >= indices[idx].block {
-                chosen = idx;
-            } else {
-                break;
-            }
+        root: &mut [u8],
+        header: &ExtentHeader,
+        buf: &mut [u8],
+    ) -> VfsResult<()> {
+        if header.depth as usize + 1 >= EXTENT_MAX_DEPTH {
+            return Err(VfsError::NoSpace);
         }
-        let leaf_block = indices[chosen].leaf;
         let block_size = self.fs_block_size() as usize;
-        let leaf_capacity = extent_capacity(block_size);
-        let mut scratch = [0u8; EXT4_SCRATCH_SIZE];
-        self.read_fs_block(leaf_block, &mut scratch[..block_size])?;
-        let mut leaf_header = parse_extent_header(&scratch)?;
-        if leaf_header.depth != 0 {
+        let count = header.entries as usize;
+        let child = self.allocate_block()? as u64;
+        buf[..block_size].fill(0);
+        write_extent_header(buf, count as u16, header.depth, extent_capacity(block_size) as u16);
+        let end = extent_entry_offset(count);
+        buf[EXTENT_HEADER_SIZE..end].copy_from_slice(&root[EXTENT_HEADER_SIZE..end]);
+        self.write_fs_block(child, &buf[..block_size])?;
+        let first = if count > 0 { extent_key(root, 0) } else { 0 };
+        root[..INODE_BLOCK_LEN].fill(0);
+        write_extent_header(root, 1, header.depth + 1, EXTENT_INODE_CAPACITY as u16);
+        write_extent_index(root, 0, ExtentIndex { block: first, leaf: child });
+        self.store_extent_node(inode, ExtentNode::Root, root)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn split_extent_node(
+        &self,
+        inode: &mut Ext4Inode,
+        parent: ExtentNode,
+        parent_buf: &mut [u8],
+        parent_header: &ExtentHeader,
+        parent_capacity: usize,
+        idx: usize,
+        child_buf: &mut [u8],
+        child_header: &ExtentHeader,
+        appending: bool,
+    ) -> VfsResult<()> {
+        let block_size = self.fs_block_size() as usize;
+        let capacity = extent_capacity(block_size) as u16;
+        let count = child_header.entries as usize;
+        let parent_count = parent_header.entries as usize;
+        if parent_count >= parent_capacity || count < 2 {
             return Err(VfsError::Invalid);
         }
-        let mut leaf_entries = leaf_header.entries as usize;
-        if leaf_entries > leaf_capacity {
-            return Err(VfsError::Invalid);
+        let old_block = read_extent_index(parent_buf, idx).leaf;
+        // 追加写只移出最后一项，避免顺序增长时每个节点只用一半。
+        let split = if appending { count - 1 } else { count / 2 };
+        let new_block = self.allocate_block()? as u64;
+        write_extent_header(child_buf, split as u16, child_header.depth, capacity);
+        self.write_fs_block(old_block, &child_buf[..block_size])?;
+        let new_key = extent_key(child_buf, split);
+        child_buf.copy_within(extent_entry_offset(split)..extent_entry_offset(count), EXTENT_HEADER_SIZE);
+        write_extent_header(child_buf, (count - split) as u16, child_header.depth, capacity);
+        self.write_fs_block(new_block, &child_buf[..block_size])?;
+        parent_buf.copy_within(
+            extent_entry_offset(idx + 1)..extent_entry_offset(parent_count),
+            extent_entry_offset(idx + 2),
+        );
+        write_extent_index(parent_buf, idx + 1, ExtentIndex { block: new_key, leaf: new_block });
+        write_extent_header(parent_buf, (parent_count + 1) as u16, parent_header.depth, parent_capacity as u16);
+        self.store_extent_node(inode, parent, parent_buf)
+    }
+
+    /// Unmap logical blocks `[start, end)`, optionally releasing the data blocks.
+    fn remove_extent_range(&self, inode: &mut Ext4Inode, start: u64, end: u64, free: bool) -> VfsResult<()> {
+        if let Some(tail) = self.unmap_extent_range(inode, start, end, free)? {
+            self.insert_extent(inode, tail)?;
         }
+        Ok(())
+    }
 
-        for idx in 0..leaf_entries {
-            let entry = read_extent_entry(&scratch, idx);
-            if entry.covers(block_index) {
-                let phys = entry.start + (block_index - entry.block) as u64;
-                return Ok(phys);
+    /// Trim extents overlapping `[start, end)` leaf by leaf. Returns the tail of a
+    /// split extent that did not fit back into its full leaf.
+    #[inline(never)]
+    fn unmap_extent_range(
+        &self,
+        inode: &mut Ext4Inode,
+        start: u64,
+        end: u64,
+        free: bool,
+    ) -> VfsResult<Option<ExtentEntry>> {
+        let mut buf = [0u8; EXT4_SCRATCH_SIZE];
+        let mut logical = start;
+        let mut pending = None;
+        while logical < end {
+            let cursor = self.find_extent_leaf(inode, logical, &mut buf)?;
+            let header = parse_extent_header(&buf)?;
+            let capacity = self.extent_node_capacity(cursor.leaf);
+            let mut count = header.entries as usize;
+            let mut kept = 0usize;
+            let mut idx = 0usize;
+            let mut changed = false;
+            while idx < count {
+                let entry = read_extent_entry(&buf, idx);
+                idx += 1;
+                let (first, last) = (entry.block as u64, entry.end());
+                if last <= start || first >= end {
+                    write_extent_entry(&mut buf, kept, entry);
+                    kept += 1;
+                    continue;
+                }
+                changed = true;
+                let cut_start = first.max(start);
+                let cut_end = last.min(end);
+                if free {
+                    self.free_blocks(entry.start + (cut_start - first), cut_end - cut_start)?;
+                }
+                if first < cut_start {
+                    let mut head = entry;
+                    head.len = (cut_start - first) as u16;
+                    write_extent_entry(&mut buf, kept, head);
+                    kept += 1;
+                }
+                if cut_end < last {
+                    let tail = ExtentEntry {
+                        block: cut_end as u32,
+                        len: (last - cut_end) as u16,
+                        start: entry.start + (cut_end - first),
+                        unwritten: entry.unwritten,
+                    };
+                    if kept < idx {
+                        write_extent_entry(&mut buf, kept, tail);
+                        kept += 1;
+                    } else if count < capacity {
+                        // 区间落在单个 extent 内部：原地拆成首尾两段。
+                        buf.copy_within(
+                            extent_entry_offset(idx)..extent_entry_offset(count),
+                            extent_entry_offset(idx + 1),
+                        );
+                        write_extent_entry(&mut buf, kept, tail);
+                        kept += 1;
+                        idx += 1;
+                        count += 1;
+                    } else {
+                        pending = Some(tail);
+                    }
+                }
+            }
+            if changed {
+                write_extent_header(&mut buf, kept as u16, header.depth, capacity as u16);
+                self.store_extent_node(inode, cursor.leaf, &buf)?;
+                if kept == 0 {
+                    self.release_extent_leaf(inode, &cursor, &mut buf)?;
+                }
+            }
+            match cursor.next_key {
+                Some(next) if next > logical && next < end => logical = next,
+                _ => break,
             }
         }
+        Ok(pending)
+    }
 
-        let mut insert_pos = leaf_entries;
-        for idx in 0..leaf_entries {
-            let entry = read_extent_entry(&scratch, idx);
-            if block_index < entry.block {
-                insert_pos = idx;
+    /// Drop an empty node and any ancestors that become empty as a result.
+    fn release_extent_leaf(&self, inode: &mut Ext4Inode, cursor: &ExtentCursor, buf: &mut [u8]) -> VfsResult<()> {
+        let mut child = cursor.leaf;
+        for level in (0..cursor.levels).rev() {
+            let ExtentNode::Block(block) = child else {
+                break;
+            };
+            self.free_blocks(block, 1)?;
+            let (parent, idx) = cursor.path[level];
+            let (mut header, capacity) = self.load_extent_node(inode, parent, buf)?;
+            let count = header.entries as usize;
+            buf.copy_within(extent_entry_offset(idx + 1)..extent_entry_offset(count), extent_entry_offset(idx));
+            header.entries -= 1;
+            if header.entries == 0 && parent == ExtentNode::Root {
+                header.depth = 0;
+            }
+            write_extent_header(buf, header.entries, header.depth, capacity as u16);
+            self.store_extent_node(inode, parent, buf)?;
+            if header.entries > 0 {
                 break;
             }
+            child = parent;
         }
+        Ok(())
+    }
 
-        let new_start = match prealloc {
-            Some(addr) => addr,
-            None => {
-                let new_block = self.allocate_block()?;
-                self.zero_fs_block(new_block)?;
-                new_block as u64
+    /// Reserve blocks for every hole in `[start, end)` as unwritten extents.
+    fn allocate_unwritten(&self, ino: InodeId, inode: &mut Ext4Inode, start: u64, end: u64) -> VfsResult<()> {
+        let mut logical = start;
+        while logical < end {
+            let mut hole_end = end;
+            let mut mapped_end = logical;
+            self.for_each_extent(inode, logical, |entry| {
+                if entry.block as u64 <= logical {
+                    mapped_end = entry.end();
+                    return Ok(true);
+                }
+                hole_end = hole_end.min(entry.block as u64);
+                Ok(true)
+            })?;
+            if mapped_end > logical {
+                logical = mapped_end;
+                continue;
             }
-        };
+            while logical < hole_end {
+                let goal = self.allocation_goal(inode, ino, logical as u32)?;
+                let want = core::cmp::min(hole_end - logical, EXT_UNWRITTEN_MAX_LEN as u64) as u32;
+                let (phys, got) = self.allocate_blocks(goal, want)?;
+                self.insert_extent(
+                    inode,
+                    ExtentEntry {
+                        block: logical as u32,
+                        len: got as u16,
+                        start: phys,
+                        unwritten: true,
+                    },
+                )?;
+                logical += got as u64;
+            }
+        }
+        Ok(())
+    }
 
-        if insert_pos > 0 {
-            let prev = read_extent_entry(&scratch, insert_pos - 1);
-            if prev.can_extend(block_index, new_start) {
-                let mut updated = prev;
-                updated.len += 1;
-                write_extent_entry(&mut scratch, insert_pos - 1, updated);
-                write_extent_header(&mut scratch, leaf_header.entries, leaf_header.depth, leaf_capacity as u16);
-                self.write_fs_block(leaf_block, &scratch[..block_size])?;
-                return Ok(new_start);
-            }
-        }
-
-        if leaf_entries < leaf_capacity {
-            let start = extent_entry_offset(insert_pos);
-            let end = extent_entry_offset(leaf_entries);
-            let dst = extent_entry_offset(insert_pos + 1);
-            scratch.copy_within(start..end, dst);
-            write_extent_entry(
-                &mut scratch,
-                insert_pos,
-                ExtentEntry {
-                    block: block_index,
-                    len: 1,
-                    start: new_start,
-                },
-            );
-            leaf_entries += 1;
-            leaf_header.entries = leaf_entries as u16;
-            write_extent_header(&mut scratch, leaf_header.entries, leaf_header.depth, leaf_capacity as u16);
-            self.write_fs_block(leaf_block, &scratch[..block_size])?;
-            return Ok(new_start);
-        }
-
-        let new_leaf = self.allocate_block()?;
-        self.zero_fs_block(new_leaf)?;
-        let mut leaf_raw = [0u8; EXT4_SCRATCH_SIZE];
-        write_extent_header(&mut leaf_raw, 1, 0, leaf_capacity as u16);
-        write_extent_entry(
-            &mut leaf_raw,
-            0,
+    /// Allocate up to `count` blocks near the goal for a hole at `logical` and
+    /// map them as one initialized extent.
+    fn map_new_run(&self, ino: InodeId, inode: &mut Ext4Inode, logical: u32, count: u32) -> VfsResult<(u64, u32)> {
+        let goal = self.allocation_goal(inode, ino, logical)?;
+        let want = core::cmp::min(count, EXT_INIT_MAX_LEN);
+        let (phys, got) = self.allocate_blocks(goal, want)?;
+        self.insert_extent(
+            inode,
             ExtentEntry {
-                block: block_index,
-                len: 1,
-                start: new_start,
+                block: logical,
+                len: got as u16,
+                start: phys,
+                unwritten: false,
             },
-        );
-        self.write_fs_block(new_leaf as u64, &leaf_raw[..block_size])?;
+        )?;
+        Ok((phys, got))
+    }
 
-        if index_count >= EXTENT_INODE_CAPACITY {
-            self.upgrade_extent_root_to_depth2(inode, raw, header)?;
-            return self.allocate_extent_block_in_tree(inode, raw, header, block_index, Some(new_start));
+    /// Write whole blocks into a hole, allocating contiguous runs as it goes.
+    fn write_new_run(&self, ino: InodeId, inode: &mut Ext4Inode, logical: u32, data: &[u8]) -> VfsResult<usize> {
+        let block_size = self.fs_block_size() as usize;
+        let blocks = (data.len() / block_size) as u32;
+        let mut done = 0u32;
+        while done < blocks {
+            let (phys, got) = self.map_new_run(ino, inode, logical + done, blocks - done)?;
+            let from = done as usize * block_size;
+            let to = (done + got) as usize * block_size;
+            write_bytes(&self.cache, phys * block_size as u64, &data[from..to])?;
+            done += got;
         }
-        let new_index = ExtentIndex {
-            block: block_index,
-            leaf: new_leaf as u64,
-        };
-        let mut insert_idx = index_count;
-        for idx in 0..index_count {
-            if new_index.block < indices[idx].block {
-                insert_idx = idx;
-                break;
+        Ok(blocks as usize * block_size)
+    }
+
+    /// Number of blocks from `logical` (at most `max`) that are neither mapped nor staged.
+    fn hole_run(&self, ino: InodeId, inode: &Ext4Inode, logical: u32, max: u32) -> VfsResult<u32> {
+        let mut limit = logical as u64 + max as u64;
+        self.for_each_extent(inode, logical as u64, |entry| {
+            limit = limit.min(entry.block as u64);
+            Ok(true)
+        })?;
+        let owner = self.owner_id();
+        let guard = EXT4_DELALLOC.lock();
+        for slot in guard.get_mut().iter() {
+            if slot.used && slot.owner == owner && slot.inode == ino && slot.logical >= logical {
+                limit = limit.min(slot.logical as u64);
             }
         }
-        let start = extent_entry_offset(insert_idx);
-        let end = extent_entry_offset(index_count);
-        let dst = extent_entry_offset(insert_idx + 1);
-        raw.copy_within(start..end, dst);
-        write_extent_index(raw, insert_idx, new_index);
-        header.entries = (index_count + 1) as u16;
-        write_extent_header(raw, header.entries, header.depth, EXTENT_INODE_CAPACITY as u16);
-        store_inode_extents(inode, raw);
-        Ok(new_start)
+        Ok(limit.saturating_sub(logical as u64) as u32)
     }
 
-    fn allocate_extent_block_in_depth2(
+    fn owner_id(&self) -> usize {
+        self as *const Self as usize
+    }
+
+    /// Buffer a write to an unallocated block. Returns false when the staging
+    /// table is held by other filesystems and the caller must allocate now.
+    fn stage_block(
         &self,
+        ino: InodeId,
         inode: &mut Ext4Inode,
-        raw: &mut [u8; INODE_BLOCK_LEN],
-        header: &mut ExtentHeader,
-        block_index: u32,
-        prealloc: Option<u64>,
-    ) -> VfsResult<u64> {
-        if header.entries as usize > EXTENT_INODE_CAPACITY {
-            return Err(VfsError::Invalid);
-        }
-        let mut root_indices = [ExtentIndex::default(); EXTENT_INODE_CAPACITY];
-        let root_count = header.entries as usize;
-        for idx in 0..root_count {
-            root_indices[idx] = read_extent_index(raw, idx);
+        logical: u32,
+        in_block: usize,
+        data: &[u8],
+    ) -> VfsResult<bool> {
+        let owner = self.owner_id();
+        let guard = EXT4_DELALLOC.lock();
+        let slots = guard.get_mut();
+        if let Some(slot) = slots.iter_mut().find(|slot| slot.is(owner, ino, logical)) {
+            slot.data[in_block..in_block + data.len()].copy_from_slice(data);
+            return Ok(true);
         }
-        if root_count == 0 {
-            return Err(VfsError::Invalid);
-        }
-        let mut root_pos = 0usize;
-        for idx in 1..root_count {
-            if block_index >= root_indices[idx].block {
-                root_pos = idx;
-            } else {
-                break;
-            }
+        if slots.iter().all(|slot| slot.used) {
+            self.writeback_slots(slots, None, Some((ino, inode)))?;
         }
-        let index_block = root_indices[root_pos].leaf;
+        let Some(slot) = slots.iter_mut().find(|slot| !slot.used) else {
+            return Ok(false);
+        };
         let block_size = self.fs_block_size() as usize;
-        let index_capacity = extent_capacity(block_size);
-        let mut index_buf = [0u8; EXT4_SCRATCH_SIZE];
-        self.read_fs_block(index_block, &mut index_buf[..block_size])?;
-        let mut index_header = parse_extent_header(&index_buf)?;
-        if index_header.depth != 1 {
-            return Err(VfsError::Invalid);
-        }
-        let mut index_count = index_header.entries as usize;
-        if index_count == 0 || index_count > index_capacity {
-            return Err(VfsError::Invalid);
-        }
-        let mut leaf_index = read_extent_index(&index_buf, 0);
-        for idx in 1..index_count {
-            let entry = read_extent_index(&index_buf, idx);
-            if block_index >= entry.block {
-                leaf_index = entry;
-            } else {
-                break;
+        slot.owner = owner;
+        slot.inode = ino;
+        slot.logical = logical;
+        slot.used = true;
+        slot.data[..block_size].fill(0);
+        slot.data[in_block..in_block + data.len()].copy_from_slice(data);
+        Ok(true)
+    }
+
+    /// Copy staged blocks of `ino` over a read of `[offset, offset + buf.len())`.
+    fn read_staged(&self, ino: InodeId, offset: u64, buf: &mut [u8]) {
+        let owner = self.owner_id();
+        let block_size = self.fs_block_size() as u64;
+        let end = offset + buf.len() as u64;
+        let guard = EXT4_DELALLOC.lock();
+        for slot in guard.get_mut().iter() {
+            if !slot.used || slot.owner != owner || slot.inode != ino {
+                continue;
             }
-        }
-        let leaf_block = leaf_index.leaf;
-        let leaf_capacity = extent_capacity(block_size);
-        let mut leaf_buf = [0u8; EXT4_SCRATCH_SIZE];
-        self.read_fs_block(leaf_block, &mut leaf_buf[..block_size])?;
-        let mut leaf_header = parse_extent_header(&leaf_buf)?;
-        if leaf_header.depth != 0 {
-            return Err(VfsError::Invalid);
-        }
-        let mut leaf_entries = leaf_header.entries as usize;
-        if leaf_entries > leaf_capacity {
-            return Err(VfsError::Invalid);
-        }
-        for idx in 0..leaf_entries {
-            let entry = read_extent_entry(&leaf_buf, idx);
-            if entry.covers(block_index) {
-                let phys = entry.start + (block_index - entry.block) as u64;
-                return Ok(phys);
+            let block_start = slot.logical as u64 * block_size;
+            let from = block_start.max(offset);
+            let to = (block_start + block_size).min(end);
+            if from < to {
+                let src = (from - block_start) as usize..(to - block_start) as usize;
+                buf[(from - offset) as usize..(to - offset) as usize].copy_from_slice(&slot.data[src]);
             }
         }
+    }
 
-        let mut insert_pos = leaf_entries;
-        for idx in 0..leaf_entries {
-            let entry = read_extent_entry(&leaf_buf, idx);
-            if block_index < entry.block {
-                insert_pos = idx;
-                break;
-            }
-        }
+    /// Allocate and write out staged blocks, optionally only those of `only`.
+    fn writeback(&self, only: Option<InodeId>) -> VfsResult<()> {
+        let guard = EXT4_DELALLOC.lock();
+        self.writeback_slots(guard.get_mut(), only, None)
+    }
 
-        let new_start = match prealloc {
-            Some(addr) => addr,
-            None => {
-                let new_block = self.allocate_block()?;
-                self.zero_fs_block(new_block)?;
-                new_block as u64
+    /// Write back staged blocks one logically contiguous run at a time. When the
+    /// caller holds an inode in memory it is passed as `current` and updated in
+    /// place instead of being reloaded from disk.
+    fn writeback_slots(
+        &self,
+        slots: &mut [DelallocSlot],
+        only: Option<InodeId>,
+        mut current: Option<(InodeId, &mut Ext4Inode)>,
+    ) -> VfsResult<()> {
+        let owner = self.owner_id();
+        loop {
+            let Some((ino, start)) = slots
+                .iter()
+                .filter(|slot| slot.used && slot.owner == owner && only.map_or(true, |ino| slot.inode == ino))
+                .map(|slot| (slot.inode, slot.logical))
+                .min()
+            else {
+                return Ok(());
+            };
+            let mut len = 1u32;
+            while slots.iter().any(|slot| slot.is(owner, ino, start + len)) {
+                len += 1;
             }
-        };
+            let is_current = matches!(current, Some((cur, _)) if cur == ino);
+            let mut loaded = if is_current { None } else { Some(self.read_inode(ino)?) };
+            let inode = match loaded.as_mut() {
+                Some(inode) => inode,
+                None => current.as_mut().map(|(_, inode)| &mut **inode).ok_or(VfsError::Invalid)?,
+            };
+            let mut done = 0u32;
+            while done < len {
+                let (phys, got) = self.map_new_run(ino, inode, start + done, len - done)?;
+                for idx in 0..got {
+                    let logical = start + done + idx;
+                    let slot = slots
+                        .iter_mut()
+                        .find(|slot| slot.is(owner, ino, logical))
+                        .ok_or(VfsError::Invalid)?;
+                    self.write_fs_block(phys + idx as u64, &slot.data)?;
+                    slot.used = false;
+                }
+                done += got;
+            }
+            if let Some(inode) = loaded {
+                self.write_inode(ino, &inode)?;
+            }
+        }
+    }
 
-        if insert_pos > 0 {
-            let prev = read_extent_entry(&leaf_buf, insert_pos - 1);
-            if prev.can_extend(block_index, new_start) {
-                let mut updated = prev;
-                updated.len += 1;
-                write_extent_entry(&mut leaf_buf, insert_pos - 1, updated);
-                write_extent_header(&mut leaf_buf, leaf_header.entries, leaf_header.depth, leaf_capacity as u16);
-                self.write_fs_block(leaf_block, &leaf_buf[..block_size])?;
-                return Ok(new_start);
-            }
-        }
-
-        if leaf_entries < leaf_capacity {
-            let start = extent_entry_offset(insert_pos);
-            let end = extent_entry_offset(leaf_entries);
-            let dst = extent_entry_offset(insert_pos + 1);
-            leaf_buf.copy_within(start..end, dst);
-            write_extent_entry(
-                &mut leaf_buf,
-                insert_pos,
-                ExtentEntry {
-                    block: block_index,
-                    len: 1,
-                    start: new_start,
-                },
-            );
-            leaf_entries += 1;
-            leaf_header.entries = leaf_entries as u16;
-            write_extent_header(&mut leaf_buf, leaf_header.entries, leaf_header.depth, leaf_capacity as u16);
-            self.write_fs_block(leaf_block, &leaf_buf[..block_size])?;
-            return Ok(new_start);
-        }
-
-        let new_leaf = self.allocate_block()?;
-        self.zero_fs_block(new_leaf)?;
-        let mut new_leaf_buf = [0u8; EXT4_SCRATCH_SIZE];
-        write_extent_header(&mut new_leaf_buf, 1, 0, leaf_capacity as u16);
-        write_extent_entry(
-            &mut new_leaf_buf,
-            0,
+    /// Turn one unwritten block into an initialized mapping before data lands in it.
+    fn convert_unwritten(&self, inode: &mut Ext4Inode, logical: u32, phys: u64) -> VfsResult<()> {
+        self.remove_extent_range(inode, logical as u64, logical as u64 + 1, false)?;
+        self.insert_extent(
+            inode,
             ExtentEntry {
-                block: block_index,
+                block: logical,
                 len: 1,
-                start: new_start,
+                start: phys,
+                unwritten: false,
             },
-        );
-        self.write_fs_block(new_leaf as u64, &new_leaf_buf[..block_size])?;
+        )
+    }
 
-        if index_count < index_capacity {
-            let new_index = ExtentIndex {
-                block: block_index,
-                leaf: new_leaf as u64,
-            };
-            let mut insert_idx = index_count;
-            for idx in 0..index_count {
-                let entry = read_extent_index(&index_buf, idx);
-                if new_index.block < entry.block {
-                    insert_idx = idx;
-                    break;
-                }
+    /// Release blocks in `[start, end)` for either mapping scheme.
+    fn punch_blocks(&self, inode: &mut Ext4Inode, start: u64, end: u64) -> VfsResult<()> {
+        if start >= end {
+            return Ok(());
+        }
+        if (inode.flags & EXT4_EXTENTS_FLAG) != 0 {
+            return self.remove_extent_range(inode, start, end, true);
+        }
+        for (idx, block) in inode.blocks.iter_mut().enumerate().take(EXT4_DIRECT_BLOCKS) {
+            let logical = idx as u64;
+            if *block != 0 && logical >= start && logical < end {
+                self.free_blocks(*block as u64, 1)?;
+                *block = 0;
             }
-            let start = extent_entry_offset(insert_idx);
-            let end = extent_entry_offset(index_count);
-            let dst = extent_entry_offset(insert_idx + 1);
-            index_buf.copy_within(start..end, dst);
-            write_extent_index(&mut index_buf, insert_idx, new_index);
-            index_count += 1;
-            index_header.entries = index_count as u16;
-            write_extent_header(&mut index_buf, index_header.entries, index_header.depth, index_capacity as u16);
-            self.write_fs_block(index_block, &index_buf[..block_size])?;
-            return Ok(new_start);
         }
+        let ptrs_per_block = self.fs_block_size() as u64 / 4;
+        let mut base = EXT4_DIRECT_BLOCKS as u64;
+        let mut span = ptrs_per_block;
+        for slot in EXT4_DIRECT_BLOCKS..inode.blocks.len() {
+            let level = (slot - EXT4_DIRECT_BLOCKS + 1) as u32;
+            let root = inode.blocks[slot];
+            if root != 0 && start < base + span && end > base && self.punch_indirect(root, level, base, start, end)? {
+                self.free_blocks(root as u64, 1)?;
+                inode.blocks[slot] = 0;
+            }
+            base += span;
+            span *= ptrs_per_block;
+        }
+        Ok(())
+    }
 
-        let last_entry = read_extent_index(&index_buf, index_count - 1);
-        if block_index <= last_entry.block {
-            return Err(VfsError::NotSupported);
+    /// Clear pointers under an indirect block; returns true when the block itself
+    /// is fully covered and can be released.
+    fn punch_indirect(&self, block: u32, level: u32, base: u64, start: u64, end: u64) -> VfsResult<bool> {
+        let block_size = self.fs_block_size() as u64;
+        let ptrs_per_block = block_size / 4;
+        let child_span = ptrs_per_block.pow(level - 1);
+        let first = start.saturating_sub(base) / child_span;
+        let last = core::cmp::min(ptrs_per_block, (end - base + child_span - 1) / child_span);
+        for index in first..last {
+            let child_base = base + index * child_span;
+            let ptr = self.read_indirect_ptr(block, index, block_size)?;
+            if ptr == 0 {
+                continue;
+            }
+            let release = level == 1 || self.punch_indirect(ptr, level - 1, child_base, start, end)?;
+            if release {
+                self.free_blocks(ptr as u64, 1)?;
+                write_bytes(&self.cache, block as u64 * block_size + index * 4, &[0u8; 4])?;
+            }
         }
-        let new_index_block = self.allocate_block()?;
-        self.zero_fs_block(new_index_block)?;
-        let mut new_index_buf = [0u8; EXT4_SCRATCH_SIZE];
-        write_extent_header(&mut new_index_buf, 1, 1, index_capacity as u16);
-        write_extent_index(
-            &mut new_index_buf,
-            0,
-            ExtentIndex {
-                block: block_index,
-                leaf: new_leaf as u64,
-            },
-        );
-        self.write_fs_block(new_index_block as u64, &new_index_buf[..block_size])?;
+        Ok(start <= base && end >= base + ptrs_per_block * child_span)
+    }
 
-        if root_count >= EXTENT_INODE_CAPACITY {
-            return Err(VfsError::NotSupported);
+    /// Zero bytes `[from, to)` inside one block if that block holds written data.
+    fn zero_block_range(&self, inode: &Ext4Inode, from: u64, to: u64) -> VfsResult<()> {
+        if from >= to {
+            return Ok(());
         }
-        let new_root = ExtentIndex {
-            block: block_index,
-            leaf: new_index_block as u64,
+        let block_size = self.fs_block_size() as u64;
+        let Some(mapping) = self.map_block(inode, (from / block_size) as u32)? else {
+            return Ok(());
         };
-        let mut insert_root = root_count;
-        for idx in 0..root_count {
-            if new_root.block < root_indices[idx].block {
-                insert_root = idx;
-                break;
-            }
+        if mapping.unwritten {
+            return Ok(());
         }
-        let start = extent_entry_offset(insert_root);
-        let end = extent_entry_offset(root_count);
-        let dst = extent_entry_offset(insert_root + 1);
-        raw.copy_within(start..end, dst);
-        write_extent_index(raw, insert_root, new_root);
-        header.entries = (root_count + 1) as u16;
-        write_extent_header(raw, header.entries, header.depth, EXTENT_INODE_CAPACITY as u16);
-        store_inode_extents(inode, raw);
-        Ok(new_start)
+        let zeros = [0u8; 512];
+        let mut offset = mapping.phys * block_size + from % block_size;
+        let mut remaining = (to - from) as usize;
+        while remaining > 0 {
+            let chunk = core::cmp::min(remaining, zeros.len());
+            write_bytes(&self.cache, offset, &zeros[..chunk])?;
+            offset += chunk as u64;
+            remaining -= chunk;
+        }
+        Ok(())
     }
 
-    fn upgrade_extent_root_to_depth2(
-        &self,
-        inode: &mut Ext4Inode,
-        raw: &mut [u8; INODE_BLOCK_LEN],
-        header: &mut ExtentHeader,
-    ) -> VfsResult<()> {
-        if header.depth != 1 {
-            return Err(VfsError::Invalid);
+    /// Map holes in `[start, end)` to fresh blocks without touching existing data.
+    fn allocate_range(&self, ino: InodeId, inode: &mut Ext4Inode, start: u64, end: u64) -> VfsResult<()> {
+        if (inode.flags & EXT4_EXTENTS_FLAG) != 0 {
+            return self.allocate_unwritten(ino, inode, start, end);
         }
-        let count = header.entries as usize;
-        if count == 0 || count > EXTENT_INODE_CAPACITY {
-            return Err(VfsError::Invalid);
+        for block_index in start..end {
+            let block_index = u32::try_from(block_index).map_err(|_| VfsError::Invalid)?;
+            if self.map_block(inode, block_index)?.is_none() {
+                self.allocate_data_block(inode, block_index)?;
+            }
         }
-        let block_size = self.fs_block_size() as usize;
-        let index_capacity = extent_capacity(block_size);
-        let index_block = self.allocate_block()?;
-        self.zero_fs_block(index_block)?;
-        let mut index_buf = [0u8; EXT4_SCRATCH_SIZE];
-        write_extent_header(&mut index_buf, count as u16, 1, index_capacity as u16);
-        for idx in 0..count {
-            let entry = read_extent_index(raw, idx);
-            write_extent_index(&mut index_buf, idx, entry);
-        }
-        self.write_fs_block(index_block as u64, &index_buf[..block_size])?;
-        let first_block = read_extent_index(raw, 0).block;
-        raw.fill(0);
-        write_extent_header(raw, 1, 2, EXTENT_INODE_CAPACITY as u16);
-        write_extent_index(
-            raw,
-            0,
-            ExtentIndex {
-                block: first_block,
-                leaf: index_block as u64,
-            },
-        );
-        store_inode_extents(inode, raw);
-        header.entries = 1;
-        header.depth = 2;
         Ok(())
     }
 
-    fn upgrade_inode_extents(
-        &self,
-        inode: &mut Ext4Inode,
-        raw: &mut [u8; INODE_BLOCK_LEN],
-        entries: [ExtentEntry; EXTENT_INODE_CAPACITY],
-        count: usize,
-        block_index: u32,
-        new_start: u64,
-    ) -> VfsResult<u64> {
-        let block_size = self.fs_block_size() as usize;
-        let leaf_capacity = extent_capacity(block_size);
-        let leaf_block = self.allocate_block()?;
-        self.zero_fs_block(leaf_block)?;
-        let mut scratch = [0u8; EXT4_SCRATCH_SIZE];
-        write_extent_header(&mut scratch, count as u16, 0, leaf_capacity as u16);
-        for idx in 0..count {
-            write_extent_entry(&mut scratch, idx, entries[idx]);
+    /// Zero the partial blocks at both edges of the byte range `[start, end)`.
+    fn zero_partial_edges(&self, inode: &Ext4Inode, start: u64, end: u64) -> VfsResult<()> {
+        let block_size = self.fs_block_size() as u64;
+        let head_end = core::cmp::min(end, (start + block_size - 1) / block_size * block_size);
+        self.zero_block_range(inode, start, head_end)?;
+        let tail_start = end / block_size * block_size;
+        if tail_start >= head_end {
+            self.zero_block_range(inode, tail_start, end)?;
         }
-        self.write_fs_block(leaf_block as u64, &scratch[..block_size])?;
-
-        raw.fill(0);
-        write_extent_header(raw, 1, 1, EXTENT_INODE_CAPACITY as u16);
-        let first_block = entries[0].block;
-        write_extent_index(
-            raw,
-            0,
-            ExtentIndex {
-                block: first_block,
-                leaf: leaf_block as u64,
-            },
-        );
-        store_inode_extents(inode, raw);
-        let mut header = ExtentHeader { entries: 1, depth: 1 };
-        self.allocate_extent_block_in_tree(inode, raw, &mut header, block_index, Some(new_start))
+        Ok(())
     }
 
     fn read_indirect_ptr(&self, block: u32, index: u64, block_size: u64) -> VfsResult<u32> {
@@ -1083,16 +1445,206 @@ impl<'a> Ext4Fs<'a> {
             return Err(VfsError::Invalid);
         }
         let index = self.alloc_from_bitmap(desc.inode_bitmap, total)?;
+        self.adjust_group_count(0, GROUP_DESC_FREE_INODES_OFFSET, -1)?;
+        self.adjust_free_count(SUPERBLOCK_FREE_INODES_OFFSET, -1)?;
         Ok(index as InodeId + 1)
     }
 
     fn allocate_block(&self) -> VfsResult<u32> {
-        let desc = self.read_group_desc(0)?;
-        let total = self.superblock.blocks_per_group;
-        if total == 0 {
+        self.allocate_block_near(self.superblock.first_data_block as u64)
+    }
+
+    fn allocate_block_near(&self, goal: u64) -> VfsResult<u32> {
+        let (block, _) = self.allocate_blocks(goal, 1)?;
+        u32::try_from(block).map_err(|_| VfsError::NoSpace)
+    }
+
+    fn block_groups(&self) -> u32 {
+        let per_group = self.superblock.blocks_per_group as u64;
+        let data_blocks = self.superblock.blocks_count.saturating_sub(self.superblock.first_data_block as u64);
+        if per_group == 0 || data_blocks == 0 {
+            return 1;
+        }
+        ((data_blocks + per_group - 1) / per_group) as u32
+    }
+
+    /// Number of usable bits in a group's block bitmap.
+    fn group_block_count(&self, group: u32) -> u32 {
+        let per_group = self.superblock.blocks_per_group as u64;
+        let first = group as u64 * per_group;
+        let data_blocks = self.superblock.blocks_count.saturating_sub(self.superblock.first_data_block as u64);
+        if data_blocks == 0 {
+            return per_group as u32;
+        }
+        data_blocks.saturating_sub(first).min(per_group) as u32
+    }
+
+    /// Split a block number into its group and bitmap bit.
+    fn block_group_bit(&self, block: u64) -> Option<(u32, u32)> {
+        let per_group = self.superblock.blocks_per_group as u64;
+        let rel = block.checked_sub(self.superblock.first_data_block as u64)?;
+        if per_group == 0 {
+            return None;
+        }
+        let group = (rel / per_group) as u32;
+        let bit = (rel % per_group) as u32;
+        if group >= self.block_groups() || bit >= self.group_block_count(group) {
+            return None;
+        }
+        Some((group, bit))
+    }
+
+    fn group_first_block(&self, group: u32) -> u64 {
+        self.superblock.first_data_block as u64 + group as u64 * self.superblock.blocks_per_group as u64
+    }
+
+    /// Allocate up to `max` contiguous blocks, searching forward from `goal`
+    /// through its group and then the following groups.
+    fn allocate_blocks(&self, goal: u64, max: u32) -> VfsResult<(u64, u32)> {
+        if max == 0 {
             return Err(VfsError::Invalid);
         }
-        self.alloc_from_bitmap(desc.block_bitmap, total)
+        let groups = self.block_groups();
+        let (goal_group, goal_bit) = self.block_group_bit(goal).unwrap_or((0, 0));
+        for step in 0..=groups {
+            let group = (goal_group + step) % groups;
+            // 最后一轮回到目标组，补扫目标位之前的区域。
+            let from = if step == 0 { goal_bit } else { 0 };
+            if step == groups && goal_bit == 0 {
+                break;
+            }
+            let desc = self.read_group_desc(group)?;
+            let bitmap = desc.block_bitmap as u64 * self.fs_block_size() as u64;
+            let total = self.group_block_count(group);
+            if let Some((bit, len)) = self.find_free_run(bitmap, total, from, max)? {
+                self.update_bitmap(bitmap, bit, len, true)?;
+                self.adjust_group_count(group, GROUP_DESC_FREE_BLOCKS_OFFSET, -(len as i64))?;
+                self.adjust_free_count(SUPERBLOCK_FREE_BLOCKS_OFFSET, -(len as i64))?;
+                return Ok((self.group_first_block(group) + bit as u64, len));
+            }
+        }
+        Err(VfsError::NoSpace)
+    }
+
+    /// First run of clear bits at or after `from`, capped at `max` bits.
+    fn find_free_run(&self, bitmap: u64, total: u32, from: u32, max: u32) -> VfsResult<Option<(u32, u32)>> {
+        let mut chunk = [0u8; BITMAP_CHUNK];
+        let total_bytes = ((total + 7) / 8) as usize;
+        let mut run_start = 0u32;
+        let mut run_len = 0u32;
+        let mut bit = from;
+        while bit < total {
+            let base = (bit / 8) as usize / BITMAP_CHUNK * BITMAP_CHUNK;
+            let len = core::cmp::min(BITMAP_CHUNK, total_bytes - base);
+            read_bytes(&self.cache, bitmap + base as u64, &mut chunk[..len])?;
+            let chunk_end = core::cmp::min(total, ((base + len) * 8) as u32);
+            while bit < chunk_end {
+                let byte = chunk[bit as usize / 8 - base];
+                if run_len == 0 && bit % 8 == 0 && byte == 0xff {
+                    bit += 8;
+                    continue;
+                }
+                if byte & (1u8 << (bit % 8)) != 0 {
+                    if run_len > 0 {
+                        return Ok(Some((run_start, run_len)));
+                    }
+                } else {
+                    if run_len == 0 {
+                        run_start = bit;
+                    }
+                    run_len += 1;
+                    if run_len == max {
+                        return Ok(Some((run_start, run_len)));
+                    }
+                }
+                bit += 1;
+            }
+        }
+        Ok((run_len > 0).then_some((run_start, run_len)))
+    }
+
+    fn update_bitmap(&self, bitmap: u64, first: u32, count: u32, set: bool) -> VfsResult<()> {
+        let mut bit = first;
+        let end = first + count;
+        while bit < end {
+            let mut byte = [0u8; 1];
+            let offset = bitmap + (bit / 8) as u64;
+            read_bytes(&self.cache, offset, &mut byte)?;
+            while bit < end {
+                let mask = 1u8 << (bit % 8);
+                if set {
+                    byte[0] |= mask;
+                } else {
+                    byte[0] &= !mask;
+                }
+                bit += 1;
+                if bit % 8 == 0 {
+                    break;
+                }
+            }
+            write_bytes(&self.cache, offset, &byte)?;
+        }
+        Ok(())
+    }
+
+    /// Return `count` blocks starting at `start` to their group bitmaps.
+    fn free_blocks(&self, start: u64, count: u64) -> VfsResult<()> {
+        let mut block = start;
+        let end = start + count;
+        while block < end {
+            let (group, bit) = self.block_group_bit(block).ok_or(VfsError::Invalid)?;
+            let in_group = core::cmp::min(end - block, (self.group_block_count(group) - bit) as u64) as u32;
+            let desc = self.read_group_desc(group)?;
+            let bitmap = desc.block_bitmap as u64 * self.fs_block_size() as u64;
+            self.update_bitmap(bitmap, bit, in_group, false)?;
+            self.adjust_group_count(group, GROUP_DESC_FREE_BLOCKS_OFFSET, in_group as i64)?;
+            block += in_group as u64;
+        }
+        self.adjust_free_count(SUPERBLOCK_FREE_BLOCKS_OFFSET, count as i64)
+    }
+
+    /// Keep a superblock free block/inode counter in step with bitmap changes.
+    fn adjust_free_count(&self, offset: usize, delta: i64) -> VfsResult<()> {
+        let mut raw = [0u8; 4];
+        read_bytes(&self.cache, SUPERBLOCK_OFFSET + offset as u64, &mut raw)?;
+        let value = (u32::from_le_bytes(raw) as i64 + delta).clamp(0, u32::MAX as i64) as u32;
+        write_bytes(&self.cache, SUPERBLOCK_OFFSET + offset as u64, &value.to_le_bytes())
+    }
+
+    /// Number of blocks staged for delayed allocation on this filesystem.
+    fn staged_blocks(&self) -> u64 {
+        let owner = self.owner_id();
+        let guard = EXT4_DELALLOC.lock();
+        guard.get_mut().iter().filter(|slot| slot.used && slot.owner == owner).count() as u64
+    }
+
+    /// Preferred physical block for `logical`: continue the nearest mapped
+    /// extent, otherwise start of the inode's block group.
+    fn allocation_goal(&self, inode: &Ext4Inode, ino: InodeId, logical: u32) -> VfsResult<u64> {
+        if (inode.flags & EXT4_EXTENTS_FLAG) != 0 {
+            let mut buf = [0u8; EXT4_SCRATCH_SIZE];
+            self.find_extent_leaf(inode, logical as u64, &mut buf)?;
+            let entries = parse_extent_header(&buf)?.entries as usize;
+            let nearest = (0..entries)
+                .map(|idx| read_extent_entry(&buf, idx))
+                .take_while(|entry| entry.block <= logical)
+                .last();
+            if let Some(entry) = nearest {
+                return Ok(entry.start + (logical - entry.block) as u64);
+            }
+        } else if logical > 0 {
+            if let Some(prev) = self.map_indirect_block(inode, logical - 1)? {
+                return Ok(prev + 1);
+            }
+        }
+        Ok(self.inode_group_start(ino))
+    }
+
+    /// First block of the group that holds inode `ino`.
+    fn inode_group_start(&self, ino: InodeId) -> u64 {
+        let inodes_per_group = self.superblock.inodes_per_group.max(1) as u64;
+        let group = ((ino.saturating_sub(1)) / inodes_per_group) as u32;
+        self.group_first_block(group % self.block_groups())
     }
 
     fn zero_fs_block(&self, block: u32) -> VfsResult<()> {
@@ -1120,7 +1672,7 @@ impl<'a> Ext4Fs<'a> {
         }
 
         for block_index in 0..total_blocks {
-            let Some(block) = self.map_block(&inode_meta, block_index)? else {
+            let Some(block) = self.map_block(&inode_meta, block_index)?.map(|m| m.phys) else {
                 continue;
             };
             self.read_fs_block(block, &mut scratch[..block_size])?;
@@ -1161,7 +1713,192 @@ impl<'a> Ext4Fs<'a> {
             return self.write_fs_block(block, &scratch[..block_size]);
         }
 
-        Err(VfsError::NoMem)
+        // 所有块都已满：追加一个新块，由新目录项占满整块。
+        let mut inode_meta = inode_meta;
+        let block = self.allocate_data_block(&mut inode_meta, total_blocks)?;
+        scratch[..block_size].fill(0);
+        write_dir_entry(&mut scratch, 0, inode, name_bytes, kind, block_size as u16)?;
+        self.write_fs_block(block, &scratch[..block_size])?;
+        inode_meta.size = (total_blocks as u64 + 1) * block_size as u64;
+        self.write_inode(dir_inode, &inode_meta)
+    }
+
+    /// Create a directory with one extent-mapped block holding "." and "..".
+    fn mkdir(&self, parent: InodeId, name: &str, mode: u16) -> VfsResult<InodeId> {
+        if name.is_empty() || name.len() > axvfs::MAX_NAME_LEN {
+            return Err(VfsError::Invalid);
+        }
+        if inode_mode_type(self.read_inode(parent)?.mode) != FileType::Dir {
+            return Err(VfsError::NotDir);
+        }
+        let inode = self.allocate_inode()?;
+        self.reset_inode(inode)?;
+        let block_size = self.fs_block_size() as usize;
+        let block = self.allocate_block_near(self.inode_group_start(inode))?;
+        let mut scratch = [0u8; EXT4_SCRATCH_SIZE];
+        write_dir_entry(&mut scratch, 0, inode, b".", FileType::Dir, 12)?;
+        write_dir_entry(&mut scratch, 12, parent, b"..", FileType::Dir, (block_size - 12) as u16)?;
+        self.write_fs_block(block as u64, &scratch[..block_size])?;
+        let mut inode_meta = Ext4Inode {
+            mode: EXT4_MODE_DIR | (mode & 0o7777),
+            uid: 0,
+            gid: 0,
+            size: block_size as u64,
+            flags: EXT4_EXTENTS_FLAG,
+            blocks: [0u32; 15],
+        };
+        init_inode_extents(&mut inode_meta);
+        let extent = ExtentEntry {
+            block: 0,
+            len: 1,
+            start: block as u64,
+            unwritten: false,
+        };
+        self.insert_extent(&mut inode_meta, extent)?;
+        self.write_inode(inode, &inode_meta)?;
+        // "." 与父目录中的名字各算一次链接，子目录的 ".." 计入父目录。
+        self.adjust_links(inode, 2)?;
+        self.adjust_group_count(0, GROUP_DESC_USED_DIRS_OFFSET, 1)?;
+        self.insert_dir_entry(parent, name, inode, FileType::Dir)?;
+        self.adjust_links(parent, 1)?;
+        Ok(inode)
+    }
+
+    /// Add `delta` to an inode's on-disk link count.
+    fn adjust_links(&self, inode: InodeId, delta: i32) -> VfsResult<()> {
+        let mut raw = [0u8; 512];
+        let (offset, _) = self.read_inode_raw(inode, &mut raw)?;
+        let links = (read_u16(&raw, INODE_LINKS_COUNT_OFFSET) as i32 + delta).clamp(0, u16::MAX as i32) as u16;
+        write_bytes(&self.cache, offset + INODE_LINKS_COUNT_OFFSET as u64, &links.to_le_bytes())
+    }
+
+    /// Read the raw on-disk inode, returning its byte offset and size.
+    fn read_inode_raw(&self, inode: InodeId, buf: &mut [u8; 512]) -> VfsResult<(u64, usize)> {
+        let (offset, inode_size) = self.inode_location(inode)?;
+        read_bytes(&self.cache, offset, &mut buf[..inode_size])?;
+        Ok((offset, inode_size))
+    }
+
+    /// Zero a freshly allocated inode so it inherits no stale xattr state.
+    fn reset_inode(&self, inode: InodeId) -> VfsResult<()> {
+        let (offset, inode_size) = self.inode_location(inode)?;
+        let mut raw = [0u8; 512];
+        if inode_size >= EXT4_GOOD_OLD_INODE_SIZE + EXT4_NEW_EXTRA_ISIZE as usize {
+            write_u16(&mut raw, INODE_EXTRA_ISIZE_OFFSET, EXT4_NEW_EXTRA_ISIZE);
+        }
+        write_bytes(&self.cache, offset, &raw[..inode_size])
+    }
+
+    fn read_xattr_block(&self, block: u64, buf: &mut [u8]) -> VfsResult<()> {
+        self.read_fs_block(block, buf)?;
+        if read_u32(buf, 0) != XATTR_MAGIC || read_u32(buf, XATTR_BLOCK_BLOCKS_OFFSET) != 1 {
+            return Err(VfsError::Io);
+        }
+        Ok(())
+    }
+
+    /// Set (`value` is `Some`) or remove one attribute.
+    ///
+    /// New values go into the inode body first and spill into the `i_file_acl`
+    /// block; a shared block is copied before it is modified and an emptied
+    /// block is released.
+    #[inline(never)]
+    fn update_xattr(
+        &self,
+        inode: InodeId,
+        index: u8,
+        name: &[u8],
+        value: Option<&[u8]>,
+        mode: XattrMode,
+    ) -> VfsResult<()> {
+        let block_size = self.fs_block_size() as usize;
+        let mut raw = [0u8; 512];
+        let (offset, inode_size) = self.read_inode_raw(inode, &mut raw)?;
+        let header = inode_xattr_header(&raw, inode_size);
+        if let Some(header) = header {
+            if read_u32(&raw, header) != XATTR_MAGIC {
+                raw[header..inode_size].fill(0);
+            }
+        }
+        let acl = inode_file_acl(&raw);
+        let mut block = [0u8; EXT4_SCRATCH_SIZE];
+        if acl != 0 {
+            self.read_xattr_block(acl, &mut block)?;
+        }
+        let in_body =
+            header.is_some_and(|h| xattr_find(&raw[h..inode_size], XATTR_IBODY_HEADER_SIZE, index, name).is_some());
+        let in_block = acl != 0 && xattr_find(&block[..block_size], XATTR_BLOCK_HEADER_SIZE, index, name).is_some();
+        match (mode, in_body || in_block) {
+            (XattrMode::Create, true) => return Err(VfsError::AlreadyExists),
+            (XattrMode::Replace, false) => return Err(VfsError::NotFound),
+            _ => {}
+        }
+        let skip = (index, name);
+        let add = value.map(|value| (index, name, value));
+        let mut placed = add.is_none();
+        if let Some(header) = header {
+            let guard = EXT4_SCRATCH.lock();
+            let scratch = &mut guard.get_mut()[..inode_size - header];
+            let body = &mut raw[header..inode_size];
+            let mut rebuilt = xattr_rebuild(body, scratch, XATTR_IBODY_HEADER_SIZE, skip, add);
+            if rebuilt.is_ok() {
+                placed = true;
+            } else if rebuilt == Err(VfsError::NoSpace) {
+                rebuilt = xattr_rebuild(body, scratch, XATTR_IBODY_HEADER_SIZE, skip, None);
+            }
+            let nonempty = rebuilt?;
+            body.copy_from_slice(scratch);
+            write_u32(body, 0, if nonempty { XATTR_MAGIC } else { 0 });
+        }
+        let mut new_acl = acl;
+        if in_block || !placed {
+            if acl == 0 {
+                write_u32(&mut block, 0, XATTR_MAGIC);
+                write_u32(&mut block, XATTR_BLOCK_REFCOUNT_OFFSET, 1);
+                write_u32(&mut block, XATTR_BLOCK_BLOCKS_OFFSET, 1);
+            }
+            let add = if placed { None } else { add };
+            let nonempty = {
+                let guard = EXT4_SCRATCH.lock();
+                let scratch = &mut guard.get_mut()[..block_size];
+                let nonempty = xattr_rebuild(&block[..block_size], scratch, XATTR_BLOCK_HEADER_SIZE, skip, add)?;
+                block[..block_size].copy_from_slice(scratch);
+                nonempty
+            };
+            let refcount = read_u32(&block, XATTR_BLOCK_REFCOUNT_OFFSET);
+            let shared = acl != 0 && refcount > 1;
+            if nonempty {
+                if acl == 0 || shared {
+                    new_acl = self.allocate_block_near(self.inode_group_start(inode))? as u64;
+                    write_u32(&mut block, XATTR_BLOCK_REFCOUNT_OFFSET, 1);
+                }
+                let hash = xattr_block_hash(&block[..block_size]);
+                write_u32(&mut block, XATTR_BLOCK_HASH_OFFSET, hash);
+                self.write_fs_block(new_acl, &block[..block_size])?;
+            } else {
+                new_acl = 0;
+            }
+            if shared {
+                let refcount_at = acl * block_size as u64 + XATTR_BLOCK_REFCOUNT_OFFSET as u64;
+                write_bytes(&self.cache, refcount_at, &(refcount - 1).to_le_bytes())?;
+            } else if acl != 0 && !nonempty {
+                self.free_blocks(acl, 1)?;
+            }
+        }
+        set_inode_file_acl(&mut raw, new_acl);
+        write_bytes(&self.cache, offset, &raw[..inode_size])
+    }
+}
+
+impl Drop for Ext4Fs<'_> {
+    fn drop(&mut self) {
+        // 卸载时尽力回写；失败的暂存块直接丢弃，避免被之后同地址的实例误认。
+        let _ = self.writeback(None);
+        let owner = self.owner_id();
+        let guard = EXT4_DELALLOC.lock();
+        for slot in guard.get_mut().iter_mut().filter(|slot| slot.owner == owner) {
+            slot.used = false;
+        }
     }
 }
 
@@ -1188,9 +1925,22 @@ impl VfsOps for Ext4Fs<'_> {
     }
 
     fn create(&self, parent: InodeId, name: &str, kind: FileType, mode: u16) -> VfsResult<InodeId> {
-        if kind != FileType::File {
-            return Err(VfsError::NotSupported);
+        match kind {
+            FileType::File | FileType::Fifo => self.mknod(parent, name, kind, mode, 0),
+            FileType::Dir => self.mkdir(parent, name, mode),
+            _ => Err(VfsError::NotSupported),
         }
+    }
+
+    fn mknod(&self, parent: InodeId, name: &str, kind: FileType, mode: u16, rdev: u64) -> VfsResult<InodeId> {
+        let type_bits = match kind {
+            FileType::File => EXT4_MODE_FILE,
+            FileType::Fifo => EXT4_MODE_FIFO,
+            FileType::Char => EXT4_MODE_CHAR,
+            FileType::Block => EXT4_MODE_BLOCK,
+            FileType::Socket => EXT4_MODE_SOCKET,
+            _ => return Err(VfsError::NotSupported),
+        };
         if name.is_empty() || name.len() > axvfs::MAX_NAME_LEN {
             return Err(VfsError::Invalid);
         }
@@ -1199,14 +1949,25 @@ impl VfsOps for Ext4Fs<'_> {
             return Err(VfsError::NotDir);
         }
         let inode = self.allocate_inode()?;
+        self.reset_inode(inode)?;
         let mut inode_meta = Ext4Inode {
-            mode: EXT4_MODE_FILE | (mode & 0o777),
+            mode: type_bits | (mode & 0o7777),
+            uid: 0,
+            gid: 0,
             size: 0,
-            flags: EXT4_EXTENTS_FLAG,
+            flags: 0,
             blocks: [0u32; 15],
         };
-        init_inode_extents(&mut inode_meta);
+        match kind {
+            FileType::File => {
+                inode_meta.flags = EXT4_EXTENTS_FLAG;
+                init_inode_extents(&mut inode_meta);
+            }
+            FileType::Char | FileType::Block => encode_inode_rdev(&mut inode_meta, rdev),
+            _ => {}
+        }
         self.write_inode(inode, &inode_meta)?;
+        self.adjust_links(inode, 1)?;
         self.insert_dir_entry(parent, name, inode, kind)?;
         Ok(inode)
     }
@@ -1218,16 +1979,24 @@ impl VfsOps for Ext4Fs<'_> {
     fn metadata(&self, inode: InodeId) -> VfsResult<Metadata> {
         let inode_meta = self.read_inode(inode)?;
         let file_type = inode_mode_type(inode_meta.mode);
-        let mode = (inode_meta.mode & 0o777) as u16;
-        Ok(Metadata::new(file_type, inode_meta.size, mode))
+        let mode = inode_meta.mode & 0o7777;
+        let meta = Metadata::new(file_type, inode_meta.size, mode).with_owner(inode_meta.uid, inode_meta.gid);
+        match file_type {
+            FileType::Char | FileType::Block => Ok(meta.with_rdev(decode_inode_rdev(&inode_meta))),
+            _ => Ok(meta),
+        }
     }
 
     fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
         let inode_meta = self.read_inode(inode)?;
-        if inode_mode_type(inode_meta.mode) == FileType::Dir {
-            return Err(VfsError::NotDir);
+        match inode_mode_type(inode_meta.mode) {
+            FileType::Dir => return Err(VfsError::NotDir),
+            FileType::Char | FileType::Block | FileType::Fifo | FileType::Socket => return Err(VfsError::Invalid),
+            _ => {}
         }
-        self.read_from_inode(&inode_meta, offset, buf)
+        let read = self.read_from_inode(&inode_meta, offset, buf)?;
+        self.read_staged(inode, offset, &mut buf[..read]);
+        Ok(read)
     }
 
     fn write_at(&self, inode: InodeId, offset: u64, buf: &[u8]) -> VfsResult<usize> {
@@ -1235,11 +2004,15 @@ impl VfsOps for Ext4Fs<'_> {
             return Ok(0);
         }
         let mut inode_meta = self.read_inode(inode)?;
-        if inode_mode_type(inode_meta.mode) == FileType::Dir {
-            return Err(VfsError::NotDir);
+        match inode_mode_type(inode_meta.mode) {
+            FileType::Dir => return Err(VfsError::NotDir),
+            FileType::Char | FileType::Block | FileType::Fifo | FileType::Socket => return Err(VfsError::Invalid),
+            _ => {}
         }
-        // Minimal write path: direct/indirect blocks only, no extent growth or journaling.
+        // Minimal write path without journaling. Holes in extent files are
+        // staged and allocated at writeback; long runs are allocated directly.
         let block_size = self.fs_block_size() as u64;
+        let extents = (inode_meta.flags & EXT4_EXTENTS_FLAG) != 0;
         let mut total = 0usize;
         let mut cur_offset = offset;
         while total < buf.len() {
@@ -1247,7 +2020,46 @@ impl VfsOps for Ext4Fs<'_> {
             let in_block = (cur_offset % block_size) as usize;
             let to_copy = core::cmp::min(buf.len() - total, block_size as usize - in_block);
             let phys = match self.map_block(&inode_meta, block_index)? {
-                Some(block) => block,
+                Some(mapping) if mapping.unwritten => {
+                    if to_copy < block_size as usize {
+                        self.zero_fs_block(mapping.phys as u32)?;
+                    }
+                    self.convert_unwritten(&mut inode_meta, block_index, mapping.phys)?;
+                    mapping.phys
+                }
+                Some(mapping) => mapping.phys,
+                None if extents => {
+                    let full = if in_block == 0 { (buf.len() - total) / block_size as usize } else { 0 };
+                    let run = if full >= EXT4_DELALLOC_SLOTS {
+                        self.hole_run(inode, &inode_meta, block_index, full as u32)?
+                    } else {
+                        0
+                    };
+                    let written = if run > 0 {
+                        let len = run as usize * block_size as usize;
+                        self.write_new_run(inode, &mut inode_meta, block_index, &buf[total..total + len])?
+                    } else if self.stage_block(
+                        inode,
+                        &mut inode_meta,
+                        block_index,
+                        in_block,
+                        &buf[total..total + to_copy],
+                    )? {
+                        to_copy
+                    } else {
+                        0
+                    };
+                    if written > 0 {
+                        total += written;
+                        cur_offset += written as u64;
+                        continue;
+                    }
+                    let (phys, _) = self.map_new_run(inode, &mut inode_meta, block_index, 1)?;
+                    if to_copy < block_size as usize {
+                        self.zero_fs_block(phys as u32)?;
+                    }
+                    phys
+                }
                 None => self.allocate_data_block(&mut inode_meta, block_index)?,
             };
             let block_offset = phys * block_size + in_block as u64;
@@ -1291,28 +2103,192 @@ impl VfsOps for Ext4Fs<'_> {
     }
 
     fn flush(&self) -> VfsResult<()> {
+        self.writeback(None)?;
         self.cache.flush()
     }
 
+    fn statfs(&self) -> VfsResult<StatFs> {
+        let mut raw = [0u8; 20];
+        read_bytes(&self.cache, SUPERBLOCK_OFFSET, &mut raw)?;
+        let reserved = read_u32(&raw, SUPERBLOCK_R_BLOCKS_COUNT_OFFSET) as u64;
+        // 延迟分配暂存的块尚未落到位图上，按已占用计入。
+        let free = (read_u32(&raw, SUPERBLOCK_FREE_BLOCKS_OFFSET) as u64).saturating_sub(self.staged_blocks());
+        Ok(StatFs {
+            magic: EXT4_SUPER_MAGIC,
+            block_size: self.fs_block_size(),
+            blocks: self.superblock.blocks_count,
+            blocks_free: free,
+            blocks_avail: free.saturating_sub(reserved),
+            files: read_u32(&raw, SUPERBLOCK_INODES_COUNT_OFFSET) as u64,
+            files_free: read_u32(&raw, SUPERBLOCK_FREE_INODES_OFFSET) as u64,
+            name_max: axvfs::MAX_NAME_LEN as u32,
+        })
+    }
+
     fn truncate(&self, inode: InodeId, size: u64) -> VfsResult<()> {
+        self.writeback(Some(inode))?;
         let mut inode_meta = self.read_inode(inode)?;
         if inode_mode_type(inode_meta.mode) == FileType::Dir {
             return Err(VfsError::NotDir);
         }
-        if size <= inode_meta.size {
-            // Minimal truncate: shrink size without reclaiming blocks.
-            inode_meta.size = size;
-            return self.write_inode(inode, &inode_meta);
+        if size < inode_meta.size {
+            let block_size = self.fs_block_size() as u64;
+            let keep = (size + block_size - 1) / block_size;
+            self.punch_blocks(&mut inode_meta, keep, EXT4_MAX_LOGICAL_BLOCKS)?;
+            // 保留块的尾部清零，之后再扩展文件时读到的是零而不是旧数据。
+            self.zero_block_range(&inode_meta, size, keep * block_size)?;
+        }
+        // Growing leaves a hole; unmapped blocks read back as zeros.
+        inode_meta.size = size;
+        self.write_inode(inode, &inode_meta)
+    }
+
+    fn fallocate(&self, inode: InodeId, mode: FallocMode, offset: u64, len: u64, keep_size: bool) -> VfsResult<()> {
+        self.writeback(Some(inode))?;
+        let mut inode_meta = self.read_inode(inode)?;
+        match inode_mode_type(inode_meta.mode) {
+            FileType::File => {}
+            FileType::Dir => return Err(VfsError::NotDir),
+            _ => return Err(VfsError::Invalid),
+        }
+        let end = offset.checked_add(len).ok_or(VfsError::Invalid)?;
+        let block_size = self.fs_block_size() as u64;
+        if len == 0 || (end + block_size - 1) / block_size > EXT4_MAX_LOGICAL_BLOCKS {
+            return Err(VfsError::Invalid);
+        }
+        let first_full = (offset + block_size - 1) / block_size;
+        let last_full = end / block_size;
+        match mode {
+            FallocMode::Allocate => {
+                self.allocate_range(inode, &mut inode_meta, offset / block_size, (end + block_size - 1) / block_size)?;
+            }
+            FallocMode::PunchHole => {
+                self.zero_partial_edges(&inode_meta, offset, end)?;
+                self.punch_blocks(&mut inode_meta, first_full, last_full)?;
+            }
+            FallocMode::ZeroRange => {
+                self.zero_partial_edges(&inode_meta, offset, end)?;
+                self.punch_blocks(&mut inode_meta, first_full, last_full)?;
+                self.allocate_range(inode, &mut inode_meta, first_full, last_full)?;
+            }
+        }
+        if mode != FallocMode::PunchHole && !keep_size && end > inode_meta.size {
+            inode_meta.size = end;
+        }
+        self.write_inode(inode, &inode_meta)
+    }
+
+    fn fiemap(&self, inode: InodeId, offset: u64, extents: &mut [FileExtent]) -> VfsResult<usize> {
+        self.writeback(Some(inode))?;
+        let inode_meta = self.read_inode(inode)?;
+        if inode_mode_type(inode_meta.mode) != FileType::File {
+            return Err(VfsError::Invalid);
         }
         let block_size = self.fs_block_size() as u64;
-        let blocks_needed = (size + block_size - 1) / block_size;
-        for block_index in 0..blocks_needed {
-            let block_index = block_index as u32;
-            if self.map_block(&inode_meta, block_index)?.is_none() {
-                let _ = self.allocate_data_block(&mut inode_meta, block_index)?;
+        let mut count = 0usize;
+        if extents.is_empty() {
+            return Ok(0);
+        }
+        if (inode_meta.flags & EXT4_EXTENTS_FLAG) != 0 {
+            self.for_each_extent(&inode_meta, offset / block_size, |entry| {
+                extents[count] = FileExtent {
+                    logical: entry.block as u64 * block_size,
+                    physical: entry.start * block_size,
+                    length: entry.len as u64 * block_size,
+                    unwritten: entry.unwritten,
+                };
+                count += 1;
+                Ok(count == extents.len())
+            })?;
+            return Ok(count);
+        }
+        let blocks = (inode_meta.size + block_size - 1) / block_size;
+        for block_index in offset / block_size..blocks {
+            let Some(phys) = self.map_indirect_block(&inode_meta, block_index as u32)? else {
+                continue;
+            };
+            if count > 0 {
+                let last = &mut extents[count - 1];
+                if last.logical + last.length == block_index * block_size
+                    && last.physical + last.length == phys * block_size
+                {
+                    last.length += block_size;
+                    continue;
+                }
+                if count == extents.len() {
+                    break;
+                }
             }
+            extents[count] = FileExtent {
+                logical: block_index * block_size,
+                physical: phys * block_size,
+                length: block_size,
+                unwritten: false,
+            };
+            count += 1;
         }
-        inode_meta.size = size;
+        Ok(count)
+    }
+
+    fn getxattr(&self, inode: InodeId, name: &str, buf: &mut [u8]) -> VfsResult<usize> {
+        let (index, key) = xattr_split(name)?;
+        let mut raw = [0u8; 512];
+        let (_, inode_size) = self.read_inode_raw(inode, &mut raw)?;
+        if let Some(header) = inode_xattr_header(&raw, inode_size).filter(|&h| read_u32(&raw, h) == XATTR_MAGIC) {
+            let body = &raw[header..inode_size];
+            if let Some(entry) = xattr_find(body, XATTR_IBODY_HEADER_SIZE, index, key) {
+                return xattr_copy(xattr_value(body, XATTR_IBODY_HEADER_SIZE, &entry)?, buf);
+            }
+        }
+        let acl = inode_file_acl(&raw);
+        if acl == 0 {
+            return Err(VfsError::NotFound);
+        }
+        let block_size = self.fs_block_size() as usize;
+        let mut block = [0u8; EXT4_SCRATCH_SIZE];
+        self.read_xattr_block(acl, &mut block)?;
+        let block = &block[..block_size];
+        let entry = xattr_find(block, XATTR_BLOCK_HEADER_SIZE, index, key).ok_or(VfsError::NotFound)?;
+        xattr_copy(xattr_value(block, 0, &entry)?, buf)
+    }
+
+    fn setxattr(&self, inode: InodeId, name: &str, value: &[u8], mode: XattrMode) -> VfsResult<()> {
+        let (index, key) = xattr_split(name)?;
+        self.update_xattr(inode, index, key, Some(value), mode)
+    }
+
+    fn listxattr(&self, inode: InodeId, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut raw = [0u8; 512];
+        let (_, inode_size) = self.read_inode_raw(inode, &mut raw)?;
+        let mut len = 0;
+        if let Some(header) = inode_xattr_header(&raw, inode_size).filter(|&h| read_u32(&raw, h) == XATTR_MAGIC) {
+            xattr_list(&raw[header..inode_size], XATTR_IBODY_HEADER_SIZE, buf, &mut len);
+        }
+        let acl = inode_file_acl(&raw);
+        if acl != 0 {
+            let block_size = self.fs_block_size() as usize;
+            let mut block = [0u8; EXT4_SCRATCH_SIZE];
+            self.read_xattr_block(acl, &mut block)?;
+            xattr_list(&block[..block_size], XATTR_BLOCK_HEADER_SIZE, buf, &mut len);
+        }
+        Ok(len)
+    }
+
+    fn removexattr(&self, inode: InodeId, name: &str) -> VfsResult<()> {
+        let (index, key) = xattr_split(name)?;
+        self.update_xattr(inode, index, key, None, XattrMode::Replace)
+    }
+
+    fn set_mode(&self, inode: InodeId, mode: u16) -> VfsResult<()> {
+        let mut inode_meta = self.read_inode(inode)?;
+        inode_meta.mode = (inode_meta.mode & !0o7777) | (mode & 0o7777);
+        self.write_inode(inode, &inode_meta)
+    }
+
+    fn set_owner(&self, inode: InodeId, uid: u32, gid: u32) -> VfsResult<()> {
+        let mut inode_meta = self.read_inode(inode)?;
+        inode_meta.uid = uid;
+        inode_meta.gid = gid;
         self.write_inode(inode, &inode_meta)
     }
 }
@@ -1331,6 +2307,16 @@ fn read_bytes(cache: &BlockCache<'_>, offset: u64, buf: &mut [u8]) -> VfsResult<
     while remaining > 0 {
         let block_id = cur_offset / block_size_u64;
         let in_block = (cur_offset % block_size_u64) as usize;
+        if in_block == 0 && remaining >= block_size {
+            // Whole blocks go straight into the caller buffer so the device
+            // sees one multi-block request instead of one per sector.
+            let whole = remaining - remaining % block_size;
+            cache.read_blocks(block_id, &mut buf[buf_offset..buf_offset + whole])?;
+            remaining -= whole;
+            buf_offset += whole;
+            cur_offset += whole as u64;
+            continue;
+        }
         let to_copy = core::cmp::min(remaining, block_size - in_block);
         cache.read_block(block_id, &mut scratch[..block_size])?;
         buf[buf_offset..buf_offset + to_copy]
@@ -1372,6 +2358,11 @@ fn write_bytes(cache: &BlockCache<'_>, offset: u64, buf: &[u8]) -> VfsResult<()>
     Ok(())
 }
 
+fn count_clear_bits(bitmap: &[u8], bits: u32) -> u32 {
+    let bits = core::cmp::min(bits as usize, bitmap.len() * 8);
+    (0..bits).filter(|&bit| bitmap[bit / 8] & (1 << (bit % 8)) == 0).count() as u32
+}
+
 fn read_u16(buf: &[u8], offset: usize) -> u16 {
     u16::from_le_bytes([buf[offset], buf[offset + 1]])
 }
@@ -1394,15 +2385,37 @@ fn inode_mode_type(mode: u16) -> FileType {
     match mode & 0xf000 {
         EXT4_MODE_DIR => FileType::Dir,
         EXT4_MODE_FILE => FileType::File,
-        0x2000 => FileType::Char,
-        0x6000 => FileType::Block,
-        0x1000 => FileType::Fifo,
+        EXT4_MODE_CHAR => FileType::Char,
+        EXT4_MODE_BLOCK => FileType::Block,
+        EXT4_MODE_FIFO => FileType::Fifo,
         0xa000 => FileType::Symlink,
         0xc000 => FileType::Socket,
         _ => FileType::File,
     }
 }
 
+/// Store a device number the way Linux does: old 16-bit form in `i_block[0]`
+/// when it fits, otherwise the new 32-bit form in `i_block[1]`.
+fn encode_inode_rdev(inode: &mut Ext4Inode, rdev: u64) {
+    let major = axvfs::dev_major(rdev);
+    let minor = axvfs::dev_minor(rdev);
+    inode.blocks = [0u32; 15];
+    if major < 256 && minor < 256 {
+        inode.blocks[0] = (major << 8) | minor;
+    } else {
+        inode.blocks[1] = (minor & 0xff) | (major << 8) | ((minor & !0xff) << 12);
+    }
+}
+
+fn decode_inode_rdev(inode: &Ext4Inode) -> u64 {
+    let old = inode.blocks[0];
+    if old != 0 {
+        return axvfs::make_dev((old >> 8) & 0xff, old & 0xff);
+    }
+    let new = inode.blocks[1];
+    axvfs::make_dev((new & 0xfff00) >> 8, (new & 0xff) | ((new >> 12) & 0xfff00))
+}
+
 fn dir_entry_size(name_len: usize) -> usize {
     let size = EXT4_DIR_ENTRY_HEADER + name_len;
     (size + 3) & !3
@@ -1443,16 +2456,7 @@ struct ExtentEntry {
     block: u32,
     len: u16,
     start: u64,
-}
-
-impl Default for ExtentEntry {
-    fn default() -> Self {
-        Self {
-            block: 0,
-            len: 0,
-            start: 0,
-        }
-    }
+    unwritten: bool,
 }
 
 impl ExtentEntry {
@@ -1463,12 +2467,46 @@ impl ExtentEntry {
         logical >= self.block && logical < self.block + self.len as u32
     }
 
-    fn can_extend(&self, logical: u32, phys: u64) -> bool {
-        if self.len == 0 || self.len >= EXTENT_LEN_MAX {
-            return false;
+    fn end(&self) -> u64 {
+        self.block as u64 + self.len as u64
+    }
+
+    fn max_len(&self) -> u32 {
+        if self.unwritten {
+            EXT_UNWRITTEN_MAX_LEN
+        } else {
+            EXT_INIT_MAX_LEN
         }
-        logical == self.block + self.len as u32 && phys == self.start + self.len as u64
     }
+
+    /// Whether `next` continues this extent both logically and physically.
+    fn can_merge(&self, next: &ExtentEntry) -> bool {
+        self.unwritten == next.unwritten
+            && self.end() == next.block as u64
+            && self.start + self.len as u64 == next.start
+            && self.len as u32 + next.len as u32 <= self.max_len()
+    }
+}
+
+/// Physical location of a logical block.
+#[derive(Clone, Copy)]
+struct BlockMap {
+    phys: u64,
+    unwritten: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExtentNode {
+    Root,
+    Block(u64),
+}
+
+/// Path from the inode root down to one extent leaf.
+struct ExtentCursor {
+    path: [(ExtentNode, usize); EXTENT_MAX_DEPTH],
+    levels: usize,
+    leaf: ExtentNode,
+    next_key: Option<u64>,
 }
 
 #[derive(Clone, Copy)]
@@ -1545,21 +2583,30 @@ fn store_inode_extents(inode: &mut Ext4Inode, raw: &[u8; INODE_BLOCK_LEN]) {
 fn read_extent_entry(buf: &[u8], idx: usize) -> ExtentEntry {
     let offset = extent_entry_offset(idx);
     let ee_block = read_u32(buf, offset);
-    let ee_len = read_u16(buf, offset + 4) & EXTENT_LEN_MAX;
+    let raw_len = read_u16(buf, offset + 4) as u32;
     let ee_start_hi = read_u16(buf, offset + 6) as u32;
     let ee_start_lo = read_u32(buf, offset + 8);
     let start = ((ee_start_hi as u64) << 32) | ee_start_lo as u64;
+    // 长度超过 32768 的 extent 是未初始化（预分配）区间。
+    let unwritten = raw_len > EXT_INIT_MAX_LEN;
+    let len = if unwritten { raw_len - EXT_INIT_MAX_LEN } else { raw_len };
     ExtentEntry {
         block: ee_block,
-        len: ee_len,
+        len: len as u16,
         start,
+        unwritten,
     }
 }
 
 fn write_extent_entry(buf: &mut [u8], idx: usize, entry: ExtentEntry) {
     let offset = extent_entry_offset(idx);
     write_u32(buf, offset, entry.block);
-    write_u16(buf, offset + 4, entry.len);
+    let raw_len = if entry.unwritten {
+        entry.len as u32 + EXT_INIT_MAX_LEN
+    } else {
+        entry.len as u32
+    };
+    write_u16(buf, offset + 4, raw_len as u16);
     write_u16(buf, offset + 6, (entry.start >> 32) as u16);
     write_u32(buf, offset + 8, entry.start as u32);
 }
@@ -1581,43 +2628,308 @@ fn write_extent_index(buf: &mut [u8], idx: usize, entry: ExtentIndex) {
     write_u16(buf, offset + 10, 0);
 }
 
-fn map_extent_entries(buf: &[u8], entries: u16, logical: u32) -> VfsResult<Option<u64>> {
-    let mut offset = EXTENT_HEADER_SIZE;
-    for _ in 0..entries {
-        if offset + EXTENT_ENTRY_SIZE > buf.len() {
-            break;
+fn extent_key(buf: &[u8], idx: usize) -> u32 {
+    read_u32(buf, extent_entry_offset(idx))
+}
+
+/// Index of the last slot whose key is not above `logical` (or 0).
+fn extent_slot_for(buf: &[u8], entries: usize, logical: u64) -> usize {
+    (1..entries)
+        .take_while(|&idx| extent_key(buf, idx) as u64 <= logical)
+        .last()
+        .unwrap_or(0)
+}
+
+/// Extend a neighbouring leaf entry to cover `new` instead of adding a slot.
+fn leaf_merge(buf: &mut [u8], header: &ExtentHeader, new: ExtentEntry) -> bool {
+    let count = header.entries as usize;
+    let next_idx = (0..count).find(|&idx| extent_key(buf, idx) as u64 >= new.end());
+    let prev_idx = match next_idx {
+        Some(0) => None,
+        Some(idx) => Some(idx - 1),
+        None => count.checked_sub(1),
+    };
+    if let Some(prev_idx) = prev_idx {
+        let mut prev = read_extent_entry(buf, prev_idx);
+        if prev.can_merge(&new) {
+            prev.len += new.len;
+            if let Some(next_idx) = next_idx {
+                let next = read_extent_entry(buf, next_idx);
+                if prev.can_merge(&next) {
+                    prev.len += next.len;
+                    buf.copy_within(
+                        extent_entry_offset(next_idx + 1)..extent_entry_offset(count),
+                        extent_entry_offset(next_idx),
+                    );
+                    let max = read_u16(buf, 4);
+                    write_extent_header(buf, (count - 1) as u16, 0, max);
+                }
+            }
+            write_extent_entry(buf, prev_idx, prev);
+            return true;
+        }
+    }
+    if let Some(next_idx) = next_idx {
+        let mut next = read_extent_entry(buf, next_idx);
+        if new.can_merge(&next) {
+            next.block = new.block;
+            next.start = new.start;
+            next.len += new.len;
+            write_extent_entry(buf, next_idx, next);
+            return true;
+        }
+    }
+    false
+}
+
+fn leaf_insert(buf: &mut [u8], header: &mut ExtentHeader, capacity: usize, new: ExtentEntry) -> VfsResult<()> {
+    let count = header.entries as usize;
+    if count >= capacity {
+        return Err(VfsError::NoSpace);
+    }
+    let pos = (0..count).find(|&idx| extent_key(buf, idx) > new.block).unwrap_or(count);
+    buf.copy_within(extent_entry_offset(pos)..extent_entry_offset(count), extent_entry_offset(pos + 1));
+    write_extent_entry(buf, pos, new);
+    header.entries += 1;
+    write_extent_header(buf, header.entries, 0, capacity as u16);
+    Ok(())
+}
+
+/// Offset of the in-inode xattr header, or `None` when the inode has no room past `i_extra_isize`.
+fn inode_xattr_header(raw: &[u8], inode_size: usize) -> Option<usize> {
+    if inode_size < EXT4_GOOD_OLD_INODE_SIZE + 2 {
+        return None;
+    }
+    let header = EXT4_GOOD_OLD_INODE_SIZE + read_u16(raw, INODE_EXTRA_ISIZE_OFFSET) as usize;
+    (header % 4 == 0 && header + XATTR_IBODY_HEADER_SIZE + 4 <= inode_size).then_some(header)
+}
+
+fn inode_file_acl(raw: &[u8]) -> u64 {
+    read_u32(raw, INODE_FILE_ACL_LO_OFFSET) as u64 | (read_u16(raw, INODE_FILE_ACL_HIGH_OFFSET) as u64) << 32
+}
+
+fn set_inode_file_acl(raw: &mut [u8], block: u64) {
+    write_u32(raw, INODE_FILE_ACL_LO_OFFSET, block as u32);
+    write_u16(raw, INODE_FILE_ACL_HIGH_OFFSET, (block >> 32) as u16);
+}
+
+#[derive(Clone, Copy, Debug)]
+struct XattrEntry {
+    offset: usize,
+    name_index: u8,
+    name_len: usize,
+    value_offs: usize,
+    value_inum: u32,
+    value_size: usize,
+    hash: u32,
+}
+
+impl XattrEntry {
+    fn name<'b>(&self, region: &'b [u8]) -> &'b [u8] {
+        let start = self.offset + XATTR_ENTRY_HEADER_SIZE;
+        &region[start..start + self.name_len]
+    }
+}
+
+fn xattr_entry_size(name_len: usize) -> usize {
+    (XATTR_ENTRY_HEADER_SIZE + name_len + 3) & !3
+}
+
+/// Walk the entry table of an xattr region (the inode body after its magic,
+/// or a whole block) up to the four-byte zero terminator.
+fn xattr_entries(region: &[u8], first: usize) -> impl Iterator<Item = XattrEntry> + '_ {
+    let mut offset = first;
+    core::iter::from_fn(move || {
+        if offset + XATTR_ENTRY_HEADER_SIZE > region.len() || read_u32(region, offset) == 0 {
+            return None;
+        }
+        let entry = XattrEntry {
+            offset,
+            name_index: region[offset + 1],
+            name_len: region[offset] as usize,
+            value_offs: read_u16(region, offset + 2) as usize,
+            value_inum: read_u32(region, offset + 4),
+            value_size: read_u32(region, offset + 8) as usize,
+            hash: read_u32(region, offset + 12),
+        };
+        let next = offset + xattr_entry_size(entry.name_len);
+        if next > region.len() {
+            return None;
+        }
+        offset = next;
+        Some(entry)
+    })
+}
+
+fn xattr_find(region: &[u8], first: usize, index: u8, name: &[u8]) -> Option<XattrEntry> {
+    xattr_entries(region, first).find(|entry| entry.name_index == index && entry.name(region) == name)
+}
+
+/// `e_value_offs` counts from the first entry in the inode body and from the block start otherwise.
+fn xattr_value_base(first: usize) -> usize {
+    if first == XATTR_BLOCK_HEADER_SIZE {
+        0
+    } else {
+        first
+    }
+}
+
+fn xattr_value<'b>(region: &'b [u8], base: usize, entry: &XattrEntry) -> VfsResult<&'b [u8]> {
+    // EA_INODE 大值存放在独立 inode 中，暂不支持读取。
+    if entry.value_inum != 0 {
+        return Err(VfsError::NotSupported);
+    }
+    let start = base + entry.value_offs;
+    region.get(start..start + entry.value_size).ok_or(VfsError::Io)
+}
+
+fn xattr_copy(value: &[u8], buf: &mut [u8]) -> VfsResult<usize> {
+    let count = core::cmp::min(value.len(), buf.len());
+    buf[..count].copy_from_slice(&value[..count]);
+    Ok(value.len())
+}
+
+/// Map a full attribute name to its on-disk name index and suffix.
+fn xattr_split(name: &str) -> VfsResult<(u8, &[u8])> {
+    for (index, prefix) in XATTR_PREFIXES {
+        let Some(suffix) = name.strip_prefix(prefix) else {
+            continue;
+        };
+        if !prefix.ends_with('.') {
+            if !suffix.is_empty() {
+                continue;
+            }
+        } else if suffix.is_empty() || suffix.len() > u8::MAX as usize {
+            return Err(VfsError::Invalid);
         }
-        let ee_block = read_u32(buf, offset);
-        let ee_len = read_u16(buf, offset + 4) & EXTENT_LEN_MAX;
-        let ee_start_hi = read_u16(buf, offset + 6) as u32;
-        let ee_start_lo = read_u32(buf, offset + 8);
-        if logical >= ee_block && logical < ee_block + ee_len as u32 {
-            let phys = ((ee_start_hi as u64) << 32) | ee_start_lo as u64;
-            return Ok(Some(phys + (logical - ee_block) as u64));
+        return Ok((index, suffix.as_bytes()));
+    }
+    Err(VfsError::NotSupported)
+}
+
+fn xattr_prefix(index: u8) -> Option<&'static str> {
+    XATTR_PREFIXES.iter().find(|(idx, _)| *idx == index).map(|(_, prefix)| *prefix)
+}
+
+/// Append `prefix + name + NUL` for each entry; `len` keeps counting past the end of `out`.
+fn xattr_list(region: &[u8], first: usize, out: &mut [u8], len: &mut usize) {
+    for entry in xattr_entries(region, first) {
+        let Some(prefix) = xattr_prefix(entry.name_index) else {
+            continue;
+        };
+        for part in [prefix.as_bytes(), entry.name(region), b"\0"] {
+            if let Some(dst) = out.get_mut(*len..*len + part.len()) {
+                dst.copy_from_slice(part);
+            }
+            *len += part.len();
         }
-        offset += EXTENT_ENTRY_SIZE;
     }
-    Ok(None)
 }
 
-fn find_extent_index(buf: &[u8], entries: u16, logical: u32) -> VfsResult<Option<u64>> {
-    let mut offset = EXTENT_HEADER_SIZE;
-    let mut chosen: Option<u64> = None;
-    for _ in 0..entries {
-        if offset + EXTENT_ENTRY_SIZE > buf.len() {
-            break;
+/// Rewrite `src` into `dst` without the `skip` entry, inserting `add` in ext4
+/// sort order (index, name length, name). Entries grow from `first` and values
+/// from the end of the region. Returns whether any entry remains.
+fn xattr_rebuild(
+    src: &[u8],
+    dst: &mut [u8],
+    first: usize,
+    skip: (u8, &[u8]),
+    add: Option<(u8, &[u8], &[u8])>,
+) -> VfsResult<bool> {
+    let base = xattr_value_base(first);
+    dst.fill(0);
+    dst[..first].copy_from_slice(&src[..first]);
+    let mut cursor = (first, dst.len());
+    let mut pending = add;
+    let mut any = false;
+    for entry in xattr_entries(src, first) {
+        let name = entry.name(src);
+        if (entry.name_index, name) == skip {
+            continue;
+        }
+        if let Some((index, add_name, value)) = pending {
+            if (index, add_name.len(), add_name) < (entry.name_index, entry.name_len, name) {
+                let hash = xattr_entry_hash(add_name, value);
+                xattr_put(dst, &mut cursor, base, (index, add_name), value, (0, value.len()), hash)?;
+                pending = None;
+            }
         }
-        let ei_block = read_u32(buf, offset);
-        let ei_leaf_lo = read_u32(buf, offset + 4);
-        let ei_leaf_hi = read_u16(buf, offset + 8) as u32;
-        if logical >= ei_block {
-            chosen = Some(((ei_leaf_hi as u64) << 32) | ei_leaf_lo as u64);
+        let value = if entry.value_inum != 0 {
+            &[][..]
         } else {
-            break;
+            xattr_value(src, base, &entry)?
+        };
+        let stored = (entry.value_inum, entry.value_size);
+        xattr_put(dst, &mut cursor, base, (entry.name_index, name), value, stored, entry.hash)?;
+        any = true;
+    }
+    if let Some((index, add_name, value)) = pending {
+        let hash = xattr_entry_hash(add_name, value);
+        xattr_put(dst, &mut cursor, base, (index, add_name), value, (0, value.len()), hash)?;
+        any = true;
+    }
+    Ok(any)
+}
+
+/// Write one entry at `cursor.0` and its local value just below `cursor.1`.
+/// `stored` is the (`e_value_inum`, `e_value_size`) pair to record.
+fn xattr_put(
+    dst: &mut [u8],
+    cursor: &mut (usize, usize),
+    base: usize,
+    (index, name): (u8, &[u8]),
+    value: &[u8],
+    stored: (u32, usize),
+    hash: u32,
+) -> VfsResult<()> {
+    let (next, values) = *cursor;
+    let entry_end = next + xattr_entry_size(name.len());
+    let value_start = if value.is_empty() {
+        values
+    } else {
+        values.checked_sub((value.len() + 3) & !3).ok_or(VfsError::NoSpace)?
+    };
+    // 表项之后至少保留 4 字节零作为结束标记。
+    if entry_end + 4 > value_start {
+        return Err(VfsError::NoSpace);
+    }
+    dst[value_start..value_start + value.len()].copy_from_slice(value);
+    dst[next] = name.len() as u8;
+    dst[next + 1] = index;
+    let value_offs = if value.is_empty() { 0 } else { value_start - base };
+    write_u16(dst, next + 2, value_offs as u16);
+    write_u32(dst, next + 4, stored.0);
+    write_u32(dst, next + 8, stored.1 as u32);
+    write_u32(dst, next + 12, hash);
+    let name_at = next + XATTR_ENTRY_HEADER_SIZE;
+    dst[name_at..name_at + name.len()].copy_from_slice(name);
+    *cursor = (entry_end, value_start);
+    Ok(())
+}
+
+/// `ext4_xattr_hash_entry`: name bytes as signed chars, then the value as padded LE words.
+fn xattr_entry_hash(name: &[u8], value: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for &ch in name {
+        hash = (hash << 5) ^ (hash >> 27) ^ (ch as i8 as i32 as u32);
+    }
+    for chunk in value.chunks(4) {
+        let mut word = [0u8; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        hash = (hash << 16) ^ (hash >> 16) ^ u32::from_le_bytes(word);
+    }
+    hash
+}
+
+fn xattr_block_hash(block: &[u8]) -> u32 {
+    let mut hash = 0u32;
+    for entry in xattr_entries(block, XATTR_BLOCK_HEADER_SIZE) {
+        if entry.hash == 0 {
+            return 0;
         }
-        offset += EXTENT_ENTRY_SIZE;
+        hash = (hash << 16) ^ (hash >> 16) ^ entry.hash;
     }
-    Ok(chosen)
+    hash
 }
 
 #[cfg(test)]
@@ -1773,6 +3085,31 @@ mod tests {
         assert_eq!(&buf[..read], file_data);
     }
 
+    #[test]
+    fn truncate_indirect_block() {
+        let mut data = [0u8; 32 * 1024];
+        let file_data = b"indirect";
+        build_ext4_with_indirect(&mut data, file_data);
+        let dev = TestBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.lookup(root, "init").unwrap().unwrap();
+        let mut extents = [FileExtent::empty(); 2];
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 1);
+        assert_eq!((extents[0].logical, extents[0].length), (1024 * 12, 1024));
+        fs.truncate(inode, 0).unwrap();
+        assert_eq!(fs.read_inode(inode).unwrap().blocks[12], 0);
+        fs.truncate(inode, 1024 * 13).unwrap();
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 0);
+        let mut buf = [0xffu8; 8];
+        let read = fs.read_at(inode, 1024 * 12, &mut buf).unwrap();
+        assert_eq!(read, buf.len());
+        assert_eq!(buf, [0u8; 8]);
+    }
+
     #[test]
     fn ext4_init_image() {
         let path = match env::var("AXFS_EXT4_IMAGE") {
@@ -1880,6 +3217,131 @@ mod tests {
         assert_eq!(&buf[..read], &payload[..read]);
     }
 
+    #[test]
+    fn mknod_fifo_and_devices() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let fifo = fs.create(root, "fifo", FileType::Fifo, 0o600).unwrap();
+        let meta = fs.metadata(fifo).unwrap();
+        assert_eq!(meta.file_type, FileType::Fifo);
+        assert_eq!(meta.mode, 0o600);
+        assert_eq!(fs.read_at(fifo, 0, &mut [0u8; 4]), Err(VfsError::Invalid));
+
+        let tty = axvfs::make_dev(4, 64);
+        let char_inode = fs.mknod(root, "ttyS0", FileType::Char, 0o620, tty).unwrap();
+        let meta = fs.metadata(char_inode).unwrap();
+        assert_eq!(meta.file_type, FileType::Char);
+        assert_eq!(meta.rdev, tty);
+
+        let big = axvfs::make_dev(259, 300);
+        let block_inode = fs.mknod(root, "nvme", FileType::Block, 0o660, big).unwrap();
+        assert_eq!(fs.metadata(block_inode).unwrap().rdev, big);
+        assert_eq!(fs.lookup(root, "nvme").unwrap(), Some(block_inode));
+        let sock = fs.mknod(root, "sock", FileType::Socket, 0o755, 0).unwrap();
+        let meta = fs.metadata(sock).unwrap();
+        assert_eq!(meta.file_type, FileType::Socket);
+        assert_eq!(meta.mode, 0o755);
+        assert_eq!(fs.read_at(sock, 0, &mut [0u8; 4]), Err(VfsError::Invalid));
+        assert_eq!(
+            fs.mknod(root, "link", FileType::Symlink, 0o777, 0),
+            Err(VfsError::NotSupported)
+        );
+    }
+
+    #[test]
+    fn mkdir_links_dot_entries() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let links = |inode| {
+            let mut raw = [0u8; 512];
+            fs.read_inode_raw(inode, &mut raw).unwrap();
+            read_u16(&raw, INODE_LINKS_COUNT_OFFSET)
+        };
+        let root_links = links(root);
+        let dir = fs.create(root, "sub", FileType::Dir, 0o755).unwrap();
+        let meta = fs.metadata(dir).unwrap();
+        assert_eq!((meta.file_type, meta.mode), (FileType::Dir, 0o755));
+        assert_eq!(fs.lookup(dir, "..").unwrap(), Some(root));
+        assert_eq!((links(dir), links(root)), (2, root_links + 1));
+
+        let mut entries = [DirEntry::empty(); 4];
+        let count = fs.read_dir(dir, 0, &mut entries).unwrap();
+        let names: Vec<&[u8]> = entries[..count].iter().map(|entry| entry.name()).collect();
+        assert_eq!(names, [&b"."[..], &b".."[..]]);
+
+        let file = fs.create(dir, "f", FileType::File, 0o644).unwrap();
+        assert_eq!(links(file), 1);
+        fs.write_at(file, 0, b"nested").unwrap();
+        let inner = fs.lookup(root, "sub").unwrap().unwrap();
+        assert_eq!(fs.lookup(inner, "f").unwrap(), Some(file));
+    }
+
+    #[test]
+    fn set_owner_and_setuid_mode() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "su", FileType::File, 0o755).unwrap();
+        assert_eq!((fs.metadata(inode).unwrap().uid, fs.metadata(inode).unwrap().gid), (0, 0));
+        fs.set_owner(inode, 70000, 1000).unwrap();
+        fs.set_mode(inode, 0o4750).unwrap();
+        fs.write_at(inode, 0, b"x").unwrap();
+        let meta = fs.metadata(inode).unwrap();
+        assert_eq!((meta.uid, meta.gid), (70000, 1000));
+        assert_eq!(meta.mode, 0o4750);
+        assert_eq!(meta.file_type, FileType::File);
+        assert_eq!(meta.size, 1);
+    }
+
+    #[test]
+    fn statfs_tracks_free_counts() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let sb = &mut data[SUPERBLOCK_OFFSET as usize..SUPERBLOCK_OFFSET as usize + SUPERBLOCK_SIZE];
+        write_u32(sb, SUPERBLOCK_INODES_COUNT_OFFSET, 32);
+        write_u32(sb, SUPERBLOCK_BLOCKS_COUNT_OFFSET, 64);
+        write_u32(sb, SUPERBLOCK_R_BLOCKS_COUNT_OFFSET, 2);
+        write_u32(sb, SUPERBLOCK_FREE_BLOCKS_OFFSET, 50);
+        write_u32(sb, SUPERBLOCK_FREE_INODES_OFFSET, 20);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let stat = fs.statfs().unwrap();
+        assert_eq!(stat.magic, EXT4_SUPER_MAGIC);
+        assert_eq!(stat.block_size, 1024);
+        assert_eq!((stat.blocks, stat.blocks_free, stat.blocks_avail), (64, 50, 48));
+        assert_eq!((stat.files, stat.files_free), (32, 20));
+
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "df", FileType::File, 0o644).unwrap();
+        assert_eq!(fs.statfs().unwrap().files_free, 19);
+        fs.write_at(inode, 0, &[7u8; 3 * 1024]).unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, 47);
+        fs.flush().unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, 47);
+        fs.truncate(inode, 0).unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, 50);
+    }
+
     #[test]
     fn write_indirect_block() {
         let mut data = vec![0u8; 128 * 1024];
@@ -1989,6 +3451,307 @@ mod tests {
         assert_eq!(buf[0], b'a' + (last_idx % 26) as u8);
     }
 
+    #[test]
+    fn truncate_frees_blocks_and_reads_holes() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "shrink", FileType::File, 0o644).unwrap();
+        let before = used_blocks(&fs, &dev);
+        let payload = [b'x'; 8 * 1024];
+        fs.write_at(inode, 0, &payload).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), before + 8);
+
+        fs.truncate(inode, 1500).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), before + 2);
+        fs.truncate(inode, 1 << 30).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), before + 2);
+        assert_eq!(fs.metadata(inode).unwrap().size, 1 << 30);
+
+        let mut buf = [0xffu8; 2048];
+        let read = fs.read_at(inode, 0, &mut buf).unwrap();
+        assert_eq!(read, buf.len());
+        assert!(buf[..1500].iter().all(|&b| b == b'x'));
+        assert!(buf[1500..].iter().all(|&b| b == 0));
+        let read = fs.read_at(inode, (1 << 30) - 16, &mut buf).unwrap();
+        assert_eq!(read, 16);
+        assert!(buf[..16].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn fallocate_unwritten_and_fiemap() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "prealloc", FileType::File, 0o644).unwrap();
+        let before = used_blocks(&fs, &dev);
+        fs.fallocate(inode, FallocMode::Allocate, 0, 4096, false).unwrap();
+        assert_eq!(fs.metadata(inode).unwrap().size, 4096);
+        assert_eq!(used_blocks(&fs, &dev), before + 4);
+        let mut extents = [FileExtent::empty(); 4];
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 1);
+        assert_eq!((extents[0].logical, extents[0].length, extents[0].unwritten), (0, 4096, true));
+
+        let mut buf = [0xffu8; 4096];
+        fs.read_at(inode, 0, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 0));
+
+        fs.write_at(inode, 1030, b"payload").unwrap();
+        assert_eq!(used_blocks(&fs, &dev), before + 4);
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 3);
+        assert_eq!((extents[0].length, extents[0].unwritten), (1024, true));
+        assert_eq!((extents[1].logical, extents[1].length, extents[1].unwritten), (1024, 1024, false));
+        assert_eq!((extents[2].logical, extents[2].length, extents[2].unwritten), (2048, 2048, true));
+        fs.read_at(inode, 1024, &mut buf[..1024]).unwrap();
+        assert_eq!(&buf[6..13], b"payload");
+        assert!(buf[..6].iter().chain(&buf[13..1024]).all(|&b| b == 0));
+
+        fs.fallocate(inode, FallocMode::Allocate, 8192, 1024, true).unwrap();
+        assert_eq!(fs.metadata(inode).unwrap().size, 4096);
+        assert_eq!(fs.fiemap(inode, 4096, &mut extents).unwrap(), 1);
+        assert_eq!(extents[0].logical, 8192);
+
+        fs.fallocate(inode, FallocMode::PunchHole, 0, 2048, true).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), before + 3);
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 2);
+        assert_eq!(extents[0].logical, 2048);
+
+        fs.write_at(inode, 2048, &[b'z'; 2048]).unwrap();
+        fs.fallocate(inode, FallocMode::ZeroRange, 2100, 1500, true).unwrap();
+        fs.read_at(inode, 2048, &mut buf[..2048]).unwrap();
+        assert!(buf[..52].iter().all(|&b| b == b'z'));
+        assert!(buf[52..1552].iter().all(|&b| b == 0));
+        assert!(buf[1552..2048].iter().all(|&b| b == b'z'));
+    }
+
+    #[test]
+    fn copy_range_keeps_holes() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let src = fs.create(root, "sparse", FileType::File, 0o644).unwrap();
+        fs.write_at(src, 0, &[b'a'; 1024]).unwrap();
+        fs.write_at(src, 3072, &[b'b'; 1024]).unwrap();
+        let dst = fs.create(root, "copy", FileType::File, 0o644).unwrap();
+        let before = used_blocks(&fs, &dev);
+        assert_eq!(fs.copy_range(src, 0, dst, 0, 1 << 20).unwrap(), 4096);
+        assert_eq!(fs.metadata(dst).unwrap().size, 4096);
+        assert_eq!(used_blocks(&fs, &dev), before + 2);
+        let mut buf = [0xffu8; 4096];
+        fs.read_at(dst, 0, &mut buf).unwrap();
+        assert!(buf[..1024].iter().all(|&b| b == b'a'));
+        assert!(buf[1024..3072].iter().all(|&b| b == 0));
+        assert!(buf[3072..].iter().all(|&b| b == b'b'));
+
+        // 目标已有数据的区间必须写入零而不是跳过。
+        fs.write_at(dst, 1024, &[b'c'; 1024]).unwrap();
+        assert_eq!(fs.copy_range(src, 1024, dst, 1024, 1024).unwrap(), 1024);
+        fs.read_at(dst, 1024, &mut buf[..1024]).unwrap();
+        assert!(buf[..1024].iter().all(|&b| b == 0));
+
+        // 源文件末尾的空洞也要延长目标文件大小。
+        fs.truncate(src, 8192).unwrap();
+        assert_eq!(fs.copy_range(src, 4096, dst, 4096, 8192).unwrap(), 4096);
+        assert_eq!(fs.metadata(dst).unwrap().size, 8192);
+    }
+
+    #[test]
+    fn punch_sparse_extent_tree() {
+        let mut data = vec![0u8; 1024 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "sparse", FileType::File, 0o644).unwrap();
+        let before = used_blocks(&fs, &dev);
+        let block_size = fs.fs_block_size() as u64;
+        let total = (extent_capacity(block_size as usize) * EXTENT_INODE_CAPACITY + 1) as u64;
+        for idx in 0..total {
+            fs.write_at(inode, idx * 2 * block_size, &[b'a' + (idx % 26) as u8]).unwrap();
+        }
+        fs.flush().unwrap();
+        let depth = fs.read_inode(inode).unwrap().blocks[1] >> 16;
+        assert_eq!(depth, 2);
+
+        fs.fallocate(inode, FallocMode::PunchHole, block_size, total * block_size, true).unwrap();
+        let mut extents = [FileExtent::empty(); 4];
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 4);
+        assert_eq!(extents[0].logical, 0);
+        assert_eq!(extents[1].logical, (total + 1) * block_size);
+        let mut buf = [0xffu8; 1];
+        fs.read_at(inode, 2 * block_size, &mut buf).unwrap();
+        assert_eq!(buf[0], 0);
+        fs.read_at(inode, 0, &mut buf).unwrap();
+        assert_eq!(buf[0], b'a');
+
+        fs.truncate(inode, 0).unwrap();
+        assert_eq!(fs.fiemap(inode, 0, &mut extents).unwrap(), 0);
+        assert_eq!(used_blocks(&fs, &dev), before);
+    }
+
+    #[test]
+    fn goal_directed_block_runs() {
+        let mut data = vec![0u8; 256 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        assert_eq!(fs.allocate_blocks(100, 8).unwrap(), (100, 8));
+        assert_eq!(fs.allocate_blocks(100, 8).unwrap(), (108, 8));
+        fs.free_blocks(102, 2).unwrap();
+        assert_eq!(fs.allocate_blocks(100, 8).unwrap(), (102, 2));
+        // Before the goal only after wrapping past the end of the group.
+        assert_eq!(fs.allocate_blocks(8191, 4).unwrap(), (8191, 1));
+        assert_eq!(fs.allocate_blocks(8191, 4).unwrap(), (7, 4));
+    }
+
+    #[test]
+    fn sequential_writes_stay_in_one_extent() {
+        let mut data = vec![0u8; 512 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let small = fs.create(root, "small", FileType::File, 0o644).unwrap();
+        let large = fs.create(root, "large", FileType::File, 0o644).unwrap();
+        let block_size = fs.fs_block_size() as usize;
+        for idx in 0..48 {
+            let chunk = [b'a' + (idx % 26) as u8; 1024];
+            fs.write_at(small, (idx * block_size) as u64, &chunk[..block_size]).unwrap();
+        }
+        let mut buf = [0u8; 1024];
+        fs.read_at(small, 47 * block_size as u64, &mut buf[..block_size]).unwrap();
+        assert!(buf[..block_size].iter().all(|&b| b == b'a' + 47 % 26));
+        fs.flush().unwrap();
+        let payload = vec![b'L'; 64 * block_size];
+        fs.write_at(large, 0, &payload).unwrap();
+        let mut extents = [FileExtent::empty(); 4];
+        assert_eq!(fs.fiemap(small, 0, &mut extents).unwrap(), 1);
+        assert_eq!(extents[0].length, 48 * block_size as u64);
+        assert_eq!(fs.fiemap(large, 0, &mut extents).unwrap(), 1);
+        assert_eq!(extents[0].length, 64 * block_size as u64);
+        let root_header = inode_extent_raw(&fs.read_inode(large).unwrap());
+        assert_eq!(parse_extent_header(&root_header).unwrap().depth, 0);
+        fs.read_at(large, 63 * block_size as u64, &mut buf[..block_size]).unwrap();
+        assert!(buf[..block_size].iter().all(|&b| b == b'L'));
+    }
+
+    #[test]
+    fn xattr_block_roundtrip() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_for_write(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "pkg", FileType::File, 0o644).unwrap();
+        let baseline = used_blocks(&fs, &dev);
+        let mut buf = [0u8; 64];
+        assert_eq!(fs.getxattr(inode, "user.mime", &mut buf), Err(VfsError::NotFound));
+        fs.setxattr(inode, "trusted.origin", b"mirror", XattrMode::Any).unwrap();
+        fs.setxattr(inode, "user.mime", b"text/plain", XattrMode::Create).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), baseline + 1);
+        assert_eq!(
+            fs.setxattr(inode, "user.mime", b"x", XattrMode::Create),
+            Err(VfsError::AlreadyExists)
+        );
+        assert_eq!(
+            fs.setxattr(inode, "user.other", b"x", XattrMode::Replace),
+            Err(VfsError::NotFound)
+        );
+        assert_eq!(
+            fs.setxattr(inode, "bogus.name", b"x", XattrMode::Any),
+            Err(VfsError::NotSupported)
+        );
+        fs.setxattr(inode, "user.mime", b"application/x-tar", XattrMode::Replace).unwrap();
+        let len = fs.getxattr(inode, "user.mime", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"application/x-tar");
+        assert_eq!(fs.getxattr(inode, "user.mime", &mut buf[..4]).unwrap(), len);
+        let len = fs.listxattr(inode, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"user.mime\0trusted.origin\0");
+        assert_eq!(fs.listxattr(inode, &mut []).unwrap(), len);
+
+        fs.flush().unwrap();
+        let acl = inode_file_acl_of(&fs, inode) as usize;
+        {
+            let data = dev.data.borrow();
+            let block = &data[acl * 1024..(acl + 1) * 1024];
+            assert_eq!(read_u32(block, 0), XATTR_MAGIC);
+            assert_eq!(read_u32(block, XATTR_BLOCK_HASH_OFFSET), xattr_block_hash(block));
+        }
+
+        fs.removexattr(inode, "user.mime").unwrap();
+        assert_eq!(fs.removexattr(inode, "user.mime"), Err(VfsError::NotFound));
+        fs.removexattr(inode, "trusted.origin").unwrap();
+        assert_eq!(fs.listxattr(inode, &mut buf).unwrap(), 0);
+        assert_eq!(used_blocks(&fs, &dev), baseline);
+    }
+
+    #[test]
+    fn xattr_in_inode_then_block() {
+        let mut data = vec![0u8; 64 * 1024];
+        build_ext4_large_inodes(&mut data);
+        let dev = FileBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Ext4Fs::new(&dev).unwrap();
+        let root = fs.root().unwrap();
+        let inode = fs.create(root, "pkg", FileType::File, 0o644).unwrap();
+        let baseline = used_blocks(&fs, &dev);
+        fs.setxattr(inode, "security.selinux", b"s0", XattrMode::Any).unwrap();
+        fs.setxattr(inode, "system.posix_acl_access", &[2, 0, 0, 0], XattrMode::Any)
+            .unwrap();
+        assert_eq!(used_blocks(&fs, &dev), baseline);
+        assert_eq!(inode_file_acl_of(&fs, inode), 0);
+
+        let big = [0x5au8; 200];
+        fs.setxattr(inode, "user.blob", &big, XattrMode::Any).unwrap();
+        assert_eq!(used_blocks(&fs, &dev), baseline + 1);
+        let mut buf = [0u8; 256];
+        let len = fs.getxattr(inode, "user.blob", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &big[..]);
+        let len = fs.getxattr(inode, "system.posix_acl_access", &mut buf).unwrap();
+        assert_eq!(&buf[..len], &[2, 0, 0, 0]);
+        let len = fs.listxattr(inode, &mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"system.posix_acl_access\0security.selinux\0user.blob\0"
+        );
+
+        // 缩短后的值回到 inode 内，外部块随之释放。
+        fs.setxattr(inode, "user.blob", b"small", XattrMode::Replace).unwrap();
+        assert_eq!(inode_file_acl_of(&fs, inode), 0);
+        assert_eq!(used_blocks(&fs, &dev), baseline);
+        let len = fs.getxattr(inode, "user.blob", &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"small");
+    }
+
     fn build_minimal_ext4(buf: &mut [u8], file_data: &[u8]) {
         const BLOCK_SIZE: usize = 1024;
         const BLOCK_BITMAP_BLOCK: usize = 3;
@@ -2324,4 +4087,33 @@ mod tests {
         let offset = bit % 8;
         buf[byte] |= 1u8 << offset;
     }
+
+    fn used_blocks(fs: &Ext4Fs<'_>, dev: &FileBlockDevice) -> usize {
+        fs.flush().unwrap();
+        let data = dev.data.borrow();
+        data[3 * 1024..4 * 1024].iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    fn build_ext4_large_inodes(buf: &mut [u8]) {
+        const LARGE_INODE_SIZE: usize = 256;
+        const INODE_TABLE_BLOCK: usize = 5;
+
+        build_ext4_for_write(buf);
+        let sb = SUPERBLOCK_OFFSET as usize;
+        write_u32(&mut buf[sb..], SUPERBLOCK_INODES_PER_GROUP_OFFSET, 4);
+        write_u16(&mut buf[sb..], SUPERBLOCK_INODE_SIZE_OFFSET, LARGE_INODE_SIZE as u16);
+        let table = INODE_TABLE_BLOCK * 1024;
+        let old_root = table + TEST_INODE_SIZE;
+        let new_root = table + LARGE_INODE_SIZE;
+        buf.copy_within(old_root..old_root + TEST_INODE_SIZE, new_root);
+        buf[old_root..new_root].fill(0);
+        buf[new_root + TEST_INODE_SIZE..new_root + LARGE_INODE_SIZE].fill(0);
+        write_u16(&mut buf[new_root..], INODE_EXTRA_ISIZE_OFFSET, EXT4_NEW_EXTRA_ISIZE);
+    }
+
+    fn inode_file_acl_of(fs: &Ext4Fs<'_>, inode: InodeId) -> u64 {
+        let mut raw = [0u8; 512];
+        fs.read_inode_raw(inode, &mut raw).unwrap();
+        inode_file_acl(&raw)
+    }
 }
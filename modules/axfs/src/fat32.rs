@@ -1,6 +1,8 @@
 //! FAT32 filesystem implementation.
 
-use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult, MAX_NAME_LEN};
+use axvfs::{
+    DirEntry, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult, MAX_NAME_LEN, MSDOS_SUPER_MAGIC,
+};
 use core::cell::UnsafeCell;
 use core::hint::spin_loop;
 use core::sync::atomic::{AtomicBool, Ordering};
@@ -20,6 +22,14 @@ const BPB_FAT_SIZE_16_OFFSET: usize = 22;
 const BPB_TOTAL_SECTORS_32_OFFSET: usize = 32;
 const BPB_FAT_SIZE_32_OFFSET: usize = 36;
 const BPB_ROOT_CLUSTER_OFFSET: usize = 44;
+const BPB_FS_INFO_OFFSET: usize = 48;
+const FSINFO_LEAD_SIG_OFFSET: usize = 0;
+const FSINFO_LEAD_SIG: u32 = 0x4161_5252;
+const FSINFO_STRUCT_SIG_OFFSET: usize = 484;
+const FSINFO_STRUCT_SIG: u32 = 0x6141_7272;
+const FSINFO_FREE_COUNT_OFFSET: usize = 488;
+const FSINFO_NEXT_FREE_OFFSET: usize = 492;
+const FSINFO_UNKNOWN: u32 = 0xffff_ffff;
 const DIR_ENTRY_SIZE: usize = 32;
 const FAT_ENTRY_SIZE: usize = 4;
 const FAT32_EOC_MIN: u32 = 0x0fff_fff8;
@@ -95,6 +105,8 @@ pub struct Bpb {
     pub sectors_per_fat: u32,
     /// Root directory cluster.
     pub root_cluster: u32,
+    /// FSInfo sector number (0 or 0xffff when absent).
+    pub fs_info_sector: u16,
 }
 
 impl Bpb {
@@ -147,6 +159,7 @@ impl Bpb {
             buf[BPB_ROOT_CLUSTER_OFFSET + 2],
             buf[BPB_ROOT_CLUSTER_OFFSET + 3],
         ]);
+        let fs_info_sector = read_u16(buf, BPB_FS_INFO_OFFSET);
         let total_sectors = if total_sectors_16 != 0 {
             total_sectors_16 as u32
         } else {
@@ -176,6 +189,7 @@ impl Bpb {
             total_sectors,
             sectors_per_fat,
             root_cluster,
+            fs_info_sector,
         })
     }
 
@@ -210,6 +224,17 @@ fn inode_is_dir(inode: InodeId) -> bool {
     (inode & INODE_DIR_FLAG) != 0
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Free cluster hint from FSInfo next to the count obtained by scanning the FAT.
+pub struct FatUsage {
+    /// Data clusters on the volume.
+    pub clusters: u32,
+    /// FSInfo free cluster count, when present and plausible.
+    pub fs_info_free: Option<u32>,
+    /// Zero entries found in the first FAT.
+    pub scanned_free: u32,
+}
+
 /// FAT32 filesystem backed by a block device.
 pub struct Fat32Fs<'a> {
     cache: BlockCache<'a>,
@@ -345,6 +370,15 @@ impl<'a> Fat32Fs<'a> {
             + cluster_index * self.bpb.sectors_per_cluster as u32
     }
 
+    /// Compare the FSInfo free cluster hint with a full FAT scan.
+    pub fn usage(&self) -> VfsResult<FatUsage> {
+        Ok(FatUsage {
+            clusters: self.cluster_count(),
+            fs_info_free: self.fs_info_free_count()?,
+            scanned_free: self.scan_free_clusters()?,
+        })
+    }
+
     /// Read a sector into the provided buffer.
     pub fn read_sector(&self, sector: BlockId, buf: &mut [u8]) -> VfsResult<()> {
         self.cache.read_block(sector, buf)
@@ -458,12 +492,99 @@ impl<'a> Fat32Fs<'a> {
             if self.read_fat_entry(cluster)? == 0 {
                 self.write_fat_entry(cluster, 0x0fff_ffff)?;
                 self.zero_cluster(cluster)?;
+                self.note_cluster_allocated(cluster)?;
                 return Ok(cluster);
             }
         }
         Err(VfsError::NoMem)
     }
 
+    fn fs_info_sector(&self) -> Option<u32> {
+        let sector = self.bpb.fs_info_sector;
+        if sector == 0 || sector == 0xffff || sector >= self.bpb.reserved_sectors {
+            return None;
+        }
+        let bytes_per_sector = self.bytes_per_sector();
+        if !(BPB_SIZE..=FAT_SCRATCH_SIZE).contains(&bytes_per_sector) {
+            return None;
+        }
+        Some(sector as u32)
+    }
+
+    /// Free cluster count recorded in FSInfo, if the sector is present and the hint is plausible.
+    fn fs_info_free_count(&self) -> VfsResult<Option<u32>> {
+        let Some(sector) = self.fs_info_sector() else {
+            return Ok(None);
+        };
+        let bytes_per_sector = self.bytes_per_sector();
+        let guard = FAT_SCRATCH.lock();
+        let scratch = guard.get_mut();
+        self.read_sector(sector as BlockId, &mut scratch[..bytes_per_sector])?;
+        if read_u32(scratch, FSINFO_LEAD_SIG_OFFSET) != FSINFO_LEAD_SIG
+            || read_u32(scratch, FSINFO_STRUCT_SIG_OFFSET) != FSINFO_STRUCT_SIG
+        {
+            return Ok(None);
+        }
+        let free = read_u32(scratch, FSINFO_FREE_COUNT_OFFSET);
+        if free == FSINFO_UNKNOWN || free > self.cluster_count() {
+            return Ok(None);
+        }
+        Ok(Some(free))
+    }
+
+    /// Keep the FSInfo free count and next-free hint in step with an allocation.
+    fn note_cluster_allocated(&self, cluster: u32) -> VfsResult<()> {
+        let Some(sector) = self.fs_info_sector() else {
+            return Ok(());
+        };
+        let bytes_per_sector = self.bytes_per_sector();
+        let guard = FAT_SCRATCH.lock();
+        let scratch = guard.get_mut();
+        self.read_sector(sector as BlockId, &mut scratch[..bytes_per_sector])?;
+        if read_u32(scratch, FSINFO_LEAD_SIG_OFFSET) != FSINFO_LEAD_SIG
+            || read_u32(scratch, FSINFO_STRUCT_SIG_OFFSET) != FSINFO_STRUCT_SIG
+        {
+            return Ok(());
+        }
+        let free = read_u32(scratch, FSINFO_FREE_COUNT_OFFSET);
+        if free != FSINFO_UNKNOWN && free != 0 {
+            write_u32(scratch, FSINFO_FREE_COUNT_OFFSET, free - 1);
+        }
+        write_u32(scratch, FSINFO_NEXT_FREE_OFFSET, cluster + 1);
+        self.write_sector(sector as BlockId, &scratch[..bytes_per_sector])
+    }
+
+    fn cluster_count(&self) -> u32 {
+        self.max_cluster().saturating_sub(1)
+    }
+
+    /// Count free clusters by walking the first FAT one sector at a time.
+    fn scan_free_clusters(&self) -> VfsResult<u32> {
+        let bytes_per_sector = self.bytes_per_sector();
+        if bytes_per_sector == 0 || bytes_per_sector > FAT_SCRATCH_SIZE {
+            return Err(VfsError::Invalid);
+        }
+        let entries_per_sector = (bytes_per_sector / FAT_ENTRY_SIZE) as u32;
+        let max_cluster = self.max_cluster();
+        let guard = FAT_SCRATCH.lock();
+        let scratch = guard.get_mut();
+        let mut free = 0u32;
+        let mut loaded = u32::MAX;
+        for cluster in 2..=max_cluster {
+            let sector_index = cluster / entries_per_sector;
+            if sector_index != loaded {
+                let sector = self.bpb.fat_start_sector() + sector_index;
+                self.read_sector(sector as BlockId, &mut scratch[..bytes_per_sector])?;
+                loaded = sector_index;
+            }
+            let offset = (cluster % entries_per_sector) as usize * FAT_ENTRY_SIZE;
+            if read_u32(scratch, offset) & 0x0fff_ffff == 0 {
+                free += 1;
+            }
+        }
+        Ok(free)
+    }
+
     fn write_cluster_bytes(&self, cluster: u32, offset: usize, buf: &[u8]) -> VfsResult<usize> {
         let cluster_size = self.cluster_size();
         if offset >= cluster_size || buf.is_empty() {
@@ -915,10 +1036,9 @@ fn write_dir_entry(dir: &mut [u8], index: usize, name: &[u8], attr: u8, cluster:
     entry.fill(0);
     if name.len() == 11 {
         entry[..11].copy_from_slice(name);
-    } else if name == b"." {
-        entry[..1].copy_from_slice(b".");
-    } else if name == b".." {
-        entry[..2].copy_from_slice(b"..");
+    } else if name == b"." || name == b".." {
+        entry[..11].fill(b' ');
+        entry[..name.len()].copy_from_slice(name);
     } else {
         entry[..core::cmp::min(name.len(), 11)].copy_from_slice(&name[..core::cmp::min(name.len(), 11)]);
     }
@@ -967,8 +1087,8 @@ impl VfsOps for Fat32Fs<'_> {
             FileType::File
         };
         let size = self.inode_actual_size(inode)? as u64;
-        let mode = if file_type == FileType::Dir { 0o755 } else { 0o644 };
-        Ok(Metadata::new(file_type, size, mode))
+        // FAT has no permission bits; match the Linux vfat default (fmask=022) so images stay executable.
+        Ok(Metadata::new(file_type, size, 0o755))
     }
 
     fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
@@ -1210,6 +1330,19 @@ impl VfsOps for Fat32Fs<'_> {
     fn flush(&self) -> VfsResult<()> {
         self.cache.flush()
     }
+
+    fn statfs(&self) -> VfsResult<StatFs> {
+        let free = match self.fs_info_free_count()? {
+            Some(free) => free,
+            None => self.scan_free_clusters()?,
+        };
+        let mut stat = StatFs::empty(MSDOS_SUPER_MAGIC);
+        stat.block_size = self.cluster_size() as u32;
+        stat.blocks = self.cluster_count() as u64;
+        stat.blocks_free = free as u64;
+        stat.blocks_avail = free as u64;
+        Ok(stat)
+    }
 }
 
 #[cfg(test)]
@@ -1454,4 +1587,24 @@ mod tests {
         assert_eq!(read, 1);
         assert_eq!(&out[..read], &payload[..1]);
     }
+
+    #[test]
+    fn statfs_counts_free_clusters() {
+        let mut data = [0u8; IMAGE_SIZE];
+        build_minimal_image(&mut data, "init", b"init-data").unwrap();
+        let dev = TestBlockDevice {
+            block_size: 512,
+            data: RefCell::new(data),
+        };
+        let fs = Fat32Fs::new(&dev).unwrap();
+        let stat = fs.statfs().unwrap();
+        assert_eq!(stat.magic, MSDOS_SUPER_MAGIC);
+        assert_eq!(stat.block_size, 512);
+        assert_eq!(stat.blocks, 11);
+        assert_eq!(stat.blocks_free, 8);
+        let root = fs.root().unwrap();
+        let inode = fs.lookup(root, "fatlog.txt").unwrap().unwrap();
+        fs.write_at(inode, 0, &[0x5a_u8; 1024]).unwrap();
+        assert_eq!(fs.statfs().unwrap().blocks_free, 7);
+    }
 }
@@ -3,14 +3,20 @@
 //! Filesystem implementations and VFS integration.
 
 pub mod block;
+pub mod dcache;
 pub mod devfs;
 pub mod fat32;
 pub mod ext4;
 pub mod memfs;
 pub mod mount;
+pub mod partition;
 pub mod procfs;
 
-pub use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult, MAX_NAME_LEN};
+pub use axvfs::{
+    Credentials, DirEntry, FallocMode, FileExtent, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult,
+    XattrMode, ACCESS_EXEC, ACCESS_READ, ACCESS_WRITE, EXT4_SUPER_MAGIC, MAX_NAME_LEN, MSDOS_SUPER_MAGIC,
+    PROC_SUPER_MAGIC, TMPFS_MAGIC,
+};
 
 #[cfg(test)]
 extern crate std;
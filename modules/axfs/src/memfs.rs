@@ -5,7 +5,7 @@ use core::cmp::min;
 use core::hint::spin_loop;
 use core::sync::atomic::{AtomicBool, Ordering};
 
-use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult};
+use axvfs::{DirEntry, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult, TMPFS_MAGIC};
 
 /// Root inode identifier for memfs.
 pub const ROOT_ID: InodeId = 1;
@@ -158,7 +158,7 @@ const NODES: [Node; 8] = [
         parent: ROOT_ID,
         name: "init",
         file_type: FileType::File,
-        mode: 0o444,
+        mode: 0o555,
     },
     Node {
         id: PROC_ID,
@@ -478,6 +478,16 @@ impl<'a> VfsOps for MemFs<'a> {
         };
         fill_dir_entries(list, offset, entries)
     }
+
+    fn statfs(&self) -> VfsResult<StatFs> {
+        // Fixed node table: the embedded /init and the /tmp log buffer are all the space there is.
+        let mut stat = StatFs::empty(TMPFS_MAGIC);
+        let block_size = stat.block_size as u64;
+        let bytes = self.init_image.map_or(0, |image| image.len()) + TMP_LOG_SIZE;
+        stat.blocks = (bytes as u64 + block_size - 1) / block_size;
+        stat.files = NODES.len() as u64;
+        Ok(stat)
+    }
 }
 
 fn fill_dir_entries(list: &[DirEntrySpec], offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
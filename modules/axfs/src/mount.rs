@@ -1,10 +1,21 @@
 //! Mount table and path resolution helpers.
 
-use axvfs::{FileType, InodeId, VfsError, VfsOps, VfsResult};
+use axvfs::{Credentials, FileType, InodeId, VfsError, VfsOps, VfsResult, ACCESS_EXEC};
 
 /// Maximum path traversal depth to avoid unbounded recursion.
 pub const MAX_PATH_DEPTH: usize = 64;
 
+/// Mount is read-only (`statfs.f_flags` bit values).
+pub const ST_RDONLY: u32 = 0x0001;
+/// Set-user-ID and set-group-ID bits are ignored on exec.
+pub const ST_NOSUID: u32 = 0x0002;
+/// Device nodes may not be opened.
+pub const ST_NODEV: u32 = 0x0004;
+/// Programs may not be executed.
+pub const ST_NOEXEC: u32 = 0x0008;
+/// Access times are only updated relative to mtime/ctime.
+pub const ST_RELATIME: u32 = 0x1000;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 /// Known mount identifiers.
 pub enum MountId {
@@ -21,30 +32,45 @@ pub struct MountPoint<'a> {
     pub path: &'a str,
     /// Filesystem instance for this mount.
     pub fs: &'a dyn VfsOps,
+    /// `ST_*` mount flags.
+    pub flags: u32,
 }
 
 impl<'a> MountPoint<'a> {
     /// Create a mount point definition.
     pub fn new(id: MountId, path: &'a str, fs: &'a dyn VfsOps) -> Self {
-        Self { id, path, fs }
+        Self { id, path, fs, flags: 0 }
+    }
+
+    /// Set the `ST_*` mount flags.
+    pub fn with_flags(mut self, flags: u32) -> Self {
+        self.flags = flags;
+        self
     }
 }
 
 /// Fixed-size mount table for resolving paths to filesystems.
 pub struct MountTable<'a, const N: usize> {
     mounts: [MountPoint<'a>; N],
+    creds: Option<Credentials<'a>>,
 }
 
 impl<'a, const N: usize> MountTable<'a, N> {
     /// Create a new mount table.
     pub fn new(mounts: [MountPoint<'a>; N]) -> Self {
-        Self { mounts }
+        Self { mounts, creds: None }
+    }
+
+    /// Require search permission for `creds` on every directory walked during resolution.
+    pub fn with_credentials(mut self, creds: Credentials<'a>) -> Self {
+        self.creds = Some(creds);
+        self
     }
 
     /// Resolve a path to the mount and inode.
     pub fn resolve_path(&self, path: &str) -> VfsResult<(MountId, InodeId)> {
         let (mount, rel) = self.find_mount(path)?;
-        let inode = resolve_path_fs(mount.fs, rel)?;
+        let inode = resolve_path_fs(mount.fs, rel, self.creds.as_ref())?;
         Ok((mount.id, inode))
     }
 
@@ -65,7 +91,7 @@ impl<'a, const N: usize> MountTable<'a, N> {
         }
         let parent_path = if parent.is_empty() { "/" } else { parent };
         let (mount, rel) = self.find_mount(parent_path)?;
-        let inode = resolve_path_fs(mount.fs, rel)?;
+        let inode = resolve_path_fs(mount.fs, rel, self.creds.as_ref())?;
         Ok((mount.id, inode, name))
     }
 
@@ -74,6 +100,11 @@ impl<'a, const N: usize> MountTable<'a, N> {
         self.mounts.iter().find(|mount| mount.id == id).map(|mount| mount.fs)
     }
 
+    /// Return the `ST_*` flags of the given mount id.
+    pub fn flags_for(&self, id: MountId) -> u32 {
+        self.mounts.iter().find(|mount| mount.id == id).map_or(0, |mount| mount.flags)
+    }
+
     /// Flush all mounted filesystems.
     pub fn flush_all(&self) -> VfsResult<()> {
         for mount in &self.mounts {
@@ -118,7 +149,7 @@ fn match_mount_path<'a>(mount_path: &str, path: &'a str) -> Option<&'a str> {
     None
 }
 
-fn resolve_path_fs(fs: &dyn VfsOps, path: &str) -> VfsResult<InodeId> {
+fn resolve_path_fs(fs: &dyn VfsOps, path: &str, creds: Option<&Credentials<'_>>) -> VfsResult<InodeId> {
     let root = fs.root()?;
     if path.is_empty() || path == "/" {
         return Ok(root);
@@ -141,6 +172,9 @@ fn resolve_path_fs(fs: &dyn VfsOps, path: &str) -> VfsResult<InodeId> {
         if meta.file_type != FileType::Dir {
             return Err(VfsError::NotDir);
         }
+        if creds.is_some_and(|creds| !creds.may_access(&meta, ACCESS_EXEC)) {
+            return Err(VfsError::Permission);
+        }
         let next = fs.lookup(current, segment)?.ok_or(VfsError::NotFound)?;
         current = next;
         if depth >= MAX_PATH_DEPTH {
@@ -190,6 +224,27 @@ mod tests {
         assert_eq!(inode, procfs::ROOT_ID);
     }
 
+    #[test]
+    fn per_mount_statfs_and_flags() {
+        use axvfs::{PROC_SUPER_MAGIC, TMPFS_MAGIC};
+
+        let rootfs = memfs::MemFs::new();
+        let devfs = devfs::DevFs::new();
+        let procfs = procfs::ProcFs::new();
+        let mounts = MountTable::new([
+            MountPoint::new(MountId::Root, "/", &rootfs).with_flags(ST_RDONLY),
+            MountPoint::new(MountId::Dev, "/dev", &devfs).with_flags(ST_NOSUID),
+            MountPoint::new(MountId::Proc, "/proc", &procfs).with_flags(ST_NOSUID | ST_NODEV | ST_NOEXEC),
+        ]);
+        let (mount, _) = mounts.resolve_path("/proc").unwrap();
+        assert_eq!(mounts.fs_for(mount).unwrap().statfs().unwrap().magic, PROC_SUPER_MAGIC);
+        assert_eq!(mounts.flags_for(mount), ST_NOSUID | ST_NODEV | ST_NOEXEC);
+        let (mount, _) = mounts.resolve_path("/dev/shm").unwrap();
+        assert_eq!(mounts.fs_for(mount).unwrap().statfs().unwrap().magic, TMPFS_MAGIC);
+        assert_eq!(mounts.flags_for(mount), ST_NOSUID);
+        assert_eq!(mounts.flags_for(MountId::Root), ST_RDONLY);
+    }
+
     #[test]
     fn resolve_parent_paths() {
         let rootfs = memfs::MemFs::new();
@@ -209,4 +264,54 @@ mod tests {
         assert_eq!(parent, memfs::ROOT_ID);
         assert_eq!(name, "proc");
     }
+
+    #[test]
+    fn search_denied_on_private_directory() {
+        use axvfs::{Credentials, DirEntry, Metadata};
+
+        struct PrivateFs;
+
+        impl VfsOps for PrivateFs {
+            fn root(&self) -> VfsResult<InodeId> {
+                Ok(1)
+            }
+            fn lookup(&self, parent: InodeId, name: &str) -> VfsResult<Option<InodeId>> {
+                Ok((parent == 1 && name == "file").then_some(2))
+            }
+            fn create(&self, _parent: InodeId, _name: &str, _kind: FileType, _mode: u16) -> VfsResult<InodeId> {
+                Err(VfsError::NotSupported)
+            }
+            fn remove(&self, _parent: InodeId, _name: &str) -> VfsResult<()> {
+                Err(VfsError::NotSupported)
+            }
+            fn metadata(&self, inode: InodeId) -> VfsResult<Metadata> {
+                match inode {
+                    1 => Ok(Metadata::new(FileType::Dir, 0, 0o750).with_owner(1000, 100)),
+                    _ => Ok(Metadata::new(FileType::File, 0, 0o644).with_owner(1000, 100)),
+                }
+            }
+            fn read_at(&self, _inode: InodeId, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
+                Ok(0)
+            }
+            fn write_at(&self, _inode: InodeId, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
+                Err(VfsError::NotSupported)
+            }
+            fn read_dir(&self, _inode: InodeId, _offset: usize, _entries: &mut [DirEntry]) -> VfsResult<usize> {
+                Ok(0)
+            }
+        }
+
+        let fs = PrivateFs;
+        let lookup = |creds: Credentials<'_>| {
+            MountTable::new([MountPoint::new(MountId::Root, "/", &fs)])
+                .with_credentials(creds)
+                .resolve_path("/file")
+        };
+        assert_eq!(lookup(Credentials::new(1000, 1000, &[])), Ok((MountId::Root, 2)));
+        assert_eq!(lookup(Credentials::new(2000, 2000, &[100])), Ok((MountId::Root, 2)));
+        assert_eq!(lookup(Credentials::new(3000, 3000, &[])), Err(VfsError::Permission));
+        assert_eq!(lookup(Credentials::new(0, 0, &[])), Ok((MountId::Root, 2)));
+        let unchecked = MountTable::new([MountPoint::new(MountId::Root, "/", &fs)]);
+        assert_eq!(unchecked.resolve_parent("/file"), Ok((MountId::Root, 1, "file")));
+    }
 }
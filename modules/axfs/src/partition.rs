@@ -0,0 +1,565 @@
+//! MBR/GPT partition table parsing and partition block devices.
+
+use axvfs::{VfsError, VfsResult};
+
+use crate::block::{BlockDevice, BlockId};
+
+/// Maximum number of partitions reported for a single disk.
+pub const MAX_PARTITIONS: usize = 16;
+
+const SECTOR_SIZE: usize = 512;
+const MBR_SIGNATURE_OFFSET: usize = 510;
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+const MBR_TABLE_OFFSET: usize = 446;
+const MBR_ENTRY_SIZE: usize = 16;
+const MBR_PRIMARY_ENTRIES: usize = 4;
+const MBR_TYPE_EMPTY: u8 = 0x00;
+const MBR_TYPE_GPT_PROTECTIVE: u8 = 0xee;
+const MBR_FIRST_LOGICAL_INDEX: u32 = 5;
+const MBR_MAX_LOGICAL: usize = 64;
+
+const GPT_HEADER_LBA: u64 = 1;
+const GPT_SIGNATURE: &[u8; 8] = b"EFI PART";
+const GPT_HEADER_MIN_SIZE: usize = 92;
+const GPT_HEADER_CRC_OFFSET: usize = 16;
+const GPT_HEADER_SIZE_OFFSET: usize = 12;
+const GPT_FIRST_USABLE_OFFSET: usize = 40;
+const GPT_LAST_USABLE_OFFSET: usize = 48;
+const GPT_ENTRIES_LBA_OFFSET: usize = 72;
+const GPT_ENTRY_COUNT_OFFSET: usize = 80;
+const GPT_ENTRY_SIZE_OFFSET: usize = 84;
+const GPT_ENTRY_MIN_SIZE: usize = 128;
+const GPT_ENTRY_FIRST_LBA_OFFSET: usize = 32;
+const GPT_ENTRY_LAST_LBA_OFFSET: usize = 40;
+const GPT_MAX_ENTRIES: u32 = 128;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Partition table format detected on a disk.
+pub enum TableKind {
+    Mbr,
+    Gpt,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// A single partition described in device blocks.
+pub struct Partition {
+    /// 1-based partition number (`vda1` is 1; MBR logical partitions start at 5).
+    pub number: u32,
+    /// First device block of the partition.
+    pub start: BlockId,
+    /// Number of device blocks in the partition.
+    pub blocks: u64,
+}
+
+impl Partition {
+    const fn empty() -> Self {
+        Self {
+            number: 0,
+            start: 0,
+            blocks: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Partitions discovered on a disk, in on-disk order.
+pub struct PartitionTable {
+    kind: TableKind,
+    entries: [Partition; MAX_PARTITIONS],
+    len: usize,
+}
+
+impl PartitionTable {
+    const fn new(kind: TableKind) -> Self {
+        Self {
+            kind,
+            entries: [Partition::empty(); MAX_PARTITIONS],
+            len: 0,
+        }
+    }
+
+    /// Return the detected table format.
+    pub fn kind(&self) -> TableKind {
+        self.kind
+    }
+
+    /// Return the discovered partitions.
+    pub fn partitions(&self) -> &[Partition] {
+        &self.entries[..self.len]
+    }
+
+    /// Find a partition by its 1-based number.
+    pub fn find(&self, number: u32) -> Option<Partition> {
+        self.partitions().iter().copied().find(|part| part.number == number)
+    }
+
+    fn push(&mut self, part: Partition) {
+        if self.len < MAX_PARTITIONS && part.blocks != 0 {
+            self.entries[self.len] = part;
+            self.len += 1;
+        }
+    }
+}
+
+/// Scan a disk for an MBR or GPT partition table.
+///
+/// Table LBAs are interpreted in device blocks and the disk holds `blocks`
+/// of them. Returns `Ok(None)` for whole-disk filesystem images without a
+/// partition table.
+pub fn scan(device: &dyn BlockDevice, blocks: u64) -> VfsResult<Option<PartitionTable>> {
+    let block_size = device.block_size();
+    if !(SECTOR_SIZE..=4096).contains(&block_size) || block_size % SECTOR_SIZE != 0 {
+        return Err(VfsError::Invalid);
+    }
+    let mut buf = [0u8; 4096];
+    let sector = &mut buf[..block_size];
+    device.read_block(0, sector)?;
+    if sector[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2] != MBR_SIGNATURE {
+        return Ok(None);
+    }
+    let mut primary = [MbrEntry::default(); MBR_PRIMARY_ENTRIES];
+    for (idx, entry) in primary.iter_mut().enumerate() {
+        *entry = MbrEntry::parse(&sector[MBR_TABLE_OFFSET + idx * MBR_ENTRY_SIZE..]);
+    }
+    if primary.iter().any(|entry| entry.kind == MBR_TYPE_GPT_PROTECTIVE) {
+        if let Some(table) = scan_gpt(device, blocks)? {
+            return Ok(Some(table));
+        }
+    }
+    // FAT boot sectors carry 0x55aa too; treat them as whole-disk images.
+    if looks_like_fat_boot_sector(sector) || !primary.iter().any(|entry| entry.is_valid()) {
+        return Ok(None);
+    }
+    let mut table = PartitionTable::new(TableKind::Mbr);
+    for (idx, entry) in primary.iter().enumerate() {
+        if !entry.is_valid() || entry.kind == MBR_TYPE_GPT_PROTECTIVE {
+            continue;
+        }
+        if entry.is_extended() {
+            if (entry.lba_start as u64) < blocks {
+                scan_ebr_chain(device, entry.lba_start as u64, blocks, &mut table)?;
+            }
+            continue;
+        }
+        if let Some(part) = mbr_partition(idx as u32 + 1, entry.lba_start as u64, entry.sectors, blocks) {
+            table.push(part);
+        }
+    }
+    Ok(Some(table))
+}
+
+/// An MBR partition of `sectors` blocks at `start`, unless it runs past the `blocks`-long disk.
+fn mbr_partition(number: u32, start: u64, sectors: u32, blocks: u64) -> Option<Partition> {
+    let end = start.checked_add(sectors as u64)?;
+    (end <= blocks).then_some(Partition {
+        number,
+        start,
+        blocks: sectors as u64,
+    })
+}
+
+fn looks_like_fat_boot_sector(sector: &[u8]) -> bool {
+    let jump = sector[0] == 0xeb || sector[0] == 0xe9;
+    jump && (&sector[0x36..0x39] == b"FAT" || &sector[0x52..0x57] == b"FAT32")
+}
+
+fn scan_ebr_chain(
+    device: &dyn BlockDevice,
+    extended_start: u64,
+    blocks: u64,
+    table: &mut PartitionTable,
+) -> VfsResult<()> {
+    let block_size = device.block_size();
+    let mut buf = [0u8; 4096];
+    let mut ebr_lba = extended_start;
+    let mut number = MBR_FIRST_LOGICAL_INDEX;
+    for _ in 0..MBR_MAX_LOGICAL {
+        let ebr = &mut buf[..block_size];
+        device.read_block(ebr_lba, ebr)?;
+        if ebr[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2] != MBR_SIGNATURE {
+            break;
+        }
+        let logical = MbrEntry::parse(&ebr[MBR_TABLE_OFFSET..]);
+        let next = MbrEntry::parse(&ebr[MBR_TABLE_OFFSET + MBR_ENTRY_SIZE..]);
+        if logical.is_valid() {
+            // Logical partitions are relative to their own EBR.
+            let start = ebr_lba + logical.lba_start as u64;
+            if let Some(part) = mbr_partition(number, start, logical.sectors, blocks) {
+                table.push(part);
+            }
+            number += 1;
+        }
+        if !next.is_valid() {
+            break;
+        }
+        // Chain links are relative to the start of the extended partition.
+        // A link off the end of the disk ends the chain; the partitions found so far stand.
+        let next_lba = extended_start + next.lba_start as u64;
+        if next_lba <= ebr_lba || next_lba >= blocks {
+            break;
+        }
+        ebr_lba = next_lba;
+    }
+    Ok(())
+}
+
+fn scan_gpt(device: &dyn BlockDevice, blocks: u64) -> VfsResult<Option<PartitionTable>> {
+    let block_size = device.block_size();
+    let mut buf = [0u8; 4096];
+    let header = &mut buf[..block_size];
+    device.read_block(GPT_HEADER_LBA, header)?;
+    if &header[..8] != GPT_SIGNATURE {
+        return Ok(None);
+    }
+    let header_size = read_u32(header, GPT_HEADER_SIZE_OFFSET) as usize;
+    if header_size < GPT_HEADER_MIN_SIZE || header_size > block_size {
+        return Ok(None);
+    }
+    let expected_crc = read_u32(header, GPT_HEADER_CRC_OFFSET);
+    header[GPT_HEADER_CRC_OFFSET..GPT_HEADER_CRC_OFFSET + 4].fill(0);
+    if crc32(&header[..header_size]) != expected_crc {
+        return Ok(None);
+    }
+    // Entries must lie in the usable range the header declares and on the disk itself.
+    let first_usable = read_u64(header, GPT_FIRST_USABLE_OFFSET);
+    let last_usable = read_u64(header, GPT_LAST_USABLE_OFFSET).min(blocks.saturating_sub(1));
+    let entries_lba = read_u64(header, GPT_ENTRIES_LBA_OFFSET);
+    let entry_count = read_u32(header, GPT_ENTRY_COUNT_OFFSET).min(GPT_MAX_ENTRIES);
+    let entry_size = read_u32(header, GPT_ENTRY_SIZE_OFFSET) as usize;
+    if entry_size < GPT_ENTRY_MIN_SIZE || entry_size > block_size || block_size % entry_size != 0 {
+        return Ok(None);
+    }
+    let entries_per_block = block_size / entry_size;
+    let mut table = PartitionTable::new(TableKind::Gpt);
+    let mut cached_block = u64::MAX;
+    for idx in 0..entry_count as usize {
+        let Some(block) = entries_lba.checked_add((idx / entries_per_block) as u64) else {
+            break;
+        };
+        if block != cached_block {
+            device.read_block(block, &mut buf[..block_size])?;
+            cached_block = block;
+        }
+        let offset = (idx % entries_per_block) * entry_size;
+        let entry = &buf[offset..offset + entry_size];
+        // An all-zero type GUID marks an unused slot.
+        if entry[..16].iter().all(|&b| b == 0) {
+            continue;
+        }
+        let first = read_u64(entry, GPT_ENTRY_FIRST_LBA_OFFSET);
+        let last = read_u64(entry, GPT_ENTRY_LAST_LBA_OFFSET);
+        if first < first_usable || last > last_usable {
+            continue;
+        }
+        let Some(len) = last.checked_sub(first).and_then(|span| span.checked_add(1)) else {
+            continue;
+        };
+        table.push(Partition {
+            number: idx as u32 + 1,
+            start: first,
+            blocks: len,
+        });
+    }
+    Ok(Some(table))
+}
+
+#[derive(Clone, Copy, Default)]
+struct MbrEntry {
+    status: u8,
+    kind: u8,
+    lba_start: u32,
+    sectors: u32,
+}
+
+impl MbrEntry {
+    fn parse(buf: &[u8]) -> Self {
+        Self {
+            status: buf[0],
+            kind: buf[4],
+            lba_start: read_u32(buf, 8),
+            sectors: read_u32(buf, 12),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        (self.status == 0 || self.status == 0x80)
+            && self.kind != MBR_TYPE_EMPTY
+            && self.lba_start != 0
+            && self.sectors != 0
+    }
+
+    fn is_extended(&self) -> bool {
+        matches!(self.kind, 0x05 | 0x0f | 0x85)
+    }
+}
+
+/// Block device view of a single partition that translates block offsets.
+pub struct PartitionDevice<'a> {
+    device: &'a dyn BlockDevice,
+    start: BlockId,
+    blocks: u64,
+}
+
+impl<'a> PartitionDevice<'a> {
+    /// Create a view over `part` on the underlying disk.
+    pub fn new(device: &'a dyn BlockDevice, part: Partition) -> Self {
+        Self {
+            device,
+            start: part.start,
+            blocks: part.blocks,
+        }
+    }
+
+    /// Return the partition length in device blocks.
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    fn translate(&self, block_id: BlockId) -> VfsResult<BlockId> {
+        if block_id >= self.blocks {
+            return Err(VfsError::Invalid);
+        }
+        Ok(self.start + block_id)
+    }
+
+    fn translate_range(&self, block_id: BlockId, count: u64) -> VfsResult<BlockId> {
+        if block_id.checked_add(count).map_or(true, |end| end > self.blocks) {
+            return Err(VfsError::Invalid);
+        }
+        Ok(self.start + block_id)
+    }
+}
+
+impl BlockDevice for PartitionDevice<'_> {
+    fn block_size(&self) -> usize {
+        self.device.block_size()
+    }
+
+    fn read_block(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        self.device.read_block(self.translate(block_id)?, buf)
+    }
+
+    fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+        self.device.write_block(self.translate(block_id)?, buf)
+    }
+
+    fn flush(&self) -> VfsResult<()> {
+        self.device.flush()
+    }
+
+    fn read_blocks(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        let count = (buf.len() / self.block_size().max(1)) as u64;
+        self.device
+            .read_blocks(self.translate_range(block_id, count)?, buf)
+    }
+
+    fn write_blocks(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+        let count = (buf.len() / self.block_size().max(1)) as u64;
+        self.device
+            .write_blocks(self.translate_range(block_id, count)?, buf)
+    }
+
+    fn discard_blocks(&self, block_id: BlockId, count: u64) -> VfsResult<()> {
+        self.device
+            .discard_blocks(self.translate_range(block_id, count)?, count)
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]])
+}
+
+fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    read_u32(buf, offset) as u64 | ((read_u32(buf, offset + 4) as u64) << 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    use std::{vec, vec::Vec};
+
+    struct MemDisk {
+        block_size: usize,
+        data: RefCell<Vec<u8>>,
+    }
+
+    impl MemDisk {
+        fn new(block_size: usize, blocks: usize) -> Self {
+            Self {
+                block_size,
+                data: RefCell::new(vec![0u8; block_size * blocks]),
+            }
+        }
+
+        fn blocks(&self) -> u64 {
+            (self.data.borrow().len() / self.block_size) as u64
+        }
+    }
+
+    impl BlockDevice for MemDisk {
+        fn block_size(&self) -> usize {
+            self.block_size
+        }
+
+        fn read_block(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+            let offset = block_id as usize * self.block_size;
+            let data = self.data.borrow();
+            if offset + self.block_size > data.len() {
+                return Err(VfsError::NotFound);
+            }
+            buf[..self.block_size].copy_from_slice(&data[offset..offset + self.block_size]);
+            Ok(())
+        }
+
+        fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+            let offset = block_id as usize * self.block_size;
+            let mut data = self.data.borrow_mut();
+            if offset + self.block_size > data.len() {
+                return Err(VfsError::NotFound);
+            }
+            data[offset..offset + self.block_size].copy_from_slice(&buf[..self.block_size]);
+            Ok(())
+        }
+
+        fn flush(&self) -> VfsResult<()> {
+            Ok(())
+        }
+    }
+
+    fn put_mbr_entry(sector: &mut [u8], slot: usize, kind: u8, start: u32, sectors: u32) {
+        let off = MBR_TABLE_OFFSET + slot * MBR_ENTRY_SIZE;
+        sector[off + 4] = kind;
+        sector[off + 8..off + 12].copy_from_slice(&start.to_le_bytes());
+        sector[off + 12..off + 16].copy_from_slice(&sectors.to_le_bytes());
+        sector[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2].copy_from_slice(&MBR_SIGNATURE);
+    }
+
+    #[test]
+    fn scan_mbr_primary_and_logical() {
+        let disk = MemDisk::new(512, 256);
+        {
+            let mut data = disk.data.borrow_mut();
+            put_mbr_entry(&mut data[..512], 0, 0x83, 8, 32);
+            put_mbr_entry(&mut data[..512], 1, 0x05, 64, 128);
+            // First EBR at the start of the extended partition.
+            let ebr = 64 * 512;
+            put_mbr_entry(&mut data[ebr..ebr + 512], 0, 0x83, 2, 16);
+            put_mbr_entry(&mut data[ebr..ebr + 512], 1, 0x05, 32, 40);
+            // Second EBR, linked relative to the extended partition.
+            let ebr = 96 * 512;
+            put_mbr_entry(&mut data[ebr..ebr + 512], 0, 0x0c, 4, 20);
+        }
+        let table = scan(&disk, disk.blocks()).unwrap().unwrap();
+        assert_eq!(table.kind(), TableKind::Mbr);
+        let parts = table.partitions();
+        assert_eq!(parts.len(), 3);
+        assert_eq!(parts[0], Partition { number: 1, start: 8, blocks: 32 });
+        assert_eq!(parts[1], Partition { number: 5, start: 66, blocks: 16 });
+        assert_eq!(parts[2], Partition { number: 6, start: 100, blocks: 20 });
+    }
+
+    #[test]
+    fn scan_mbr_rejects_out_of_range_entries() {
+        let disk = MemDisk::new(512, 256);
+        {
+            let mut data = disk.data.borrow_mut();
+            put_mbr_entry(&mut data[..512], 0, 0x83, 8, 32);
+            put_mbr_entry(&mut data[..512], 1, 0x83, 200, 100);
+            put_mbr_entry(&mut data[..512], 2, 0x83, u32::MAX, u32::MAX);
+            put_mbr_entry(&mut data[..512], 3, 0x05, 64, 192);
+            let ebr = 64 * 512;
+            put_mbr_entry(&mut data[ebr..ebr + 512], 0, 0x83, 2, 16);
+            put_mbr_entry(&mut data[ebr..ebr + 512], 1, 0x05, 32, 40);
+            // The second logical partition runs past the disk, the third link points off it.
+            let ebr = 96 * 512;
+            put_mbr_entry(&mut data[ebr..ebr + 512], 0, 0x83, 4, 500);
+            put_mbr_entry(&mut data[ebr..ebr + 512], 1, 0x05, 1000, 8);
+        }
+        let table = scan(&disk, disk.blocks()).unwrap().unwrap();
+        assert_eq!(
+            table.partitions(),
+            [Partition { number: 1, start: 8, blocks: 32 }, Partition { number: 5, start: 66, blocks: 16 }]
+        );
+    }
+
+    #[test]
+    fn scan_without_table() {
+        let disk = MemDisk::new(512, 8);
+        assert!(scan(&disk, disk.blocks()).unwrap().is_none());
+        // A boot sector signature alone does not make a partition table.
+        disk.data.borrow_mut()[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2]
+            .copy_from_slice(&MBR_SIGNATURE);
+        assert!(scan(&disk, disk.blocks()).unwrap().is_none());
+    }
+
+    /// Write a protective MBR, a GPT header with entries at LBA 2 and the given used entries.
+    fn put_gpt(data: &mut [u8], first_usable: u64, last_usable: u64, entries: &[(usize, u64, u64)]) {
+        put_mbr_entry(&mut data[..512], 0, MBR_TYPE_GPT_PROTECTIVE, 1, 127);
+        for &(slot, first, last) in entries {
+            let entry = 2 * 512 + slot * 128;
+            data[entry] = 0xaf;
+            data[entry + 32..entry + 40].copy_from_slice(&first.to_le_bytes());
+            data[entry + 40..entry + 48].copy_from_slice(&last.to_le_bytes());
+        }
+        let header = &mut data[512..1024];
+        header[..8].copy_from_slice(GPT_SIGNATURE);
+        header[12..16].copy_from_slice(&92u32.to_le_bytes());
+        header[40..48].copy_from_slice(&first_usable.to_le_bytes());
+        header[48..56].copy_from_slice(&last_usable.to_le_bytes());
+        header[72..80].copy_from_slice(&2u64.to_le_bytes());
+        header[80..84].copy_from_slice(&4u32.to_le_bytes());
+        header[84..88].copy_from_slice(&128u32.to_le_bytes());
+        let crc = crc32(&header[..92]);
+        header[16..20].copy_from_slice(&crc.to_le_bytes());
+    }
+
+    #[test]
+    fn scan_gpt_entries() {
+        let disk = MemDisk::new(512, 128);
+        // Entry 1 left empty.
+        put_gpt(&mut disk.data.borrow_mut(), 34, 126, &[(0, 34, 49), (2, 64, 99)]);
+        let table = scan(&disk, disk.blocks()).unwrap().unwrap();
+        assert_eq!(table.kind(), TableKind::Gpt);
+        assert_eq!(table.partitions().len(), 2);
+        assert_eq!(table.find(1), Some(Partition { number: 1, start: 34, blocks: 16 }));
+        assert_eq!(table.find(3), Some(Partition { number: 3, start: 64, blocks: 36 }));
+        assert_eq!(table.find(2), None);
+    }
+
+    #[test]
+    fn scan_gpt_rejects_out_of_range_entries() {
+        let disk = MemDisk::new(512, 128);
+        let entries = [(0, 2, 40), (1, 100, 140), (2, 0, u64::MAX), (3, 40, 126)];
+        // The header claims more usable blocks than the disk has.
+        put_gpt(&mut disk.data.borrow_mut(), 34, 1000, &entries);
+        let table = scan(&disk, disk.blocks()).unwrap().unwrap();
+        assert_eq!(table.partitions(), [Partition { number: 4, start: 40, blocks: 87 }]);
+    }
+
+    #[test]
+    fn partition_device_translates_offsets() {
+        let disk = MemDisk::new(512, 16);
+        let part = Partition { number: 1, start: 4, blocks: 2 };
+        let dev = PartitionDevice::new(&disk, part);
+        let buf = [0x5au8; 512];
+        dev.write_block(1, &buf).unwrap();
+        assert_eq!(disk.data.borrow()[5 * 512], 0x5a);
+        let mut read = [0u8; 512];
+        dev.read_block(1, &mut read).unwrap();
+        assert_eq!(read, buf);
+        assert_eq!(dev.read_block(2, &mut read), Err(VfsError::Invalid));
+    }
+}
@@ -1,42 +1,263 @@
-//! Minimal procfs placeholder.
+//! Process information filesystem backed by a kernel-provided data source.
+//!
+//! File contents are produced on every read by a generator (`ProcSource::render`)
+//! that writes into a window over the requested offset, so no buffers are kept.
 
-use axvfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps, VfsResult};
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+
+use axvfs::{DirEntry, FileType, InodeId, Metadata, StatFs, VfsError, VfsOps, VfsResult, PROC_SUPER_MAGIC};
 
 /// Root inode identifier for procfs.
 pub const ROOT_ID: InodeId = 1;
+/// Inode of the `/proc/self` symlink, whose target is the caller's pid.
+///
+/// `lookup` resolves `self` straight to the pid directory so path walks never
+/// follow it; only `readlink` needs this inode.
+pub const SELF_ID: InodeId = 11;
+/// Maximum number of pids listed in `/proc`.
+pub const MAX_PROC_PIDS: usize = 64;
+/// Maximum number of fds listed in `/proc/<pid>/fd`.
+pub const MAX_PROC_FDS: usize = 256;
+
+const DIR_MODE: u16 = 0o555;
+const FILE_MODE: u16 = 0o444;
+const LINK_MODE: u16 = 0o777;
+const FD_LINK_MODE: u16 = 0o700;
+
+// 每个 pid 占用 inode 空间 PID_INO_BASE | pid << 16 | kind。
+const PID_INO_BASE: InodeId = 1 << 32;
+const PID_SHIFT: u32 = 16;
+const KIND_MASK: InodeId = 0xffff;
+const KIND_DIR: u16 = 0;
+const KIND_STAT: u16 = 1;
+const KIND_STATUS: u16 = 2;
+const KIND_CMDLINE: u16 = 3;
+const KIND_MAPS: u16 = 4;
+const KIND_FD_DIR: u16 = 5;
+const KIND_CWD: u16 = 6;
+const KIND_EXE: u16 = 7;
+const KIND_FD_BASE: u16 = 0x100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Generated procfs file; symlink variants render their target path.
+pub enum ProcFile {
+    Meminfo,
+    Cpuinfo,
+    Uptime,
+    Loadavg,
+    Mounts,
+    Interrupts,
+    Stat,
+    /// `/proc/net/sockstat`.
+    NetSockstat,
+    PidStat(u32),
+    PidStatus(u32),
+    PidCmdline(u32),
+    PidMaps(u32),
+    PidCwd(u32),
+    PidExe(u32),
+    PidFd(u32, u32),
+}
 
-const ROOT_FILE_TYPE: FileType = FileType::Dir;
-const ROOT_MODE: u16 = 0o755;
+/// Kernel-side data source for procfs.
+pub trait ProcSource: Sync {
+    /// Return the pid of the calling process, used to resolve `/proc/self`.
+    fn current_pid(&self) -> Option<u32>;
+    /// Fill `out` with live pids in ascending order and return the count.
+    fn pids(&self, out: &mut [u32]) -> usize;
+    /// Fill `out` with the open fds of `pid` in ascending order and return the count.
+    fn fds(&self, pid: u32, out: &mut [u32]) -> usize;
+    /// Return the raw NUL-separated argv of `pid`, which need not be UTF-8.
+    fn cmdline(&self, pid: u32) -> Option<&[u8]>;
+    /// Write the full contents of `file` into `out`; `PidCmdline` goes through `cmdline` instead.
+    fn render(&self, file: ProcFile, out: &mut dyn Write) -> fmt::Result;
+}
 
 #[derive(Clone, Copy)]
-struct DirEntrySpec {
+struct TopEntry {
     ino: InodeId,
-    name: &'static [u8],
+    name: &'static str,
+    file: ProcFile,
+}
+
+const TOP_FILES: [TopEntry; 7] = [
+    TopEntry { ino: 2, name: "meminfo", file: ProcFile::Meminfo },
+    TopEntry { ino: 3, name: "cpuinfo", file: ProcFile::Cpuinfo },
+    TopEntry { ino: 4, name: "uptime", file: ProcFile::Uptime },
+    TopEntry { ino: 5, name: "loadavg", file: ProcFile::Loadavg },
+    TopEntry { ino: 6, name: "mounts", file: ProcFile::Mounts },
+    TopEntry { ino: 7, name: "interrupts", file: ProcFile::Interrupts },
+    TopEntry { ino: 8, name: "stat", file: ProcFile::Stat },
+];
+
+/// Inode of the `/proc/net` directory.
+const NET_DIR_ID: InodeId = 9;
+
+const NET_FILES: [TopEntry; 1] = [TopEntry { ino: 10, name: "sockstat", file: ProcFile::NetSockstat }];
+
+#[derive(Clone, Copy)]
+struct PidEntry {
+    kind: u16,
+    name: &'static str,
     file_type: FileType,
 }
 
-const PROC_ENTRIES: [DirEntrySpec; 2] = [
-    DirEntrySpec {
-        ino: ROOT_ID,
-        name: b".",
-        file_type: FileType::Dir,
-    },
-    DirEntrySpec {
-        ino: ROOT_ID,
-        name: b"..",
-        file_type: FileType::Dir,
-    },
+const PID_ENTRIES: [PidEntry; 7] = [
+    PidEntry { kind: KIND_STAT, name: "stat", file_type: FileType::File },
+    PidEntry { kind: KIND_STATUS, name: "status", file_type: FileType::File },
+    PidEntry { kind: KIND_CMDLINE, name: "cmdline", file_type: FileType::File },
+    PidEntry { kind: KIND_MAPS, name: "maps", file_type: FileType::File },
+    PidEntry { kind: KIND_FD_DIR, name: "fd", file_type: FileType::Dir },
+    PidEntry { kind: KIND_CWD, name: "cwd", file_type: FileType::Symlink },
+    PidEntry { kind: KIND_EXE, name: "exe", file_type: FileType::Symlink },
 ];
 
-/// Minimal procfs implementation with only the root directory.
-pub struct ProcFs;
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Node {
+    Root,
+    NetDir,
+    SelfLink,
+    File(ProcFile),
+    PidDir(u32),
+    FdDir(u32),
+}
+
+fn pid_ino(pid: u32, kind: u16) -> InodeId {
+    PID_INO_BASE | ((pid as InodeId) << PID_SHIFT) | kind as InodeId
+}
+
+fn decode(ino: InodeId) -> Option<Node> {
+    if ino == ROOT_ID {
+        return Some(Node::Root);
+    }
+    if ino == NET_DIR_ID {
+        return Some(Node::NetDir);
+    }
+    if ino == SELF_ID {
+        return Some(Node::SelfLink);
+    }
+    if ino < PID_INO_BASE {
+        return TOP_FILES
+            .iter()
+            .chain(NET_FILES.iter())
+            .find(|entry| entry.ino == ino)
+            .map(|entry| Node::File(entry.file));
+    }
+    let pid = ((ino - PID_INO_BASE) >> PID_SHIFT) as u32;
+    let kind = (ino & KIND_MASK) as u16;
+    let node = match kind {
+        KIND_DIR => Node::PidDir(pid),
+        KIND_STAT => Node::File(ProcFile::PidStat(pid)),
+        KIND_STATUS => Node::File(ProcFile::PidStatus(pid)),
+        KIND_CMDLINE => Node::File(ProcFile::PidCmdline(pid)),
+        KIND_MAPS => Node::File(ProcFile::PidMaps(pid)),
+        KIND_FD_DIR => Node::FdDir(pid),
+        KIND_CWD => Node::File(ProcFile::PidCwd(pid)),
+        KIND_EXE => Node::File(ProcFile::PidExe(pid)),
+        kind if kind >= KIND_FD_BASE => {
+            Node::File(ProcFile::PidFd(pid, (kind - KIND_FD_BASE) as u32))
+        }
+        _ => return None,
+    };
+    Some(node)
+}
+
+struct SourceSlot {
+    inner: UnsafeCell<Option<&'static dyn ProcSource>>,
+}
+
+// SAFETY: the source is installed once during boot before procfs is mounted.
+unsafe impl Sync for SourceSlot {}
+
+/// Procfs instance; contents come from the installed `ProcSource`.
+pub struct ProcFs {
+    source: SourceSlot,
+}
 
 impl ProcFs {
-    /// Create a new procfs instance.
+    /// Create a new procfs instance without a data source.
     pub const fn new() -> Self {
-        Self
+        Self {
+            source: SourceSlot {
+                inner: UnsafeCell::new(None),
+            },
+        }
     }
 
+    /// Install the kernel data source; call once during boot.
+    pub fn set_source(&self, source: &'static dyn ProcSource) {
+        // SAFETY: 启动阶段单次写入，之后只读。
+        unsafe {
+            *self.source.inner.get() = Some(source);
+        }
+    }
+
+    fn source(&self) -> Option<&'static dyn ProcSource> {
+        // SAFETY: written once during boot, read-only afterwards.
+        unsafe { *self.source.inner.get() }
+    }
+
+    fn pid_alive(&self, pid: u32) -> bool {
+        let Some(source) = self.source() else {
+            return false;
+        };
+        let mut pids = [0u32; MAX_PROC_PIDS];
+        let count = source.pids(&mut pids);
+        pids[..count].contains(&pid)
+    }
+
+    fn fd_open(&self, pid: u32, fd: u32) -> bool {
+        let Some(source) = self.source() else {
+            return false;
+        };
+        let mut fds = [0u32; MAX_PROC_FDS];
+        let count = source.fds(pid, &mut fds);
+        fds[..count].contains(&fd)
+    }
+
+    /// Validate that the node still refers to a live process/fd.
+    fn exists(&self, node: Node) -> bool {
+        match node {
+            Node::Root | Node::NetDir | Node::SelfLink => true,
+            Node::PidDir(pid) | Node::FdDir(pid) => self.pid_alive(pid),
+            Node::File(ProcFile::PidFd(pid, fd)) => self.pid_alive(pid) && self.fd_open(pid, fd),
+            Node::File(file) => match pid_of(file) {
+                Some(pid) => self.pid_alive(pid),
+                None => true,
+            },
+        }
+    }
+}
+
+impl Default for ProcFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn pid_of(file: ProcFile) -> Option<u32> {
+    match file {
+        ProcFile::PidStat(pid)
+        | ProcFile::PidStatus(pid)
+        | ProcFile::PidCmdline(pid)
+        | ProcFile::PidMaps(pid)
+        | ProcFile::PidCwd(pid)
+        | ProcFile::PidExe(pid)
+        | ProcFile::PidFd(pid, _) => Some(pid),
+        _ => None,
+    }
+}
+
+fn is_symlink(file: ProcFile) -> bool {
+    matches!(file, ProcFile::PidCwd(_) | ProcFile::PidExe(_) | ProcFile::PidFd(_, _))
+}
+
+fn parse_u32(name: &str) -> Option<u32> {
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    name.parse().ok()
 }
 
 impl VfsOps for ProcFs {
@@ -44,8 +265,57 @@ impl VfsOps for ProcFs {
         Ok(ROOT_ID)
     }
 
-    fn lookup(&self, _parent: InodeId, _name: &str) -> VfsResult<Option<InodeId>> {
-        Ok(None)
+    fn lookup(&self, parent: InodeId, name: &str) -> VfsResult<Option<InodeId>> {
+        let node = decode(parent).ok_or(VfsError::NotFound)?;
+        if !self.exists(node) {
+            return Err(VfsError::NotFound);
+        }
+        let found = match node {
+            Node::Root => match name {
+                "." | ".." => Some(ROOT_ID),
+                "net" => Some(NET_DIR_ID),
+                // self 直接解析为当前进程目录，路径遍历无需跟随符号链接。
+                "self" => self
+                    .source()
+                    .and_then(|source| source.current_pid())
+                    .map(|pid| pid_ino(pid, KIND_DIR)),
+                _ => match parse_u32(name) {
+                    Some(pid) if pid <= u16::MAX as u32 => {
+                        self.pid_alive(pid).then(|| pid_ino(pid, KIND_DIR))
+                    }
+                    Some(_) => None,
+                    None => TOP_FILES
+                        .iter()
+                        .find(|entry| entry.name == name)
+                        .map(|entry| entry.ino),
+                },
+            },
+            Node::NetDir => match name {
+                "." => Some(parent),
+                ".." => Some(ROOT_ID),
+                _ => NET_FILES.iter().find(|entry| entry.name == name).map(|entry| entry.ino),
+            },
+            Node::PidDir(pid) => match name {
+                "." => Some(parent),
+                ".." => Some(ROOT_ID),
+                _ => PID_ENTRIES
+                    .iter()
+                    .find(|entry| entry.name == name)
+                    .map(|entry| pid_ino(pid, entry.kind)),
+            },
+            Node::FdDir(pid) => match name {
+                "." => Some(parent),
+                ".." => Some(pid_ino(pid, KIND_DIR)),
+                _ => match parse_u32(name) {
+                    Some(fd) if fd < (u16::MAX - KIND_FD_BASE) as u32 => self
+                        .fd_open(pid, fd)
+                        .then(|| pid_ino(pid, KIND_FD_BASE + fd as u16)),
+                    _ => None,
+                },
+            },
+            Node::SelfLink | Node::File(_) => return Err(VfsError::NotDir),
+        };
+        Ok(found)
     }
 
     fn create(&self, _parent: InodeId, _name: &str, _kind: FileType, _mode: u16) -> VfsResult<InodeId> {
@@ -57,15 +327,45 @@ impl VfsOps for ProcFs {
     }
 
     fn metadata(&self, inode: InodeId) -> VfsResult<Metadata> {
-        if inode == ROOT_ID {
-            Ok(Metadata::new(ROOT_FILE_TYPE, 0, ROOT_MODE))
-        } else {
-            Err(VfsError::NotFound)
+        let node = decode(inode).ok_or(VfsError::NotFound)?;
+        if !self.exists(node) {
+            return Err(VfsError::NotFound);
         }
+        let meta = match node {
+            Node::Root | Node::NetDir | Node::PidDir(_) | Node::FdDir(_) => {
+                Metadata::new(FileType::Dir, 0, DIR_MODE)
+            }
+            Node::SelfLink => Metadata::new(FileType::Symlink, 0, LINK_MODE),
+            Node::File(ProcFile::PidFd(_, _)) => Metadata::new(FileType::Symlink, 0, FD_LINK_MODE),
+            Node::File(file) if is_symlink(file) => Metadata::new(FileType::Symlink, 0, LINK_MODE),
+            Node::File(_) => Metadata::new(FileType::File, 0, FILE_MODE),
+        };
+        Ok(meta)
     }
 
-    fn read_at(&self, _inode: InodeId, _offset: u64, _buf: &mut [u8]) -> VfsResult<usize> {
-        Err(VfsError::NotSupported)
+    fn read_at(&self, inode: InodeId, offset: u64, buf: &mut [u8]) -> VfsResult<usize> {
+        let node = decode(inode).ok_or(VfsError::NotFound)?;
+        if !self.exists(node) {
+            return Err(VfsError::NotFound);
+        }
+        let source = self.source().ok_or(VfsError::NotFound)?;
+        let mut window = WindowWriter::new(buf, offset);
+        let result = match node {
+            Node::SelfLink => {
+                let pid = source.current_pid().ok_or(VfsError::NotFound)?;
+                write!(window, "{}", pid)
+            }
+            Node::File(ProcFile::PidCmdline(pid)) => {
+                // argv 原样输出，非 UTF-8 或被截断的多字节字符也不报错。
+                window.write_bytes(source.cmdline(pid).ok_or(VfsError::NotFound)?)
+            }
+            Node::File(file) => source.render(file, &mut window),
+            _ => return Err(VfsError::Invalid),
+        };
+        if result.is_err() && !window.is_full() {
+            return Err(VfsError::Io);
+        }
+        Ok(window.written())
     }
 
     fn write_at(&self, _inode: InodeId, _offset: u64, _buf: &[u8]) -> VfsResult<usize> {
@@ -73,25 +373,311 @@ impl VfsOps for ProcFs {
     }
 
     fn read_dir(&self, inode: InodeId, offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
-        if inode != ROOT_ID {
-            return Err(VfsError::NotDir);
+        let node = decode(inode).ok_or(VfsError::NotFound)?;
+        if !self.exists(node) {
+            return Err(VfsError::NotFound);
+        }
+        let mut list = DirList::new(offset, entries);
+        match node {
+            Node::Root => {
+                list.push(ROOT_ID, ".", FileType::Dir)?;
+                list.push(ROOT_ID, "..", FileType::Dir)?;
+                for entry in TOP_FILES.iter() {
+                    list.push(entry.ino, entry.name, FileType::File)?;
+                }
+                list.push(NET_DIR_ID, "net", FileType::Dir)?;
+                if self.source().and_then(|source| source.current_pid()).is_some() {
+                    list.push(SELF_ID, "self", FileType::Symlink)?;
+                }
+                if let Some(source) = self.source() {
+                    let mut pids = [0u32; MAX_PROC_PIDS];
+                    let count = source.pids(&mut pids);
+                    for &pid in pids[..count].iter() {
+                        list.push_number(pid_ino(pid, KIND_DIR), pid, FileType::Dir)?;
+                    }
+                }
+            }
+            Node::NetDir => {
+                list.push(inode, ".", FileType::Dir)?;
+                list.push(ROOT_ID, "..", FileType::Dir)?;
+                for entry in NET_FILES.iter() {
+                    list.push(entry.ino, entry.name, FileType::File)?;
+                }
+            }
+            Node::PidDir(pid) => {
+                list.push(inode, ".", FileType::Dir)?;
+                list.push(ROOT_ID, "..", FileType::Dir)?;
+                for entry in PID_ENTRIES.iter() {
+                    list.push(pid_ino(pid, entry.kind), entry.name, entry.file_type)?;
+                }
+            }
+            Node::FdDir(pid) => {
+                list.push(inode, ".", FileType::Dir)?;
+                list.push(pid_ino(pid, KIND_DIR), "..", FileType::Dir)?;
+                if let Some(source) = self.source() {
+                    let mut fds = [0u32; MAX_PROC_FDS];
+                    let count = source.fds(pid, &mut fds);
+                    for &fd in fds[..count].iter() {
+                        if fd >= (u16::MAX - KIND_FD_BASE) as u32 {
+                            continue;
+                        }
+                        list.push_number(pid_ino(pid, KIND_FD_BASE + fd as u16), fd, FileType::Symlink)?;
+                    }
+                }
+            }
+            Node::SelfLink | Node::File(_) => return Err(VfsError::NotDir),
         }
-        fill_dir_entries(&PROC_ENTRIES, offset, entries)
+        Ok(list.written)
+    }
+
+    fn statfs(&self) -> VfsResult<StatFs> {
+        Ok(StatFs::empty(PROC_SUPER_MAGIC))
     }
 }
 
-fn fill_dir_entries(list: &[DirEntrySpec], offset: usize, entries: &mut [DirEntry]) -> VfsResult<usize> {
-    if offset >= list.len() {
-        return Ok(0);
+/// Directory listing cursor that skips `offset` entries and fills `entries`.
+struct DirList<'a> {
+    skip: usize,
+    entries: &'a mut [DirEntry],
+    written: usize,
+}
+
+impl<'a> DirList<'a> {
+    fn new(skip: usize, entries: &'a mut [DirEntry]) -> Self {
+        Self {
+            skip,
+            entries,
+            written: 0,
+        }
     }
-    let mut written = 0usize;
-    for spec in list.iter().skip(offset).take(entries.len()) {
+
+    fn push(&mut self, ino: InodeId, name: &str, file_type: FileType) -> VfsResult<()> {
+        if self.skip > 0 {
+            self.skip -= 1;
+            return Ok(());
+        }
+        if self.written >= self.entries.len() {
+            return Ok(());
+        }
         let mut entry = DirEntry::empty();
-        entry.ino = spec.ino;
-        entry.file_type = spec.file_type;
-        entry.set_name(spec.name)?;
-        entries[written] = entry;
-        written += 1;
+        entry.ino = ino;
+        entry.file_type = file_type;
+        entry.set_name(name.as_bytes())?;
+        self.entries[self.written] = entry;
+        self.written += 1;
+        Ok(())
+    }
+
+    fn push_number(&mut self, ino: InodeId, value: u32, file_type: FileType) -> VfsResult<()> {
+        let mut digits = [0u8; 10];
+        let mut len = 0usize;
+        let mut value = value;
+        loop {
+            digits[len] = b'0' + (value % 10) as u8;
+            len += 1;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        digits[..len].reverse();
+        let name = core::str::from_utf8(&digits[..len]).map_err(|_| VfsError::Invalid)?;
+        self.push(ino, name, file_type)
+    }
+}
+
+/// `fmt::Write` sink that keeps only the bytes in `[offset, offset + buf.len())`.
+struct WindowWriter<'a> {
+    buf: &'a mut [u8],
+    offset: u64,
+    pos: u64,
+    written: usize,
+}
+
+impl<'a> WindowWriter<'a> {
+    fn new(buf: &'a mut [u8], offset: u64) -> Self {
+        Self {
+            buf,
+            offset,
+            pos: 0,
+            written: 0,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.written >= self.buf.len()
+    }
+
+    fn written(&self) -> usize {
+        self.written
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> fmt::Result {
+        let start = self.pos;
+        let end = start + bytes.len() as u64;
+        self.pos = end;
+        let window_start = self.offset + self.written as u64;
+        if end > window_start && !self.is_full() {
+            let from = (window_start - start) as usize;
+            let take = core::cmp::min(bytes.len() - from, self.buf.len() - self.written);
+            self.buf[self.written..self.written + take].copy_from_slice(&bytes[from..from + take]);
+            self.written += take;
+        }
+        if self.is_full() {
+            // 窗口已满：返回错误以提前终止生成器。
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+impl Write for WindowWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.write_bytes(s.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeSource;
+
+    impl ProcSource for FakeSource {
+        fn current_pid(&self) -> Option<u32> {
+            Some(7)
+        }
+
+        fn pids(&self, out: &mut [u32]) -> usize {
+            out[0] = 1;
+            out[1] = 7;
+            2
+        }
+
+        fn fds(&self, pid: u32, out: &mut [u32]) -> usize {
+            if pid != 7 {
+                return 0;
+            }
+            out[..3].copy_from_slice(&[0, 1, 3]);
+            3
+        }
+
+        fn cmdline(&self, pid: u32) -> Option<&[u8]> {
+            (pid == 7).then_some(b"sh\0-c\0\xff\xe4\xb8\0".as_slice())
+        }
+
+        fn render(&self, file: ProcFile, out: &mut dyn Write) -> fmt::Result {
+            match file {
+                ProcFile::Uptime => out.write_str("12.34 5.67\n"),
+                ProcFile::NetSockstat => out.write_str("sockets: used 3\n"),
+                ProcFile::PidStat(pid) => {
+                    for idx in 0..100 {
+                        write!(out, "{}:{} ", pid, idx)?;
+                    }
+                    Ok(())
+                }
+                ProcFile::PidFd(_, fd) => write!(out, "pipe:[{}]", fd),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    static SOURCE: FakeSource = FakeSource;
+
+    fn procfs() -> ProcFs {
+        let fs = ProcFs::new();
+        fs.set_source(&SOURCE);
+        fs
+    }
+
+    #[test]
+    fn lookup_resolves_self_and_pid_dirs() {
+        let fs = procfs();
+        let self_dir = fs.lookup(ROOT_ID, "self").unwrap().unwrap();
+        assert_eq!(Some(self_dir), fs.lookup(ROOT_ID, "7").unwrap());
+        assert_eq!(fs.lookup(ROOT_ID, "3").unwrap(), None);
+        let stat = fs.lookup(self_dir, "stat").unwrap().unwrap();
+        assert_eq!(fs.metadata(stat).unwrap().file_type, FileType::File);
+        let fd_dir = fs.lookup(self_dir, "fd").unwrap().unwrap();
+        assert_eq!(fs.lookup(fd_dir, "2").unwrap(), None);
+        let fd3 = fs.lookup(fd_dir, "3").unwrap().unwrap();
+        assert_eq!(fs.metadata(fd3).unwrap().file_type, FileType::Symlink);
+        let mut buf = [0u8; 32];
+        let len = fs.read_at(fd3, 0, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"pipe:[3]");
+    }
+
+    #[test]
+    fn self_is_a_symlink_to_the_current_pid() {
+        let fs = procfs();
+        let meta = fs.metadata(SELF_ID).unwrap();
+        assert_eq!(meta.file_type, FileType::Symlink);
+        assert_eq!(meta.mode, LINK_MODE);
+        let mut buf = [0u8; 16];
+        let len = fs.read_at(SELF_ID, 0, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"7");
+        assert_eq!(fs.lookup(SELF_ID, "stat"), Err(VfsError::NotDir));
+    }
+
+    #[test]
+    fn cmdline_keeps_raw_bytes() {
+        let fs = procfs();
+        let pid_dir = fs.lookup(ROOT_ID, "7").unwrap().unwrap();
+        let cmdline = fs.lookup(pid_dir, "cmdline").unwrap().unwrap();
+        let mut buf = [0u8; 32];
+        let len = fs.read_at(cmdline, 0, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"sh\0-c\0\xff\xe4\xb8\0");
+        let len = fs.read_at(cmdline, 6, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\xff\xe4\xb8\0");
+    }
+
+    #[test]
+    fn read_dir_lists_files_and_pids() {
+        let fs = procfs();
+        let mut entries = [DirEntry::empty(); 16];
+        let count = fs.read_dir(ROOT_ID, 0, &mut entries).unwrap();
+        assert_eq!(count, 2 + TOP_FILES.len() + 1 + 1 + 2);
+        assert_eq!(entries[2 + TOP_FILES.len()].name(), b"net");
+        assert_eq!(entries[2].name(), b"meminfo");
+        assert_eq!(entries[count - 3].name(), b"self");
+        assert_eq!(entries[count - 3].ino, SELF_ID);
+        assert_eq!(entries[count - 3].file_type, FileType::Symlink);
+        assert_eq!(entries[count - 1].name(), b"7");
+        let tail = fs.read_dir(ROOT_ID, count - 1, &mut entries).unwrap();
+        assert_eq!(tail, 1);
+        assert_eq!(entries[0].name(), b"7");
+    }
+
+    #[test]
+    fn net_dir_holds_sockstat() {
+        let fs = procfs();
+        let net = fs.lookup(ROOT_ID, "net").unwrap().unwrap();
+        assert_eq!(fs.metadata(net).unwrap().file_type, FileType::Dir);
+        assert_eq!(fs.lookup(net, "..").unwrap(), Some(ROOT_ID));
+        assert_eq!(fs.lookup(net, "dev").unwrap(), None);
+        let sockstat = fs.lookup(net, "sockstat").unwrap().unwrap();
+        let mut buf = [0u8; 32];
+        let len = fs.read_at(sockstat, 0, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"sockets: used 3\n");
+        let mut entries = [DirEntry::empty(); 4];
+        assert_eq!(fs.read_dir(net, 0, &mut entries).unwrap(), 3);
+        assert_eq!(entries[2].name(), b"sockstat");
+        assert_eq!(fs.lookup(sockstat, "x"), Err(VfsError::NotDir));
+    }
+
+    #[test]
+    fn read_at_windows_generated_content() {
+        let fs = procfs();
+        let uptime = fs.lookup(ROOT_ID, "uptime").unwrap().unwrap();
+        let mut buf = [0u8; 64];
+        let len = fs.read_at(uptime, 3, &mut buf).unwrap();
+        assert_eq!(&buf[..len], b"34 5.67\n");
+        assert_eq!(fs.read_at(uptime, 64, &mut buf).unwrap(), 0);
+
+        let stat = fs.lookup(fs.lookup(ROOT_ID, "7").unwrap().unwrap(), "stat").unwrap().unwrap();
+        let mut small = [0u8; 8];
+        let len = fs.read_at(stat, 4, &mut small).unwrap();
+        assert_eq!(len, 8);
+        assert_eq!(&small, b"7:1 7:2 ");
     }
-    Ok(written)
 }
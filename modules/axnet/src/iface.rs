@@ -0,0 +1,218 @@
+//! Interface configuration: boot-time IPv4 policy and `ifconfig`-style snapshots.
+
+use core::str::FromStr;
+
+use smoltcp::wire::Ipv4Address;
+
+use crate::NetError;
+
+/// Interface is administratively up (Linux `IFF_UP`).
+pub const IFF_UP: u16 = 0x1;
+/// Interface supports broadcast (Linux `IFF_BROADCAST`).
+pub const IFF_BROADCAST: u16 = 0x2;
+/// Interface is the loopback device (Linux `IFF_LOOPBACK`).
+pub const IFF_LOOPBACK: u16 = 0x8;
+/// Interface has resources allocated (Linux `IFF_RUNNING`).
+pub const IFF_RUNNING: u16 = 0x40;
+/// Interface supports multicast (Linux `IFF_MULTICAST`).
+pub const IFF_MULTICAST: u16 = 0x1000;
+
+/// Static IPv4 settings for the external interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ipv4Config {
+    pub addr: Ipv4Address,
+    pub prefix_len: u8,
+    pub gateway: Option<Ipv4Address>,
+}
+
+/// How the external interface obtains its IPv4 address at boot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NetConfig {
+    /// Run the DHCP client; apply `fallback` if no lease arrives in time or a lease is lost.
+    Dhcp { fallback: Option<Ipv4Config> },
+    /// Use the given address and never start DHCP.
+    Static(Ipv4Config),
+    /// Leave the external interface without an IPv4 address.
+    Off,
+}
+
+impl NetConfig {
+    /// Parse the kernel `ip=` parameter (absent means plain DHCP).
+    ///
+    /// Accepts `dhcp`, `off`/`none`, or the Linux form
+    /// `<client>:<server>:<gw>:<netmask>:<host>:<dev>:<autoconf>`. A static client address is
+    /// used as the DHCP fallback unless `autoconf` is `off`/`none`, which pins it; with an
+    /// empty client, `autoconf` alone picks plain DHCP or no address.
+    pub fn from_ip_param(param: Option<&str>) -> Result<Self, NetError> {
+        let Some(param) = param else {
+            return Ok(Self::Dhcp { fallback: None });
+        };
+        match param {
+            "" | "dhcp" | "on" | "any" => return Ok(Self::Dhcp { fallback: None }),
+            "off" | "none" => return Ok(Self::Off),
+            _ => {}
+        }
+        let mut fields = param.split(':');
+        let client = fields.next().unwrap_or("");
+        let _server = fields.next();
+        let gateway = fields.next().unwrap_or("");
+        let mask = fields.next().unwrap_or("");
+        let _host = fields.next();
+        let _dev = fields.next();
+        let pinned = match fields.next().unwrap_or("") {
+            "off" | "none" => true,
+            "" | "dhcp" | "on" | "any" => false,
+            _ => return Err(NetError::Invalid),
+        };
+        // 客户端地址为空（如 `ip=:::::eth0:dhcp`）时只按 autoconf 决定是否跑 DHCP。
+        if client.is_empty() {
+            return Ok(if pinned { Self::Off } else { Self::Dhcp { fallback: None } });
+        }
+        let addr = parse_ipv4(client)?;
+        let gateway = match gateway {
+            "" => None,
+            gw => Some(parse_ipv4(gw)?),
+        };
+        let prefix_len = match mask {
+            "" => classful_prefix(addr),
+            mask => netmask_prefix(Ipv4Address::from_str(mask).map_err(|_| NetError::Invalid)?)?,
+        };
+        let config = Ipv4Config {
+            addr,
+            prefix_len,
+            gateway,
+        };
+        if pinned {
+            Ok(Self::Static(config))
+        } else {
+            Ok(Self::Dhcp {
+                fallback: Some(config),
+            })
+        }
+    }
+}
+
+/// Snapshot of one interface, shaped after what `SIOCGIF*` ioctls report.
+#[derive(Debug, Clone, Copy)]
+pub struct IfaceInfo {
+    /// 1-based interface index (`SIOCGIFINDEX`).
+    pub index: u32,
+    pub name: &'static str,
+    pub mac: [u8; 6],
+    /// Current IPv4 address and prefix length, if configured.
+    pub ipv4: Option<(Ipv4Address, u8)>,
+    /// `IFF_*` bits.
+    pub flags: u16,
+    pub mtu: usize,
+}
+
+/// Convert a contiguous netmask into a prefix length.
+pub fn netmask_prefix(mask: Ipv4Address) -> Result<u8, NetError> {
+    let bits = u32::from_be_bytes(mask.0);
+    if bits.leading_ones() + bits.trailing_zeros() != 32 {
+        return Err(NetError::Invalid);
+    }
+    Ok(bits.leading_ones() as u8)
+}
+
+/// Convert a prefix length into a netmask.
+pub fn prefix_netmask(prefix_len: u8) -> Ipv4Address {
+    let bits = match prefix_len {
+        0 => 0,
+        len if len >= 32 => u32::MAX,
+        len => u32::MAX << (32 - len),
+    };
+    Ipv4Address::from_bytes(&bits.to_be_bytes())
+}
+
+/// Default prefix for an address without an explicit netmask (class A/B/C).
+pub fn classful_prefix(addr: Ipv4Address) -> u8 {
+    match addr.0[0] {
+        0..=127 => 8,
+        128..=191 => 16,
+        _ => 24,
+    }
+}
+
+fn parse_ipv4(text: &str) -> Result<Ipv4Address, NetError> {
+    let addr = Ipv4Address::from_str(text).map_err(|_| NetError::Invalid)?;
+    if addr.is_unspecified() || addr.is_broadcast() || addr.is_multicast() {
+        return Err(NetError::Invalid);
+    }
+    Ok(addr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: Ipv4Address = Ipv4Address([10, 0, 2, 15]);
+    const GATEWAY: Ipv4Address = Ipv4Address([10, 0, 2, 2]);
+
+    #[test]
+    fn ip_param_keywords() {
+        for param in [None, Some(""), Some("dhcp"), Some("on"), Some("any")] {
+            assert_eq!(NetConfig::from_ip_param(param), Ok(NetConfig::Dhcp { fallback: None }));
+        }
+        assert_eq!(NetConfig::from_ip_param(Some("off")), Ok(NetConfig::Off));
+        assert_eq!(NetConfig::from_ip_param(Some("none")), Ok(NetConfig::Off));
+    }
+
+    #[test]
+    fn ip_param_static_client() {
+        let with_mask = Ipv4Config { addr: ADDR, prefix_len: 24, gateway: Some(GATEWAY) };
+        assert_eq!(
+            NetConfig::from_ip_param(Some("10.0.2.15::10.0.2.2:255.255.255.0::eth0:off")),
+            Ok(NetConfig::Static(with_mask))
+        );
+        // 无掩码时按地址类别取前缀，autoconf 缺省时静态地址只作 DHCP 的后备。
+        let classful = Ipv4Config { addr: ADDR, prefix_len: 8, gateway: None };
+        assert_eq!(
+            NetConfig::from_ip_param(Some("10.0.2.15")),
+            Ok(NetConfig::Dhcp { fallback: Some(classful) })
+        );
+        assert_eq!(
+            NetConfig::from_ip_param(Some("10.0.2.15:::::eth0:dhcp")),
+            Ok(NetConfig::Dhcp { fallback: Some(classful) })
+        );
+        assert_eq!(NetConfig::from_ip_param(Some("10.0.2.15::::::none")), Ok(NetConfig::Static(classful)));
+    }
+
+    #[test]
+    fn ip_param_empty_client() {
+        assert_eq!(NetConfig::from_ip_param(Some(":::::eth0:dhcp")), Ok(NetConfig::Dhcp { fallback: None }));
+        assert_eq!(NetConfig::from_ip_param(Some("::10.0.2.2::::on")), Ok(NetConfig::Dhcp { fallback: None }));
+        assert_eq!(NetConfig::from_ip_param(Some(":::::eth0:off")), Ok(NetConfig::Off));
+    }
+
+    #[test]
+    fn ip_param_rejects_bad_fields() {
+        for param in [
+            "10.0.2.15::10.0.2.2:255.0.255.0::eth0:off",
+            "10.0.2.15:::255.255.255.300",
+            "10.0.2",
+            "0.0.0.0::::::off",
+            "224.0.0.1",
+            "10.0.2.15::10.0.2.256",
+            "10.0.2.15::::::static",
+            ":::::eth0:bogus",
+        ] {
+            assert_eq!(NetConfig::from_ip_param(Some(param)), Err(NetError::Invalid), "{param}");
+        }
+    }
+
+    #[test]
+    fn netmask_and_prefix_conversions() {
+        assert_eq!(netmask_prefix(Ipv4Address([255, 255, 255, 0])), Ok(24));
+        assert_eq!(netmask_prefix(Ipv4Address([0, 0, 0, 0])), Ok(0));
+        assert_eq!(netmask_prefix(Ipv4Address([255, 255, 255, 255])), Ok(32));
+        assert_eq!(netmask_prefix(Ipv4Address([255, 0, 255, 0])), Err(NetError::Invalid));
+        assert_eq!(netmask_prefix(Ipv4Address([0, 255, 255, 255])), Err(NetError::Invalid));
+        for len in 0..=32 {
+            assert_eq!(netmask_prefix(prefix_netmask(len)), Ok(len));
+        }
+        assert_eq!(classful_prefix(Ipv4Address([10, 1, 2, 3])), 8);
+        assert_eq!(classful_prefix(Ipv4Address([172, 16, 0, 1])), 16);
+        assert_eq!(classful_prefix(Ipv4Address([192, 168, 1, 1])), 24);
+    }
+}
@@ -1,7 +1,17 @@
 #![no_std]
 //! Network stack facade and NetDevice abstraction.
 
+#[cfg(test)]
+extern crate std;
+
+mod iface;
+mod pool;
 mod smoltcp_impl;
+mod sockopt;
+#[cfg(test)]
+mod tests;
+#[cfg(test)]
+mod wire;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Socket-layer errors surfaced to the kernel.
@@ -16,6 +26,20 @@ pub enum NetError {
     IsConnected,
     Unreachable,
     ConnRefused,
+    /// Datagram does not fit in one IP packet.
+    MsgSize,
+    /// Unconnected datagram socket and no destination given.
+    DestAddrRequired,
+    /// Broadcast destination without `SO_BROADCAST`.
+    Access,
+    /// Sending half already shut down.
+    Pipe,
+    /// Multicast group already joined by this socket.
+    AddrInUse,
+    /// Multicast group not joined by this socket.
+    AddrNotAvail,
+    /// Too many multicast groups, on the socket or the interface.
+    NoBufs,
 }
 
 /// Minimal net device interface for raw frame I/O.
@@ -30,18 +54,37 @@ pub trait NetDevice {
     fn poll(&self) -> bool;
 }
 
+/// Page provider for socket buffers and socket tables, which grow on demand.
+pub trait NetMemory {
+    /// Allocate `pages` zeroed, physically contiguous and directly mapped 4 KiB pages.
+    fn alloc_pages(&self, pages: usize) -> Option<core::ptr::NonNull<u8>>;
+    /// Give back pages obtained from `alloc_pages`.
+    fn free_pages(&self, ptr: core::ptr::NonNull<u8>, pages: usize);
+}
+
+pub use iface::{
+    classful_prefix, netmask_prefix, prefix_netmask, IfaceInfo, Ipv4Config, NetConfig, IFF_BROADCAST, IFF_LOOPBACK,
+    IFF_MULTICAST, IFF_RUNNING, IFF_UP,
+};
 pub use smoltcp_impl::{
-    arp_probe_gateway_once, init, notify_irq, ping_gateway_once, poll, request_poll,
-    socket_accept, socket_bind, socket_close, socket_connect, socket_connecting, socket_create,
-    socket_listen, socket_local_endpoint, socket_poll, socket_recv, socket_recv_window_event,
-    socket_remote_endpoint, socket_send, socket_shutdown, socket_take_error, tcp_loopback_test_once,
-    NetEvent, SocketId, TcpRecvWindow,
+    arp_probe_gateway_once, iface_by_name, iface_info, iface_set_ipv4, init, notify_irq, ping_gateway_once, poll,
+    request_poll, socket_accept, socket_acquire, socket_bind, socket_close, socket_connect, socket_connecting,
+    socket_create, socket_disconnect, socket_domain, socket_hdrincl, socket_join_multicast, socket_leave_multicast,
+    socket_listen, socket_local_endpoint, socket_options, socket_poll, socket_recv, socket_recv_window_event,
+    socket_release, socket_remote_endpoint, socket_send, socket_send_queue, socket_send_with, socket_set_hdrincl,
+    socket_set_option, socket_set_v6only, socket_shutdown, socket_take_error, socket_type, socket_v6only, sockstat,
+    tcp_loopback_test_once, NetEvent, Received, SockStat, SocketId, TcpRecvWindow, NET_MTU,
+};
+pub use sockopt::{
+    SockOpt, SocketOptions, SOCK_MIN_BUF, TCP_KEEPCNT_DEFAULT, TCP_KEEPIDLE_DEFAULT, TCP_KEEPINTVL_DEFAULT,
 };
-pub use smoltcp::wire::{IpAddress, Ipv4Address};
+pub use smoltcp::wire::{IpAddress, Ipv4Address, Ipv6Address};
 
 #[allow(dead_code)]
-/// Socket wrapper for TCP/UDP sockets managed by the stack.
+/// Socket wrapper for the TCP/UDP, ping and raw sockets managed by the stack.
 pub enum AxSocket<'a> {
     Tcp(smoltcp::socket::tcp::Socket<'a>),
     Udp(smoltcp::socket::udp::Socket<'a>),
+    Icmp(smoltcp::socket::icmp::Socket<'a>),
+    Raw(smoltcp::socket::raw::Socket<'a>),
 }
@@ -0,0 +1,354 @@
+//! Socket buffer pool.
+//!
+//! Buffers are power-of-two blocks from `POOL_MIN_BLOCK` up to a whole
+//! `POOL_CHUNK`, split and merged buddy-style inside chunks requested from
+//! `NetMemory` the first time they are needed. Up to `POOL_IDLE_CHUNKS` fully
+//! free chunks stay cached for the next socket; any further chunk is handed
+//! back with `NetMemory::free_pages` as soon as its last block is freed.
+
+use core::ptr::NonNull;
+use core::slice;
+
+use crate::NetMemory;
+
+/// Size of one chunk, also the largest block.
+pub const POOL_CHUNK: usize = 65536;
+/// Smallest block handed out.
+pub const POOL_MIN_BLOCK: usize = 2048;
+/// Upper bound on live chunks, i.e. 64 MiB of socket buffers.
+pub const POOL_MAX_CHUNKS: usize = 1024;
+/// Fully free chunks kept instead of being returned to `NetMemory`.
+pub const POOL_IDLE_CHUNKS: usize = 2;
+/// Page size assumed for `NetMemory`.
+pub const NET_PAGE_SIZE: usize = 4096;
+// 64K、32K … 2K 共 6 级，最小一级 32 块，恰好放进一个 u32 位图。
+const LEVELS: usize = 6;
+
+/// A block owned by one socket buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoolBlock {
+    chunk: u16,
+    level: u8,
+    index: u8,
+}
+
+impl PoolBlock {
+    /// Block size in bytes.
+    pub const fn len(&self) -> usize {
+        POOL_CHUNK >> self.level
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Chunk {
+    base: Option<NonNull<u8>>,
+    /// Bit `i` of `free[level]`: block `i` of that level is free.
+    free: [u32; LEVELS],
+}
+
+const EMPTY_CHUNK: Chunk = Chunk { base: None, free: [0; LEVELS] };
+
+impl Chunk {
+    fn alloc(&mut self, level: usize) -> Option<u8> {
+        let free = self.free[level];
+        if free != 0 {
+            let index = free.trailing_zeros() as u8;
+            self.free[level] &= !(1 << index);
+            return Some(index);
+        }
+        if level == 0 {
+            return None;
+        }
+        // 拆分上一级的块：左半分出，右半挂回本级空闲位图。
+        let parent = self.alloc(level - 1)?;
+        self.free[level] |= 1 << (parent * 2 + 1);
+        Some(parent * 2)
+    }
+
+    fn release(&mut self, level: usize, index: u8) {
+        let buddy = index ^ 1;
+        if level > 0 && self.free[level] & (1 << buddy) != 0 {
+            self.free[level] &= !(1 << buddy);
+            self.release(level - 1, index / 2);
+        } else {
+            self.free[level] |= 1 << index;
+        }
+    }
+
+    /// Whether no block of the chunk is handed out.
+    fn is_idle(&self) -> bool {
+        self.free[0] != 0
+    }
+
+    /// Whether a larger block is free to be split down to `level`.
+    fn can_split(&self, level: usize) -> bool {
+        self.free[..level].iter().any(|&free| free != 0)
+    }
+}
+
+/// Buddy allocator behind every socket buffer.
+pub struct BufferPool {
+    /// Chunk slots; returned chunks leave a slot with `base: None` for reuse.
+    chunks: [Chunk; POOL_MAX_CHUNKS],
+    /// Slots in use so far; every slot at or past it is empty.
+    slots: usize,
+    /// Chunks currently held from `NetMemory`.
+    live: usize,
+    /// Live chunks with no block handed out.
+    idle: usize,
+    used: usize,
+}
+
+impl BufferPool {
+    /// Create an empty pool; chunks are requested on the first allocations.
+    pub const fn new() -> Self {
+        Self {
+            chunks: [EMPTY_CHUNK; POOL_MAX_CHUNKS],
+            slots: 0,
+            live: 0,
+            idle: 0,
+            used: 0,
+        }
+    }
+
+    /// Allocate a block of at least `len` bytes, rounded up to a power of two.
+    ///
+    /// An exact-size free block is preferred over splitting a larger one, and
+    /// a new chunk is requested only when no cached chunk has room.
+    pub fn alloc(&mut self, mem: &dyn NetMemory, len: usize) -> Option<PoolBlock> {
+        let level = block_level(len)?;
+        let cached = &self.chunks[..self.slots];
+        let exact = cached.iter().position(|chunk| chunk.free[level] != 0);
+        let chunk = match exact.or_else(|| cached.iter().position(|chunk| chunk.can_split(level))) {
+            Some(chunk) => chunk,
+            None => self.add_chunk(mem)?,
+        };
+        if self.chunks[chunk].is_idle() {
+            self.idle -= 1;
+        }
+        let index = self.chunks[chunk].alloc(level)?;
+        let block = PoolBlock { chunk: chunk as u16, level: level as u8, index };
+        self.used += block.len();
+        Some(block)
+    }
+
+    /// Return `block` to the pool, merging it with its free buddy.
+    ///
+    /// A chunk left fully free goes back to `mem` unless fewer than
+    /// `POOL_IDLE_CHUNKS` idle chunks are cached.
+    pub fn free(&mut self, mem: &dyn NetMemory, block: PoolBlock) {
+        let chunk = block.chunk as usize;
+        self.chunks[chunk].release(block.level as usize, block.index);
+        self.used -= block.len();
+        if !self.chunks[chunk].is_idle() {
+            return;
+        }
+        if self.idle < POOL_IDLE_CHUNKS {
+            self.idle += 1;
+            return;
+        }
+        let base = self.chunks[chunk].base.take().expect("pool block without chunk");
+        mem.free_pages(base, POOL_CHUNK / NET_PAGE_SIZE);
+        self.chunks[chunk] = EMPTY_CHUNK;
+        self.live -= 1;
+        while self.slots > 0 && self.chunks[self.slots - 1].base.is_none() {
+            self.slots -= 1;
+        }
+    }
+
+    /// Memory of a live block.
+    ///
+    /// # Safety
+    ///
+    /// `block` must come from `alloc` and not be freed yet, and the caller must
+    /// hold the only reference to its bytes.
+    pub unsafe fn bytes(&self, block: PoolBlock) -> &'static mut [u8] {
+        let base = self.chunks[block.chunk as usize].base.expect("pool block without chunk");
+        let offset = block.index as usize * block.len();
+        slice::from_raw_parts_mut(base.as_ptr().add(offset), block.len())
+    }
+
+    /// Bytes currently handed out in blocks.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Bytes of chunks held from `NetMemory`, in use or not.
+    pub fn capacity(&self) -> usize {
+        self.live * POOL_CHUNK
+    }
+
+    fn add_chunk(&mut self, mem: &dyn NetMemory) -> Option<usize> {
+        // 优先复用已归还 chunk 留下的空槽，保持 slots 紧凑。
+        let chunk = match self.chunks[..self.slots].iter().position(|chunk| chunk.base.is_none()) {
+            Some(chunk) => chunk,
+            None if self.slots < POOL_MAX_CHUNKS => self.slots,
+            None => return None,
+        };
+        let base = mem.alloc_pages(POOL_CHUNK / NET_PAGE_SIZE)?;
+        self.chunks[chunk] = Chunk { base: Some(base), free: [1, 0, 0, 0, 0, 0] };
+        self.slots = self.slots.max(chunk + 1);
+        self.live += 1;
+        self.idle += 1;
+        Some(chunk)
+    }
+}
+
+impl Default for BufferPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Level of the smallest block holding `len` bytes; 0 is a whole chunk.
+fn block_level(len: usize) -> Option<usize> {
+    if len > POOL_CHUNK {
+        return None;
+    }
+    let size = len.max(POOL_MIN_BLOCK).next_power_of_two();
+    Some((POOL_CHUNK / size).trailing_zeros() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use std::vec::Vec;
+
+    use crate::wire::HostMemory;
+
+    /// Hands out addresses without backing memory and counts requests and returns; `bytes` is never called on it.
+    struct FakeMemory {
+        chunks: Cell<usize>,
+        freed: Cell<usize>,
+        limit: usize,
+    }
+
+    impl FakeMemory {
+        fn new(limit: usize) -> Self {
+            Self { chunks: Cell::new(0), freed: Cell::new(0), limit }
+        }
+
+        fn live(&self) -> usize {
+            self.chunks.get() - self.freed.get()
+        }
+    }
+
+    impl NetMemory for FakeMemory {
+        fn alloc_pages(&self, pages: usize) -> Option<NonNull<u8>> {
+            assert_eq!(pages, POOL_CHUNK / NET_PAGE_SIZE);
+            if self.live() == self.limit {
+                return None;
+            }
+            self.chunks.set(self.chunks.get() + 1);
+            NonNull::new((self.chunks.get() * POOL_CHUNK) as *mut u8)
+        }
+
+        fn free_pages(&self, ptr: NonNull<u8>, pages: usize) {
+            assert_eq!(pages, POOL_CHUNK / NET_PAGE_SIZE);
+            assert_eq!(ptr.as_ptr() as usize % POOL_CHUNK, 0);
+            self.freed.set(self.freed.get() + 1);
+        }
+    }
+
+    #[test]
+    fn split_blocks_merge_back_into_a_chunk() {
+        let mut pool = BufferPool::new();
+        let a = pool.alloc(&HostMemory, POOL_MIN_BLOCK).unwrap();
+        let b = pool.alloc(&HostMemory, POOL_MIN_BLOCK).unwrap();
+        assert_eq!((a.chunk, a.level, a.index), (0, LEVELS as u8 - 1, 0));
+        assert_eq!((b.chunk, b.level, b.index), (0, LEVELS as u8 - 1, 1));
+        // SAFETY: 两块都是刚分配的，各自只有这一个引用。
+        let (a_bytes, b_bytes) = unsafe { (pool.bytes(a), pool.bytes(b)) };
+        assert_eq!(b_bytes.as_ptr() as usize - a_bytes.as_ptr() as usize, POOL_MIN_BLOCK);
+        assert_eq!(pool.capacity(), POOL_CHUNK);
+
+        pool.free(&HostMemory, a);
+        pool.free(&HostMemory, b);
+        assert_eq!(pool.chunks[0].free, [1, 0, 0, 0, 0, 0]);
+        let whole = pool.alloc(&HostMemory, POOL_CHUNK).unwrap();
+        assert_eq!((whole.chunk, whole.len()), (0, POOL_CHUNK));
+        assert_eq!(pool.capacity(), POOL_CHUNK);
+    }
+
+    #[test]
+    fn exact_fit_beats_splitting() {
+        let mem = FakeMemory::new(POOL_MAX_CHUNKS);
+        let mut pool = BufferPool::new();
+        let _left = pool.alloc(&mem, POOL_CHUNK / 2).unwrap();
+        let right = pool.alloc(&mem, POOL_CHUNK / 2).unwrap();
+        // 第二个 chunk 拆到 2K，留下一个空闲的 2K 伙伴。
+        let small = pool.alloc(&mem, POOL_MIN_BLOCK).unwrap();
+        assert_eq!(small.chunk, 1);
+        pool.free(&mem, right);
+
+        // chunk 0 有可拆分的 32K，但 chunk 1 恰好有空闲的 2K。
+        let exact = pool.alloc(&mem, POOL_MIN_BLOCK).unwrap();
+        assert_eq!((exact.chunk, exact.index), (1, small.index ^ 1));
+        let larger = pool.alloc(&mem, POOL_MIN_BLOCK * 2).unwrap();
+        assert_eq!(larger.chunk, 1);
+        assert_eq!(mem.chunks.get(), 2);
+    }
+
+    #[test]
+    fn chunks_run_out_at_the_cap() {
+        let mem = FakeMemory::new(POOL_MAX_CHUNKS + 1);
+        let mut pool = BufferPool::new();
+        let blocks: Vec<_> = (0..POOL_MAX_CHUNKS).map(|_| pool.alloc(&mem, POOL_CHUNK).unwrap()).collect();
+        assert_eq!(pool.alloc(&mem, POOL_MIN_BLOCK), None);
+        assert_eq!(mem.chunks.get(), POOL_MAX_CHUNKS);
+        assert_eq!(pool.capacity(), POOL_MAX_CHUNKS * POOL_CHUNK);
+
+        // 空出的 chunk 先留在池中复用，不再向 NetMemory 申请。
+        pool.free(&mem, blocks[7]);
+        assert_eq!(pool.alloc(&mem, POOL_MIN_BLOCK).map(|block| block.chunk), Some(7));
+        assert_eq!((mem.chunks.get(), mem.freed.get()), (POOL_MAX_CHUNKS, 0));
+    }
+
+    #[test]
+    fn free_chunks_past_the_idle_cache_are_returned() {
+        let mem = FakeMemory::new(POOL_MAX_CHUNKS);
+        let mut pool = BufferPool::new();
+        let count = POOL_IDLE_CHUNKS + 3;
+        let blocks: Vec<_> = (0..count).map(|_| pool.alloc(&mem, POOL_CHUNK).unwrap()).collect();
+        for &block in blocks.iter().take(POOL_IDLE_CHUNKS) {
+            pool.free(&mem, block);
+        }
+        assert_eq!(mem.freed.get(), 0);
+
+        // 空闲缓存已满，之后整块空出的 chunk 立即归还。
+        pool.free(&mem, blocks[count - 1]);
+        assert_eq!(mem.freed.get(), 1);
+        pool.free(&mem, blocks[POOL_IDLE_CHUNKS]);
+        assert_eq!(mem.freed.get(), 2);
+        assert_eq!(pool.capacity(), (count - 2) * POOL_CHUNK);
+        assert_eq!(pool.slots, count - 1);
+
+        // 缓存的空闲 chunk 优先复用，用完后才占回空槽并重新申请。
+        for _ in 0..POOL_IDLE_CHUNKS {
+            assert!(pool.alloc(&mem, POOL_CHUNK).unwrap().chunk < POOL_IDLE_CHUNKS as u16);
+        }
+        assert_eq!(pool.alloc(&mem, POOL_CHUNK).map(|block| block.chunk), Some(POOL_IDLE_CHUNKS as u16));
+        assert_eq!((mem.chunks.get(), mem.live()), (count + 1, count - 1));
+    }
+
+    #[test]
+    fn used_counts_rounded_block_sizes() {
+        let mem = FakeMemory::new(1);
+        let mut pool = BufferPool::new();
+        assert_eq!((pool.used(), pool.capacity()), (0, 0));
+        let a = pool.alloc(&mem, 1).unwrap();
+        let b = pool.alloc(&mem, 3000).unwrap();
+        assert_eq!((a.len(), b.len()), (POOL_MIN_BLOCK, 4096));
+        assert_eq!(pool.used(), POOL_MIN_BLOCK + 4096);
+        assert_eq!(pool.alloc(&mem, POOL_CHUNK + 1), None);
+        // 唯一的 chunk 已被拆开，整块请求拿不到新 chunk。
+        assert_eq!(pool.alloc(&mem, POOL_CHUNK), None);
+        assert_eq!(pool.used(), POOL_MIN_BLOCK + 4096);
+
+        pool.free(&mem, b);
+        pool.free(&mem, a);
+        assert_eq!((pool.used(), pool.capacity()), (0, POOL_CHUNK));
+        assert_eq!(mem.freed.get(), 0);
+    }
+}
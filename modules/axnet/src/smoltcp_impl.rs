@@ -1,41 +1,86 @@
 #![allow(dead_code)]
 //! smoltcp-based network stack glue.
 
-use core::mem::MaybeUninit;
-use core::ptr;
+use core::mem::{align_of, size_of, MaybeUninit};
+use core::ops::{Deref, DerefMut};
+use core::ptr::{self, NonNull};
+use core::slice;
 use core::sync::atomic::{AtomicBool, AtomicU16, AtomicU32, Ordering};
 
-use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet, SocketStorage};
-use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::iface::{Config, Interface, MulticastError, SocketHandle, SocketSet, SocketStorage};
+use smoltcp::phy::{ChecksumCapabilities, Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::socket::dhcpv4::{Event as Dhcpv4Event, Socket as Dhcpv4Socket};
 use smoltcp::socket::icmp::{
     Endpoint as IcmpEndpoint, PacketBuffer as IcmpPacketBuffer, PacketMetadata as IcmpPacketMetadata,
     Socket as IcmpSocket,
 };
+use smoltcp::socket::raw::{PacketBuffer as RawPacketBuffer, PacketMetadata as RawPacketMetadata, Socket as RawSocket};
 use smoltcp::socket::tcp::{Socket as TcpSocket, SocketBuffer as TcpSocketBuffer, State as TcpState};
 use smoltcp::socket::udp::{PacketBuffer as UdpPacketBuffer, PacketMetadata as UdpPacketMetadata, Socket as UdpSocket};
-use smoltcp::time::Instant;
+use smoltcp::time::{Duration, Instant};
 use smoltcp::wire::{
-    ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol, Icmpv4Packet,
-    Icmpv4Repr, IpAddress, IpCidr, IpEndpoint, IpListenEndpoint, Ipv4Address,
+    ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol, EthernetRepr, Icmpv4Packet,
+    Icmpv4Repr, Icmpv6Packet, Icmpv6Repr, IpAddress, IpCidr, IpEndpoint, IpListenEndpoint, IpProtocol, IpVersion,
+    Ipv4Address, Ipv4Cidr, Ipv4Packet, Ipv4Repr, Ipv6Address, Ipv6Cidr, Ipv6Packet, Ipv6Repr, NdiscNeighborFlags,
+    NdiscPrefixInfoFlags, NdiscRepr, TcpPacket,
 };
 
-use crate::{NetDevice, NetError};
+use crate::iface::{IfaceInfo, Ipv4Config, NetConfig, IFF_BROADCAST, IFF_LOOPBACK, IFF_MULTICAST, IFF_RUNNING, IFF_UP};
+use crate::pool::{BufferPool, PoolBlock, NET_PAGE_SIZE};
+use crate::sockopt::{SockOpt, SocketOptions};
+use crate::{NetDevice, NetError, NetMemory};
 
-const NET_MTU: usize = 1500;
+/// MTU of eth0 and `lo`. Nothing is fragmented on the way out, so no datagram
+/// (headers included) may be larger.
+pub const NET_MTU: usize = 1500;
 const NET_BUF_SIZE: usize = 2048;
-const MAX_SOCKETS: usize = 8;
-const SOCKET_STORAGE_LEN: usize = MAX_SOCKETS + 1;
+/// Socket table size at boot; it doubles on demand up to `MAX_SOCKETS`.
+const INITIAL_SOCKETS: usize = 16;
+const MAX_SOCKETS: usize = 1024;
+/// Upper bound on the accept queue of one listener (connections handshaking or
+/// waiting for accept(2)), Linux's historical `SOMAXCONN`.
+const MAX_BACKLOG: usize = 128;
+/// Pre-listening sockets kept per listener; each holds TCP buffers from the
+/// pool, so only a few wait for SYNs and more are opened as they are taken.
+const ACCEPT_POOL_IDLE: usize = 4;
+/// Multicast groups one socket may join (Linux `IP_MAX_MEMBERSHIPS` is 20).
+const SOCKET_MAX_GROUPS: usize = 8;
+// smoltcp 存储比槽位多三项：网关 ping 的 ICMP socket、DHCP 客户端，以及调整 TCP 缓冲区时新旧 socket 并存的一项。
+const RESERVED_SOCKETS: usize = 3;
 const ICMP_META_LEN: usize = 4;
 const ICMP_BUF_LEN: usize = 256;
 // Increase TCP buffers to reduce window exhaustion during perf tests.
 const TCP_BUF_LEN: usize = 65536;
 const UDP_BUF_LEN: usize = 2048;
-const UDP_META_LEN: usize = 4;
+/// Room for packet metadata added to each datagram buffer block; entries fill whatever the block has left.
+const DGRAM_META_BYTES: usize = 256;
+/// smoltcp's delayed-ACK timeout, restored when `TCP_QUICKACK` is cleared.
+const TCP_ACK_DELAY_MS: u64 = 10;
+/// How long a closed TCP socket may keep its slot while the FIN handshake finishes.
+const TCP_ORPHAN_TIMEOUT_MS: u64 = 60_000;
 const ARP_POLL_RETRY: u16 = 8;
-
-const NET_IPV4_ADDR: [u8; 4] = [10, 0, 2, 15];
-const NET_IPV4_GATEWAY: [u8; 4] = [10, 0, 2, 2];
-const NET_IPV4_PREFIX: u8 = 24;
+/// How long DHCP may go without a lease before the static fallback is applied.
+const DHCP_FALLBACK_MS: u64 = 3000;
+
+const NET_LOOPBACK_ADDR: [u8; 4] = [127, 0, 0, 1];
+const NET_LOOPBACK_PREFIX: u8 = 8;
+const NET_IPV6_LINK_LOCAL_PREFIX: Ipv6Address = Ipv6Address([0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+const NET_IPV6_SLAAC_PREFIX: u8 = 64;
+/// Router solicitations sent at boot and their spacing (RFC 4861 `MAX_RTR_SOLICITATIONS`/`RTR_SOLICITATION_INTERVAL`).
+const NDP_RS_COUNT: u8 = 3;
+const NDP_RS_INTERVAL_MS: u64 = 4000;
+const AF_INET: i32 = 2;
+const AF_INET6: i32 = 10;
+const IPPROTO_ICMP: i32 = 1;
+const IPPROTO_TCP: i32 = 6;
+const IPPROTO_UDP: i32 = 17;
+const IPPROTO_ICMPV6: i32 = 58;
+/// Hop limit for IP headers built by raw sockets, matching smoltcp's default.
+const DEFAULT_HOP_LIMIT: u8 = 64;
+// 没有网卡时使用的本地管理 MAC，帧只在 lo 上流转。
+const NET_LOOPBACK_MAC: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+const LOOPBACK_QUEUE_LEN: usize = 32;
+const SELFTEST_QUEUE_LEN: usize = 8;
 // poll/ppoll 事件位与 syscall 侧保持一致。
 const NET_POLLIN: u16 = 0x001;
 const NET_POLLOUT: u16 = 0x004;
@@ -44,6 +89,7 @@ const NET_POLLHUP: u16 = 0x010;
 const LOOPBACK_PORT: u16 = 40000;
 
 static NET_READY: AtomicBool = AtomicBool::new(false);
+static NET_HAS_NIC: AtomicBool = AtomicBool::new(false);
 static NET_NEED_POLL: AtomicBool = AtomicBool::new(false);
 static NET_PING_REQUESTED: AtomicBool = AtomicBool::new(false);
 static NET_ARP_REQUESTED: AtomicBool = AtomicBool::new(false);
@@ -52,28 +98,33 @@ static NET_ARP_REPLY_IP: AtomicU32 = AtomicU32::new(0);
 static NET_ARP_SENT_IP: AtomicU32 = AtomicU32::new(0);
 static NET_RX_SEEN: AtomicBool = AtomicBool::new(false);
 static NEXT_EPHEMERAL_PORT: AtomicU16 = AtomicU16::new(49152);
+static NEXT_ACCEPT_SEQ: AtomicU32 = AtomicU32::new(1);
 static NET_POLLING: AtomicBool = AtomicBool::new(false);
+// eth0 当前地址与网关（0 表示未配置），供帧级辅助函数在不借用 NET_STATE 时读取。
+static NET_IPV4_ADDR: AtomicU32 = AtomicU32::new(0);
+static NET_IPV4_GATEWAY: AtomicU32 = AtomicU32::new(0);
+// eth0 的 IPv6 地址（链路本地、SLAAC 全局），未配置时为全零；与 NET_STATE 同样只在单核上访问。
+static mut NET_IPV6_ADDRS: [Ipv6Address; 2] = [Ipv6Address::UNSPECIFIED; 2];
+// 收包路径记录的最近一次可用于 SLAAC 的路由通告：(前缀, 路由器地址, 是否可作默认路由)。
+static mut NET_ROUTER_ADVERT: Option<(Ipv6Address, Ipv6Address, bool)> = None;
 
 static mut RX_BUF: [u8; NET_BUF_SIZE] = [0; NET_BUF_SIZE];
 static mut TX_BUF: [u8; NET_BUF_SIZE] = [0; NET_BUF_SIZE];
-static mut LOOPBACK_QUEUE: LoopbackQueue = LoopbackQueue::new();
+static mut LOOPBACK_QUEUE: LoopbackQueue<LOOPBACK_QUEUE_LEN> = LoopbackQueue::new();
 static mut ICMP_RX_META: [IcmpPacketMetadata; ICMP_META_LEN] = [IcmpPacketMetadata::EMPTY; ICMP_META_LEN];
 static mut ICMP_RX_BUF: [u8; ICMP_BUF_LEN] = [0; ICMP_BUF_LEN];
 static mut ICMP_TX_META: [IcmpPacketMetadata; ICMP_META_LEN] = [IcmpPacketMetadata::EMPTY; ICMP_META_LEN];
 static mut ICMP_TX_BUF: [u8; ICMP_BUF_LEN] = [0; ICMP_BUF_LEN];
-static mut TCP_RX_BUF: [[u8; TCP_BUF_LEN]; MAX_SOCKETS] = [[0; TCP_BUF_LEN]; MAX_SOCKETS];
-static mut TCP_TX_BUF: [[u8; TCP_BUF_LEN]; MAX_SOCKETS] = [[0; TCP_BUF_LEN]; MAX_SOCKETS];
-static mut UDP_RX_META: [[UdpPacketMetadata; UDP_META_LEN]; MAX_SOCKETS] =
-    [[UdpPacketMetadata::EMPTY; UDP_META_LEN]; MAX_SOCKETS];
-static mut UDP_RX_BUF: [[u8; UDP_BUF_LEN]; MAX_SOCKETS] = [[0; UDP_BUF_LEN]; MAX_SOCKETS];
-static mut UDP_TX_META: [[UdpPacketMetadata; UDP_META_LEN]; MAX_SOCKETS] =
-    [[UdpPacketMetadata::EMPTY; UDP_META_LEN]; MAX_SOCKETS];
-static mut UDP_TX_BUF: [[u8; UDP_BUF_LEN]; MAX_SOCKETS] = [[0; UDP_BUF_LEN]; MAX_SOCKETS];
+// SAFETY: 缓冲池与 NET_STATE 一样只在单核上串行访问；块在 socket 释放时归还。
+static mut BUFFER_POOL: BufferPool = BufferPool::new();
 static mut ARP_TX_BUF: [u8; 64] = [0; 64];
+static mut NDP_TX_BUF: [u8; 128] = [0; 128];
 const ARP_FRAME_LEN: usize = 42;
 
+/// Interface device: frames for `lo` addresses stay in `LOOPBACK_QUEUE`, the rest go to the NIC if any.
 struct SmolDevice {
-    dev: &'static dyn NetDevice,
+    dev: Option<&'static dyn NetDevice>,
+    mac: EthernetAddress,
 }
 
 struct SmolRxToken {
@@ -81,7 +132,8 @@ struct SmolRxToken {
 }
 
 struct SmolTxToken<'a> {
-    dev: &'a dyn NetDevice,
+    dev: Option<&'a dyn NetDevice>,
+    mac: EthernetAddress,
 }
 
 /// Socket identifier used by the network stack.
@@ -99,26 +151,50 @@ impl Device for SmolDevice {
     }
 
     fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        loop {
+            let len = self.recv_frame()?;
+            // 监听队列已满时丢弃 SYN，而不是让 smoltcp 回 RST；对端超时后会重传。
+            // SAFETY: static buffers/state are initialized and accessed under net state lock.
+            if !syn_for_full_listener(unsafe { &RX_BUF[..len] }) {
+                return Some((SmolRxToken { len }, self.tx_token()));
+            }
+        }
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(self.tx_token())
+    }
+}
+
+impl SmolDevice {
+    /// Pull the next frame into `RX_BUF`; returns its length.
+    fn recv_frame(&mut self) -> Option<usize> {
         // Loopback frames take priority to wake local TCP listeners.
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
         let loopback_len = unsafe { LOOPBACK_QUEUE.pop(&mut RX_BUF) };
         if let Some(len) = loopback_len {
             let _ = NET_RX_SEEN.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire);
-            return Some((SmolRxToken { len }, SmolTxToken { dev: self.dev }));
+            return Some(len);
         }
-        if !self.dev.poll() {
+        let dev = self.dev?;
+        if !dev.poll() {
             return None;
         }
         // SAFETY: single-token receive; buffer is reused once token is consumed.
-        let len = unsafe { self.dev.recv(&mut RX_BUF).ok()? };
+        let len = unsafe { dev.recv(&mut RX_BUF).ok()? };
         let _ = NET_RX_SEEN.compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire);
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
         record_arp_reply(unsafe { &RX_BUF[..len] });
-        Some((SmolRxToken { len }, SmolTxToken { dev: self.dev }))
+        // SAFETY: static buffers/state are initialized and accessed under net state lock.
+        record_router_advert(unsafe { &RX_BUF[..len] });
+        Some(len)
     }
 
-    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
-        Some(SmolTxToken { dev: self.dev })
+    fn tx_token(&self) -> SmolTxToken<'static> {
+        SmolTxToken {
+            dev: self.dev,
+            mac: self.mac,
+        }
     }
 }
 
@@ -144,8 +220,7 @@ impl TxToken for SmolTxToken<'_> {
         // SAFETY: TX buffer is used by a single token at a time.
         let buf = unsafe { &mut TX_BUF[..len] };
         let result = f(buf);
-        let mac = EthernetAddress(self.dev.mac_address());
-        if try_loopback_arp(buf, mac) {
+        if try_loopback_arp(buf, self.mac) || try_loopback_ndp(buf, self.mac) {
             return result;
         }
         if should_loopback(buf) {
@@ -156,7 +231,16 @@ impl TxToken for SmolTxToken<'_> {
             NET_NEED_POLL.store(true, Ordering::Release);
             return result;
         }
-        let _ = self.dev.send(buf);
+        if is_udp_group_frame(buf) {
+            // SAFETY: single-hart; loopback queue is only touched here and in receive.
+            unsafe {
+                LOOPBACK_QUEUE.push(buf);
+            }
+            NET_NEED_POLL.store(true, Ordering::Release);
+        }
+        if let Some(dev) = self.dev {
+            let _ = dev.send(buf);
+        }
         result
     }
 }
@@ -165,25 +249,55 @@ struct NetState {
     iface: Interface,
     sockets: SocketSet<'static>,
     device: SmolDevice,
+    /// Pages behind the buffer pool and the socket tables.
+    mem: &'static dyn NetMemory,
     icmp_handle: SocketHandle,
     ping_ident: u16,
     ping_seq: u16,
+    /// Boot policy for eth0; its fallback is applied when DHCP has no lease.
+    config: NetConfig,
+    dhcp_handle: Option<SocketHandle>,
+    /// Uptime after which the fallback is applied; set on the first poll.
+    dhcp_deadline_ms: Option<u64>,
+    /// Current eth0 IPv4 settings and whether they came from a DHCP lease.
+    eth: Option<Ipv4Config>,
+    eth_dhcp: bool,
+    /// eth0 IPv6 addresses: EUI-64 link-local, and the global one formed from a router advertisement.
+    eth6_link_local: Option<Ipv6Cidr>,
+    eth6_global: Option<Ipv6Cidr>,
+    /// Router solicitations still to send, and when the next one is due.
+    rs_left: u8,
+    rs_next_ms: u64,
+    /// Time of the last poll; IGMP reports sent from a setsockopt call are stamped with it.
+    now: Instant,
 }
 
 // SAFETY: global net state is serialized by single-hart boot and idle loop.
 static mut NET_STATE: Option<NetState> = None;
 
-/// Initialize the network stack with the provided device.
-pub fn init(dev: &'static dyn NetDevice) -> Result<(), NetError> {
+/// Initialize the network stack.
+///
+/// `lo` (127.0.0.1/8, ::1) is always configured; `dev`, when present, adds eth0,
+/// addressed according to `config` (static now, or DHCP from the first poll),
+/// plus an IPv6 link-local address and SLAAC from router advertisements.
+/// Socket tables and buffers are taken from `mem` as sockets are created.
+pub fn init(
+    dev: Option<&'static dyn NetDevice>,
+    mem: &'static dyn NetMemory,
+    config: NetConfig,
+) -> Result<(), NetError> {
     if NET_READY.load(Ordering::Acquire) {
         return Ok(());
     }
+    if !grow_socket_tables(mem, None) {
+        return Err(NetError::NoMem);
+    }
 
-    let mac = dev.mac_address();
+    let mac = dev.map_or(NET_LOOPBACK_MAC, |dev| dev.mac_address());
     let hw_addr = EthernetAddress(mac);
-    let ip = IpCidr::new(IpAddress::v4(NET_IPV4_ADDR[0], NET_IPV4_ADDR[1], NET_IPV4_ADDR[2], NET_IPV4_ADDR[3]), NET_IPV4_PREFIX);
+    NET_HAS_NIC.store(dev.is_some(), Ordering::Release);
 
-    let mut device = SmolDevice { dev };
+    let mut device = SmolDevice { dev, mac: hw_addr };
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let mut sockets = unsafe { SocketSet::new(&mut SOCKET_STORAGE[..]) };
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
@@ -192,30 +306,39 @@ pub fn init(dev: &'static dyn NetDevice) -> Result<(), NetError> {
     let icmp_tx = unsafe { IcmpPacketBuffer::new(&mut ICMP_TX_META[..], &mut ICMP_TX_BUF[..]) };
     let icmp_socket = IcmpSocket::new(icmp_rx, icmp_tx);
     let icmp_handle = sockets.add(icmp_socket);
+    let dhcp_handle = match config {
+        NetConfig::Dhcp { .. } if dev.is_some() => Some(sockets.add(Dhcpv4Socket::new())),
+        _ => None,
+    };
 
-    let mut config = Config::new(hw_addr.into());
-    config.random_seed = 0x1234_5678;
-    let mut iface = Interface::new(config, &mut device, Instant::from_millis(0));
-    iface.update_ip_addrs(|addrs| {
-        let _ = addrs.push(ip);
-    });
-    let gateway = Ipv4Address::new(
-        NET_IPV4_GATEWAY[0],
-        NET_IPV4_GATEWAY[1],
-        NET_IPV4_GATEWAY[2],
-        NET_IPV4_GATEWAY[3],
-    );
-    let _ = iface.routes_mut().add_default_ipv4_route(gateway);
+    let mut iface_config = Config::new(hw_addr.into());
+    iface_config.random_seed = 0x1234_5678;
+    let iface = Interface::new(iface_config, &mut device, Instant::from_millis(0));
 
     let ping_ident = u16::from_le_bytes([mac[4], mac[5]]);
-    let state = NetState {
+    let mut state = NetState {
         iface,
         sockets,
         device,
+        mem,
         icmp_handle,
         ping_ident,
         ping_seq: 1,
+        config,
+        dhcp_handle,
+        dhcp_deadline_ms: None,
+        eth: None,
+        eth_dhcp: false,
+        eth6_link_local: dev.map(|_| Ipv6Cidr::new(eui64_address(NET_IPV6_LINK_LOCAL_PREFIX, mac), 64)),
+        eth6_global: None,
+        rs_left: if dev.is_some() { NDP_RS_COUNT } else { 0 },
+        rs_next_ms: 0,
+        now: Instant::from_millis(0),
     };
+    sync_ip_addrs(&mut state);
+    if let (Some(_), NetConfig::Static(static_config)) = (dev, config) {
+        apply_ipv4(&mut state, Some(static_config), false);
+    }
 
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     unsafe {
@@ -242,6 +365,12 @@ pub enum NetEvent {
     ArpReply { from: Ipv4Address },
     ArpProbeSent { target: Ipv4Address },
     RxFrameSeen,
+    /// eth0 got a new address: from a DHCP lease when `dhcp`, else the static fallback.
+    Ipv4Configured { config: Ipv4Config, dhcp: bool },
+    /// eth0 lost its DHCP lease and has no fallback address.
+    Ipv4Deconfigured,
+    /// eth0 formed a global IPv6 address from a router advertisement.
+    Ipv6Configured { addr: Ipv6Address, prefix_len: u8 },
     TcpRecvWindow {
         id: SocketId,
         port: u16,
@@ -275,13 +404,10 @@ pub fn poll(now_ms: u64) -> Option<NetEvent> {
     let Some(state) = (unsafe { NET_STATE.as_mut() }) else {
         return None;
     };
-    if NET_PING_REQUESTED.swap(false, Ordering::AcqRel) {
-        let gateway = IpAddress::Ipv4(Ipv4Address::new(
-            NET_IPV4_GATEWAY[0],
-            NET_IPV4_GATEWAY[1],
-            NET_IPV4_GATEWAY[2],
-            NET_IPV4_GATEWAY[3],
-        ));
+    // 网关探测要等 eth0 拿到地址和网关（DHCP 租约或静态配置）后才发出。
+    let gateway_ready = gateway_ipv4().is_some();
+    if gateway_ready && NET_PING_REQUESTED.swap(false, Ordering::AcqRel) {
+        let gateway = IpAddress::Ipv4(gateway_ipv4().unwrap_or(Ipv4Address::UNSPECIFIED));
         let socket = state.sockets.get_mut::<IcmpSocket>(state.icmp_handle);
         if !socket.is_open() {
             let _ = socket.bind(IcmpEndpoint::Ident(state.ping_ident));
@@ -303,18 +429,27 @@ pub fn poll(now_ms: u64) -> Option<NetEvent> {
             NET_NEED_POLL.store(true, Ordering::Release);
         }
     }
-    if NET_ARP_REQUESTED.swap(false, Ordering::AcqRel) {
+    if gateway_ready && NET_ARP_REQUESTED.swap(false, Ordering::AcqRel) {
         send_arp_probe(state);
         NET_ARP_PENDING.store(ARP_POLL_RETRY, Ordering::Release);
         NET_NEED_POLL.store(true, Ordering::Release);
     }
+    state.now = timestamp;
     let activity = state
         .iface
         .poll(timestamp, &mut state.device, &mut state.sockets);
+    update_accept_queue(state);
+    reap_orphans(state);
     let pending_tcp = has_pending_tcp(state);
     if pending_tcp {
         NET_NEED_POLL.store(true, Ordering::Release);
     }
+    if let Some(event) = poll_dhcp(state, now_ms) {
+        return Some(event);
+    }
+    if let Some(event) = poll_slaac(state, now_ms) {
+        return Some(event);
+    }
 
     if let Some(target) = take_arp_sent() {
         return Some(NetEvent::ArpProbeSent { target });
@@ -384,18 +519,18 @@ pub fn tcp_loopback_test_once() -> Result<(), NetError> {
     run_tcp_loopback()
 }
 
-struct LoopbackQueue {
-    frames: [[u8; NET_BUF_SIZE]; 8],
-    lens: [usize; 8],
+struct LoopbackQueue<const N: usize> {
+    frames: [[u8; NET_BUF_SIZE]; N],
+    lens: [usize; N],
     head: usize,
     tail: usize,
 }
 
-impl LoopbackQueue {
+impl<const N: usize> LoopbackQueue<N> {
     const fn new() -> Self {
         Self {
-            frames: [[0; NET_BUF_SIZE]; 8],
-            lens: [0; 8],
+            frames: [[0; NET_BUF_SIZE]; N],
+            lens: [0; N],
             head: 0,
             tail: 0,
         }
@@ -425,7 +560,7 @@ impl LoopbackQueue {
 }
 
 struct LoopbackDevice {
-    queue: LoopbackQueue,
+    queue: LoopbackQueue<SELFTEST_QUEUE_LEN>,
     rx_buf: [u8; NET_BUF_SIZE],
 }
 
@@ -444,7 +579,7 @@ struct LoopRxToken<'a> {
 }
 
 struct LoopTxToken<'a> {
-    queue: &'a mut LoopbackQueue,
+    queue: &'a mut LoopbackQueue<SELFTEST_QUEUE_LEN>,
 }
 
 impl Device for LoopbackDevice {
@@ -605,6 +740,9 @@ pub fn ping_gateway_once() -> Result<(), NetError> {
     if !NET_READY.load(Ordering::Acquire) {
         return Err(NetError::NotReady);
     }
+    if !NET_HAS_NIC.load(Ordering::Acquire) {
+        return Err(NetError::Unreachable);
+    }
     NET_PING_REQUESTED.store(true, Ordering::Release);
     NET_NEED_POLL.store(true, Ordering::Release);
     Ok(())
@@ -615,82 +753,699 @@ pub fn arp_probe_gateway_once() -> Result<(), NetError> {
     if !NET_READY.load(Ordering::Acquire) {
         return Err(NetError::NotReady);
     }
+    if !NET_HAS_NIC.load(Ordering::Acquire) {
+        return Err(NetError::Unreachable);
+    }
     NET_ARP_REQUESTED.store(true, Ordering::Release);
     NET_NEED_POLL.store(true, Ordering::Release);
     Ok(())
 }
 
+/// Return interface `n` in enumeration order (`lo`, then eth0 when a NIC is attached).
+pub fn iface_info(n: usize) -> Option<IfaceInfo> {
+    if !NET_READY.load(Ordering::Acquire) {
+        return None;
+    }
+    // SAFETY: NET_STATE is only read here after init.
+    let state = unsafe { NET_STATE.as_ref() }?;
+    match n {
+        0 => Some(IfaceInfo {
+            index: 1,
+            name: "lo",
+            mac: [0; 6],
+            ipv4: Some((Ipv4Address(NET_LOOPBACK_ADDR), NET_LOOPBACK_PREFIX)),
+            flags: IFF_UP | IFF_LOOPBACK | IFF_RUNNING,
+            mtu: NET_MTU,
+        }),
+        1 if state.device.dev.is_some() => Some(IfaceInfo {
+            index: 2,
+            name: "eth0",
+            mac: state.device.mac.0,
+            ipv4: state.eth.map(|config| (config.addr, config.prefix_len)),
+            flags: IFF_UP | IFF_BROADCAST | IFF_RUNNING | IFF_MULTICAST,
+            mtu: NET_MTU,
+        }),
+        _ => None,
+    }
+}
+
+/// Look up an interface by name.
+pub fn iface_by_name(name: &str) -> Option<IfaceInfo> {
+    (0..2).filter_map(iface_info).find(|info| info.name == name)
+}
+
+/// Assign an IPv4 address to an interface (`SIOCSIFADDR`/`SIOCSIFNETMASK`).
+///
+/// Manual configuration of eth0 stops the DHCP client so a later lease cannot
+/// override it; the gateway is kept only if it is still on-link. `lo` only
+/// accepts its fixed 127.0.0.1/8.
+pub fn iface_set_ipv4(name: &str, addr: Ipv4Address, prefix_len: u8) -> Result<(), NetError> {
+    if !NET_READY.load(Ordering::Acquire) {
+        return Err(NetError::NotReady);
+    }
+    // SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    if prefix_len > 32 {
+        return Err(NetError::Invalid);
+    }
+    if name == "lo" {
+        return if addr == Ipv4Address(NET_LOOPBACK_ADDR) && prefix_len == NET_LOOPBACK_PREFIX {
+            Ok(())
+        } else {
+            Err(NetError::Unsupported)
+        };
+    }
+    if name != "eth0" || state.device.dev.is_none() {
+        return Err(NetError::Invalid);
+    }
+    if addr.is_unspecified() || addr.is_broadcast() || addr.is_multicast() {
+        return Err(NetError::Invalid);
+    }
+    if let Some(handle) = state.dhcp_handle.take() {
+        state.sockets.remove(handle);
+    }
+    let subnet = Ipv4Cidr::new(addr, prefix_len);
+    let gateway = state
+        .eth
+        .and_then(|config| config.gateway)
+        .filter(|gateway| subnet.contains_addr(gateway));
+    apply_ipv4(
+        state,
+        Some(Ipv4Config {
+            addr,
+            prefix_len,
+            gateway,
+        }),
+        false,
+    );
+    Ok(())
+}
+
+/// Drive the DHCP client: apply lease changes, and the static fallback when no lease arrives in time.
+fn poll_dhcp(state: &mut NetState, now_ms: u64) -> Option<NetEvent> {
+    let handle = state.dhcp_handle?;
+    let fallback = match state.config {
+        NetConfig::Dhcp { fallback } => fallback,
+        _ => None,
+    };
+    let lease = match state.sockets.get_mut::<Dhcpv4Socket>(handle).poll() {
+        Some(Dhcpv4Event::Configured(lease)) => Some(Ipv4Config {
+            addr: lease.address.address(),
+            prefix_len: lease.address.prefix_len(),
+            gateway: lease.router,
+        }),
+        Some(Dhcpv4Event::Deconfigured) => {
+            // 首次 poll 也会报告 Deconfigured；只有此前地址来自租约时才需要回退。
+            if !state.eth_dhcp {
+                return None;
+            }
+            state.dhcp_deadline_ms = None;
+            apply_ipv4(state, fallback, false);
+            return Some(match fallback {
+                Some(config) => NetEvent::Ipv4Configured { config, dhcp: false },
+                None => NetEvent::Ipv4Deconfigured,
+            });
+        }
+        None => None,
+    };
+    if let Some(config) = lease {
+        apply_ipv4(state, Some(config), true);
+        return Some(NetEvent::Ipv4Configured { config, dhcp: true });
+    }
+    let deadline = *state
+        .dhcp_deadline_ms
+        .get_or_insert(now_ms.saturating_add(DHCP_FALLBACK_MS));
+    if state.eth.is_some() || now_ms < deadline {
+        return None;
+    }
+    let config = fallback?;
+    apply_ipv4(state, Some(config), false);
+    Some(NetEvent::Ipv4Configured { config, dhcp: false })
+}
+
+/// Replace eth0's IPv4 address and default route, keeping `lo` untouched.
+fn apply_ipv4(state: &mut NetState, config: Option<Ipv4Config>, dhcp: bool) {
+    state.eth = config;
+    state.eth_dhcp = dhcp && config.is_some();
+    sync_ip_addrs(state);
+    let gateway = config.and_then(|config| config.gateway);
+    state.iface.routes_mut().remove_default_ipv4_route();
+    if let Some(gateway) = gateway {
+        let _ = state.iface.routes_mut().add_default_ipv4_route(gateway);
+    }
+    NET_IPV4_ADDR.store(config.map_or(0, |config| ipv4_to_u32(config.addr)), Ordering::Release);
+    NET_IPV4_GATEWAY.store(gateway.map_or(0, ipv4_to_u32), Ordering::Release);
+    NET_NEED_POLL.store(true, Ordering::Release);
+}
+
+/// Rebuild the interface address list from `state`.
+///
+/// eth0 addresses go first and `lo` last: smoltcp uses the first address of the
+/// destination's family as the source, and local destinations still loop back.
+fn sync_ip_addrs(state: &mut NetState) {
+    let eth4 = state.eth.map(|config| IpCidr::new(IpAddress::Ipv4(config.addr), config.prefix_len));
+    let eth6 = [state.eth6_global, state.eth6_link_local];
+    state.iface.update_ip_addrs(|addrs| {
+        addrs.clear();
+        if let Some(cidr) = eth4 {
+            let _ = addrs.push(cidr);
+        }
+        for cidr in eth6.into_iter().flatten() {
+            let _ = addrs.push(IpCidr::Ipv6(cidr));
+        }
+        let _ = addrs.push(IpCidr::new(IpAddress::Ipv4(Ipv4Address(NET_LOOPBACK_ADDR)), NET_LOOPBACK_PREFIX));
+        let _ = addrs.push(IpCidr::new(IpAddress::Ipv6(Ipv6Address::LOOPBACK), 128));
+    });
+    // SAFETY: single-hart; the frame helpers read these between polls.
+    unsafe {
+        NET_IPV6_ADDRS = eth6.map(|cidr| cidr.map_or(Ipv6Address::UNSPECIFIED, |cidr| cidr.address()));
+    }
+}
+
+/// Send boot-time router solicitations and form a global address from the latest router advertisement.
+fn poll_slaac(state: &mut NetState, now_ms: u64) -> Option<NetEvent> {
+    if state.rs_left > 0 && now_ms >= state.rs_next_ms {
+        send_router_solicit(state);
+        state.rs_left -= 1;
+        state.rs_next_ms = now_ms.saturating_add(NDP_RS_INTERVAL_MS);
+    }
+    // SAFETY: single-hart; written by the receive path during iface.poll.
+    let (prefix, router, default_route) = unsafe { NET_ROUTER_ADVERT.take() }?;
+    if default_route {
+        let _ = state.iface.routes_mut().add_default_ipv6_route(router);
+    } else {
+        state.iface.routes_mut().remove_default_ipv6_route();
+    }
+    let cidr = Ipv6Cidr::new(eui64_address(prefix, state.device.mac.0), NET_IPV6_SLAAC_PREFIX);
+    state.rs_left = 0;
+    if state.eth6_global == Some(cidr) {
+        return None;
+    }
+    state.eth6_global = Some(cidr);
+    sync_ip_addrs(state);
+    NET_NEED_POLL.store(true, Ordering::Release);
+    Some(NetEvent::Ipv6Configured {
+        addr: cidr.address(),
+        prefix_len: NET_IPV6_SLAAC_PREFIX,
+    })
+}
+
+/// Combine the upper 64 bits of `prefix` with the modified EUI-64 interface id derived from `mac`.
+fn eui64_address(prefix: Ipv6Address, mac: [u8; 6]) -> Ipv6Address {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&prefix.0[..8]);
+    bytes[8..].copy_from_slice(&[mac[0] ^ 0x02, mac[1], mac[2], 0xff, 0xfe, mac[3], mac[4], mac[5]]);
+    Ipv6Address(bytes)
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum AxSocketKind {
     Tcp,
     Udp,
+    /// `SOCK_DGRAM` + `IPPROTO_ICMP`/`IPPROTO_ICMPV6`: echo requests out, matching replies in.
+    Icmp,
+    /// `SOCK_RAW`: whole IP packets of one protocol.
+    Raw,
 }
 
 #[derive(Clone, Copy)]
 struct SocketSlot {
     used: bool,
     kind: AxSocketKind,
+    /// Address family from socket(2): `AF_INET` or `AF_INET6`.
+    domain: i32,
+    /// `IPV6_V6ONLY`: an `AF_INET6` socket refuses IPv4 (v4-mapped) peers.
+    v6only: bool,
+    /// TCP, ping and raw: address given to bind(2); `None` for INADDR_ANY.
+    local_addr: Option<IpAddress>,
+    /// Port, or the echo identifier of a ping socket.
     local_port: u16,
+    /// Raw IPv4 only: `IP_HDRINCL`, sends carry their own IP header.
+    hdrincl: bool,
     listening: bool,
     connecting: bool,
     last_error: Option<NetError>,
     last_rx_window: u32,
     last_rx_window_poll: u32,
+    /// Listener whose accept queue holds this connection until accept(2).
+    parent: Option<SocketId>,
+    /// Listener only: accept queue depth requested by listen(2).
+    backlog: u8,
+    /// Listener only: SYNs still admitted, bounded by idle pre-listening sockets and backlog room.
+    syn_room: u8,
+    /// Queued connection: completion order, 0 while the handshake is in progress.
+    accept_seq: u32,
+    opts: SocketOptions,
+    /// TCP closed by the owner but still finishing its FIN/RST exchange; reaped by `poll`.
+    orphan: bool,
+    /// Descriptors referring to the socket, counted by `socket_acquire`/`socket_release`.
+    refs: u16,
+    /// Pool blocks behind the receive and send buffers, returned when the slot is released.
+    bufs: [Option<PoolBlock>; 2],
+    /// UDP: peer from connect(2), the default destination and the only accepted source.
+    peer: Option<IpEndpoint>,
+    /// IPv4 multicast groups joined through `IP_ADD_MEMBERSHIP`.
+    groups: [Option<Ipv4Address>; SOCKET_MAX_GROUPS],
     handle: MaybeUninit<SocketHandle>,
 }
 
 const EMPTY_SOCKET_SLOT: SocketSlot = SocketSlot {
     used: false,
     kind: AxSocketKind::Tcp,
+    domain: AF_INET,
+    v6only: false,
+    local_addr: None,
     local_port: 0,
+    hdrincl: false,
     listening: false,
     connecting: false,
     last_error: None,
     last_rx_window: 0,
     last_rx_window_poll: 0,
+    parent: None,
+    backlog: 0,
+    syn_room: 0,
+    accept_seq: 0,
+    opts: SocketOptions::new(TCP_BUF_LEN),
+    orphan: false,
+    refs: 0,
+    bufs: [None; 2],
+    peer: None,
+    groups: [None; SOCKET_MAX_GROUPS],
     handle: MaybeUninit::uninit(),
 };
 
-static mut SOCKET_TABLE: [SocketSlot; MAX_SOCKETS] = [EMPTY_SOCKET_SLOT; MAX_SOCKETS];
+/// Array in pages from `NetMemory`; the socket table and smoltcp's socket storage live in these.
+struct Table<T> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+impl<T> Table<T> {
+    const fn new() -> Self {
+        Self { ptr: NonNull::dangling(), len: 0 }
+    }
+
+    fn pages(len: usize) -> usize {
+        (len * size_of::<T>()).div_ceil(NET_PAGE_SIZE)
+    }
+}
+
+impl<T> Deref for Table<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        // SAFETY: ptr 指向 len 个已初始化的元素（或 len 为 0 时的悬空指针）。
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for Table<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        // SAFETY: 同上，且表只经由这一个 static 访问。
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+// SAFETY: socket table access is serialized by the single-hart runtime.
+static mut SOCKET_TABLE: Table<SocketSlot> = Table::new();
+// SAFETY: 与 SOCKET_TABLE 一同增长，NET_STATE.sockets 借用其全部元素。
+static mut SOCKET_STORAGE: Table<SocketStorage<'static>> = Table::new();
+
+/// Current size of the socket table.
+fn socket_capacity() -> usize {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe { SOCKET_TABLE.len() }
+}
+
+/// Double the socket table and smoltcp's socket storage, moving live entries across.
+///
+/// Entries are moved bitwise, so socket ids and handles stay valid. At boot,
+/// before a socket set exists, this allocates the initial tables.
+fn grow_socket_tables(mem: &dyn NetMemory, mut sockets: Option<&mut SocketSet<'static>>) -> bool {
+    let old_len = socket_capacity();
+    let len = if old_len == 0 { INITIAL_SOCKETS } else { old_len * 2 };
+    if len > MAX_SOCKETS {
+        return false;
+    }
+    let slot_pages = Table::<SocketSlot>::pages(len);
+    let storage_pages = Table::<SocketStorage>::pages(len + RESERVED_SOCKETS);
+    let Some(slots) = mem.alloc_pages(slot_pages) else {
+        return false;
+    };
+    let Some(storage) = mem.alloc_pages(storage_pages) else {
+        mem.free_pages(slots, slot_pages);
+        return false;
+    };
+    let slots = slots.cast::<SocketSlot>();
+    let storage = storage.cast::<SocketStorage<'static>>();
+    // SAFETY: 新表足够大且尚未被引用；旧 socket 集合先被替换为空集合，之后才搬移其存储。
+    unsafe {
+        if let Some(set) = sockets.as_deref_mut() {
+            *set = SocketSet::new(&mut [][..]);
+        }
+        ptr::copy_nonoverlapping(SOCKET_TABLE.ptr.as_ptr(), slots.as_ptr(), old_len);
+        for idx in old_len..len {
+            ptr::write(slots.as_ptr().add(idx), EMPTY_SOCKET_SLOT);
+        }
+        let old_storage = SOCKET_STORAGE.len;
+        ptr::copy_nonoverlapping(SOCKET_STORAGE.ptr.as_ptr(), storage.as_ptr(), old_storage);
+        for idx in old_storage..len + RESERVED_SOCKETS {
+            ptr::write(storage.as_ptr().add(idx), SocketStorage::EMPTY);
+        }
+        if old_len != 0 {
+            mem.free_pages(SOCKET_TABLE.ptr.cast(), Table::<SocketSlot>::pages(old_len));
+            mem.free_pages(SOCKET_STORAGE.ptr.cast(), Table::<SocketStorage>::pages(old_storage));
+        }
+        SOCKET_TABLE = Table { ptr: slots, len };
+        SOCKET_STORAGE = Table { ptr: storage, len: len + RESERVED_SOCKETS };
+        if let Some(set) = sockets {
+            *set = SocketSet::new(&mut SOCKET_STORAGE[..]);
+        }
+    }
+    true
+}
 
-/// Create a TCP/UDP socket and return its socket id.
-pub fn socket_create(domain: i32, sock_type: i32, _protocol: i32) -> Result<SocketId, NetError> {
+/// Create a socket and return its socket id.
+///
+/// Besides TCP and UDP this covers ping sockets (`SOCK_DGRAM` with `IPPROTO_ICMP`
+/// or `IPPROTO_ICMPV6`) and `SOCK_RAW` for ICMP/ICMPv6, TCP and UDP.
+pub fn socket_create(domain: i32, sock_type: i32, protocol: i32) -> Result<SocketId, NetError> {
     if !NET_READY.load(Ordering::Acquire) {
         return Err(NetError::NotReady);
     }
-    if domain != 2 {
+    if domain != AF_INET && domain != AF_INET6 {
         return Err(NetError::Unsupported);
     }
-    let kind = match sock_type & 0xf {
-        1 => AxSocketKind::Tcp,
-        2 => AxSocketKind::Udp,
+    let icmp = if domain == AF_INET6 { IPPROTO_ICMPV6 } else { IPPROTO_ICMP };
+    let kind = match (sock_type & 0xf, protocol) {
+        (1, 0 | IPPROTO_TCP) => AxSocketKind::Tcp,
+        (2, 0 | IPPROTO_UDP) => AxSocketKind::Udp,
+        (2, proto) if proto == icmp => AxSocketKind::Icmp,
+        (3, IPPROTO_TCP | IPPROTO_UDP) => AxSocketKind::Raw,
+        (3, proto) if proto == icmp => AxSocketKind::Raw,
         _ => return Err(NetError::Unsupported),
     };
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
-    let slot = reserve_socket_slot(kind).ok_or(NetError::NoMem)?;
-    let handle = match kind {
-        AxSocketKind::Tcp => {
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-            let rx = unsafe { TcpSocketBuffer::new(&mut TCP_RX_BUF[slot][..]) };
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-            let tx = unsafe { TcpSocketBuffer::new(&mut TCP_TX_BUF[slot][..]) };
-            state.sockets.add(TcpSocket::new(rx, tx))
+    let slot = reserve_socket_slot(state, kind).ok_or(NetError::NoMem)?;
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[slot].domain = domain;
+    }
+    let added = match kind {
+        AxSocketKind::Tcp => add_tcp_socket(state, slot),
+        AxSocketKind::Udp | AxSocketKind::Icmp | AxSocketKind::Raw => add_datagram_socket(state, slot, protocol),
+    };
+    match added {
+        Ok(handle) => {
+            set_socket_handle(slot, handle);
+            Ok(slot)
         }
-        AxSocketKind::Udp => {
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-            let rx = unsafe {
-                UdpPacketBuffer::new(&mut UDP_RX_META[slot][..], &mut UDP_RX_BUF[slot][..])
-            };
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-            let tx = unsafe {
-                UdpPacketBuffer::new(&mut UDP_TX_META[slot][..], &mut UDP_TX_BUF[slot][..])
-            };
-            state.sockets.add(UdpSocket::new(rx, tx))
+        Err(err) => {
+            release_socket_slot(state.mem, slot);
+            Err(err)
         }
+    }
+}
+
+/// Return the address family the socket was created with.
+pub fn socket_domain(id: SocketId) -> Result<i32, NetError> {
+    socket_handle(id).ok_or(NetError::Invalid)?;
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    Ok(unsafe { SOCKET_TABLE[id].domain })
+}
+
+/// Return the socket type from socket(2): `SOCK_STREAM`, `SOCK_DGRAM` or `SOCK_RAW`.
+pub fn socket_type(id: SocketId) -> Result<i32, NetError> {
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
+    Ok(match kind {
+        AxSocketKind::Tcp => 1,
+        AxSocketKind::Udp | AxSocketKind::Icmp => 2,
+        AxSocketKind::Raw => 3,
+    })
+}
+
+/// Return the `IPV6_V6ONLY` setting of an `AF_INET6` socket.
+pub fn socket_v6only(id: SocketId) -> Result<bool, NetError> {
+    if socket_domain(id)? != AF_INET6 {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    Ok(unsafe { SOCKET_TABLE[id].v6only })
+}
+
+/// Set `IPV6_V6ONLY`; like Linux it can only change before the socket is bound.
+pub fn socket_set_v6only(id: SocketId, v6only: bool) -> Result<(), NetError> {
+    if socket_domain(id)? != AF_INET6 {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    let bound = match kind {
+        AxSocketKind::Tcp => socket_local_port(id).is_ok() || state.sockets.get::<TcpSocket>(handle).is_open(),
+        AxSocketKind::Udp => state.sockets.get::<UdpSocket>(handle).is_open(),
+        AxSocketKind::Icmp | AxSocketKind::Raw => {
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            unsafe { SOCKET_TABLE[id].local_addr.is_some() || SOCKET_TABLE[id].local_port != 0 }
+        }
+    };
+    if bound {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[id].v6only = v6only;
+    }
+    Ok(())
+}
+
+/// Whether socket `id` may exchange traffic with `addr`.
+fn family_accepts(id: SocketId, addr: &IpAddress) -> bool {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    slot_accepts(unsafe { &SOCKET_TABLE[id] }, addr)
+}
+
+/// `AF_INET` sockets see only IPv4, `AF_INET6` sockets see IPv6 and, unless V6ONLY, IPv4 peers too.
+fn slot_accepts(slot: &SocketSlot, addr: &IpAddress) -> bool {
+    match addr {
+        IpAddress::Ipv4(_) => slot.domain == AF_INET || !slot.v6only,
+        IpAddress::Ipv6(_) => slot.domain == AF_INET6,
+    }
+}
+
+fn socket_ip_version(id: SocketId) -> IpVersion {
+    unspecified_addr(id).version()
+}
+
+/// Ping and raw sockets carry one IP version, so v4-mapped peers do not apply to them.
+fn family_matches(id: SocketId, addr: &IpAddress) -> bool {
+    unspecified_addr(id).version() == addr.version()
+}
+
+fn unspecified_addr(id: SocketId) -> IpAddress {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    match unsafe { SOCKET_TABLE[id].domain } {
+        AF_INET6 => IpAddress::Ipv6(Ipv6Address::UNSPECIFIED),
+        _ => IpAddress::Ipv4(Ipv4Address::UNSPECIFIED),
+    }
+}
+
+/// Drop queued datagrams from peers outside the socket's family, e.g. IPv6 on a wildcard `AF_INET` socket,
+/// and on a connected socket everything not sent by its peer.
+fn discard_foreign_datagrams(id: SocketId, socket: &mut UdpSocket) {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let peer = unsafe { SOCKET_TABLE[id].peer };
+    while let Ok((_, meta)) = socket.peek() {
+        if family_accepts(id, &meta.endpoint.addr) && peer.map_or(true, |peer| peer == meta.endpoint) {
+            break;
+        }
+        let _ = socket.recv();
+    }
+}
+
+/// Add a TCP socket whose buffers and options come from the slot's `SocketOptions`.
+///
+/// The buffers are taken from the pool; on success the slot owns them in place
+/// of whatever it held before.
+fn add_tcp_socket(state: &mut NetState, slot: SocketId) -> Result<SocketHandle, NetError> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let opts = unsafe { SOCKET_TABLE[slot].opts };
+    let [rx, tx] = alloc_socket_bufs(state.mem, opts.rcvbuf, opts.sndbuf)?;
+    // SAFETY: 两个块刚从缓冲池分配，只交给这个 socket。
+    let (rx_buf, tx_buf) = unsafe { (BUFFER_POOL.bytes(rx), BUFFER_POOL.bytes(tx)) };
+    let rx_buf = TcpSocketBuffer::new(&mut rx_buf[..opts.rcvbuf]);
+    let tx_buf = TcpSocketBuffer::new(&mut tx_buf[..opts.sndbuf]);
+    let mut socket = TcpSocket::new(rx_buf, tx_buf);
+    apply_tcp_options(&mut socket, &opts);
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[slot].bufs = [Some(rx), Some(tx)];
+    }
+    Ok(state.sockets.add(socket))
+}
+
+/// Add a UDP, ping or raw socket; its packet buffers come from the pool like TCP's.
+fn add_datagram_socket(state: &mut NetState, slot: SocketId, protocol: i32) -> Result<SocketHandle, NetError> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let (kind, domain, opts) = unsafe { (SOCKET_TABLE[slot].kind, SOCKET_TABLE[slot].domain, SOCKET_TABLE[slot].opts) };
+    let [rx, tx] = alloc_socket_bufs(state.mem, opts.rcvbuf + DGRAM_META_BYTES, opts.sndbuf + DGRAM_META_BYTES)?;
+    // SAFETY: 两个块刚从缓冲池分配，只交给这个 socket。
+    let handle = unsafe {
+        match kind {
+            AxSocketKind::Icmp => {
+                let (rx_meta, rx_buf) = packet_storage(rx, opts.rcvbuf, IcmpPacketMetadata::EMPTY);
+                let (tx_meta, tx_buf) = packet_storage(tx, opts.sndbuf, IcmpPacketMetadata::EMPTY);
+                let rx = IcmpPacketBuffer::new(rx_meta, rx_buf);
+                state.sockets.add(IcmpSocket::new(rx, IcmpPacketBuffer::new(tx_meta, tx_buf)))
+            }
+            AxSocketKind::Raw => {
+                let (rx_meta, rx_buf) = packet_storage(rx, opts.rcvbuf, RawPacketMetadata::EMPTY);
+                let (tx_meta, tx_buf) = packet_storage(tx, opts.sndbuf, RawPacketMetadata::EMPTY);
+                let rx = RawPacketBuffer::new(rx_meta, rx_buf);
+                let tx = RawPacketBuffer::new(tx_meta, tx_buf);
+                let version = if domain == AF_INET6 { IpVersion::Ipv6 } else { IpVersion::Ipv4 };
+                state.sockets.add(RawSocket::new(version, IpProtocol::from(protocol as u8), rx, tx))
+            }
+            AxSocketKind::Udp | AxSocketKind::Tcp => {
+                let (rx_meta, rx_buf) = packet_storage(rx, opts.rcvbuf, UdpPacketMetadata::EMPTY);
+                let (tx_meta, tx_buf) = packet_storage(tx, opts.sndbuf, UdpPacketMetadata::EMPTY);
+                let rx = UdpPacketBuffer::new(rx_meta, rx_buf);
+                state.sockets.add(UdpSocket::new(rx, UdpPacketBuffer::new(tx_meta, tx_buf)))
+            }
+        }
+    };
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[slot].bufs = [Some(rx), Some(tx)];
+    }
+    Ok(handle)
+}
+
+/// Split a datagram buffer block into `payload` bytes of packet data followed by
+/// as many metadata entries as fit in the rest, each set to `empty`.
+///
+/// # Safety
+///
+/// `block` must be a live pool block that nothing else references.
+unsafe fn packet_storage<M: Copy>(block: PoolBlock, payload: usize, empty: M) -> (&'static mut [M], &'static mut [u8]) {
+    let (data, rest) = BUFFER_POOL.bytes(block).split_at_mut(payload);
+    let offset = rest.as_ptr().align_offset(align_of::<M>());
+    let count = rest.len().saturating_sub(offset) / size_of::<M>();
+    let meta = rest.as_mut_ptr().add(offset).cast::<M>();
+    for idx in 0..count {
+        ptr::write(meta.add(idx), empty);
+    }
+    (slice::from_raw_parts_mut(meta, count), data)
+}
+
+/// Take blocks for a socket's receive and send buffers from the pool.
+fn alloc_socket_bufs(mem: &dyn NetMemory, rx_len: usize, tx_len: usize) -> Result<[PoolBlock; 2], NetError> {
+    // SAFETY: buffer pool access is serialized by the single-hart runtime.
+    unsafe {
+        let rx = BUFFER_POOL.alloc(mem, rx_len).ok_or(NetError::NoMem)?;
+        let Some(tx) = BUFFER_POOL.alloc(mem, tx_len) else {
+            BUFFER_POOL.free(mem, rx);
+            return Err(NetError::NoMem);
+        };
+        Ok([rx, tx])
+    }
+}
+
+fn free_socket_bufs(mem: &dyn NetMemory, bufs: [Option<PoolBlock>; 2]) {
+    for block in bufs.into_iter().flatten() {
+        // SAFETY: buffer pool access is serialized by the single-hart runtime.
+        unsafe { BUFFER_POOL.free(mem, block) };
+    }
+}
+
+fn apply_tcp_options(socket: &mut TcpSocket, opts: &SocketOptions) {
+    socket.set_nagle_enabled(!opts.nodelay);
+    socket.set_ack_delay((!opts.quickack).then(|| Duration::from_millis(TCP_ACK_DELAY_MS)));
+    socket.set_keep_alive(opts.keep_alive_ms().map(Duration::from_millis));
+    socket.set_timeout(opts.timeout_ms().map(Duration::from_millis));
+    socket.set_hop_limit(opts.hop_limit);
+}
+
+/// Upper bound for `SO_RCVBUF`/`SO_SNDBUF` on a socket of `kind`.
+fn max_buf_len(kind: AxSocketKind) -> usize {
+    match kind {
+        AxSocketKind::Tcp => TCP_BUF_LEN,
+        AxSocketKind::Udp | AxSocketKind::Icmp | AxSocketKind::Raw => UDP_BUF_LEN,
+    }
+}
+
+/// Change one socket option and push it down to the smoltcp socket.
+///
+/// TCP buffer sizes only take effect while the socket is unconnected, since
+/// smoltcp fixes them at creation; a listener passes them to new connections.
+pub fn socket_set_option(id: SocketId, opt: SockOpt) -> Result<(), NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    let tcp_only = matches!(
+        opt,
+        SockOpt::NoDelay(_)
+            | SockOpt::KeepIdle(_)
+            | SockOpt::KeepIntvl(_)
+            | SockOpt::KeepCnt(_)
+            | SockOpt::QuickAck(_)
+            | SockOpt::UserTimeout(_)
+    );
+    if tcp_only && kind != AxSocketKind::Tcp {
+        return Err(NetError::Unsupported);
+    }
+    if opt == SockOpt::HopLimit(Some(0)) {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let (old, opts, listening) = unsafe {
+        let slot = &mut SOCKET_TABLE[id];
+        let old = slot.opts;
+        slot.opts.apply(opt, max_buf_len(kind));
+        (old, slot.opts, slot.listening)
     };
-    set_socket_handle(slot, handle);
-    Ok(slot)
+    match kind {
+        AxSocketKind::Tcp => {
+            let resized = (old.rcvbuf, old.sndbuf) != (opts.rcvbuf, opts.sndbuf);
+            let closed = state.sockets.get::<TcpSocket>(handle).state() == TcpState::Closed;
+            if resized && closed && !listening {
+                // 先分配新缓冲区；分配失败时保留原 socket 和原设置。
+                // SAFETY: socket table access is serialized by the single-hart runtime.
+                let old_bufs = unsafe { SOCKET_TABLE[id].bufs };
+                match add_tcp_socket(state, id) {
+                    Ok(new_handle) => {
+                        let _ = state.sockets.remove(handle);
+                        free_socket_bufs(state.mem, old_bufs);
+                        set_socket_handle(id, new_handle);
+                    }
+                    Err(err) => {
+                        // SAFETY: socket table access is serialized by the single-hart runtime.
+                        unsafe {
+                            SOCKET_TABLE[id].opts = old;
+                        }
+                        return Err(err);
+                    }
+                }
+            } else {
+                apply_tcp_options(state.sockets.get_mut::<TcpSocket>(handle), &opts);
+            }
+        }
+        AxSocketKind::Udp => state.sockets.get_mut::<UdpSocket>(handle).set_hop_limit(opts.hop_limit),
+        AxSocketKind::Icmp => state.sockets.get_mut::<IcmpSocket>(handle).set_hop_limit(opts.hop_limit),
+        // raw_send 构造首部时读取 hop_limit。
+        AxSocketKind::Raw => {}
+    }
+    NET_NEED_POLL.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Return the socket's current options.
+pub fn socket_options(id: SocketId) -> Result<SocketOptions, NetError> {
+    socket_handle(id).ok_or(NetError::Invalid)?;
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    Ok(unsafe { SOCKET_TABLE[id].opts })
 }
 
 /// Bind a socket to a local address/port.
@@ -698,6 +1453,9 @@ pub fn socket_bind(id: SocketId, addr: IpAddress, port: u16) -> Result<(), NetEr
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
     let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if !family_accepts(id, &addr) {
+        return Err(NetError::Invalid);
+    }
     match kind {
         AxSocketKind::Tcp => {
             set_socket_local_port(id, port)?;
@@ -708,27 +1466,185 @@ pub fn socket_bind(id: SocketId, addr: IpAddress, port: u16) -> Result<(), NetEr
             if port == 0 {
                 return Err(NetError::Invalid);
             }
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            unsafe {
+                SOCKET_TABLE[id].local_addr = Some(addr).filter(|addr| !addr.is_unspecified());
+            }
             Ok(())
         }
         AxSocketKind::Udp => {
+            if state.sockets.get::<UdpSocket>(handle).is_open() {
+                return Err(NetError::Invalid);
+            }
+            // 端口 0 与 Linux 一样分配临时端口。
+            let port = if port == 0 { socket_local_port(id)? } else { port };
             let socket = state.sockets.get_mut::<UdpSocket>(handle);
-            socket.bind((addr, port)).map_err(|_| NetError::Invalid)?;
+            // 通配地址不限定目的地址，由 family_accepts 按协议族过滤。
+            let endpoint = IpListenEndpoint {
+                addr: Some(addr).filter(|addr| !addr.is_unspecified()),
+                port,
+            };
+            socket.bind(endpoint).map_err(|_| NetError::Invalid)?;
+            Ok(())
+        }
+        AxSocketKind::Icmp | AxSocketKind::Raw => {
+            if !family_matches(id, &addr) {
+                return Err(NetError::Invalid);
+            }
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            let slot = unsafe { &mut SOCKET_TABLE[id] };
+            if slot.local_addr.is_some() || slot.local_port != 0 {
+                return Err(NetError::Invalid);
+            }
+            // 绑定的地址只用作源地址；ping 的端口即回显标识符，0 表示自动分配。
+            slot.local_addr = Some(addr).filter(|addr| !addr.is_unspecified());
+            if kind == AxSocketKind::Icmp {
+                if port != 0 {
+                    set_socket_local_port(id, port)?;
+                }
+                icmp_ident(state, id, handle)?;
+            }
             Ok(())
         }
     }
 }
 
-/// Connect a socket to a remote address/port.
-pub fn socket_connect(id: SocketId, addr: IpAddress, port: u16) -> Result<(), NetError> {
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
-    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
-    match kind {
-        AxSocketKind::Tcp => {
-            set_socket_listening(id, false)?;
-            let local_port = socket_local_port(id)?;
-            let socket = state.sockets.get_mut::<TcpSocket>(handle);
-            let tcp_state = socket.state();
+/// Bind an unbound UDP socket to an ephemeral port, as Linux does on the first send or connect(2).
+fn udp_autobind(id: SocketId, socket: &mut UdpSocket) -> Result<(), NetError> {
+    if !socket.is_open() {
+        let port = socket_local_port(id)?;
+        socket.bind(port).map_err(|_| NetError::Invalid)?;
+    }
+    Ok(())
+}
+
+/// Echo identifier of a ping socket, the counterpart of a UDP port: picked from
+/// the ephemeral range on first use unless bind(2) chose one.
+fn icmp_ident(state: &mut NetState, id: SocketId, handle: SocketHandle) -> Result<u16, NetError> {
+    let ident = socket_local_port(id)?;
+    let socket = state.sockets.get_mut::<IcmpSocket>(handle);
+    if !socket.is_open() {
+        socket.bind(IcmpEndpoint::Ident(ident)).map_err(|_| NetError::Invalid)?;
+    }
+    Ok(ident)
+}
+
+/// Whether `addr` is the limited broadcast address or eth0's subnet broadcast.
+fn is_broadcast(state: &NetState, addr: &IpAddress) -> bool {
+    let IpAddress::Ipv4(addr) = *addr else {
+        return false;
+    };
+    let subnet = state.eth.and_then(|eth| Ipv4Cidr::new(eth.addr, eth.prefix_len).broadcast());
+    addr.is_broadcast() || subnet == Some(addr)
+}
+
+/// Source address for a header built here, chosen like smoltcp does: eth0 before `lo`,
+/// and the link-local address for link-local IPv6 destinations.
+fn source_addr(dst: IpAddress) -> IpAddress {
+    match dst {
+        IpAddress::Ipv4(_) => IpAddress::Ipv4(local_ipv4().unwrap_or(Ipv4Address(NET_LOOPBACK_ADDR))),
+        IpAddress::Ipv6(dst) => {
+            // SAFETY: single-hart; NET_IPV6_ADDRS is only rewritten by sync_ip_addrs.
+            let [global, link_local] = unsafe { NET_IPV6_ADDRS };
+            let order = if dst.is_link_local() { [link_local, global] } else { [global, link_local] };
+            let addr = order.into_iter().find(|addr| !addr.is_unspecified()).filter(|_| !dst.is_loopback());
+            IpAddress::Ipv6(addr.unwrap_or(Ipv6Address::LOOPBACK))
+        }
+    }
+}
+
+/// Queue one packet on a raw socket.
+///
+/// Without `IP_HDRINCL` the IP header is built here, and for ICMPv6 the checksum
+/// is filled in as Linux does. With it `buf` is the whole IPv4 packet; only the
+/// total length, an unspecified source and the header checksum are fixed up.
+fn raw_send(
+    state: &mut NetState,
+    id: SocketId,
+    handle: SocketHandle,
+    buf: &[u8],
+    dst: IpAddress,
+) -> Result<usize, NetError> {
+    if !family_matches(id, &dst) {
+        return Err(NetError::Unreachable);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let (hdrincl, bound, hop_limit) =
+        unsafe { (SOCKET_TABLE[id].hdrincl, SOCKET_TABLE[id].local_addr, SOCKET_TABLE[id].opts.hop_limit) };
+    let src = bound.unwrap_or_else(|| source_addr(dst));
+    let socket = state.sockets.get_mut::<RawSocket>(handle);
+    let protocol = socket.ip_protocol();
+    let header_len = match dst {
+        _ if hdrincl => {
+            // 入队后无法撤回，先检查用户给出的首部。
+            let ihl = buf.first().map_or(0, |byte| usize::from(byte & 0xf) * 4);
+            if buf.len() < 20 || buf[0] >> 4 != 4 || ihl < 20 || ihl > buf.len() || buf[9] != u8::from(protocol) {
+                return Err(NetError::Invalid);
+            }
+            0
+        }
+        IpAddress::Ipv4(_) => 20,
+        IpAddress::Ipv6(_) => 40,
+    };
+    let total = header_len + buf.len();
+    if total > NET_MTU {
+        return Err(NetError::MsgSize);
+    }
+    let packet = socket.send(total).map_err(|_| NetError::WouldBlock)?;
+    packet[header_len..].copy_from_slice(buf);
+    match (src, dst) {
+        (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(_)) if hdrincl => {
+            let mut ip = Ipv4Packet::new_unchecked(&mut packet[..]);
+            ip.set_total_len(total as u16);
+            if ip.src_addr().is_unspecified() {
+                ip.set_src_addr(src_addr);
+            }
+            ip.fill_checksum();
+        }
+        (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(dst_addr)) => {
+            let repr = Ipv4Repr {
+                src_addr,
+                dst_addr,
+                next_header: protocol,
+                payload_len: buf.len(),
+                hop_limit: hop_limit.unwrap_or(DEFAULT_HOP_LIMIT),
+            };
+            repr.emit(&mut Ipv4Packet::new_unchecked(&mut packet[..header_len]), &ChecksumCapabilities::default());
+        }
+        (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => {
+            let repr = Ipv6Repr {
+                src_addr,
+                dst_addr,
+                next_header: protocol,
+                payload_len: buf.len(),
+                hop_limit: hop_limit.unwrap_or(DEFAULT_HOP_LIMIT),
+            };
+            repr.emit(&mut Ipv6Packet::new_unchecked(&mut packet[..header_len]));
+            if protocol == IpProtocol::Icmpv6 && buf.len() >= 4 {
+                Icmpv6Packet::new_unchecked(&mut packet[header_len..]).fill_checksum(&src, &dst);
+            }
+        }
+        _ => {}
+    }
+    Ok(buf.len())
+}
+
+/// Connect a socket to a remote address/port.
+pub fn socket_connect(id: SocketId, addr: IpAddress, port: u16) -> Result<(), NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    match kind {
+        AxSocketKind::Tcp => {
+            if socket_is_listening(id)? {
+                return Err(NetError::Invalid);
+            }
+            if !family_accepts(id, &addr) {
+                return Err(NetError::Unreachable);
+            }
+            let local_port = socket_local_port(id)?;
+            let socket = state.sockets.get_mut::<TcpSocket>(handle);
+            let tcp_state = socket.state();
             match tcp_state {
                 TcpState::SynSent | TcpState::SynReceived => {
                     let _ = set_socket_connecting(id, true);
@@ -741,7 +1657,7 @@ pub fn socket_connect(id: SocketId, addr: IpAddress, port: u16) -> Result<(), Ne
             set_socket_connecting(id, true)?;
             set_socket_error(id, None)?;
             let local = IpListenEndpoint {
-                addr: None,
+                addr: socket_local_addr(id),
                 port: local_port,
             };
             socket
@@ -753,69 +1669,260 @@ pub fn socket_connect(id: SocketId, addr: IpAddress, port: u16) -> Result<(), Ne
             NET_NEED_POLL.store(true, Ordering::Release);
             Ok(())
         }
-        AxSocketKind::Udp => Err(NetError::Unsupported),
+        AxSocketKind::Udp => {
+            if !family_accepts(id, &addr) {
+                return Err(NetError::Unreachable);
+            }
+            if addr.is_unspecified() || port == 0 {
+                return Err(NetError::Invalid);
+            }
+            udp_autobind(id, state.sockets.get_mut::<UdpSocket>(handle))?;
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            unsafe {
+                SOCKET_TABLE[id].peer = Some(IpEndpoint::new(addr, port));
+            }
+            Ok(())
+        }
+        AxSocketKind::Icmp | AxSocketKind::Raw => Err(NetError::Unsupported),
     }
 }
 
+/// Dissolve a UDP socket's association, connect(2) with `AF_UNSPEC`.
+pub fn socket_disconnect(id: SocketId) -> Result<(), NetError> {
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if kind != AxSocketKind::Udp {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[id].peer = None;
+    }
+    Ok(())
+}
+
 /// Place a TCP socket into listening state.
-pub fn socket_listen(id: SocketId, _backlog: usize) -> Result<(), NetError> {
+///
+/// The listener's own smoltcp socket stays closed; instead a few hidden
+/// sockets listen on the port and are replenished as SYNs take them, until
+/// `backlog` connections are handshaking or waiting for `accept`. Calling it
+/// again resizes the queue.
+pub fn socket_listen(id: SocketId, backlog: usize) -> Result<(), NetError> {
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
     let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
     match kind {
         AxSocketKind::Tcp => {
-            let local_port = socket_local_port(id)?;
-            let socket = state.sockets.get_mut::<TcpSocket>(handle);
-            socket
-                .listen(IpListenEndpoint { addr: None, port: local_port })
-                .map_err(|_| NetError::Invalid)?;
+            socket_local_port(id)?;
+            if state.sockets.get_mut::<TcpSocket>(handle).is_open() {
+                return Err(NetError::Invalid);
+            }
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            unsafe {
+                SOCKET_TABLE[id].backlog = backlog.clamp(1, MAX_BACKLOG) as u8;
+            }
             set_socket_listening(id, true)?;
+            if fill_accept_pool(state, id)? == 0 {
+                set_socket_listening(id, false)?;
+                return Err(NetError::NoMem);
+            }
             NET_NEED_POLL.store(true, Ordering::Release);
             Ok(())
         }
-        AxSocketKind::Udp => Err(NetError::Unsupported),
+        AxSocketKind::Udp | AxSocketKind::Icmp | AxSocketKind::Raw => Err(NetError::Unsupported),
     }
 }
 
-/// Accept an incoming connection from a listening socket.
-pub fn socket_accept(id: SocketId) -> Result<(SocketId, SocketId, Option<(IpAddress, u16)>), NetError> {
+/// Accept the oldest completed connection queued on a listening socket.
+pub fn socket_accept(id: SocketId) -> Result<(SocketId, Option<(IpAddress, u16)>), NetError> {
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
-    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
     let AxSocketKind::Tcp = kind else {
         return Err(NetError::Unsupported);
     };
     if !socket_is_listening(id)? {
         return Err(NetError::Invalid);
     }
-    let socket = state.sockets.get_mut::<TcpSocket>(handle);
-    let tcp_state = socket.state();
-    if socket.is_listening() || tcp_state == TcpState::SynReceived {
+    update_accept_queue(state);
+    let Some(accepted) = oldest_queued(id) else {
         NET_NEED_POLL.store(true, Ordering::Release);
         return Err(NetError::WouldBlock);
+    };
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        let slot = &mut SOCKET_TABLE[accepted];
+        slot.parent = None;
+        slot.accept_seq = 0;
+    }
+    let (_, handle) = socket_handle(accepted).ok_or(NetError::Invalid)?;
+    let remote = state.sockets.get::<TcpSocket>(handle).remote_endpoint().map(|ep| (ep.addr, ep.port));
+    // 队列腾出一个位置，补充预监听 socket；槽位或缓冲区不足时下次 poll 再补。
+    let _ = fill_accept_pool(state, id);
+    NET_NEED_POLL.store(true, Ordering::Release);
+    Ok((accepted, remote))
+}
+
+/// Open pre-listening sockets for `id` until `ACCEPT_POOL_IDLE` wait for a SYN
+/// or the backlog is reached, then recount `syn_room`; returns the pool size.
+fn fill_accept_pool(state: &mut NetState, id: SocketId) -> Result<usize, NetError> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let (backlog, addr, port) =
+        unsafe { (SOCKET_TABLE[id].backlog as usize, SOCKET_TABLE[id].local_addr, SOCKET_TABLE[id].local_port) };
+    let (mut idle, queued) = accept_pool_counts(state, id);
+    let room = backlog.saturating_sub(queued);
+    let mut result = Ok(());
+    while idle < room.min(ACCEPT_POOL_IDLE) {
+        let Some(member) = reserve_socket_slot(state, AxSocketKind::Tcp) else {
+            break;
+        };
+        // SAFETY: socket table access is serialized by the single-hart runtime.
+        unsafe {
+            SOCKET_TABLE[member].opts = SOCKET_TABLE[id].opts;
+        }
+        let Ok(handle) = add_tcp_socket(state, member) else {
+            release_socket_slot(state.mem, member);
+            break;
+        };
+        set_socket_handle(member, handle);
+        let socket = state.sockets.get_mut::<TcpSocket>(handle);
+        if socket.listen(IpListenEndpoint { addr, port }).is_err() {
+            let _ = state.sockets.remove(handle);
+            release_socket_slot(state.mem, member);
+            result = Err(NetError::Invalid);
+            break;
+        }
+        // SAFETY: socket table access is serialized by the single-hart runtime.
+        unsafe {
+            SOCKET_TABLE[member].domain = SOCKET_TABLE[id].domain;
+            SOCKET_TABLE[member].v6only = SOCKET_TABLE[id].v6only;
+            SOCKET_TABLE[member].local_addr = addr;
+            SOCKET_TABLE[member].local_port = port;
+            SOCKET_TABLE[member].parent = Some(id);
+        }
+        idle += 1;
     }
-    if tcp_state != TcpState::Established && tcp_state != TcpState::CloseWait
-    {
-        return Err(NetError::Invalid);
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[id].syn_room = idle.min(room).min(u8::MAX as usize) as u8;
     }
-    let remote = socket.remote_endpoint().map(|ep| (ep.addr, ep.port));
-    let local_port = socket_local_port(id)?;
-    let listener_id = reserve_socket_slot(AxSocketKind::Tcp).ok_or(NetError::NoMem)?;
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-    let rx = unsafe { TcpSocketBuffer::new(&mut TCP_RX_BUF[listener_id][..]) };
-// SAFETY: static buffers/state are initialized and accessed under net state lock.
-    let tx = unsafe { TcpSocketBuffer::new(&mut TCP_TX_BUF[listener_id][..]) };
-    let listener_handle = state.sockets.add(TcpSocket::new(rx, tx));
-    set_socket_handle(listener_id, listener_handle);
-    set_socket_local_port(listener_id, local_port)?;
-    let listener = state.sockets.get_mut::<TcpSocket>(listener_handle);
-    listener
-        .listen(IpListenEndpoint { addr: None, port: local_port })
-        .map_err(|_| NetError::Invalid)?;
-    set_socket_listening(id, false)?;
-    set_socket_listening(listener_id, true)?;
-    NET_NEED_POLL.store(true, Ordering::Release);
-    Ok((id, listener_id, remote))
+    result.map(|()| idle + queued)
+}
+
+/// Count `id`'s pre-listening sockets still waiting for a SYN and its queued
+/// connections, i.e. those handshaking or waiting for accept(2).
+fn accept_pool_counts(state: &NetState, id: SocketId) -> (usize, usize) {
+    let (mut idle, mut queued) = (0, 0);
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        for slot in SOCKET_TABLE.iter().filter(|slot| slot.used && slot.parent == Some(id)) {
+            let handle = ptr::read(slot.handle.as_ptr());
+            let tcp_state = state.sockets.get::<TcpSocket>(handle).state();
+            if slot.accept_seq == 0 && matches!(tcp_state, TcpState::Listen | TcpState::Closed) {
+                idle += 1;
+            } else {
+                queued += 1;
+            }
+        }
+    }
+    (idle, queued)
+}
+
+fn oldest_queued(id: SocketId) -> Option<SocketId> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.used && slot.parent == Some(id) && slot.accept_seq != 0)
+            .min_by_key(|(_, slot)| slot.accept_seq)
+            .map(|(idx, _)| idx)
+    }
+}
+
+/// Queue connections whose handshake completed, re-arm sockets whose handshake
+/// was reset, and replace the pre-listening sockets SYNs have taken.
+///
+/// A wildcard listen endpoint matches both IP versions in smoltcp, so
+/// connections from a family the listener does not accept are reset here.
+fn update_accept_queue(state: &mut NetState) {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        for idx in 0..SOCKET_TABLE.len() {
+            let slot = &mut SOCKET_TABLE[idx];
+            if slot.parent.is_none() || !slot.used || slot.accept_seq != 0 {
+                continue;
+            }
+            let handle = ptr::read(slot.handle.as_ptr());
+            let socket = state.sockets.get_mut::<TcpSocket>(handle);
+            match socket.state() {
+                TcpState::SynReceived | TcpState::Listen => {}
+                TcpState::Closed => {
+                    let _ = socket.listen(IpListenEndpoint { addr: slot.local_addr, port: slot.local_port });
+                }
+                _ => match socket.remote_endpoint() {
+                    Some(remote) if !slot_accepts(slot, &remote.addr) => socket.abort(),
+                    _ => slot.accept_seq = NEXT_ACCEPT_SEQ.fetch_add(1, Ordering::Relaxed),
+                },
+            }
+        }
+        // 槽位或缓冲区不足时预监听 socket 暂时变少，下次 poll 再补。
+        for id in 0..SOCKET_TABLE.len() {
+            if SOCKET_TABLE[id].used && SOCKET_TABLE[id].listening {
+                let _ = fill_accept_pool(state, id);
+            }
+        }
+    }
+}
+
+/// Whether `frame` opens a connection to a listener with no `syn_room` left.
+///
+/// smoltcp resets a SYN no socket listens for; dropping it instead lets the
+/// peer retransmit once accept(2) has made room, as with a full Linux queue.
+/// Each SYN let through takes one unit of `syn_room` until the next poll recounts.
+fn syn_for_full_listener(frame: &[u8]) -> bool {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return false;
+    };
+    let (dst, protocol, payload) = match eth.ethertype() {
+        EthernetProtocol::Ipv4 => {
+            let Ok(ip) = Ipv4Packet::new_checked(eth.payload()) else {
+                return false;
+            };
+            (IpAddress::Ipv4(ip.dst_addr()), ip.next_header(), ip.payload())
+        }
+        EthernetProtocol::Ipv6 => {
+            let Ok(ip) = Ipv6Packet::new_checked(eth.payload()) else {
+                return false;
+            };
+            (IpAddress::Ipv6(ip.dst_addr()), ip.next_header(), ip.payload())
+        }
+        _ => return false,
+    };
+    if protocol != IpProtocol::Tcp {
+        return false;
+    }
+    let Ok(tcp) = TcpPacket::new_checked(payload) else {
+        return false;
+    };
+    if !tcp.syn() || tcp.ack() {
+        return false;
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        let Some(listener) = SOCKET_TABLE.iter_mut().find(|slot| {
+            slot.used
+                && slot.listening
+                && slot.local_port == tcp.dst_port()
+                && slot.local_addr.map_or(true, |addr| addr == dst || addr.is_unspecified())
+        }) else {
+            return false;
+        };
+        if listener.syn_room == 0 {
+            return true;
+        }
+        listener.syn_room -= 1;
+    }
+    false
 }
 
 /// Send data on a socket.
@@ -826,18 +1933,7 @@ pub fn socket_send(id: SocketId, buf: &[u8], addr: Option<(IpAddress, u16)>) ->
     let sent = match kind {
         AxSocketKind::Tcp => {
             let socket = state.sockets.get_mut::<TcpSocket>(handle);
-            let tcp_state = socket.state();
-            if matches!(tcp_state, TcpState::Listen | TcpState::Closed) {
-                return Err(NetError::Invalid);
-            }
-            if matches!(tcp_state, TcpState::SynSent | TcpState::SynReceived) {
-                NET_NEED_POLL.store(true, Ordering::Release);
-                return Err(NetError::WouldBlock);
-            }
-            if !socket.can_send() {
-                NET_NEED_POLL.store(true, Ordering::Release);
-                return Err(NetError::WouldBlock);
-            }
+            tcp_send_ready(socket)?;
             match socket.send_slice(buf) {
                 Ok(0) => return Err(NetError::WouldBlock),
                 Ok(size) => size,
@@ -845,27 +1941,151 @@ pub fn socket_send(id: SocketId, buf: &[u8], addr: Option<(IpAddress, u16)>) ->
             }
         }
         AxSocketKind::Udp => {
-            let socket = state.sockets.get_mut::<UdpSocket>(handle);
-            let Some((addr, port)) = addr else {
-                return Err(NetError::Invalid);
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            let (peer, broadcast) = unsafe { (SOCKET_TABLE[id].peer, SOCKET_TABLE[id].opts.broadcast) };
+            let remote = match addr {
+                Some((addr, port)) => IpEndpoint::new(addr, port),
+                None => peer.ok_or(NetError::DestAddrRequired)?,
             };
-            match socket.send_slice(buf, IpEndpoint::new(addr, port)) {
+            if !family_accepts(id, &remote.addr) {
+                return Err(NetError::Unreachable);
+            }
+            if !broadcast && is_broadcast(state, &remote.addr) {
+                return Err(NetError::Access);
+            }
+            let header_len = match remote.addr {
+                IpAddress::Ipv4(_) => 28,
+                IpAddress::Ipv6(_) => 48,
+            };
+            if header_len + buf.len() > NET_MTU {
+                return Err(NetError::MsgSize);
+            }
+            let socket = state.sockets.get_mut::<UdpSocket>(handle);
+            udp_autobind(id, socket)?;
+            match socket.send_slice(buf, remote) {
                 Ok(()) => {}
                 Err(smoltcp::socket::udp::SendError::BufferFull) => return Err(NetError::WouldBlock),
                 Err(smoltcp::socket::udp::SendError::Unaddressable) => return Err(NetError::Invalid),
             }
             buf.len()
         }
+        AxSocketKind::Icmp => {
+            let Some((addr, _)) = addr else {
+                return Err(NetError::Invalid);
+            };
+            if !family_matches(id, &addr) {
+                return Err(NetError::Unreachable);
+            }
+            // 与 Linux ping socket 一致：只允许回显请求，标识符改写为套接字的，校验和由 smoltcp 重算。
+            let (echo_request, header_len) = match addr {
+                IpAddress::Ipv4(_) => (8, 20),
+                IpAddress::Ipv6(_) => (128, 40),
+            };
+            if buf.len() < 8 || buf[0] != echo_request || buf[1] != 0 {
+                return Err(NetError::Invalid);
+            }
+            if header_len + buf.len() > NET_MTU {
+                return Err(NetError::MsgSize);
+            }
+            let ident = icmp_ident(state, id, handle)?;
+            let socket = state.sockets.get_mut::<IcmpSocket>(handle);
+            let packet = socket.send(buf.len(), addr).map_err(|err| match err {
+                smoltcp::socket::icmp::SendError::BufferFull => NetError::WouldBlock,
+                smoltcp::socket::icmp::SendError::Unaddressable => NetError::Invalid,
+            })?;
+            packet.copy_from_slice(buf);
+            packet[4..6].copy_from_slice(&ident.to_be_bytes());
+            buf.len()
+        }
+        AxSocketKind::Raw => {
+            let Some((addr, _)) = addr else {
+                return Err(NetError::Invalid);
+            };
+            raw_send(state, id, handle, buf, addr)?
+        }
     };
     NET_NEED_POLL.store(true, Ordering::Release);
     Ok(sent)
 }
 
+/// Let `fill` write up to `max` bytes straight into a TCP socket's send buffer.
+///
+/// `fill` gets each contiguous piece of free buffer space and returns how many
+/// bytes it produced; a short count ends the send. This saves sendfile(2) a
+/// bounce copy. Other socket kinds return `Unsupported`.
+pub fn socket_send_with(id: SocketId, max: usize, mut fill: impl FnMut(&mut [u8]) -> usize) -> Result<usize, NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if kind != AxSocketKind::Tcp {
+        return Err(NetError::Unsupported);
+    }
+    let socket = state.sockets.get_mut::<TcpSocket>(handle);
+    tcp_send_ready(socket)?;
+    let mut sent = 0usize;
+    while sent < max {
+        // 环形缓冲区回绕时空闲空间分两段，第二轮取剩下的一段。
+        let (produced, room) = socket
+            .send(|buf| {
+                let room = buf.len().min(max - sent);
+                let produced = fill(&mut buf[..room]).min(room);
+                (produced, (produced, room))
+            })
+            .map_err(|_| NetError::Invalid)?;
+        sent += produced;
+        if produced == 0 || produced < room {
+            break;
+        }
+    }
+    NET_NEED_POLL.store(true, Ordering::Release);
+    Ok(sent)
+}
+
+/// Check that a TCP socket can take more data: `WouldBlock` while connecting or
+/// with a full send buffer, `Pipe` once the local side has sent its FIN.
+fn tcp_send_ready(socket: &TcpSocket) -> Result<(), NetError> {
+    let tcp_state = socket.state();
+    if matches!(tcp_state, TcpState::Listen | TcpState::Closed) {
+        return Err(NetError::Invalid);
+    }
+    if matches!(tcp_state, TcpState::SynSent | TcpState::SynReceived) {
+        NET_NEED_POLL.store(true, Ordering::Release);
+        return Err(NetError::WouldBlock);
+    }
+    // 本端已发出 FIN（shutdown 或 close）后不能再写。
+    if !socket.may_send() {
+        return Err(NetError::Pipe);
+    }
+    if !socket.can_send() {
+        NET_NEED_POLL.store(true, Ordering::Release);
+        return Err(NetError::WouldBlock);
+    }
+    Ok(())
+}
+
+/// What one `socket_recv` call delivered.
+pub struct Received {
+    /// Bytes copied into the buffer.
+    pub len: usize,
+    /// Full datagram length, larger than `len` when the datagram was truncated; `len` for TCP.
+    pub msg_len: usize,
+    /// Sender of a datagram; `None` for TCP.
+    pub from: Option<(IpAddress, u16)>,
+}
+
+impl Received {
+    fn datagram(buf: &mut [u8], packet: &[u8], from: (IpAddress, u16)) -> Self {
+        let len = packet.len().min(buf.len());
+        buf[..len].copy_from_slice(&packet[..len]);
+        Self { len, msg_len: packet.len(), from: Some(from) }
+    }
+}
+
 /// Receive data from a socket.
-pub fn socket_recv(
-    id: SocketId,
-    buf: &mut [u8],
-) -> Result<(usize, Option<(IpAddress, u16)>), NetError> {
+///
+/// A datagram is consumed whole even when `buf` only takes part of it. With
+/// `peek` the data stays queued; ping sockets cannot peek.
+pub fn socket_recv(id: SocketId, buf: &mut [u8], peek: bool) -> Result<Received, NetError> {
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
     let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
@@ -880,27 +2100,56 @@ pub fn socket_recv(
                 NET_NEED_POLL.store(true, Ordering::Release);
                 return Err(NetError::WouldBlock);
             }
-            let size = match socket.recv_slice(buf) {
+            let result = if peek { socket.peek_slice(buf) } else { socket.recv_slice(buf) };
+            let size = match result {
                 Ok(0) => return Err(NetError::WouldBlock),
                 Ok(size) => size,
-                Err(smoltcp::socket::tcp::RecvError::Finished) => return Ok((0, None)),
+                Err(smoltcp::socket::tcp::RecvError::Finished) => {
+                    return Ok(Received { len: 0, msg_len: 0, from: None });
+                }
                 Err(smoltcp::socket::tcp::RecvError::InvalidState) => return Err(NetError::Invalid),
             };
             NET_NEED_POLL.store(true, Ordering::Release);
-            Ok((size, None))
+            Ok(Received { len: size, msg_len: size, from: None })
         }
         AxSocketKind::Udp => {
             let socket = state.sockets.get_mut::<UdpSocket>(handle);
-            let (size, endpoint) = match socket.recv_slice(buf) {
-                Ok((size, endpoint)) => {
-                    if size == 0 {
-                        return Err(NetError::WouldBlock);
-                    }
-                    (size, endpoint)
+            discard_foreign_datagrams(id, socket);
+            let (packet, meta) = if peek {
+                socket.peek().map(|(packet, meta)| (packet, *meta))
+            } else {
+                socket.recv()
+            }
+            .map_err(|_| NetError::WouldBlock)?;
+            Ok(Received::datagram(buf, packet, (meta.endpoint.addr, meta.endpoint.port)))
+        }
+        AxSocketKind::Icmp => {
+            if peek {
+                return Err(NetError::Unsupported);
+            }
+            let socket = state.sockets.get_mut::<IcmpSocket>(handle);
+            loop {
+                let (packet, from) = socket.recv().map_err(|_| NetError::WouldBlock)?;
+                // 绑定标识符后 smoltcp 也会交来同标识符的回显请求（如 ping 本机时自己发出的），只保留应答。
+                let echo_reply = match from {
+                    IpAddress::Ipv4(_) => 0,
+                    IpAddress::Ipv6(_) => 129,
+                };
+                if packet.first() != Some(&echo_reply) || !family_matches(id, &from) {
+                    continue;
                 }
-                Err(smoltcp::socket::udp::RecvError::Exhausted) => return Err(NetError::WouldBlock),
+                return Ok(Received::datagram(buf, packet, (from, 0)));
+            }
+        }
+        AxSocketKind::Raw => {
+            let socket = state.sockets.get_mut::<RawSocket>(handle);
+            let packet = if peek { socket.peek() } else { socket.recv() }.map_err(|_| NetError::WouldBlock)?;
+            // 与 Linux 一致：IPv4 原始套接字收到含首部的整个报文，IPv6 只收到载荷。
+            let (from, payload) = match socket_ip_version(id) {
+                IpVersion::Ipv4 => (IpAddress::Ipv4(Ipv4Packet::new_unchecked(packet).src_addr()), packet),
+                IpVersion::Ipv6 => (IpAddress::Ipv6(Ipv6Packet::new_unchecked(packet).src_addr()), &packet[40..]),
             };
-            Ok((size, Some((endpoint.endpoint.addr, endpoint.endpoint.port))))
+            Ok(Received::datagram(buf, payload, (from, 0)))
         }
     }
 }
@@ -955,7 +2204,7 @@ fn poll_tcp_window_event(state: &mut NetState) -> Option<NetEvent> {
     // SAFETY: socket table access is serialized by the single-hart runtime.
     unsafe {
         for (id, slot) in SOCKET_TABLE.iter_mut().enumerate() {
-            if !slot.used || slot.kind != AxSocketKind::Tcp {
+            if !slot.used || slot.orphan || slot.kind != AxSocketKind::Tcp {
                 continue;
             }
             let handle = ptr::read(slot.handle.as_ptr());
@@ -991,6 +2240,9 @@ pub fn socket_poll(id: SocketId, events: u16) -> Result<u16, NetError> {
     match kind {
         AxSocketKind::Tcp => {
             let listening = socket_is_listening(id)?;
+            if listening {
+                update_accept_queue(state);
+            }
             let socket = state.sockets.get_mut::<TcpSocket>(handle);
             let tcp_state = socket.state();
             if matches!(tcp_state, TcpState::SynSent | TcpState::SynReceived) {
@@ -1002,8 +2254,8 @@ pub fn socket_poll(id: SocketId, events: u16) -> Result<u16, NetError> {
             }
             if (events & NET_POLLIN) != 0 {
                 if listening {
-                    // 监听 socket 就绪：连接完成后转为 Established/CloseWait。
-                    if matches!(tcp_state, TcpState::Established | TcpState::CloseWait) {
+                    // 监听 socket 就绪：accept 队列中已有完成握手的连接。
+                    if oldest_queued(id).is_some() {
                         revents |= NET_POLLIN;
                     }
                 } else {
@@ -1019,7 +2271,7 @@ pub fn socket_poll(id: SocketId, events: u16) -> Result<u16, NetError> {
                     revents |= NET_POLLOUT;
                 }
             }
-            if tcp_state == TcpState::Closed {
+            if tcp_state == TcpState::Closed && !listening {
                 if socket_connecting(id)? {
                     let _ = set_socket_connecting(id, false);
                     let _ = set_socket_error(id, Some(NetError::ConnRefused));
@@ -1029,6 +2281,26 @@ pub fn socket_poll(id: SocketId, events: u16) -> Result<u16, NetError> {
         }
         AxSocketKind::Udp => {
             let socket = state.sockets.get_mut::<UdpSocket>(handle);
+            discard_foreign_datagrams(id, socket);
+            if (events & NET_POLLIN) != 0 && socket.can_recv() {
+                revents |= NET_POLLIN;
+            }
+            if (events & NET_POLLOUT) != 0 && socket.can_send() {
+                revents |= NET_POLLOUT;
+            }
+        }
+        AxSocketKind::Icmp => {
+            // 排队的可能只是被 socket_recv 丢弃的回显请求，此时读端会得到 WouldBlock。
+            let socket = state.sockets.get::<IcmpSocket>(handle);
+            if (events & NET_POLLIN) != 0 && socket.can_recv() {
+                revents |= NET_POLLIN;
+            }
+            if (events & NET_POLLOUT) != 0 && socket.can_send() {
+                revents |= NET_POLLOUT;
+            }
+        }
+        AxSocketKind::Raw => {
+            let socket = state.sockets.get::<RawSocket>(handle);
             if (events & NET_POLLIN) != 0 && socket.can_recv() {
                 revents |= NET_POLLIN;
             }
@@ -1045,12 +2317,159 @@ pub fn socket_close(id: SocketId) -> Result<(), NetError> {
 // SAFETY: static buffers/state are initialized and accessed under net state lock.
     let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
     let (kind, handle) = socket_handle(id).ok_or(NetError::Invalid)?;
-    let _ = state.sockets.remove(handle);
-    release_socket_slot(id);
+    // 关闭监听 socket 时一并丢弃尚未 accept 的连接。
+    for member in 0..socket_capacity() {
+        // SAFETY: socket table access is serialized by the single-hart runtime.
+        let queued = unsafe { SOCKET_TABLE[member].used && SOCKET_TABLE[member].parent == Some(id) };
+        if let Some((_, member_handle)) = socket_handle(member).filter(|_| queued) {
+            let _ = state.sockets.remove(member_handle);
+            release_socket_slot(state.mem, member);
+        }
+    }
     NET_NEED_POLL.store(true, Ordering::Release);
-    match kind {
-        AxSocketKind::Tcp | AxSocketKind::Udp => Ok(()),
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let (listening, linger) = unsafe { (SOCKET_TABLE[id].listening, SOCKET_TABLE[id].opts.linger) };
+    if kind == AxSocketKind::Tcp && !listening {
+        let socket = state.sockets.get_mut::<TcpSocket>(handle);
+        if !matches!(socket.state(), TcpState::Closed | TcpState::Listen | TcpState::TimeWait) {
+            // 默认在后台发完数据并走完 FIN 握手；SO_LINGER 为 0 时直接发 RST。
+            if linger == Some(0) {
+                socket.abort();
+            } else {
+                socket.close();
+                socket.set_timeout(Some(Duration::from_millis(TCP_ORPHAN_TIMEOUT_MS)));
+            }
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            unsafe {
+                SOCKET_TABLE[id].orphan = true;
+            }
+            return Ok(());
+        }
     }
+    leave_all_groups(state, id);
+    let _ = state.sockets.remove(handle);
+    release_socket_slot(state.mem, id);
+    Ok(())
+}
+
+/// Take a reference to a socket for another descriptor (dup, fork, SCM_RIGHTS).
+pub fn socket_acquire(id: SocketId) -> Result<(), NetError> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let slot = unsafe { SOCKET_TABLE.get_mut(id) }.filter(|slot| slot.used).ok_or(NetError::Invalid)?;
+    slot.refs = slot.refs.checked_add(1).ok_or(NetError::NoMem)?;
+    Ok(())
+}
+
+/// Drop a reference taken by `socket_acquire`; returns whether it was the last,
+/// in which case the caller closes the socket with `socket_close`.
+pub fn socket_release(id: SocketId) -> Result<bool, NetError> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let slot = unsafe { SOCKET_TABLE.get_mut(id) }.filter(|slot| slot.used).ok_or(NetError::Invalid)?;
+    slot.refs = slot.refs.saturating_sub(1);
+    Ok(slot.refs == 0)
+}
+
+/// Free orphaned TCP sockets once their FIN exchange has finished, the RST went out, or they timed out.
+fn reap_orphans(state: &mut NetState) {
+    for id in 0..socket_capacity() {
+        let Some(handle) = orphan_handle(id) else {
+            continue;
+        };
+        let socket = state.sockets.get::<TcpSocket>(handle);
+        let done = match socket.state() {
+            TcpState::TimeWait => true,
+            TcpState::Closed => socket.remote_endpoint().is_none(),
+            _ => false,
+        };
+        if done {
+            let _ = state.sockets.remove(handle);
+            release_socket_slot(state.mem, id);
+        }
+    }
+}
+
+fn orphan_handle(id: SocketId) -> Option<SocketHandle> {
+    // SAFETY: SocketHandle is a plain index and can be copied by value.
+    unsafe {
+        let slot = SOCKET_TABLE.get(id)?;
+        (slot.used && slot.orphan).then(|| ptr::read(slot.handle.as_ptr()))
+    }
+}
+
+/// Bytes written to a TCP socket that the peer has not acknowledged yet.
+pub fn socket_send_queue(id: SocketId) -> Result<usize, NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    match socket_handle(id).ok_or(NetError::Invalid)? {
+        (AxSocketKind::Tcp, handle) => Ok(state.sockets.get::<TcpSocket>(handle).send_queue()),
+        _ => Ok(0),
+    }
+}
+
+/// Socket counts and buffer memory for `/proc/net/sockstat`; memory is in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SockStat {
+    /// Slots in use, of every kind, including orphans and queued connections.
+    pub sockets: usize,
+    /// TCP sockets owned by a descriptor, orphaned, or accepted but not yet returned by accept(2).
+    pub tcp_inuse: usize,
+    pub tcp_orphan: usize,
+    pub tcp_tw: usize,
+    /// Every TCP slot, counting the pre-listening sockets of accept queues.
+    pub tcp_alloc: usize,
+    pub tcp_mem: usize,
+    pub udp_inuse: usize,
+    pub udp_mem: usize,
+    /// Raw sockets; ping sockets count in `sockets` only, as in Linux.
+    pub raw_inuse: usize,
+    /// Bytes of the buffer pool handed out, and cached from the kernel.
+    pub pool_used: usize,
+    pub pool_capacity: usize,
+    /// Current size of the socket table.
+    pub table_len: usize,
+}
+
+/// Snapshot socket usage and buffer memory.
+pub fn sockstat() -> SockStat {
+    let mut stat = SockStat::default();
+    // SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let Some(state) = (unsafe { NET_STATE.as_ref() }) else {
+        return stat;
+    };
+    // SAFETY: socket table and buffer pool access is serialized by the single-hart runtime.
+    unsafe {
+        stat.pool_used = BUFFER_POOL.used();
+        stat.pool_capacity = BUFFER_POOL.capacity();
+        stat.table_len = SOCKET_TABLE.len();
+        for slot in SOCKET_TABLE.iter().filter(|slot| slot.used) {
+            let mem: usize = slot.bufs.iter().flatten().map(PoolBlock::len).sum();
+            stat.sockets += 1;
+            match slot.kind {
+                AxSocketKind::Tcp => {
+                    stat.tcp_alloc += 1;
+                    stat.tcp_mem += mem;
+                    // 尚未完成握手的预监听 socket 不算在用。
+                    if slot.parent.is_none() || slot.accept_seq != 0 {
+                        stat.tcp_inuse += 1;
+                    }
+                    if slot.orphan {
+                        stat.tcp_orphan += 1;
+                    }
+                    let handle = ptr::read(slot.handle.as_ptr());
+                    if state.sockets.get::<TcpSocket>(handle).state() == TcpState::TimeWait {
+                        stat.tcp_tw += 1;
+                    }
+                }
+                AxSocketKind::Udp => {
+                    stat.udp_inuse += 1;
+                    stat.udp_mem += mem;
+                }
+                AxSocketKind::Raw => stat.raw_inuse += 1,
+                AxSocketKind::Icmp => {}
+            }
+        }
+    }
+    stat
 }
 
 /// Shutdown a socket for reading and/or writing.
@@ -1070,7 +2489,7 @@ pub fn socket_shutdown(id: SocketId, how: usize) -> Result<(), NetError> {
                 _ => Err(NetError::Invalid),
             }
         }
-        AxSocketKind::Udp => match how {
+        AxSocketKind::Udp | AxSocketKind::Icmp | AxSocketKind::Raw => match how {
             0 | 1 | 2 => Ok(()),
             _ => Err(NetError::Invalid),
         },
@@ -1085,15 +2504,21 @@ pub fn socket_local_endpoint(id: SocketId) -> Result<(IpAddress, u16), NetError>
     match kind {
         AxSocketKind::Tcp => {
             let port = socket_local_port(id)?;
-            let ip = IpAddress::Ipv4(local_ipv4());
-            Ok((ip, port))
+            let socket = state.sockets.get::<TcpSocket>(handle);
+            let ip = socket.local_endpoint().map(|ep| ep.addr).or(socket_local_addr(id));
+            Ok((ip.unwrap_or(unspecified_addr(id)), port))
         }
         AxSocketKind::Udp => {
             let socket = state.sockets.get_mut::<UdpSocket>(handle);
             let endpoint = socket.endpoint();
-            let ip = endpoint.addr.unwrap_or(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED));
+            let ip = endpoint.addr.unwrap_or(unspecified_addr(id));
             Ok((ip, endpoint.port))
         }
+        AxSocketKind::Icmp | AxSocketKind::Raw => {
+            // SAFETY: socket table access is serialized by the single-hart runtime.
+            let slot = unsafe { &SOCKET_TABLE[id] };
+            Ok((slot.local_addr.unwrap_or(unspecified_addr(id)), slot.local_port))
+        }
     }
 }
 
@@ -1111,10 +2536,101 @@ pub fn socket_remote_endpoint(id: SocketId) -> Result<Option<(IpAddress, u16)>,
             }
             Ok(socket.remote_endpoint().map(|ep| (ep.addr, ep.port)))
         }
-        AxSocketKind::Udp => Ok(None),
+        // SAFETY: socket table access is serialized by the single-hart runtime.
+        AxSocketKind::Udp => Ok(unsafe { SOCKET_TABLE[id].peer }.map(|ep| (ep.addr, ep.port))),
+        AxSocketKind::Icmp | AxSocketKind::Raw => Ok(None),
+    }
+}
+
+/// Join an IPv4 multicast group on eth0 (`IP_ADD_MEMBERSHIP`).
+///
+/// The interface joins, and sends its IGMP report, only for the first socket
+/// in the group; any datagram socket bound to the port then receives it.
+pub fn socket_join_multicast(id: SocketId, group: Ipv4Address) -> Result<(), NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if kind == AxSocketKind::Tcp {
+        return Err(NetError::Unsupported);
+    }
+    if !group.is_multicast() {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let groups = unsafe { &mut SOCKET_TABLE[id].groups };
+    if groups.contains(&Some(group)) {
+        return Err(NetError::AddrInUse);
+    }
+    let free = groups.iter().position(Option::is_none).ok_or(NetError::NoBufs)?;
+    if group_members(group) == 0 {
+        match state.iface.join_multicast_group(&mut state.device, group, state.now) {
+            // Exhausted 时组已登记，只是首个报告没发出；查询到来时会补报。
+            Ok(_) | Err(MulticastError::Exhausted) => {}
+            Err(MulticastError::GroupTableFull) => return Err(NetError::NoBufs),
+            Err(MulticastError::Ipv6NotSupported) => return Err(NetError::Invalid),
+        }
+    }
+    groups[free] = Some(group);
+    NET_NEED_POLL.store(true, Ordering::Release);
+    Ok(())
+}
+
+/// Leave a group joined with `socket_join_multicast` (`IP_DROP_MEMBERSHIP`).
+pub fn socket_leave_multicast(id: SocketId, group: Ipv4Address) -> Result<(), NetError> {
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    let state = unsafe { NET_STATE.as_mut() }.ok_or(NetError::NotReady)?;
+    socket_handle(id).ok_or(NetError::Invalid)?;
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let groups = unsafe { &mut SOCKET_TABLE[id].groups };
+    let joined = groups.iter().position(|joined| *joined == Some(group)).ok_or(NetError::AddrNotAvail)?;
+    groups[joined] = None;
+    if group_members(group) == 0 {
+        let _ = state.iface.leave_multicast_group(&mut state.device, group, state.now);
+        NET_NEED_POLL.store(true, Ordering::Release);
+    }
+    Ok(())
+}
+
+/// Drop every membership of a socket that is going away.
+fn leave_all_groups(state: &mut NetState, id: SocketId) {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let groups = unsafe { core::mem::replace(&mut SOCKET_TABLE[id].groups, [None; SOCKET_MAX_GROUPS]) };
+    for group in groups.into_iter().flatten() {
+        if group_members(group) == 0 {
+            let _ = state.iface.leave_multicast_group(&mut state.device, group, state.now);
+        }
     }
 }
 
+/// Sockets currently in `group`.
+fn group_members(group: Ipv4Address) -> usize {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe { SOCKET_TABLE.iter().filter(|slot| slot.used && slot.groups.contains(&Some(group))).count() }
+}
+
+/// Set `IP_HDRINCL` on an IPv4 raw socket.
+pub fn socket_set_hdrincl(id: SocketId, hdrincl: bool) -> Result<(), NetError> {
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if kind != AxSocketKind::Raw || socket_domain(id)? != AF_INET {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe {
+        SOCKET_TABLE[id].hdrincl = hdrincl;
+    }
+    Ok(())
+}
+
+/// Return the `IP_HDRINCL` setting of an IPv4 raw socket.
+pub fn socket_hdrincl(id: SocketId) -> Result<bool, NetError> {
+    let (kind, _) = socket_handle(id).ok_or(NetError::Invalid)?;
+    if kind != AxSocketKind::Raw || socket_domain(id)? != AF_INET {
+        return Err(NetError::Invalid);
+    }
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    Ok(unsafe { SOCKET_TABLE[id].hdrincl })
+}
+
 /// Return true if the socket is mid-connection.
 pub fn socket_connecting(id: SocketId) -> Result<bool, NetError> {
     // SAFETY: single-hart early stage, socket table is serialized.
@@ -1140,24 +2656,45 @@ pub fn socket_take_error(id: SocketId) -> Result<Option<NetError>, NetError> {
     }
 }
 
-fn reserve_socket_slot(kind: AxSocketKind) -> Option<SocketId> {
-    // SAFETY: single-hart early stage, socket table is serialized.
-    unsafe {
-        for (idx, slot) in SOCKET_TABLE.iter_mut().enumerate() {
-            if !slot.used {
-                slot.used = true;
-                slot.kind = kind;
-                slot.local_port = 0;
-                slot.listening = false;
-                slot.connecting = false;
-                slot.last_error = None;
-                slot.last_rx_window = 0;
-                slot.last_rx_window_poll = 0;
-                return Some(idx);
+/// Claim a free slot, growing the socket table when all are taken.
+fn reserve_socket_slot(state: &mut NetState, kind: AxSocketKind) -> Option<SocketId> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    let free = unsafe { SOCKET_TABLE.iter().position(|slot| !slot.used) };
+    let idx = match free {
+        Some(idx) => idx,
+        None => {
+            let idx = socket_capacity();
+            if !grow_socket_tables(state.mem, Some(&mut state.sockets)) {
+                return None;
             }
+            idx
         }
-    }
-    None
+    };
+    // SAFETY: single-hart early stage, socket table is serialized.
+    let slot = unsafe { &mut SOCKET_TABLE[idx] };
+    slot.used = true;
+    slot.kind = kind;
+    slot.domain = AF_INET;
+    slot.v6only = false;
+    slot.local_addr = None;
+    slot.local_port = 0;
+    slot.hdrincl = false;
+    slot.listening = false;
+    slot.connecting = false;
+    slot.last_error = None;
+    slot.last_rx_window = 0;
+    slot.last_rx_window_poll = 0;
+    slot.parent = None;
+    slot.backlog = 0;
+    slot.syn_room = 0;
+    slot.accept_seq = 0;
+    slot.opts = SocketOptions::new(max_buf_len(kind));
+    slot.orphan = false;
+    slot.refs = 0;
+    slot.bufs = [None; 2];
+    slot.peer = None;
+    slot.groups = [None; SOCKET_MAX_GROUPS];
+    Some(idx)
 }
 
 fn set_socket_handle(id: SocketId, handle: SocketHandle) {
@@ -1228,11 +2765,16 @@ fn socket_local_port(id: SocketId) -> Result<u16, NetError> {
     Ok(port)
 }
 
+fn socket_local_addr(id: SocketId) -> Option<IpAddress> {
+    // SAFETY: socket table access is serialized by the single-hart runtime.
+    unsafe { SOCKET_TABLE.get(id).filter(|slot| slot.used).and_then(|slot| slot.local_addr) }
+}
+
 fn socket_handle(id: SocketId) -> Option<(AxSocketKind, SocketHandle)> {
     // SAFETY: SocketHandle is a plain index and can be copied by value.
     unsafe {
         let slot = SOCKET_TABLE.get(id)?;
-        if !slot.used {
+        if !slot.used || slot.orphan {
             return None;
         }
         let handle = ptr::read(slot.handle.as_ptr());
@@ -1240,17 +2782,29 @@ fn socket_handle(id: SocketId) -> Option<(AxSocketKind, SocketHandle)> {
     }
 }
 
-fn release_socket_slot(id: SocketId) {
+fn release_socket_slot(mem: &dyn NetMemory, id: SocketId) {
     // SAFETY: single-hart early stage, socket table is serialized.
     unsafe {
         if let Some(slot) = SOCKET_TABLE.get_mut(id) {
             slot.used = false;
+            slot.local_addr = None;
             slot.local_port = 0;
+            slot.hdrincl = false;
             slot.listening = false;
             slot.connecting = false;
             slot.last_error = None;
             slot.last_rx_window = 0;
             slot.last_rx_window_poll = 0;
+            slot.parent = None;
+            slot.backlog = 0;
+            slot.syn_room = 0;
+            slot.accept_seq = 0;
+            slot.orphan = false;
+            slot.refs = 0;
+            free_socket_bufs(mem, slot.bufs);
+            slot.bufs = [None; 2];
+            slot.peer = None;
+            slot.groups = [None; SOCKET_MAX_GROUPS];
         }
     }
 }
@@ -1279,22 +2833,175 @@ fn set_socket_error(id: SocketId, err: Option<NetError>) -> Result<(), NetError>
     Ok(())
 }
 
-static mut SOCKET_STORAGE: [SocketStorage<'static>; SOCKET_STORAGE_LEN] =
-    [SocketStorage::EMPTY; SOCKET_STORAGE_LEN];
-
 fn should_loopback(frame: &[u8]) -> bool {
     const ETH_HDR_LEN: usize = 14;
     const ETH_TYPE_IPV4: u16 = 0x0800;
+    const ETH_TYPE_IPV6: u16 = 0x86dd;
     if frame.len() < ETH_HDR_LEN + 20 {
         return false;
     }
     let eth_type = u16::from_be_bytes([frame[12], frame[13]]);
-    if eth_type != ETH_TYPE_IPV4 {
+    let ip = &frame[ETH_HDR_LEN..];
+    match eth_type {
+        ETH_TYPE_IPV4 => is_local_ipv4(Ipv4Address([ip[16], ip[17], ip[18], ip[19]])),
+        ETH_TYPE_IPV6 if ip.len() >= 40 => is_local_ipv6(Ipv6Address::from_bytes(&ip[24..40])),
+        _ => false,
+    }
+}
+
+/// IPv4 UDP to a broadcast or multicast address. Local sockets get a copy as well,
+/// as on Linux, where `IP_MULTICAST_LOOP` is on by default.
+fn is_udp_group_frame(frame: &[u8]) -> bool {
+    const ETH_HDR_LEN: usize = 14;
+    const IP_PROTO_UDP: u8 = 17;
+    // 广播与组播目的 MAC 的最低位都是 1。
+    frame.len() >= ETH_HDR_LEN + 20
+        && frame[0] & 1 != 0
+        && frame[12..14] == [0x08, 0x00]
+        && frame[ETH_HDR_LEN + 9] == IP_PROTO_UDP
+}
+
+/// Addresses owned by this host: all of 127.0.0.0/8 plus eth0's address once configured.
+fn is_local_ipv4(addr: Ipv4Address) -> bool {
+    addr.0[0] == NET_LOOPBACK_ADDR[0] || local_ipv4() == Some(addr)
+}
+
+/// Addresses owned by this host: ::1 plus eth0's link-local and global addresses.
+fn is_local_ipv6(addr: Ipv6Address) -> bool {
+    // SAFETY: single-hart; NET_IPV6_ADDRS is only rewritten by sync_ip_addrs.
+    addr.is_loopback() || (!addr.is_unspecified() && unsafe { NET_IPV6_ADDRS.contains(&addr) })
+}
+
+/// Answer neighbor solicitations for our own IPv6 addresses locally, the NDP
+/// counterpart of `try_loopback_arp`.
+fn try_loopback_ndp(frame: &[u8], mac: EthernetAddress) -> bool {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return false;
+    };
+    if eth.ethertype() != EthernetProtocol::Ipv6 {
         return false;
     }
-    let ip = &frame[ETH_HDR_LEN..];
-    let dst = [ip[16], ip[17], ip[18], ip[19]];
-    dst == NET_IPV4_ADDR
+    let Ok(ip) = Ipv6Packet::new_checked(eth.payload()) else {
+        return false;
+    };
+    if ip.next_header() != IpProtocol::Icmpv6 {
+        return false;
+    }
+    let Ok(icmp) = Icmpv6Packet::new_checked(ip.payload()) else {
+        return false;
+    };
+    let Ok(NdiscRepr::NeighborSolicit { target_addr, .. }) = NdiscRepr::parse(&icmp) else {
+        return false;
+    };
+    if !is_local_ipv6(target_addr) {
+        return false;
+    }
+    let advert = NdiscRepr::NeighborAdvert {
+        flags: NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE,
+        target_addr,
+        lladdr: Some(mac.into()),
+    };
+    // SAFETY: single-hart; buffer reused for loopback replies.
+    let buf = unsafe { &mut NDP_TX_BUF[..] };
+    let len = emit_ndisc_frame(buf, mac, eth.src_addr(), target_addr, ip.src_addr(), advert);
+// SAFETY: static buffers/state are initialized and accessed under net state lock.
+    unsafe {
+        LOOPBACK_QUEUE.push(&buf[..len]);
+    }
+    NET_NEED_POLL.store(true, Ordering::Release);
+    true
+}
+
+/// Build an Ethernet + IPv6 + NDP frame into `buf` and return its length.
+fn emit_ndisc_frame(
+    buf: &mut [u8],
+    src_mac: EthernetAddress,
+    dst_mac: EthernetAddress,
+    src_addr: Ipv6Address,
+    dst_addr: Ipv6Address,
+    ndisc: NdiscRepr,
+) -> usize {
+    let icmp = Icmpv6Repr::Ndisc(ndisc);
+    let ip = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: icmp.buffer_len(),
+        hop_limit: 0xff,
+    };
+    let eth = EthernetRepr {
+        src_addr: src_mac,
+        dst_addr: dst_mac,
+        ethertype: EthernetProtocol::Ipv6,
+    };
+    let len = eth.buffer_len() + ip.buffer_len() + icmp.buffer_len();
+    let mut frame = EthernetFrame::new_unchecked(&mut buf[..len]);
+    eth.emit(&mut frame);
+    let mut ip_pkt = Ipv6Packet::new_unchecked(frame.payload_mut());
+    ip.emit(&mut ip_pkt);
+    let mut icmp_pkt = Icmpv6Packet::new_unchecked(ip_pkt.payload_mut());
+    icmp.emit(
+        &IpAddress::Ipv6(src_addr),
+        &IpAddress::Ipv6(dst_addr),
+        &mut icmp_pkt,
+        &ChecksumCapabilities::default(),
+    );
+    len
+}
+
+fn send_router_solicit(state: &mut NetState) {
+    let (Some(dev), Some(src)) = (state.device.dev, state.eth6_link_local) else {
+        return;
+    };
+    let dst = Ipv6Address::LINK_LOCAL_ALL_ROUTERS;
+    let solicit = NdiscRepr::RouterSolicit {
+        lladdr: Some(state.device.mac.into()),
+    };
+    let dst_mac = EthernetAddress([0x33, 0x33, dst.0[12], dst.0[13], dst.0[14], dst.0[15]]);
+    // SAFETY: single-hart; the NDP buffer is only used from the poll path and TX token.
+    let buf = unsafe { &mut NDP_TX_BUF[..] };
+    let len = emit_ndisc_frame(buf, state.device.mac, dst_mac, src.address(), dst, solicit);
+    let _ = dev.send(&buf[..len]);
+}
+
+/// Remember a router advertisement carrying an autonomous /64 prefix for SLAAC.
+fn record_router_advert(frame: &[u8]) {
+    let Ok(eth) = EthernetFrame::new_checked(frame) else {
+        return;
+    };
+    if eth.ethertype() != EthernetProtocol::Ipv6 {
+        return;
+    }
+    let Ok(ip) = Ipv6Packet::new_checked(eth.payload()) else {
+        return;
+    };
+    if ip.next_header() != IpProtocol::Icmpv6 || ip.hop_limit() != 0xff || !ip.src_addr().is_link_local() {
+        return;
+    }
+    let Ok(icmp) = Icmpv6Packet::new_checked(ip.payload()) else {
+        return;
+    };
+    let Ok(NdiscRepr::RouterAdvert {
+        router_lifetime,
+        prefix_info: Some(prefix),
+        ..
+    }) = NdiscRepr::parse(&icmp)
+    else {
+        return;
+    };
+    if prefix.prefix_len != NET_IPV6_SLAAC_PREFIX
+        || !prefix.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+        || prefix.valid_lifetime.total_millis() == 0
+        || prefix.prefix.is_link_local()
+    {
+        return;
+    }
+    let default_route = router_lifetime.total_millis() != 0;
+    // SAFETY: single-hart; consumed by poll_slaac after iface.poll returns.
+    unsafe {
+        NET_ROUTER_ADVERT = Some((prefix.prefix, ip.src_addr(), default_route));
+    }
+    NET_NEED_POLL.store(true, Ordering::Release);
 }
 
 fn try_loopback_arp(frame: &[u8], mac: EthernetAddress) -> bool {
@@ -1320,13 +3027,13 @@ fn try_loopback_arp(frame: &[u8], mac: EthernetAddress) -> bool {
     else {
         return false;
     };
-    if target_protocol_addr != local_ipv4() {
+    if !is_local_ipv4(target_protocol_addr) {
         return false;
     }
     let reply = ArpRepr::EthernetIpv4 {
         operation: ArpOperation::Reply,
         source_hardware_addr: mac,
-        source_protocol_addr: local_ipv4(),
+        source_protocol_addr: target_protocol_addr,
         target_hardware_addr: source_hardware_addr,
         target_protocol_addr: source_protocol_addr,
     };
@@ -1348,22 +3055,14 @@ fn try_loopback_arp(frame: &[u8], mac: EthernetAddress) -> bool {
     true
 }
 
-fn local_ipv4() -> Ipv4Address {
-    Ipv4Address::new(
-        NET_IPV4_ADDR[0],
-        NET_IPV4_ADDR[1],
-        NET_IPV4_ADDR[2],
-        NET_IPV4_ADDR[3],
-    )
+fn local_ipv4() -> Option<Ipv4Address> {
+    let ip = NET_IPV4_ADDR.load(Ordering::Acquire);
+    (ip != 0).then(|| Ipv4Address::from_bytes(&ip.to_be_bytes()))
 }
 
-fn gateway_ipv4() -> Ipv4Address {
-    Ipv4Address::new(
-        NET_IPV4_GATEWAY[0],
-        NET_IPV4_GATEWAY[1],
-        NET_IPV4_GATEWAY[2],
-        NET_IPV4_GATEWAY[3],
-    )
+fn gateway_ipv4() -> Option<Ipv4Address> {
+    let ip = NET_IPV4_GATEWAY.load(Ordering::Acquire);
+    (ip != 0).then(|| Ipv4Address::from_bytes(&ip.to_be_bytes()))
 }
 
 fn ipv4_to_u32(addr: Ipv4Address) -> u32 {
@@ -1412,10 +3111,10 @@ fn record_arp_reply(frame: &[u8]) {
     else {
         return;
     };
-    if target_protocol_addr != local_ipv4() {
+    if local_ipv4() != Some(target_protocol_addr) {
         return;
     }
-    if source_protocol_addr != gateway_ipv4() {
+    if gateway_ipv4() != Some(source_protocol_addr) {
         return;
     }
     let _ = NET_ARP_REPLY_IP.compare_exchange(
@@ -1427,9 +3126,13 @@ fn record_arp_reply(frame: &[u8]) {
 }
 
 fn send_arp_probe(state: &mut NetState) {
-    let src_mac = EthernetAddress(state.device.dev.mac_address());
-    let src_ip = local_ipv4();
-    let target_ip = gateway_ipv4();
+    let Some(dev) = state.device.dev else {
+        return;
+    };
+    let (Some(src_ip), Some(target_ip)) = (local_ipv4(), gateway_ipv4()) else {
+        return;
+    };
+    let src_mac = state.device.mac;
     let arp = ArpRepr::EthernetIpv4 {
         operation: ArpOperation::Request,
         source_hardware_addr: src_mac,
@@ -1453,7 +3156,7 @@ fn send_arp_probe(state: &mut NetState) {
         let mut pkt = ArpPacket::new_unchecked(frame.payload_mut());
         arp.emit(&mut pkt);
     }
-    let _ = state.device.dev.send(buf);
+    let _ = dev.send(buf);
     let _ = NET_ARP_SENT_IP.compare_exchange(
         0,
         ipv4_to_u32(target_ip),
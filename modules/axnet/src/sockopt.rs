@@ -0,0 +1,124 @@
+//! Per-socket options set through setsockopt(2) and how they map onto smoltcp.
+
+/// Smallest buffer size accepted for `SO_RCVBUF`/`SO_SNDBUF`.
+pub const SOCK_MIN_BUF: usize = 2048;
+/// Linux `tcp_keepalive_time` default, in seconds.
+pub const TCP_KEEPIDLE_DEFAULT: u32 = 7200;
+/// Linux `tcp_keepalive_intvl` default, in seconds.
+pub const TCP_KEEPINTVL_DEFAULT: u32 = 75;
+/// Linux `tcp_keepalive_probes` default.
+pub const TCP_KEEPCNT_DEFAULT: u32 = 9;
+
+/// One option change, named after its Linux counterpart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SockOpt {
+    /// `SO_REUSEADDR`.
+    ReuseAddr(bool),
+    /// `SO_REUSEPORT`.
+    ReusePort(bool),
+    /// `SO_KEEPALIVE`.
+    KeepAlive(bool),
+    /// `SO_RCVBUF` in bytes, already doubled the way Linux reports it back.
+    RecvBuf(usize),
+    /// `SO_SNDBUF` in bytes, already doubled.
+    SendBuf(usize),
+    /// `SO_LINGER`: `Some(seconds)` when enabled.
+    Linger(Option<u32>),
+    /// `TCP_NODELAY`.
+    NoDelay(bool),
+    /// `TCP_KEEPIDLE` in seconds.
+    KeepIdle(u32),
+    /// `TCP_KEEPINTVL` in seconds.
+    KeepIntvl(u32),
+    /// `TCP_KEEPCNT`.
+    KeepCnt(u32),
+    /// `TCP_QUICKACK`.
+    QuickAck(bool),
+    /// `TCP_USER_TIMEOUT` in milliseconds, 0 for the default.
+    UserTimeout(u32),
+    /// `IP_TTL`/`IPV6_UNICAST_HOPS`; `None` restores the default.
+    HopLimit(Option<u8>),
+    /// `SO_BROADCAST`: datagrams may be sent to broadcast addresses.
+    Broadcast(bool),
+}
+
+/// Current option values of a socket, as getsockopt(2) reports them.
+///
+/// Connections queued on a listener start with the listener's options.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SocketOptions {
+    pub reuseaddr: bool,
+    pub reuseport: bool,
+    pub keepalive: bool,
+    /// Receive and send buffer capacity in bytes.
+    pub rcvbuf: usize,
+    pub sndbuf: usize,
+    pub linger: Option<u32>,
+    pub nodelay: bool,
+    pub keepidle: u32,
+    pub keepintvl: u32,
+    pub keepcnt: u32,
+    pub quickack: bool,
+    pub user_timeout_ms: u32,
+    pub hop_limit: Option<u8>,
+    pub broadcast: bool,
+}
+
+impl SocketOptions {
+    /// Defaults for a fresh socket whose buffers hold `buf_len` bytes each.
+    pub const fn new(buf_len: usize) -> Self {
+        Self {
+            reuseaddr: false,
+            reuseport: false,
+            keepalive: false,
+            rcvbuf: buf_len,
+            sndbuf: buf_len,
+            linger: None,
+            nodelay: false,
+            keepidle: TCP_KEEPIDLE_DEFAULT,
+            keepintvl: TCP_KEEPINTVL_DEFAULT,
+            keepcnt: TCP_KEEPCNT_DEFAULT,
+            quickack: false,
+            user_timeout_ms: 0,
+            hop_limit: None,
+            broadcast: false,
+        }
+    }
+
+    /// Record `opt`; buffer sizes are clamped to `[SOCK_MIN_BUF, max_buf]`.
+    pub fn apply(&mut self, opt: SockOpt, max_buf: usize) {
+        match opt {
+            SockOpt::ReuseAddr(on) => self.reuseaddr = on,
+            SockOpt::ReusePort(on) => self.reuseport = on,
+            SockOpt::KeepAlive(on) => self.keepalive = on,
+            SockOpt::RecvBuf(len) => self.rcvbuf = len.clamp(SOCK_MIN_BUF, max_buf),
+            SockOpt::SendBuf(len) => self.sndbuf = len.clamp(SOCK_MIN_BUF, max_buf),
+            SockOpt::Linger(linger) => self.linger = linger,
+            SockOpt::NoDelay(on) => self.nodelay = on,
+            SockOpt::KeepIdle(secs) => self.keepidle = secs,
+            SockOpt::KeepIntvl(secs) => self.keepintvl = secs,
+            SockOpt::KeepCnt(count) => self.keepcnt = count,
+            SockOpt::QuickAck(on) => self.quickack = on,
+            SockOpt::UserTimeout(ms) => self.user_timeout_ms = ms,
+            SockOpt::HopLimit(hops) => self.hop_limit = hops,
+            SockOpt::Broadcast(on) => self.broadcast = on,
+        }
+    }
+
+    /// Keep-alive interval for smoltcp: the idle time before the first probe.
+    pub fn keep_alive_ms(&self) -> Option<u64> {
+        self.keepalive.then(|| u64::from(self.keepidle) * 1000)
+    }
+
+    /// Abort timeout for smoltcp.
+    ///
+    /// `TCP_USER_TIMEOUT` wins; otherwise a keep-alive socket gives up after the
+    /// idle time plus all probes, like Linux, and anything else never times out.
+    pub fn timeout_ms(&self) -> Option<u64> {
+        if self.user_timeout_ms != 0 {
+            return Some(u64::from(self.user_timeout_ms));
+        }
+        let probes = u64::from(self.keepintvl) * u64::from(self.keepcnt);
+        self.keepalive.then(|| (u64::from(self.keepidle) + probes) * 1000)
+    }
+}
@@ -0,0 +1,498 @@
+//! Socket-layer conformance tests against a peer stack on an in-memory wire.
+//!
+//! The stack is a process-wide singleton, so it is initialized once with eth0
+//! on a `wire` and every test holds `LOCK` while it runs. Each test gets a
+//! fresh `Peer` and its own ports, and closes the sockets it opened.
+
+use std::boxed::Box;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::vec;
+use std::vec::Vec;
+
+use smoltcp::iface::SocketHandle;
+use smoltcp::socket::tcp::State as TcpState;
+use smoltcp::wire::{IpEndpoint, Ipv4Cidr};
+
+use super::*;
+use crate::wire::{wire, HostMemory, Peer, WireEnd};
+
+const AF_INET: i32 = 2;
+const SOCK_STREAM: i32 = 1;
+const SOCK_DGRAM: i32 = 2;
+const POLLIN: u16 = 0x001;
+const POLLOUT: u16 = 0x004;
+const POLLHUP: u16 = 0x010;
+const LOCAL_IP: Ipv4Address = Ipv4Address([10, 0, 2, 15]);
+const PEER_IP: Ipv4Address = Ipv4Address([10, 0, 2, 2]);
+const LOCAL: IpAddress = IpAddress::Ipv4(LOCAL_IP);
+const PEER: IpAddress = IpAddress::Ipv4(PEER_IP);
+const ANY: IpAddress = IpAddress::Ipv4(Ipv4Address::UNSPECIFIED);
+/// Simulated time per step; above the delayed-ACK timeout so every step acks.
+const STEP_MS: u64 = 20;
+const MAX_STEPS: usize = 500;
+
+static LOCK: Mutex<()> = Mutex::new(());
+static HOST_MEMORY: HostMemory = HostMemory;
+static PEER_END: OnceLock<WireEnd> = OnceLock::new();
+static CLOCK: AtomicU64 = AtomicU64::new(0);
+
+/// The stack under test plus the peer; holds `LOCK` for the test's lifetime.
+struct Net {
+    peer: Peer,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Net {
+    fn new() -> Self {
+        let lock = LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let end = *PEER_END.get_or_init(|| {
+            let (ours, theirs) = wire([0x52, 0x54, 0, 0x12, 0x34, 0x56], [0x52, 0x54, 0, 0x12, 0x34, 0x57]);
+            let dev: &'static WireEnd = Box::leak(Box::new(ours));
+            let config = Ipv4Config { addr: LOCAL_IP, prefix_len: 24, gateway: None };
+            init(Some(dev), &HOST_MEMORY, NetConfig::Static(config)).unwrap();
+            theirs
+        });
+        end.clear();
+        let mut net = Self { peer: Peer::new(end, Ipv4Cidr::new(PEER_IP, 24)), _lock: lock };
+        // 让上一个测试遗留的连接在新的对端面前收尾。
+        net.run(10);
+        net
+    }
+
+    /// Advance the clock one step and poll both stacks.
+    fn step(&mut self) {
+        let now = CLOCK.fetch_add(STEP_MS, Ordering::Relaxed) + STEP_MS;
+        request_poll();
+        let _ = poll(now);
+        self.peer.poll(now);
+    }
+
+    fn run(&mut self, steps: usize) {
+        for _ in 0..steps {
+            self.step();
+        }
+    }
+
+    /// Step until `done` holds, failing the test if it never does.
+    fn until(&mut self, what: &str, mut done: impl FnMut(&mut Self) -> bool) {
+        for _ in 0..MAX_STEPS {
+            if done(self) {
+                return;
+            }
+            self.step();
+        }
+        panic!("timed out waiting for {what}");
+    }
+
+    /// Listen on the peer at `port` and connect a fresh stack socket to it.
+    fn connect_to_peer(&mut self, port: u16, rcvbuf: Option<usize>) -> (SocketId, SocketHandle) {
+        let listener = self.peer.tcp_socket(65536);
+        self.peer.tcp(listener).listen(port).unwrap();
+        let id = socket_create(AF_INET, SOCK_STREAM, 0).unwrap();
+        if let Some(len) = rcvbuf {
+            socket_set_option(id, SockOpt::RecvBuf(len)).unwrap();
+        }
+        socket_connect(id, PEER, port).unwrap();
+        self.until("connect", |_| socket_poll(id, POLLOUT).unwrap() & POLLOUT != 0);
+        assert_eq!(self.peer.tcp(listener).state(), TcpState::Established);
+        (id, listener)
+    }
+
+    /// Read from `id` until `len` bytes arrived.
+    fn recv_exact(&mut self, id: SocketId, len: usize) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut buf = [0u8; 1024];
+        self.until("stream data", |_| {
+            match socket_recv(id, &mut buf, false) {
+                Ok(received) => data.extend_from_slice(&buf[..received.len]),
+                Err(err) => assert_eq!(err, NetError::WouldBlock),
+            }
+            data.len() >= len
+        });
+        data
+    }
+}
+
+fn pattern(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i % 251) as u8).collect()
+}
+
+#[test]
+fn tcp_connect_exchanges_data() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7001, None);
+    assert_eq!(socket_remote_endpoint(id).unwrap(), Some((PEER, 7001)));
+    assert_eq!(socket_local_endpoint(id).unwrap().0, LOCAL);
+    assert_eq!(socket_connect(id, PEER, 7001), Err(NetError::IsConnected));
+
+    assert_eq!(socket_send(id, b"hello", None), Ok(5));
+    net.until("peer data", |net| net.peer.tcp(peer).recv_queue() == 5);
+    let mut buf = [0u8; 16];
+    assert_eq!(net.peer.tcp(peer).recv_slice(&mut buf), Ok(5));
+    assert_eq!(&buf[..5], b"hello");
+
+    net.peer.tcp(peer).send_slice(b"world!").unwrap();
+    net.until("readable", |_| socket_poll(id, POLLIN).unwrap() & POLLIN != 0);
+    let received = socket_recv(id, &mut buf, true).unwrap();
+    assert_eq!((received.len, received.from), (6, None));
+    let received = socket_recv(id, &mut buf, false).unwrap();
+    assert_eq!(&buf[..received.len], b"world!");
+    assert_eq!(socket_recv(id, &mut buf, false).map(|r| r.len), Err(NetError::WouldBlock));
+
+    socket_close(id).unwrap();
+    net.until("peer close", |net| !net.peer.tcp(peer).may_recv());
+}
+
+#[test]
+fn tcp_connect_refused() {
+    let mut net = Net::new();
+    let id = socket_create(AF_INET, SOCK_STREAM, 0).unwrap();
+    socket_connect(id, PEER, 7002).unwrap();
+    assert_eq!(socket_connect(id, PEER, 7002), Err(NetError::InProgress));
+    net.until("reset", |_| socket_poll(id, POLLOUT).unwrap() & POLLHUP != 0);
+    assert_eq!(socket_take_error(id), Ok(Some(NetError::ConnRefused)));
+    assert_eq!(socket_take_error(id), Ok(None));
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn tcp_accept_honours_backlog() {
+    let mut net = Net::new();
+    let listener = socket_create(AF_INET, SOCK_STREAM, 0).unwrap();
+    socket_bind(listener, ANY, 7003).unwrap();
+    socket_listen(listener, 2).unwrap();
+    assert_eq!(socket_accept(listener).map(|(id, _)| id), Err(NetError::WouldBlock));
+    assert_eq!(socket_poll(listener, POLLIN), Ok(0));
+
+    // 队列深度为 2：第三个 SYN 被丢弃而不是 RST，对端停在 SynSent 等待重传。
+    let clients: Vec<_> = (0..3).map(|_| net.peer.tcp_socket(4096)).collect();
+    for (i, &client) in clients.iter().enumerate() {
+        net.peer.connect(client, (LOCAL_IP, 7003), 50001 + i as u16);
+    }
+    net.until("handshakes", |net| {
+        clients[..2].iter().all(|&c| net.peer.tcp(c).state() == TcpState::Established)
+    });
+    // 对端进入 Established 时最后一个 ACK 还在线上。
+    net.run(2);
+    let states: Vec<_> = clients.iter().map(|&c| net.peer.tcp(c).state()).collect();
+    assert_eq!(states, [TcpState::Established, TcpState::Established, TcpState::SynSent]);
+    assert_eq!(socket_poll(listener, POLLIN), Ok(POLLIN));
+
+    let mut accepted = Vec::new();
+    for _ in 0..2 {
+        let (id, remote) = socket_accept(listener).unwrap();
+        accepted.push((id, remote.unwrap()));
+    }
+    let mut ports: Vec<_> = accepted.iter().map(|&(_, (addr, port))| (addr, port)).collect();
+    ports.sort();
+    assert_eq!(ports, [(PEER, 50001), (PEER, 50002)]);
+    assert_eq!(socket_accept(listener).map(|(id, _)| id), Err(NetError::WouldBlock));
+
+    // accept 之后队列补满，第三个客户端重传的 SYN 得以完成握手。
+    net.until("retransmitted SYN", |_| socket_poll(listener, POLLIN).unwrap() & POLLIN != 0);
+    let (third, remote) = socket_accept(listener).unwrap();
+    assert_eq!(remote, Some((PEER, 50003)));
+    assert_eq!(net.peer.tcp(clients[2]).state(), TcpState::Established);
+
+    for (id, _) in accepted {
+        socket_close(id).unwrap();
+    }
+    socket_close(third).unwrap();
+    socket_close(listener).unwrap();
+    net.until("peer close", |net| clients.iter().all(|&c| !net.peer.tcp(c).may_recv()));
+}
+
+#[test]
+fn tcp_listen_keeps_few_idle_sockets() {
+    let mut net = Net::new();
+    let listener = socket_create(AF_INET, SOCK_STREAM, 0).unwrap();
+    socket_bind(listener, ANY, 7008).unwrap();
+    let before = sockstat();
+    socket_listen(listener, 128).unwrap();
+    // 只有少量预监听 socket 占用缓冲区，而不是整个 backlog。
+    let after = sockstat();
+    let idle = after.tcp_alloc - before.tcp_alloc;
+    assert!((1..=8).contains(&idle));
+    assert!(after.pool_used - before.pool_used <= idle * 2 * 65536);
+
+    // 连接数超过空闲的预监听 socket 时按需补充，全部进入队列。
+    let count = idle * 2 + 1;
+    let clients: Vec<_> = (0..count).map(|_| net.peer.tcp_socket(4096)).collect();
+    for (i, &client) in clients.iter().enumerate() {
+        net.peer.connect(client, (LOCAL_IP, 7008), 51001 + i as u16);
+    }
+    let mut accepted = Vec::new();
+    net.until("handshakes", |_| {
+        while let Ok((id, _)) = socket_accept(listener) {
+            accepted.push(id);
+        }
+        accepted.len() == count
+    });
+    let stat = sockstat();
+    assert_eq!(stat.tcp_alloc - stat.tcp_inuse, idle);
+
+    for id in accepted {
+        socket_close(id).unwrap();
+    }
+    socket_close(listener).unwrap();
+    net.until("peer close", |net| clients.iter().all(|&c| !net.peer.tcp(c).may_recv()));
+}
+
+#[test]
+fn tcp_shutdown_write_keeps_receiving() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7004, None);
+    socket_shutdown(id, 1).unwrap();
+    net.until("peer sees FIN", |net| net.peer.tcp(peer).state() == TcpState::CloseWait);
+    assert_eq!(socket_send(id, b"late", None), Err(NetError::Pipe));
+
+    net.peer.tcp(peer).send_slice(b"still open").unwrap();
+    assert_eq!(net.recv_exact(id, 10), b"still open");
+    net.peer.tcp(peer).close();
+    let mut buf = [0u8; 16];
+    net.until("EOF", |_| socket_recv(id, &mut buf, false).map(|r| r.len) == Ok(0));
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn tcp_peer_shutdown_reports_eof() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7005, None);
+    net.peer.tcp(peer).send_slice(b"bye").unwrap();
+    net.peer.tcp(peer).close();
+    assert_eq!(net.recv_exact(id, 3), b"bye");
+    // 数据读完后 FIN 仍让读端就绪，recv 返回 0。
+    net.until("FIN", |_| socket_poll(id, POLLIN).unwrap() & POLLIN != 0);
+    let mut buf = [0u8; 16];
+    assert_eq!(socket_recv(id, &mut buf, false).map(|r| r.len), Ok(0));
+
+    // 对端只关闭了写方向，本端仍可发送。
+    assert_eq!(socket_send(id, b"reply", None), Ok(5));
+    net.until("peer data", |net| net.peer.tcp(peer).recv_queue() == 5);
+    socket_close(id).unwrap();
+    net.until("peer TIME-WAIT", |net| net.peer.tcp(peer).state() == TcpState::TimeWait);
+}
+
+#[test]
+fn tcp_socket_outlives_all_but_last_reference() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7007, None);
+    // 发送方的描述符与 SCM_RIGHTS 在途引用各持一份，发送方随后关闭。
+    socket_acquire(id).unwrap();
+    socket_acquire(id).unwrap();
+    assert_eq!(socket_release(id), Ok(false));
+
+    // 接收方拿到的 socket 仍是同一条连接。
+    assert_eq!(socket_send(id, b"handed over", None), Ok(11));
+    net.until("peer data", |net| net.peer.tcp(peer).recv_queue() == 11);
+    net.peer.tcp(peer).send_slice(b"ack").unwrap();
+    assert_eq!(net.recv_exact(id, 3), b"ack");
+
+    assert_eq!(socket_release(id), Ok(true));
+    socket_close(id).unwrap();
+    net.until("peer sees FIN", |net| net.peer.tcp(peer).state() == TcpState::CloseWait);
+    net.peer.tcp(peer).close();
+}
+
+#[test]
+fn tcp_send_with_fills_the_send_buffer() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7009, None);
+    let data = pattern(3000);
+    let mut pos = 0usize;
+    // 数据源在 1000 字节处给出短计数，发送随之结束。
+    let sent = socket_send_with(id, data.len(), |buf| {
+        let len = buf.len().min(1000 - pos);
+        buf[..len].copy_from_slice(&data[pos..pos + len]);
+        pos += len;
+        len
+    });
+    assert_eq!(sent, Ok(1000));
+    let sent = socket_send_with(id, data.len() - pos, |buf| {
+        buf.copy_from_slice(&data[pos..pos + buf.len()]);
+        pos += buf.len();
+        buf.len()
+    });
+    assert_eq!(sent, Ok(2000));
+
+    let mut data_in = Vec::new();
+    let mut buf = [0u8; 1024];
+    net.until("peer data", |net| {
+        let received = net.peer.tcp(peer).recv_slice(&mut buf).unwrap();
+        data_in.extend_from_slice(&buf[..received]);
+        data_in.len() == data.len()
+    });
+    assert_eq!(data_in, data);
+
+    let udp = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    assert_eq!(socket_send_with(udp, 1, |_| 0), Err(NetError::Unsupported));
+    socket_close(udp).unwrap();
+    socket_close(id).unwrap();
+    net.until("peer sees FIN", |net| net.peer.tcp(peer).state() == TcpState::CloseWait);
+    net.peer.tcp(peer).close();
+}
+
+#[test]
+fn tcp_recv_window_closes_and_reopens() {
+    let mut net = Net::new();
+    let (id, peer) = net.connect_to_peer(7006, Some(4096));
+    let first = socket_recv_window_event(id).unwrap().unwrap();
+    assert_eq!((first.window, first.capacity, first.queued), (4096, 4096, 0));
+    assert!(socket_recv_window_event(id).unwrap().is_none());
+
+    let data = pattern(10000);
+    let mut sent = net.peer.tcp(peer).send_slice(&data).unwrap();
+    net.until("zero window", |net| {
+        sent += net.peer.tcp(peer).send_slice(&data[sent..]).unwrap();
+        socket_recv_window_event(id).unwrap().map_or(false, |ev| ev.window == 0)
+    });
+    assert!(socket_recv_window_event(id).unwrap().is_none());
+    // 零窗口时对端发不出去，数据积压在它的发送队列里。
+    net.run(5);
+    assert!(net.peer.tcp(peer).send_queue() > 0);
+
+    let mut buf = [0u8; 4096];
+    let received = socket_recv(id, &mut buf, false).unwrap();
+    assert_eq!(received.len, 4096);
+    let reopened = socket_recv_window_event(id).unwrap().unwrap();
+    assert_eq!((reopened.window, reopened.queued), (4096, 0));
+
+    let mut data_in = buf[..received.len].to_vec();
+    net.until("rest of the data", |net| {
+        sent += net.peer.tcp(peer).send_slice(&data[sent..]).unwrap();
+        if let Ok(received) = socket_recv(id, &mut buf, false) {
+            data_in.extend_from_slice(&buf[..received.len]);
+        }
+        data_in.len() == data.len()
+    });
+    assert_eq!(data_in, data);
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn udp_exchanges_datagrams() {
+    let mut net = Net::new();
+    let peer = net.peer.udp_socket(7011);
+    let id = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    socket_bind(id, ANY, 7010).unwrap();
+    assert_eq!(socket_bind(id, ANY, 7012), Err(NetError::Invalid));
+
+    assert_eq!(socket_send(id, b"ping", Some((PEER, 7011))), Ok(4));
+    net.until("peer datagram", |net| net.peer.udp(peer).can_recv());
+    let (payload, meta) = net.peer.udp(peer).recv().unwrap();
+    assert_eq!(payload, b"ping");
+    assert_eq!(meta.endpoint, IpEndpoint::new(LOCAL, 7010));
+
+    net.peer.udp(peer).send_slice(b"pong!", IpEndpoint::new(LOCAL, 7010)).unwrap();
+    net.until("readable", |_| socket_poll(id, POLLIN).unwrap() & POLLIN != 0);
+    let mut buf = [0u8; 16];
+    let peeked = socket_recv(id, &mut buf, true).unwrap();
+    assert_eq!((peeked.len, peeked.from), (5, Some((PEER, 7011))));
+    // 缓冲区不够时截断，msg_len 仍是完整长度，剩余部分随报文丢弃。
+    let received = socket_recv(id, &mut buf[..2], false).unwrap();
+    assert_eq!((received.len, received.msg_len), (2, 5));
+    assert_eq!(&buf[..2], b"po");
+    assert_eq!(socket_recv(id, &mut buf, false).map(|r| r.len), Err(NetError::WouldBlock));
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn udp_connect_filters_other_senders() {
+    let mut net = Net::new();
+    let chosen = net.peer.udp_socket(7021);
+    let other = net.peer.udp_socket(7022);
+    let id = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    socket_connect(id, PEER, 7021).unwrap();
+    assert_eq!(socket_remote_endpoint(id), Ok(Some((PEER, 7021))));
+    let (_, port) = socket_local_endpoint(id).unwrap();
+    assert_ne!(port, 0);
+
+    assert_eq!(socket_send(id, b"hi", None), Ok(2));
+    net.until("peer datagram", |net| net.peer.udp(chosen).can_recv());
+
+    net.peer.udp(other).send_slice(b"stranger", IpEndpoint::new(LOCAL, port)).unwrap();
+    net.peer.udp(chosen).send_slice(b"friend", IpEndpoint::new(LOCAL, port)).unwrap();
+    net.run(5);
+    let mut buf = [0u8; 16];
+    let received = socket_recv(id, &mut buf, false).unwrap();
+    assert_eq!(&buf[..received.len], b"friend");
+    assert_eq!(socket_recv(id, &mut buf, false).map(|r| r.len), Err(NetError::WouldBlock));
+
+    socket_disconnect(id).unwrap();
+    assert_eq!(socket_remote_endpoint(id), Ok(None));
+    assert_eq!(socket_send(id, b"hi", None), Err(NetError::DestAddrRequired));
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn udp_send_errors() {
+    let _net = Net::new();
+    let id = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    assert_eq!(socket_send(id, b"x", None), Err(NetError::DestAddrRequired));
+    let broadcast = IpAddress::Ipv4(Ipv4Address([10, 0, 2, 255]));
+    assert_eq!(socket_send(id, b"x", Some((broadcast, 7030))), Err(NetError::Access));
+    assert_eq!(socket_send(id, b"x", Some((IpAddress::Ipv4(Ipv4Address::BROADCAST), 7030))), Err(NetError::Access));
+    socket_set_option(id, SockOpt::Broadcast(true)).unwrap();
+    assert_eq!(socket_send(id, b"x", Some((broadcast, 7030))), Ok(1));
+
+    let big = vec![0u8; NET_MTU - 28 + 1];
+    assert_eq!(socket_send(id, &big, Some((PEER, 7030))), Err(NetError::MsgSize));
+    assert_eq!(socket_send(id, &big[1..], Some((PEER, 7030))), Ok(NET_MTU - 28));
+    assert_eq!(socket_listen(id, 1), Err(NetError::Unsupported));
+    assert_eq!(socket_disconnect(id), Ok(()));
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn udp_multicast_membership() {
+    let mut net = Net::new();
+    let group = Ipv4Address([239, 1, 2, 3]);
+    let peer = net.peer.udp_socket(7041);
+    let id = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    socket_bind(id, ANY, 7040).unwrap();
+    assert_eq!(socket_leave_multicast(id, group), Err(NetError::AddrNotAvail));
+    socket_join_multicast(id, group).unwrap();
+    assert_eq!(socket_join_multicast(id, group), Err(NetError::AddrInUse));
+
+    net.peer.udp(peer).send_slice(b"to group", IpEndpoint::new(IpAddress::Ipv4(group), 7040)).unwrap();
+    net.until("group datagram", |_| socket_poll(id, POLLIN).unwrap() & POLLIN != 0);
+    let mut buf = [0u8; 16];
+    let received = socket_recv(id, &mut buf, false).unwrap();
+    assert_eq!(&buf[..received.len], b"to group");
+    assert_eq!(received.from, Some((PEER, 7041)));
+
+    socket_leave_multicast(id, group).unwrap();
+    socket_close(id).unwrap();
+}
+
+#[test]
+fn socket_calls_reject_bad_state() {
+    let _net = Net::new();
+    assert_eq!(socket_create(1, SOCK_STREAM, 0), Err(NetError::Unsupported));
+    assert_eq!(socket_create(AF_INET, 5, 0), Err(NetError::Unsupported));
+    assert_eq!(socket_send(usize::MAX, b"x", None), Err(NetError::Invalid));
+    assert_eq!(socket_close(usize::MAX), Err(NetError::Invalid));
+
+    let id = socket_create(AF_INET, SOCK_STREAM, 0).unwrap();
+    let mut buf = [0u8; 4];
+    assert_eq!(socket_send(id, b"x", None), Err(NetError::Invalid));
+    assert_eq!(socket_recv(id, &mut buf, false).map(|r| r.len), Err(NetError::Invalid));
+    assert_eq!(socket_accept(id).map(|(id, _)| id), Err(NetError::Invalid));
+    assert_eq!(socket_bind(id, ANY, 0), Err(NetError::Invalid));
+    assert_eq!(socket_disconnect(id), Err(NetError::Invalid));
+    assert_eq!(socket_shutdown(id, 3), Err(NetError::Invalid));
+    assert_eq!(socket_recv_window_event(id).map(|ev| ev.is_some()), Ok(true));
+
+    socket_bind(id, ANY, 7050).unwrap();
+    socket_listen(id, 1).unwrap();
+    assert_eq!(socket_connect(id, PEER, 7050), Err(NetError::Invalid));
+    assert_eq!(socket_send(id, b"x", None), Err(NetError::Invalid));
+    socket_close(id).unwrap();
+    assert_eq!(socket_poll(id, POLLIN), Err(NetError::Invalid));
+
+    let udp = socket_create(AF_INET, SOCK_DGRAM, 0).unwrap();
+    assert_eq!(socket_recv_window_event(udp).map(|ev| ev.is_some()), Err(NetError::Invalid));
+    assert_eq!(socket_connect(udp, ANY, 7051), Err(NetError::Invalid));
+    socket_close(udp).unwrap();
+}
@@ -0,0 +1,207 @@
+//! In-memory Ethernet wire for host tests.
+//!
+//! `wire` joins two ends with one frame queue per direction. The stack under
+//! test drives one end as its `NetDevice`; the other end backs a `Peer`, a bare
+//! smoltcp interface owned by the test, so every frame the stack emits is
+//! answered by a real TCP/IP implementation instead of canned bytes.
+
+use core::ptr::NonNull;
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::boxed::Box;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::vec;
+use std::vec::Vec;
+
+use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet, SocketStorage};
+use smoltcp::phy::{Device, DeviceCapabilities, Medium, RxToken, TxToken};
+use smoltcp::socket::{tcp, udp};
+use smoltcp::time::Instant;
+use smoltcp::wire::{EthernetAddress, IpCidr, Ipv4Address, Ipv4Cidr};
+
+use crate::pool::NET_PAGE_SIZE;
+use crate::{NetDevice, NetError, NetMemory, NET_MTU};
+
+type FrameQueue = Mutex<VecDeque<Vec<u8>>>;
+
+/// One side of a wire: sends into the peer's queue, receives from its own.
+#[derive(Clone, Copy)]
+pub struct WireEnd {
+    mac: [u8; 6],
+    rx: &'static FrameQueue,
+    tx: &'static FrameQueue,
+}
+
+/// Create a wire whose ends carry the MAC addresses `a` and `b`.
+pub fn wire(a: [u8; 6], b: [u8; 6]) -> (WireEnd, WireEnd) {
+    let ab: &'static FrameQueue = Box::leak(Box::new(Mutex::new(VecDeque::new())));
+    let ba: &'static FrameQueue = Box::leak(Box::new(Mutex::new(VecDeque::new())));
+    (WireEnd { mac: a, rx: ba, tx: ab }, WireEnd { mac: b, rx: ab, tx: ba })
+}
+
+impl WireEnd {
+    /// Drop frames still in flight in both directions.
+    pub fn clear(&self) {
+        self.rx.lock().unwrap().clear();
+        self.tx.lock().unwrap().clear();
+    }
+
+    fn take(&self) -> Option<Vec<u8>> {
+        self.rx.lock().unwrap().pop_front()
+    }
+
+    fn put(&self, frame: &[u8]) {
+        self.tx.lock().unwrap().push_back(frame.to_vec());
+    }
+}
+
+impl NetDevice for WireEnd {
+    fn mac_address(&self) -> [u8; 6] {
+        self.mac
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> Result<usize, NetError> {
+        let frame = self.take().ok_or(NetError::WouldBlock)?;
+        let dst = buf.get_mut(..frame.len()).ok_or(NetError::BufferTooSmall)?;
+        dst.copy_from_slice(&frame);
+        Ok(frame.len())
+    }
+
+    fn send(&self, buf: &[u8]) -> Result<(), NetError> {
+        self.put(buf);
+        Ok(())
+    }
+
+    fn poll(&self) -> bool {
+        !self.rx.lock().unwrap().is_empty()
+    }
+}
+
+/// `NetMemory` backed by the host allocator.
+pub struct HostMemory;
+
+impl NetMemory for HostMemory {
+    fn alloc_pages(&self, pages: usize) -> Option<NonNull<u8>> {
+        let layout = Layout::from_size_align(pages * NET_PAGE_SIZE, NET_PAGE_SIZE).ok()?;
+        // SAFETY: 页数非零时布局大小非零。
+        NonNull::new(unsafe { alloc_zeroed(layout) })
+    }
+
+    fn free_pages(&self, ptr: NonNull<u8>, pages: usize) {
+        let layout = Layout::from_size_align(pages * NET_PAGE_SIZE, NET_PAGE_SIZE).unwrap();
+        // SAFETY: ptr 来自 alloc_pages，且页数相同。
+        unsafe { dealloc(ptr.as_ptr(), layout) }
+    }
+}
+
+struct PeerDevice(WireEnd);
+
+struct PeerRxToken(Vec<u8>);
+
+struct PeerTxToken(WireEnd);
+
+impl Device for PeerDevice {
+    type RxToken<'a> = PeerRxToken;
+    type TxToken<'a> = PeerTxToken;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.max_transmission_unit = NET_MTU;
+        caps.medium = Medium::Ethernet;
+        caps
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        Some((PeerRxToken(self.0.take()?), PeerTxToken(self.0)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(PeerTxToken(self.0))
+    }
+}
+
+impl RxToken for PeerRxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.0)
+    }
+}
+
+impl TxToken for PeerTxToken {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = vec![0; len];
+        let result = f(&mut frame);
+        self.0.put(&frame);
+        result
+    }
+}
+
+/// Sockets on the far end of the wire; buffers are leaked, a peer lives for one test.
+pub struct Peer {
+    iface: Interface,
+    device: PeerDevice,
+    sockets: SocketSet<'static>,
+}
+
+impl Peer {
+    /// Attach a stack addressed `cidr` to `end`.
+    pub fn new(end: WireEnd, cidr: Ipv4Cidr) -> Self {
+        let mut device = PeerDevice(end);
+        let mut config = Config::new(EthernetAddress(end.mac).into());
+        config.random_seed = 0x8765_4321;
+        let mut iface = Interface::new(config, &mut device, Instant::from_millis(0));
+        iface.update_ip_addrs(|addrs| {
+            let _ = addrs.push(IpCidr::Ipv4(cidr));
+        });
+        let storage = Box::leak((0..16).map(|_| SocketStorage::EMPTY).collect::<Box<[_]>>());
+        Self { iface, device, sockets: SocketSet::new(&mut storage[..]) }
+    }
+
+    /// Run the peer's interface at `now_ms`.
+    pub fn poll(&mut self, now_ms: u64) {
+        let timestamp = Instant::from_millis(now_ms as i64);
+        self.iface.poll(timestamp, &mut self.device, &mut self.sockets);
+    }
+
+    /// Add a TCP socket with `buf_len`-byte buffers each way.
+    pub fn tcp_socket(&mut self, buf_len: usize) -> SocketHandle {
+        let rx = tcp::SocketBuffer::new(leak_bytes(buf_len));
+        let tx = tcp::SocketBuffer::new(leak_bytes(buf_len));
+        self.sockets.add(tcp::Socket::new(rx, tx))
+    }
+
+    /// Add a UDP socket bound to `port`.
+    pub fn udp_socket(&mut self, port: u16) -> SocketHandle {
+        let meta = || Box::leak((0..8).map(|_| udp::PacketMetadata::EMPTY).collect::<Box<[_]>>());
+        let rx = udp::PacketBuffer::new(&mut meta()[..], leak_bytes(4096));
+        let tx = udp::PacketBuffer::new(&mut meta()[..], leak_bytes(4096));
+        let mut socket = udp::Socket::new(rx, tx);
+        socket.bind(port).unwrap();
+        self.sockets.add(socket)
+    }
+
+    /// The TCP socket behind `handle`.
+    pub fn tcp(&mut self, handle: SocketHandle) -> &mut tcp::Socket<'static> {
+        self.sockets.get_mut(handle)
+    }
+
+    /// The UDP socket behind `handle`.
+    pub fn udp(&mut self, handle: SocketHandle) -> &mut udp::Socket<'static> {
+        self.sockets.get_mut(handle)
+    }
+
+    /// Open a connection from the TCP socket `handle`, bound to `local_port`, to `remote`.
+    pub fn connect(&mut self, handle: SocketHandle, remote: (Ipv4Address, u16), local_port: u16) {
+        let socket = self.sockets.get_mut::<tcp::Socket>(handle);
+        socket.connect(self.iface.context(), (remote.0, remote.1), local_port).unwrap();
+    }
+}
+
+fn leak_bytes(len: usize) -> &'static mut [u8] {
+    Box::leak(vec![0u8; len].into_boxed_slice())
+}
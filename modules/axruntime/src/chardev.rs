@@ -0,0 +1,82 @@
+//! Kernel character devices published under `/dev`.
+
+use axfs::devfs::CharDevice;
+use axfs::{VfsError, VfsResult};
+
+use crate::sbi;
+use crate::syscall;
+
+const MEM_MAJOR: u32 = 1;
+const TTY_MAJOR: u32 = 4;
+const TTYAUX_MAJOR: u32 = 5;
+
+/// SBI console shared by `/dev/console`, `/dev/tty` and `/dev/ttyS0`.
+struct ConsoleDevice;
+
+impl CharDevice for ConsoleDevice {
+    fn read(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        let mut read = 0usize;
+        while read < buf.len() {
+            match syscall::console_take() {
+                Some(ch) => {
+                    buf[read] = ch;
+                    read += 1;
+                }
+                None => break,
+            }
+        }
+        if read == 0 && !buf.is_empty() {
+            return Err(VfsError::Busy);
+        }
+        Ok(read)
+    }
+
+    fn write(&self, buf: &[u8]) -> VfsResult<usize> {
+        for &byte in buf {
+            sbi::console_putchar(byte);
+        }
+        Ok(buf.len())
+    }
+
+    fn poll_read(&self) -> bool {
+        syscall::console_peek()
+    }
+}
+
+/// `/dev/random` and `/dev/urandom`, backed by the `getrandom` RNG.
+struct RandomDevice;
+
+impl CharDevice for RandomDevice {
+    fn read(&self, buf: &mut [u8]) -> VfsResult<usize> {
+        for chunk in buf.chunks_mut(8) {
+            let bytes = syscall::rng_next().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
+        }
+        Ok(buf.len())
+    }
+
+    fn write(&self, buf: &[u8]) -> VfsResult<usize> {
+        // 写入熵被接受但不参与混合，与 Linux 非特权写入行为一致。
+        Ok(buf.len())
+    }
+}
+
+static CONSOLE: ConsoleDevice = ConsoleDevice;
+static RANDOM: RandomDevice = RandomDevice;
+
+/// Register the built-in character devices with devfs.
+pub fn init() {
+    let devfs = syscall::devfs();
+    let nodes: [(&str, u32, u32, u16, &'static dyn CharDevice); 5] = [
+        ("console", TTYAUX_MAJOR, 1, 0o600, &CONSOLE),
+        ("tty", TTYAUX_MAJOR, 0, 0o666, &CONSOLE),
+        ("ttyS0", TTY_MAJOR, 64, 0o660, &CONSOLE),
+        ("random", MEM_MAJOR, 8, 0o666, &RANDOM),
+        ("urandom", MEM_MAJOR, 9, 0o666, &RANDOM),
+    ];
+    for (name, major, minor, mode, device) in nodes {
+        if devfs.register_char(name, major, minor, mode, device).is_err() {
+            crate::println!("devfs: failed to register /dev/{}", name);
+        }
+    }
+}
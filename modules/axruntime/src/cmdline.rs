@@ -0,0 +1,44 @@
+//! Kernel command line (`/chosen/bootargs`) storage and lookup.
+
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::dtb::MAX_BOOTARGS_LEN;
+
+struct CmdlineCell {
+    buf: UnsafeCell<[u8; MAX_BOOTARGS_LEN]>,
+}
+
+unsafe impl Sync for CmdlineCell {}
+
+static CMDLINE: CmdlineCell = CmdlineCell {
+    buf: UnsafeCell::new([0; MAX_BOOTARGS_LEN]),
+};
+static CMDLINE_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Record the boot command line; called once during early boot.
+pub fn init(bootargs: &str) {
+    let len = core::cmp::min(bootargs.len(), MAX_BOOTARGS_LEN);
+    // SAFETY: 单核早期阶段写入一次，之后只读。
+    unsafe {
+        (*CMDLINE.buf.get())[..len].copy_from_slice(&bootargs.as_bytes()[..len]);
+    }
+    CMDLINE_LEN.store(len, Ordering::Release);
+}
+
+/// Return the full kernel command line.
+pub fn as_str() -> &'static str {
+    let len = CMDLINE_LEN.load(Ordering::Acquire);
+    // SAFETY: buffer is written once before CMDLINE_LEN is published.
+    let bytes = unsafe { &(*CMDLINE.buf.get())[..len] };
+    core::str::from_utf8(bytes).unwrap_or("")
+}
+
+/// Return the value of a `key=value` parameter, if present.
+pub fn param(key: &str) -> Option<&'static str> {
+    as_str().split_ascii_whitespace().find_map(|arg| {
+        let (name, value) = arg.split_once('=')?;
+        (name == key).then_some(value)
+    })
+}
+
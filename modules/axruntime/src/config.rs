@@ -17,5 +17,7 @@ pub const ENABLE_USER_SHELL: bool = cfg!(feature = "user-shell");
 pub const ENABLE_SCHED_DEMO: bool = cfg!(feature = "sched-demo");
 /// 通过 `--features ext4-write-test` 启用 ext4 写路径冒烟自测。
 pub const ENABLE_EXT4_WRITE_TEST: bool = cfg!(feature = "ext4-write-test");
+/// Bytes of physical memory /dev/shm may hold, index pages included.
+pub const SHM_SIZE_LIMIT: usize = 64 * 1024 * 1024;
 /// Base virtual address for the built-in user test image.
 pub const USER_TEST_BASE: usize = 0x4000_0000;
@@ -0,0 +1,310 @@
+//! Per-process credentials: real, effective, saved and filesystem ids plus
+//! supplementary groups.
+//!
+//! There are no capabilities yet, so effective uid 0 stands in for
+//! CAP_SETUID/CAP_SETGID when changing ids. Every change of the effective id
+//! also resets the filesystem id, as on Linux; only setfsuid/setfsgid make
+//! them diverge.
+
+use axfs::Credentials;
+
+const MAX_PROCS: usize = crate::config::MAX_TASKS;
+
+/// Maximum number of supplementary groups per process.
+pub const NGROUPS_MAX: usize = 32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Errors reported by credential changes.
+pub enum CredError {
+    /// The caller may not switch to the requested id.
+    Perm,
+    /// The request is malformed (e.g. too many groups).
+    Invalid,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// Credential set of one process.
+pub struct Cred {
+    pub ruid: u32,
+    pub euid: u32,
+    pub suid: u32,
+    pub fsuid: u32,
+    pub rgid: u32,
+    pub egid: u32,
+    pub sgid: u32,
+    pub fsgid: u32,
+    groups: [u32; NGROUPS_MAX],
+    ngroups: usize,
+}
+
+impl Cred {
+    const ROOT: Self = Self {
+        ruid: 0,
+        euid: 0,
+        suid: 0,
+        fsuid: 0,
+        rgid: 0,
+        egid: 0,
+        sgid: 0,
+        fsgid: 0,
+        groups: [0; NGROUPS_MAX],
+        ngroups: 0,
+    };
+
+    /// Supplementary group list.
+    pub fn groups(&self) -> &[u32] {
+        &self.groups[..self.ngroups]
+    }
+
+    /// Identity used for filesystem permission checks.
+    pub fn fs_credentials(&self) -> Credentials<'_> {
+        Credentials::new(self.fsuid, self.fsgid, self.groups())
+    }
+
+    /// Identity used by access(2)/faccessat(2), which check the real ids.
+    pub fn real_credentials(&self) -> Credentials<'_> {
+        Credentials::new(self.ruid, self.rgid, self.groups())
+    }
+
+    fn privileged(&self) -> bool {
+        self.euid == 0
+    }
+
+    fn is_uid(&self, uid: u32) -> bool {
+        uid == self.ruid || uid == self.euid || uid == self.suid
+    }
+
+    fn is_gid(&self, gid: u32) -> bool {
+        gid == self.rgid || gid == self.egid || gid == self.sgid
+    }
+}
+
+// SAFETY: 单核阶段按进程顺序访问凭据表。
+static mut CREDS: [Cred; MAX_PROCS] = [Cred::ROOT; MAX_PROCS];
+
+/// Reset a process slot to root credentials.
+pub fn init(idx: usize) {
+    if idx < MAX_PROCS {
+        // SAFETY: 单核阶段顺序初始化凭据。
+        unsafe {
+            CREDS[idx] = Cred::ROOT;
+        }
+    }
+}
+
+/// Copy credentials from a parent into a forked child.
+pub fn clone(parent: usize, child: usize) {
+    if parent < MAX_PROCS && child < MAX_PROCS {
+        // SAFETY: 单核阶段顺序复制凭据。
+        unsafe {
+            CREDS[child] = CREDS[parent];
+        }
+    }
+}
+
+/// Return a copy of the credentials for a process slot.
+pub fn get(idx: usize) -> Cred {
+    // SAFETY: 单核阶段顺序读取凭据。
+    unsafe { CREDS.get(idx).copied().unwrap_or(Cred::ROOT) }
+}
+
+/// Credentials of the running process; kernel context runs as root.
+pub fn current() -> Cred {
+    crate::runtime::current_task_id().map_or(Cred::ROOT, get)
+}
+
+fn update<R>(idx: usize, f: impl FnOnce(&mut Cred) -> Result<R, CredError>) -> Result<R, CredError> {
+    if idx >= MAX_PROCS {
+        return Err(CredError::Invalid);
+    }
+    // SAFETY: 单核阶段顺序更新凭据；失败时不写回。
+    let mut cred = unsafe { CREDS[idx] };
+    let result = f(&mut cred)?;
+    // SAFETY: 同上。
+    unsafe {
+        CREDS[idx] = cred;
+    }
+    Ok(result)
+}
+
+/// setuid(2): root sets all uids, others may only switch the effective uid to the real or saved one.
+pub fn setuid(idx: usize, uid: u32) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if cred.privileged() {
+            cred.ruid = uid;
+            cred.suid = uid;
+        } else if uid != cred.ruid && uid != cred.suid {
+            return Err(CredError::Perm);
+        }
+        cred.euid = uid;
+        cred.fsuid = uid;
+        Ok(())
+    })
+}
+
+/// setgid(2), with the same rules as `setuid` applied to group ids.
+pub fn setgid(idx: usize, gid: u32) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if cred.privileged() {
+            cred.rgid = gid;
+            cred.sgid = gid;
+        } else if gid != cred.rgid && gid != cred.sgid {
+            return Err(CredError::Perm);
+        }
+        cred.egid = gid;
+        cred.fsgid = gid;
+        Ok(())
+    })
+}
+
+/// setreuid(2); `None` leaves an id unchanged.
+///
+/// The saved uid follows the new effective uid whenever the real uid is set or the
+/// effective uid moves away from the old real uid.
+pub fn setreuid(idx: usize, ruid: Option<u32>, euid: Option<u32>) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if !cred.privileged() {
+            if ruid.is_some_and(|uid| uid != cred.ruid && uid != cred.euid) {
+                return Err(CredError::Perm);
+            }
+            if euid.is_some_and(|uid| !cred.is_uid(uid)) {
+                return Err(CredError::Perm);
+            }
+        }
+        let old_ruid = cred.ruid;
+        if let Some(uid) = ruid {
+            cred.ruid = uid;
+        }
+        if let Some(uid) = euid {
+            cred.euid = uid;
+        }
+        if ruid.is_some() || euid.is_some_and(|uid| uid != old_ruid) {
+            cred.suid = cred.euid;
+        }
+        cred.fsuid = cred.euid;
+        Ok(())
+    })
+}
+
+/// setregid(2), with the same rules as `setreuid` applied to group ids.
+pub fn setregid(idx: usize, rgid: Option<u32>, egid: Option<u32>) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if !cred.privileged() {
+            if rgid.is_some_and(|gid| gid != cred.rgid && gid != cred.egid) {
+                return Err(CredError::Perm);
+            }
+            if egid.is_some_and(|gid| !cred.is_gid(gid)) {
+                return Err(CredError::Perm);
+            }
+        }
+        let old_rgid = cred.rgid;
+        if let Some(gid) = rgid {
+            cred.rgid = gid;
+        }
+        if let Some(gid) = egid {
+            cred.egid = gid;
+        }
+        if rgid.is_some() || egid.is_some_and(|gid| gid != old_rgid) {
+            cred.sgid = cred.egid;
+        }
+        cred.fsgid = cred.egid;
+        Ok(())
+    })
+}
+
+/// setresuid(2); unprivileged callers may only pick among their current real, effective and saved uids.
+pub fn setresuid(idx: usize, ruid: Option<u32>, euid: Option<u32>, suid: Option<u32>) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if !cred.privileged() && [ruid, euid, suid].iter().flatten().any(|&uid| !cred.is_uid(uid)) {
+            return Err(CredError::Perm);
+        }
+        if let Some(uid) = ruid {
+            cred.ruid = uid;
+        }
+        if let Some(uid) = euid {
+            cred.euid = uid;
+        }
+        if let Some(uid) = suid {
+            cred.suid = uid;
+        }
+        cred.fsuid = cred.euid;
+        Ok(())
+    })
+}
+
+/// setresgid(2), with the same rules as `setresuid` applied to group ids.
+pub fn setresgid(idx: usize, rgid: Option<u32>, egid: Option<u32>, sgid: Option<u32>) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if !cred.privileged() && [rgid, egid, sgid].iter().flatten().any(|&gid| !cred.is_gid(gid)) {
+            return Err(CredError::Perm);
+        }
+        if let Some(gid) = rgid {
+            cred.rgid = gid;
+        }
+        if let Some(gid) = egid {
+            cred.egid = gid;
+        }
+        if let Some(gid) = sgid {
+            cred.sgid = gid;
+        }
+        cred.fsgid = cred.egid;
+        Ok(())
+    })
+}
+
+/// setfsuid(2): returns the previous fsuid and silently ignores disallowed ids.
+pub fn setfsuid(idx: usize, uid: u32) -> u32 {
+    update(idx, |cred| {
+        let old = cred.fsuid;
+        if cred.privileged() || cred.is_uid(uid) || uid == cred.fsuid {
+            cred.fsuid = uid;
+        }
+        Ok(old)
+    })
+    .unwrap_or(0)
+}
+
+/// setfsgid(2): returns the previous fsgid and silently ignores disallowed ids.
+pub fn setfsgid(idx: usize, gid: u32) -> u32 {
+    update(idx, |cred| {
+        let old = cred.fsgid;
+        if cred.privileged() || cred.is_gid(gid) || gid == cred.fsgid {
+            cred.fsgid = gid;
+        }
+        Ok(old)
+    })
+    .unwrap_or(0)
+}
+
+/// setgroups(2): replace the supplementary group list (root only).
+pub fn setgroups(idx: usize, groups: &[u32]) -> Result<(), CredError> {
+    update(idx, |cred| {
+        if groups.len() > NGROUPS_MAX {
+            return Err(CredError::Invalid);
+        }
+        if !cred.privileged() {
+            return Err(CredError::Perm);
+        }
+        cred.groups[..groups.len()].copy_from_slice(groups);
+        cred.ngroups = groups.len();
+        Ok(())
+    })
+}
+
+/// Apply execve credential rules: set-user-ID/set-group-ID images switch the effective
+/// ids, and the saved ids always follow the effective ones.
+pub fn apply_exec(idx: usize, uid: Option<u32>, gid: Option<u32>) {
+    let _ = update(idx, |cred| {
+        if let Some(uid) = uid {
+            cred.euid = uid;
+        }
+        if let Some(gid) = gid {
+            cred.egid = gid;
+        }
+        cred.suid = cred.euid;
+        cred.sgid = cred.egid;
+        cred.fsuid = cred.euid;
+        cred.fsgid = cred.egid;
+        Ok(())
+    });
+}
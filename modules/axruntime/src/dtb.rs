@@ -15,9 +15,16 @@ const FDT_END: u32 = 9;
 
 const MAX_DEPTH: usize = 16;
 
-const MAX_VIRTIO_MMIO: usize = 4;
+/// Maximum number of virtio-mmio transports recorded (QEMU virt exposes 8).
+pub const MAX_VIRTIO_MMIO: usize = 8;
 /// Maximum number of memory-mapped device regions returned to the MMU.
 pub const MAX_DEVICE_REGIONS: usize = MAX_VIRTIO_MMIO + 1;
+/// Maximum length of `/chosen/bootargs` kept for the kernel command line.
+pub const MAX_BOOTARGS_LEN: usize = 256;
+/// Maximum length of the boot hart `riscv,isa` string.
+pub const MAX_ISA_LEN: usize = 128;
+/// Maximum length of the boot hart `mmu-type` string.
+pub const MAX_MMU_TYPE_LEN: usize = 16;
 
 #[derive(Copy, Clone, Debug, Default)]
 /// Virtio-mmio device description extracted from the DTB.
@@ -43,6 +50,20 @@ pub struct DtbInfo {
     pub virtio_mmio_len: usize,
     /// PLIC MMIO region.
     pub plic: Option<MemoryRegion>,
+    /// Raw `/chosen/bootargs` bytes.
+    pub bootargs: [u8; MAX_BOOTARGS_LEN],
+    /// Number of valid bootargs bytes.
+    pub bootargs_len: usize,
+    /// Number of `cpu@` nodes under `/cpus`.
+    pub cpu_count: usize,
+    /// Raw `riscv,isa` bytes of the first CPU node.
+    pub isa: [u8; MAX_ISA_LEN],
+    /// Number of valid isa bytes.
+    pub isa_len: usize,
+    /// Raw `mmu-type` bytes of the first CPU node.
+    pub mmu_type: [u8; MAX_MMU_TYPE_LEN],
+    /// Number of valid mmu-type bytes.
+    pub mmu_type_len: usize,
 }
 
 impl Default for DtbInfo {
@@ -54,6 +75,13 @@ impl Default for DtbInfo {
             virtio_mmio: [VirtioMmioDevice::default(); MAX_VIRTIO_MMIO],
             virtio_mmio_len: 0,
             plic: None,
+            bootargs: [0; MAX_BOOTARGS_LEN],
+            bootargs_len: 0,
+            cpu_count: 0,
+            isa: [0; MAX_ISA_LEN],
+            isa_len: 0,
+            mmu_type: [0; MAX_MMU_TYPE_LEN],
+            mmu_type_len: 0,
         }
     }
 }
@@ -64,6 +92,21 @@ impl DtbInfo {
         &self.virtio_mmio[..self.virtio_mmio_len]
     }
 
+    /// Return the kernel command line from `/chosen/bootargs`.
+    pub fn bootargs(&self) -> &str {
+        core::str::from_utf8(&self.bootargs[..self.bootargs_len]).unwrap_or("")
+    }
+
+    /// Return the `riscv,isa` string of the first CPU.
+    pub fn isa(&self) -> &str {
+        core::str::from_utf8(&self.isa[..self.isa_len]).unwrap_or("")
+    }
+
+    /// Return the `mmu-type` string of the first CPU.
+    pub fn mmu_type(&self) -> &str {
+        core::str::from_utf8(&self.mmu_type[..self.mmu_type_len]).unwrap_or("")
+    }
+
     /// Collect device regions for identity mapping.
     pub fn collect_device_regions(&self, out: &mut [MemoryRegion]) -> usize {
         let mut count = 0usize;
@@ -99,6 +142,8 @@ struct NodeState {
     is_uart: bool,
     is_virtio_mmio: bool,
     is_plic: bool,
+    is_chosen: bool,
+    is_cpu: bool,
     virtio_irq: Option<u32>,
 }
 
@@ -204,6 +249,13 @@ pub fn parse(dtb_addr: usize) -> Result<DtbInfo, DtbError> {
                     if node_name.starts_with("plic@") {
                         state.is_plic = true;
                     }
+                    if depth == 1 && node_name == "chosen" {
+                        state.is_chosen = true;
+                    }
+                    if depth == 2 && node_name.starts_with("cpu@") {
+                        state.is_cpu = true;
+                        info.cpu_count += 1;
+                    }
                 }
                 stack[depth] = state;
                 depth += 1;
@@ -282,6 +334,15 @@ pub fn parse(dtb_addr: usize) -> Result<DtbInfo, DtbError> {
                             state.is_plic = true;
                         }
                     }
+                    Some("bootargs") if state.is_chosen => {
+                        info.bootargs_len = copy_prop_str(value, len, &mut info.bootargs);
+                    }
+                    Some("riscv,isa") if state.is_cpu && info.isa_len == 0 => {
+                        info.isa_len = copy_prop_str(value, len, &mut info.isa);
+                    }
+                    Some("mmu-type") if state.is_cpu && info.mmu_type_len == 0 => {
+                        info.mmu_type_len = copy_prop_str(value, len, &mut info.mmu_type);
+                    }
                     Some("interrupts") => {
                         if state.is_virtio_mmio && len >= 4 {
 // SAFETY: FDT bounds are validated before pointer access.
@@ -354,6 +415,17 @@ fn read_cells(value: *const u8, cells: usize) -> Option<u64> {
     Some(out)
 }
 
+/// Copy a NUL-terminated string property into `out`, truncating if needed.
+fn copy_prop_str(value: *const u8, len: usize, out: &mut [u8]) -> usize {
+    // SAFETY: FDT bounds are validated before pointer access.
+    let str_len = unsafe { cstr_len(value, value.add(len)) };
+    let copy_len = core::cmp::min(str_len, out.len());
+    // SAFETY: FDT bounds are validated before pointer access.
+    let bytes = unsafe { core::slice::from_raw_parts(value, copy_len) };
+    out[..copy_len].copy_from_slice(bytes);
+    copy_len
+}
+
 fn has_uart_compat(value: *const u8, len: usize) -> bool {
     let mut offset = 0usize;
     while offset < len {
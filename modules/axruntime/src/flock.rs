@@ -0,0 +1,263 @@
+//! Advisory file locks: POSIX record locks, OFD locks and BSD flock locks.
+//!
+//! Locks live in a fixed table keyed by (mount, inode). Record locks (POSIX
+//! and OFD) conflict with each other; flock locks form a separate family, as
+//! on Linux. Blocked lockers sleep on a single wait queue and retry after any
+//! unlock.
+
+use axfs::mount::MountId;
+use axfs::InodeId;
+
+use crate::runtime;
+use crate::task_wait_queue::TaskWaitQueue;
+
+const MAX_FILE_LOCKS: usize = 64;
+const MAX_OWNERS: usize = crate::config::MAX_TASKS;
+
+/// Lock range end meaning "through end of file".
+pub const LOCK_TO_EOF: u64 = u64::MAX;
+
+/// File identity used as lock key.
+pub type LockKey = (MountId, InodeId);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Holder of a lock.
+pub enum LockOwner {
+    /// fcntl F_SETLK record lock, owned by a process slot.
+    Process(usize),
+    /// F_OFD_SETLK record lock, owned by an open file description.
+    OpenFile(u32),
+    /// flock(2) whole-file lock, owned by an open file description.
+    Flock(u32),
+}
+
+impl LockOwner {
+    fn is_flock(self) -> bool {
+        matches!(self, LockOwner::Flock(_))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Requested lock operation.
+pub enum LockType {
+    Read,
+    Write,
+    Unlock,
+}
+
+#[derive(Clone, Copy, Debug)]
+/// A held lock over the byte range `[start, end)`.
+pub struct FileLock {
+    pub key: LockKey,
+    pub owner: LockOwner,
+    pub kind: LockType,
+    pub start: u64,
+    pub end: u64,
+    /// PID reported by F_GETLK.
+    pub pid: usize,
+}
+
+impl FileLock {
+    fn overlaps(&self, start: u64, end: u64) -> bool {
+        self.start < end && start < self.end
+    }
+
+    fn conflicts(&self, key: LockKey, owner: LockOwner, kind: LockType, start: u64, end: u64) -> bool {
+        self.key == key
+            && self.owner != owner
+            && self.owner.is_flock() == owner.is_flock()
+            && (self.kind == LockType::Write || kind == LockType::Write)
+            && self.overlaps(start, end)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Lock manager errors.
+pub enum LockError {
+    /// A conflicting lock is held and the caller asked not to wait.
+    WouldBlock,
+    /// Waiting would close a cycle of blocked POSIX lock owners.
+    Deadlock,
+    /// The lock table is full.
+    NoLocks,
+}
+
+// 单核阶段所有锁表访问都在系统调用上下文中串行进行。
+static mut FILE_LOCKS: [Option<FileLock>; MAX_FILE_LOCKS] = [None; MAX_FILE_LOCKS];
+// 记录每个进程槽位正在等待的 POSIX 锁持有者，用于 EDEADLK 检测。
+static mut WAITING_FOR: [Option<usize>; MAX_OWNERS] = [None; MAX_OWNERS];
+static LOCK_WAITERS: TaskWaitQueue = TaskWaitQueue::new();
+
+/// Return the first lock that would block `kind` over `[start, end)`.
+pub fn test(key: LockKey, owner: LockOwner, kind: LockType, start: u64, end: u64) -> Option<FileLock> {
+    // SAFETY: 单核阶段串行访问锁表。
+    unsafe {
+        FILE_LOCKS
+            .iter()
+            .flatten()
+            .find(|lock| lock.conflicts(key, owner, kind, start, end))
+            .copied()
+    }
+}
+
+/// Acquire, convert or release a lock, optionally waiting for conflicting holders.
+pub fn set(
+    key: LockKey,
+    owner: LockOwner,
+    pid: usize,
+    kind: LockType,
+    start: u64,
+    end: u64,
+    wait: bool,
+) -> Result<(), LockError> {
+    loop {
+        let Some(blocker) = try_set(key, owner, pid, kind, start, end)? else {
+            return Ok(());
+        };
+        if !wait {
+            return Err(LockError::WouldBlock);
+        }
+        let waiter = match owner {
+            LockOwner::Process(slot) => Some(slot),
+            _ => None,
+        };
+        if let (Some(waiter), LockOwner::Process(holder)) = (waiter, blocker.owner) {
+            if would_deadlock(waiter, holder) {
+                return Err(LockError::Deadlock);
+            }
+            set_waiting(waiter, Some(holder));
+        }
+        runtime::block_current(&LOCK_WAITERS);
+        if let Some(waiter) = waiter {
+            set_waiting(waiter, None);
+        }
+    }
+}
+
+/// Drop every record lock a process holds on `key` (any close of the file does this).
+pub fn release_process(slot: usize, key: LockKey) {
+    release_where(|lock| lock.owner == LockOwner::Process(slot) && lock.key == key);
+}
+
+/// Drop OFD and flock locks of an open file description that is no longer referenced.
+pub fn release_open_file(open_id: u32) {
+    release_where(|lock| matches!(lock.owner, LockOwner::OpenFile(id) | LockOwner::Flock(id) if id == open_id));
+}
+
+fn release_where(pred: impl Fn(&FileLock) -> bool) {
+    let mut released = false;
+    // SAFETY: 单核阶段串行访问锁表。
+    unsafe {
+        for slot in FILE_LOCKS.iter_mut() {
+            if slot.as_ref().is_some_and(&pred) {
+                *slot = None;
+                released = true;
+            }
+        }
+    }
+    if released {
+        runtime::wake_all(&LOCK_WAITERS);
+    }
+}
+
+/// Apply the request if nothing conflicts; otherwise return the blocking lock.
+fn try_set(
+    key: LockKey,
+    owner: LockOwner,
+    pid: usize,
+    kind: LockType,
+    start: u64,
+    end: u64,
+) -> Result<Option<FileLock>, LockError> {
+    if kind != LockType::Unlock {
+        if let Some(blocker) = test(key, owner, kind, start, end) {
+            return Ok(Some(blocker));
+        }
+    }
+    // SAFETY: 单核阶段串行访问锁表。
+    let locks = unsafe { &mut FILE_LOCKS };
+    let mine = |lock: &FileLock| lock.key == key && lock.owner == owner;
+    // 同一持有者的锁互不重叠，至多一把锁会被从中间拆开。
+    let splits = locks
+        .iter()
+        .flatten()
+        .filter(|lock| mine(lock) && lock.start < start && lock.end > end)
+        .count();
+    let needed = splits + usize::from(kind != LockType::Unlock);
+    if locks.iter().filter(|slot| slot.is_none()).count() < needed {
+        return Err(LockError::NoLocks);
+    }
+    let (mut new_start, mut new_end) = (start, end);
+    for idx in 0..MAX_FILE_LOCKS {
+        let Some(lock) = locks[idx] else {
+            continue;
+        };
+        if !mine(&lock) {
+            continue;
+        }
+        let touches = lock.overlaps(start, end) || lock.end == start || lock.start == end;
+        if !touches {
+            continue;
+        }
+        if lock.kind == kind {
+            // 同类型相邻或重叠的锁合并进新锁。
+            new_start = new_start.min(lock.start);
+            new_end = new_end.max(lock.end);
+            locks[idx] = None;
+        } else if lock.overlaps(start, end) {
+            locks[idx] = None;
+            if lock.start < start {
+                insert(locks, FileLock { end: start, ..lock });
+            }
+            if lock.end > end {
+                insert(locks, FileLock { start: end, ..lock });
+            }
+        }
+    }
+    if kind != LockType::Unlock {
+        insert(
+            locks,
+            FileLock {
+                key,
+                owner,
+                kind,
+                start: new_start,
+                end: new_end,
+                pid,
+            },
+        );
+    }
+    runtime::wake_all(&LOCK_WAITERS);
+    Ok(None)
+}
+
+fn insert(locks: &mut [Option<FileLock>; MAX_FILE_LOCKS], lock: FileLock) {
+    if let Some(slot) = locks.iter_mut().find(|slot| slot.is_none()) {
+        *slot = Some(lock);
+    }
+}
+
+/// Follow the waits-for chain from `holder`; reaching `waiter` means a cycle.
+fn would_deadlock(waiter: usize, holder: usize) -> bool {
+    let mut current = holder;
+    for _ in 0..MAX_OWNERS {
+        if current == waiter {
+            return true;
+        }
+        // SAFETY: 单核阶段串行访问等待关系表。
+        match unsafe { WAITING_FOR.get(current).copied().flatten() } {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+    false
+}
+
+fn set_waiting(slot: usize, holder: Option<usize>) {
+    // SAFETY: 单核阶段串行访问等待关系表。
+    unsafe {
+        if let Some(entry) = WAITING_FOR.get_mut(slot) {
+            *entry = holder;
+        }
+    }
+}
@@ -1,16 +1,21 @@
-//! Root filesystem device selection and ramdisk helper.
+//! Block device registry, root filesystem device selection and ramdisk helper.
 
 use core::mem::MaybeUninit;
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use axfs::block::{BlockDevice, BlockId};
+use axfs::partition::{self, PartitionDevice, MAX_PARTITIONS};
 use axfs::{fat32, VfsError, VfsResult};
 
 use crate::dtb::VirtioMmioDevice;
-use crate::virtio_blk;
+use crate::virtio_blk::{self, MAX_VIRTIO_BLK};
 
 const ROOTFS_BLOCK_SIZE: usize = 512;
 const ROOTFS_IMAGE_MAX: usize = 16 * 1024;
+/// Linux uses major 254 for virtio-blk; each disk owns 16 minors.
+const VIRTIO_BLK_MAJOR: u32 = 254;
+const MINORS_PER_DISK: u32 = 16;
+const MAX_BLOCK_NAME_LEN: usize = 8;
 
 static ROOTFS_READY: AtomicBool = AtomicBool::new(false);
 static ROOTFS_SIZE: AtomicUsize = AtomicUsize::new(0);
@@ -19,6 +24,18 @@ static mut ROOTFS_IMAGE: [u8; ROOTFS_IMAGE_MAX] = [0; ROOTFS_IMAGE_MAX];
 static ROOT_DEVICE_READY: AtomicBool = AtomicBool::new(false);
 // SAFETY: 单核早期阶段初始化一次，后续只读。
 static mut ROOT_DEVICE: MaybeUninit<RootBlockDevice> = MaybeUninit::uninit();
+// 根设备名（如 /dev/vda1），供 /proc/mounts 使用；ramdisk 为空。
+static mut ROOT_DEVICE_NAME: [u8; MAX_BLOCK_NAME_LEN] = [0; MAX_BLOCK_NAME_LEN];
+static ROOT_DEVICE_NAME_LEN: AtomicUsize = AtomicUsize::new(0);
+const NO_PARTITION: Option<PartitionDevice<'static>> = None;
+const EMPTY_DISK_PARTITIONS: [Option<PartitionDevice<'static>>; MAX_PARTITIONS] =
+    [NO_PARTITION; MAX_PARTITIONS];
+// SAFETY: 单核早期阶段扫描分区表时写入，之后只读。
+static mut PARTITIONS: [[Option<PartitionDevice<'static>>; MAX_PARTITIONS]; MAX_VIRTIO_BLK] =
+    [EMPTY_DISK_PARTITIONS; MAX_VIRTIO_BLK];
+// 各磁盘上的分区号，与 PARTITIONS 同下标。
+static mut PARTITION_NUMBERS: [[u32; MAX_PARTITIONS]; MAX_VIRTIO_BLK] =
+    [[0; MAX_PARTITIONS]; MAX_VIRTIO_BLK];
 
 #[derive(Clone, Copy)]
 /// In-memory ramdisk block device for the initial rootfs.
@@ -73,6 +90,7 @@ impl BlockDevice for RootFsDevice {
 /// Selected root block device backend.
 pub enum RootBlockDevice {
     Virtio(&'static virtio_blk::VirtioBlkDevice),
+    Partition(&'static PartitionDevice<'static>),
     Ramdisk(RootFsDevice),
 }
 
@@ -81,24 +99,191 @@ impl RootBlockDevice {
     pub fn as_block_device(&self) -> &dyn BlockDevice {
         match self {
             Self::Virtio(dev) => *dev,
+            Self::Partition(dev) => *dev,
             Self::Ramdisk(dev) => dev,
         }
     }
 }
 
-/// Initialize block device backends for rootfs.
+/// Back /dev/shm with frames, initialize block device backends, scan partitions and publish `/dev/vd*`.
 pub fn init(virtio_mmio: &[VirtioMmioDevice]) {
+    crate::syscall::devfs().set_shm_memory(&crate::mm::ShmPages, crate::config::SHM_SIZE_LIMIT);
     virtio_blk::init(virtio_mmio);
+    for disk in 0..virtio_blk::device_count() {
+        let Some(dev) = virtio_blk::device(disk) else {
+            continue;
+        };
+        register_disk(disk, dev);
+    }
+}
+
+fn register_disk(disk: usize, dev: &'static virtio_blk::VirtioBlkDevice) {
+    let letter = b'a' + disk as u8;
+    let mut name = [0u8; MAX_BLOCK_NAME_LEN];
+    let len = format_block_name(&mut name, letter, 0);
+    let devfs = crate::syscall::devfs();
+    let minor_base = disk as u32 * MINORS_PER_DISK;
+    let name_str = core::str::from_utf8(&name[..len]).unwrap_or("vd?");
+    if devfs
+        .register_block(name_str, VIRTIO_BLK_MAJOR, minor_base, dev, dev.capacity())
+        .is_err()
+    {
+        crate::println!("blk: failed to register /dev/{}", name_str);
+    }
+
+    let table = match partition::scan(dev, dev.capacity()) {
+        Ok(Some(table)) => table,
+        Ok(None) => {
+            crate::println!("blk: {} sectors={} (no partition table)", name_str, dev.capacity());
+            return;
+        }
+        Err(_) => {
+            crate::println!("blk: {} partition table unreadable", name_str);
+            return;
+        }
+    };
+    crate::println!(
+        "blk: {} sectors={} {:?} partitions={}",
+        name_str,
+        dev.capacity(),
+        table.kind(),
+        table.partitions().len()
+    );
+    for (slot, part) in table.partitions().iter().enumerate() {
+        if part.number >= MINORS_PER_DISK {
+            continue;
+        }
+        // SAFETY: 单核早期阶段写入分区句柄，注册后只读。
+        let part_dev: &'static PartitionDevice<'static> = unsafe {
+            PARTITIONS[disk][slot] = Some(PartitionDevice::new(dev, *part));
+            PARTITION_NUMBERS[disk][slot] = part.number;
+            match &PARTITIONS[disk][slot] {
+                Some(part_dev) => part_dev,
+                None => continue,
+            }
+        };
+        let mut part_name = [0u8; MAX_BLOCK_NAME_LEN];
+        let part_len = format_block_name(&mut part_name, letter, part.number);
+        let part_str = core::str::from_utf8(&part_name[..part_len]).unwrap_or("vd?");
+        if devfs
+            .register_block(
+                part_str,
+                VIRTIO_BLK_MAJOR,
+                minor_base + part.number,
+                part_dev,
+                part_dev.blocks(),
+            )
+            .is_err()
+        {
+            crate::println!("blk: failed to register /dev/{}", part_str);
+        }
+    }
+}
+
+/// Format `vd<letter>[<number>]` into `buf`, returning its length.
+fn format_block_name(buf: &mut [u8; MAX_BLOCK_NAME_LEN], letter: u8, number: u32) -> usize {
+    buf[..2].copy_from_slice(b"vd");
+    buf[2] = letter;
+    let mut len = 3;
+    if number >= 10 {
+        buf[len] = b'0' + (number / 10) as u8;
+        len += 1;
+    }
+    if number > 0 {
+        buf[len] = b'0' + (number % 10) as u8;
+        len += 1;
+    }
+    len
+}
+
+/// Parse `root=` as `/dev/vdXN` or `vdXN` into (disk index, partition number).
+fn parse_root_param(value: &str) -> Option<(usize, u32)> {
+    let name = value.strip_prefix("/dev/").unwrap_or(value);
+    let rest = name.strip_prefix("vd")?;
+    let letter = *rest.as_bytes().first()?;
+    if !letter.is_ascii_lowercase() {
+        return None;
+    }
+    let disk = (letter - b'a') as usize;
+    let digits = &rest[1..];
+    if digits.is_empty() {
+        return Some((disk, 0));
+    }
+    let number = digits.parse::<u32>().ok()?;
+    if number == 0 {
+        return None;
+    }
+    Some((disk, number))
+}
+
+/// Look up a registered partition device by disk index and partition number.
+fn partition_device(disk: usize, number: u32) -> Option<&'static PartitionDevice<'static>> {
+    if disk >= MAX_VIRTIO_BLK {
+        return None;
+    }
+    // SAFETY: PARTITIONS 在 init 之后只读。
+    unsafe {
+        let slot = PARTITION_NUMBERS[disk].iter().position(|&n| n == number)?;
+        PARTITIONS[disk][slot].as_ref()
+    }
+}
+
+fn select_root_device() -> RootBlockDevice {
+    if let Some(value) = crate::cmdline::param("root") {
+        let selected = parse_root_param(value).and_then(|(disk, number)| {
+            let dev = if number == 0 {
+                virtio_blk::device(disk).map(RootBlockDevice::Virtio)
+            } else {
+                partition_device(disk, number).map(RootBlockDevice::Partition)
+            };
+            dev.map(|dev| (dev, disk, number))
+        });
+        match selected {
+            Some((dev, disk, number)) => {
+                crate::println!("rootfs: root={}", value);
+                record_root_name(disk, number);
+                return dev;
+            }
+            None => crate::println!("rootfs: root={} not found, using default", value),
+        }
+    }
+    // 默认：vda 有分区表时取第一个分区，否则整盘；无磁盘时回退到 ramdisk。
+    if let Some(dev) = virtio_blk::device(0) {
+        // SAFETY: PARTITIONS 在 init 之后只读。
+        let (first, number) = unsafe { (PARTITIONS[0][0].as_ref(), PARTITION_NUMBERS[0][0]) };
+        return match first {
+            Some(part) => {
+                record_root_name(0, number);
+                RootBlockDevice::Partition(part)
+            }
+            None => {
+                record_root_name(0, 0);
+                RootBlockDevice::Virtio(dev)
+            }
+        };
+    }
+    RootBlockDevice::Ramdisk(RootFsDevice::new())
+}
+
+fn record_root_name(disk: usize, number: u32) {
+    // SAFETY: 单核初始化时写入一次，之后只读。
+    let len = unsafe { format_block_name(&mut ROOT_DEVICE_NAME, b'a' + disk as u8, number) };
+    ROOT_DEVICE_NAME_LEN.store(len, Ordering::Release);
+}
+
+/// Return the root device name without `/dev/` (empty for the ramdisk).
+pub fn root_device_name() -> &'static str {
+    let _ = root_device();
+    let len = ROOT_DEVICE_NAME_LEN.load(Ordering::Acquire);
+    // SAFETY: the name is published before its length and never changes afterwards.
+    let bytes = unsafe { &ROOT_DEVICE_NAME[..len] };
+    core::str::from_utf8(bytes).unwrap_or("")
 }
 
 /// Return the selected root block device.
 pub fn root_device() -> &'static RootBlockDevice {
     if !ROOT_DEVICE_READY.load(Ordering::Acquire) {
-        let dev = if let Some(dev) = virtio_blk::device() {
-            RootBlockDevice::Virtio(dev)
-        } else {
-            RootBlockDevice::Ramdisk(RootFsDevice::new())
-        };
+        let dev = select_root_device();
         // SAFETY: 单核初始化时写入静态设备句柄。
         unsafe {
             ROOT_DEVICE.write(dev);
@@ -0,0 +1,329 @@
+//! inotify instances: watch lists and per-instance event queues.
+//!
+//! Watches are keyed by (mount, inode). The syscall layer reports VFS changes
+//! through `notify`, once for the object itself and once for its parent
+//! directory with the entry name, which matches how Linux fans events out.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use axfs::mount::MountId;
+use axfs::InodeId;
+
+use crate::runtime;
+use crate::task_wait_queue::TaskWaitQueue;
+
+const INOTIFY_SLOTS: usize = 4;
+const MAX_WATCHES: usize = 16;
+const QUEUE_BYTES: usize = 2048;
+/// Size of `struct inotify_event` without the trailing name.
+pub const EVENT_HEADER_SIZE: usize = 16;
+
+pub const IN_ACCESS: u32 = 0x0000_0001;
+pub const IN_MODIFY: u32 = 0x0000_0002;
+pub const IN_ATTRIB: u32 = 0x0000_0004;
+pub const IN_CLOSE_WRITE: u32 = 0x0000_0008;
+pub const IN_CLOSE_NOWRITE: u32 = 0x0000_0010;
+pub const IN_OPEN: u32 = 0x0000_0020;
+pub const IN_MOVED_FROM: u32 = 0x0000_0040;
+pub const IN_MOVED_TO: u32 = 0x0000_0080;
+pub const IN_CREATE: u32 = 0x0000_0100;
+pub const IN_DELETE: u32 = 0x0000_0200;
+pub const IN_DELETE_SELF: u32 = 0x0000_0400;
+pub const IN_MOVE_SELF: u32 = 0x0000_0800;
+pub const IN_ALL_EVENTS: u32 = 0x0000_0fff;
+pub const IN_Q_OVERFLOW: u32 = 0x0000_4000;
+pub const IN_IGNORED: u32 = 0x0000_8000;
+pub const IN_ONLYDIR: u32 = 0x0100_0000;
+pub const IN_DONT_FOLLOW: u32 = 0x0200_0000;
+pub const IN_EXCL_UNLINK: u32 = 0x0400_0000;
+pub const IN_MASK_CREATE: u32 = 0x1000_0000;
+pub const IN_MASK_ADD: u32 = 0x2000_0000;
+pub const IN_ISDIR: u32 = 0x4000_0000;
+pub const IN_ONESHOT: u32 = 0x8000_0000;
+
+const WATCH_FLAGS: u32 = IN_ONLYDIR | IN_DONT_FOLLOW | IN_EXCL_UNLINK | IN_MASK_CREATE | IN_MASK_ADD | IN_ONESHOT;
+
+/// File identity a watch is attached to.
+pub type WatchKey = (MountId, InodeId);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// inotify error codes returned to the syscall layer.
+pub enum InotifyError {
+    /// Bad mask, unknown watch descriptor or a too small read buffer.
+    Invalid,
+    /// IN_MASK_CREATE on an inode that is already watched.
+    Exists,
+    /// The instance watch list is full.
+    NoSpace,
+    /// The instance slot is not in use.
+    BadInstance,
+}
+
+#[derive(Clone, Copy)]
+struct Watch {
+    key: WatchKey,
+    wd: i32,
+    mask: u32,
+}
+
+struct Instance {
+    used: bool,
+    refs: usize,
+    next_wd: i32,
+    watches: [Option<Watch>; MAX_WATCHES],
+    /// Start of the newest queued event, used to coalesce repeats.
+    last: Option<usize>,
+    len: usize,
+    queue: [u8; QUEUE_BYTES],
+}
+
+const EMPTY_INSTANCE: Instance = Instance {
+    used: false,
+    refs: 0,
+    next_wd: 1,
+    watches: [None; MAX_WATCHES],
+    last: None,
+    len: 0,
+    queue: [0; QUEUE_BYTES],
+};
+
+// SAFETY: inotify 表在单核阶段由系统调用串行访问。
+static mut INSTANCES: [Instance; INOTIFY_SLOTS] = [EMPTY_INSTANCE; INOTIFY_SLOTS];
+static INOTIFY_WAITERS: [TaskWaitQueue; INOTIFY_SLOTS] = [
+    TaskWaitQueue::new(),
+    TaskWaitQueue::new(),
+    TaskWaitQueue::new(),
+    TaskWaitQueue::new(),
+];
+static NEXT_COOKIE: AtomicU32 = AtomicU32::new(1);
+
+fn instance(id: usize) -> Result<&'static mut Instance, InotifyError> {
+    // SAFETY: 单核阶段串行访问 inotify 表。
+    let inst = unsafe { INSTANCES.get_mut(id) }.ok_or(InotifyError::BadInstance)?;
+    if inst.used {
+        Ok(inst)
+    } else {
+        Err(InotifyError::BadInstance)
+    }
+}
+
+/// Allocate an inotify instance holding one reference.
+pub fn alloc() -> Option<usize> {
+    // SAFETY: 单核阶段串行访问 inotify 表。
+    let instances = unsafe { &mut INSTANCES };
+    let id = instances.iter().position(|inst| !inst.used)?;
+    instances[id] = EMPTY_INSTANCE;
+    instances[id].used = true;
+    instances[id].refs = 1;
+    Some(id)
+}
+
+/// Take an extra descriptor reference (dup, fork).
+pub fn acquire(id: usize) {
+    if let Ok(inst) = instance(id) {
+        inst.refs += 1;
+    }
+}
+
+/// Drop a descriptor reference; the last one frees the instance and its watches.
+pub fn release(id: usize) {
+    if let Ok(inst) = instance(id) {
+        inst.refs = inst.refs.saturating_sub(1);
+        if inst.refs == 0 {
+            *inst = EMPTY_INSTANCE;
+        }
+    }
+}
+
+/// Wait queue readers of `id` sleep on.
+pub fn queue(id: usize) -> &'static TaskWaitQueue {
+    &INOTIFY_WAITERS[id % INOTIFY_SLOTS]
+}
+
+/// Allocate a cookie tying an IN_MOVED_FROM event to its IN_MOVED_TO.
+pub fn next_cookie() -> u32 {
+    NEXT_COOKIE.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Add a watch on `key` or update the mask of the existing one; returns the watch descriptor.
+pub fn add_watch(id: usize, key: WatchKey, mask: u32) -> Result<i32, InotifyError> {
+    if mask & !(IN_ALL_EVENTS | WATCH_FLAGS) != 0 || mask & IN_ALL_EVENTS == 0 {
+        return Err(InotifyError::Invalid);
+    }
+    if mask & IN_MASK_ADD != 0 && mask & IN_MASK_CREATE != 0 {
+        return Err(InotifyError::Invalid);
+    }
+    let inst = instance(id)?;
+    if let Some(watch) = inst.watches.iter_mut().flatten().find(|watch| watch.key == key) {
+        if mask & IN_MASK_CREATE != 0 {
+            return Err(InotifyError::Exists);
+        }
+        let kept = if mask & IN_MASK_ADD != 0 { watch.mask } else { 0 };
+        watch.mask = kept | (mask & !(IN_MASK_ADD | IN_MASK_CREATE));
+        return Ok(watch.wd);
+    }
+    let slot = inst
+        .watches
+        .iter_mut()
+        .find(|slot| slot.is_none())
+        .ok_or(InotifyError::NoSpace)?;
+    let wd = inst.next_wd;
+    inst.next_wd = inst.next_wd.checked_add(1).unwrap_or(1);
+    *slot = Some(Watch {
+        key,
+        wd,
+        mask: mask & !(IN_MASK_ADD | IN_MASK_CREATE),
+    });
+    Ok(wd)
+}
+
+/// Remove watch `wd` and queue its IN_IGNORED event.
+pub fn rm_watch(id: usize, wd: i32) -> Result<(), InotifyError> {
+    let inst = instance(id)?;
+    let slot = inst
+        .watches
+        .iter_mut()
+        .find(|slot| slot.is_some_and(|watch| watch.wd == wd))
+        .ok_or(InotifyError::Invalid)?;
+    *slot = None;
+    push_event(inst, wd, IN_IGNORED, 0, None);
+    runtime::wake_all(queue(id));
+    Ok(())
+}
+
+/// Whether any instance holds a watch; lets callers skip path lookups.
+pub fn watching() -> bool {
+    // SAFETY: 单核阶段只读扫描 inotify 表。
+    unsafe {
+        INSTANCES
+            .iter()
+            .any(|inst| inst.used && inst.watches.iter().any(Option::is_some))
+    }
+}
+
+/// Queue `mask` for every watch on `key`; `name` is set when `key` is the parent directory.
+pub fn notify(key: WatchKey, mask: u32, cookie: u32, name: Option<&str>) {
+    for id in 0..INOTIFY_SLOTS {
+        let Ok(inst) = instance(id) else {
+            continue;
+        };
+        let mut queued = false;
+        for idx in 0..MAX_WATCHES {
+            let Some(watch) = inst.watches[idx] else {
+                continue;
+            };
+            if watch.key != key || watch.mask & mask & IN_ALL_EVENTS == 0 {
+                continue;
+            }
+            push_event(inst, watch.wd, mask, cookie, name);
+            if watch.mask & IN_ONESHOT != 0 {
+                inst.watches[idx] = None;
+                push_event(inst, watch.wd, IN_IGNORED, 0, None);
+            }
+            queued = true;
+        }
+        if queued {
+            runtime::wake_all(queue(id));
+        }
+    }
+}
+
+/// Drop every watch on an inode that no longer exists, queueing IN_IGNORED.
+pub fn forget(key: WatchKey) {
+    for id in 0..INOTIFY_SLOTS {
+        let Ok(inst) = instance(id) else {
+            continue;
+        };
+        let mut queued = false;
+        for idx in 0..MAX_WATCHES {
+            let Some(watch) = inst.watches[idx] else {
+                continue;
+            };
+            if watch.key == key {
+                inst.watches[idx] = None;
+                push_event(inst, watch.wd, IN_IGNORED, 0, None);
+                queued = true;
+            }
+        }
+        if queued {
+            runtime::wake_all(queue(id));
+        }
+    }
+}
+
+/// Bytes waiting to be read (FIONREAD).
+pub fn pending(id: usize) -> Result<usize, InotifyError> {
+    Ok(instance(id)?.len)
+}
+
+/// Move whole events into `out`; returns 0 when the queue is empty.
+pub fn read(id: usize, out: &mut [u8]) -> Result<usize, InotifyError> {
+    let inst = instance(id)?;
+    let mut taken = 0usize;
+    while taken < inst.len {
+        let size = event_size(&inst.queue[taken..inst.len]);
+        if taken + size > out.len() {
+            break;
+        }
+        taken += size;
+    }
+    if taken == 0 && inst.len > 0 {
+        // 与 Linux 一致：缓冲区放不下第一个事件时返回 EINVAL。
+        return Err(InotifyError::Invalid);
+    }
+    out[..taken].copy_from_slice(&inst.queue[..taken]);
+    inst.queue.copy_within(taken..inst.len, 0);
+    inst.len -= taken;
+    inst.last = inst.last.and_then(|last| last.checked_sub(taken));
+    Ok(taken)
+}
+
+fn event_size(event: &[u8]) -> usize {
+    let name_len = u32::from_le_bytes([event[12], event[13], event[14], event[15]]) as usize;
+    EVENT_HEADER_SIZE + name_len
+}
+
+fn push_event(inst: &mut Instance, wd: i32, mask: u32, cookie: u32, name: Option<&str>) {
+    // 名字含结尾 NUL，并按事件头大小补齐，与 Linux 的记录布局一致。
+    let name_len = name.map_or(0, |name| (name.len() + EVENT_HEADER_SIZE) & !(EVENT_HEADER_SIZE - 1));
+    let size = EVENT_HEADER_SIZE + name_len;
+    let mut header = [0u8; EVENT_HEADER_SIZE];
+    header[0..4].copy_from_slice(&wd.to_le_bytes());
+    header[4..8].copy_from_slice(&mask.to_le_bytes());
+    header[8..12].copy_from_slice(&cookie.to_le_bytes());
+    header[12..16].copy_from_slice(&(name_len as u32).to_le_bytes());
+    if let Some(last) = inst.last {
+        // 连续重复的事件合并为一条。
+        let same_header = inst.queue[last..last + EVENT_HEADER_SIZE] == header;
+        let same_name = name.map_or(true, |name| {
+            inst.queue[last + EVENT_HEADER_SIZE..last + EVENT_HEADER_SIZE + name.len()] == *name.as_bytes()
+        });
+        if same_header && same_name && last + size == inst.len {
+            return;
+        }
+    }
+    // 预留一个事件头的位置给溢出事件。
+    if inst.len + size + EVENT_HEADER_SIZE > QUEUE_BYTES {
+        let overflowed = inst
+            .last
+            .is_some_and(|last| inst.queue[last + 4..last + 8] == IN_Q_OVERFLOW.to_le_bytes());
+        if !overflowed && inst.len + EVENT_HEADER_SIZE <= QUEUE_BYTES {
+            let start = inst.len;
+            inst.queue[start..start + 4].copy_from_slice(&(-1i32).to_le_bytes());
+            inst.queue[start + 4..start + 8].copy_from_slice(&IN_Q_OVERFLOW.to_le_bytes());
+            inst.queue[start + 8..start + EVENT_HEADER_SIZE].fill(0);
+            inst.len += EVENT_HEADER_SIZE;
+            inst.last = Some(start);
+        }
+        return;
+    }
+    let start = inst.len;
+    inst.queue[start..start + EVENT_HEADER_SIZE].copy_from_slice(&header);
+    let body = &mut inst.queue[start + EVENT_HEADER_SIZE..start + size];
+    body.fill(0);
+    if let Some(name) = name {
+        body[..name.len()].copy_from_slice(name.as_bytes());
+    }
+    inst.len += size;
+    inst.last = Some(start);
+}
@@ -3,7 +3,9 @@
 //! Kernel entry point and subsystem initialization order.
 
 mod console;
+mod chardev;
 mod dtb;
+mod cmdline;
 mod sbi;
 mod trap;
 mod mm;
@@ -14,7 +16,11 @@ mod sleep;
 mod sleep_queue;
 mod wait;
 mod wait_queue;
+mod cred;
+mod flock;
 mod futex;
+mod inotify;
+mod unix_socket;
 mod syscall;
 mod user;
 mod fs;
@@ -28,6 +34,7 @@ mod context;
 mod stack;
 mod config;
 mod process;
+mod procinfo;
 mod async_exec;
 
 use core::panic::PanicInfo;
@@ -50,6 +57,10 @@ pub extern "C" fn rust_main(hart_id: usize, dtb_addr: usize) -> ! {
             dtb::DtbInfo::default()
         }
     };
+    cmdline::init(dtb_info.bootargs());
+    if !cmdline::as_str().is_empty() {
+        crate::println!("cmdline: {}", cmdline::as_str());
+    }
 
     if let Some(region) = dtb_info.uart {
         crate::println!(
@@ -84,19 +95,35 @@ pub extern "C" fn rust_main(hart_id: usize, dtb_addr: usize) -> ! {
     let device_count = dtb_info.collect_device_regions(&mut device_regions);
     mm::init(dtb_info.memory, &device_regions[..device_count]);
     plic::init(dtb_info.plic);
+    chardev::init();
     fs::init(dtb_info.virtio_mmio_devices());
+    procinfo::init(&dtb_info);
     virtio_net::init(dtb_info.virtio_mmio_devices());
-    if let Some(dev) = virtio_net::device() {
-        if axnet::init(dev).is_ok() {
-            crate::println!("axnet: interface up (static 10.0.2.15/24)");
-            let _ = axnet::arp_probe_gateway_once();
-            #[cfg(feature = "net-loopback-test")]
-            {
-                if axnet::tcp_loopback_test_once().is_ok() {
-                    crate::println!("net: tcp loopback ok");
-                } else {
-                    crate::println!("net: tcp loopback failed");
+    let nic = virtio_net::device().map(|dev| dev as &'static dyn axnet::NetDevice);
+    // eth0 的地址策略来自 `ip=` 启动参数（即 DTB /chosen/bootargs），缺省走 DHCP。
+    let net_config = axnet::NetConfig::from_ip_param(cmdline::param("ip")).unwrap_or_else(|_| {
+        crate::println!("axnet: ignoring malformed ip= parameter");
+        axnet::NetConfig::Dhcp { fallback: None }
+    });
+    if axnet::init(nic, &mm::NetPages, net_config).is_ok() {
+        crate::println!("axnet: lo up (127.0.0.1/8, ::1/128)");
+        if nic.is_some() {
+            match net_config {
+                axnet::NetConfig::Static(config) => {
+                    crate::println!("axnet: eth0 up (static {}/{})", config.addr, config.prefix_len);
                 }
+                axnet::NetConfig::Dhcp { .. } => crate::println!("axnet: eth0 up (dhcp)"),
+                axnet::NetConfig::Off => crate::println!("axnet: eth0 up (unconfigured)"),
+            }
+            // 探测在 eth0 拿到地址和网关后才真正发出。
+            let _ = axnet::arp_probe_gateway_once();
+        }
+        #[cfg(feature = "net-loopback-test")]
+        {
+            if axnet::tcp_loopback_test_once().is_ok() {
+                crate::println!("net: tcp loopback ok");
+            } else {
+                crate::println!("net: tcp loopback failed");
             }
         }
     }
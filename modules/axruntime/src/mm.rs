@@ -5,7 +5,7 @@ use core::arch::asm;
 use core::cmp::{max, min};
 use core::marker::PhantomData;
 use core::mem::{size_of, MaybeUninit};
-use core::ptr;
+use core::ptr::{self, NonNull};
 use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 /// Base page size used by the kernel (4KiB).
@@ -356,6 +356,41 @@ pub fn alloc_contiguous_frames(count: usize) -> Option<PhysPageNum> {
     Some(frame)
 }
 
+/// Return frames from `alloc_contiguous_frames` to the free list page by page.
+pub fn free_contiguous_frames(pa: usize, count: usize) {
+    for idx in 0..count {
+        let _ = release_frame(pa + idx * PAGE_SIZE);
+    }
+}
+
+/// Frame source for axnet's socket tables and buffer pool.
+pub struct NetPages;
+
+impl axnet::NetMemory for NetPages {
+    fn alloc_pages(&self, pages: usize) -> Option<NonNull<u8>> {
+        let frame = alloc_contiguous_frames(pages)?;
+        NonNull::new(frame.addr().as_usize() as *mut u8)
+    }
+
+    fn free_pages(&self, ptr: NonNull<u8>, pages: usize) {
+        free_contiguous_frames(ptr.as_ptr() as usize, pages);
+    }
+}
+
+/// Frame source for /dev/shm file contents.
+pub struct ShmPages;
+
+impl axfs::devfs::ShmMemory for ShmPages {
+    fn alloc_page(&self) -> Option<NonNull<u8>> {
+        let frame = alloc_frame()?;
+        NonNull::new(frame.addr().as_usize() as *mut u8)
+    }
+
+    fn free_page(&self, page: NonNull<u8>) {
+        free_contiguous_frames(page.as_ptr() as usize, 1);
+    }
+}
+
 #[derive(Clone, Copy)]
 /// Requested access type for user pointer validation.
 pub enum UserAccess {
@@ -516,6 +551,84 @@ pub fn memory_size() -> usize {
     MEM_SIZE.load(Ordering::Relaxed)
 }
 
+/// Return `(total, free)` frame counts managed by the frame allocator.
+pub fn frame_stats() -> (usize, usize) {
+    if !FRAME_ALLOC_READY.load(Ordering::Acquire) {
+        return (0, 0);
+    }
+    let total = FRAME_COUNT.load(Ordering::Relaxed);
+    // SAFETY: initialized once in init_frame_allocator before FRAME_ALLOC_READY is set.
+    let allocator = unsafe { FRAME_ALLOC.assume_init_ref() };
+    let next = allocator.next.load(Ordering::Relaxed);
+    let untouched = allocator.end.saturating_sub(next) / PAGE_SIZE;
+    // SAFETY: read-only snapshot of the free list length.
+    let recycled = with_no_irq(|| unsafe { FRAME_FREE_LEN });
+    (total, min(total, untouched + recycled))
+}
+
+/// Visit contiguous user mappings as `[start, end)` ranges with their permissions.
+pub fn for_each_user_range<F>(root_pa: usize, mut f: F)
+where
+    F: FnMut(usize, usize, UserMapFlags),
+{
+    let kernel_root_pa = kernel_root_pa();
+    if root_pa == 0 || kernel_root_pa == 0 || root_pa == kernel_root_pa {
+        return;
+    }
+    const PERM_MASK: usize = PTE_R | PTE_W | PTE_X | PTE_COW;
+    // SAFETY: page tables are identity-mapped and stable while we only read them.
+    let root = unsafe { &*(root_pa as *const PageTable) };
+    // SAFETY: kernel root is initialized before any user root exists.
+    let kernel_root = unsafe { &*(kernel_root_pa as *const PageTable) };
+    // 合并相邻且权限一致的 4KiB 页，COW 页按可写处理。
+    let mut current: Option<(usize, usize, usize)> = None;
+    let emit = |range: (usize, usize, usize), f: &mut F| {
+        let (start, end, perm) = range;
+        let flags = UserMapFlags {
+            read: (perm & PTE_R) != 0,
+            write: (perm & (PTE_W | PTE_COW)) != 0,
+            exec: (perm & PTE_X) != 0,
+        };
+        f(start, end, flags);
+    };
+    for l2_idx in 0..SV39_ENTRIES {
+        let l2e = root.entries[l2_idx];
+        if !l2e.is_valid() || l2e.is_leaf() || l2e.bits == kernel_root.entries[l2_idx].bits {
+            continue;
+        }
+        // SAFETY: entry points to a valid next-level table page.
+        let l1 = unsafe { &*(l2e.ppn().addr().as_usize() as *const PageTable) };
+        for l1_idx in 0..SV39_ENTRIES {
+            let l1e = l1.entries[l1_idx];
+            if !l1e.is_valid() || l1e.is_leaf() {
+                continue;
+            }
+            // SAFETY: entry points to a valid next-level table page.
+            let l0 = unsafe { &*(l1e.ppn().addr().as_usize() as *const PageTable) };
+            for l0_idx in 0..SV39_ENTRIES {
+                let l0e = l0.entries[l0_idx];
+                if !l0e.is_valid() || !l0e.is_leaf() || (l0e.flags() & PTE_U) == 0 {
+                    continue;
+                }
+                let va = (l2_idx << 30) | (l1_idx << 21) | (l0_idx << PAGE_SHIFT);
+                let perm = l0e.flags() & PERM_MASK;
+                let perm = if (perm & PTE_COW) != 0 { perm | PTE_W } else { perm };
+                current = match current {
+                    Some((start, end, cur)) if end == va && cur == perm => Some((start, va + PAGE_SIZE, cur)),
+                    Some(range) => {
+                        emit(range, &mut f);
+                        Some((va, va + PAGE_SIZE, perm))
+                    }
+                    None => Some((va, va + PAGE_SIZE, perm)),
+                };
+            }
+        }
+    }
+    if let Some(range) = current {
+        emit(range, &mut f);
+    }
+}
+
 /// Return the physical address of the currently active root page table.
 pub fn current_root_pa() -> usize {
     let satp = read_satp();
@@ -12,7 +12,15 @@ const PLIC_ENABLE_STRIDE: usize = 0x80;
 const PLIC_CONTEXT_STRIDE: usize = 0x1000;
 const PLIC_CONTEXT_S: usize = 1;
 
+/// Highest IRQ number tracked for accounting.
+pub const MAX_IRQS: usize = 64;
+
 static PLIC_BASE: AtomicUsize = AtomicUsize::new(0);
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO_COUNT: AtomicUsize = AtomicUsize::new(0);
+// 每个 IRQ 的 claim 次数，供 /proc/interrupts 与 /proc/stat 使用。
+static IRQ_COUNTS: [AtomicUsize; MAX_IRQS] = [ZERO_COUNT; MAX_IRQS];
+static mut IRQ_NAMES: [Option<&'static str>; MAX_IRQS] = [None; MAX_IRQS];
 
 /// Initialize the PLIC mapping from the device tree.
 pub fn init(region: Option<MemoryRegion>) {
@@ -29,13 +37,19 @@ pub fn init(region: Option<MemoryRegion>) {
     }
 }
 
-/// Enable a specific IRQ in the PLIC.
-pub fn enable(irq: u32) {
+/// Enable a specific IRQ in the PLIC and record the owning device name.
+pub fn enable(irq: u32, name: &'static str) {
     let base = PLIC_BASE.load(Ordering::Acquire);
     if base == 0 || irq == 0 {
         return;
     }
     let irq = irq as usize;
+    if irq < MAX_IRQS {
+        // SAFETY: IRQs are enabled during single-hart driver init only.
+        unsafe {
+            IRQ_NAMES[irq] = Some(name);
+        }
+    }
     let priority_addr = base + PLIC_PRIORITY_BASE + irq * 4;
     let enable_addr = base
         + PLIC_ENABLE_BASE
@@ -62,6 +76,9 @@ pub fn claim() -> Option<u32> {
     if irq == 0 {
         None
     } else {
+        if let Some(count) = IRQ_COUNTS.get(irq as usize) {
+            count.fetch_add(1, Ordering::Relaxed);
+        }
         Some(irq)
     }
 }
@@ -78,3 +95,32 @@ pub fn complete(irq: u32) {
         ptr::write_volatile(claim_addr as *mut u32, irq);
     }
 }
+
+/// Visit every enabled IRQ as `(irq, name, count)`.
+pub fn for_each_irq<F>(mut f: F)
+where
+    F: FnMut(u32, &'static str, usize),
+{
+    for (irq, count) in IRQ_COUNTS.iter().enumerate() {
+        // SAFETY: names are written during driver init and read-only afterwards.
+        let Some(name) = (unsafe { IRQ_NAMES[irq] }) else {
+            continue;
+        };
+        f(irq as u32, name, count.load(Ordering::Relaxed));
+    }
+}
+
+/// Return the number of times `irq` has been claimed.
+pub fn irq_count(irq: usize) -> usize {
+    IRQ_COUNTS
+        .get(irq)
+        .map_or(0, |count| count.load(Ordering::Relaxed))
+}
+
+/// Return the total number of claimed external interrupts.
+pub fn total_count() -> usize {
+    IRQ_COUNTS
+        .iter()
+        .map(|count| count.load(Ordering::Relaxed))
+        .sum()
+}
@@ -1,11 +1,13 @@
 #![allow(dead_code)]
 //! Minimal process table and wait/exit helpers.
 
+use core::sync::atomic::{AtomicUsize, Ordering};
+
 use crate::futex;
 use crate::mm;
 use crate::runtime;
 use crate::syscall::Errno;
-use crate::task::TaskId;
+use crate::task::{TaskId, TaskState};
 use crate::task_wait_queue::TaskWaitQueue;
 
 const MAX_PROCS: usize = crate::config::MAX_TASKS;
@@ -23,6 +25,10 @@ static mut PROC_PPID: [usize; MAX_PROCS] = [0; MAX_PROCS];
 static mut PROC_EXIT: [i32; MAX_PROCS] = [0; MAX_PROCS];
 static mut PROC_ROOT: [usize; MAX_PROCS] = [0; MAX_PROCS];
 static mut PROC_CLEARTID: [usize; MAX_PROCS] = [0; MAX_PROCS];
+static mut PROC_START_TICK: [u64; MAX_PROCS] = [0; MAX_PROCS];
+static mut PROC_CPU_TICKS: [u64; MAX_PROCS] = [0; MAX_PROCS];
+static LAST_PID: AtomicUsize = AtomicUsize::new(0);
+static FORK_COUNT: AtomicUsize = AtomicUsize::new(0);
 // 固定大小等待队列：每个父进程一个，用于 waitpid 阻塞。
 static PROC_WAITERS: [TaskWaitQueue; MAX_PROCS] = [
     TaskWaitQueue::new(),
@@ -47,11 +53,94 @@ pub fn init_process(task_id: TaskId, parent_pid: usize, root_pa: usize) -> usize
             PROC_EXIT[idx] = 0;
             PROC_ROOT[idx] = root_pa;
             PROC_CLEARTID[idx] = 0;
+            PROC_START_TICK[idx] = crate::time::ticks();
+            PROC_CPU_TICKS[idx] = 0;
         }
     }
+    LAST_PID.store(pid, Ordering::Relaxed);
+    FORK_COUNT.fetch_add(1, Ordering::Relaxed);
     pid
 }
 
+#[derive(Clone, Copy)]
+/// Snapshot of a process record for procfs.
+pub struct ProcInfo {
+    /// Process ID.
+    pub pid: usize,
+    /// Parent process ID (0 for the init process).
+    pub ppid: usize,
+    /// Linux-style state letter (R/S/Z).
+    pub state: char,
+    /// User page table root; 0 once the address space is released.
+    pub root_pa: usize,
+    /// Tick at which the process was created.
+    pub start_tick: u64,
+    /// Timer ticks charged to the process.
+    pub cpu_ticks: u64,
+}
+
+/// Return a snapshot of the process identified by `pid`.
+pub fn info(pid: usize) -> Option<ProcInfo> {
+    let idx = pid.checked_sub(1)?;
+    // SAFETY: read-only access to the process table on a single hart.
+    unsafe {
+        if idx >= MAX_PROCS || PROC_STATE[idx] == ProcState::Empty {
+            return None;
+        }
+        let state = match PROC_STATE[idx] {
+            ProcState::Zombie => 'Z',
+            _ => match crate::task::state(idx) {
+                Some(TaskState::Blocked) => 'S',
+                _ => 'R',
+            },
+        };
+        Some(ProcInfo {
+            pid,
+            ppid: PROC_PPID[idx],
+            state,
+            root_pa: PROC_ROOT[idx],
+            start_tick: PROC_START_TICK[idx],
+            cpu_ticks: PROC_CPU_TICKS[idx],
+        })
+    }
+}
+
+/// Fill `out` with live (running or zombie) pids in ascending order.
+pub fn pids(out: &mut [usize]) -> usize {
+    let mut count = 0;
+    // SAFETY: read-only access to the process table on a single hart.
+    unsafe {
+        for (idx, state) in PROC_STATE.iter().enumerate() {
+            if *state == ProcState::Empty || count >= out.len() {
+                continue;
+            }
+            out[count] = idx + 1;
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Return the most recently allocated pid.
+pub fn last_pid() -> usize {
+    LAST_PID.load(Ordering::Relaxed)
+}
+
+/// Return the number of processes created since boot.
+pub fn fork_count() -> usize {
+    FORK_COUNT.load(Ordering::Relaxed)
+}
+
+/// Charge one timer tick to the process running on `task_id`.
+pub fn account_tick(task_id: TaskId) {
+    // SAFETY: called from the timer trap on a single hart.
+    unsafe {
+        if task_id < MAX_PROCS && PROC_STATE[task_id] == ProcState::Running {
+            PROC_CPU_TICKS[task_id] += 1;
+        }
+    }
+}
+
 /// Return the current process ID, if any.
 pub fn current_pid() -> Option<usize> {
     let task_id = runtime::current_task_id()?;
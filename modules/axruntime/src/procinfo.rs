@@ -0,0 +1,405 @@
+//! Kernel data source behind `/proc`.
+
+use core::cell::UnsafeCell;
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use axfs::procfs::{ProcFile, ProcSource};
+use axfs::mount::{MountId, ST_NODEV, ST_NOEXEC, ST_NOSUID, ST_RDONLY, ST_RELATIME};
+
+use crate::dtb::{DtbInfo, MAX_ISA_LEN, MAX_MMU_TYPE_LEN};
+use crate::{mm, plic, process, runtime, task, time};
+
+/// Clock ticks per second reported to user space (`USER_HZ`).
+const USER_HZ: u64 = 100;
+/// Supervisor timer interrupt number in the RISC-V INTC domain.
+const TIMER_IRQ: u32 = 5;
+const MAX_COMM_LEN: usize = 15;
+
+struct CpuInfoCell {
+    isa: UnsafeCell<[u8; MAX_ISA_LEN]>,
+    mmu: UnsafeCell<[u8; MAX_MMU_TYPE_LEN]>,
+}
+
+// SAFETY: written once during boot before procfs is reachable, read-only afterwards.
+unsafe impl Sync for CpuInfoCell {}
+
+static CPU_INFO: CpuInfoCell = CpuInfoCell {
+    isa: UnsafeCell::new([0; MAX_ISA_LEN]),
+    mmu: UnsafeCell::new([0; MAX_MMU_TYPE_LEN]),
+};
+static CPU_COUNT: AtomicUsize = AtomicUsize::new(1);
+static ISA_LEN: AtomicUsize = AtomicUsize::new(0);
+static MMU_LEN: AtomicUsize = AtomicUsize::new(0);
+
+struct KernelProcSource;
+
+static PROC_SOURCE: KernelProcSource = KernelProcSource;
+
+/// Record CPU details from the DTB and attach the data source to `/proc`.
+pub fn init(dtb_info: &DtbInfo) {
+    // SAFETY: 单核早期阶段写入一次，之后只读。
+    unsafe {
+        (*CPU_INFO.isa.get())[..dtb_info.isa_len].copy_from_slice(dtb_info.isa().as_bytes());
+        (*CPU_INFO.mmu.get())[..dtb_info.mmu_type_len]
+            .copy_from_slice(dtb_info.mmu_type().as_bytes());
+    }
+    ISA_LEN.store(dtb_info.isa().len(), Ordering::Release);
+    MMU_LEN.store(dtb_info.mmu_type().len(), Ordering::Release);
+    CPU_COUNT.store(dtb_info.cpu_count.max(1), Ordering::Release);
+    crate::syscall::procfs().set_source(&PROC_SOURCE);
+}
+
+fn cpu_isa() -> &'static str {
+    let len = ISA_LEN.load(Ordering::Acquire);
+    // SAFETY: buffer is written once before ISA_LEN is published.
+    let bytes = unsafe { &(*CPU_INFO.isa.get())[..len] };
+    core::str::from_utf8(bytes).unwrap_or("")
+}
+
+fn cpu_mmu() -> &'static str {
+    let len = MMU_LEN.load(Ordering::Acquire);
+    // SAFETY: buffer is written once before MMU_LEN is published.
+    let bytes = unsafe { &(*CPU_INFO.mmu.get())[..len] };
+    match core::str::from_utf8(bytes) {
+        Ok("riscv,sv39") | Err(_) => "sv39",
+        Ok(other) => other.strip_prefix("riscv,").unwrap_or(other),
+    }
+}
+
+fn ticks_to_clock(ticks: u64) -> u64 {
+    let hz = time::tick_hz();
+    if hz == 0 {
+        return 0;
+    }
+    ticks.saturating_mul(USER_HZ) / hz
+}
+
+/// Write `value` in hundredths as `int.frac`.
+fn write_centis(out: &mut dyn Write, centis: u64) -> fmt::Result {
+    write!(out, "{}.{:02}", centis / 100, centis % 100)
+}
+
+fn load_fixed(out: &mut dyn Write, value: usize) -> fmt::Result {
+    let int = value >> runtime::LOAD_FSHIFT;
+    let frac = ((value & ((1 << runtime::LOAD_FSHIFT) - 1)) * 100) >> runtime::LOAD_FSHIFT;
+    write!(out, "{}.{:02}", int, frac)
+}
+
+/// Return the command name (exe basename, at most 15 bytes).
+fn comm(idx: usize) -> &'static str {
+    let Some(exe) = crate::syscall::proc_exe(idx) else {
+        return "init";
+    };
+    let name = exe.rsplit('/').next().unwrap_or(exe);
+    let mut end = core::cmp::min(name.len(), MAX_COMM_LEN);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
+
+/// Return `(mapped bytes, mapped pages)` for a user address space.
+fn vm_usage(root_pa: usize) -> (usize, usize) {
+    let mut bytes = 0usize;
+    mm::for_each_user_range(root_pa, |start, end, _| bytes += end - start);
+    (bytes, bytes / mm::PAGE_SIZE)
+}
+
+fn state_name(state: char) -> &'static str {
+    match state {
+        'R' => "R (running)",
+        'S' => "S (sleeping)",
+        'Z' => "Z (zombie)",
+        _ => "? (unknown)",
+    }
+}
+
+fn render_meminfo(out: &mut dyn Write) -> fmt::Result {
+    let (_, free_frames) = mm::frame_stats();
+    let total_kb = mm::memory_size() / 1024;
+    let free_kb = free_frames * mm::PAGE_SIZE / 1024;
+    let rows: [(&str, usize); 9] = [
+        ("MemTotal:", total_kb),
+        ("MemFree:", free_kb),
+        ("MemAvailable:", free_kb),
+        ("Buffers:", 0),
+        ("Cached:", 0),
+        ("SwapTotal:", 0),
+        ("SwapFree:", 0),
+        ("Shmem:", 0),
+        ("SReclaimable:", 0),
+    ];
+    for (name, value) in rows {
+        writeln!(out, "{:<16}{:>8} kB", name, value)?;
+    }
+    Ok(())
+}
+
+fn render_cpuinfo(out: &mut dyn Write) -> fmt::Result {
+    for cpu in 0..CPU_COUNT.load(Ordering::Acquire) {
+        writeln!(out, "processor\t: {}", cpu)?;
+        writeln!(out, "hart\t\t: {}", cpu)?;
+        writeln!(out, "isa\t\t: {}", cpu_isa())?;
+        writeln!(out, "mmu\t\t: {}", cpu_mmu())?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn render_uptime(out: &mut dyn Write) -> fmt::Result {
+    let hz = time::tick_hz().max(1);
+    write_centis(out, time::uptime_ms() / 10)?;
+    out.write_str(" ")?;
+    write_centis(out, runtime::idle_ticks() * 100 / hz)?;
+    out.write_str("\n")
+}
+
+fn render_loadavg(out: &mut dyn Write) -> fmt::Result {
+    let avg = runtime::load_avg();
+    for value in avg {
+        load_fixed(out, value)?;
+        out.write_str(" ")?;
+    }
+    let (runnable, blocked) = task::state_counts();
+    writeln!(out, "{}/{} {}", runnable, runnable + blocked, process::last_pid())
+}
+
+fn render_stat(out: &mut dyn Write) -> fmt::Result {
+    let ticks = time::ticks();
+    let idle = runtime::idle_ticks();
+    let user = ticks_to_clock(ticks.saturating_sub(idle));
+    let idle = ticks_to_clock(idle);
+    writeln!(out, "cpu  {} 0 0 {} 0 0 0 0 0 0", user, idle)?;
+    writeln!(out, "cpu0 {} 0 0 {} 0 0 0 0 0 0", user, idle)?;
+    write!(out, "intr {}", plic::total_count() as u64 + ticks)?;
+    for irq in 0..plic::MAX_IRQS {
+        let count = if irq as u32 == TIMER_IRQ {
+            ticks as usize + plic::irq_count(irq)
+        } else {
+            plic::irq_count(irq)
+        };
+        write!(out, " {}", count)?;
+    }
+    writeln!(out)?;
+    writeln!(out, "ctxt {}", runtime::context_switches())?;
+    writeln!(out, "btime 0")?;
+    writeln!(out, "processes {}", process::fork_count())?;
+    let (runnable, blocked) = task::state_counts();
+    writeln!(out, "procs_running {}", runnable)?;
+    writeln!(out, "procs_blocked {}", blocked)
+}
+
+fn render_mounts(out: &mut dyn Write) -> fmt::Result {
+    let device = crate::fs::root_device_name();
+    if device.is_empty() {
+        write!(out, "rootfs")?;
+    } else {
+        write!(out, "/dev/{}", device)?;
+    }
+    write!(out, " / {} ", crate::syscall::rootfs_type())?;
+    write_mount_options(out, MountId::Root)?;
+    write!(out, "devtmpfs /dev devtmpfs ")?;
+    write_mount_options(out, MountId::Dev)?;
+    write!(out, "proc /proc proc ")?;
+    write_mount_options(out, MountId::Proc)
+}
+
+fn write_mount_options(out: &mut dyn Write, mount: MountId) -> fmt::Result {
+    const OPTIONS: [(u32, &str); 4] = [
+        (ST_NOSUID, ",nosuid"),
+        (ST_NODEV, ",nodev"),
+        (ST_NOEXEC, ",noexec"),
+        (ST_RELATIME, ",relatime"),
+    ];
+    let flags = crate::syscall::mount_flags(mount);
+    out.write_str(if flags & ST_RDONLY != 0 { "ro" } else { "rw" })?;
+    for (bit, name) in OPTIONS {
+        if flags & bit != 0 {
+            out.write_str(name)?;
+        }
+    }
+    writeln!(out, " 0 0")
+}
+
+/// Linux layout, with memory in pages; the trailing `NETPOOL` line covers the buffer pool as a whole.
+fn render_sockstat(out: &mut dyn Write) -> fmt::Result {
+    let stat = axnet::sockstat();
+    let pages = |bytes: usize| bytes.div_ceil(mm::PAGE_SIZE);
+    writeln!(out, "sockets: used {}", stat.sockets)?;
+    writeln!(
+        out,
+        "TCP: inuse {} orphan {} tw {} alloc {} mem {}",
+        stat.tcp_inuse,
+        stat.tcp_orphan,
+        stat.tcp_tw,
+        stat.tcp_alloc,
+        pages(stat.tcp_mem)
+    )?;
+    writeln!(out, "UDP: inuse {} mem {}", stat.udp_inuse, pages(stat.udp_mem))?;
+    writeln!(out, "UDPLITE: inuse 0")?;
+    writeln!(out, "RAW: inuse {}", stat.raw_inuse)?;
+    writeln!(out, "FRAG: inuse 0 memory 0")?;
+    writeln!(
+        out,
+        "NETPOOL: used {} cached {} slots {}",
+        pages(stat.pool_used),
+        pages(stat.pool_capacity),
+        stat.table_len
+    )
+}
+
+fn render_interrupts(out: &mut dyn Write) -> fmt::Result {
+    writeln!(out, "           CPU0")?;
+    writeln!(
+        out,
+        "{:>4}: {:>10}  RISC-V INTC {:>3} Edge      riscv-timer",
+        TIMER_IRQ,
+        time::ticks(),
+        TIMER_IRQ
+    )?;
+    let mut result = Ok(());
+    plic::for_each_irq(|irq, name, count| {
+        if result.is_ok() {
+            result = writeln!(out, "{:>4}: {:>10}  SiFive PLIC {:>3} Edge      {}", irq, count, irq, name);
+        }
+    });
+    result
+}
+
+fn render_pid_stat(out: &mut dyn Write, info: &process::ProcInfo) -> fmt::Result {
+    let idx = info.pid - 1;
+    let (vsize, rss) = if info.state == 'Z' { (0, 0) } else { vm_usage(info.root_pa) };
+    write!(
+        out,
+        "{} ({}) {} {} {} {} 0 -1 0 0 0 0 0 ",
+        info.pid,
+        comm(idx),
+        info.state,
+        info.ppid,
+        info.pid,
+        info.pid
+    )?;
+    write!(
+        out,
+        "{} 0 0 0 20 0 1 0 {} {} {} 18446744073709551615 ",
+        ticks_to_clock(info.cpu_ticks),
+        ticks_to_clock(info.start_tick),
+        vsize,
+        rss
+    )?;
+    let start_stack = task::user_sp(idx).unwrap_or(0);
+    writeln!(
+        out,
+        "0 0 {} 0 0 0 0 0 0 0 0 0 17 0 0 0 0 0 0 0 0 0 0 0 0 0 0",
+        start_stack
+    )
+}
+
+fn render_pid_status(out: &mut dyn Write, info: &process::ProcInfo) -> fmt::Result {
+    let idx = info.pid - 1;
+    writeln!(out, "Name:\t{}", comm(idx))?;
+    writeln!(out, "Umask:\t{:04o}", crate::syscall::proc_umask(idx))?;
+    writeln!(out, "State:\t{}", state_name(info.state))?;
+    writeln!(out, "Tgid:\t{}", info.pid)?;
+    writeln!(out, "Pid:\t{}", info.pid)?;
+    writeln!(out, "PPid:\t{}", info.ppid)?;
+    writeln!(out, "TracerPid:\t0")?;
+    let cred = crate::cred::get(idx);
+    writeln!(out, "Uid:\t{}\t{}\t{}\t{}", cred.ruid, cred.euid, cred.suid, cred.fsuid)?;
+    writeln!(out, "Gid:\t{}\t{}\t{}\t{}", cred.rgid, cred.egid, cred.sgid, cred.fsgid)?;
+    writeln!(out, "FDSize:\t{}", crate::syscall::fd_capacity())?;
+    write!(out, "Groups:")?;
+    for gid in cred.groups() {
+        write!(out, "\t{}", gid)?;
+    }
+    writeln!(out)?;
+    if info.state != 'Z' {
+        let (vsize, rss) = vm_usage(info.root_pa);
+        writeln!(out, "VmSize:\t{:>8} kB", vsize / 1024)?;
+        writeln!(out, "VmRSS:\t{:>8} kB", rss * mm::PAGE_SIZE / 1024)?;
+    }
+    writeln!(out, "Threads:\t1")
+}
+
+fn render_pid_maps(out: &mut dyn Write, info: &process::ProcInfo) -> fmt::Result {
+    if info.state == 'Z' {
+        return Ok(());
+    }
+    let mut result = Ok(());
+    mm::for_each_user_range(info.root_pa, |start, end, flags| {
+        if result.is_err() {
+            return;
+        }
+        result = writeln!(
+            out,
+            "{:08x}-{:08x} {}{}{}p 00000000 00:00 0",
+            start,
+            end,
+            if flags.read { 'r' } else { '-' },
+            if flags.write { 'w' } else { '-' },
+            if flags.exec { 'x' } else { '-' }
+        );
+    });
+    result
+}
+
+impl ProcSource for KernelProcSource {
+    fn current_pid(&self) -> Option<u32> {
+        process::current_pid().map(|pid| pid as u32)
+    }
+
+    fn pids(&self, out: &mut [u32]) -> usize {
+        let mut pids = [0usize; crate::config::MAX_TASKS];
+        let count = process::pids(&mut pids).min(out.len());
+        for (dst, &pid) in out.iter_mut().zip(pids[..count].iter()) {
+            *dst = pid as u32;
+        }
+        count
+    }
+
+    fn fds(&self, pid: u32, out: &mut [u32]) -> usize {
+        match process::info(pid as usize) {
+            Some(info) if info.state != 'Z' => crate::syscall::proc_fds(info.pid - 1, out),
+            _ => 0,
+        }
+    }
+
+    fn cmdline(&self, pid: u32) -> Option<&[u8]> {
+        match process::info(pid as usize) {
+            Some(info) if info.state != 'Z' => Some(crate::syscall::proc_cmdline(info.pid - 1)),
+            _ => None,
+        }
+    }
+
+    fn render(&self, file: ProcFile, out: &mut dyn Write) -> fmt::Result {
+        let pid = match file {
+            ProcFile::Meminfo => return render_meminfo(out),
+            ProcFile::Cpuinfo => return render_cpuinfo(out),
+            ProcFile::Uptime => return render_uptime(out),
+            ProcFile::Loadavg => return render_loadavg(out),
+            ProcFile::Mounts => return render_mounts(out),
+            ProcFile::Interrupts => return render_interrupts(out),
+            ProcFile::Stat => return render_stat(out),
+            ProcFile::NetSockstat => return render_sockstat(out),
+            ProcFile::PidStat(pid)
+            | ProcFile::PidStatus(pid)
+            | ProcFile::PidCmdline(pid)
+            | ProcFile::PidMaps(pid)
+            | ProcFile::PidCwd(pid)
+            | ProcFile::PidExe(pid)
+            | ProcFile::PidFd(pid, _) => pid,
+        };
+        let info = process::info(pid as usize).ok_or(fmt::Error)?;
+        let idx = info.pid - 1;
+        match file {
+            ProcFile::PidStat(_) => render_pid_stat(out, &info),
+            ProcFile::PidStatus(_) => render_pid_status(out, &info),
+            ProcFile::PidMaps(_) => render_pid_maps(out, &info),
+            ProcFile::PidCwd(_) => out.write_str(crate::syscall::proc_cwd(idx).ok_or(fmt::Error)?),
+            ProcFile::PidExe(_) => out.write_str(crate::syscall::proc_exe(idx).ok_or(fmt::Error)?),
+            ProcFile::PidFd(_, fd) => crate::syscall::write_fd_target(idx, fd as usize, out),
+            _ => Err(fmt::Error),
+        }
+    }
+}
@@ -25,6 +25,10 @@ static RUN_QUEUE: RunQueue = RunQueue::new();
 static SLEEP_QUEUE: SleepQueue = SleepQueue::new();
 static WAIT_QUEUE: WaitQueue = WaitQueue::new();
 static NET_WAITERS: TaskWaitQueue = TaskWaitQueue::new();
+static IDLE_TICKS: AtomicU64 = AtomicU64::new(0);
+static CONTEXT_SWITCHES: AtomicU64 = AtomicU64::new(0);
+// 1/5/15 分钟负载均值，定点数格式与 Linux 相同（FSHIFT=11）。
+static LOAD_AVG: [AtomicUsize; 3] = [AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)];
 // CURRENT_TASK is valid only while executing inside a task context.
 static mut CURRENT_TASK: Option<TaskId> = None;
 static mut IDLE_TASK: TaskControlBlock = task::idle_task();
@@ -75,6 +79,16 @@ fn dummy_task_c() -> ! {
 pub fn on_tick(ticks: u64) {
     const NET_POLL_TICK_INTERVAL: u64 = 2;
     TICK_COUNT.store(ticks, Ordering::Relaxed);
+    match current_task_id() {
+        Some(task_id) => crate::process::account_tick(task_id),
+        None => {
+            IDLE_TICKS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    let load_interval = time::tick_hz().saturating_mul(LOAD_FREQ_SECS);
+    if load_interval != 0 && ticks % load_interval == 0 {
+        update_load_avg();
+    }
     if ticks % NET_POLL_TICK_INTERVAL == 0 {
         axnet::request_poll();
         if let Some(event) = axnet::poll(time::uptime_ms()) {
@@ -106,6 +120,45 @@ pub fn on_tick(ticks: u64) {
     }
 }
 
+/// Fixed-point shift used by `load_avg` values.
+pub const LOAD_FSHIFT: usize = 11;
+const LOAD_FIXED_1: usize = 1 << LOAD_FSHIFT;
+const LOAD_FREQ_SECS: u64 = 5;
+// exp(-5s/1min), exp(-5s/5min), exp(-5s/15min) 的定点表示。
+const LOAD_EXP: [usize; 3] = [1884, 2014, 2037];
+
+fn update_load_avg() {
+    let (runnable, _) = task::state_counts();
+    let active = runnable * LOAD_FIXED_1;
+    for (avg, exp) in LOAD_AVG.iter().zip(LOAD_EXP) {
+        let old = avg.load(Ordering::Relaxed);
+        let mut new = old * exp + active * (LOAD_FIXED_1 - exp);
+        if active >= old {
+            new += LOAD_FIXED_1 - 1;
+        }
+        avg.store(new >> LOAD_FSHIFT, Ordering::Relaxed);
+    }
+}
+
+/// Return the 1/5/15 minute load averages in `LOAD_FSHIFT` fixed point.
+pub fn load_avg() -> [usize; 3] {
+    [
+        LOAD_AVG[0].load(Ordering::Relaxed),
+        LOAD_AVG[1].load(Ordering::Relaxed),
+        LOAD_AVG[2].load(Ordering::Relaxed),
+    ]
+}
+
+/// Return the number of ticks spent in the idle context.
+pub fn idle_ticks() -> u64 {
+    IDLE_TICKS.load(Ordering::Relaxed)
+}
+
+/// Return the number of switches from idle into a task.
+pub fn context_switches() -> u64 {
+    CONTEXT_SWITCHES.load(Ordering::Relaxed)
+}
+
 fn log_net_event(event: axnet::NetEvent, tag: &str) {
     match event {
         axnet::NetEvent::IcmpEchoReply { seq, from } => {
@@ -120,6 +173,25 @@ fn log_net_event(event: axnet::NetEvent, tag: &str) {
         axnet::NetEvent::RxFrameSeen => {
             crate::println!("net: rx frame seen");
         }
+        axnet::NetEvent::Ipv4Configured { config, dhcp } => {
+            let source = if dhcp { "dhcp" } else { "fallback" };
+            match config.gateway {
+                Some(gateway) => crate::println!(
+                    "net: eth0 {}/{} gw {} ({})",
+                    config.addr,
+                    config.prefix_len,
+                    gateway,
+                    source
+                ),
+                None => crate::println!("net: eth0 {}/{} ({})", config.addr, config.prefix_len, source),
+            }
+        }
+        axnet::NetEvent::Ipv4Deconfigured => {
+            crate::println!("net: eth0 lease lost");
+        }
+        axnet::NetEvent::Ipv6Configured { addr, prefix_len } => {
+            crate::println!("net: eth0 {}/{} (slaac)", addr, prefix_len);
+        }
         axnet::NetEvent::TcpRecvWindow {
             id,
             port,
@@ -347,6 +419,7 @@ pub fn schedule_once() {
             CURRENT_TASK = None;
             return;
         }
+        CONTEXT_SWITCHES.fetch_add(1, Ordering::Relaxed);
         crate::scheduler::switch(&mut IDLE_TASK, &*task_ptr);
         if CURRENT_TASK == Some(next_id) {
             CURRENT_TASK = None;
@@ -543,6 +616,60 @@ pub fn wait_timeout_ms(queue: &TaskWaitQueue, timeout_ms: u64) -> WaitResult {
     }
 }
 
+/// Like `wait_timeout_ms`, but skip sleeping if `done` holds once the task is queued.
+///
+/// Checking `done` only after enqueueing closes the window in which a wake-up
+/// from an IRQ handler would find the queue still empty.
+pub fn wait_timeout_ms_unless(queue: &TaskWaitQueue, timeout_ms: u64, done: impl Fn() -> bool) -> WaitResult {
+    let tick_hz = time::tick_hz();
+    if tick_hz == 0 {
+        return WaitResult::Timeout;
+    }
+    let delta = (timeout_ms.saturating_mul(tick_hz).saturating_add(999) / 1000).max(1);
+    let wake_tick = time::ticks().saturating_add(delta);
+
+    // SAFETY: single-hart early use; CURRENT_TASK is only accessed in init/idle/task contexts.
+    unsafe {
+        let Some(task_id) = CURRENT_TASK else {
+            return WaitResult::Timeout;
+        };
+        let Some(task_ptr) = task::task_ptr(task_id) else {
+            return WaitResult::Timeout;
+        };
+        let _ = task::set_wait_reason(task_id, WaitReason::None);
+        if !task::transition_state(task_id, TaskState::Running, TaskState::Blocked) {
+            return WaitResult::Timeout;
+        }
+        if !queue.push(task_id) {
+            let _ = task::transition_state(task_id, TaskState::Blocked, TaskState::Running);
+            return WaitResult::Timeout;
+        }
+        if !SLEEP_QUEUE.push(task_id, wake_tick) {
+            let _ = queue.pop(task_id);
+            let _ = task::transition_state(task_id, TaskState::Blocked, TaskState::Running);
+            return WaitResult::Timeout;
+        }
+        // 已在队列上再检查条件；若唤醒已先一步把任务放进运行队列，就照常切走再回来。
+        if done() {
+            let _ = SLEEP_QUEUE.remove(task_id);
+            let _ = queue.pop(task_id);
+            if task::transition_state(task_id, TaskState::Blocked, TaskState::Running) {
+                let _ = task::take_wait_reason(task_id);
+                return WaitResult::Notified;
+            }
+        }
+        NEED_RESCHED.store(true, Ordering::Relaxed);
+        CURRENT_TASK = None;
+        crate::scheduler::switch(&mut *task_ptr, &IDLE_TASK);
+        let _ = SLEEP_QUEUE.remove(task_id);
+        let _ = queue.pop(task_id);
+        match task::take_wait_reason(task_id) {
+            WaitReason::Notified => WaitResult::Notified,
+            _ => WaitResult::Timeout,
+        }
+    }
+}
+
 /// Return the shared network wait queue used by socket syscalls.
 pub fn net_wait_queue() -> &'static TaskWaitQueue {
     &NET_WAITERS
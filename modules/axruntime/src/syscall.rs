@@ -3,22 +3,33 @@
 
 use core::cmp::min;
 use core::mem::{size_of, MaybeUninit};
-use core::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use core::sync::atomic::{AtomicU32, AtomicU64, AtomicU8, Ordering};
 
-use axfs::{devfs, ext4, fat32, memfs, procfs, DirEntry, FileType, InodeId, VfsError, VfsOps};
-use axfs::mount::{MountId, MountPoint, MountTable};
+use axfs::{
+    devfs, ext4, fat32, memfs, procfs, DirEntry, FallocMode, FileExtent, FileType, InodeId, Metadata, StatFs,
+    VfsError, VfsOps, XattrMode, ACCESS_EXEC, ACCESS_READ, ACCESS_WRITE,
+};
+use axfs::dcache::{CachedFs, DentryCache};
+use axfs::mount::{MountId, MountPoint, MountTable, ST_NODEV, ST_NOEXEC, ST_NOSUID, ST_RDONLY, ST_RELATIME};
+use crate::cred::{self, CredError};
+use crate::flock::{self, LockError, LockOwner, LockType};
 use crate::futex;
+use crate::inotify::{self, InotifyError};
 use crate::mm::{self, UserAccess, UserPtr, UserSlice};
 use crate::{sbi, time};
 use crate::task::TaskId;
 use crate::trap::TrapFrame;
+use crate::unix_socket::{self, UnixAddr, UnixCred, UnixError, UnixKind};
 
 #[repr(i32)]
 #[derive(Debug, Clone, Copy)]
 /// Linux-compatible errno values used by syscalls.
 pub enum Errno {
+    Perm = 1,
     NoEnt = 2,
+    Acces = 13,
     Exist = 17,
+    XDev = 18,
     IsDir = 21,
     MFile = 24,
     NoSys = 38,
@@ -30,15 +41,33 @@ pub enum Errno {
     NotDir = 20,
     Range = 34,
     Again = 11,
+    NxIo = 6,
+    NoDev = 19,
     NoMem = 12,
+    NoSpc = 28,
     Child = 10,
     NetUnreach = 101,
+    AfNoSupport = 97,
+    AddrNotAvail = 99,
     IsConn = 106,
     NotConn = 107,
     ConnRefused = 111,
     TimedOut = 110,
     Already = 114,
     InProgress = 115,
+    OpNotSupp = 95,
+    NoData = 61,
+    TooBig = 7,
+    Deadlk = 35,
+    NoLck = 37,
+    AddrInUse = 98,
+    DestAddrReq = 89,
+    MsgSize = 90,
+    ProtoType = 91,
+    ProtoNoSupport = 93,
+    TooManyRefs = 109,
+    NoProtoOpt = 92,
+    NoBufs = 105,
 }
 
 impl Errno {
@@ -69,6 +98,12 @@ static mut ROOTFS_FAT32: MaybeUninit<fat32::Fat32Fs<'static>> = MaybeUninit::uni
 static mut ROOTFS_MEMFS: MaybeUninit<memfs::MemFs<'static>> = MaybeUninit::uninit();
 static DEVFS: devfs::DevFs = devfs::DevFs::new();
 static PROCFS: procfs::ProcFs = procfs::ProcFs::new();
+static DCACHE: DentryCache = DentryCache::new();
+
+/// Return the global devfs instance so drivers can register device nodes.
+pub fn devfs() -> &'static devfs::DevFs {
+    &DEVFS
+}
 static TCP_CONNECT_LOGGED: AtomicU8 = AtomicU8::new(0);
 static TCP_ACCEPT_LOGGED: AtomicU8 = AtomicU8::new(0);
 static TCP_RECV_LOGGED: AtomicU8 = AtomicU8::new(0);
@@ -102,6 +137,9 @@ pub fn handle_syscall(tf: &mut TrapFrame) {
 fn dispatch(tf: &mut TrapFrame, ctx: SyscallContext) -> Result<usize, Errno> {
     match ctx.nr {
         SYS_EVENTFD2 => sys_eventfd2(ctx.args[0], ctx.args[1]),
+        SYS_INOTIFY_INIT1 => sys_inotify_init1(ctx.args[0]),
+        SYS_INOTIFY_ADD_WATCH => sys_inotify_add_watch(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_INOTIFY_RM_WATCH => sys_inotify_rm_watch(ctx.args[0], ctx.args[1]),
         SYS_EPOLL_CREATE1 => sys_epoll_create1(ctx.args[0]),
         SYS_EPOLL_CTL => sys_epoll_ctl(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_EPOLL_PWAIT => sys_epoll_pwait(
@@ -168,10 +206,41 @@ fn dispatch(tf: &mut TrapFrame, ctx: SyscallContext) -> Result<usize, Errno> {
         SYS_STATFS => sys_statfs(ctx.args[0], ctx.args[1]),
         SYS_FSTATFS => sys_fstatfs(ctx.args[0], ctx.args[1]),
         SYS_FTRUNCATE => sys_ftruncate(ctx.args[0], ctx.args[1]),
+        SYS_FALLOCATE => sys_fallocate(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_SENDFILE => sys_sendfile(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_SPLICE => sys_splice(
+            ctx.args[0],
+            ctx.args[1],
+            ctx.args[2],
+            ctx.args[3],
+            ctx.args[4],
+            ctx.args[5],
+        ),
+        SYS_TEE => sys_tee(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_COPY_FILE_RANGE => sys_copy_file_range(
+            ctx.args[0],
+            ctx.args[1],
+            ctx.args[2],
+            ctx.args[3],
+            ctx.args[4],
+            ctx.args[5],
+        ),
+        // 目前的文件系统不跟随符号链接解析路径，l* 变体与普通变体行为一致。
+        SYS_SETXATTR => sys_setxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
+        SYS_LSETXATTR => sys_setxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
+        SYS_FSETXATTR => sys_fsetxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
+        SYS_GETXATTR => sys_getxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_LGETXATTR => sys_getxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_FGETXATTR => sys_fgetxattr(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
+        SYS_LISTXATTR => sys_listxattr(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_LLISTXATTR => sys_listxattr(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_FLISTXATTR => sys_flistxattr(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_REMOVEXATTR => sys_removexattr(ctx.args[0], ctx.args[1]),
+        SYS_LREMOVEXATTR => sys_removexattr(ctx.args[0], ctx.args[1]),
+        SYS_FREMOVEXATTR => sys_fremovexattr(ctx.args[0], ctx.args[1]),
         SYS_FCHMODAT => sys_fchmodat(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_FCHOWNAT => sys_fchownat(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
         SYS_UTIMENSAT => sys_utimensat(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
-        SYS_POLL => sys_poll(ctx.args[0], ctx.args[1], ctx.args[2]),
         SYS_PPOLL => sys_ppoll(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
         SYS_PPOLL_TIME64 => sys_ppoll(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3], ctx.args[4]),
         SYS_CLOCK_GETTIME => sys_clock_gettime(ctx.args[0], ctx.args[1]),
@@ -188,6 +257,14 @@ fn dispatch(tf: &mut TrapFrame, ctx: SyscallContext) -> Result<usize, Errno> {
         SYS_GETEGID => sys_getegid(),
         SYS_GETRESUID => sys_getresuid(ctx.args[0], ctx.args[1], ctx.args[2]),
         SYS_GETRESGID => sys_getresgid(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_SETUID => sys_setuid(ctx.args[0]),
+        SYS_SETGID => sys_setgid(ctx.args[0]),
+        SYS_SETREUID => sys_setreuid(ctx.args[0], ctx.args[1]),
+        SYS_SETREGID => sys_setregid(ctx.args[0], ctx.args[1]),
+        SYS_SETRESUID => sys_setresuid(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_SETRESGID => sys_setresgid(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_SETFSUID => sys_setfsuid(ctx.args[0]),
+        SYS_SETFSGID => sys_setfsgid(ctx.args[0]),
         SYS_GETTID => sys_gettid(),
         SYS_SCHED_YIELD => sys_sched_yield(),
         SYS_SET_TID_ADDRESS => sys_set_tid_address(ctx.args[0]),
@@ -212,6 +289,7 @@ fn dispatch(tf: &mut TrapFrame, ctx: SyscallContext) -> Result<usize, Errno> {
         SYS_RT_SIGACTION => sys_rt_sigaction(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_RT_SIGPROCMASK => sys_rt_sigprocmask(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_FCNTL => sys_fcntl(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_FLOCK => sys_flock(ctx.args[0], ctx.args[1]),
         SYS_UMASK => sys_umask(ctx.args[0]),
         SYS_PRCTL => sys_prctl(ctx.args[0], ctx.args[1]),
         SYS_SCHED_SETAFFINITY => sys_sched_setaffinity(ctx.args[0], ctx.args[1], ctx.args[2]),
@@ -228,6 +306,7 @@ fn dispatch(tf: &mut TrapFrame, ctx: SyscallContext) -> Result<usize, Errno> {
         SYS_GETCPU => sys_getcpu(ctx.args[0], ctx.args[1]),
         SYS_WAIT4 => sys_wait4(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_SOCKET => sys_socket(ctx.args[0], ctx.args[1], ctx.args[2]),
+        SYS_SOCKETPAIR => sys_socketpair(ctx.args[0], ctx.args[1], ctx.args[2], ctx.args[3]),
         SYS_BIND => sys_bind(ctx.args[0], ctx.args[1], ctx.args[2]),
         SYS_GETSOCKNAME => sys_getsockname(ctx.args[0], ctx.args[1], ctx.args[2]),
         SYS_GETPEERNAME => sys_getpeername(ctx.args[0], ctx.args[1], ctx.args[2]),
@@ -260,6 +339,9 @@ const SYS_MPROTECT: usize = 226;
 const SYS_MADVISE: usize = 233;
 const SYS_RSEQ: usize = 293;
 const SYS_EVENTFD2: usize = 19;
+const SYS_INOTIFY_INIT1: usize = 26;
+const SYS_INOTIFY_ADD_WATCH: usize = 27;
+const SYS_INOTIFY_RM_WATCH: usize = 28;
 const SYS_EPOLL_CREATE1: usize = 20;
 const SYS_EPOLL_CTL: usize = 21;
 const SYS_EPOLL_PWAIT: usize = 22;
@@ -296,11 +378,27 @@ const SYS_STATX: usize = 291;
 const SYS_STATFS: usize = 43;
 const SYS_FSTATFS: usize = 44;
 const SYS_FTRUNCATE: usize = 46;
+const SYS_FALLOCATE: usize = 47;
+const SYS_SENDFILE: usize = 71;
+const SYS_SPLICE: usize = 76;
+const SYS_TEE: usize = 77;
+const SYS_COPY_FILE_RANGE: usize = 285;
+const SYS_SETXATTR: usize = 5;
+const SYS_LSETXATTR: usize = 6;
+const SYS_FSETXATTR: usize = 7;
+const SYS_GETXATTR: usize = 8;
+const SYS_LGETXATTR: usize = 9;
+const SYS_FGETXATTR: usize = 10;
+const SYS_LISTXATTR: usize = 11;
+const SYS_LLISTXATTR: usize = 12;
+const SYS_FLISTXATTR: usize = 13;
+const SYS_REMOVEXATTR: usize = 14;
+const SYS_LREMOVEXATTR: usize = 15;
+const SYS_FREMOVEXATTR: usize = 16;
 const SYS_FCHMODAT: usize = 53;
 const SYS_FCHOWNAT: usize = 54;
 const SYS_UTIMENSAT: usize = 88;
 const SYS_RENAMEAT2: usize = 276;
-const SYS_POLL: usize = 7;
 const SYS_PPOLL: usize = 73;
 const SYS_PPOLL_TIME64: usize = 414;
 const SYS_GETCWD: usize = 17;
@@ -320,9 +418,11 @@ const SYS_GET_ROBUST_LIST: usize = 100;
 const SYS_RT_SIGACTION: usize = 134;
 const SYS_RT_SIGPROCMASK: usize = 135;
 const SYS_FCNTL: usize = 25;
+const SYS_FLOCK: usize = 32;
 const SYS_UMASK: usize = 166;
 const SYS_PRCTL: usize = 167;
 const SYS_SOCKET: usize = 198;
+const SYS_SOCKETPAIR: usize = 199;
 const SYS_BIND: usize = 200;
 const SYS_GETSOCKNAME: usize = 204;
 const SYS_GETPEERNAME: usize = 205;
@@ -363,6 +463,34 @@ const TCSETSF: usize = 0x5404;
 const TIOCGPGRP: usize = 0x540f;
 const TIOCSPGRP: usize = 0x5410;
 const TIOCSCTTY: usize = 0x540e;
+const FS_IOC_FIEMAP: usize = 0xc020_660b;
+const FIONREAD: usize = 0x541b;
+const SIOCGIFCONF: usize = 0x8912;
+const SIOCGIFFLAGS: usize = 0x8913;
+const SIOCSIFFLAGS: usize = 0x8914;
+const SIOCGIFADDR: usize = 0x8915;
+const SIOCSIFADDR: usize = 0x8916;
+const SIOCGIFBRDADDR: usize = 0x8919;
+const SIOCGIFNETMASK: usize = 0x891b;
+const SIOCSIFNETMASK: usize = 0x891c;
+const SIOCGIFMTU: usize = 0x8921;
+const SIOCGIFHWADDR: usize = 0x8927;
+const SIOCGIFINDEX: usize = 0x8933;
+const IFNAMSIZ: usize = 16;
+const ARPHRD_ETHER: u16 = 1;
+const ARPHRD_LOOPBACK: u16 = 772;
+const FIEMAP_FLAG_SYNC: u32 = 0x1;
+const FIEMAP_EXTENT_LAST: u32 = 0x1;
+const FIEMAP_EXTENT_UNWRITTEN: u32 = 0x800;
+const FALLOC_FL_KEEP_SIZE: usize = 0x1;
+const FALLOC_FL_PUNCH_HOLE: usize = 0x2;
+const FALLOC_FL_ZERO_RANGE: usize = 0x10;
+const XATTR_CREATE: usize = 0x1;
+const XATTR_REPLACE: usize = 0x2;
+const XATTR_NAME_MAX: usize = 255;
+const XATTR_SIZE_MAX: usize = 65536;
+/// Kernel bounce buffer for xattr values and name lists (one ext4 block).
+const XATTR_VALUE_BUF: usize = 4096;
 const SYS_CLOCK_GETTIME: usize = 113;
 const SYS_CLOCK_GETTIME64: usize = 403;
 const SYS_CLOCK_GETRES: usize = 114;
@@ -377,6 +505,14 @@ const SYS_GETGID: usize = 176;
 const SYS_GETEGID: usize = 177;
 const SYS_GETRESUID: usize = 148;
 const SYS_GETRESGID: usize = 150;
+const SYS_SETREGID: usize = 143;
+const SYS_SETGID: usize = 144;
+const SYS_SETREUID: usize = 145;
+const SYS_SETUID: usize = 146;
+const SYS_SETRESUID: usize = 147;
+const SYS_SETRESGID: usize = 149;
+const SYS_SETFSUID: usize = 151;
+const SYS_SETFSGID: usize = 152;
 const SYS_GETTID: usize = 178;
 const SYS_SYSINFO: usize = 179;
 const SYS_SCHED_YIELD: usize = 124;
@@ -398,10 +534,15 @@ const MAP_SHARED: usize = 0x01;
 const MAP_PRIVATE: usize = 0x02;
 const MAP_FIXED: usize = 0x10;
 const MAP_ANON: usize = 0x20;
+const S_IFMT: u32 = 0o170000;
 const S_IFCHR: u32 = 0o020000;
 const S_IFBLK: u32 = 0o060000;
 const S_IFDIR: u32 = 0o040000;
 const S_IFREG: u32 = 0o100000;
+const S_ISUID: u16 = 0o4000;
+const S_ISGID: u16 = 0o2000;
+const S_ISVTX: u16 = 0o1000;
+const AT_EACCESS: usize = 0x200;
 const O_CLOEXEC: usize = 0x80000;
 const O_NONBLOCK: usize = 0x800;
 const O_CREAT: usize = 0x40;
@@ -414,9 +555,38 @@ const O_RDWR: usize = 2;
 const O_ACCMODE: usize = 3;
 const SOL_SOCKET: usize = 1;
 const SO_REUSEADDR: usize = 2;
+const SO_TYPE: usize = 3;
 const SO_ERROR: usize = 4;
+const SO_BROADCAST: usize = 6;
+const SO_SNDBUF: usize = 7;
+const SO_RCVBUF: usize = 8;
+const SO_KEEPALIVE: usize = 9;
+const SO_LINGER: usize = 13;
+const SO_REUSEPORT: usize = 15;
+const SO_PASSCRED: usize = 16;
+const SO_PEERCRED: usize = 17;
 const SO_RCVTIMEO: usize = 20;
 const SO_SNDTIMEO: usize = 21;
+const IPPROTO_IP: usize = 0;
+const IP_TTL: usize = 2;
+const IP_HDRINCL: usize = 3;
+const IP_ADD_MEMBERSHIP: usize = 35;
+const IP_DROP_MEMBERSHIP: usize = 36;
+/// TTL reported by `IP_TTL` until it is set, smoltcp's default hop limit.
+const IP_DEFAULT_TTL: u8 = 64;
+const IPPROTO_TCP: usize = 6;
+const TCP_NODELAY: usize = 1;
+const TCP_KEEPIDLE: usize = 4;
+const TCP_KEEPINTVL: usize = 5;
+const TCP_KEEPCNT: usize = 6;
+const TCP_QUICKACK: usize = 12;
+const TCP_USER_TIMEOUT: usize = 18;
+const TCP_MAX_KEEPIDLE: i32 = 32767;
+const TCP_MAX_KEEPINTVL: i32 = 32767;
+const TCP_MAX_KEEPCNT: i32 = 127;
+const IPPROTO_IPV6: usize = 41;
+const IPV6_UNICAST_HOPS: usize = 16;
+const IPV6_V6ONLY: usize = 26;
 const SHUT_RD: usize = 0;
 const SHUT_WR: usize = 1;
 const SHUT_RDWR: usize = 2;
@@ -424,8 +594,12 @@ const AT_FDCWD: isize = -100;
 const AT_SYMLINK_NOFOLLOW: usize = 0x100;
 const AT_SYMLINK_FOLLOW: usize = 0x400;
 const AT_EMPTY_PATH: usize = 0x1000;
+const AT_REMOVEDIR: usize = 0x200;
 const FD_TABLE_BASE: usize = 3;
-const FD_TABLE_SLOTS: usize = 16;
+/// Descriptors queued in unix socket messages but not yet received, across all sockets.
+const UNIX_INFLIGHT_SLOTS: usize = 32;
+// 每进程 256 个 fd，足以让一个服务进程同时持有数百个连接。
+const FD_TABLE_SLOTS: usize = 253;
 const MAX_PROCS: usize = crate::config::MAX_TASKS;
 const PIPE_SLOTS: usize = 8;
 const PIPE_BUFFER_SIZE: usize = 512;
@@ -434,6 +608,8 @@ const TIMERFD_SLOTS: usize = 16;
 const EPOLL_SLOTS: usize = 16;
 const EPOLL_ITEM_SLOTS: usize = 64;
 const MAX_PATH_LEN: usize = 128;
+const MAX_CMDLINE_LEN: usize = 256;
+const OPEN_FILE_SLOTS: usize = 256;
 const VFS_MOUNT_COUNT: usize = 3;
 const SIG_BLOCK: usize = 0;
 const SIG_UNBLOCK: usize = 1;
@@ -443,9 +619,24 @@ const F_SETFD: usize = 2;
 const FD_CLOEXEC: usize = 1;
 const F_GETFL: usize = 3;
 const F_SETFL: usize = 4;
+const F_GETLK: usize = 5;
+const F_SETLK: usize = 6;
+const F_SETLKW: usize = 7;
+const F_OFD_GETLK: usize = 36;
+const F_OFD_SETLK: usize = 37;
+const F_OFD_SETLKW: usize = 38;
+const F_RDLCK: i16 = 0;
+const F_WRLCK: i16 = 1;
+const F_UNLCK: i16 = 2;
+const LOCK_SH: usize = 1;
+const LOCK_EX: usize = 2;
+const LOCK_NB: usize = 4;
+const LOCK_UN: usize = 8;
 const SEEK_SET: usize = 0;
 const SEEK_CUR: usize = 1;
 const SEEK_END: usize = 2;
+const SEEK_DATA: usize = 3;
+const SEEK_HOLE: usize = 4;
 const EPOLL_CTL_ADD: usize = 1;
 const EPOLL_CTL_DEL: usize = 2;
 const EPOLL_CTL_MOD: usize = 3;
@@ -460,6 +651,16 @@ const EFD_CLOEXEC: usize = 0x80000;
 const TFD_NONBLOCK: usize = 0x800;
 const TFD_CLOEXEC: usize = 0x80000;
 const TFD_TIMER_ABSTIME: usize = 0x1;
+const IN_NONBLOCK: usize = 0x800;
+const SPLICE_F_MOVE: usize = 0x1;
+const SPLICE_F_NONBLOCK: usize = 0x2;
+const SPLICE_F_MORE: usize = 0x4;
+const SPLICE_F_GIFT: usize = 0x8;
+/// Linux caps a single read/write style transfer at this many bytes.
+const MAX_RW_COUNT: usize = 0x7fff_f000;
+/// Bounce buffer for sendfile between non-pipe ends: one filesystem block.
+const TRANSFER_CHUNK: usize = 4096;
+const IN_CLOEXEC: usize = 0x80000;
 const POLLIN: u16 = 0x001;
 const POLLOUT: u16 = 0x004;
 const POLLERR: u16 = 0x008;
@@ -475,6 +676,7 @@ const RUSAGE_SELF: isize = 0;
 const RUSAGE_CHILDREN: isize = -1;
 const RUSAGE_THREAD: isize = 1;
 const S_IFIFO: u32 = 0o010000;
+const S_IFSOCK: u32 = 0o140000;
 
 static RNG_STATE: AtomicU64 = AtomicU64::new(0);
 const DEFAULT_PRCTL_NAME: [u8; 16] = *b"aurora\0\0\0\0\0\0\0\0\0\0";
@@ -599,6 +801,12 @@ struct Stat {
     __unused5: u32,
 }
 
+// statfs.f_flags 的 ST_VALID 位；其余 ST_* 来自挂载表。
+const ST_VALID: u32 = 0x0020;
+const PIPEFS_MAGIC: u64 = 0x5049_5045;
+const SOCKFS_MAGIC: u64 = 0x534f_434b;
+const ANON_INODE_FS_MAGIC: u64 = 0x0904_1934;
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct Statfs {
@@ -658,6 +866,8 @@ struct VfsHandle {
     mount: MountId,
     inode: InodeId,
     file_type: FileType,
+    /// Identity of the open file description, shared by dup and fork; owns OFD and flock locks.
+    open_id: u32,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -669,10 +879,15 @@ enum FdObject {
     Vfs(VfsHandle),
     PipeRead(usize),
     PipeWrite(usize),
+    /// FIFO opened with O_RDWR: holds both a reader and a writer reference.
+    PipeRdWr(usize),
     Socket(axnet::SocketId),
     Eventfd(usize),
     Timerfd(usize),
     Epoll(usize),
+    Inotify(usize),
+    /// AF_UNIX endpoint in `unix_socket`.
+    Unix(usize),
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -687,8 +902,13 @@ struct FdEntry {
 #[derive(Clone, Copy)]
 struct Pipe {
     used: bool,
+    /// Backing (mount, inode) for named pipes created by mknod.
+    fifo: Option<(MountId, InodeId)>,
     readers: usize,
     writers: usize,
+    /// Monotonic open counters used by FIFO open-blocking.
+    reader_opens: usize,
+    writer_opens: usize,
     read_pos: usize,
     write_pos: usize,
     len: usize,
@@ -730,8 +950,11 @@ struct EpollInstance {
 
 const EMPTY_PIPE: Pipe = Pipe {
     used: false,
+    fifo: None,
     readers: 0,
     writers: 0,
+    reader_opens: 0,
+    writer_opens: 0,
     read_pos: 0,
     write_pos: 0,
     len: 0,
@@ -767,6 +990,36 @@ const EMPTY_EPOLL: EpollInstance = EpollInstance {
     items: [EMPTY_EPOLL_ITEM; EPOLL_ITEM_SLOTS],
 };
 
+/// Name an open file description was opened under.
+#[derive(Clone, Copy)]
+struct OpenFileName {
+    /// Open file description the name belongs to; 0 marks a free slot.
+    open_id: u32,
+    /// Directory holding the name, cleared once the name is unlinked.
+    parent: Option<(MountId, InodeId)>,
+    len: usize,
+    path: [u8; MAX_PATH_LEN],
+}
+
+const EMPTY_OPEN_FILE_NAME: OpenFileName = OpenFileName {
+    open_id: 0,
+    parent: None,
+    len: 0,
+    path: [0; MAX_PATH_LEN],
+};
+
+impl OpenFileName {
+    fn path(&self) -> &str {
+        core::str::from_utf8(&self.path[..self.len]).unwrap_or("")
+    }
+
+    /// Last component of the path, the name parent-directory events carry.
+    fn name(&self) -> &str {
+        let path = self.path().trim_end_matches('/');
+        path.rsplit('/').next().unwrap_or(path)
+    }
+}
+
 const EMPTY_FD_ENTRY: FdEntry = FdEntry {
     object: FdObject::Empty,
     flags: 0,
@@ -775,10 +1028,13 @@ const EMPTY_FD_ENTRY: FdEntry = FdEntry {
     send_timeout_ms: 0,
 };
 
+static NEXT_OPEN_ID: AtomicU32 = AtomicU32::new(1);
 // SAFETY: 单核早期阶段，fd 表按进程索引串行访问。
 static mut FD_TABLES: [[FdEntry; FD_TABLE_SLOTS]; MAX_PROCS] = [[EMPTY_FD_ENTRY; FD_TABLE_SLOTS]; MAX_PROCS];
 // SAFETY: 仅用于重定向标准 fd，单核阶段按进程顺序访问。
 static mut STDIO_REDIRECT: [[Option<FdEntry>; 3]; MAX_PROCS] = [[None; 3]; MAX_PROCS];
+// SAFETY: SCM_RIGHTS 在途描述符在单核阶段串行访问，每项持有一份对象引用。
+static mut UNIX_INFLIGHT: [FdEntry; UNIX_INFLIGHT_SLOTS] = [EMPTY_FD_ENTRY; UNIX_INFLIGHT_SLOTS];
 // SAFETY: 标准 fd 的状态标志在单核阶段按进程顺序访问。
 static mut STDIO_FLAGS: [[usize; 3]; MAX_PROCS] = [[0; 3]; MAX_PROCS];
 // SAFETY: 当前工作目录缓存按进程顺序访问。
@@ -787,6 +1043,14 @@ static mut PROC_CWD: [[u8; MAX_PATH_LEN]; MAX_PROCS] = [[0; MAX_PATH_LEN]; MAX_P
 static mut PROC_CWD_LEN: [usize; MAX_PROCS] = [0; MAX_PROCS];
 // SAFETY: umask 按进程顺序访问。
 static mut PROC_UMASK: [u16; MAX_PROCS] = [0; MAX_PROCS];
+// SAFETY: execve 目标路径按进程顺序访问，供 /proc/<pid>/exe 使用。
+static mut PROC_EXE: [[u8; MAX_PATH_LEN]; MAX_PROCS] = [[0; MAX_PATH_LEN]; MAX_PROCS];
+static mut PROC_EXE_LEN: [usize; MAX_PROCS] = [0; MAX_PROCS];
+// SAFETY: execve 参数（NUL 分隔）按进程顺序访问，供 /proc/<pid>/cmdline 使用。
+static mut PROC_CMDLINE: [[u8; MAX_CMDLINE_LEN]; MAX_PROCS] = [[0; MAX_CMDLINE_LEN]; MAX_PROCS];
+static mut PROC_CMDLINE_LEN: [usize; MAX_PROCS] = [0; MAX_PROCS];
+// SAFETY: openat 记录的打开文件名（按 open_id），单核阶段顺序访问。
+static mut OPEN_FILE_NAMES: [OpenFileName; OPEN_FILE_SLOTS] = [EMPTY_OPEN_FILE_NAME; OPEN_FILE_SLOTS];
 // SAFETY: 控制台输入缓存仅在单核阶段顺序访问。
 static mut CONSOLE_STASH: i16 = -1;
 // SAFETY: pipe 表在早期阶段串行访问。
@@ -868,6 +1132,38 @@ struct Winsize {
     ws_ypixel: u16,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Flock {
+    l_type: i16,
+    l_whence: i16,
+    l_start: i64,
+    l_len: i64,
+    l_pid: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Fiemap {
+    fm_start: u64,
+    fm_length: u64,
+    fm_flags: u32,
+    fm_mapped_extents: u32,
+    fm_extent_count: u32,
+    fm_reserved: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FiemapExtent {
+    fe_logical: u64,
+    fe_physical: u64,
+    fe_length: u64,
+    fe_reserved64: [u64; 2],
+    fe_flags: u32,
+    fe_reserved: [u32; 3],
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct Termios {
@@ -896,6 +1192,23 @@ struct EpollEvent {
     data: u64,
 }
 
+/// Linux `struct ifreq`: interface name plus a 24-byte request union.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IfReq {
+    ifr_name: [u8; IFNAMSIZ],
+    ifr_data: [u8; 24],
+}
+
+/// Linux `struct ifconf` on LP64: byte length, padding, then the buffer pointer.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IfConf {
+    ifc_len: i32,
+    _pad: u32,
+    ifc_buf: usize,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 struct SockAddrIn {
@@ -905,12 +1218,75 @@ struct SockAddrIn {
     sin_zero: [u8; 8],
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SockAddrIn6 {
+    sin6_family: u16,
+    sin6_port: u16,
+    sin6_flowinfo: u32,
+    sin6_addr: [u8; 16],
+    sin6_scope_id: u32,
+}
+
+/// Linux `struct sockaddr_un`.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SockAddrUn {
+    sun_family: u16,
+    sun_path: [u8; unix_socket::UNIX_PATH_MAX],
+}
+
+/// Linux `struct cmsghdr` on LP64; data follows at the next 8-byte boundary.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct CmsgHdr {
+    cmsg_len: usize,
+    cmsg_level: i32,
+    cmsg_type: i32,
+}
+
+/// Linux `struct ucred` (`SCM_CREDENTIALS`, `SO_PEERCRED`).
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct UCred {
+    pid: i32,
+    uid: u32,
+    gid: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Linger {
+    l_onoff: i32,
+    l_linger: i32,
+}
+
+/// Linux `struct ip_mreq`; the longer `ip_mreqn` starts the same way.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IpMreq {
+    imr_multiaddr: u32,
+    imr_interface: u32,
+}
+
+const AF_UNSPEC: u16 = 0;
+const AF_UNIX: u16 = 1;
 const AF_INET: u16 = 2;
+const AF_INET6: u16 = 10;
 const SOCK_STREAM: usize = 1;
 const SOCK_DGRAM: usize = 2;
+const SOCK_RAW: usize = 3;
+const SOCK_SEQPACKET: usize = 5;
 const SOCK_NONBLOCK: usize = 0x800;
 const SOCK_CLOEXEC: usize = 0x80000;
+const MSG_PEEK: usize = 0x2;
+const MSG_CTRUNC: i32 = 0x8;
+const MSG_TRUNC: usize = 0x20;
 const MSG_DONTWAIT: usize = 0x40;
+const MSG_WAITALL: usize = 0x100;
+const MSG_CMSG_CLOEXEC: usize = 0x4000_0000;
+const SCM_RIGHTS: i32 = 1;
+const SCM_CREDENTIALS: i32 = 2;
 
 fn sys_exit(_code: usize) -> Result<usize, Errno> {
     let pid = crate::process::current_pid().unwrap_or(1);
@@ -945,9 +1321,16 @@ fn sys_execve(tf: &mut TrapFrame, pathname: usize, argv: usize, envp: usize) ->
     validate_user_path(root_pa, pathname)?;
     validate_user_ptr_list(root_pa, argv)?;
     validate_user_ptr_list(root_pa, envp)?;
+    // 在旧地址空间释放前保存路径与参数，供 /proc/<pid>/{exe,cmdline} 使用。
+    let mut exe_buf = [0u8; MAX_PATH_LEN];
+    let exe_len = read_user_path_abs(root_pa, pathname, &mut exe_buf)
+        .map(|path| path.len())
+        .unwrap_or(0);
+    let mut cmdline_buf = [0u8; MAX_CMDLINE_LEN];
+    let cmdline_len = read_user_cmdline(root_pa, argv, &mut cmdline_buf);
     // 通过 VFS 读取目标 ELF 镜像，统一路径与加载链路。
-    let image = match execve_vfs_image(root_pa, pathname) {
-        Ok(image) => image,
+    let (image, setuid, setgid) = match execve_vfs_image(root_pa, pathname) {
+        Ok(exec) => exec,
         Err(err) => {
             if cfg!(feature = "user-tcp-echo") {
                 crate::println!("sys_execve: read image failed ({:?})", err);
@@ -979,6 +1362,10 @@ fn sys_execve(tf: &mut TrapFrame, pathname: usize, argv: usize, envp: usize) ->
         let _ = crate::task::set_heap_top(task_id, ctx.heap_top);
     }
     let _ = crate::process::update_current_root(ctx.root_pa);
+    if let Some(idx) = current_proc_index() {
+        set_proc_exec_info(idx, &exe_buf[..exe_len], &cmdline_buf[..cmdline_len]);
+        cred::apply_exec(idx, setuid, setgid);
+    }
     close_cloexec_fds();
     if ctx.root_pa != root_pa {
         crate::mm::release_user_root(root_pa);
@@ -1189,6 +1576,66 @@ fn sys_eventfd2(initval: usize, flags: usize) -> Result<usize, Errno> {
     Ok(fd)
 }
 
+fn sys_inotify_init1(flags: usize) -> Result<usize, Errno> {
+    if flags & !(IN_NONBLOCK | IN_CLOEXEC) != 0 {
+        return Err(Errno::Inval);
+    }
+    let id = inotify::alloc().ok_or(Errno::MFile)?;
+    let mut status_flags = 0;
+    if (flags & IN_NONBLOCK) != 0 {
+        status_flags |= O_NONBLOCK;
+    }
+    if (flags & IN_CLOEXEC) != 0 {
+        status_flags |= O_CLOEXEC;
+    }
+    let entry = FdEntry {
+        object: FdObject::Inotify(id),
+        flags: status_flags,
+        offset: 0,
+        recv_timeout_ms: 0,
+        send_timeout_ms: 0,
+    };
+    alloc_fd(entry).ok_or_else(|| {
+        inotify::release(id);
+        Errno::MFile
+    })
+}
+
+fn sys_inotify_add_watch(fd: usize, pathname: usize, mask: usize) -> Result<usize, Errno> {
+    let id = resolve_inotify_fd(fd)?;
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let mask = mask as u32;
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = read_user_path_abs(root_pa, pathname, &mut path_buf)?;
+    let (mount, inode, file_type) = with_mounts(|mounts| {
+        let (mount, inode) = mounts.resolve_path(path).map_err(map_vfs_err)?;
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        Ok((mount, inode, meta.file_type))
+    })?;
+    if (mask & inotify::IN_ONLYDIR) != 0 && file_type != FileType::Dir {
+        return Err(Errno::NotDir);
+    }
+    let wd = inotify::add_watch(id, (mount, inode), mask).map_err(map_inotify_err)?;
+    Ok(wd as usize)
+}
+
+fn sys_inotify_rm_watch(fd: usize, wd: usize) -> Result<usize, Errno> {
+    let id = resolve_inotify_fd(fd)?;
+    inotify::rm_watch(id, wd as i32).map_err(map_inotify_err)?;
+    Ok(0)
+}
+
+fn resolve_inotify_fd(fd: usize) -> Result<usize, Errno> {
+    match resolve_fd(fd).ok_or(Errno::Badf)?.object {
+        FdObject::Inotify(id) => Ok(id),
+        _ => Err(Errno::Inval),
+    }
+}
+
 fn sys_epoll_create1(flags: usize) -> Result<usize, Errno> {
     if flags & !EPOLL_CLOEXEC != 0 {
         return Err(Errno::Inval);
@@ -1407,9 +1854,25 @@ pub fn prepare_user_init() -> Option<crate::user::UserContext> {
     crate::user::load_exec_elf(0, image, 0, 0).ok()
 }
 
-fn execve_vfs_image(root_pa: usize, pathname: usize) -> Result<&'static [u8], Errno> {
+/// Exec image plus the set-user-ID/set-group-ID owners the process adopts on success.
+type ExecImage = (&'static [u8], Option<u32>, Option<u32>);
+
+fn execve_vfs_image(root_pa: usize, pathname: usize) -> Result<ExecImage, Errno> {
     let (mount, inode) = vfs_lookup_inode(root_pa, pathname)?;
-    vfs_read_inode_image(mount, inode)
+    let meta = with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        fs.metadata(inode).map_err(map_vfs_err)
+    })?;
+    if meta.file_type != FileType::File || mount_flags(mount) & ST_NOEXEC != 0 {
+        return Err(Errno::Acces);
+    }
+    check_access(&meta, ACCESS_EXEC)?;
+    let image = vfs_read_inode_image(mount, inode)?;
+    // setgid 仅在组执行位置位时生效；无组执行位的 setgid 表示强制锁。nosuid 挂载忽略两者。
+    let nosuid = mount_flags(mount) & ST_NOSUID != 0;
+    let uid = (!nosuid && meta.mode & S_ISUID != 0).then_some(meta.uid);
+    let gid = (!nosuid && meta.mode & S_ISGID != 0 && meta.mode & 0o010 != 0).then_some(meta.gid);
+    Ok((image, uid, gid))
 }
 
 fn sys_clone(
@@ -1511,10 +1974,13 @@ fn sys_pread64(fd: usize, buf: usize, len: usize, offset: usize) -> Result<usize
         | FdObject::Stderr
         | FdObject::PipeRead(_)
         | FdObject::PipeWrite(_)
+        | FdObject::PipeRdWr(_)
         | FdObject::Socket(_)
         | FdObject::Eventfd(_)
         | FdObject::Timerfd(_)
-        | FdObject::Epoll(_) => Err(Errno::Pipe),
+        | FdObject::Epoll(_)
+        | FdObject::Inotify(_)
+        | FdObject::Unix(_) => Err(Errno::Pipe),
         FdObject::Empty => Err(Errno::Badf),
     }
 }
@@ -1533,20 +1999,27 @@ fn sys_pwrite64(fd: usize, buf: usize, len: usize, offset: usize) -> Result<usiz
             if handle.file_type == FileType::Dir {
                 return Err(Errno::IsDir);
             }
-            with_mounts(|mounts| {
+            let written = with_mounts(|mounts| {
                 let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
                 write_vfs_at(root_pa, fs, handle.inode, offset, buf, len)
-            })
+            })?;
+            if written > 0 {
+                fsnotify_file(handle, inotify::IN_MODIFY);
+            }
+            Ok(written)
         }
         FdObject::Stdin
         | FdObject::Stdout
         | FdObject::Stderr
         | FdObject::PipeRead(_)
         | FdObject::PipeWrite(_)
+        | FdObject::PipeRdWr(_)
         | FdObject::Socket(_)
         | FdObject::Eventfd(_)
         | FdObject::Timerfd(_)
-        | FdObject::Epoll(_) => Err(Errno::Pipe),
+        | FdObject::Epoll(_)
+        | FdObject::Inotify(_)
+        | FdObject::Unix(_) => Err(Errno::Pipe),
         FdObject::Empty => Err(Errno::Badf),
     }
 }
@@ -1617,7 +2090,7 @@ fn sys_pwritev(fd: usize, iov_ptr: usize, iovcnt: usize, offset: usize) -> Resul
             if handle.file_type == FileType::Dir {
                 return Err(Errno::IsDir);
             }
-            with_mounts(|mounts| {
+            let total = with_mounts(|mounts| {
                 let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
                 let mut total = 0usize;
                 for index in 0..iovcnt {
@@ -1639,7 +2112,11 @@ fn sys_pwritev(fd: usize, iov_ptr: usize, iovcnt: usize, offset: usize) -> Resul
                     }
                 }
                 Ok(total)
-            })
+            })?;
+            if total > 0 {
+                fsnotify_file(handle, inotify::IN_MODIFY);
+            }
+            Ok(total)
         }
         FdObject::Empty => Err(Errno::Badf),
         _ => Err(Errno::Pipe),
@@ -1797,9 +2274,26 @@ fn sys_pipe2(pipefd: usize, flags: usize) -> Result<usize, Errno> {
 }
 
 fn sys_socket(domain: usize, sock_type: usize, protocol: usize) -> Result<usize, Errno> {
+    if domain == AF_UNIX as usize {
+        let kind = unix_kind(sock_type, protocol)?;
+        let id = unix_socket::alloc(kind, unix_cred()).ok_or(Errno::MFile)?;
+        return alloc_fd(unix_fd_entry(id, sock_type)).ok_or_else(|| {
+            unix_release(id);
+            Errno::MFile
+        });
+    }
     let sock_type_base = sock_type & 0xf;
+    // 原始套接字需要 CAP_NET_RAW，这里以 euid 0 代替；ping socket 不受限。
+    if sock_type_base == SOCK_RAW && cred::current().euid != 0 {
+        return Err(Errno::Perm);
+    }
     let socket_id = axnet::socket_create(domain as i32, sock_type_base as i32, protocol as i32)
-        .map_err(map_net_err)?;
+        .map_err(|err| match err {
+            axnet::NetError::Unsupported if domain == AF_INET as usize || domain == AF_INET6 as usize => {
+                Errno::ProtoNoSupport
+            }
+            err => map_net_err(err),
+        })?;
     let mut flags = 0;
     if (sock_type & SOCK_NONBLOCK) != 0 {
         flags |= O_NONBLOCK;
@@ -1817,30 +2311,72 @@ fn sys_socket(domain: usize, sock_type: usize, protocol: usize) -> Result<usize,
     alloc_fd(entry).ok_or(Errno::MFile)
 }
 
+fn sys_socketpair(domain: usize, sock_type: usize, protocol: usize, sv: usize) -> Result<usize, Errno> {
+    if domain != AF_UNIX as usize {
+        return Err(Errno::OpNotSupp);
+    }
+    let root_pa = mm::current_root_pa();
+    if sv == 0 || root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let kind = unix_kind(sock_type, protocol)?;
+    let (first, second) = unix_socket::pair(kind, unix_cred()).ok_or(Errno::MFile)?;
+    let Some(fd0) = alloc_fd(unix_fd_entry(first, sock_type)) else {
+        unix_release(first);
+        unix_release(second);
+        return Err(Errno::MFile);
+    };
+    let Some(fd1) = alloc_fd(unix_fd_entry(second, sock_type)) else {
+        let _ = close_fd(fd0);
+        unix_release(second);
+        return Err(Errno::MFile);
+    };
+    let fds = [fd0 as i32, fd1 as i32];
+    if UserPtr::new(sv).write(root_pa, fds).is_none() {
+        let _ = close_fd(fd0);
+        let _ = close_fd(fd1);
+        return Err(Errno::Fault);
+    }
+    Ok(0)
+}
+
 fn sys_bind(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
+    if let FdObject::Unix(id) = resolve_fd(fd).ok_or(Errno::Badf)?.object {
+        return unix_bind(root_pa, id, addr, len);
+    }
     let socket_id = resolve_socket_fd(fd)?;
-    let (ip, port) = parse_sockaddr_in(root_pa, addr, len)?;
+    let (ip, port) = parse_sockaddr(root_pa, socket_id, addr, len)?;
     axnet::socket_bind(socket_id, ip, port).map_err(map_net_err)?;
     Ok(0)
 }
 
 fn sys_getsockname(fd: usize, addr: usize, addrlen: usize) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
+    if let FdObject::Unix(id) = resolve_fd(fd).ok_or(Errno::Badf)?.object {
+        let name = unix_socket::name(id).map_err(map_unix_err)?;
+        write_sockaddr_un(root_pa, addr, addrlen, &name)?;
+        return Ok(0);
+    }
     let socket_id = resolve_socket_fd(fd)?;
     let (ip, port) = axnet::socket_local_endpoint(socket_id).map_err(map_net_err)?;
-    write_sockaddr_in(root_pa, addr, addrlen, Some((ip, port)))?;
+    write_sockaddr(root_pa, socket_id, addr, addrlen, Some((ip, port)))?;
     Ok(0)
 }
 
 fn sys_getpeername(fd: usize, addr: usize, addrlen: usize) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
+    if let FdObject::Unix(id) = resolve_fd(fd).ok_or(Errno::Badf)?.object {
+        let name = unix_socket::peer_name(id).map_err(map_unix_err)?;
+        write_sockaddr_un(root_pa, addr, addrlen, &name)?;
+        return Ok(0);
+    }
     let socket_id = resolve_socket_fd(fd)?;
     let endpoint = axnet::socket_remote_endpoint(socket_id).map_err(map_net_err)?;
     let Some((ip, port)) = endpoint else {
         return Err(Errno::NotConn);
     };
-    write_sockaddr_in(root_pa, addr, addrlen, Some((ip, port)))?;
+    write_sockaddr(root_pa, socket_id, addr, addrlen, Some((ip, port)))?;
     Ok(0)
 }
 
@@ -1851,6 +2387,10 @@ fn sys_sync() -> Result<usize, Errno> {
 
 fn sys_connect(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_connect(root_pa, id, &entry, addr, len);
+    }
     let (socket_id, entry) = resolve_socket_entry(fd)?;
     let nonblock = (entry.flags & O_NONBLOCK) != 0;
     let timeout_ms = entry.send_timeout_ms;
@@ -1858,7 +2398,12 @@ fn sys_connect(fd: usize, addr: usize, len: usize) -> Result<usize, Errno> {
     if cfg!(feature = "user-tcp-echo") && TCP_CONNECT_LOGGED.swap(1, Ordering::Relaxed) == 0 {
         crate::println!("sys_connect: fd={} nonblock={}", fd, nonblock);
     }
-    let (ip, port) = parse_sockaddr_in(root_pa, addr, len)?;
+    // AF_UNSPEC 解除 UDP socket 的关联。
+    if len >= size_of::<u16>() && UserPtr::<u16>::new(addr).read(root_pa) == Some(AF_UNSPEC) {
+        axnet::socket_disconnect(socket_id).map_err(map_net_err)?;
+        return Ok(0);
+    }
+    let (ip, port) = parse_sockaddr(root_pa, socket_id, addr, len)?;
     match axnet::socket_connect(socket_id, ip, port) {
         Ok(()) => {}
         Err(axnet::NetError::InProgress) => {
@@ -1961,30 +2506,122 @@ fn sys_setsockopt(
     optlen: usize,
 ) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
-    let _socket_id = resolve_socket_fd(fd)?;
-    if level != SOL_SOCKET {
-        return Err(Errno::Inval);
-    }
-    match optname {
-        SO_REUSEADDR => {
-            if optlen < size_of::<u32>() || optval == 0 {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    let socket_id = match entry.object {
+        FdObject::Socket(socket_id) => Some(socket_id),
+        FdObject::Unix(_) => None,
+        _ => return Err(Errno::Badf),
+    };
+    match (level, optname) {
+        (SOL_SOCKET, SO_RCVTIMEO) => {
+            let timeout_ms = read_timeval_ms(root_pa, optval, optlen)?;
+            return set_socket_timeout(fd, Some(timeout_ms), None);
+        }
+        (SOL_SOCKET, SO_SNDTIMEO) => {
+            let timeout_ms = read_timeval_ms(root_pa, optval, optlen)?;
+            return set_socket_timeout(fd, None, Some(timeout_ms));
+        }
+        (SOL_SOCKET, SO_LINGER) => {
+            if optlen < size_of::<Linger>() || optval == 0 {
                 return Err(Errno::Inval);
             }
-            UserPtr::<u32>::new(optval)
+            let linger = UserPtr::<Linger>::new(optval)
                 .read(root_pa)
                 .ok_or(Errno::Fault)?;
-            Ok(0)
+            let Some(socket_id) = socket_id else {
+                return Ok(0);
+            };
+            let secs = (linger.l_onoff != 0).then_some(linger.l_linger.max(0) as u32);
+            axnet::socket_set_option(socket_id, axnet::SockOpt::Linger(secs)).map_err(map_net_err)?;
+            return Ok(0);
         }
-        SO_RCVTIMEO => {
-            let timeout_ms = read_timeval_ms(root_pa, optval, optlen)?;
-            set_socket_timeout(fd, Some(timeout_ms), None)
+        (IPPROTO_IP, IP_ADD_MEMBERSHIP | IP_DROP_MEMBERSHIP) => {
+            let Some(socket_id) = socket_id else {
+                return Err(Errno::NoProtoOpt);
+            };
+            if optlen < size_of::<IpMreq>() || optval == 0 {
+                return Err(Errno::Inval);
+            }
+            let mreq = UserPtr::<IpMreq>::new(optval)
+                .read(root_pa)
+                .ok_or(Errno::Fault)?;
+            // 只有 eth0 能收发组播，imr_interface 不参与选择。
+            let group = axnet::Ipv4Address::from_bytes(&mreq.imr_multiaddr.to_ne_bytes());
+            if optname == IP_ADD_MEMBERSHIP {
+                axnet::socket_join_multicast(socket_id, group)
+            } else {
+                axnet::socket_leave_multicast(socket_id, group)
+            }
+            .map_err(|err| match err {
+                axnet::NetError::Unsupported => Errno::NoProtoOpt,
+                err => map_net_err(err),
+            })?;
+            return Ok(0);
         }
-        SO_SNDTIMEO => {
-            let timeout_ms = read_timeval_ms(root_pa, optval, optlen)?;
-            set_socket_timeout(fd, None, Some(timeout_ms))
+        _ => {}
+    }
+    if optlen < size_of::<i32>() || optval == 0 {
+        return Err(Errno::Inval);
+    }
+    let value = UserPtr::<i32>::new(optval)
+        .read(root_pa)
+        .ok_or(Errno::Fault)?;
+    let Some(socket_id) = socket_id else {
+        let FdObject::Unix(id) = entry.object else {
+            return Err(Errno::Badf);
+        };
+        return match (level, optname) {
+            (SOL_SOCKET, SO_PASSCRED) => {
+                unix_socket::set_passcred(id, value != 0).map_err(map_unix_err)?;
+                Ok(0)
+            }
+            // 本地套接字的缓冲区与保活都是固定的，接受设置但不改变行为。
+            (SOL_SOCKET, SO_REUSEADDR | SO_REUSEPORT | SO_KEEPALIVE | SO_RCVBUF | SO_SNDBUF) => Ok(0),
+            _ => Err(Errno::NoProtoOpt),
+        };
+    };
+    let opt = match (level, optname) {
+        (IPPROTO_IPV6, IPV6_V6ONLY) => {
+            axnet::socket_set_v6only(socket_id, value != 0).map_err(map_net_err)?;
+            return Ok(0);
         }
-        _ => Err(Errno::Inval),
+        (IPPROTO_IP, IP_HDRINCL) => {
+            axnet::socket_set_hdrincl(socket_id, value != 0).map_err(map_net_err)?;
+            return Ok(0);
+        }
+        (SOL_SOCKET, SO_REUSEADDR) => axnet::SockOpt::ReuseAddr(value != 0),
+        (SOL_SOCKET, SO_REUSEPORT) => axnet::SockOpt::ReusePort(value != 0),
+        (SOL_SOCKET, SO_KEEPALIVE) => axnet::SockOpt::KeepAlive(value != 0),
+        (SOL_SOCKET, SO_BROADCAST) => axnet::SockOpt::Broadcast(value != 0),
+        // 与 Linux 一致，内核记录并报告请求值的两倍。
+        (SOL_SOCKET, SO_RCVBUF) => axnet::SockOpt::RecvBuf((value.max(0) as usize).saturating_mul(2)),
+        (SOL_SOCKET, SO_SNDBUF) => axnet::SockOpt::SendBuf((value.max(0) as usize).saturating_mul(2)),
+        (IPPROTO_TCP, TCP_NODELAY) => axnet::SockOpt::NoDelay(value != 0),
+        (IPPROTO_TCP, TCP_KEEPIDLE) => axnet::SockOpt::KeepIdle(sockopt_range(value, 1, TCP_MAX_KEEPIDLE)?),
+        (IPPROTO_TCP, TCP_KEEPINTVL) => axnet::SockOpt::KeepIntvl(sockopt_range(value, 1, TCP_MAX_KEEPINTVL)?),
+        (IPPROTO_TCP, TCP_KEEPCNT) => axnet::SockOpt::KeepCnt(sockopt_range(value, 1, TCP_MAX_KEEPCNT)?),
+        (IPPROTO_TCP, TCP_QUICKACK) => axnet::SockOpt::QuickAck(value != 0),
+        (IPPROTO_TCP, TCP_USER_TIMEOUT) => axnet::SockOpt::UserTimeout(sockopt_range(value, 0, i32::MAX)?),
+        // -1 恢复默认跳数。
+        (IPPROTO_IP, IP_TTL) | (IPPROTO_IPV6, IPV6_UNICAST_HOPS) if value == -1 => axnet::SockOpt::HopLimit(None),
+        (IPPROTO_IP, IP_TTL) | (IPPROTO_IPV6, IPV6_UNICAST_HOPS) => {
+            axnet::SockOpt::HopLimit(Some(sockopt_range(value, 1, 255)? as u8))
+        }
+        _ => return Err(Errno::NoProtoOpt),
+    };
+    axnet::socket_set_option(socket_id, opt).map_err(|err| match err {
+        axnet::NetError::Unsupported => Errno::NoProtoOpt,
+        err => map_net_err(err),
+    })?;
+    Ok(0)
+}
+
+/// Check an integer option against its valid range, as Linux does with EINVAL.
+fn sockopt_range(value: i32, min: i32, max: i32) -> Result<u32, Errno> {
+    if value < min || value > max {
+        return Err(Errno::Inval);
     }
+    Ok(value as u32)
 }
 
 fn sys_getsockopt(
@@ -1995,81 +2632,156 @@ fn sys_getsockopt(
     optlen: usize,
 ) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
-    let socket_id = resolve_socket_fd(fd)?;
-    if level != SOL_SOCKET {
-        return Err(Errno::Inval);
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if !matches!(entry.object, FdObject::Socket(_) | FdObject::Unix(_)) {
+        return Err(Errno::Badf);
     }
     if optlen == 0 {
         return Err(Errno::Fault);
     }
-    match optname {
-        SO_ERROR => {
-            let len = UserPtr::<u32>::new(optlen)
-                .read(root_pa)
-                .ok_or(Errno::Fault)? as usize;
-            if len < size_of::<u32>() || optval == 0 {
-                return Err(Errno::Inval);
-            }
-            let value = if let Some(err) = axnet::socket_take_error(socket_id).map_err(map_net_err)? {
+    match (level, optname) {
+        (SOL_SOCKET, SO_RCVTIMEO) => {
+            let (recv_ms, _) = socket_timeouts(fd)?;
+            write_timeval_ms(root_pa, optval, optlen, recv_ms)?;
+            return Ok(0);
+        }
+        (SOL_SOCKET, SO_SNDTIMEO) => {
+            let (_, send_ms) = socket_timeouts(fd)?;
+            write_timeval_ms(root_pa, optval, optlen, send_ms)?;
+            return Ok(0);
+        }
+        _ => {}
+    }
+    let socket_id = match entry.object {
+        FdObject::Socket(socket_id) => socket_id,
+        FdObject::Unix(id) => return unix_getsockopt(id, level, optname, root_pa, optval, optlen),
+        _ => return Err(Errno::Badf),
+    };
+    let opts = axnet::socket_options(socket_id).map_err(map_net_err)?;
+    let sock_type = axnet::socket_type(socket_id).map_err(map_net_err)? as usize;
+    if level == IPPROTO_TCP && sock_type != SOCK_STREAM {
+        return Err(Errno::NoProtoOpt);
+    }
+    let value = match (level, optname) {
+        (SOL_SOCKET, SO_LINGER) => {
+            let linger = Linger {
+                l_onoff: opts.linger.is_some() as i32,
+                l_linger: opts.linger.unwrap_or(0) as i32,
+            };
+            return write_sockopt(root_pa, optval, optlen, linger);
+        }
+        (SOL_SOCKET, SO_ERROR) => {
+            if let Some(err) = axnet::socket_take_error(socket_id).map_err(map_net_err)? {
                 map_net_err(err) as i32
             } else if axnet::socket_connecting(socket_id).map_err(map_net_err)? {
                 Errno::InProgress as i32
             } else {
                 0
-            };
-            UserPtr::new(optval)
-                .write(root_pa, value as u32)
-                .ok_or(Errno::Fault)?;
-            UserPtr::new(optlen)
-                .write(root_pa, size_of::<u32>() as u32)
-                .ok_or(Errno::Fault)?;
-            Ok(0)
-        }
-        SO_REUSEADDR => {
-            let len = UserPtr::<u32>::new(optlen)
-                .read(root_pa)
-                .ok_or(Errno::Fault)? as usize;
-            if len < size_of::<u32>() || optval == 0 {
-                return Err(Errno::Inval);
             }
-            UserPtr::new(optval)
-                .write(root_pa, 0u32)
-                .ok_or(Errno::Fault)?;
-            UserPtr::new(optlen)
-                .write(root_pa, size_of::<u32>() as u32)
-                .ok_or(Errno::Fault)?;
-            Ok(0)
-        }
-        SO_RCVTIMEO => {
-            let (recv_ms, _) = socket_timeouts(fd)?;
-            write_timeval_ms(root_pa, optval, optlen, recv_ms)?;
-            Ok(0)
         }
-        SO_SNDTIMEO => {
-            let (_, send_ms) = socket_timeouts(fd)?;
-            write_timeval_ms(root_pa, optval, optlen, send_ms)?;
-            Ok(0)
+        (SOL_SOCKET, SO_TYPE) => sock_type as i32,
+        (SOL_SOCKET, SO_REUSEADDR) => opts.reuseaddr as i32,
+        (SOL_SOCKET, SO_REUSEPORT) => opts.reuseport as i32,
+        (SOL_SOCKET, SO_KEEPALIVE) => opts.keepalive as i32,
+        (SOL_SOCKET, SO_BROADCAST) => opts.broadcast as i32,
+        (SOL_SOCKET, SO_RCVBUF) => opts.rcvbuf as i32,
+        (SOL_SOCKET, SO_SNDBUF) => opts.sndbuf as i32,
+        (IPPROTO_TCP, TCP_NODELAY) => opts.nodelay as i32,
+        (IPPROTO_TCP, TCP_KEEPIDLE) => opts.keepidle as i32,
+        (IPPROTO_TCP, TCP_KEEPINTVL) => opts.keepintvl as i32,
+        (IPPROTO_TCP, TCP_KEEPCNT) => opts.keepcnt as i32,
+        (IPPROTO_TCP, TCP_QUICKACK) => opts.quickack as i32,
+        (IPPROTO_TCP, TCP_USER_TIMEOUT) => opts.user_timeout_ms as i32,
+        (IPPROTO_IP, IP_TTL) | (IPPROTO_IPV6, IPV6_UNICAST_HOPS) => {
+            i32::from(opts.hop_limit.unwrap_or(IP_DEFAULT_TTL))
+        }
+        (IPPROTO_IP, IP_HDRINCL) => axnet::socket_hdrincl(socket_id).map_err(map_net_err)? as i32,
+        (IPPROTO_IPV6, IPV6_V6ONLY) => axnet::socket_v6only(socket_id).map_err(map_net_err)? as i32,
+        _ => return Err(Errno::NoProtoOpt),
+    };
+    write_sockopt(root_pa, optval, optlen, value)
+}
+
+/// getsockopt(2) on an AF_UNIX socket.
+fn unix_getsockopt(
+    id: usize,
+    level: usize,
+    optname: usize,
+    root_pa: usize,
+    optval: usize,
+    optlen: usize,
+) -> Result<usize, Errno> {
+    if level != SOL_SOCKET {
+        return Err(Errno::NoProtoOpt);
+    }
+    let value = match optname {
+        SO_TYPE => match unix_socket::kind(id).map_err(map_unix_err)? {
+            UnixKind::Stream => SOCK_STREAM as i32,
+            UnixKind::Dgram => SOCK_DGRAM as i32,
+            UnixKind::SeqPacket => SOCK_SEQPACKET as i32,
+        },
+        SO_PASSCRED => unix_socket::passcred(id).map_err(map_unix_err)? as i32,
+        SO_PEERCRED => {
+            let cred = unix_socket::peer_cred(id).map_err(map_unix_err)?;
+            let ucred = UCred {
+                pid: cred.pid as i32,
+                uid: cred.uid,
+                gid: cred.gid,
+            };
+            return write_sockopt(root_pa, optval, optlen, ucred);
         }
-        _ => Err(Errno::Inval),
+        SO_RCVBUF | SO_SNDBUF => unix_socket::MAX_MESSAGE as i32,
+        // 本地套接字没有异步连接错误，也不支持地址复用与保活。
+        SO_ERROR | SO_REUSEADDR | SO_REUSEPORT | SO_KEEPALIVE => 0,
+        _ => return Err(Errno::NoProtoOpt),
+    };
+    write_sockopt(root_pa, optval, optlen, value)
+}
+
+/// Store a fixed-size option value for getsockopt; `*optlen` becomes its size.
+fn write_sockopt<T: Copy>(root_pa: usize, optval: usize, optlen: usize, value: T) -> Result<usize, Errno> {
+    let len = UserPtr::<u32>::new(optlen)
+        .read(root_pa)
+        .ok_or(Errno::Fault)? as usize;
+    if len < size_of::<T>() || optval == 0 {
+        return Err(Errno::Inval);
     }
+    UserPtr::new(optval).write(root_pa, value).ok_or(Errno::Fault)?;
+    UserPtr::new(optlen)
+        .write(root_pa, size_of::<T>() as u32)
+        .ok_or(Errno::Fault)?;
+    Ok(0)
 }
 
 fn sys_shutdown(fd: usize, how: usize) -> Result<usize, Errno> {
-    let socket_id = resolve_socket_fd(fd)?;
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
     if how != SHUT_RD && how != SHUT_WR && how != SHUT_RDWR {
         return Err(Errno::Inval);
     }
+    if let FdObject::Unix(id) = entry.object {
+        unix_socket::shutdown(id, how != SHUT_WR, how != SHUT_RD).map_err(map_unix_err)?;
+        return Ok(0);
+    }
+    let socket_id = resolve_socket_fd(fd)?;
     axnet::socket_shutdown(socket_id, how).map_err(map_net_err)?;
     Ok(0)
 }
 
 fn sys_listen(fd: usize, backlog: usize) -> Result<usize, Errno> {
+    if let FdObject::Unix(id) = resolve_fd(fd).ok_or(Errno::Badf)?.object {
+        unix_socket::listen(id, backlog, unix_cred()).map_err(map_unix_err)?;
+        return Ok(0);
+    }
     let socket_id = resolve_socket_fd(fd)?;
     axnet::socket_listen(socket_id, backlog).map_err(map_net_err)?;
     Ok(0)
 }
 
 fn sys_accept(fd: usize, addr: usize, addrlen: usize) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_accept(id, &entry, addr, addrlen);
+    }
     let (socket_id, entry) = resolve_socket_entry(fd)?;
     let nonblock = (entry.flags & O_NONBLOCK) != 0;
     let timeout_ms = entry.recv_timeout_ms;
@@ -2078,8 +2790,7 @@ fn sys_accept(fd: usize, addr: usize, addrlen: usize) -> Result<usize, Errno> {
     }
     loop {
         match axnet::socket_accept(socket_id) {
-            Ok((accepted_id, listener_id, remote)) => {
-                replace_socket_fd(fd, listener_id)?;
+            Ok((accepted_id, remote)) => {
                 let entry = FdEntry {
                     object: FdObject::Socket(accepted_id),
                     flags: entry.flags & O_NONBLOCK,
@@ -2088,7 +2799,7 @@ fn sys_accept(fd: usize, addr: usize, addrlen: usize) -> Result<usize, Errno> {
                     send_timeout_ms: entry.send_timeout_ms,
                 };
                 let newfd = alloc_fd(entry).ok_or(Errno::MFile)?;
-                write_sockaddr_in(mm::current_root_pa(), addr, addrlen, remote)?;
+                write_sockaddr(mm::current_root_pa(), socket_id, addr, addrlen, remote)?;
                 return Ok(newfd);
             }
             Err(axnet::NetError::WouldBlock) => {
@@ -2125,130 +2836,185 @@ fn sys_accept4(fd: usize, addr: usize, addrlen: usize, flags: usize) -> Result<u
     Ok(newfd)
 }
 
-fn sys_sendto(
-    fd: usize,
-    buf: usize,
-    len: usize,
+/// Send `len` bytes of `bufs` on an inet socket, blocking as `entry` and `flags` allow.
+///
+/// A datagram leaves whole or not at all; a stream send goes out in scratch-sized
+/// pieces and returns early once the send buffer fills. MSG_NOSIGNAL needs no
+/// handling: no SIGPIPE is ever raised, a shut-down stream just fails with EPIPE.
+fn net_send(
+    root_pa: usize,
+    socket_id: axnet::SocketId,
+    entry: &FdEntry,
     flags: usize,
-    addr: usize,
-    addrlen: usize,
+    to: Option<(axnet::IpAddress, u16)>,
+    bufs: UserBufs,
+    len: usize,
 ) -> Result<usize, Errno> {
-    let root_pa = mm::current_root_pa();
-    let (socket_id, entry) = resolve_socket_entry(fd)?;
     let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
-    let timeout_ms = entry.send_timeout_ms;
-    let endpoint = if addr != 0 {
-        let (ip, port) = parse_sockaddr_in(root_pa, addr, addrlen)?;
-        Some((ip, port))
-    } else {
-        None
-    };
-    if len == 0 {
+    let stream = axnet::socket_type(socket_id).map_err(map_net_err)? as usize == SOCK_STREAM;
+    let mut scratch = [0u8; axnet::NET_MTU];
+    if stream && len == 0 {
         return Ok(0);
     }
+    if !stream && len > scratch.len() {
+        return Err(Errno::MsgSize);
+    }
     let mut total = 0usize;
-    let mut remaining = len;
-    let mut scratch = [0u8; 512];
-    while remaining > 0 {
-        let chunk = core::cmp::min(remaining, scratch.len());
-        let src = buf.checked_add(total).ok_or(Errno::Fault)?;
-        UserSlice::new(src, chunk)
-            .copy_to_slice(root_pa, &mut scratch[..chunk])
-            .ok_or(Errno::Fault)?;
-        let sent = match axnet::socket_send(socket_id, &scratch[..chunk], endpoint) {
-            Ok(sent) => sent,
-            Err(axnet::NetError::WouldBlock) => {
-                if total > 0 {
-                    break;
-                }
-                if nonblock || !can_block_current() {
-                    return Err(Errno::Again);
-                }
-                if timeout_ms == 0 {
-                    crate::runtime::block_current(crate::runtime::net_wait_queue());
-                } else if crate::runtime::wait_timeout_ms(crate::runtime::net_wait_queue(), timeout_ms)
-                    == crate::wait::WaitResult::Timeout
-                {
-                    return Err(Errno::TimedOut);
+    loop {
+        let chunk = (len - total).min(scratch.len());
+        if !bufs.copy_in(root_pa, total, &mut scratch[..chunk]) {
+            return if total > 0 { Ok(total) } else { Err(Errno::Fault) };
+        }
+        let err = match axnet::socket_send(socket_id, &scratch[..chunk], to) {
+            Ok(sent) => {
+                total += sent;
+                if !stream || sent < chunk || total == len {
+                    return Ok(total);
                 }
                 continue;
             }
-            Err(err) => return Err(map_net_err(err)),
+            Err(axnet::NetError::WouldBlock) if total == 0 && !nonblock && can_block_current() => {
+                match net_wait(entry.send_timeout_ms) {
+                    Ok(()) => continue,
+                    Err(err) => err,
+                }
+            }
+            Err(err) => map_net_err(err),
         };
-        total += sent;
-        if sent < chunk {
-            break;
-        }
-        remaining = remaining.saturating_sub(sent);
+        return if total > 0 { Ok(total) } else { Err(err) };
     }
-    Ok(total)
 }
 
-fn sys_recvfrom(
-    fd: usize,
-    buf: usize,
-    len: usize,
+/// Receive up to `max` bytes into `bufs` from an inet socket, blocking as `entry` and `flags` allow.
+///
+/// A datagram is read whole and truncated to `max`. A stream read gathers what
+/// is queued, or with MSG_WAITALL waits for `max` bytes or end of stream;
+/// MSG_PEEK leaves the data queued and MSG_TRUNC on a stream discards it.
+fn net_recv(
+    root_pa: usize,
+    socket_id: axnet::SocketId,
+    entry: &FdEntry,
     flags: usize,
-    addr: usize,
-    addrlen: usize,
-) -> Result<usize, Errno> {
-    let root_pa = mm::current_root_pa();
-    let (socket_id, entry) = resolve_socket_entry(fd)?;
+    bufs: UserBufs,
+    max: usize,
+) -> Result<axnet::Received, Errno> {
     let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
-    let timeout_ms = entry.recv_timeout_ms;
+    let stream = axnet::socket_type(socket_id).map_err(map_net_err)? as usize == SOCK_STREAM;
+    let peek = (flags & MSG_PEEK) != 0;
+    // 窥视只能看到队首的一段，等不到 max 字节。
+    let waitall = stream && !peek && (flags & MSG_WAITALL) != 0;
+    let discard = stream && (flags & MSG_TRUNC) != 0;
     if cfg!(feature = "user-tcp-echo") && TCP_RECV_LOGGED.swap(1, Ordering::Relaxed) == 0 {
-        crate::println!("sys_recv: fd={} nonblock={}", fd, nonblock);
-    }
-    if len == 0 {
-        return Ok(0);
+        crate::println!("sys_recv: id={} nonblock={}", socket_id, nonblock);
     }
+    let mut scratch = [0u8; axnet::NET_MTU];
     let mut total = 0usize;
-    let mut remaining = len;
-    let mut scratch = [0u8; 512];
-    let mut last_endpoint = None;
-    while remaining > 0 {
-        let chunk = core::cmp::min(remaining, scratch.len());
+    loop {
+        if stream && total == max {
+            break;
+        }
+        let chunk = (max - total).min(scratch.len());
         log_tcp_window_event(socket_id, "pre");
-        let (read, endpoint) = match axnet::socket_recv(socket_id, &mut scratch[..chunk]) {
-            Ok(result) => result,
-            Err(axnet::NetError::WouldBlock) => {
-                if total > 0 {
-                    break;
-                }
-                if nonblock || !can_block_current() {
-                    return Err(Errno::Again);
+        let err = match axnet::socket_recv(socket_id, &mut scratch[..chunk], peek) {
+            Ok(got) => {
+                log_tcp_window_event(socket_id, "post");
+                if !discard && !bufs.copy_out(root_pa, total, &scratch[..got.len]) {
+                    return Err(Errno::Fault);
                 }
-                if cfg!(feature = "user-tcp-echo") && TCP_RECV_LOGGED.swap(2, Ordering::Relaxed) == 1 {
-                    crate::println!("sys_recv: blocking");
+                if !stream {
+                    return Ok(got);
                 }
-                if timeout_ms == 0 {
-                    crate::runtime::block_current(crate::runtime::net_wait_queue());
-                } else if crate::runtime::wait_timeout_ms(crate::runtime::net_wait_queue(), timeout_ms)
-                    == crate::wait::WaitResult::Timeout
-                {
-                    return Err(Errno::TimedOut);
+                total += got.len;
+                // 读走数据后立即轮询，让窗口更新尽快发出。
+                let _ = axnet::poll(crate::time::uptime_ms());
+                if got.len == 0 || peek || (got.len < chunk && !waitall) {
+                    break;
                 }
                 continue;
             }
-            Err(err) => return Err(map_net_err(err)),
+            Err(axnet::NetError::WouldBlock) if total == 0 || waitall => {
+                if nonblock || !can_block_current() {
+                    Errno::Again
+                } else {
+                    if cfg!(feature = "user-tcp-echo") && TCP_RECV_LOGGED.swap(2, Ordering::Relaxed) == 1 {
+                        crate::println!("sys_recv: blocking");
+                    }
+                    match net_wait(entry.recv_timeout_ms) {
+                        Ok(()) => continue,
+                        Err(err) => err,
+                    }
+                }
+            }
+            Err(axnet::NetError::WouldBlock) => break,
+            Err(err) => map_net_err(err),
         };
-        log_tcp_window_event(socket_id, "post");
-        let dst = buf.checked_add(total).ok_or(Errno::Fault)?;
-        UserSlice::new(dst, read)
-            .copy_from_slice(root_pa, &scratch[..read])
-            .ok_or(Errno::Fault)?;
-        total += read;
-        last_endpoint = endpoint;
-        if last_endpoint.is_none() {
-            let _ = axnet::poll(crate::time::uptime_ms());
-        }
-        if read < chunk {
+        if total > 0 {
             break;
         }
-        remaining = remaining.saturating_sub(read);
+        return Err(err);
     }
-    write_sockaddr_in(root_pa, addr, addrlen, last_endpoint)?;
-    Ok(total)
+    Ok(axnet::Received { len: total, msg_len: total, from: None })
+}
+
+/// Sleep on the network wait queue; a zero `timeout_ms` waits without limit.
+fn net_wait(timeout_ms: u64) -> Result<(), Errno> {
+    let queue = crate::runtime::net_wait_queue();
+    if timeout_ms == 0 {
+        crate::runtime::block_current(queue);
+    } else if crate::runtime::wait_timeout_ms(queue, timeout_ms) == crate::wait::WaitResult::Timeout {
+        return Err(Errno::TimedOut);
+    }
+    Ok(())
+}
+
+/// Byte count an inet receive reports: the full datagram length with MSG_TRUNC.
+fn net_recv_len(received: &axnet::Received, flags: usize) -> usize {
+    if (flags & MSG_TRUNC) != 0 {
+        received.msg_len
+    } else {
+        received.len
+    }
+}
+
+fn sys_sendto(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addrlen: usize,
+) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_sendto(id, &entry, buf, len, flags, addr, addrlen);
+    }
+    let root_pa = mm::current_root_pa();
+    let (socket_id, entry) = resolve_socket_entry(fd)?;
+    let endpoint = if addr != 0 {
+        Some(parse_sockaddr(root_pa, socket_id, addr, addrlen)?)
+    } else {
+        None
+    };
+    net_send(root_pa, socket_id, &entry, flags, endpoint, UserBufs::Flat { base: buf, len }, len)
+}
+
+fn sys_recvfrom(
+    fd: usize,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addrlen: usize,
+) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_recvfrom(id, &entry, buf, len, flags, addr, addrlen);
+    }
+    let root_pa = mm::current_root_pa();
+    let (socket_id, entry) = resolve_socket_entry(fd)?;
+    let received = net_recv(root_pa, socket_id, &entry, flags, UserBufs::Flat { base: buf, len }, len)?;
+    write_sockaddr(root_pa, socket_id, addr, addrlen, received.from)?;
+    Ok(net_recv_len(&received, flags))
 }
 
 fn log_tcp_window_event(socket_id: axnet::SocketId, tag: &str) {
@@ -2273,10 +3039,12 @@ fn sendmsg_inner(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
     if msg == 0 {
         return Err(Errno::Fault);
     }
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_sendmsg(id, &entry, msg, flags);
+    }
     let root_pa = mm::current_root_pa();
     let (socket_id, entry) = resolve_socket_entry(fd)?;
-    let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
-    let timeout_ms = entry.send_timeout_ms;
     let hdr = UserPtr::<MsgHdr>::new(msg)
         .read(root_pa)
         .ok_or(Errno::Fault)?;
@@ -2286,68 +3054,29 @@ fn sendmsg_inner(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
     if hdr.msg_control != 0 || hdr.msg_controllen != 0 {
         return Err(Errno::Inval);
     }
-    if hdr.msg_iovlen == 0 {
-        return Ok(0);
-    }
     let endpoint = if hdr.msg_name != 0 {
-        let namelen = hdr.msg_namelen as usize;
-        let (ip, port) = parse_sockaddr_in(root_pa, hdr.msg_name, namelen)?;
-        Some((ip, port))
+        Some(parse_sockaddr(root_pa, socket_id, hdr.msg_name, hdr.msg_namelen as usize)?)
     } else {
         None
     };
-    let mut total = 0usize;
-    let mut scratch = [0u8; 512];
-    for idx in 0..hdr.msg_iovlen {
-        let iov = load_iovec(root_pa, hdr.msg_iov, idx)?;
-        if iov.iov_len == 0 {
-            continue;
-        }
-        let mut remaining = iov.iov_len;
-        while remaining > 0 {
-            let chunk = core::cmp::min(remaining, scratch.len());
-            let src = iov.iov_base.checked_add(iov.iov_len - remaining).ok_or(Errno::Fault)?;
-            UserSlice::new(src, chunk)
-                .copy_to_slice(root_pa, &mut scratch[..chunk])
-                .ok_or(Errno::Fault)?;
-            let sent = match axnet::socket_send(socket_id, &scratch[..chunk], endpoint) {
-                Ok(sent) => sent,
-                Err(axnet::NetError::WouldBlock) => {
-                    if total > 0 {
-                        return Ok(total);
-                    }
-                    if nonblock || !can_block_current() {
-                        return Err(Errno::Again);
-                    }
-                    if timeout_ms == 0 {
-                        crate::runtime::block_current(crate::runtime::net_wait_queue());
-                    } else if crate::runtime::wait_timeout_ms(crate::runtime::net_wait_queue(), timeout_ms)
-                        == crate::wait::WaitResult::Timeout
-                    {
-                        return Err(Errno::TimedOut);
-                    }
-                    continue;
-                }
-                Err(err) => return Err(map_net_err(err)),
-            };
-            total += sent;
-            if sent < chunk {
-                return Ok(total);
-            }
-            remaining = remaining.saturating_sub(sent);
-        }
-    }
-    Ok(total)
+    let bufs = UserBufs::Iov {
+        iov: hdr.msg_iov,
+        count: hdr.msg_iovlen,
+    };
+    let len = bufs.total_len(root_pa)?;
+    net_send(root_pa, socket_id, &entry, flags, endpoint, bufs, len)
 }
 
 fn recvmsg_inner(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
     if msg == 0 {
         return Err(Errno::Fault);
     }
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    if let FdObject::Unix(id) = entry.object {
+        return unix_recvmsg(id, &entry, msg, flags);
+    }
     let root_pa = mm::current_root_pa();
     let (socket_id, entry) = resolve_socket_entry(fd)?;
-    let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
-    let timeout_ms = entry.recv_timeout_ms;
     let mut hdr = UserPtr::<MsgHdr>::new(msg)
         .read(root_pa)
         .ok_or(Errno::Fault)?;
@@ -2357,82 +3086,689 @@ fn recvmsg_inner(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
     if hdr.msg_control != 0 || hdr.msg_controllen != 0 {
         return Err(Errno::Inval);
     }
-    if hdr.msg_iovlen == 0 {
+    let bufs = UserBufs::Iov {
+        iov: hdr.msg_iov,
+        count: hdr.msg_iovlen,
+    };
+    let max = bufs.total_len(root_pa)?;
+    let received = net_recv(root_pa, socket_id, &entry, flags, bufs, max)?;
+    if hdr.msg_name != 0 {
+        hdr.msg_namelen = match received.from {
+            Some((ip, port)) => encode_sockaddr(root_pa, socket_id, hdr.msg_name, hdr.msg_namelen as usize, ip, port)?,
+            None => 0,
+        } as u32;
+    }
+    hdr.msg_flags = if received.msg_len > received.len { MSG_TRUNC as i32 } else { 0 };
+    UserPtr::new(msg).write(root_pa, hdr).ok_or(Errno::Fault)?;
+    Ok(net_recv_len(&received, flags))
+}
+
+fn unix_kind(sock_type: usize, protocol: usize) -> Result<UnixKind, Errno> {
+    if protocol != 0 {
+        return Err(Errno::ProtoNoSupport);
+    }
+    match sock_type & 0xf {
+        SOCK_STREAM => Ok(UnixKind::Stream),
+        SOCK_DGRAM => Ok(UnixKind::Dgram),
+        SOCK_SEQPACKET => Ok(UnixKind::SeqPacket),
+        _ => Err(Errno::Inval),
+    }
+}
+
+fn unix_fd_entry(id: usize, sock_type: usize) -> FdEntry {
+    let mut flags = 0;
+    if (sock_type & SOCK_NONBLOCK) != 0 {
+        flags |= O_NONBLOCK;
+    }
+    if (sock_type & SOCK_CLOEXEC) != 0 {
+        flags |= O_CLOEXEC;
+    }
+    FdEntry {
+        object: FdObject::Unix(id),
+        flags,
+        offset: 0,
+        recv_timeout_ms: 0,
+        send_timeout_ms: 0,
+    }
+}
+
+/// Credentials a unix socket reports for the calling process.
+fn unix_cred() -> UnixCred {
+    let cred = cred::current();
+    UnixCred {
+        pid: current_pid() as u32,
+        uid: cred.euid,
+        gid: cred.egid,
+    }
+}
+
+fn unix_release(id: usize) {
+    unix_socket::release(id, unix_drop_inflight);
+}
+
+/// Read a `sockaddr_un`; a bare family (length 2) yields the unnamed address.
+fn read_sockaddr_un(root_pa: usize, addr: usize, len: usize) -> Result<UnixAddr, Errno> {
+    if addr == 0 || len < size_of::<u16>() || len > size_of::<SockAddrUn>() {
+        return Err(Errno::Inval);
+    }
+    let mut raw = [0u8; size_of::<SockAddrUn>()];
+    UserSlice::new(addr, len)
+        .copy_to_slice(root_pa, &mut raw[..len])
+        .ok_or(Errno::Fault)?;
+    if u16::from_ne_bytes([raw[0], raw[1]]) != AF_UNIX {
+        return Err(Errno::Inval);
+    }
+    UnixAddr::new(&raw[size_of::<u16>()..len]).map_err(map_unix_err)
+}
+
+/// Encode `name` at `addr`, truncated to `cap` bytes; returns the full address length.
+fn encode_sockaddr_un(root_pa: usize, addr: usize, cap: usize, name: &UnixAddr) -> Result<usize, Errno> {
+    let mut raw = [0u8; size_of::<SockAddrUn>()];
+    raw[..size_of::<u16>()].copy_from_slice(&AF_UNIX.to_ne_bytes());
+    let bytes = name.as_bytes();
+    raw[size_of::<u16>()..size_of::<u16>() + bytes.len()].copy_from_slice(bytes);
+    // 路径名按 Linux 习惯带上结尾 NUL（sun_path 放得下时），抽象名按原长度。
+    let terminator = usize::from(!name.is_unnamed() && !name.is_abstract());
+    let len = (size_of::<u16>() + bytes.len() + terminator).min(raw.len());
+    let copy = len.min(cap);
+    UserSlice::new(addr, copy)
+        .copy_from_slice(root_pa, &raw[..copy])
+        .ok_or(Errno::Fault)?;
+    Ok(len)
+}
+
+fn write_sockaddr_un(root_pa: usize, addr: usize, addrlen: usize, name: &UnixAddr) -> Result<(), Errno> {
+    if addr == 0 || addrlen == 0 {
+        return Ok(());
+    }
+    let cap = UserPtr::<u32>::new(addrlen)
+        .read(root_pa)
+        .ok_or(Errno::Fault)? as usize;
+    let len = encode_sockaddr_un(root_pa, addr, cap, name)?;
+    UserPtr::new(addrlen)
+        .write(root_pa, len as u32)
+        .ok_or(Errno::Fault)?;
+    Ok(())
+}
+
+/// Find the socket bound to `name`: abstract names directly, path names through their socket inode.
+fn unix_lookup(name: &UnixAddr) -> Result<usize, Errno> {
+    if name.is_abstract() {
+        return unix_socket::lookup(name, None).ok_or(Errno::ConnRefused);
+    }
+    let raw = name.path().ok_or(Errno::Inval)?;
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = abs_path(raw, &mut path_buf)?;
+    let node = with_mounts(|mounts| {
+        let (mount, inode) = mounts.resolve_path(path).map_err(map_vfs_err)?;
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        if meta.file_type != FileType::Socket {
+            return Err(Errno::ConnRefused);
+        }
+        check_access(&meta, ACCESS_WRITE)?;
+        Ok((mount, inode))
+    })?;
+    unix_socket::lookup(name, Some(node)).ok_or(Errno::ConnRefused)
+}
+
+fn unix_bind(root_pa: usize, id: usize, addr: usize, len: usize) -> Result<usize, Errno> {
+    let name = read_sockaddr_un(root_pa, addr, len)?;
+    if name.is_unnamed() {
+        unix_socket::autobind(id).map_err(map_unix_err)?;
         return Ok(0);
     }
-    let mut total = 0usize;
-    let mut scratch = [0u8; 512];
-    let mut last_endpoint = None;
-    for idx in 0..hdr.msg_iovlen {
-        let iov = load_iovec(root_pa, hdr.msg_iov, idx)?;
-        if iov.iov_len == 0 {
-            continue;
+    let Some(raw) = name.path() else {
+        unix_socket::bind(id, name, None).map_err(map_unix_err)?;
+        return Ok(0);
+    };
+    // 先确认未绑定，避免已命名的套接字留下多余的套接字文件。
+    if !unix_socket::name(id).map_err(map_unix_err)?.is_unnamed() {
+        return Err(Errno::Inval);
+    }
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = abs_path(raw, &mut path_buf)?;
+    let node = match vfs_mknod(path, FileType::Socket, 0o777 & !current_umask(), 0) {
+        Ok(node) => node,
+        Err(Errno::Exist) => return Err(Errno::AddrInUse),
+        Err(err) => return Err(err),
+    };
+    unix_socket::bind(id, name, Some(node)).map_err(map_unix_err)?;
+    Ok(0)
+}
+
+/// Sleep on a unix socket queue, honouring a SO_RCVTIMEO/SO_SNDTIMEO value (0 = forever).
+fn unix_wait(queue: &'static crate::task_wait_queue::TaskWaitQueue, timeout_ms: u64) -> Result<(), Errno> {
+    if timeout_ms == 0 {
+        crate::runtime::block_current(queue);
+    } else if crate::runtime::wait_timeout_ms(queue, timeout_ms) == crate::wait::WaitResult::Timeout {
+        return Err(Errno::TimedOut);
+    }
+    Ok(())
+}
+
+fn unix_connect(root_pa: usize, id: usize, entry: &FdEntry, addr: usize, len: usize) -> Result<usize, Errno> {
+    let name = read_sockaddr_un(root_pa, addr, len)?;
+    if name.is_unnamed() {
+        return Err(Errno::Inval);
+    }
+    let target = unix_lookup(&name)?;
+    loop {
+        match unix_socket::connect(id, target, unix_cred()) {
+            Ok(()) => return Ok(0),
+            Err(UnixError::WouldBlock) => {
+                // backlog 已满：阻塞到监听者 accept 腾出位置。
+                if (entry.flags & O_NONBLOCK) != 0 || !can_block_current() {
+                    return Err(Errno::Again);
+                }
+                unix_wait(unix_socket::queue(target), entry.send_timeout_ms)?;
+            }
+            Err(err) => return Err(map_unix_err(err)),
         }
-        let mut remaining = iov.iov_len;
-        while remaining > 0 {
-            let chunk = core::cmp::min(remaining, scratch.len());
-            let (read, endpoint) = match axnet::socket_recv(socket_id, &mut scratch[..chunk]) {
-                Ok(result) => result,
-                Err(axnet::NetError::WouldBlock) => {
-                    if total > 0 {
-                        return Ok(total);
+    }
+}
+
+fn unix_accept(id: usize, entry: &FdEntry, addr: usize, addrlen: usize) -> Result<usize, Errno> {
+    let server = loop {
+        match unix_socket::accept(id) {
+            Ok(server) => break server,
+            Err(UnixError::WouldBlock) => {
+                if (entry.flags & O_NONBLOCK) != 0 || !can_block_current() {
+                    return Err(Errno::Again);
+                }
+                unix_wait(unix_socket::queue(id), entry.recv_timeout_ms)?;
+            }
+            Err(err) => return Err(map_unix_err(err)),
+        }
+    };
+    let accepted = FdEntry {
+        object: FdObject::Unix(server),
+        flags: entry.flags & O_NONBLOCK,
+        offset: 0,
+        recv_timeout_ms: entry.recv_timeout_ms,
+        send_timeout_ms: entry.send_timeout_ms,
+    };
+    let newfd = alloc_fd(accepted).ok_or_else(|| {
+        unix_release(server);
+        Errno::MFile
+    })?;
+    let peer = unix_socket::peer_name(server).unwrap_or(UnixAddr::UNNAMED);
+    write_sockaddr_un(mm::current_root_pa(), addr, addrlen, &peer)?;
+    Ok(newfd)
+}
+
+/// User memory moved by one socket transfer.
+#[derive(Clone, Copy)]
+enum UserBufs {
+    /// A single buffer (read, write, sendto, recvfrom).
+    Flat { base: usize, len: usize },
+    /// An iovec array (sendmsg, recvmsg).
+    Iov { iov: usize, count: usize },
+}
+
+impl UserBufs {
+    fn total_len(self, root_pa: usize) -> Result<usize, Errno> {
+        match self {
+            Self::Flat { len, .. } => Ok(len),
+            Self::Iov { iov, count } => {
+                let mut total = 0usize;
+                for idx in 0..count {
+                    let vec = load_iovec(root_pa, iov, idx)?;
+                    total = total.checked_add(vec.iov_len).ok_or(Errno::Inval)?;
+                }
+                Ok(total)
+            }
+        }
+    }
+
+    /// Call `copy(user_addr, range)` for the user pieces covering `offset..offset + len`.
+    fn for_each(
+        self,
+        root_pa: usize,
+        offset: usize,
+        len: usize,
+        mut copy: impl FnMut(usize, core::ops::Range<usize>) -> Option<usize>,
+    ) -> bool {
+        match self {
+            Self::Flat { base, .. } => base.checked_add(offset).and_then(|addr| copy(addr, 0..len)).is_some(),
+            Self::Iov { iov, count } => {
+                let mut skip = offset;
+                let mut done = 0usize;
+                for idx in 0..count {
+                    if done == len {
+                        break;
                     }
-                    if nonblock || !can_block_current() {
-                        return Err(Errno::Again);
+                    let Ok(vec) = load_iovec(root_pa, iov, idx) else {
+                        return false;
+                    };
+                    if skip >= vec.iov_len {
+                        skip -= vec.iov_len;
+                        continue;
                     }
-                    if timeout_ms == 0 {
-                        crate::runtime::block_current(crate::runtime::net_wait_queue());
-                    } else if crate::runtime::wait_timeout_ms(crate::runtime::net_wait_queue(), timeout_ms)
-                        == crate::wait::WaitResult::Timeout
-                    {
-                        return Err(Errno::TimedOut);
+                    let take = (vec.iov_len - skip).min(len - done);
+                    let Some(addr) = vec.iov_base.checked_add(skip) else {
+                        return false;
+                    };
+                    if copy(addr, done..done + take).is_none() {
+                        return false;
                     }
-                    continue;
+                    done += take;
+                    skip = 0;
                 }
-                Err(err) => return Err(map_net_err(err)),
-            };
-            let dst = iov
-                .iov_base
-                .checked_add(iov.iov_len - remaining)
-                .ok_or(Errno::Fault)?;
-            UserSlice::new(dst, read)
-                .copy_from_slice(root_pa, &scratch[..read])
-                .ok_or(Errno::Fault)?;
-            total += read;
-            last_endpoint = endpoint;
-            if read < chunk {
-                break;
+                done == len
             }
-            remaining = remaining.saturating_sub(read);
         }
     }
-    if hdr.msg_name != 0 {
-        let Some((ip, port)) = last_endpoint else {
-            return Ok(total);
-        };
-        let namelen = hdr.msg_namelen as usize;
-        if namelen < size_of::<SockAddrIn>() {
+
+    fn copy_in(self, root_pa: usize, offset: usize, dst: &mut [u8]) -> bool {
+        self.for_each(root_pa, offset, dst.len(), |addr, range| {
+            UserSlice::new(addr, range.len()).copy_to_slice(root_pa, &mut dst[range])
+        })
+    }
+
+    fn copy_out(self, root_pa: usize, offset: usize, src: &[u8]) -> bool {
+        self.for_each(root_pa, offset, src.len(), |addr, range| {
+            UserSlice::new(addr, range.len()).copy_from_slice(root_pa, &src[range])
+        })
+    }
+}
+
+/// Ancillary data of one outgoing unix message.
+struct UnixControl {
+    cred: UnixCred,
+    nrights: usize,
+    /// `UNIX_INFLIGHT` handles of the attached descriptors.
+    rights: [u32; unix_socket::SCM_MAX_FD],
+}
+
+impl UnixControl {
+    fn new() -> Self {
+        Self {
+            cred: unix_cred(),
+            nrights: 0,
+            rights: [0; unix_socket::SCM_MAX_FD],
+        }
+    }
+
+    /// Drop descriptors that were never queued.
+    fn discard(&mut self) {
+        for &handle in &self.rights[..self.nrights] {
+            unix_drop_inflight(handle);
+        }
+        self.nrights = 0;
+    }
+}
+
+fn cmsg_align(len: usize) -> usize {
+    (len + size_of::<usize>() - 1) & !(size_of::<usize>() - 1)
+}
+
+/// Parse `SCM_RIGHTS`/`SCM_CREDENTIALS` from a sendmsg control buffer into `out`.
+///
+/// On error the caller still owns (and must discard) the rights parked so far.
+fn unix_parse_control(root_pa: usize, control: usize, controllen: usize, out: &mut UnixControl) -> Result<(), Errno> {
+    let header = size_of::<CmsgHdr>();
+    let mut offset = 0usize;
+    while controllen - offset >= header {
+        let cmsg = UserPtr::<CmsgHdr>::new(control + offset)
+            .read(root_pa)
+            .ok_or(Errno::Fault)?;
+        if cmsg.cmsg_len < header || cmsg.cmsg_len > controllen - offset {
+            return Err(Errno::Inval);
+        }
+        let data = control + offset + header;
+        let data_len = cmsg.cmsg_len - header;
+        if cmsg.cmsg_level != SOL_SOCKET as i32 {
             return Err(Errno::Inval);
         }
-        let sock = SockAddrIn {
-            sin_family: AF_INET,
-            sin_port: port.to_be(),
-            sin_addr: match ip {
-                axnet::IpAddress::Ipv4(addr) => {
-                    let bytes = addr.as_bytes();
-                    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        match cmsg.cmsg_type {
+            SCM_RIGHTS => {
+                let count = data_len / size_of::<i32>();
+                if out.nrights + count > unix_socket::SCM_MAX_FD {
+                    return Err(Errno::TooManyRefs);
                 }
-            },
-            sin_zero: [0; 8],
+                for idx in 0..count {
+                    let fd = UserPtr::<i32>::new(data + idx * size_of::<i32>())
+                        .read(root_pa)
+                        .ok_or(Errno::Fault)?;
+                    out.rights[out.nrights] = unix_hold_fd(fd)?;
+                    out.nrights += 1;
+                }
+            }
+            SCM_CREDENTIALS => {
+                if data_len < size_of::<UCred>() {
+                    return Err(Errno::Inval);
+                }
+                let ucred = UserPtr::<UCred>::new(data).read(root_pa).ok_or(Errno::Fault)?;
+                // 非特权进程只能声明自己的 pid 与真实/有效/保存的 uid、gid。
+                let cred = cred::current();
+                let honest = ucred.pid as usize == current_pid()
+                    && [cred.ruid, cred.euid, cred.suid].contains(&ucred.uid)
+                    && [cred.rgid, cred.egid, cred.sgid].contains(&ucred.gid);
+                if !honest && cred.euid != 0 {
+                    return Err(Errno::Perm);
+                }
+                out.cred = UnixCred {
+                    pid: ucred.pid as u32,
+                    uid: ucred.uid,
+                    gid: ucred.gid,
+                };
+            }
+            _ => return Err(Errno::Inval),
+        }
+        offset = offset.saturating_add(cmsg_align(cmsg.cmsg_len)).min(controllen);
+    }
+    Ok(())
+}
+
+/// Park a reference to descriptor `fd` for SCM_RIGHTS; returns its in-flight handle.
+fn unix_hold_fd(fd: i32) -> Result<u32, Errno> {
+    if fd < 0 {
+        return Err(Errno::Badf);
+    }
+    let mut entry = resolve_fd(fd as usize).ok_or(Errno::Badf)?;
+    entry.flags &= !O_CLOEXEC;
+    // SAFETY: 单核阶段串行访问在途描述符表。
+    let slot = unsafe { UNIX_INFLIGHT.iter().position(|slot| slot.object == FdObject::Empty) };
+    let slot = slot.ok_or(Errno::TooManyRefs)?;
+    // SAFETY: 同上，slot 来自上面的查找。
+    unsafe {
+        UNIX_INFLIGHT[slot] = entry;
+    }
+    pipe_acquire(entry.object);
+    Ok(slot as u32)
+}
+
+fn unix_inflight(handle: u32) -> Option<FdEntry> {
+    // SAFETY: 单核阶段串行访问在途描述符表。
+    let entry = unsafe { *UNIX_INFLIGHT.get(handle as usize)? };
+    (entry.object != FdObject::Empty).then_some(entry)
+}
+
+fn unix_take_inflight(handle: u32) -> Option<FdEntry> {
+    let entry = unix_inflight(handle)?;
+    // SAFETY: 单核阶段串行访问在途描述符表，handle 已在上面校验。
+    unsafe {
+        UNIX_INFLIGHT[handle as usize] = EMPTY_FD_ENTRY;
+    }
+    Some(entry)
+}
+
+/// Drop an in-flight descriptor that will never be received, as if it were closed.
+fn unix_drop_inflight(handle: u32) {
+    if let Some(entry) = unix_take_inflight(handle) {
+        unix_drop_entry(entry);
+    }
+}
+
+fn unix_drop_entry(entry: FdEntry) {
+    pipe_release(entry.object);
+    release_open_file(entry);
+}
+
+/// Drop the descriptors of a message read without a control buffer (read, recvfrom).
+fn unix_discard_rights(received: &unix_socket::Received, flags: usize) {
+    if (flags & MSG_PEEK) == 0 {
+        for &handle in &received.rights[..received.nrights] {
+            unix_drop_inflight(handle);
+        }
+    }
+}
+
+/// Send `len` bytes produced by `fill(offset, dst)`, blocking as `entry` and `flags` allow.
+///
+/// Blocking stream sends loop until everything is queued; rights ride on the
+/// first chunk that goes out.
+fn unix_send(
+    id: usize,
+    entry: &FdEntry,
+    flags: usize,
+    to: Option<usize>,
+    len: usize,
+    control: &mut UnixControl,
+    mut fill: impl FnMut(usize, &mut [u8]) -> bool,
+) -> Result<usize, Errno> {
+    let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
+    let stream = unix_socket::kind(id).map_err(map_unix_err)? == UnixKind::Stream;
+    let mut sent = 0usize;
+    loop {
+        let rights = &control.rights[..control.nrights];
+        let result = unix_socket::send(id, to, len - sent, control.cred, rights, |offset, dst| {
+            fill(sent + offset, dst)
+        });
+        let err = match result {
+            Ok(queued) => {
+                control.nrights = 0;
+                sent += queued;
+                if sent == len || !stream {
+                    return Ok(sent);
+                }
+                continue;
+            }
+            Err(UnixError::WouldBlock) if !nonblock && can_block_current() => {
+                match unix_wait(unix_socket::send_queue(id, to), entry.send_timeout_ms) {
+                    Ok(()) => continue,
+                    Err(err) => err,
+                }
+            }
+            Err(err) => map_unix_err(err),
+        };
+        return if sent > 0 { Ok(sent) } else { Err(err) };
+    }
+}
+
+/// Receive up to `max` bytes into `drain(offset, src)`, blocking as `entry` and `flags` allow.
+fn unix_recv(
+    id: usize,
+    entry: &FdEntry,
+    flags: usize,
+    max: usize,
+    mut drain: impl FnMut(usize, &[u8]) -> bool,
+) -> Result<unix_socket::Received, Errno> {
+    let nonblock = (entry.flags & O_NONBLOCK) != 0 || (flags & MSG_DONTWAIT) != 0;
+    loop {
+        match unix_socket::recv(id, max, (flags & MSG_PEEK) != 0, &mut drain) {
+            Ok(received) => return Ok(received),
+            Err(UnixError::WouldBlock) => {
+                if nonblock || !can_block_current() {
+                    return Err(Errno::Again);
+                }
+                unix_wait(unix_socket::queue(id), entry.recv_timeout_ms)?;
+            }
+            Err(err) => return Err(map_unix_err(err)),
+        }
+    }
+}
+
+/// Byte count a receive reports: the full datagram length with MSG_TRUNC.
+fn unix_recv_len(received: &unix_socket::Received, flags: usize) -> usize {
+    if (flags & MSG_TRUNC) != 0 {
+        received.msg_len
+    } else {
+        received.len
+    }
+}
+
+fn unix_sendto(
+    id: usize,
+    entry: &FdEntry,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addrlen: usize,
+) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    let to = if addr != 0 {
+        Some(unix_lookup(&read_sockaddr_un(root_pa, addr, addrlen)?)?)
+    } else {
+        None
+    };
+    let bufs = UserBufs::Flat { base: buf, len };
+    unix_send(id, entry, flags, to, len, &mut UnixControl::new(), |offset, dst| {
+        bufs.copy_in(root_pa, offset, dst)
+    })
+}
+
+fn unix_recvfrom(
+    id: usize,
+    entry: &FdEntry,
+    buf: usize,
+    len: usize,
+    flags: usize,
+    addr: usize,
+    addrlen: usize,
+) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    let bufs = UserBufs::Flat { base: buf, len };
+    let received = unix_recv(id, entry, flags, len, |offset, src| bufs.copy_out(root_pa, offset, src))?;
+    unix_discard_rights(&received, flags);
+    write_sockaddr_un(root_pa, addr, addrlen, &received.from)?;
+    Ok(unix_recv_len(&received, flags))
+}
+
+fn unix_sendmsg(id: usize, entry: &FdEntry, msg: usize, flags: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    let hdr = UserPtr::<MsgHdr>::new(msg)
+        .read(root_pa)
+        .ok_or(Errno::Fault)?;
+    if hdr.msg_iovlen > IOV_MAX {
+        return Err(Errno::Inval);
+    }
+    let to = if hdr.msg_name != 0 && hdr.msg_namelen != 0 {
+        Some(unix_lookup(&read_sockaddr_un(root_pa, hdr.msg_name, hdr.msg_namelen as usize)?)?)
+    } else {
+        None
+    };
+    let bufs = UserBufs::Iov {
+        iov: hdr.msg_iov,
+        count: hdr.msg_iovlen,
+    };
+    let len = bufs.total_len(root_pa)?;
+    let mut control = UnixControl::new();
+    let result = match unix_parse_control(root_pa, hdr.msg_control, hdr.msg_controllen, &mut control) {
+        Ok(()) => unix_send(id, entry, flags, to, len, &mut control, |offset, dst| {
+            bufs.copy_in(root_pa, offset, dst)
+        }),
+        Err(err) => Err(err),
+    };
+    control.discard();
+    result
+}
+
+fn unix_recvmsg(id: usize, entry: &FdEntry, msg: usize, flags: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    let mut hdr = UserPtr::<MsgHdr>::new(msg)
+        .read(root_pa)
+        .ok_or(Errno::Fault)?;
+    if hdr.msg_iovlen > IOV_MAX {
+        return Err(Errno::Inval);
+    }
+    let bufs = UserBufs::Iov {
+        iov: hdr.msg_iov,
+        count: hdr.msg_iovlen,
+    };
+    let max = bufs.total_len(root_pa)?;
+    let received = unix_recv(id, entry, flags, max, |offset, src| bufs.copy_out(root_pa, offset, src))?;
+    let mut msg_flags = 0i32;
+    if received.msg_len > received.len {
+        msg_flags |= MSG_TRUNC as i32;
+    }
+    let written = unix_put_control(root_pa, &hdr, &received, flags, &mut msg_flags)?;
+    if hdr.msg_name != 0 {
+        hdr.msg_namelen = encode_sockaddr_un(root_pa, hdr.msg_name, hdr.msg_namelen as usize, &received.from)? as u32;
+    }
+    hdr.msg_controllen = written;
+    hdr.msg_flags = msg_flags;
+    UserPtr::new(msg).write(root_pa, hdr).ok_or(Errno::Fault)?;
+    Ok(unix_recv_len(&received, flags))
+}
+
+/// Write `SCM_CREDENTIALS` and `SCM_RIGHTS` into the recvmsg control buffer.
+///
+/// Descriptors that do not fit (or cannot be installed) are closed and
+/// reported with MSG_CTRUNC. Returns the control bytes used.
+fn unix_put_control(
+    root_pa: usize,
+    hdr: &MsgHdr,
+    received: &unix_socket::Received,
+    flags: usize,
+    msg_flags: &mut i32,
+) -> Result<usize, Errno> {
+    let header = size_of::<CmsgHdr>();
+    let peek = (flags & MSG_PEEK) != 0;
+    let control = if hdr.msg_control == 0 { 0 } else { hdr.msg_controllen };
+    let mut written = 0usize;
+    if let Some(cred) = received.cred {
+        let cmsg_len = header + size_of::<UCred>();
+        if control - written >= cmsg_len {
+            let cmsg = CmsgHdr {
+                cmsg_len,
+                cmsg_level: SOL_SOCKET as i32,
+                cmsg_type: SCM_CREDENTIALS,
+            };
+            let ucred = UCred {
+                pid: cred.pid as i32,
+                uid: cred.uid,
+                gid: cred.gid,
+            };
+            UserPtr::new(hdr.msg_control + written).write(root_pa, cmsg).ok_or(Errno::Fault)?;
+            UserPtr::new(hdr.msg_control + written + header)
+                .write(root_pa, ucred)
+                .ok_or(Errno::Fault)?;
+            written += cmsg_align(cmsg_len).min(control - written);
+        } else {
+            *msg_flags |= MSG_CTRUNC;
+        }
+    }
+    if received.nrights == 0 {
+        return Ok(written);
+    }
+    let room = (control - written).saturating_sub(header) / size_of::<i32>();
+    let mut fds = [0i32; unix_socket::SCM_MAX_FD];
+    let mut installed = 0usize;
+    for (idx, &handle) in received.rights[..received.nrights].iter().enumerate() {
+        let entry = if peek { unix_inflight(handle) } else { unix_take_inflight(handle) };
+        let Some(mut entry) = entry else {
+            continue;
         };
-        UserPtr::new(hdr.msg_name)
-            .write(root_pa, sock)
+        if idx < room {
+            if (flags & MSG_CMSG_CLOEXEC) != 0 {
+                entry.flags |= O_CLOEXEC;
+            }
+            if let Some(fd) = alloc_fd(entry) {
+                fds[installed] = fd as i32;
+                installed += 1;
+                if !peek {
+                    // 在途引用转交给新描述符。
+                    pipe_release(entry.object);
+                }
+                continue;
+            }
+        }
+        *msg_flags |= MSG_CTRUNC;
+        if !peek {
+            unix_drop_entry(entry);
+        }
+    }
+    if installed > 0 {
+        let cmsg_len = header + installed * size_of::<i32>();
+        let cmsg = CmsgHdr {
+            cmsg_len,
+            cmsg_level: SOL_SOCKET as i32,
+            cmsg_type: SCM_RIGHTS,
+        };
+        UserPtr::new(hdr.msg_control + written).write(root_pa, cmsg).ok_or(Errno::Fault)?;
+        let mut bytes = [0u8; unix_socket::SCM_MAX_FD * size_of::<i32>()];
+        for (chunk, fd) in bytes.chunks_exact_mut(size_of::<i32>()).zip(&fds[..installed]) {
+            chunk.copy_from_slice(&fd.to_ne_bytes());
+        }
+        UserSlice::new(hdr.msg_control + written + header, installed * size_of::<i32>())
+            .copy_from_slice(root_pa, &bytes[..installed * size_of::<i32>()])
             .ok_or(Errno::Fault)?;
-        hdr.msg_namelen = size_of::<SockAddrIn>() as u32;
-        hdr.msg_flags = 0;
-        UserPtr::new(msg).write(root_pa, hdr).ok_or(Errno::Fault)?;
+        written += cmsg_align(cmsg_len).min(control - written);
     }
-    Ok(total)
+    Ok(written)
 }
 
 fn sys_sendmsg(fd: usize, msg: usize, flags: usize) -> Result<usize, Errno> {
@@ -2540,9 +3876,13 @@ fn sys_openat(_dirfd: usize, pathname: usize, flags: usize, _mode: usize) -> Res
                 }
                 let (mount, parent, name) = mounts.resolve_parent(path).map_err(map_vfs_err)?;
                 let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+                let parent_meta = fs.metadata(parent).map_err(map_vfs_err)?;
+                check_access(&parent_meta, ACCESS_WRITE | ACCESS_EXEC)?;
                 let inode = fs
                     .create(parent, name, FileType::File, create_mode)
                     .map_err(map_vfs_err)?;
+                assign_new_owner(fs, &parent_meta, inode)?;
+                inotify::notify((mount, parent), inotify::IN_CREATE, 0, Some(name));
                 created = true;
                 (mount, inode)
             }
@@ -2553,18 +3893,24 @@ fn sys_openat(_dirfd: usize, pathname: usize, flags: usize, _mode: usize) -> Res
         }
         let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
         let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        // 新建文件按 open(2) 语义不再检查其自身权限位。
+        if !created {
+            check_access(&meta, open_access(flags))?;
+        }
+        let (mount, inode) = match meta.file_type {
+            // mknod 创建的设备节点按设备号转发到 devfs 中的驱动。
+            FileType::Char | FileType::Block => (MountId::Dev, DEVFS.lookup_rdev(meta.rdev).ok_or(Errno::NxIo)?),
+            FileType::Fifo => return open_fifo(mount, inode, flags),
+            FileType::Socket => return Err(Errno::NxIo),
+            _ => (mount, inode),
+        };
         match meta.file_type {
             FileType::Dir => {
                 if accmode != O_RDONLY {
                     return Err(Errno::IsDir);
                 }
             }
-            FileType::Char | FileType::Block => {}
-            FileType::File => {
-                if accmode != O_RDONLY && (meta.mode & 0o222) == 0 {
-                    return Err(Errno::Inval);
-                }
-            }
+            FileType::Char | FileType::Block | FileType::File => {}
             _ => {
                 if accmode == O_WRONLY || accmode == O_RDWR {
                     return Err(Errno::Inval);
@@ -2585,7 +3931,15 @@ fn sys_openat(_dirfd: usize, pathname: usize, flags: usize, _mode: usize) -> Res
             mount,
             inode,
             file_type: meta.file_type,
+            open_id: NEXT_OPEN_ID.fetch_add(1, Ordering::Relaxed),
         };
+        // 记录打开时的父目录与名字，使读写与关闭事件也能投递给父目录上的监视。
+        let parent = mounts.resolve_parent(path).ok().map(|(mount, parent, _)| (mount, parent));
+        record_open_file(handle.open_id, path, parent)?;
+        if (flags & O_TRUNC) != 0 {
+            fsnotify_file(handle, inotify::IN_MODIFY);
+        }
+        fsnotify_file(handle, inotify::IN_OPEN);
         alloc_fd(FdEntry {
             object: FdObject::Vfs(handle),
             flags: status_flags,
@@ -2593,23 +3947,62 @@ fn sys_openat(_dirfd: usize, pathname: usize, flags: usize, _mode: usize) -> Res
             recv_timeout_ms: 0,
             send_timeout_ms: 0,
         })
-        .ok_or(Errno::MFile)
+        .ok_or_else(|| {
+            forget_open_file(handle.open_id);
+            Errno::MFile
+        })
     })
 }
 
-fn sys_mknodat(dirfd: usize, pathname: usize, _mode: usize, _dev: usize) -> Result<usize, Errno> {
-    // 占位实现：仅校验目录 fd 与路径指针，拒绝真实节点创建。
+fn sys_mknodat(dirfd: usize, pathname: usize, mode: usize, dev: usize) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
     validate_at_dirfd(dirfd)?;
     validate_user_path(root_pa, pathname)?;
-    vfs_check_parent(root_pa, pathname)?;
-    match vfs_lookup_inode(root_pa, pathname) {
-        Ok(_) => Err(Errno::Exist),
-        Err(err) => Err(err),
+    let kind = match mode as u32 & S_IFMT {
+        0 | S_IFREG => FileType::File,
+        S_IFIFO => FileType::Fifo,
+        S_IFCHR => FileType::Char,
+        S_IFBLK => FileType::Block,
+        S_IFSOCK => FileType::Socket,
+        S_IFDIR => return Err(Errno::Perm),
+        _ => return Err(Errno::Inval),
+    };
+    // 设备节点需要 CAP_MKNOD，这里以 fsuid 0 代替。
+    if matches!(kind, FileType::Char | FileType::Block) && cred::current().fsuid != 0 {
+        return Err(Errno::Perm);
     }
+    let perm = (mode as u16 & 0o7777) & !current_umask();
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = read_user_path_abs(root_pa, pathname, &mut path_buf)?;
+    vfs_mknod(path, kind, perm, dev as u64)?;
+    Ok(0)
+}
+
+/// Create a special file at absolute `path` (mknod, or bind of a unix socket).
+fn vfs_mknod(path: &str, kind: FileType, perm: u16, dev: u64) -> Result<(MountId, InodeId), Errno> {
+    with_mounts(|mounts| {
+        match mounts.resolve_path(path) {
+            Ok(_) => return Err(Errno::Exist),
+            Err(VfsError::NotFound) => {}
+            Err(err) => return Err(map_vfs_err(err)),
+        }
+        let (mount, parent, name) = mounts.resolve_parent(path).map_err(map_vfs_err)?;
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let parent_meta = fs.metadata(parent).map_err(map_vfs_err)?;
+        check_access(&parent_meta, ACCESS_WRITE | ACCESS_EXEC)?;
+        let inode = fs
+            .mknod(parent, name, kind, perm, dev)
+            .map_err(|err| match err {
+                VfsError::NotSupported => Errno::Perm,
+                err => map_vfs_err(err),
+            })?;
+        assign_new_owner(fs, &parent_meta, inode)?;
+        inotify::notify((mount, parent), inotify::IN_CREATE, 0, Some(name));
+        Ok((mount, inode))
+    })
 }
 
 fn sys_mkdirat(_dirfd: usize, pathname: usize, _mode: usize) -> Result<usize, Errno> {
@@ -2627,19 +4020,43 @@ fn sys_mkdirat(_dirfd: usize, pathname: usize, _mode: usize) -> Result<usize, Er
     }
 }
 
-fn sys_unlinkat(_dirfd: usize, pathname: usize, _flags: usize) -> Result<usize, Errno> {
+fn sys_unlinkat(_dirfd: usize, pathname: usize, flags: usize) -> Result<usize, Errno> {
     if pathname == 0 {
         return Err(Errno::Fault);
     }
+    if flags & !AT_REMOVEDIR != 0 {
+        return Err(Errno::Inval);
+    }
     let root_pa = mm::current_root_pa();
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    vfs_check_parent(root_pa, pathname)?;
-    match vfs_lookup_inode(root_pa, pathname) {
-        Ok(_) => Err(Errno::Inval),
-        Err(err) => Err(err),
-    }
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = read_user_path_abs(root_pa, pathname, &mut path_buf)?;
+    with_mounts(|mounts| {
+        let (mount, inode) = mounts.resolve_path(path).map_err(map_vfs_err)?;
+        let (parent_mount, parent, name) = mounts.resolve_parent(path).map_err(map_vfs_err)?;
+        let fs = mounts.fs_for(parent_mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        let is_dir = meta.file_type == FileType::Dir;
+        match (is_dir, flags & AT_REMOVEDIR != 0) {
+            (true, false) => return Err(Errno::IsDir),
+            (false, true) => return Err(Errno::NotDir),
+            _ => {}
+        }
+        check_delete(&fs.metadata(parent).map_err(map_vfs_err)?, &meta)?;
+        // 只读或未实现删除的文件系统按 EPERM 报告。
+        fs.remove(parent, name).map_err(|err| match err {
+            VfsError::NotSupported => Errno::Perm,
+            err => map_vfs_err(err),
+        })?;
+        let isdir = if is_dir { inotify::IN_ISDIR } else { 0 };
+        inotify::notify((parent_mount, parent), inotify::IN_DELETE | isdir, 0, Some(name));
+        inotify::notify((mount, inode), inotify::IN_DELETE_SELF, 0, None);
+        inotify::forget((mount, inode));
+        unlink_open_files((parent_mount, parent), name);
+        Ok(0)
+    })
 }
 
 fn sys_symlinkat(oldpath: usize, newdirfd: usize, newpath: usize) -> Result<usize, Errno> {
@@ -2698,7 +4115,7 @@ fn sys_renameat2(
     newpath: usize,
     flags: usize,
 ) -> Result<usize, Errno> {
-    // 占位实现：仅支持 flags=0，且不执行真实重命名。
+    // 仅支持 flags=0；真实重命名交给文件系统的 rename 实现。
     if flags != 0 {
         return Err(Errno::Inval);
     }
@@ -2708,23 +4125,52 @@ fn sys_renameat2(
     }
     validate_at_dirfd(olddirfd)?;
     validate_at_dirfd(newdirfd)?;
-    validate_user_path(root_pa, oldpath)?;
-    validate_user_path(root_pa, newpath)?;
-    vfs_check_parent(root_pa, newpath)?;
-    let (old_mount, old_inode) = match vfs_lookup_inode(root_pa, oldpath) {
-        Ok(value) => value,
-        Err(err) => return Err(err),
-    };
-    match vfs_lookup_inode(root_pa, newpath) {
-        Ok((new_mount, new_inode)) => {
-            if new_mount == old_mount && new_inode == old_inode {
-                Ok(0)
-            } else {
-                Err(Errno::Exist)
-            }
+    let mut old_buf = [0u8; MAX_PATH_LEN];
+    let old_path = read_user_path_abs(root_pa, oldpath, &mut old_buf)?;
+    let mut new_buf = [0u8; MAX_PATH_LEN];
+    let new_path = read_user_path_abs(root_pa, newpath, &mut new_buf)?;
+    with_mounts(|mounts| {
+        let (mount, inode) = mounts.resolve_path(old_path).map_err(map_vfs_err)?;
+        let replaced = match mounts.resolve_path(new_path) {
+            Ok(target) if target == (mount, inode) => return Ok(0),
+            Ok(target) => Some(target),
+            Err(VfsError::NotFound) => None,
+            Err(err) => return Err(map_vfs_err(err)),
+        };
+        let (old_mount, old_parent, old_name) = mounts.resolve_parent(old_path).map_err(map_vfs_err)?;
+        let (new_mount, new_parent, new_name) = mounts.resolve_parent(new_path).map_err(map_vfs_err)?;
+        if old_mount != new_mount {
+            return Err(Errno::XDev);
         }
-        Err(err) => Err(err),
-    }
+        let fs = mounts.fs_for(old_mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        let is_dir = meta.file_type == FileType::Dir;
+        check_delete(&fs.metadata(old_parent).map_err(map_vfs_err)?, &meta)?;
+        let new_parent_meta = fs.metadata(new_parent).map_err(map_vfs_err)?;
+        match replaced {
+            Some((_, target)) => check_delete(&new_parent_meta, &fs.metadata(target).map_err(map_vfs_err)?)?,
+            None => check_access(&new_parent_meta, ACCESS_WRITE | ACCESS_EXEC)?,
+        }
+        fs.rename(old_parent, old_name, new_parent, new_name)
+            .map_err(|err| match err {
+                VfsError::NotSupported => Errno::Perm,
+                err => map_vfs_err(err),
+            })?;
+        if replaced.is_some() {
+            unlink_open_files((new_mount, new_parent), new_name);
+        }
+        rename_open_files((old_mount, old_parent), old_name, (new_mount, new_parent), new_path);
+        let isdir = if is_dir { inotify::IN_ISDIR } else { 0 };
+        let cookie = inotify::next_cookie();
+        inotify::notify((old_mount, old_parent), inotify::IN_MOVED_FROM | isdir, cookie, Some(old_name));
+        inotify::notify((new_mount, new_parent), inotify::IN_MOVED_TO | isdir, cookie, Some(new_name));
+        inotify::notify((mount, inode), inotify::IN_MOVE_SELF, 0, None);
+        if let Some(target) = replaced {
+            inotify::notify(target, inotify::IN_DELETE_SELF, 0, None);
+            inotify::forget(target);
+        }
+        Ok(0)
+    })
 }
 
 fn sys_getdents64(fd: usize, buf: usize, len: usize) -> Result<usize, Errno> {
@@ -2841,23 +4287,48 @@ fn sys_newfstatat(_dirfd: usize, pathname: usize, stat_ptr: usize, _flags: usize
         let meta = fs.metadata(inode).map_err(map_vfs_err)?;
         let size = meta.size as usize;
         let mode = file_type_mode(meta.file_type) | meta.mode as u32;
+        let mut stat = build_stat(mode, size);
+        stat.st_rdev = meta.rdev as usize;
+        stat.st_uid = meta.uid;
+        stat.st_gid = meta.gid;
         UserPtr::new(stat_ptr)
-            .write(root_pa, build_stat(mode, size))
+            .write(root_pa, stat)
             .ok_or(Errno::Fault)?;
         Ok(0)
     })
 }
 
-fn sys_faccessat(_dirfd: usize, pathname: usize, _mode: usize, _flags: usize) -> Result<usize, Errno> {
+fn sys_faccessat(_dirfd: usize, pathname: usize, mode: usize, flags: usize) -> Result<usize, Errno> {
     if pathname == 0 {
         return Err(Errno::Fault);
     }
+    if mode & !0o7 != 0 || flags & !(AT_EACCESS | AT_SYMLINK_NOFOLLOW) != 0 {
+        return Err(Errno::Inval);
+    }
     let root_pa = mm::current_root_pa();
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    let _ = vfs_lookup_inode(root_pa, pathname)?;
-    Ok(0)
+    let (mount, inode) = vfs_lookup_inode(root_pa, pathname)?;
+    if mode == 0 {
+        return Ok(0);
+    }
+    let meta = with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        fs.metadata(inode).map_err(map_vfs_err)
+    })?;
+    // access(2) 默认按真实 uid/gid 检查，AT_EACCESS 改用有效（文件系统）身份。
+    let cred = cred::current();
+    let creds = if flags & AT_EACCESS != 0 {
+        cred.fs_credentials()
+    } else {
+        cred.real_credentials()
+    };
+    if creds.may_access(&meta, mode as u8) {
+        Ok(0)
+    } else {
+        Err(Errno::Acces)
+    }
 }
 
 fn sys_statx(
@@ -2892,14 +4363,30 @@ fn sys_readlinkat(_dirfd: usize, pathname: usize, buf: usize, len: usize) -> Res
         return Ok(0);
     }
     validate_user_write(root_pa, buf, len)?;
-    let (mount, inode) = vfs_lookup_inode(root_pa, pathname)?;
+    let mut path_buf = [0u8; MAX_PATH_LEN];
+    let path = read_user_path_abs(root_pa, pathname, &mut path_buf)?;
     with_mounts(|mounts| {
+        // /proc/self 在路径遍历中直接解析为进程目录，readlink 需要取链接本身。
+        let (mount, inode) = match mounts.resolve_parent(path) {
+            Ok((MountId::Proc, procfs::ROOT_ID, "self")) => (MountId::Proc, procfs::SELF_ID),
+            _ => mounts.resolve_path(path).map_err(map_vfs_err)?,
+        };
         let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
         let meta = fs.metadata(inode).map_err(map_vfs_err)?;
         if meta.file_type != FileType::Symlink {
             return Err(Errno::Inval);
         }
-        Err(Errno::Inval)
+        // 目前只有 procfs 生成的链接可读取目标。
+        if mount != MountId::Proc {
+            return Err(Errno::Inval);
+        }
+        let mut target = [0u8; MAX_PATH_LEN];
+        let want = core::cmp::min(len, target.len());
+        let read = fs.read_at(inode, 0, &mut target[..want]).map_err(map_vfs_err)?;
+        UserSlice::new(buf, read)
+            .copy_from_slice(root_pa, &target[..read])
+            .ok_or(Errno::Fault)?;
+        Ok(read)
     })
 }
 
@@ -2915,11 +4402,10 @@ fn sys_statfs(pathname: usize, buf: usize) -> Result<usize, Errno> {
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    // 占位实现：仅支持根目录与 /dev 伪节点。
     validate_user_path(root_pa, pathname)?;
-    let _ = vfs_lookup_inode(root_pa, pathname)?;
+    let (mount, _) = vfs_lookup_inode(root_pa, pathname)?;
     UserPtr::new(buf)
-        .write(root_pa, default_statfs())
+        .write(root_pa, vfs_statfs(mount)?)
         .ok_or(Errno::Fault)?;
     Ok(0)
 }
@@ -2932,12 +4418,19 @@ fn sys_fstatfs(fd: usize, buf: usize) -> Result<usize, Errno> {
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    // 占位实现：识别标准 fd / pseudo fd / pipe。
-    if resolve_fd(fd).is_none() {
-        return Err(Errno::Badf);
-    }
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    // 匿名对象按 Linux 伪文件系统 magic 返回，无容量信息。
+    let stat = match entry.object {
+        FdObject::Vfs(handle) => vfs_statfs(handle.mount)?,
+        FdObject::Stdin | FdObject::Stdout | FdObject::Stderr => vfs_statfs(MountId::Dev)?,
+        FdObject::PipeRead(_) | FdObject::PipeWrite(_) | FdObject::PipeRdWr(_) => {
+            build_statfs(&StatFs::empty(PIPEFS_MAGIC), [0, 0], 0)
+        }
+        FdObject::Socket(_) | FdObject::Unix(_) => build_statfs(&StatFs::empty(SOCKFS_MAGIC), [0, 0], 0),
+        _ => build_statfs(&StatFs::empty(ANON_INODE_FS_MAGIC), [0, 0], 0),
+    };
     UserPtr::new(buf)
-        .write(root_pa, default_statfs())
+        .write(root_pa, stat)
         .ok_or(Errno::Fault)?;
     Ok(0)
 }
@@ -2957,13 +4450,444 @@ fn sys_ftruncate(fd: usize, len: usize) -> Result<usize, Errno> {
     }
     with_mounts(|mounts| {
         let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
-        fs.truncate(handle.inode, len as u64).map_err(map_vfs_err)?;
-        Ok(0)
+        fs.truncate(handle.inode, len as u64).map_err(map_vfs_err)
+    })?;
+    fsnotify_file(handle, inotify::IN_MODIFY);
+    Ok(0)
+}
+
+fn sys_fallocate(fd: usize, mode: usize, offset: usize, len: usize) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    let handle = match entry.object {
+        FdObject::Vfs(handle) => handle,
+        FdObject::Empty => return Err(Errno::Badf),
+        _ => return Err(Errno::Pipe),
+    };
+    if entry.flags & O_ACCMODE == O_RDONLY {
+        return Err(Errno::Badf);
+    }
+    if (offset as isize) < 0 || (len as isize) <= 0 {
+        return Err(Errno::Inval);
+    }
+    match handle.file_type {
+        FileType::File => {}
+        FileType::Dir => return Err(Errno::IsDir),
+        _ => return Err(Errno::Inval),
+    }
+    let keep_size = mode & FALLOC_FL_KEEP_SIZE != 0;
+    let falloc_mode = match mode & !FALLOC_FL_KEEP_SIZE {
+        0 => FallocMode::Allocate,
+        // PUNCH_HOLE 必须与 KEEP_SIZE 同时给出，与 Linux 一致。
+        FALLOC_FL_PUNCH_HOLE if keep_size => FallocMode::PunchHole,
+        FALLOC_FL_ZERO_RANGE => FallocMode::ZeroRange,
+        _ => return Err(Errno::OpNotSupp),
+    };
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+        fs.fallocate(handle.inode, falloc_mode, offset as u64, len as u64, keep_size)
+            .map_err(map_extent_err)
+    })?;
+    fsnotify_file(handle, inotify::IN_MODIFY);
+    Ok(0)
+}
+
+fn sys_sendfile(out_fd: usize, in_fd: usize, offset: usize, count: usize) -> Result<usize, Errno> {
+    let input = resolve_fd(in_fd).ok_or(Errno::Badf)?;
+    let output = resolve_fd(out_fd).ok_or(Errno::Badf)?;
+    // 与 Linux 一致：输入端必须是普通文件。
+    if !matches!(input.object, FdObject::Vfs(handle) if handle.file_type == FileType::File) {
+        return Err(Errno::Inval);
+    }
+    check_transfer_ends(input, output)?;
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let in_pos = transfer_pos(root_pa, in_fd, input, offset)?;
+    let out_pos = transfer_pos(root_pa, out_fd, output, 0)?;
+    let nonblock = (output.flags & O_NONBLOCK) != 0;
+    let moved = transfer(input, in_pos, output, out_pos, min(count, MAX_RW_COUNT), nonblock)?;
+    advance_transfer_pos(root_pa, in_fd, input, offset, in_pos + moved)?;
+    advance_transfer_pos(root_pa, out_fd, output, 0, out_pos + moved)?;
+    notify_transfer(input, output, moved);
+    Ok(moved)
+}
+
+fn sys_splice(
+    fd_in: usize,
+    off_in: usize,
+    fd_out: usize,
+    off_out: usize,
+    len: usize,
+    flags: usize,
+) -> Result<usize, Errno> {
+    if flags & !(SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT) != 0 {
+        return Err(Errno::Inval);
+    }
+    let input = resolve_fd(fd_in).ok_or(Errno::Badf)?;
+    let output = resolve_fd(fd_out).ok_or(Errno::Badf)?;
+    let in_pipe = pipe_reader_id(input.object);
+    let out_pipe = pipe_writer_id(output.object);
+    // 至少一端是 pipe，且不能在同一个 pipe 上自我搬运。
+    if (in_pipe.is_none() && out_pipe.is_none()) || (in_pipe.is_some() && in_pipe == out_pipe) {
+        return Err(Errno::Inval);
+    }
+    check_transfer_ends(input, output)?;
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let in_pos = transfer_pos(root_pa, fd_in, input, off_in)?;
+    let out_pos = transfer_pos(root_pa, fd_out, output, off_out)?;
+    let nonblock = (flags & SPLICE_F_NONBLOCK) != 0 || ((input.flags | output.flags) & O_NONBLOCK) != 0;
+    let moved = transfer(input, in_pos, output, out_pos, min(len, MAX_RW_COUNT), nonblock)?;
+    advance_transfer_pos(root_pa, fd_in, input, off_in, in_pos + moved)?;
+    advance_transfer_pos(root_pa, fd_out, output, off_out, out_pos + moved)?;
+    notify_transfer(input, output, moved);
+    Ok(moved)
+}
+
+fn sys_tee(fd_in: usize, fd_out: usize, len: usize, flags: usize) -> Result<usize, Errno> {
+    if flags & !(SPLICE_F_MOVE | SPLICE_F_NONBLOCK | SPLICE_F_MORE | SPLICE_F_GIFT) != 0 {
+        return Err(Errno::Inval);
+    }
+    let input = resolve_fd(fd_in).ok_or(Errno::Badf)?;
+    let output = resolve_fd(fd_out).ok_or(Errno::Badf)?;
+    let (Some(in_id), Some(out_id)) = (pipe_reader_id(input.object), pipe_writer_id(output.object)) else {
+        return Err(Errno::Inval);
+    };
+    if in_id == out_id || len == 0 {
+        return if in_id == out_id { Err(Errno::Inval) } else { Ok(0) };
+    }
+    let nonblock = (flags & SPLICE_F_NONBLOCK) != 0 || ((input.flags | output.flags) & O_NONBLOCK) != 0;
+    if !pipe_wait_readable(in_id, nonblock)? {
+        return Ok(0);
+    }
+    pipe_wait_writable(out_id, nonblock)?;
+    // SAFETY: 单核早期阶段串行读取 pipe 状态；输入与输出是不同的 pipe。
+    let (start, available) = unsafe { (PIPES[in_id].read_pos, PIPES[in_id].len) };
+    let mut copied = 0usize;
+    // 只复制输入 pipe 中的数据，不推进其读指针。
+    pipe_fill(out_id, min(len, available), |dst| {
+        // SAFETY: 输入 pipe 与正在填充的输出 pipe 不是同一个表项。
+        let src = unsafe { &PIPES[in_id] };
+        for (idx, byte) in dst.iter_mut().enumerate() {
+            *byte = src.buf[(start + copied + idx) % PIPE_BUFFER_SIZE];
+        }
+        copied += dst.len();
+        Ok(dst.len())
     })
 }
 
-fn sys_fchmodat(dirfd: usize, pathname: usize, _mode: usize, flags: usize) -> Result<usize, Errno> {
-    // 占位实现：仅支持 AT_FDCWD 与 AT_SYMLINK_NOFOLLOW。
+fn sys_copy_file_range(
+    fd_in: usize,
+    off_in: usize,
+    fd_out: usize,
+    off_out: usize,
+    len: usize,
+    flags: usize,
+) -> Result<usize, Errno> {
+    if flags != 0 {
+        return Err(Errno::Inval);
+    }
+    let input = resolve_fd(fd_in).ok_or(Errno::Badf)?;
+    let output = resolve_fd(fd_out).ok_or(Errno::Badf)?;
+    let (FdObject::Vfs(src), FdObject::Vfs(dst)) = (input.object, output.object) else {
+        return Err(Errno::Inval);
+    };
+    if src.file_type == FileType::Dir || dst.file_type == FileType::Dir {
+        return Err(Errno::IsDir);
+    }
+    if src.file_type != FileType::File || dst.file_type != FileType::File {
+        return Err(Errno::Inval);
+    }
+    if input.flags & O_ACCMODE == O_WRONLY
+        || output.flags & O_ACCMODE == O_RDONLY
+        || (output.flags & O_APPEND) != 0
+    {
+        return Err(Errno::Badf);
+    }
+    if src.mount != dst.mount {
+        return Err(Errno::XDev);
+    }
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let in_pos = transfer_pos(root_pa, fd_in, input, off_in)?;
+    let out_pos = transfer_pos(root_pa, fd_out, output, off_out)?;
+    let len = min(len, MAX_RW_COUNT);
+    // 同一文件内的源区间与目标区间不能重叠。
+    if src.inode == dst.inode && in_pos < out_pos + len && out_pos < in_pos + len {
+        return Err(Errno::Inval);
+    }
+    let copied = with_mounts(|mounts| {
+        let fs = mounts.fs_for(src.mount).ok_or(Errno::NoEnt)?;
+        fs.copy_range(src.inode, in_pos as u64, dst.inode, out_pos as u64, len as u64)
+            .map_err(map_vfs_err)
+    })? as usize;
+    advance_transfer_pos(root_pa, fd_in, input, off_in, in_pos + copied)?;
+    advance_transfer_pos(root_pa, fd_out, output, off_out, out_pos + copied)?;
+    notify_transfer(input, output, copied);
+    Ok(copied)
+}
+
+fn check_transfer_ends(input: FdEntry, output: FdEntry) -> Result<(), Errno> {
+    if matches!(input.object, FdObject::Vfs(_)) && input.flags & O_ACCMODE == O_WRONLY {
+        return Err(Errno::Badf);
+    }
+    if matches!(output.object, FdObject::Vfs(_)) {
+        if output.flags & O_ACCMODE == O_RDONLY {
+            return Err(Errno::Badf);
+        }
+        if (output.flags & O_APPEND) != 0 {
+            return Err(Errno::Inval);
+        }
+    }
+    Ok(())
+}
+
+/// Starting position of a file end: `*off_ptr` when given, otherwise the fd offset.
+fn transfer_pos(root_pa: usize, fd: usize, entry: FdEntry, off_ptr: usize) -> Result<usize, Errno> {
+    if !matches!(entry.object, FdObject::Vfs(_)) {
+        return if off_ptr != 0 { Err(Errno::Pipe) } else { Ok(0) };
+    }
+    if off_ptr == 0 {
+        return fd_offset(fd).ok_or(Errno::Badf);
+    }
+    let offset = UserPtr::<i64>::new(off_ptr).read(root_pa).ok_or(Errno::Fault)?;
+    usize::try_from(offset).map_err(|_| Errno::Inval)
+}
+
+/// Store the end position back to `*off_ptr`, or to the fd offset when no pointer was given.
+fn advance_transfer_pos(root_pa: usize, fd: usize, entry: FdEntry, off_ptr: usize, pos: usize) -> Result<(), Errno> {
+    if !matches!(entry.object, FdObject::Vfs(_)) {
+        return Ok(());
+    }
+    if off_ptr == 0 {
+        set_fd_offset(fd, pos);
+        return Ok(());
+    }
+    UserPtr::new(off_ptr).write(root_pa, pos as i64).ok_or(Errno::Fault)?;
+    Ok(())
+}
+
+fn notify_transfer(input: FdEntry, output: FdEntry, moved: usize) {
+    if moved == 0 {
+        return;
+    }
+    if let FdObject::Vfs(handle) = input.object {
+        fsnotify_file(handle, inotify::IN_ACCESS);
+    }
+    if let FdObject::Vfs(handle) = output.object {
+        fsnotify_file(handle, inotify::IN_MODIFY);
+    }
+}
+
+fn sys_setxattr(pathname: usize, name: usize, value: usize, size: usize, flags: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    validate_user_path(root_pa, pathname)?;
+    let target = vfs_lookup_inode(root_pa, pathname)?;
+    xattr_set(root_pa, target, name, value, size, flags)?;
+    fsnotify_at(root_pa, pathname, inotify::IN_ATTRIB);
+    Ok(0)
+}
+
+fn sys_fsetxattr(fd: usize, name: usize, value: usize, size: usize, flags: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = xattr_fd_target(fd)?;
+    xattr_set(root_pa, (handle.mount, handle.inode), name, value, size, flags)?;
+    fsnotify_file(handle, inotify::IN_ATTRIB);
+    Ok(0)
+}
+
+fn sys_getxattr(pathname: usize, name: usize, value: usize, size: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    validate_user_path(root_pa, pathname)?;
+    let target = vfs_lookup_inode(root_pa, pathname)?;
+    xattr_get(root_pa, target, name, value, size)
+}
+
+fn sys_fgetxattr(fd: usize, name: usize, value: usize, size: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = xattr_fd_target(fd)?;
+    xattr_get(root_pa, (handle.mount, handle.inode), name, value, size)
+}
+
+fn sys_listxattr(pathname: usize, list: usize, size: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    validate_user_path(root_pa, pathname)?;
+    let target = vfs_lookup_inode(root_pa, pathname)?;
+    xattr_list(root_pa, target, list, size)
+}
+
+fn sys_flistxattr(fd: usize, list: usize, size: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = xattr_fd_target(fd)?;
+    xattr_list(root_pa, (handle.mount, handle.inode), list, size)
+}
+
+fn sys_removexattr(pathname: usize, name: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    validate_user_path(root_pa, pathname)?;
+    let target = vfs_lookup_inode(root_pa, pathname)?;
+    xattr_remove(root_pa, target, name)?;
+    fsnotify_at(root_pa, pathname, inotify::IN_ATTRIB);
+    Ok(0)
+}
+
+fn sys_fremovexattr(fd: usize, name: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = xattr_fd_target(fd)?;
+    xattr_remove(root_pa, (handle.mount, handle.inode), name)?;
+    fsnotify_file(handle, inotify::IN_ATTRIB);
+    Ok(0)
+}
+
+fn xattr_fd_target(fd: usize) -> Result<VfsHandle, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    match entry.object {
+        FdObject::Vfs(handle) => Ok(handle),
+        FdObject::Empty => Err(Errno::Badf),
+        _ => Err(Errno::OpNotSupp),
+    }
+}
+
+fn read_xattr_name(root_pa: usize, name: usize, buf: &mut [u8]) -> Result<&str, Errno> {
+    if name == 0 {
+        return Err(Errno::Fault);
+    }
+    let name = read_user_path_str(root_pa, name, buf)?;
+    if name.is_empty() {
+        return Err(Errno::Range);
+    }
+    Ok(name)
+}
+
+#[inline(never)]
+fn xattr_set(
+    root_pa: usize,
+    (mount, inode): (MountId, InodeId),
+    name: usize,
+    value: usize,
+    size: usize,
+    flags: usize,
+) -> Result<usize, Errno> {
+    let mode = match flags {
+        0 => XattrMode::Any,
+        XATTR_CREATE => XattrMode::Create,
+        XATTR_REPLACE => XattrMode::Replace,
+        _ => return Err(Errno::Inval),
+    };
+    if size > XATTR_SIZE_MAX {
+        return Err(Errno::TooBig);
+    }
+    // 单个值最多占满一个 ext4 块，超出部分按 Linux ext4 行为报告 ENOSPC。
+    if size > XATTR_VALUE_BUF {
+        return Err(Errno::NoSpc);
+    }
+    let mut name_buf = [0u8; XATTR_NAME_MAX + 1];
+    let name = read_xattr_name(root_pa, name, &mut name_buf)?;
+    let mut value_buf = [0u8; XATTR_VALUE_BUF];
+    UserSlice::new(value, size)
+        .copy_to_slice(root_pa, &mut value_buf[..size])
+        .ok_or(Errno::Fault)?;
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        fs.setxattr(inode, name, &value_buf[..size], mode).map_err(map_xattr_err)
+    })?;
+    Ok(0)
+}
+
+#[inline(never)]
+fn xattr_get(
+    root_pa: usize,
+    (mount, inode): (MountId, InodeId),
+    name: usize,
+    value: usize,
+    size: usize,
+) -> Result<usize, Errno> {
+    let mut name_buf = [0u8; XATTR_NAME_MAX + 1];
+    let name = read_xattr_name(root_pa, name, &mut name_buf)?;
+    let mut value_buf = [0u8; XATTR_VALUE_BUF];
+    let want = core::cmp::min(size, XATTR_VALUE_BUF);
+    let len = with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        fs.getxattr(inode, name, &mut value_buf[..want]).map_err(map_xattr_err)
+    })?;
+    // size 为 0 时只查询长度。
+    if size == 0 {
+        return Ok(len);
+    }
+    if len > want {
+        return Err(Errno::Range);
+    }
+    UserSlice::new(value, len)
+        .copy_from_slice(root_pa, &value_buf[..len])
+        .ok_or(Errno::Fault)?;
+    Ok(len)
+}
+
+#[inline(never)]
+fn xattr_list(root_pa: usize, (mount, inode): (MountId, InodeId), list: usize, size: usize) -> Result<usize, Errno> {
+    let mut list_buf = [0u8; XATTR_VALUE_BUF];
+    let want = core::cmp::min(size, XATTR_VALUE_BUF);
+    let len = with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        match fs.listxattr(inode, &mut list_buf[..want]) {
+            // 不支持 xattr 的文件系统按空列表处理，与 Linux 一致。
+            Err(VfsError::NotSupported) => Ok(0),
+            result => result.map_err(map_xattr_err),
+        }
+    })?;
+    if size == 0 {
+        return Ok(len);
+    }
+    if len > want {
+        return Err(if len > XATTR_VALUE_BUF { Errno::TooBig } else { Errno::Range });
+    }
+    UserSlice::new(list, len)
+        .copy_from_slice(root_pa, &list_buf[..len])
+        .ok_or(Errno::Fault)?;
+    Ok(len)
+}
+
+fn xattr_remove(root_pa: usize, (mount, inode): (MountId, InodeId), name: usize) -> Result<usize, Errno> {
+    let mut name_buf = [0u8; XATTR_NAME_MAX + 1];
+    let name = read_xattr_name(root_pa, name, &mut name_buf)?;
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        fs.removexattr(inode, name).map_err(map_xattr_err)
+    })?;
+    Ok(0)
+}
+
+fn sys_fchmodat(dirfd: usize, pathname: usize, mode: usize, flags: usize) -> Result<usize, Errno> {
+    // 仅支持 AT_FDCWD 与 AT_SYMLINK_NOFOLLOW。
     if flags & !AT_SYMLINK_NOFOLLOW != 0 {
         return Err(Errno::Inval);
     }
@@ -2973,18 +4897,33 @@ fn sys_fchmodat(dirfd: usize, pathname: usize, _mode: usize, flags: usize) -> Re
     }
     validate_at_dirfd(dirfd)?;
     validate_user_path(root_pa, pathname)?;
-    let _ = vfs_lookup_inode(root_pa, pathname)?;
+    let (mount, inode) = vfs_lookup_inode(root_pa, pathname)?;
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        let cred = cred::current();
+        if cred.fsuid != 0 && cred.fsuid != meta.uid {
+            return Err(Errno::Perm);
+        }
+        let mut mode = mode as u16 & 0o7777;
+        // 非特权调用者不属于文件属组时静默清除 setgid 位。
+        if cred.fsuid != 0 && !cred.fs_credentials().in_group(meta.gid) {
+            mode &= !S_ISGID;
+        }
+        fs.set_mode(inode, mode).map_err(map_setattr_err)
+    })?;
+    fsnotify_at(root_pa, pathname, inotify::IN_ATTRIB);
     Ok(0)
 }
 
 fn sys_fchownat(
     dirfd: usize,
     pathname: usize,
-    _owner: usize,
-    _group: usize,
+    owner: usize,
+    group: usize,
     flags: usize,
 ) -> Result<usize, Errno> {
-    // 占位实现：仅支持 AT_FDCWD 与 AT_SYMLINK_NOFOLLOW。
+    // 仅支持 AT_FDCWD 与 AT_SYMLINK_NOFOLLOW。
     if flags & !AT_SYMLINK_NOFOLLOW != 0 {
         return Err(Errno::Inval);
     }
@@ -2994,7 +4933,37 @@ fn sys_fchownat(
     }
     validate_at_dirfd(dirfd)?;
     validate_user_path(root_pa, pathname)?;
-    let _ = vfs_lookup_inode(root_pa, pathname)?;
+    let (mount, inode) = vfs_lookup_inode(root_pa, pathname)?;
+    let (uid, gid) = (id_arg(owner), id_arg(group));
+    if uid.is_none() && gid.is_none() {
+        return Ok(0);
+    }
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let meta = fs.metadata(inode).map_err(map_vfs_err)?;
+        let new_uid = uid.unwrap_or(meta.uid);
+        let new_gid = gid.unwrap_or(meta.gid);
+        let cred = cred::current();
+        // 非特权调用者只能把自己文件的属组改成自己所在的组。
+        if cred.fsuid != 0
+            && (new_uid != meta.uid
+                || cred.fsuid != meta.uid
+                || (new_gid != meta.gid && !cred.fs_credentials().in_group(new_gid)))
+        {
+            return Err(Errno::Perm);
+        }
+        fs.set_owner(inode, new_uid, new_gid).map_err(map_setattr_err)?;
+        // 普通文件换属主后清除 setuid/setgid（仅带组执行位时），避免借此提权。
+        if meta.file_type == FileType::File && meta.mode & (S_ISUID | S_ISGID) != 0 {
+            let mut mode = meta.mode & !S_ISUID;
+            if meta.mode & 0o010 != 0 {
+                mode &= !S_ISGID;
+            }
+            fs.set_mode(inode, mode).map_err(map_setattr_err)?;
+        }
+        Ok(())
+    })?;
+    fsnotify_at(root_pa, pathname, inotify::IN_ATTRIB);
     Ok(0)
 }
 
@@ -3013,23 +4982,9 @@ fn sys_utimensat(dirfd: usize, pathname: usize, times: usize, flags: usize) -> R
         let size = size_of::<Timespec>() * 2;
         validate_user_read(root_pa, times, size)?;
     }
-    let _ = vfs_lookup_inode(root_pa, pathname)?;
-    Ok(0)
-}
-
-fn sys_poll(fds: usize, nfds: usize, timeout: usize) -> Result<usize, Errno> {
-    // poll 的 timeout 为有符号毫秒，负值表示无限期等待。
-    let timeout = timeout as isize;
-    let timeout_ms = if timeout < 0 {
-        None
-    } else {
-        Some(timeout as u64)
-    };
-    let root_pa = mm::current_root_pa();
-    if root_pa == 0 {
-        return Err(Errno::Fault);
-    }
-    ppoll_wait(root_pa, fds, nfds, timeout_ms)
+    vfs_lookup_inode(root_pa, pathname)?;
+    fsnotify_at(root_pa, pathname, inotify::IN_ATTRIB);
+    Ok(0)
 }
 
 fn sys_ppoll(fds: usize, nfds: usize, tmo: usize, _sigmask: usize, _sigsetsize: usize) -> Result<usize, Errno> {
@@ -3173,55 +5128,115 @@ fn sys_getppid() -> Result<usize, Errno> {
 }
 
 fn sys_getuid() -> Result<usize, Errno> {
-    Ok(0)
+    Ok(cred::current().ruid as usize)
 }
 
 fn sys_geteuid() -> Result<usize, Errno> {
-    Ok(0)
+    Ok(cred::current().euid as usize)
 }
 
 fn sys_getgid() -> Result<usize, Errno> {
-    Ok(0)
+    Ok(cred::current().rgid as usize)
 }
 
 fn sys_getegid() -> Result<usize, Errno> {
-    Ok(0)
+    Ok(cred::current().egid as usize)
 }
 
 fn sys_getresuid(ruid: usize, euid: usize, suid: usize) -> Result<usize, Errno> {
-    let root_pa = mm::current_root_pa();
-    if root_pa == 0 {
-        return Err(Errno::Fault);
-    }
-    if ruid != 0 {
-        UserPtr::new(ruid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
-    }
-    if euid != 0 {
-        UserPtr::new(euid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
-    }
-    if suid != 0 {
-        UserPtr::new(suid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
-    }
-    Ok(0)
+    let cred = cred::current();
+    write_id_triple([ruid, euid, suid], [cred.ruid, cred.euid, cred.suid])
 }
 
 fn sys_getresgid(rgid: usize, egid: usize, sgid: usize) -> Result<usize, Errno> {
+    let cred = cred::current();
+    write_id_triple([rgid, egid, sgid], [cred.rgid, cred.egid, cred.sgid])
+}
+
+/// Store ids into the non-NULL `uid_t`/`gid_t` pointers of getresuid/getresgid.
+fn write_id_triple(ptrs: [usize; 3], ids: [u32; 3]) -> Result<usize, Errno> {
     let root_pa = mm::current_root_pa();
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    if rgid != 0 {
-        UserPtr::new(rgid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
-    }
-    if egid != 0 {
-        UserPtr::new(egid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
+    for (ptr, id) in ptrs.into_iter().zip(ids) {
+        if ptr != 0 {
+            UserPtr::new(ptr).write(root_pa, id).ok_or(Errno::Fault)?;
+        }
     }
-    if sgid != 0 {
-        UserPtr::new(sgid).write(root_pa, 0usize).ok_or(Errno::Fault)?;
+    Ok(0)
+}
+
+/// Decode a uid/gid argument; `-1` means "leave unchanged".
+fn id_arg(value: usize) -> Option<u32> {
+    let id = value as u32;
+    (id != u32::MAX).then_some(id)
+}
+
+fn map_cred_err(err: CredError) -> Errno {
+    match err {
+        CredError::Perm => Errno::Perm,
+        CredError::Invalid => Errno::Inval,
     }
+}
+
+fn sys_setuid(uid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    let uid = id_arg(uid).ok_or(Errno::Inval)?;
+    cred::setuid(idx, uid).map_err(map_cred_err)?;
+    Ok(0)
+}
+
+fn sys_setgid(gid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    let gid = id_arg(gid).ok_or(Errno::Inval)?;
+    cred::setgid(idx, gid).map_err(map_cred_err)?;
+    Ok(0)
+}
+
+fn sys_setreuid(ruid: usize, euid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    cred::setreuid(idx, id_arg(ruid), id_arg(euid)).map_err(map_cred_err)?;
+    Ok(0)
+}
+
+fn sys_setregid(rgid: usize, egid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    cred::setregid(idx, id_arg(rgid), id_arg(egid)).map_err(map_cred_err)?;
+    Ok(0)
+}
+
+fn sys_setresuid(ruid: usize, euid: usize, suid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    cred::setresuid(idx, id_arg(ruid), id_arg(euid), id_arg(suid)).map_err(map_cred_err)?;
+    Ok(0)
+}
+
+fn sys_setresgid(rgid: usize, egid: usize, sgid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    cred::setresgid(idx, id_arg(rgid), id_arg(egid), id_arg(sgid)).map_err(map_cred_err)?;
     Ok(0)
 }
 
+fn sys_setfsuid(uid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    // setfsuid(-1) 只查询当前值。
+    let old = match id_arg(uid) {
+        Some(uid) => cred::setfsuid(idx, uid),
+        None => cred::get(idx).fsuid,
+    };
+    Ok(old as usize)
+}
+
+fn sys_setfsgid(gid: usize) -> Result<usize, Errno> {
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    let old = match id_arg(gid) {
+        Some(gid) => cred::setfsgid(idx, gid),
+        None => cred::get(idx).fsgid,
+    };
+    Ok(old as usize)
+}
+
 fn sys_gettid() -> Result<usize, Errno> {
     Ok(current_pid())
 }
@@ -3442,10 +5457,43 @@ fn sys_prlimit64(_pid: usize, _resource: usize, new_rlim: usize, old_rlim: usize
 
 fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> Result<usize, Errno> {
     let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
-    if matches!(entry.object, FdObject::PipeRead(_) | FdObject::PipeWrite(_)) {
+    if matches!(
+        entry.object,
+        FdObject::PipeRead(_) | FdObject::PipeWrite(_) | FdObject::PipeRdWr(_)
+    ) {
         return Err(Errno::Inval);
     }
     match cmd {
+        FS_IOC_FIEMAP => match entry.object {
+            FdObject::Vfs(handle) if handle.file_type == FileType::File => ioctl_fiemap(handle, arg),
+            _ => Err(Errno::Inval),
+        },
+        FIONREAD => match entry.object {
+            FdObject::Inotify(id) => {
+                let root_pa = mm::current_root_pa();
+                if arg == 0 || root_pa == 0 {
+                    return Err(Errno::Fault);
+                }
+                let pending = inotify::pending(id).map_err(map_inotify_err)?;
+                UserPtr::new(arg).write(root_pa, pending as i32).ok_or(Errno::Fault)?;
+                Ok(0)
+            }
+            FdObject::Unix(id) => {
+                let root_pa = mm::current_root_pa();
+                if arg == 0 || root_pa == 0 {
+                    return Err(Errno::Fault);
+                }
+                let pending = unix_socket::pending(id).map_err(map_unix_err)?;
+                UserPtr::new(arg).write(root_pa, pending as i32).ok_or(Errno::Fault)?;
+                Ok(0)
+            }
+            _ => Err(Errno::Inval),
+        },
+        SIOCGIFCONF | SIOCGIFFLAGS | SIOCSIFFLAGS | SIOCGIFADDR | SIOCSIFADDR | SIOCGIFBRDADDR | SIOCGIFNETMASK
+        | SIOCSIFNETMASK | SIOCGIFMTU | SIOCGIFHWADDR | SIOCGIFINDEX => match entry.object {
+            FdObject::Socket(_) | FdObject::Unix(_) => ioctl_netdev(cmd, arg),
+            _ => Err(Errno::Inval),
+        },
         TIOCGWINSZ => {
             if arg == 0 {
                 return Err(Errno::Fault);
@@ -3541,6 +5589,204 @@ fn sys_ioctl(fd: usize, cmd: usize, arg: usize) -> Result<usize, Errno> {
     }
 }
 
+/// Interface ioctls issued on any socket fd (the `ifconfig` subset).
+fn ioctl_netdev(cmd: usize, arg: usize) -> Result<usize, Errno> {
+    if arg == 0 {
+        return Err(Errno::Fault);
+    }
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    if cmd == SIOCGIFCONF {
+        return ioctl_ifconf(root_pa, arg);
+    }
+    let mut req = UserPtr::<IfReq>::new(arg).read(root_pa).ok_or(Errno::Fault)?;
+    let name_len = req.ifr_name.iter().position(|&b| b == 0).unwrap_or(IFNAMSIZ);
+    let name = core::str::from_utf8(&req.ifr_name[..name_len]).map_err(|_| Errno::NoDev)?;
+    let info = axnet::iface_by_name(name).ok_or(Errno::NoDev)?;
+    match cmd {
+        SIOCSIFFLAGS | SIOCSIFADDR | SIOCSIFNETMASK => {
+            if cred::current().euid != 0 {
+                return Err(Errno::Perm);
+            }
+            let (addr, prefix_len) = match cmd {
+                // 与 Linux 一致：改地址时掩码回到按地址类别推导的默认值。
+                SIOCSIFADDR => {
+                    let addr = ifreq_ipv4(&req)?;
+                    (addr, axnet::classful_prefix(addr))
+                }
+                SIOCSIFNETMASK => {
+                    let (addr, _) = info.ipv4.ok_or(Errno::AddrNotAvail)?;
+                    let prefix_len = axnet::netmask_prefix(ifreq_ipv4(&req)?).map_err(|_| Errno::Inval)?;
+                    (addr, prefix_len)
+                }
+                // 接口始终处于 up 状态，不支持 down。
+                _ => return Ok(0),
+            };
+            axnet::iface_set_ipv4(name, addr, prefix_len).map_err(map_net_err)?;
+            return Ok(0);
+        }
+        _ => {}
+    }
+    req.ifr_data = [0; 24];
+    match cmd {
+        SIOCGIFFLAGS => req.ifr_data[..2].copy_from_slice(&info.flags.to_ne_bytes()),
+        SIOCGIFADDR | SIOCGIFNETMASK | SIOCGIFBRDADDR => {
+            let (addr, prefix_len) = info.ipv4.ok_or(Errno::AddrNotAvail)?;
+            let mask = axnet::prefix_netmask(prefix_len);
+            let value = match cmd {
+                SIOCGIFADDR => addr,
+                SIOCGIFNETMASK => mask,
+                _ => {
+                    let broadcast = u32::from_be_bytes(addr.0) | !u32::from_be_bytes(mask.0);
+                    axnet::Ipv4Address::from_bytes(&broadcast.to_be_bytes())
+                }
+            };
+            set_ifreq_ipv4(&mut req, value);
+        }
+        SIOCGIFMTU => req.ifr_data[..4].copy_from_slice(&(info.mtu as i32).to_ne_bytes()),
+        SIOCGIFINDEX => req.ifr_data[..4].copy_from_slice(&(info.index as i32).to_ne_bytes()),
+        SIOCGIFHWADDR => {
+            let family = if info.flags & axnet::IFF_LOOPBACK != 0 {
+                ARPHRD_LOOPBACK
+            } else {
+                ARPHRD_ETHER
+            };
+            req.ifr_data[..2].copy_from_slice(&family.to_ne_bytes());
+            req.ifr_data[2..8].copy_from_slice(&info.mac);
+        }
+        _ => return Err(Errno::Inval),
+    }
+    UserPtr::new(arg).write(root_pa, req).ok_or(Errno::Fault)?;
+    Ok(0)
+}
+
+/// `SIOCGIFCONF`: list interfaces with an IPv4 address, or report the needed size for a NULL buffer.
+fn ioctl_ifconf(root_pa: usize, arg: usize) -> Result<usize, Errno> {
+    let mut conf = UserPtr::<IfConf>::new(arg).read(root_pa).ok_or(Errno::Fault)?;
+    let capacity = if conf.ifc_len < 0 { 0 } else { conf.ifc_len as usize / size_of::<IfReq>() };
+    let mut written = 0usize;
+    for info in (0..).map_while(axnet::iface_info) {
+        let Some((addr, _)) = info.ipv4 else {
+            continue;
+        };
+        if conf.ifc_buf != 0 {
+            if written == capacity {
+                break;
+            }
+            let mut req = IfReq {
+                ifr_name: [0; IFNAMSIZ],
+                ifr_data: [0; 24],
+            };
+            req.ifr_name[..info.name.len()].copy_from_slice(info.name.as_bytes());
+            set_ifreq_ipv4(&mut req, addr);
+            UserPtr::new(conf.ifc_buf + written * size_of::<IfReq>())
+                .write(root_pa, req)
+                .ok_or(Errno::Fault)?;
+        }
+        written += 1;
+    }
+    conf.ifc_len = (written * size_of::<IfReq>()) as i32;
+    UserPtr::new(arg).write(root_pa, conf).ok_or(Errno::Fault)?;
+    Ok(0)
+}
+
+fn ifreq_ipv4(req: &IfReq) -> Result<axnet::Ipv4Address, Errno> {
+    if u16::from_ne_bytes([req.ifr_data[0], req.ifr_data[1]]) != AF_INET {
+        return Err(Errno::Inval);
+    }
+    Ok(axnet::Ipv4Address::from_bytes(&req.ifr_data[4..8]))
+}
+
+fn set_ifreq_ipv4(req: &mut IfReq, addr: axnet::Ipv4Address) {
+    // sockaddr_in：sin_family 为本机序，sin_addr 为网络序。
+    req.ifr_data[..2].copy_from_slice(&AF_INET.to_ne_bytes());
+    req.ifr_data[4..8].copy_from_slice(addr.as_bytes());
+}
+
+fn ioctl_fiemap(handle: VfsHandle, arg: usize) -> Result<usize, Errno> {
+    if arg == 0 {
+        return Err(Errno::Fault);
+    }
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let mut request = UserPtr::<Fiemap>::new(arg).read(root_pa).ok_or(Errno::Fault)?;
+    if request.fm_flags & !FIEMAP_FLAG_SYNC != 0 {
+        return Err(Errno::Inval);
+    }
+    let end = request.fm_start.saturating_add(request.fm_length);
+    let mut extents = [FileExtent::empty(); 8];
+    let mut cursor = request.fm_start;
+    let mut mapped = 0u32;
+    // 延迟一项写出，以便在扫描到文件末尾时为最后一个 extent 标记 LAST。
+    let mut pending: Option<FileExtent> = None;
+    let mut at_eof = false;
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+        'scan: loop {
+            let count = fs.fiemap(handle.inode, cursor, &mut extents).map_err(map_extent_err)?;
+            for extent in &extents[..count] {
+                if extent.logical >= end {
+                    break 'scan;
+                }
+                if let Some(prev) = pending.take() {
+                    write_fiemap_extent(root_pa, arg, &request, mapped, prev, false)?;
+                    mapped += 1;
+                    if request.fm_extent_count != 0 && mapped == request.fm_extent_count {
+                        break 'scan;
+                    }
+                }
+                pending = Some(*extent);
+            }
+            if count < extents.len() {
+                at_eof = true;
+                break;
+            }
+            let last = extents[count - 1];
+            cursor = last.logical + last.length;
+        }
+        Ok(())
+    })?;
+    if let Some(last) = pending {
+        write_fiemap_extent(root_pa, arg, &request, mapped, last, at_eof)?;
+        mapped += 1;
+    }
+    request.fm_mapped_extents = mapped;
+    UserPtr::new(arg).write(root_pa, request).ok_or(Errno::Fault)?;
+    Ok(0)
+}
+
+fn write_fiemap_extent(
+    root_pa: usize,
+    arg: usize,
+    request: &Fiemap,
+    index: u32,
+    extent: FileExtent,
+    last: bool,
+) -> Result<(), Errno> {
+    // fm_extent_count 为 0 时只统计数量，不回写数组。
+    if request.fm_extent_count == 0 {
+        return Ok(());
+    }
+    let mut flags = if extent.unwritten { FIEMAP_EXTENT_UNWRITTEN } else { 0 };
+    if last {
+        flags |= FIEMAP_EXTENT_LAST;
+    }
+    let record = FiemapExtent {
+        fe_logical: extent.logical,
+        fe_physical: extent.physical,
+        fe_length: extent.length,
+        fe_reserved64: [0; 2],
+        fe_flags: flags,
+        fe_reserved: [0; 3],
+    };
+    let addr = arg + size_of::<Fiemap>() + index as usize * size_of::<FiemapExtent>();
+    UserPtr::new(addr).write(root_pa, record).ok_or(Errno::Fault)
+}
+
 fn sys_sysinfo(info: usize) -> Result<usize, Errno> {
     if info == 0 {
         return Err(Errno::Fault);
@@ -3622,15 +5868,25 @@ fn sys_fstat(fd: usize, stat_ptr: usize) -> Result<usize, Errno> {
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    let (mode, size) = match entry.object {
-        FdObject::PipeRead(_) | FdObject::PipeWrite(_) => (S_IFIFO | 0o600, 0),
+    // 匿名对象（管道、socket 等）归调用者所有。
+    let cred = cred::current();
+    let (mode, size, rdev, uid, gid) = match entry.object {
+        FdObject::PipeRead(_) | FdObject::PipeWrite(_) | FdObject::PipeRdWr(_) => {
+            (S_IFIFO | 0o600, 0, 0, cred.euid, cred.egid)
+        }
+        FdObject::Socket(_) | FdObject::Unix(_) => (S_IFSOCK | 0o777, 0, 0, cred.euid, cred.egid),
         FdObject::Vfs(handle) => with_mounts(|mounts| {
             let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
-            vfs_meta_for(fs, handle.inode)
+            let meta = fs.metadata(handle.inode).map_err(map_vfs_err)?;
+            let mode = file_type_mode(meta.file_type) | meta.mode as u32;
+            Ok((mode, meta.size as usize, meta.rdev, meta.uid, meta.gid))
         })?,
-        _ => (S_IFCHR | 0o666, 0),
+        _ => (S_IFCHR | 0o666, 0, 0, cred.euid, cred.egid),
     };
-    let stat = build_stat(mode, size);
+    let mut stat = build_stat(mode, size);
+    stat.st_rdev = rdev as usize;
+    stat.st_uid = uid;
+    stat.st_gid = gid;
     UserPtr::new(stat_ptr)
         .write(root_pa, stat)
         .ok_or(Errno::Fault)?;
@@ -3678,6 +5934,17 @@ fn sys_lseek(fd: usize, offset: usize, whence: usize) -> Result<usize, Errno> {
                     let (_, size) = vfs_meta_for(fs, handle.inode)?;
                     Ok(size as isize)
                 })?,
+                SEEK_DATA | SEEK_HOLE => {
+                    if (offset as isize) < 0 {
+                        return Err(Errno::NxIo);
+                    }
+                    let new_offset = with_mounts(|mounts| {
+                        let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+                        seek_data_hole(fs, handle.inode, offset as u64, whence == SEEK_HOLE)
+                    })?;
+                    set_fd_offset(fd, new_offset);
+                    return Ok(new_offset);
+                }
                 _ => return Err(Errno::Inval),
             };
             let offset = offset as isize;
@@ -3694,6 +5961,54 @@ fn sys_lseek(fd: usize, offset: usize, whence: usize) -> Result<usize, Errno> {
     }
 }
 
+/// Find the next data or hole position at or after `offset`.
+///
+/// Unwritten (preallocated) extents read as zeros and are reported as holes.
+/// Filesystems without extent maps treat the whole file as data.
+fn seek_data_hole(fs: &dyn VfsOps, inode: InodeId, offset: u64, hole: bool) -> Result<usize, Errno> {
+    let (_, size) = vfs_meta_for(fs, inode)?;
+    let size = size as u64;
+    if offset >= size {
+        return Err(Errno::NxIo);
+    }
+    let mut extents = [FileExtent::empty(); 8];
+    let mut cursor = offset;
+    let mut pos = offset;
+    loop {
+        let count = match fs.fiemap(inode, cursor, &mut extents) {
+            Ok(count) => count,
+            Err(VfsError::NotSupported) => return Ok(if hole { size } else { offset } as usize),
+            Err(err) => return Err(map_vfs_err(err)),
+        };
+        for extent in &extents[..count] {
+            let end = extent.logical + extent.length;
+            if end <= pos || extent.unwritten {
+                continue;
+            }
+            if extent.logical > pos {
+                if hole {
+                    return Ok(pos as usize);
+                }
+                pos = extent.logical;
+            }
+            if !hole {
+                return if pos < size { Ok(pos as usize) } else { Err(Errno::NxIo) };
+            }
+            pos = end;
+        }
+        if count < extents.len() || pos >= size {
+            break;
+        }
+        let last = extents[count - 1];
+        cursor = last.logical + last.length;
+    }
+    if hole {
+        Ok(min(pos, size) as usize)
+    } else {
+        Err(Errno::NxIo)
+    }
+}
+
 fn sys_set_robust_list(_head: usize, _len: usize) -> Result<usize, Errno> {
     Ok(0)
 }
@@ -3766,40 +6081,244 @@ fn sys_rt_sigprocmask(how: usize, set: usize, oldset: usize, sigsetsize: usize)
             .write(root_pa, 0)
             .ok_or(Errno::Fault)?;
     }
-    Ok(0)
+    Ok(0)
+}
+
+fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    match cmd {
+        F_GETFD => {
+            if (entry.flags & O_CLOEXEC) != 0 {
+                Ok(FD_CLOEXEC)
+            } else {
+                Ok(0)
+            }
+        }
+        F_SETFD => {
+            if arg & !FD_CLOEXEC != 0 {
+                return Err(Errno::Inval);
+            }
+            set_fd_cloexec(fd, (arg & FD_CLOEXEC) != 0)?;
+            Ok(0)
+        }
+        F_GETFL => {
+            let mode = match entry.object {
+                FdObject::Stdin | FdObject::PipeRead(_) => O_RDONLY,
+                FdObject::Stdout | FdObject::Stderr | FdObject::PipeWrite(_) => O_WRONLY,
+                FdObject::Vfs(_) => entry.flags & O_ACCMODE,
+                _ => O_RDWR,
+            };
+            Ok(mode | (entry.flags & (O_NONBLOCK | O_APPEND)))
+        }
+        F_SETFL => {
+            set_fd_flags(fd, arg)?;
+            Ok(0)
+        }
+        F_GETLK | F_OFD_GETLK => fcntl_getlk(entry, cmd == F_OFD_GETLK, arg),
+        F_SETLK | F_SETLKW | F_OFD_SETLK | F_OFD_SETLKW => {
+            let ofd = matches!(cmd, F_OFD_SETLK | F_OFD_SETLKW);
+            fcntl_setlk(entry, ofd, matches!(cmd, F_SETLKW | F_OFD_SETLKW), arg)
+        }
+        _ => Err(Errno::Inval),
+    }
+}
+
+fn fcntl_getlk(entry: FdEntry, ofd: bool, arg: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = lock_handle(entry)?;
+    let mut req = UserPtr::<Flock>::new(arg).read(root_pa).ok_or(Errno::Fault)?;
+    let kind = match req.l_type {
+        F_RDLCK => LockType::Read,
+        F_WRLCK => LockType::Write,
+        _ => return Err(Errno::Inval),
+    };
+    if ofd && req.l_pid != 0 {
+        return Err(Errno::Inval);
+    }
+    let (start, end) = lock_range(entry, handle, &req)?;
+    let owner = record_lock_owner(handle, ofd)?;
+    match flock::test((handle.mount, handle.inode), owner, kind, start, end) {
+        Some(lock) => {
+            req.l_type = if lock.kind == LockType::Write { F_WRLCK } else { F_RDLCK };
+            req.l_whence = SEEK_SET as i16;
+            req.l_start = lock.start as i64;
+            req.l_len = if lock.end == flock::LOCK_TO_EOF {
+                0
+            } else {
+                (lock.end - lock.start) as i64
+            };
+            // OFD 锁不属于任何进程，按 Linux 约定报告 -1。
+            req.l_pid = match lock.owner {
+                LockOwner::Process(_) => lock.pid as i32,
+                _ => -1,
+            };
+        }
+        None => req.l_type = F_UNLCK,
+    }
+    UserPtr::new(arg).write(root_pa, req).ok_or(Errno::Fault)?;
+    Ok(0)
+}
+
+fn fcntl_setlk(entry: FdEntry, ofd: bool, wait: bool, arg: usize) -> Result<usize, Errno> {
+    let root_pa = mm::current_root_pa();
+    if root_pa == 0 {
+        return Err(Errno::Fault);
+    }
+    let handle = lock_handle(entry)?;
+    let req = UserPtr::<Flock>::new(arg).read(root_pa).ok_or(Errno::Fault)?;
+    let accmode = entry.flags & O_ACCMODE;
+    let kind = match req.l_type {
+        F_RDLCK if accmode == O_WRONLY => return Err(Errno::Badf),
+        F_WRLCK if accmode == O_RDONLY => return Err(Errno::Badf),
+        F_RDLCK => LockType::Read,
+        F_WRLCK => LockType::Write,
+        F_UNLCK => LockType::Unlock,
+        _ => return Err(Errno::Inval),
+    };
+    if ofd && req.l_pid != 0 {
+        return Err(Errno::Inval);
+    }
+    let (start, end) = lock_range(entry, handle, &req)?;
+    let owner = record_lock_owner(handle, ofd)?;
+    flock::set((handle.mount, handle.inode), owner, current_pid(), kind, start, end, wait).map_err(map_lock_err)?;
+    Ok(0)
+}
+
+fn sys_flock(fd: usize, operation: usize) -> Result<usize, Errno> {
+    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
+    let handle = lock_handle(entry)?;
+    let kind = match operation & !LOCK_NB {
+        LOCK_SH => LockType::Read,
+        LOCK_EX => LockType::Write,
+        LOCK_UN => LockType::Unlock,
+        _ => return Err(Errno::Inval),
+    };
+    let owner = LockOwner::Flock(handle.open_id);
+    let wait = operation & LOCK_NB == 0;
+    flock::set((handle.mount, handle.inode), owner, current_pid(), kind, 0, flock::LOCK_TO_EOF, wait)
+        .map_err(map_lock_err)?;
+    Ok(0)
+}
+
+fn lock_handle(entry: FdEntry) -> Result<VfsHandle, Errno> {
+    match entry.object {
+        FdObject::Vfs(handle) => Ok(handle),
+        FdObject::Empty => Err(Errno::Badf),
+        _ => Err(Errno::Inval),
+    }
+}
+
+fn record_lock_owner(handle: VfsHandle, ofd: bool) -> Result<LockOwner, Errno> {
+    if ofd {
+        return Ok(LockOwner::OpenFile(handle.open_id));
+    }
+    current_proc_index().map(LockOwner::Process).ok_or(Errno::Badf)
+}
+
+/// Resolve `l_whence/l_start/l_len` to an absolute `[start, end)` range.
+fn lock_range(entry: FdEntry, handle: VfsHandle, req: &Flock) -> Result<(u64, u64), Errno> {
+    let base = match req.l_whence as usize {
+        SEEK_SET => 0,
+        SEEK_CUR => entry.offset as i64,
+        SEEK_END => with_mounts(|mounts| {
+            let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+            Ok(fs.metadata(handle.inode).map_err(map_vfs_err)?.size as i64)
+        })?,
+        _ => return Err(Errno::Inval),
+    };
+    let mut start = base.checked_add(req.l_start).ok_or(Errno::Inval)?;
+    let end = match req.l_len {
+        0 => return if start < 0 { Err(Errno::Inval) } else { Ok((start as u64, flock::LOCK_TO_EOF)) },
+        // 负长度表示 [start + len, start)。
+        len if len < 0 => {
+            let end = start;
+            start = start.checked_add(len).ok_or(Errno::Inval)?;
+            end
+        }
+        len => start.checked_add(len).ok_or(Errno::Inval)?,
+    };
+    if start < 0 {
+        return Err(Errno::Inval);
+    }
+    Ok((start as u64, end as u64))
+}
+
+fn map_lock_err(err: LockError) -> Errno {
+    match err {
+        LockError::WouldBlock => Errno::Again,
+        LockError::Deadlock => Errno::Deadlk,
+        LockError::NoLocks => Errno::NoLck,
+    }
+}
+
+fn map_unix_err(err: UnixError) -> Errno {
+    match err {
+        UnixError::Invalid => Errno::Inval,
+        UnixError::BadSlot => Errno::Badf,
+        UnixError::NoSpace => Errno::NoMem,
+        UnixError::AddrInUse => Errno::AddrInUse,
+        UnixError::ConnRefused => Errno::ConnRefused,
+        UnixError::WrongType => Errno::ProtoType,
+        UnixError::NotConn => Errno::NotConn,
+        UnixError::IsConn => Errno::IsConn,
+        UnixError::DestAddrReq => Errno::DestAddrReq,
+        UnixError::WouldBlock => Errno::Again,
+        UnixError::Pipe => Errno::PipeBroken,
+        UnixError::MsgSize => Errno::MsgSize,
+        UnixError::NotSupported => Errno::OpNotSupp,
+        UnixError::Fault => Errno::Fault,
+    }
+}
+
+fn map_inotify_err(err: InotifyError) -> Errno {
+    match err {
+        InotifyError::Invalid => Errno::Inval,
+        InotifyError::Exists => Errno::Exist,
+        InotifyError::NoSpace => Errno::NoSpc,
+        InotifyError::BadInstance => Errno::Badf,
+    }
+}
+
+/// Drop state tied to a descriptor that was just removed from `proc_idx`'s table.
+///
+/// Any close releases the process's record locks on the file; OFD and flock
+/// locks, and the close event, go with the last descriptor referring to the
+/// open file.
+fn release_vfs_file(proc_idx: usize, entry: FdEntry) {
+    let FdObject::Vfs(handle) = entry.object else {
+        return;
+    };
+    flock::release_process(proc_idx, (handle.mount, handle.inode));
+    release_open_file(entry);
+}
+
+/// Drop OFD/flock locks and report the close once nothing refers to the open file any more.
+fn release_open_file(entry: FdEntry) {
+    let FdObject::Vfs(handle) = entry.object else {
+        return;
+    };
+    if !open_file_in_use(handle.open_id) {
+        flock::release_open_file(handle.open_id);
+        let mask = if entry.flags & O_ACCMODE == O_RDONLY {
+            inotify::IN_CLOSE_NOWRITE
+        } else {
+            inotify::IN_CLOSE_WRITE
+        };
+        fsnotify_file(handle, mask);
+        forget_open_file(handle.open_id);
+    }
 }
 
-fn sys_fcntl(fd: usize, cmd: usize, arg: usize) -> Result<usize, Errno> {
-    let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
-    match cmd {
-        F_GETFD => {
-            if (entry.flags & O_CLOEXEC) != 0 {
-                Ok(FD_CLOEXEC)
-            } else {
-                Ok(0)
-            }
-        }
-        F_SETFD => {
-            if arg & !FD_CLOEXEC != 0 {
-                return Err(Errno::Inval);
-            }
-            set_fd_cloexec(fd, (arg & FD_CLOEXEC) != 0)?;
-            Ok(0)
-        }
-        F_GETFL => {
-            let mode = match entry.object {
-                FdObject::Stdin | FdObject::PipeRead(_) => O_RDONLY,
-                FdObject::Stdout | FdObject::Stderr | FdObject::PipeWrite(_) => O_WRONLY,
-                FdObject::Vfs(_) => entry.flags & O_ACCMODE,
-                _ => O_RDWR,
-            };
-            Ok(mode | (entry.flags & (O_NONBLOCK | O_APPEND)))
-        }
-        F_SETFL => {
-            set_fd_flags(fd, arg)?;
-            Ok(0)
-        }
-        _ => Err(Errno::Inval),
+fn open_file_in_use(open_id: u32) -> bool {
+    let shares = |entry: &FdEntry| matches!(entry.object, FdObject::Vfs(handle) if handle.open_id == open_id);
+    // SAFETY: 单核早期阶段按顺序扫描 fd 表。
+    unsafe {
+        FD_TABLES.iter().flatten().any(shares)
+            || STDIO_REDIRECT.iter().flatten().flatten().any(shares)
+            || UNIX_INFLIGHT.iter().any(shares)
     }
 }
 
@@ -3937,36 +6456,44 @@ fn sys_setpgrp() -> Result<usize, Errno> {
 }
 
 fn sys_getgroups(size: usize, list: usize) -> Result<usize, Errno> {
+    let cred = cred::current();
+    let groups = cred.groups();
     if size == 0 {
-        return Ok(0);
+        return Ok(groups.len());
     }
-    if list == 0 {
-        return Err(Errno::Fault);
+    if size < groups.len() {
+        return Err(Errno::Inval);
     }
     let root_pa = mm::current_root_pa();
     if root_pa == 0 {
         return Err(Errno::Fault);
     }
-    if mm::translate_user_ptr(root_pa, list, 1, UserAccess::Write).is_none() {
-        return Err(Errno::Fault);
+    for (idx, gid) in groups.iter().enumerate() {
+        UserPtr::new(list + idx * size_of::<u32>())
+            .write(root_pa, *gid)
+            .ok_or(Errno::Fault)?;
     }
-    Ok(0)
+    Ok(groups.len())
 }
 
 fn sys_setgroups(size: usize, list: usize) -> Result<usize, Errno> {
-    if size == 0 {
-        return Ok(0);
-    }
-    if list == 0 {
-        return Err(Errno::Fault);
-    }
-    let root_pa = mm::current_root_pa();
-    if root_pa == 0 {
-        return Err(Errno::Fault);
+    if size > cred::NGROUPS_MAX {
+        return Err(Errno::Inval);
     }
-    if mm::translate_user_ptr(root_pa, list, 1, UserAccess::Read).is_none() {
-        return Err(Errno::Fault);
+    let idx = current_proc_index().ok_or(Errno::Fault)?;
+    let mut groups = [0u32; cred::NGROUPS_MAX];
+    if size != 0 {
+        let root_pa = mm::current_root_pa();
+        if root_pa == 0 {
+            return Err(Errno::Fault);
+        }
+        for (i, gid) in groups[..size].iter_mut().enumerate() {
+            *gid = UserPtr::<u32>::new(list + i * size_of::<u32>())
+                .read(root_pa)
+                .ok_or(Errno::Fault)?;
+        }
     }
+    cred::setgroups(idx, &groups[..size]).map_err(map_cred_err)?;
     Ok(0)
 }
 
@@ -4064,7 +6591,7 @@ fn file_type_mode(file_type: FileType) -> u32 {
         FileType::Char => S_IFCHR,
         FileType::Block => S_IFBLK,
         FileType::Fifo => S_IFIFO,
-        FileType::Socket => 0,
+        FileType::Socket => S_IFSOCK,
         FileType::Symlink => 0,
     }
 }
@@ -4081,6 +6608,53 @@ fn dirent_dtype(file_type: FileType) -> u8 {
     }
 }
 
+/// Check DAC permission bits against the current filesystem uid/gid.
+fn check_access(meta: &Metadata, want: u8) -> Result<(), Errno> {
+    if cred::current().fs_credentials().may_access(meta, want) {
+        Ok(())
+    } else {
+        Err(Errno::Acces)
+    }
+}
+
+/// Permission bits open(2) needs: read and/or write by access mode, plus write for O_TRUNC.
+fn open_access(flags: usize) -> u8 {
+    let want = match flags & O_ACCMODE {
+        O_WRONLY => ACCESS_WRITE,
+        O_RDWR => ACCESS_READ | ACCESS_WRITE,
+        _ => ACCESS_READ,
+    };
+    if (flags & O_TRUNC) != 0 {
+        want | ACCESS_WRITE
+    } else {
+        want
+    }
+}
+
+/// Give a freshly created inode to the caller; set-group-ID directories pass on their group.
+fn assign_new_owner(fs: &dyn VfsOps, parent: &Metadata, inode: InodeId) -> Result<(), Errno> {
+    let cred = cred::current();
+    let gid = if parent.mode & S_ISGID != 0 { parent.gid } else { cred.fsgid };
+    if cred.fsuid == 0 && gid == 0 {
+        return Ok(());
+    }
+    match fs.set_owner(inode, cred.fsuid, gid) {
+        Ok(()) | Err(VfsError::NotSupported) => Ok(()),
+        Err(err) => Err(map_vfs_err(err)),
+    }
+}
+
+/// Removing `victim` from `dir` needs write and search permission on the directory; in a
+/// sticky directory the caller must also own the directory or the victim.
+fn check_delete(dir: &Metadata, victim: &Metadata) -> Result<(), Errno> {
+    check_access(dir, ACCESS_WRITE | ACCESS_EXEC)?;
+    let fsuid = cred::current().fsuid;
+    if dir.mode & S_ISVTX != 0 && fsuid != 0 && fsuid != dir.uid && fsuid != victim.uid {
+        return Err(Errno::Perm);
+    }
+    Ok(())
+}
+
 fn vfs_meta_for(fs: &dyn VfsOps, inode: InodeId) -> Result<(u32, usize), Errno> {
     let meta = fs.metadata(inode).map_err(map_vfs_err)?;
     let mode = file_type_mode(meta.file_type) | meta.mode as u32;
@@ -4193,12 +6767,16 @@ fn with_mounts<R>(f: impl FnOnce(&MountTable<'_, VFS_MOUNT_COUNT>) -> R) -> R {
         ROOTFS_KIND_FAT32 => log_rootfs_once("fat32", ROOTFS_LOG_FAT32),
         _ => log_rootfs_once("memfs", ROOTFS_LOG_MEMFS),
     }
-    let rootfs = rootfs_ref(kind);
+    // procfs 内容随进程变化，不经过 dentry 缓存。
+    let rootfs = CachedFs::new(rootfs_ref(kind), MountId::Root, &DCACHE);
+    let devfs = CachedFs::new(&DEVFS, MountId::Dev, &DCACHE);
+    let cred = cred::current();
     let mounts = MountTable::new([
-        MountPoint::new(MountId::Root, "/", rootfs),
-        MountPoint::new(MountId::Dev, "/dev", &DEVFS),
-        MountPoint::new(MountId::Proc, "/proc", &PROCFS),
-    ]);
+        MountPoint::new(MountId::Root, "/", &rootfs).with_flags(mount_flags(MountId::Root)),
+        MountPoint::new(MountId::Dev, "/dev", &devfs).with_flags(mount_flags(MountId::Dev)),
+        MountPoint::new(MountId::Proc, "/proc", &PROCFS).with_flags(mount_flags(MountId::Proc)),
+    ])
+    .with_credentials(cred.fs_credentials());
     f(&mounts)
 }
 
@@ -4303,12 +6881,55 @@ fn map_vfs_err(err: VfsError) -> Errno {
         VfsError::AlreadyExists => Errno::Exist,
         VfsError::Invalid => Errno::NoEnt,
         VfsError::NoMem => Errno::NoMem,
-        VfsError::Permission => Errno::Inval,
+        VfsError::Permission => Errno::Acces,
         VfsError::Busy => Errno::Again,
+        VfsError::NoSpace => Errno::NoSpc,
         VfsError::NotSupported | VfsError::Io | VfsError::Unknown => Errno::Inval,
     }
 }
 
+/// chmod/chown on filesystems without owner or mode storage fail with EPERM.
+fn map_setattr_err(err: VfsError) -> Errno {
+    match err {
+        VfsError::NotSupported => Errno::Perm,
+        err => map_vfs_err(err),
+    }
+}
+
+/// Extent operations report missing filesystem support as EOPNOTSUPP.
+fn map_extent_err(err: VfsError) -> Errno {
+    match err {
+        VfsError::NotSupported => Errno::OpNotSupp,
+        err => map_vfs_err(err),
+    }
+}
+
+/// xattr calls report a missing attribute as ENODATA and unsupported namespaces as EOPNOTSUPP.
+fn map_xattr_err(err: VfsError) -> Errno {
+    match err {
+        VfsError::NotFound => Errno::NoData,
+        VfsError::Invalid => Errno::Inval,
+        err => map_extent_err(err),
+    }
+}
+
+/// Close an AF_INET/AF_INET6 socket. With `SO_LINGER` set to a timeout, first wait
+/// until the peer has acknowledged all queued data or the timeout passes.
+fn close_net_socket(socket_id: axnet::SocketId) {
+    let linger = axnet::socket_options(socket_id).ok().and_then(|opts| opts.linger);
+    if let Some(secs) = linger.filter(|&secs| secs != 0 && can_block_current()) {
+        let deadline = crate::time::uptime_ms().saturating_add(u64::from(secs) * 1000);
+        while axnet::socket_send_queue(socket_id).unwrap_or(0) > 0 {
+            let now = crate::time::uptime_ms();
+            if now >= deadline {
+                break;
+            }
+            let _ = crate::runtime::wait_timeout_ms(crate::runtime::net_wait_queue(), deadline - now);
+        }
+    }
+    let _ = axnet::socket_close(socket_id);
+}
+
 fn map_net_err(err: axnet::NetError) -> Errno {
     match err {
         axnet::NetError::NotReady | axnet::NetError::WouldBlock => Errno::Again,
@@ -4320,6 +6941,13 @@ fn map_net_err(err: axnet::NetError) -> Errno {
         axnet::NetError::IsConnected => Errno::IsConn,
         axnet::NetError::Unreachable => Errno::NetUnreach,
         axnet::NetError::ConnRefused => Errno::ConnRefused,
+        axnet::NetError::MsgSize => Errno::MsgSize,
+        axnet::NetError::DestAddrRequired => Errno::DestAddrReq,
+        axnet::NetError::Access => Errno::Acces,
+        axnet::NetError::Pipe => Errno::PipeBroken,
+        axnet::NetError::AddrInUse => Errno::AddrInUse,
+        axnet::NetError::AddrNotAvail => Errno::AddrNotAvail,
+        axnet::NetError::NoBufs => Errno::NoBufs,
     }
 }
 
@@ -4361,28 +6989,49 @@ fn ppoll_wait_queue() -> &'static crate::task_wait_queue::TaskWaitQueue {
     &POLL_WAITERS
 }
 
-fn default_statfs() -> Statfs {
-    // 使用内存容量填充占位 statfs，保证用户态工具有可读值。
-    const TMPFS_MAGIC: u64 = 0x0102_1994;
-    let bsize = 4096u64;
-    let total_bytes = mm::memory_size() as u64;
-    let blocks = total_bytes / bsize;
+fn build_statfs(stat: &StatFs, fsid: [i32; 2], flags: u32) -> Statfs {
     Statfs {
-        f_type: TMPFS_MAGIC,
-        f_bsize: bsize,
-        f_blocks: blocks,
-        f_bfree: blocks,
-        f_bavail: blocks,
-        f_files: 0,
-        f_ffree: 0,
-        f_fsid: [0, 0],
-        f_namelen: 255,
-        f_frsize: bsize,
-        f_flags: 0,
+        f_type: stat.magic,
+        f_bsize: stat.block_size as u64,
+        f_blocks: stat.blocks,
+        f_bfree: stat.blocks_free,
+        f_bavail: stat.blocks_avail,
+        f_files: stat.files,
+        f_ffree: stat.files_free,
+        f_fsid: fsid,
+        f_namelen: stat.name_max as u64,
+        f_frsize: stat.block_size as u64,
+        f_flags: (flags | ST_VALID) as u64,
         f_spare: [0; 4],
     }
 }
 
+fn vfs_statfs(mount: MountId) -> Result<Statfs, Errno> {
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+        let stat = fs.statfs().map_err(map_vfs_err)?;
+        Ok(build_statfs(&stat, [mount_fsid(mount), 0], mounts.flags_for(mount)))
+    })
+}
+
+fn mount_fsid(mount: MountId) -> i32 {
+    match mount {
+        MountId::Root => 1,
+        MountId::Dev => 2,
+        MountId::Proc => 3,
+    }
+}
+
+/// Return the `ST_*` flags of a mount; the memfs fallback root cannot be written.
+pub fn mount_flags(mount: MountId) -> u32 {
+    match mount {
+        MountId::Root if rootfs_kind() == ROOTFS_KIND_MEMFS => ST_RDONLY | ST_RELATIME,
+        MountId::Root => ST_RELATIME,
+        MountId::Dev => ST_NOSUID | ST_RELATIME,
+        MountId::Proc => ST_NOSUID | ST_NODEV | ST_NOEXEC | ST_RELATIME,
+    }
+}
+
 fn current_proc_index() -> Option<usize> {
     let idx = crate::runtime::current_task_id()?;
     if idx < MAX_PROCS {
@@ -4410,73 +7059,339 @@ fn set_current_umask(mask: u16) -> Result<u16, Errno> {
     unsafe {
         PROC_UMASK[idx] = mask;
     }
-    Ok(old)
+    Ok(old)
+}
+
+fn init_proc_cwd(idx: usize) {
+    if idx >= MAX_PROCS {
+        return;
+    }
+    // SAFETY: 单核阶段顺序初始化 cwd。
+    unsafe {
+        PROC_CWD[idx][0] = b'/';
+        PROC_CWD_LEN[idx] = 1;
+    }
+}
+
+fn clone_proc_cwd(parent: usize, child: usize) {
+    if parent >= MAX_PROCS || child >= MAX_PROCS {
+        return;
+    }
+    // SAFETY: 单核阶段顺序复制 cwd。
+    unsafe {
+        PROC_CWD[child] = PROC_CWD[parent];
+        PROC_CWD_LEN[child] = PROC_CWD_LEN[parent];
+    }
+}
+
+fn clear_proc_cwd(idx: usize) {
+    if idx >= MAX_PROCS {
+        return;
+    }
+    // SAFETY: 单核阶段顺序清理 cwd。
+    unsafe {
+        PROC_CWD[idx] = [0; MAX_PATH_LEN];
+        PROC_CWD_LEN[idx] = 0;
+    }
+}
+
+fn current_cwd_str() -> &'static str {
+    let Some(idx) = current_proc_index() else {
+        return "/";
+    };
+    // SAFETY: 单核阶段顺序访问 cwd。
+    let len = unsafe { PROC_CWD_LEN[idx] };
+    if len == 0 || len > MAX_PATH_LEN {
+        return "/";
+    }
+    // SAFETY: cwd length is validated and bounded by MAX_PATH_LEN.
+    let bytes = unsafe { &PROC_CWD[idx][..len] };
+    core::str::from_utf8(bytes).unwrap_or("/")
+}
+
+fn set_current_cwd(path: &str) -> Result<(), Errno> {
+    let Some(idx) = current_proc_index() else {
+        return Err(Errno::Fault);
+    };
+    if !path.starts_with('/') {
+        return Err(Errno::Inval);
+    }
+    if path.is_empty() || path.len() >= MAX_PATH_LEN {
+        return Err(Errno::Range);
+    }
+    // SAFETY: 单核阶段顺序写入 cwd。
+    unsafe {
+        PROC_CWD[idx].fill(0);
+        PROC_CWD[idx][..path.len()].copy_from_slice(path.as_bytes());
+        PROC_CWD_LEN[idx] = path.len();
+    }
+    Ok(())
+}
+
+fn set_proc_exec_info(idx: usize, exe: &[u8], cmdline: &[u8]) {
+    if idx >= MAX_PROCS {
+        return;
+    }
+    let exe_len = core::cmp::min(exe.len(), MAX_PATH_LEN);
+    let cmdline_len = core::cmp::min(cmdline.len(), MAX_CMDLINE_LEN);
+    // SAFETY: 单核阶段顺序写入 exec 信息。
+    unsafe {
+        PROC_EXE[idx][..exe_len].copy_from_slice(&exe[..exe_len]);
+        PROC_EXE_LEN[idx] = exe_len;
+        PROC_CMDLINE[idx][..cmdline_len].copy_from_slice(&cmdline[..cmdline_len]);
+        PROC_CMDLINE_LEN[idx] = cmdline_len;
+    }
+}
+
+/// Copy the argv strings into `out` as NUL-terminated entries, truncating at capacity.
+fn read_user_cmdline(root_pa: usize, argv: usize, out: &mut [u8]) -> usize {
+    const MAX_ARGS: usize = 64;
+    if argv == 0 {
+        return 0;
+    }
+    let mut len = 0usize;
+    for index in 0..MAX_ARGS {
+        let Some(arg) = UserPtr::<usize>::new(argv + index * size_of::<usize>()).read(root_pa) else {
+            break;
+        };
+        if arg == 0 {
+            break;
+        }
+        let mut offset = 0usize;
+        while len < out.len() {
+            let Ok(ch) = read_user_byte(root_pa, arg + offset) else {
+                return len;
+            };
+            out[len] = ch;
+            len += 1;
+            offset += 1;
+            if ch == 0 {
+                break;
+            }
+        }
+        if len >= out.len() {
+            break;
+        }
+    }
+    len
+}
+
+/// Record the path `open_id` was opened under and the directory holding it.
+fn record_open_file(open_id: u32, path: &str, parent: Option<(MountId, InodeId)>) -> Result<(), Errno> {
+    // SAFETY: 单核阶段顺序更新打开文件名表。
+    unsafe {
+        let entry = OPEN_FILE_NAMES
+            .iter_mut()
+            .find(|entry| entry.open_id == 0)
+            .ok_or(Errno::MFile)?;
+        let len = path.len().min(MAX_PATH_LEN);
+        entry.path[..len].copy_from_slice(&path.as_bytes()[..len]);
+        entry.len = len;
+        entry.parent = parent;
+        entry.open_id = open_id;
+    }
+    Ok(())
+}
+
+fn forget_open_file(open_id: u32) {
+    // SAFETY: 单核阶段顺序更新打开文件名表。
+    unsafe {
+        if let Some(entry) = OPEN_FILE_NAMES.iter_mut().find(|entry| entry.open_id == open_id) {
+            *entry = EMPTY_OPEN_FILE_NAME;
+        }
+    }
+}
+
+fn open_file_name(open_id: u32) -> Option<&'static OpenFileName> {
+    // SAFETY: 单核阶段只读访问打开文件名表。
+    unsafe { OPEN_FILE_NAMES.iter().find(|entry| entry.open_id == open_id && open_id != 0) }
+}
+
+/// Detach open files from the directory entry `name` in `dir` once it is unlinked.
+fn unlink_open_files(dir: (MountId, InodeId), name: &str) {
+    // SAFETY: 单核阶段顺序更新打开文件名表。
+    unsafe {
+        for entry in OPEN_FILE_NAMES.iter_mut() {
+            if entry.open_id != 0 && entry.parent == Some(dir) && entry.name() == name {
+                entry.parent = None;
+            }
+        }
+    }
+}
+
+/// Move open files named `old_name` in `old_dir` over to `new_path` in `new_dir`.
+fn rename_open_files(old_dir: (MountId, InodeId), old_name: &str, new_dir: (MountId, InodeId), new_path: &str) {
+    let len = new_path.len().min(MAX_PATH_LEN);
+    // SAFETY: 单核阶段顺序更新打开文件名表。
+    unsafe {
+        for entry in OPEN_FILE_NAMES.iter_mut() {
+            if entry.open_id != 0 && entry.parent == Some(old_dir) && entry.name() == old_name {
+                entry.path[..len].copy_from_slice(&new_path.as_bytes()[..len]);
+                entry.len = len;
+                entry.parent = Some(new_dir);
+            }
+        }
+    }
+}
+
+/// Report `mask` to watches on an inode and, when `entry` names the directory
+/// entry it was reached through, to watches on that directory.
+#[inline(never)]
+fn fsnotify(mount: MountId, inode: InodeId, mask: u32, entry: Option<((MountId, InodeId), &str)>) {
+    if !inotify::watching() {
+        return;
+    }
+    let is_dir = with_mounts(|mounts| {
+        mounts
+            .fs_for(mount)
+            .and_then(|fs| fs.metadata(inode).ok())
+            .is_some_and(|meta| meta.file_type == FileType::Dir)
+    });
+    let mask = if is_dir { mask | inotify::IN_ISDIR } else { mask };
+    inotify::notify((mount, inode), mask, 0, None);
+    if let Some((dir, name)) = entry {
+        inotify::notify(dir, mask, 0, Some(name));
+    }
+}
+
+/// Report `mask` for an open file, also to its directory while the name it was opened under still exists.
+fn fsnotify_file(handle: VfsHandle, mask: u32) {
+    let entry = open_file_name(handle.open_id).and_then(|name| Some((name.parent?, name.name())));
+    fsnotify(handle.mount, handle.inode, mask, entry);
+}
+
+/// Report `mask` for the file at the user path `pathname` and to the directory holding it.
+fn fsnotify_at(root_pa: usize, pathname: usize, mask: u32) {
+    if !inotify::watching() {
+        return;
+    }
+    let mut buf = [0u8; MAX_PATH_LEN];
+    let Ok(path) = read_user_path_abs(root_pa, pathname, &mut buf) else {
+        return;
+    };
+    let Ok(((mount, inode), parent)) = with_mounts(|mounts| {
+        let target = mounts.resolve_path(path)?;
+        let (parent_mount, parent, name) = mounts.resolve_parent(path)?;
+        Ok::<_, VfsError>((target, ((parent_mount, parent), name)))
+    }) else {
+        return;
+    };
+    fsnotify(mount, inode, mask, Some(parent));
+}
+
+/// Return the procfs instance mounted at `/proc`.
+pub fn procfs() -> &'static procfs::ProcFs {
+    &PROCFS
+}
+
+/// Return the filesystem type name of the mounted root.
+pub fn rootfs_type() -> &'static str {
+    match rootfs_kind() {
+        ROOTFS_KIND_EXT4 => "ext4",
+        ROOTFS_KIND_FAT32 => "vfat",
+        _ => "ramfs",
+    }
+}
+
+/// Return the working directory of the process at `idx`.
+pub fn proc_cwd(idx: usize) -> Option<&'static str> {
+    if idx >= MAX_PROCS {
+        return None;
+    }
+    // SAFETY: 单核阶段只读访问 cwd。
+    let bytes = unsafe { &PROC_CWD[idx][..PROC_CWD_LEN[idx]] };
+    if bytes.is_empty() {
+        return None;
+    }
+    core::str::from_utf8(bytes).ok()
+}
+
+/// Return the last executed image path of the process at `idx`.
+pub fn proc_exe(idx: usize) -> Option<&'static str> {
+    if idx >= MAX_PROCS {
+        return None;
+    }
+    // SAFETY: 单核阶段只读访问 exec 信息。
+    let bytes = unsafe { &PROC_EXE[idx][..PROC_EXE_LEN[idx]] };
+    if bytes.is_empty() {
+        return None;
+    }
+    core::str::from_utf8(bytes).ok()
 }
 
-fn init_proc_cwd(idx: usize) {
+/// Return the NUL-separated argv of the process at `idx`.
+pub fn proc_cmdline(idx: usize) -> &'static [u8] {
     if idx >= MAX_PROCS {
-        return;
-    }
-    // SAFETY: 单核阶段顺序初始化 cwd。
-    unsafe {
-        PROC_CWD[idx][0] = b'/';
-        PROC_CWD_LEN[idx] = 1;
+        return &[];
     }
+    // SAFETY: 单核阶段只读访问 exec 信息。
+    unsafe { &PROC_CMDLINE[idx][..PROC_CMDLINE_LEN[idx]] }
 }
 
-fn clone_proc_cwd(parent: usize, child: usize) {
-    if parent >= MAX_PROCS || child >= MAX_PROCS {
-        return;
-    }
-    // SAFETY: 单核阶段顺序复制 cwd。
-    unsafe {
-        PROC_CWD[child] = PROC_CWD[parent];
-        PROC_CWD_LEN[child] = PROC_CWD_LEN[parent];
-    }
+/// Return the number of fd slots available to each process.
+pub fn fd_capacity() -> usize {
+    FD_TABLE_BASE + FD_TABLE_SLOTS
 }
 
-fn clear_proc_cwd(idx: usize) {
+/// Return the file mode creation mask of the process at `idx`.
+pub fn proc_umask(idx: usize) -> u16 {
+    // SAFETY: 单核阶段只读访问 umask。
+    unsafe { PROC_UMASK.get(idx).copied().unwrap_or(0) }
+}
+
+fn proc_fd_object(idx: usize, fd: usize) -> Option<FdObject> {
     if idx >= MAX_PROCS {
-        return;
+        return None;
     }
-    // SAFETY: 单核阶段顺序清理 cwd。
-    unsafe {
-        PROC_CWD[idx] = [0; MAX_PATH_LEN];
-        PROC_CWD_LEN[idx] = 0;
+    if let Some(object) = stdio_object(fd) {
+        // SAFETY: 单核阶段只读访问重定向表。
+        let redirect = unsafe { STDIO_REDIRECT[idx][fd] };
+        return Some(redirect.map_or(object, |entry| entry.object));
     }
+    let slot = fd_table_index(fd)?;
+    // SAFETY: 单核阶段只读访问 fd 表。
+    let object = unsafe { FD_TABLES[idx][slot].object };
+    (object != FdObject::Empty).then_some(object)
 }
 
-fn current_cwd_str() -> &'static str {
-    let Some(idx) = current_proc_index() else {
-        return "/";
-    };
-    // SAFETY: 单核阶段顺序访问 cwd。
-    let len = unsafe { PROC_CWD_LEN[idx] };
-    if len == 0 || len > MAX_PATH_LEN {
-        return "/";
+/// Fill `out` with the open fds of the process at `idx`.
+pub fn proc_fds(idx: usize, out: &mut [u32]) -> usize {
+    let mut count = 0;
+    for fd in 0..FD_TABLE_BASE + FD_TABLE_SLOTS {
+        if count >= out.len() {
+            break;
+        }
+        if proc_fd_object(idx, fd).is_some() {
+            out[count] = fd as u32;
+            count += 1;
+        }
     }
-    // SAFETY: cwd length is validated and bounded by MAX_PATH_LEN.
-    let bytes = unsafe { &PROC_CWD[idx][..len] };
-    core::str::from_utf8(bytes).unwrap_or("/")
+    count
 }
 
-fn set_current_cwd(path: &str) -> Result<(), Errno> {
-    let Some(idx) = current_proc_index() else {
-        return Err(Errno::Fault);
+/// Write the `/proc/<pid>/fd/<fd>` link target of the process at `idx`.
+pub fn write_fd_target(idx: usize, fd: usize, out: &mut dyn core::fmt::Write) -> core::fmt::Result {
+    let Some(object) = proc_fd_object(idx, fd) else {
+        return Err(core::fmt::Error);
     };
-    if !path.starts_with('/') {
-        return Err(Errno::Inval);
-    }
-    if path.is_empty() || path.len() >= MAX_PATH_LEN {
-        return Err(Errno::Range);
-    }
-    // SAFETY: 单核阶段顺序写入 cwd。
-    unsafe {
-        PROC_CWD[idx].fill(0);
-        PROC_CWD[idx][..path.len()].copy_from_slice(path.as_bytes());
-        PROC_CWD_LEN[idx] = path.len();
+    match object {
+        FdObject::Empty => Err(core::fmt::Error),
+        FdObject::Stdin | FdObject::Stdout | FdObject::Stderr => out.write_str("/dev/console"),
+        FdObject::Vfs(handle) => match open_file_name(handle.open_id) {
+            Some(name) if name.parent.is_some() => out.write_str(name.path()),
+            Some(name) => write!(out, "{} (deleted)", name.path()),
+            None => write!(out, "anon_inode:[{}]", handle.inode),
+        },
+        FdObject::PipeRead(id) | FdObject::PipeWrite(id) | FdObject::PipeRdWr(id) => {
+            write!(out, "pipe:[{}]", id)
+        }
+        FdObject::Socket(id) => write!(out, "socket:[{}]", id),
+        FdObject::Eventfd(_) => out.write_str("anon_inode:[eventfd]"),
+        FdObject::Timerfd(_) => out.write_str("anon_inode:[timerfd]"),
+        FdObject::Epoll(_) => out.write_str("anon_inode:[eventpoll]"),
+        FdObject::Inotify(_) => out.write_str("anon_inode:inotify"),
+        FdObject::Unix(id) => write!(out, "socket:[{}]", unix_socket::INODE_BASE + id),
     }
-    Ok(())
 }
 
 fn normalize_path<'a>(base: &str, path: &str, out: &'a mut [u8]) -> Result<&'a str, Errno> {
@@ -4544,6 +7459,11 @@ fn normalize_path<'a>(base: &str, path: &str, out: &'a mut [u8]) -> Result<&'a s
 fn read_user_path_abs<'a>(root_pa: usize, path: usize, buf: &'a mut [u8]) -> Result<&'a str, Errno> {
     let mut raw_buf = [0u8; MAX_PATH_LEN];
     let raw = read_user_path_str(root_pa, path, &mut raw_buf)?;
+    abs_path(raw, buf)
+}
+
+/// Resolve `raw` against the current working directory.
+fn abs_path<'a>(raw: &str, buf: &'a mut [u8]) -> Result<&'a str, Errno> {
     let base = if raw.starts_with('/') {
         "/"
     } else {
@@ -4686,29 +7606,12 @@ fn dup_to_fd(newfd: usize, entry: FdEntry) -> Result<usize, Errno> {
             pipe_release(old.object);
         }
         FD_TABLES[proc_idx][idx] = entry;
+        release_vfs_file(proc_idx, old);
     }
     pipe_acquire(entry.object);
     Ok(newfd)
 }
 
-fn replace_socket_fd(fd: usize, socket_id: axnet::SocketId) -> Result<(), Errno> {
-    if stdio_object(fd).is_some() {
-        return Err(Errno::Badf);
-    }
-    let proc_idx = current_proc_index().ok_or(Errno::Badf)?;
-    let idx = fd_table_index(fd).ok_or(Errno::Badf)?;
-    // SAFETY: 单核早期阶段，fd 表串行更新。
-    unsafe {
-        let entry = &mut FD_TABLES[proc_idx][idx];
-        if !matches!(entry.object, FdObject::Socket(_)) {
-            return Err(Errno::Badf);
-        }
-        entry.object = FdObject::Socket(socket_id);
-        entry.offset = 0;
-    }
-    Ok(())
-}
-
 fn close_fd(fd: usize) -> Result<usize, Errno> {
     let proc_idx = current_proc_index().ok_or(Errno::Badf)?;
     if stdio_object(fd).is_some() {
@@ -4717,6 +7620,7 @@ fn close_fd(fd: usize) -> Result<usize, Errno> {
             if let Some(old) = STDIO_REDIRECT[proc_idx][fd] {
                 pipe_release(old.object);
                 STDIO_REDIRECT[proc_idx][fd] = None;
+                release_vfs_file(proc_idx, old);
             }
         }
         return Ok(0);
@@ -4729,10 +7633,8 @@ fn close_fd(fd: usize) -> Result<usize, Errno> {
             return Err(Errno::Badf);
         }
         FD_TABLES[proc_idx][idx] = EMPTY_FD_ENTRY;
-        if let FdObject::Socket(socket_id) = old.object {
-            let _ = axnet::socket_close(socket_id);
-        }
         pipe_release(old.object);
+        release_vfs_file(proc_idx, old);
     }
     Ok(0)
 }
@@ -4747,20 +7649,17 @@ fn close_cloexec_fds() {
             if entry.object == FdObject::Empty || (entry.flags & O_CLOEXEC) == 0 {
                 continue;
             }
-            if let FdObject::Socket(socket_id) = entry.object {
-                let _ = axnet::socket_close(socket_id);
-            }
-            pipe_release(entry.object);
+            let old = *entry;
+            pipe_release(old.object);
             FD_TABLES[proc_idx][idx] = EMPTY_FD_ENTRY;
+            release_vfs_file(proc_idx, old);
         }
         for fd in 0..STDIO_REDIRECT[proc_idx].len() {
             if let Some(entry) = STDIO_REDIRECT[proc_idx][fd] {
                 if (entry.flags & O_CLOEXEC) != 0 {
-                    if let FdObject::Socket(socket_id) = entry.object {
-                        let _ = axnet::socket_close(socket_id);
-                    }
                     pipe_release(entry.object);
                     STDIO_REDIRECT[proc_idx][fd] = None;
+                    release_vfs_file(proc_idx, entry);
                 }
             } else if (STDIO_FLAGS[proc_idx][fd] & O_CLOEXEC) != 0 {
                 STDIO_FLAGS[proc_idx][fd] &= !O_CLOEXEC;
@@ -4776,13 +7675,14 @@ fn set_stdio_redirect(fd: usize, entry: FdEntry) -> Result<usize, Errno> {
     let proc_idx = current_proc_index().ok_or(Errno::Badf)?;
     // SAFETY: 单核早期阶段访问重定向表。
     unsafe {
-        if let Some(old) = STDIO_REDIRECT[proc_idx][fd] {
-            if let FdObject::Socket(socket_id) = old.object {
-                let _ = axnet::socket_close(socket_id);
-            }
+        let old = STDIO_REDIRECT[proc_idx][fd];
+        if let Some(old) = old {
             pipe_release(old.object);
         }
         STDIO_REDIRECT[proc_idx][fd] = Some(entry);
+        if let Some(old) = old {
+            release_vfs_file(proc_idx, old);
+        }
     }
     pipe_acquire(entry.object);
     Ok(fd)
@@ -4859,15 +7759,17 @@ fn clear_fd_table(idx: usize) {
     unsafe {
         for entry in FD_TABLES[idx].iter_mut() {
             if entry.object != FdObject::Empty {
-                pipe_release(entry.object);
+                let old = *entry;
+                pipe_release(old.object);
                 *entry = EMPTY_FD_ENTRY;
+                release_vfs_file(idx, old);
             }
         }
         for slot in STDIO_REDIRECT[idx].iter_mut() {
-            if let Some(entry) = *slot {
+            if let Some(entry) = slot.take() {
                 pipe_release(entry.object);
+                release_vfs_file(idx, entry);
             }
-            *slot = None;
         }
         for flag in STDIO_FLAGS[idx].iter_mut() {
             *flag = 0;
@@ -4879,11 +7781,13 @@ fn clear_fd_table(idx: usize) {
 pub fn init_fd_table(task_id: TaskId) {
     clear_fd_table(task_id);
     init_proc_cwd(task_id);
+    cred::init(task_id);
     if task_id < MAX_PROCS {
         // SAFETY: 单核阶段顺序初始化 umask。
         unsafe {
             PROC_UMASK[task_id] = 0;
         }
+        set_proc_exec_info(task_id, b"", b"");
     }
 }
 
@@ -4895,7 +7799,8 @@ pub fn clone_fd_table(parent: TaskId, child: TaskId) {
     clear_fd_table(child);
     // SAFETY: 单核早期阶段按进程顺序复制 fd 表。
     unsafe {
-        FD_TABLES[child] = FD_TABLES[parent];
+        // 直接按内存拷贝：整表赋值在未优化构建中会先落到内核栈上的临时副本。
+        core::ptr::copy_nonoverlapping(FD_TABLES[parent].as_ptr(), FD_TABLES[child].as_mut_ptr(), FD_TABLE_SLOTS);
         STDIO_REDIRECT[child] = STDIO_REDIRECT[parent];
         STDIO_FLAGS[child] = STDIO_FLAGS[parent];
         for entry in FD_TABLES[child].iter() {
@@ -4908,9 +7813,14 @@ pub fn clone_fd_table(parent: TaskId, child: TaskId) {
         }
     }
     clone_proc_cwd(parent, child);
-    // SAFETY: 单核阶段顺序复制 umask。
+    cred::clone(parent, child);
+    // SAFETY: 单核阶段顺序复制 umask 与 exec 信息。
     unsafe {
         PROC_UMASK[child] = PROC_UMASK[parent];
+        PROC_EXE[child] = PROC_EXE[parent];
+        PROC_EXE_LEN[child] = PROC_EXE_LEN[parent];
+        PROC_CMDLINE[child] = PROC_CMDLINE[parent];
+        PROC_CMDLINE_LEN[child] = PROC_CMDLINE_LEN[parent];
     }
 }
 
@@ -4919,9 +7829,10 @@ pub fn release_fd_table(task_id: TaskId) {
     clear_fd_table(task_id);
     clear_proc_cwd(task_id);
     if task_id < MAX_PROCS {
-        // SAFETY: 单核阶段顺序清理 umask。
+        // SAFETY: 单核阶段顺序清理 umask；exe 保留给僵尸进程的 comm 显示。
         unsafe {
             PROC_UMASK[task_id] = 0;
+            PROC_CMDLINE_LEN[task_id] = 0;
         }
     }
 }
@@ -4933,8 +7844,11 @@ fn alloc_pipe() -> Option<usize> {
             if !pipe.used {
                 *pipe = Pipe {
                     used: true,
+                    fifo: None,
                     readers: 0,
                     writers: 0,
+                    reader_opens: 0,
+                    writer_opens: 0,
                     read_pos: 0,
                     write_pos: 0,
                     len: 0,
@@ -5021,9 +7935,10 @@ fn alloc_epoll(flags: usize) -> Option<usize> {
 }
 
 fn pipe_acquire(object: FdObject) {
-    let (pipe_id, is_read) = match object {
-        FdObject::PipeRead(id) => (id, true),
-        FdObject::PipeWrite(id) => (id, false),
+    let (pipe_id, is_read, is_write) = match object {
+        FdObject::PipeRead(id) => (id, true, false),
+        FdObject::PipeWrite(id) => (id, false, true),
+        FdObject::PipeRdWr(id) => (id, true, true),
         FdObject::Eventfd(id) => {
             if id < EVENTFD_SLOTS {
                 // SAFETY: eventfd table is updated sequentially at early boot.
@@ -5057,28 +7972,55 @@ fn pipe_acquire(object: FdObject) {
             }
             return;
         }
+        FdObject::Inotify(id) => {
+            inotify::acquire(id);
+            return;
+        }
+        FdObject::Unix(id) => {
+            unix_socket::acquire(id);
+            return;
+        }
+        FdObject::Socket(id) => {
+            let _ = axnet::socket_acquire(id);
+            return;
+        }
         _ => return,
     };
     if pipe_id >= PIPE_SLOTS {
         return;
     }
     // SAFETY: 单核早期阶段串行更新 pipe 表。
-    unsafe {
-        if !PIPES[pipe_id].used {
+    let named = unsafe {
+        let pipe = &mut PIPES[pipe_id];
+        if !pipe.used {
             return;
         }
         if is_read {
-            PIPES[pipe_id].readers += 1;
-        } else {
-            PIPES[pipe_id].writers += 1;
+            pipe.readers += 1;
+            pipe.reader_opens += 1;
+        }
+        if is_write {
+            pipe.writers += 1;
+            pipe.writer_opens += 1;
+        }
+        pipe.fifo.is_some()
+    };
+    if named {
+        // FIFO 打开端可能正在等待对端出现。
+        if is_read {
+            let _ = crate::runtime::wake_all(pipe_write_queue(pipe_id));
+        }
+        if is_write {
+            let _ = crate::runtime::wake_all(pipe_read_queue(pipe_id));
         }
     }
 }
 
 fn pipe_release(object: FdObject) {
-    let (pipe_id, is_read) = match object {
-        FdObject::PipeRead(id) => (id, true),
-        FdObject::PipeWrite(id) => (id, false),
+    let (pipe_id, is_read, is_write) = match object {
+        FdObject::PipeRead(id) => (id, true, false),
+        FdObject::PipeWrite(id) => (id, false, true),
+        FdObject::PipeRdWr(id) => (id, true, true),
         FdObject::Eventfd(id) => {
             if id < EVENTFD_SLOTS {
                 // SAFETY: eventfd table is updated sequentially at early boot.
@@ -5121,6 +8063,21 @@ fn pipe_release(object: FdObject) {
             }
             return;
         }
+        FdObject::Inotify(id) => {
+            inotify::release(id);
+            return;
+        }
+        FdObject::Unix(id) => {
+            unix_release(id);
+            return;
+        }
+        FdObject::Socket(id) => {
+            // 最后一个描述符（含 SCM_RIGHTS 在途引用）关闭时才真正关闭 socket。
+            if axnet::socket_release(id) == Ok(true) {
+                close_net_socket(id);
+            }
+            return;
+        }
         _ => return,
     };
     if pipe_id >= PIPE_SLOTS {
@@ -5131,11 +8088,10 @@ fn pipe_release(object: FdObject) {
         if !PIPES[pipe_id].used {
             return;
         }
-        if is_read {
-            if PIPES[pipe_id].readers > 0 {
-                PIPES[pipe_id].readers -= 1;
-            }
-        } else if PIPES[pipe_id].writers > 0 {
+        if is_read && PIPES[pipe_id].readers > 0 {
+            PIPES[pipe_id].readers -= 1;
+        }
+        if is_write && PIPES[pipe_id].writers > 0 {
             PIPES[pipe_id].writers -= 1;
         }
     }
@@ -5165,47 +8121,18 @@ fn pipe_read(pipe_id: usize, root_pa: usize, buf: usize, len: usize, nonblock: b
     if len == 0 {
         return Ok(0);
     }
-    loop {
-        // SAFETY: pipe_id bounds checked; pipe table is read under serialized access.
-        let (used, available, writers) = unsafe {
-            let pipe = &PIPES[pipe_id];
-            (pipe.used, pipe.len, pipe.writers)
-        };
-        if !used {
-            return Err(Errno::Badf);
-        }
-        if available == 0 {
-            if writers == 0 {
-                return Ok(0);
-            }
-            if nonblock || !can_block_current() {
-                return Err(Errno::Again);
-            }
-            crate::runtime::block_current(pipe_read_queue(pipe_id));
-            continue;
-        }
-        break;
+    if !pipe_wait_readable(pipe_id, nonblock)? {
+        return Ok(0);
     }
-    // SAFETY: 单核早期阶段串行访问 pipe。
-    let pipe = unsafe { &mut PIPES[pipe_id] };
-    let to_read = min(len, pipe.len);
-    let mut remaining = to_read;
     let mut offset = 0usize;
-    while remaining > 0 {
-        let chunk = min(remaining, PIPE_BUFFER_SIZE - pipe.read_pos);
+    pipe_drain(pipe_id, len, |src| {
         let dst = buf.checked_add(offset).ok_or(Errno::Fault)?;
-        let src = &pipe.buf[pipe.read_pos..pipe.read_pos + chunk];
-        UserSlice::new(dst, chunk)
+        UserSlice::new(dst, src.len())
             .copy_from_slice(root_pa, src)
             .ok_or(Errno::Fault)?;
-        pipe.read_pos = (pipe.read_pos + chunk) % PIPE_BUFFER_SIZE;
-        pipe.len -= chunk;
-        remaining -= chunk;
-        offset += chunk;
-    }
-    let _ = crate::runtime::wake_one(pipe_write_queue(pipe_id));
-    let _ = crate::runtime::wake_all(ppoll_wait_queue());
-    Ok(to_read)
+        offset += src.len();
+        Ok(src.len())
+    })
 }
 
 fn pipe_write(pipe_id: usize, root_pa: usize, buf: usize, len: usize, nonblock: bool) -> Result<usize, Errno> {
@@ -5215,49 +8142,366 @@ fn pipe_write(pipe_id: usize, root_pa: usize, buf: usize, len: usize, nonblock:
     if len == 0 {
         return Ok(0);
     }
+    pipe_wait_writable(pipe_id, nonblock)?;
+    let mut offset = 0usize;
+    pipe_fill(pipe_id, len, |dst| {
+        let src = buf.checked_add(offset).ok_or(Errno::Fault)?;
+        UserSlice::new(src, dst.len())
+            .copy_to_slice(root_pa, dst)
+            .ok_or(Errno::Fault)?;
+        offset += dst.len();
+        Ok(dst.len())
+    })
+}
+
+/// Wait until the pipe holds data; `Ok(false)` means EOF (empty with no writers).
+fn pipe_wait_readable(pipe_id: usize, nonblock: bool) -> Result<bool, Errno> {
+    loop {
+        // SAFETY: pipe_id bounds checked; pipe table is read under serialized access.
+        let (used, available, writers) = unsafe {
+            let pipe = PIPES.get(pipe_id).ok_or(Errno::Badf)?;
+            (pipe.used, pipe.len, pipe.writers)
+        };
+        if !used {
+            return Err(Errno::Badf);
+        }
+        if available > 0 {
+            return Ok(true);
+        }
+        if writers == 0 {
+            return Ok(false);
+        }
+        if nonblock || !can_block_current() {
+            return Err(Errno::Again);
+        }
+        crate::runtime::block_current(pipe_read_queue(pipe_id));
+    }
+}
+
+/// Wait until the pipe has free space and at least one reader.
+fn pipe_wait_writable(pipe_id: usize, nonblock: bool) -> Result<(), Errno> {
     loop {
         // SAFETY: pipe_id bounds checked; pipe table is read under serialized access.
         let (used, readers, used_len) = unsafe {
-            let pipe = &PIPES[pipe_id];
+            let pipe = PIPES.get(pipe_id).ok_or(Errno::Badf)?;
             (pipe.used, pipe.readers, pipe.len)
         };
         if !used {
             return Err(Errno::Badf);
         }
-        if readers == 0 {
-            return Err(Errno::PipeBroken);
+        if readers == 0 {
+            return Err(Errno::PipeBroken);
+        }
+        if used_len < PIPE_BUFFER_SIZE {
+            return Ok(());
+        }
+        if nonblock || !can_block_current() {
+            return Err(Errno::Again);
+        }
+        crate::runtime::block_current(pipe_write_queue(pipe_id));
+    }
+}
+
+/// Hand up to `max` buffered bytes to `sink` in ring-contiguous pieces, consuming what it takes.
+///
+/// `sink` returns how many bytes it accepted; a short count or an error after
+/// progress ends the drain with the bytes moved so far.
+fn pipe_drain(
+    pipe_id: usize,
+    max: usize,
+    mut sink: impl FnMut(&[u8]) -> Result<usize, Errno>,
+) -> Result<usize, Errno> {
+    // SAFETY: 单核早期阶段串行访问 pipe。
+    let pipe = unsafe { &mut PIPES[pipe_id] };
+    let mut done = 0usize;
+    while done < max && pipe.len > 0 {
+        let chunk = min(max - done, min(pipe.len, PIPE_BUFFER_SIZE - pipe.read_pos));
+        let taken = match sink(&pipe.buf[pipe.read_pos..pipe.read_pos + chunk]) {
+            Ok(taken) => min(taken, chunk),
+            Err(_) if done > 0 => break,
+            Err(err) => return Err(err),
+        };
+        pipe.read_pos = (pipe.read_pos + taken) % PIPE_BUFFER_SIZE;
+        pipe.len -= taken;
+        done += taken;
+        if taken < chunk {
+            break;
+        }
+    }
+    if done > 0 {
+        let _ = crate::runtime::wake_one(pipe_write_queue(pipe_id));
+        let _ = crate::runtime::wake_all(ppoll_wait_queue());
+    }
+    Ok(done)
+}
+
+/// Let `source` fill up to `max` bytes of free ring space in contiguous pieces.
+///
+/// `source` returns how many bytes it produced; a short count or an error
+/// after progress ends the fill with the bytes queued so far.
+fn pipe_fill(
+    pipe_id: usize,
+    max: usize,
+    mut source: impl FnMut(&mut [u8]) -> Result<usize, Errno>,
+) -> Result<usize, Errno> {
+    // SAFETY: 单核早期阶段串行访问 pipe。
+    let pipe = unsafe { &mut PIPES[pipe_id] };
+    let mut done = 0usize;
+    while done < max && pipe.len < PIPE_BUFFER_SIZE {
+        let space = min(PIPE_BUFFER_SIZE - pipe.len, PIPE_BUFFER_SIZE - pipe.write_pos);
+        let chunk = min(max - done, space);
+        let produced = match source(&mut pipe.buf[pipe.write_pos..pipe.write_pos + chunk]) {
+            Ok(produced) => min(produced, chunk),
+            Err(_) if done > 0 => break,
+            Err(err) => return Err(err),
+        };
+        pipe.write_pos = (pipe.write_pos + produced) % PIPE_BUFFER_SIZE;
+        pipe.len += produced;
+        done += produced;
+        if produced < chunk {
+            break;
+        }
+    }
+    if done > 0 {
+        let _ = crate::runtime::wake_one(pipe_read_queue(pipe_id));
+        let _ = crate::runtime::wake_all(ppoll_wait_queue());
+    }
+    Ok(done)
+}
+
+fn pipe_reader_id(object: FdObject) -> Option<usize> {
+    match object {
+        FdObject::PipeRead(id) | FdObject::PipeRdWr(id) => Some(id),
+        _ => None,
+    }
+}
+
+fn pipe_writer_id(object: FdObject) -> Option<usize> {
+    match object {
+        FdObject::PipeWrite(id) | FdObject::PipeRdWr(id) => Some(id),
+        _ => None,
+    }
+}
+
+/// Move up to `len` bytes from `input` to `output` without touching user memory.
+///
+/// A pipe end is drained or filled in place through its ring and file-to-TCP
+/// copies read straight into the send buffer; other copies bounce through a
+/// block-sized kernel buffer. File ends use
+/// the explicit positions, which the caller stores back afterwards.
+#[inline(never)]
+fn transfer(
+    input: FdEntry,
+    in_pos: usize,
+    output: FdEntry,
+    out_pos: usize,
+    len: usize,
+    nonblock: bool,
+) -> Result<usize, Errno> {
+    if len == 0 {
+        return Ok(0);
+    }
+    loop {
+        // 回调内部一律非阻塞：等待放在 ring 借用之外进行。
+        let result = if let Some(pipe_id) = pipe_reader_id(input.object) {
+            if !pipe_wait_readable(pipe_id, nonblock)? {
+                return Ok(0);
+            }
+            let mut done = 0usize;
+            pipe_drain(pipe_id, len, |src| {
+                let written = kernel_write(output, out_pos + done, src, true)?;
+                done += written;
+                Ok(written)
+            })
+        } else if let Some(pipe_id) = pipe_writer_id(output.object) {
+            pipe_wait_writable(pipe_id, nonblock)?;
+            let mut done = 0usize;
+            pipe_fill(pipe_id, len, |dst| {
+                let read = kernel_read(input, in_pos + done, dst, true)?;
+                done += read;
+                Ok(read)
+            })
+        } else {
+            bounce_copy(input, in_pos, output, out_pos, len)
+        };
+        match result {
+            Err(Errno::Again) if !nonblock && can_block_current() => match pipe_writer_id(output.object) {
+                Some(pipe_id) if pipe_reader_id(input.object).is_some() => pipe_wait_writable(pipe_id, false)?,
+                // 其余 EAGAIN 只可能来自 socket 一端。
+                _ => crate::runtime::block_current(crate::runtime::net_wait_queue()),
+            },
+            result => return result,
+        }
+    }
+}
+
+/// Copy between two non-pipe ends; the input is always a file (sendfile), so a short
+/// write loses nothing and the next call re-reads from the returned position.
+///
+/// The mount table is built once for the whole copy. A TCP output takes the file
+/// data straight into its send buffer; anything else goes through `TRANSFER_CHUNK`.
+fn bounce_copy(input: FdEntry, in_pos: usize, output: FdEntry, out_pos: usize, len: usize) -> Result<usize, Errno> {
+    let FdObject::Vfs(src) = input.object else {
+        return Err(Errno::Inval);
+    };
+    with_mounts(|mounts| {
+        let fs = mounts.fs_for(src.mount).ok_or(Errno::NoEnt)?;
+        if let FdObject::Socket(socket_id) = output.object {
+            let mut read_err = None;
+            let mut pos = in_pos as u64;
+            let sent = axnet::socket_send_with(socket_id, len, |buf| match fs.read_at(src.inode, pos, buf) {
+                Ok(read) => {
+                    pos += read as u64;
+                    read
+                }
+                Err(err) => {
+                    read_err = Some(map_vfs_err(err));
+                    0
+                }
+            });
+            match sent {
+                Ok(0) => return read_err.map_or(Ok(0), Err),
+                Ok(sent) => return Ok(sent),
+                // 数据报 socket 不支持直接填充，退回中转缓冲区。
+                Err(axnet::NetError::Unsupported) => {}
+                Err(err) => return Err(map_net_err(err)),
+            }
+        }
+        let mut scratch = [0u8; TRANSFER_CHUNK];
+        let mut total = 0usize;
+        while total < len {
+            let chunk = min(len - total, scratch.len());
+            let read = match fs.read_at(src.inode, (in_pos + total) as u64, &mut scratch[..chunk]) {
+                Ok(read) => read,
+                Err(_) if total > 0 => break,
+                Err(err) => return Err(map_vfs_err(err)),
+            };
+            if read == 0 {
+                break;
+            }
+            let written = match output.object {
+                FdObject::Vfs(dst) if dst.file_type == FileType::File => {
+                    let dst_fs = mounts.fs_for(dst.mount).ok_or(Errno::NoEnt)?;
+                    dst_fs.write_at(dst.inode, (out_pos + total) as u64, &scratch[..read]).map_err(map_vfs_err)
+                }
+                _ => kernel_write(output, out_pos + total, &scratch[..read], true),
+            };
+            let written = match written {
+                Ok(written) => written,
+                Err(_) if total > 0 => break,
+                Err(err) => return Err(err),
+            };
+            total += written;
+            if written < read {
+                break;
+            }
+        }
+        Ok(total)
+    })
+}
+
+/// Read into a kernel buffer from a regular file (at `offset`), pipe or socket.
+fn kernel_read(entry: FdEntry, offset: usize, buf: &mut [u8], nonblock: bool) -> Result<usize, Errno> {
+    match entry.object {
+        FdObject::Vfs(handle) => {
+            match handle.file_type {
+                FileType::File => {}
+                FileType::Dir => return Err(Errno::IsDir),
+                _ => return Err(Errno::Inval),
+            }
+            with_mounts(|mounts| {
+                let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+                fs.read_at(handle.inode, offset as u64, buf).map_err(map_vfs_err)
+            })
+        }
+        FdObject::PipeRead(pipe_id) | FdObject::PipeRdWr(pipe_id) => {
+            if !pipe_wait_readable(pipe_id, nonblock)? {
+                return Ok(0);
+            }
+            let mut filled = 0usize;
+            pipe_drain(pipe_id, buf.len(), |src| {
+                buf[filled..filled + src.len()].copy_from_slice(src);
+                filled += src.len();
+                Ok(src.len())
+            })
+        }
+        FdObject::Socket(socket_id) => loop {
+            match axnet::socket_recv(socket_id, buf, false) {
+                Ok(received) => return Ok(received.len),
+                Err(axnet::NetError::WouldBlock) => {
+                    if nonblock || !can_block_current() {
+                        return Err(Errno::Again);
+                    }
+                    crate::runtime::block_current(crate::runtime::net_wait_queue());
+                }
+                Err(err) => return Err(map_net_err(err)),
+            }
+        },
+        FdObject::Unix(id) => {
+            let flags = if nonblock { MSG_DONTWAIT } else { 0 };
+            let received = unix_recv(id, &entry, flags, buf.len(), |offset, src| {
+                buf[offset..offset + src.len()].copy_from_slice(src);
+                true
+            })?;
+            unix_discard_rights(&received, flags);
+            Ok(received.len)
+        }
+        FdObject::Empty => Err(Errno::Badf),
+        _ => Err(Errno::Inval),
+    }
+}
+
+/// Write a kernel buffer to a regular file (at `offset`), the console, a pipe or a socket.
+fn kernel_write(entry: FdEntry, offset: usize, buf: &[u8], nonblock: bool) -> Result<usize, Errno> {
+    match entry.object {
+        FdObject::Vfs(handle) => {
+            match handle.file_type {
+                FileType::File => {}
+                FileType::Dir => return Err(Errno::IsDir),
+                _ => return Err(Errno::Inval),
+            }
+            with_mounts(|mounts| {
+                let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
+                fs.write_at(handle.inode, offset as u64, buf).map_err(map_vfs_err)
+            })
+        }
+        FdObject::Stdout | FdObject::Stderr => {
+            for &byte in buf {
+                sbi::console_putchar(byte);
+            }
+            Ok(buf.len())
+        }
+        FdObject::PipeWrite(pipe_id) | FdObject::PipeRdWr(pipe_id) => {
+            pipe_wait_writable(pipe_id, nonblock)?;
+            let mut taken = 0usize;
+            pipe_fill(pipe_id, buf.len(), |dst| {
+                dst.copy_from_slice(&buf[taken..taken + dst.len()]);
+                taken += dst.len();
+                Ok(dst.len())
+            })
         }
-        let avail = PIPE_BUFFER_SIZE.saturating_sub(used_len);
-        if avail == 0 {
-            if nonblock || !can_block_current() {
-                return Err(Errno::Again);
+        FdObject::Socket(socket_id) => loop {
+            match axnet::socket_send(socket_id, buf, None) {
+                Ok(sent) => return Ok(sent),
+                Err(axnet::NetError::WouldBlock) => {
+                    if nonblock || !can_block_current() {
+                        return Err(Errno::Again);
+                    }
+                    crate::runtime::block_current(crate::runtime::net_wait_queue());
+                }
+                Err(err) => return Err(map_net_err(err)),
             }
-            crate::runtime::block_current(pipe_write_queue(pipe_id));
-            continue;
+        },
+        FdObject::Unix(id) => {
+            let flags = if nonblock { MSG_DONTWAIT } else { 0 };
+            unix_send(id, &entry, flags, None, buf.len(), &mut UnixControl::new(), |offset, dst| {
+                dst.copy_from_slice(&buf[offset..offset + dst.len()]);
+                true
+            })
         }
-        break;
-    }
-    // SAFETY: 单核早期阶段串行访问 pipe。
-    let pipe = unsafe { &mut PIPES[pipe_id] };
-    let avail = PIPE_BUFFER_SIZE.saturating_sub(pipe.len);
-    let to_write = min(len, avail);
-    let mut remaining = to_write;
-    let mut offset = 0usize;
-    while remaining > 0 {
-        let chunk = min(remaining, PIPE_BUFFER_SIZE - pipe.write_pos);
-        let src = buf.checked_add(offset).ok_or(Errno::Fault)?;
-        let dst = &mut pipe.buf[pipe.write_pos..pipe.write_pos + chunk];
-        UserSlice::new(src, chunk)
-            .copy_to_slice(root_pa, dst)
-            .ok_or(Errno::Fault)?;
-        pipe.write_pos = (pipe.write_pos + chunk) % PIPE_BUFFER_SIZE;
-        pipe.len += chunk;
-        remaining -= chunk;
-        offset += chunk;
+        FdObject::Empty => Err(Errno::Badf),
+        _ => Err(Errno::Inval),
     }
-    let _ = crate::runtime::wake_one(pipe_read_queue(pipe_id));
-    let _ = crate::runtime::wake_all(ppoll_wait_queue());
-    Ok(to_write)
 }
 
 fn pipe_snapshot(pipe_id: usize) -> Option<(usize, usize, usize)> {
@@ -5272,6 +8516,96 @@ fn pipe_snapshot(pipe_id: usize) -> Option<(usize, usize, usize)> {
     Some((pipe.len, pipe.readers, pipe.writers))
 }
 
+/// Anonymous pipes always had a writer; a FIFO only after its first writer opened.
+fn pipe_had_writer(pipe_id: usize) -> bool {
+    if pipe_id >= PIPE_SLOTS {
+        return false;
+    }
+    // SAFETY: 单核早期阶段串行读取 pipe 状态。
+    let pipe = unsafe { &PIPES[pipe_id] };
+    pipe.fifo.is_none() || pipe.writer_opens > 0
+}
+
+fn find_fifo(mount: MountId, inode: InodeId) -> Option<usize> {
+    // SAFETY: 单核早期阶段串行读取 pipe 表。
+    unsafe {
+        PIPES
+            .iter()
+            .position(|pipe| pipe.used && pipe.fifo == Some((mount, inode)))
+    }
+}
+
+/// Attach to the shared pipe behind a FIFO inode with POSIX open semantics.
+fn open_fifo(mount: MountId, inode: InodeId, flags: usize) -> Result<usize, Errno> {
+    let accmode = flags & O_ACCMODE;
+    let nonblock = (flags & O_NONBLOCK) != 0;
+    let pipe_id = match find_fifo(mount, inode) {
+        Some(pipe_id) => pipe_id,
+        None => {
+            let pipe_id = alloc_pipe().ok_or(Errno::MFile)?;
+            // SAFETY: 单核早期阶段串行更新 pipe 表。
+            unsafe {
+                PIPES[pipe_id].fifo = Some((mount, inode));
+            }
+            pipe_id
+        }
+    };
+    // SAFETY: pipe_id comes from the pipe table and access is serialized.
+    let (readers, reader_opens, writer_opens) = unsafe {
+        let pipe = &PIPES[pipe_id];
+        (pipe.readers, pipe.reader_opens, pipe.writer_opens)
+    };
+    if accmode == O_WRONLY && nonblock && readers == 0 {
+        if pipe_snapshot(pipe_id).map_or(false, |(_, r, w)| r == 0 && w == 0) {
+            free_pipe(pipe_id);
+        }
+        return Err(Errno::NxIo);
+    }
+    let object = match accmode {
+        O_RDONLY => FdObject::PipeRead(pipe_id),
+        O_WRONLY => FdObject::PipeWrite(pipe_id),
+        _ => FdObject::PipeRdWr(pipe_id),
+    };
+    let fd = match alloc_fd(FdEntry {
+        object,
+        flags: flags & (O_ACCMODE | O_NONBLOCK | O_CLOEXEC),
+        offset: 0,
+        recv_timeout_ms: 0,
+        send_timeout_ms: 0,
+    }) {
+        Some(fd) => fd,
+        None => {
+            if pipe_snapshot(pipe_id).map_or(false, |(_, r, w)| r == 0 && w == 0) {
+                free_pipe(pipe_id);
+            }
+            return Err(Errno::MFile);
+        }
+    };
+    if nonblock || accmode == O_RDWR || !can_block_current() {
+        return Ok(fd);
+    }
+    // 读端等待写者出现、写端等待读者出现；以打开计数判断，避免对端快速关闭时漏唤醒。
+    loop {
+        // SAFETY: 本 fd 持有引用，pipe 槽位在等待期间保持有效。
+        let (peers, opens) = unsafe {
+            let pipe = &PIPES[pipe_id];
+            if accmode == O_RDONLY {
+                (pipe.writers, pipe.writer_opens - writer_opens)
+            } else {
+                (pipe.readers, pipe.reader_opens - reader_opens)
+            }
+        };
+        if peers > 0 || opens > 0 {
+            return Ok(fd);
+        }
+        if accmode == O_RDONLY {
+            crate::runtime::block_current(pipe_read_queue(pipe_id));
+        } else {
+            crate::runtime::block_current(pipe_write_queue(pipe_id));
+        }
+    }
+}
+
 fn poll_revents_for_fd(fd: i32, events: u16) -> u16 {
     if fd < 0 {
         return POLLNVAL;
@@ -5298,7 +8632,7 @@ fn poll_revents_for_fd(fd: i32, events: u16) -> u16 {
             if (events & POLLIN) != 0 && len > 0 {
                 revents |= POLLIN;
             }
-            if writers == 0 {
+            if writers == 0 && pipe_had_writer(pipe_id) {
                 revents |= POLLHUP;
                 if (events & POLLIN) != 0 {
                     revents |= POLLIN;
@@ -5322,6 +8656,19 @@ fn poll_revents_for_fd(fd: i32, events: u16) -> u16 {
             }
             revents
         }
+        FdObject::PipeRdWr(pipe_id) => {
+            let Some((len, _readers, _writers)) = pipe_snapshot(pipe_id) else {
+                return POLLNVAL;
+            };
+            let mut revents = 0u16;
+            if (events & POLLIN) != 0 && len > 0 {
+                revents |= POLLIN;
+            }
+            if (events & POLLOUT) != 0 && len < PIPE_BUFFER_SIZE {
+                revents |= POLLOUT;
+            }
+            revents
+        }
         FdObject::Eventfd(event_id) => {
             if event_id >= EVENTFD_SLOTS {
                 return POLLNVAL;
@@ -5371,9 +8718,16 @@ fn poll_revents_for_fd(fd: i32, events: u16) -> u16 {
             }
             revents
         }
+        FdObject::Inotify(id) => match inotify::pending(id) {
+            Ok(pending) if (events & POLLIN) != 0 && pending > 0 => POLLIN,
+            Ok(_) => 0,
+            Err(_) => POLLNVAL,
+        },
+        FdObject::Unix(id) => unix_socket::poll(id, events),
         FdObject::Vfs(handle) => {
             let mut revents = 0u16;
-            if (events & POLLIN) != 0 {
+            let readable = handle.mount != MountId::Dev || DEVFS.poll_read(handle.inode);
+            if (events & POLLIN) != 0 && readable {
                 revents |= POLLIN;
             }
             if (events & POLLOUT) != 0 && handle.file_type != FileType::Dir {
@@ -5692,8 +9046,13 @@ fn ppoll_single_waiter_queue(fd: i32, events: u16) -> Option<&'static crate::tas
     match entry.object {
         FdObject::PipeRead(pipe_id) if (events & POLLIN) != 0 => Some(pipe_read_queue(pipe_id)),
         FdObject::PipeWrite(pipe_id) if (events & POLLOUT) != 0 => Some(pipe_write_queue(pipe_id)),
+        FdObject::PipeRdWr(pipe_id) if (events & POLLIN) != 0 => Some(pipe_read_queue(pipe_id)),
         FdObject::Eventfd(event_id) if (events & POLLIN) != 0 => Some(eventfd_queue(event_id)),
         FdObject::Timerfd(timer_id) if (events & POLLIN) != 0 => Some(timerfd_queue(timer_id)),
+        FdObject::Inotify(id) if (events & POLLIN) != 0 => Some(inotify::queue(id)),
+        // 写者等待的是对端的接收队列。
+        FdObject::Unix(id) if (events & POLLIN) != 0 => Some(unix_socket::queue(id)),
+        FdObject::Unix(id) if (events & POLLOUT) != 0 => Some(unix_socket::send_queue(id, None)),
         FdObject::Socket(_) if (events & (POLLIN | POLLOUT)) != 0 => Some(crate::runtime::net_wait_queue()),
         _ => None,
     }
@@ -5717,7 +9076,7 @@ fn resolve_socket_entry(fd: usize) -> Result<(axnet::SocketId, FdEntry), Errno>
 
 fn socket_timeouts(fd: usize) -> Result<(u64, u64), Errno> {
     let entry = resolve_fd(fd).ok_or(Errno::Badf)?;
-    if !matches!(entry.object, FdObject::Socket(_)) {
+    if !matches!(entry.object, FdObject::Socket(_) | FdObject::Unix(_)) {
         return Err(Errno::Badf);
     }
     Ok((entry.recv_timeout_ms, entry.send_timeout_ms))
@@ -5733,7 +9092,7 @@ fn set_socket_timeout(
         // SAFETY: 单核早期阶段访问重定向表。
         unsafe {
             if let Some(mut entry) = STDIO_REDIRECT[proc_idx][fd] {
-                if !matches!(entry.object, FdObject::Socket(_)) {
+                if !matches!(entry.object, FdObject::Socket(_) | FdObject::Unix(_)) {
                     return Err(Errno::Badf);
                 }
                 if let Some(value) = recv_timeout_ms {
@@ -5752,7 +9111,7 @@ fn set_socket_timeout(
     // SAFETY: 单核早期阶段，fd 表串行更新。
     unsafe {
         let entry = &mut FD_TABLES[proc_idx][idx];
-        if entry.object == FdObject::Empty || !matches!(entry.object, FdObject::Socket(_)) {
+        if !matches!(entry.object, FdObject::Socket(_) | FdObject::Unix(_)) {
             return Err(Errno::Badf);
         }
         if let Some(value) = recv_timeout_ms {
@@ -5765,24 +9124,105 @@ fn set_socket_timeout(
     Ok(0)
 }
 
-fn parse_sockaddr_in(root_pa: usize, addr: usize, len: usize) -> Result<(axnet::IpAddress, u16), Errno> {
-    if addr == 0 || len < size_of::<SockAddrIn>() {
+/// Decode a user `sockaddr` in the socket's address family.
+///
+/// AF_INET6 sockets map `::ffff:a.b.c.d` back to IPv4 so dual-stack sockets reach IPv4 peers.
+fn parse_sockaddr(
+    root_pa: usize,
+    socket_id: usize,
+    addr: usize,
+    len: usize,
+) -> Result<(axnet::IpAddress, u16), Errno> {
+    if addr == 0 || len < size_of::<u16>() {
         return Err(Errno::Inval);
     }
-    let sock = UserPtr::<SockAddrIn>::new(addr)
+    let family = UserPtr::<u16>::new(addr)
         .read(root_pa)
         .ok_or(Errno::Fault)?;
-    if sock.sin_family != AF_INET {
+    let domain = axnet::socket_domain(socket_id).map_err(map_net_err)?;
+    match (family, domain as u16) {
+        (AF_INET, AF_INET) => {
+            if len < size_of::<SockAddrIn>() {
+                return Err(Errno::Inval);
+            }
+            let sock = UserPtr::<SockAddrIn>::new(addr)
+                .read(root_pa)
+                .ok_or(Errno::Fault)?;
+            let port = u16::from_be(sock.sin_port);
+            // sin_addr 按网络字节序存放，内存中的字节即地址各段。
+            let ip_bytes = sock.sin_addr.to_ne_bytes();
+            let ip = axnet::Ipv4Address::new(ip_bytes[0], ip_bytes[1], ip_bytes[2], ip_bytes[3]);
+            Ok((axnet::IpAddress::Ipv4(ip), port))
+        }
+        (AF_INET6, AF_INET6) => {
+            if len < size_of::<SockAddrIn6>() {
+                return Err(Errno::Inval);
+            }
+            let sock = UserPtr::<SockAddrIn6>::new(addr)
+                .read(root_pa)
+                .ok_or(Errno::Fault)?;
+            let port = u16::from_be(sock.sin6_port);
+            let ip = axnet::Ipv6Address::from_bytes(&sock.sin6_addr);
+            // v4-mapped 地址交给 axnet 按 IPv4 处理，IPV6_V6ONLY 由 axnet 检查。
+            match ip.as_ipv4() {
+                Some(v4) => Ok((axnet::IpAddress::Ipv4(v4), port)),
+                None => Ok((axnet::IpAddress::Ipv6(ip), port)),
+            }
+        }
+        (AF_INET, _) | (AF_INET6, _) => Err(Errno::Inval),
+        _ => Err(Errno::AfNoSupport),
+    }
+}
+
+/// Encode an endpoint as the socket's `sockaddr` at `addr`; returns the bytes written.
+fn encode_sockaddr(
+    root_pa: usize,
+    socket_id: usize,
+    addr: usize,
+    cap: usize,
+    ip: axnet::IpAddress,
+    port: u16,
+) -> Result<usize, Errno> {
+    let domain = axnet::socket_domain(socket_id).map_err(map_net_err)?;
+    if domain as u16 == AF_INET6 {
+        if cap < size_of::<SockAddrIn6>() {
+            return Err(Errno::Inval);
+        }
+        // AF_INET6 套接字上的 IPv4 对端以 ::ffff:a.b.c.d 形式呈现。
+        let ip = match ip {
+            axnet::IpAddress::Ipv4(v4) => axnet::Ipv6Address::from(v4),
+            axnet::IpAddress::Ipv6(v6) => v6,
+        };
+        let sock = SockAddrIn6 {
+            sin6_family: AF_INET6,
+            sin6_port: port.to_be(),
+            sin6_flowinfo: 0,
+            sin6_addr: ip.0,
+            sin6_scope_id: 0,
+        };
+        UserPtr::new(addr).write(root_pa, sock).ok_or(Errno::Fault)?;
+        return Ok(size_of::<SockAddrIn6>());
+    }
+    if cap < size_of::<SockAddrIn>() {
         return Err(Errno::Inval);
     }
-    let port = u16::from_be(sock.sin_port);
-    let ip_bytes = sock.sin_addr.to_be_bytes();
-    let ip = axnet::Ipv4Address::new(ip_bytes[0], ip_bytes[1], ip_bytes[2], ip_bytes[3]);
-    Ok((axnet::IpAddress::Ipv4(ip), port))
+    let axnet::IpAddress::Ipv4(ip) = ip else {
+        return Err(Errno::AfNoSupport);
+    };
+    let bytes = ip.as_bytes();
+    let sock = SockAddrIn {
+        sin_family: AF_INET,
+        sin_port: port.to_be(),
+        sin_addr: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        sin_zero: [0; 8],
+    };
+    UserPtr::new(addr).write(root_pa, sock).ok_or(Errno::Fault)?;
+    Ok(size_of::<SockAddrIn>())
 }
 
-fn write_sockaddr_in(
+fn write_sockaddr(
     root_pa: usize,
+    socket_id: usize,
     addr: usize,
     addrlen: usize,
     endpoint: Option<(axnet::IpAddress, u16)>,
@@ -5793,25 +9233,10 @@ fn write_sockaddr_in(
     let Some((ip, port)) = endpoint else {
         return Ok(());
     };
-    let mut len = UserPtr::<u32>::new(addrlen)
+    let len = UserPtr::<u32>::new(addrlen)
         .read(root_pa)
         .ok_or(Errno::Fault)? as usize;
-    let expected = size_of::<SockAddrIn>();
-    if len < expected {
-        return Err(Errno::Inval);
-    }
-    let ip = match ip {
-        axnet::IpAddress::Ipv4(addr) => addr,
-    };
-    let bytes = ip.as_bytes();
-    let sock = SockAddrIn {
-        sin_family: AF_INET,
-        sin_port: port.to_be(),
-        sin_addr: u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
-        sin_zero: [0; 8],
-    };
-    UserPtr::new(addr).write(root_pa, sock).ok_or(Errno::Fault)?;
-    len = expected;
+    let len = encode_sockaddr(root_pa, socket_id, addr, len, ip, port)?;
     UserPtr::new(addrlen)
         .write(root_pa, len as u32)
         .ok_or(Errno::Fault)?;
@@ -5920,6 +9345,25 @@ fn timerfd_current_spec(timer_id: usize) -> Result<Itimerspec, Errno> {
     }
 }
 
+#[inline(never)]
+fn inotify_read(id: usize, root_pa: usize, buf: usize, len: usize, nonblock: bool) -> Result<usize, Errno> {
+    let mut events = [0u8; 1024];
+    let want = min(len, events.len());
+    loop {
+        let read = inotify::read(id, &mut events[..want]).map_err(map_inotify_err)?;
+        if read > 0 {
+            UserSlice::new(buf, read)
+                .copy_from_slice(root_pa, &events[..read])
+                .ok_or(Errno::Fault)?;
+            return Ok(read);
+        }
+        if nonblock || !can_block_current() {
+            return Err(Errno::Again);
+        }
+        crate::runtime::block_current(inotify::queue(id));
+    }
+}
+
 fn timerfd_read(timer_id: usize, root_pa: usize, buf: usize, len: usize, nonblock: bool) -> Result<usize, Errno> {
     if timer_id >= TIMERFD_SLOTS {
         return Err(Errno::Badf);
@@ -5990,15 +9434,29 @@ fn read_from_entry(fd: usize, entry: FdEntry, root_pa: usize, buf: usize, len: u
             if handle.file_type == FileType::Dir {
                 return Err(Errno::IsDir);
             }
-            read_vfs_fd(fd, root_pa, handle.mount, handle.inode, buf, len)
+            if handle.file_type == FileType::Char {
+                let nonblock = (entry.flags & O_NONBLOCK) != 0;
+                return read_char_fd(root_pa, handle.mount, handle.inode, buf, len, nonblock);
+            }
+            let read = read_vfs_fd(fd, root_pa, handle.mount, handle.inode, buf, len)?;
+            if read > 0 {
+                fsnotify_file(handle, inotify::IN_ACCESS);
+            }
+            Ok(read)
         }
-        FdObject::PipeRead(pipe_id) => {
+        FdObject::PipeRead(pipe_id) | FdObject::PipeRdWr(pipe_id) => {
             let nonblock = (entry.flags & O_NONBLOCK) != 0;
             pipe_read(pipe_id, root_pa, buf, len, nonblock)
         }
         FdObject::Socket(socket_id) => {
-            let nonblock = (entry.flags & O_NONBLOCK) != 0;
-            read_socket(root_pa, socket_id, buf, len, nonblock)
+            let received = net_recv(root_pa, socket_id, &entry, 0, UserBufs::Flat { base: buf, len }, len)?;
+            Ok(received.len)
+        }
+        FdObject::Unix(id) => {
+            let bufs = UserBufs::Flat { base: buf, len };
+            let received = unix_recv(id, &entry, 0, len, |offset, src| bufs.copy_out(root_pa, offset, src))?;
+            unix_discard_rights(&received, 0);
+            Ok(received.len)
         }
         FdObject::Eventfd(event_id) => {
             let nonblock = (entry.flags & O_NONBLOCK) != 0;
@@ -6008,6 +9466,10 @@ fn read_from_entry(fd: usize, entry: FdEntry, root_pa: usize, buf: usize, len: u
             let nonblock = (entry.flags & O_NONBLOCK) != 0;
             timerfd_read(timer_id, root_pa, buf, len, nonblock)
         }
+        FdObject::Inotify(id) => {
+            let nonblock = (entry.flags & O_NONBLOCK) != 0;
+            inotify_read(id, root_pa, buf, len, nonblock)
+        }
         FdObject::Stdout | FdObject::Stderr | FdObject::PipeWrite(_) => Err(Errno::Badf),
         FdObject::Epoll(_) => Err(Errno::Inval),
         FdObject::Empty => Err(Errno::Badf),
@@ -6035,6 +9497,45 @@ fn read_vfs_fd(
     })
 }
 
+fn read_char_fd(
+    root_pa: usize,
+    mount: MountId,
+    inode: InodeId,
+    buf: usize,
+    len: usize,
+    nonblock: bool,
+) -> Result<usize, Errno> {
+    if len == 0 {
+        return Ok(0);
+    }
+    // Character devices have no file offset; retry like stdin until data arrives.
+    let mut scratch = [0u8; 512];
+    let chunk = min(len, scratch.len());
+    loop {
+        let result = with_mounts(|mounts| {
+            let fs = mounts.fs_for(mount).ok_or(Errno::NoEnt)?;
+            fs.read_at(inode, 0, &mut scratch[..chunk]).map_err(map_vfs_err)
+        });
+        match result {
+            Ok(read) => {
+                UserSlice::new(buf, read)
+                    .copy_from_slice(root_pa, &scratch[..read])
+                    .ok_or(Errno::Fault)?;
+                return Ok(read);
+            }
+            Err(Errno::Again) => {
+                if nonblock || !can_block_current() {
+                    return Err(Errno::Again);
+                }
+                if !crate::runtime::sleep_current_ms(PPOLL_RETRY_SLEEP_MS) {
+                    return Err(Errno::Again);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
 fn read_vfs_at(
     root_pa: usize,
     fs: &dyn VfsOps,
@@ -6122,120 +9623,46 @@ fn write_to_entry(fd: usize, entry: FdEntry, root_pa: usize, buf: usize, len: us
             if handle.file_type == FileType::Dir {
                 return Err(Errno::IsDir);
             }
-            if (entry.flags & O_APPEND) != 0 {
-                return with_mounts(|mounts| {
+            let written = if (entry.flags & O_APPEND) != 0 {
+                with_mounts(|mounts| {
                     let fs = mounts.fs_for(handle.mount).ok_or(Errno::NoEnt)?;
                     let (_, size) = vfs_meta_for(fs, handle.inode)?;
                     let written = write_vfs_at(root_pa, fs, handle.inode, size, buf, len)?;
                     let new_offset = size.checked_add(written).ok_or(Errno::Inval)?;
                     set_fd_offset(fd, new_offset);
                     Ok(written)
-                });
+                })?
+            } else {
+                write_vfs_fd(fd, root_pa, handle.mount, handle.inode, buf, len)?
+            };
+            if written > 0 {
+                fsnotify_file(handle, inotify::IN_MODIFY);
             }
-            write_vfs_fd(fd, root_pa, handle.mount, handle.inode, buf, len)
+            Ok(written)
         }
-        FdObject::PipeWrite(pipe_id) => {
+        FdObject::PipeWrite(pipe_id) | FdObject::PipeRdWr(pipe_id) => {
             let nonblock = (entry.flags & O_NONBLOCK) != 0;
             pipe_write(pipe_id, root_pa, buf, len, nonblock)
         }
         FdObject::Socket(socket_id) => {
-            let nonblock = (entry.flags & O_NONBLOCK) != 0;
-            write_socket(root_pa, socket_id, buf, len, nonblock)
+            net_send(root_pa, socket_id, &entry, 0, None, UserBufs::Flat { base: buf, len }, len)
+        }
+        FdObject::Unix(id) => {
+            let bufs = UserBufs::Flat { base: buf, len };
+            unix_send(id, &entry, 0, None, len, &mut UnixControl::new(), |offset, dst| {
+                bufs.copy_in(root_pa, offset, dst)
+            })
         }
         FdObject::Eventfd(event_id) => {
             let nonblock = (entry.flags & O_NONBLOCK) != 0;
             eventfd_write(event_id, root_pa, buf, len, nonblock)
         }
-        FdObject::Timerfd(_) | FdObject::Epoll(_) => Err(Errno::Inval),
+        FdObject::Timerfd(_) | FdObject::Epoll(_) | FdObject::Inotify(_) => Err(Errno::Inval),
         FdObject::Stdin | FdObject::PipeRead(_) => Err(Errno::Badf),
         FdObject::Empty => Err(Errno::Badf),
     }
 }
 
-fn read_socket(
-    root_pa: usize,
-    socket_id: axnet::SocketId,
-    buf: usize,
-    len: usize,
-    nonblock: bool,
-) -> Result<usize, Errno> {
-    if len == 0 {
-        return Ok(0);
-    }
-    let mut total = 0usize;
-    let mut remaining = len;
-    let mut scratch = [0u8; 512];
-    while remaining > 0 {
-        let chunk = core::cmp::min(remaining, scratch.len());
-        let (read, _) = match axnet::socket_recv(socket_id, &mut scratch[..chunk]) {
-            Ok(result) => result,
-            Err(axnet::NetError::WouldBlock) => {
-                if total > 0 {
-                    break;
-                }
-                if nonblock || !can_block_current() {
-                    return Err(Errno::Again);
-                }
-                crate::runtime::block_current(crate::runtime::net_wait_queue());
-                continue;
-            }
-            Err(err) => return Err(map_net_err(err)),
-        };
-        let dst = buf.checked_add(total).ok_or(Errno::Fault)?;
-        UserSlice::new(dst, read)
-            .copy_from_slice(root_pa, &scratch[..read])
-            .ok_or(Errno::Fault)?;
-        total += read;
-        if read < chunk {
-            break;
-        }
-        remaining = remaining.saturating_sub(read);
-    }
-    Ok(total)
-}
-
-fn write_socket(
-    root_pa: usize,
-    socket_id: axnet::SocketId,
-    buf: usize,
-    len: usize,
-    nonblock: bool,
-) -> Result<usize, Errno> {
-    if len == 0 {
-        return Ok(0);
-    }
-    let mut total = 0usize;
-    let mut remaining = len;
-    let mut scratch = [0u8; 512];
-    while remaining > 0 {
-        let chunk = core::cmp::min(remaining, scratch.len());
-        let src = buf.checked_add(total).ok_or(Errno::Fault)?;
-        UserSlice::new(src, chunk)
-            .copy_to_slice(root_pa, &mut scratch[..chunk])
-            .ok_or(Errno::Fault)?;
-        let sent = match axnet::socket_send(socket_id, &scratch[..chunk], None) {
-            Ok(sent) => sent,
-            Err(axnet::NetError::WouldBlock) => {
-                if total > 0 {
-                    break;
-                }
-                if nonblock || !can_block_current() {
-                    return Err(Errno::Again);
-                }
-                crate::runtime::block_current(crate::runtime::net_wait_queue());
-                continue;
-            }
-            Err(err) => return Err(map_net_err(err)),
-        };
-        total += sent;
-        if sent < chunk {
-            break;
-        }
-        remaining = remaining.saturating_sub(sent);
-    }
-    Ok(total)
-}
-
 fn read_user_byte(root_pa: usize, addr: usize) -> Result<u8, Errno> {
     let pa = mm::translate_user_ptr(root_pa, addr, 1, UserAccess::Read)
         .ok_or(Errno::Fault)?;
@@ -6291,7 +9718,8 @@ fn default_rlimit() -> Rlimit {
     }
 }
 
-fn rng_next() -> u64 {
+/// Return the next value of the kernel RNG shared by `getrandom` and `/dev/random`.
+pub fn rng_next() -> u64 {
     let mut state = RNG_STATE.load(Ordering::Relaxed);
     if state == 0 {
         state = rng_seed();
@@ -6362,7 +9790,8 @@ fn read_console_into(root_pa: usize, buf: usize, len: usize, nonblock: bool) ->
     Ok(read)
 }
 
-fn console_peek() -> bool {
+/// Return whether a console byte is ready without consuming it.
+pub fn console_peek() -> bool {
     // SAFETY: 单核早期阶段顺序访问控制台缓存。
     unsafe {
         if CONSOLE_STASH >= 0 {
@@ -6377,7 +9806,8 @@ fn console_peek() -> bool {
     false
 }
 
-fn console_take() -> Option<u8> {
+/// Take one pending console byte, if any.
+pub fn console_take() -> Option<u8> {
     // SAFETY: 单核早期阶段顺序访问控制台缓存。
     unsafe {
         if CONSOLE_STASH >= 0 {
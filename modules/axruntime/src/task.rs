@@ -266,6 +266,32 @@ pub fn is_ready(id: TaskId) -> bool {
     }
 }
 
+/// Return the scheduler state of a task, if the slot is in use.
+pub fn state(id: TaskId) -> Option<TaskState> {
+    // SAFETY: read-only access to task state during early boot.
+    unsafe {
+        if id >= MAX_TASKS || !TASK_USED[id] {
+            return None;
+        }
+        let task = &*TASK_TABLE[id].as_ptr();
+        Some(task.state)
+    }
+}
+
+/// Return `(runnable, blocked)` task counts; runnable includes the running task.
+pub fn state_counts() -> (usize, usize) {
+    let mut runnable = 0;
+    let mut blocked = 0;
+    for id in 0..MAX_TASKS {
+        match state(id) {
+            Some(TaskState::Ready | TaskState::Running) => runnable += 1,
+            Some(TaskState::Blocked) => blocked += 1,
+            None => {}
+        }
+    }
+    (runnable, blocked)
+}
+
 /// Unconditionally update task state; prefer `transition_state` when validating transitions.
 /// Unconditionally update task state.
 pub fn set_state(id: TaskId, state: TaskState) -> bool {
@@ -0,0 +1,825 @@
+//! AF_UNIX sockets: endpoint table, name registry and per-endpoint receive queues.
+//!
+//! Each endpoint owns the receive side of its connection: a byte ring plus a
+//! queue of records. A record is one datagram (or one stream write) together
+//! with the sender's name, credentials and `SCM_RIGHTS` handles. Handles are
+//! opaque here; the syscall layer owns the descriptors behind them and is
+//! handed back the ones whose records are discarded unread.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use axfs::mount::MountId;
+use axfs::InodeId;
+
+use crate::runtime;
+use crate::task_wait_queue::TaskWaitQueue;
+
+const UNIX_SLOTS: usize = 16;
+const RECV_BUF_SIZE: usize = 2048;
+const MAX_RECORDS: usize = 8;
+const MAX_BACKLOG: usize = 8;
+/// Length of `sun_path` in `struct sockaddr_un`.
+pub const UNIX_PATH_MAX: usize = 108;
+/// Descriptors one message may carry (Linux allows 253).
+pub const SCM_MAX_FD: usize = 8;
+/// Largest datagram or seqpacket message: it has to fit the peer's receive buffer.
+pub const MAX_MESSAGE: usize = RECV_BUF_SIZE;
+/// Base of the inode numbers reported in `/proc/<pid>/fd` links.
+pub const INODE_BASE: usize = 0x4000;
+
+const MAX_QUEUED_RIGHTS: usize = MAX_RECORDS * SCM_MAX_FD;
+
+const POLLIN: u16 = 0x001;
+const POLLOUT: u16 = 0x004;
+const POLLHUP: u16 = 0x010;
+const POLLNVAL: u16 = 0x020;
+
+/// Socket type from socket(2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnixKind {
+    Stream,
+    Dgram,
+    SeqPacket,
+}
+
+impl UnixKind {
+    fn connection_oriented(self) -> bool {
+        self != Self::Dgram
+    }
+
+    fn keeps_boundaries(self) -> bool {
+        self != Self::Stream
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// AF_UNIX error codes returned to the syscall layer.
+pub enum UnixError {
+    /// Bad argument or an operation the socket state does not allow.
+    Invalid,
+    /// The endpoint slot is not in use.
+    BadSlot,
+    /// The endpoint table is full.
+    NoSpace,
+    /// The name is already bound.
+    AddrInUse,
+    /// Nothing listens on the name, or the connection went away.
+    ConnRefused,
+    /// The target socket has a different type.
+    WrongType,
+    NotConn,
+    IsConn,
+    /// Datagram without a destination on an unconnected socket.
+    DestAddrReq,
+    WouldBlock,
+    /// Writing after shutdown or to a peer that stopped reading.
+    Pipe,
+    /// Message larger than the receive buffer.
+    MsgSize,
+    /// Operation not supported by this socket type.
+    NotSupported,
+    /// The copy callback failed (bad user buffer).
+    Fault,
+}
+
+/// Socket name: unnamed, a filesystem path, or an abstract name (leading NUL).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct UnixAddr {
+    len: usize,
+    bytes: [u8; UNIX_PATH_MAX],
+}
+
+impl UnixAddr {
+    pub const UNNAMED: Self = Self {
+        len: 0,
+        bytes: [0; UNIX_PATH_MAX],
+    };
+
+    /// Build a name from `sun_path` bytes; path names end at the first NUL.
+    pub fn new(sun_path: &[u8]) -> Result<Self, UnixError> {
+        if sun_path.len() > UNIX_PATH_MAX {
+            return Err(UnixError::Invalid);
+        }
+        let len = match sun_path.first() {
+            None => 0,
+            Some(0) => sun_path.len(),
+            Some(_) => sun_path.iter().position(|&byte| byte == 0).unwrap_or(sun_path.len()),
+        };
+        let mut bytes = [0; UNIX_PATH_MAX];
+        bytes[..len].copy_from_slice(&sun_path[..len]);
+        Ok(Self { len, bytes })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    pub fn is_unnamed(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_abstract(&self) -> bool {
+        self.len > 0 && self.bytes[0] == 0
+    }
+
+    /// Filesystem path of a path name.
+    pub fn path(&self) -> Option<&str> {
+        if self.is_unnamed() || self.is_abstract() {
+            return None;
+        }
+        core::str::from_utf8(self.as_bytes()).ok()
+    }
+}
+
+/// Process credentials as carried by `SCM_CREDENTIALS` and `SO_PEERCRED`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct UnixCred {
+    pub pid: u32,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+/// Result of one receive.
+pub struct Received {
+    /// Bytes handed to the copy callback.
+    pub len: usize,
+    /// Full message length; larger than `len` when a datagram was truncated.
+    pub msg_len: usize,
+    pub from: UnixAddr,
+    /// Sender credentials, present when `SO_PASSCRED` is set.
+    pub cred: Option<UnixCred>,
+    pub nrights: usize,
+    pub rights: [u32; SCM_MAX_FD],
+}
+
+#[derive(Clone, Copy)]
+struct Record {
+    len: usize,
+    from: UnixAddr,
+    cred: UnixCred,
+    nrights: usize,
+    rights: [u32; SCM_MAX_FD],
+}
+
+const EMPTY_RECORD: Record = Record {
+    len: 0,
+    from: UnixAddr::UNNAMED,
+    cred: UnixCred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    },
+    nrights: 0,
+    rights: [0; SCM_MAX_FD],
+};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    Unconnected,
+    Listening,
+    Connected,
+    /// The peer went away; queued data can still be read.
+    Disconnected,
+}
+
+struct Endpoint {
+    used: bool,
+    refs: usize,
+    kind: UnixKind,
+    state: State,
+    name: UnixAddr,
+    /// Socket inode a path name is bound to.
+    node: Option<(MountId, InodeId)>,
+    /// Connected peer, or the default destination of a datagram socket.
+    peer: Option<usize>,
+    /// Listener whose accept queue still holds this endpoint.
+    listener: Option<usize>,
+    backlog: usize,
+    npending: usize,
+    pending: [usize; MAX_BACKLOG],
+    /// Credentials taken at socket/connect/listen time, reported to peers.
+    owner: UnixCred,
+    peer_cred: UnixCred,
+    passcred: bool,
+    shut_rd: bool,
+    shut_wr: bool,
+    /// The peer shut down writing; reads see EOF once the queue drains.
+    peer_shut_wr: bool,
+    read_pos: usize,
+    len: usize,
+    buf: [u8; RECV_BUF_SIZE],
+    rec_head: usize,
+    rec_count: usize,
+    records: [Record; MAX_RECORDS],
+}
+
+const EMPTY_ENDPOINT: Endpoint = Endpoint {
+    used: false,
+    refs: 0,
+    kind: UnixKind::Stream,
+    state: State::Unconnected,
+    name: UnixAddr::UNNAMED,
+    node: None,
+    peer: None,
+    listener: None,
+    backlog: 0,
+    npending: 0,
+    pending: [0; MAX_BACKLOG],
+    owner: EMPTY_RECORD.cred,
+    peer_cred: EMPTY_RECORD.cred,
+    passcred: false,
+    shut_rd: false,
+    shut_wr: false,
+    peer_shut_wr: false,
+    read_pos: 0,
+    len: 0,
+    buf: [0; RECV_BUF_SIZE],
+    rec_head: 0,
+    rec_count: 0,
+    records: [EMPTY_RECORD; MAX_RECORDS],
+};
+
+impl Endpoint {
+    fn record(&self, idx: usize) -> &Record {
+        &self.records[(self.rec_head + idx) % MAX_RECORDS]
+    }
+
+    fn record_mut(&mut self, idx: usize) -> &mut Record {
+        &mut self.records[(self.rec_head + idx) % MAX_RECORDS]
+    }
+
+    fn free_space(&self) -> usize {
+        RECV_BUF_SIZE - self.len
+    }
+
+    /// Whether a peer's write of any size would currently block.
+    fn full(&self) -> bool {
+        self.len == RECV_BUF_SIZE || self.rec_count == MAX_RECORDS
+    }
+
+    /// Fill `len` bytes after the queued data without committing them.
+    fn stage(&mut self, len: usize, fill: &mut impl FnMut(usize, &mut [u8]) -> bool) -> bool {
+        let start = (self.read_pos + self.len) % RECV_BUF_SIZE;
+        let first = len.min(RECV_BUF_SIZE - start);
+        fill(0, &mut self.buf[start..start + first]) && (first == len || fill(first, &mut self.buf[..len - first]))
+    }
+
+    /// Hand `len` queued bytes starting `offset` bytes past the read position to `drain`.
+    fn peek(&self, offset: usize, len: usize, at: usize, drain: &mut impl FnMut(usize, &[u8]) -> bool) -> bool {
+        let start = (self.read_pos + offset) % RECV_BUF_SIZE;
+        let first = len.min(RECV_BUF_SIZE - start);
+        drain(at, &self.buf[start..start + first]) && (first == len || drain(at + first, &self.buf[..len - first]))
+    }
+
+    fn consume(&mut self, len: usize) {
+        self.read_pos = (self.read_pos + len) % RECV_BUF_SIZE;
+        self.len -= len;
+    }
+
+    fn pop_record(&mut self) {
+        self.rec_head = (self.rec_head + 1) % MAX_RECORDS;
+        self.rec_count -= 1;
+    }
+
+    /// Copy out the handles of every queued record.
+    fn queued_rights(&self, out: &mut [u32; MAX_QUEUED_RIGHTS]) -> usize {
+        let mut count = 0;
+        for idx in 0..self.rec_count {
+            let record = self.record(idx);
+            out[count..count + record.nrights].copy_from_slice(&record.rights[..record.nrights]);
+            count += record.nrights;
+        }
+        count
+    }
+}
+
+// SAFETY: AF_UNIX 端点表在单核阶段由系统调用串行访问。
+static mut ENDPOINTS: [Endpoint; UNIX_SLOTS] = [EMPTY_ENDPOINT; UNIX_SLOTS];
+#[allow(clippy::declare_interior_mutable_const)]
+const EMPTY_QUEUE: TaskWaitQueue = TaskWaitQueue::new();
+// 每个端点一个等待队列：读者等自己的队列，写者等对端（接收方）的队列。
+static UNIX_WAITERS: [TaskWaitQueue; UNIX_SLOTS] = [EMPTY_QUEUE; UNIX_SLOTS];
+static NEXT_AUTOBIND: AtomicU32 = AtomicU32::new(0);
+
+fn endpoint(id: usize) -> Result<&'static mut Endpoint, UnixError> {
+    // SAFETY: 单核阶段串行访问端点表。
+    let ep = unsafe { ENDPOINTS.get_mut(id) }.ok_or(UnixError::BadSlot)?;
+    if ep.used {
+        Ok(ep)
+    } else {
+        Err(UnixError::BadSlot)
+    }
+}
+
+fn alloc_slot(kind: UnixKind) -> Option<usize> {
+    // SAFETY: 单核阶段串行访问端点表。
+    let endpoints = unsafe { &mut ENDPOINTS };
+    let id = endpoints.iter().position(|ep| !ep.used)?;
+    endpoints[id] = EMPTY_ENDPOINT;
+    endpoints[id].used = true;
+    endpoints[id].kind = kind;
+    Some(id)
+}
+
+fn wake(id: usize) {
+    runtime::wake_all(queue(id));
+}
+
+/// Allocate an unconnected endpoint; the first descriptor takes the initial reference.
+pub fn alloc(kind: UnixKind, owner: UnixCred) -> Option<usize> {
+    let id = alloc_slot(kind)?;
+    if let Ok(ep) = endpoint(id) {
+        ep.owner = owner;
+    }
+    Some(id)
+}
+
+/// Allocate two endpoints connected to each other (socketpair).
+pub fn pair(kind: UnixKind, owner: UnixCred) -> Option<(usize, usize)> {
+    let first = alloc(kind, owner)?;
+    let Some(second) = alloc(kind, owner) else {
+        close(first, &mut |_| {});
+        return None;
+    };
+    for (id, peer) in [(first, second), (second, first)] {
+        if let Ok(ep) = endpoint(id) {
+            ep.state = State::Connected;
+            ep.peer = Some(peer);
+            ep.peer_cred = owner;
+        }
+    }
+    Some((first, second))
+}
+
+/// Take an extra descriptor reference (dup, fork, SCM_RIGHTS).
+pub fn acquire(id: usize) {
+    if let Ok(ep) = endpoint(id) {
+        ep.refs += 1;
+    }
+}
+
+/// Drop a descriptor reference; the last one closes the endpoint.
+///
+/// `discard` receives the handles of records that were never read.
+pub fn release(id: usize, mut discard: impl FnMut(u32)) {
+    let Ok(ep) = endpoint(id) else {
+        return;
+    };
+    ep.refs = ep.refs.saturating_sub(1);
+    if ep.refs == 0 {
+        close(id, &mut discard);
+    }
+}
+
+fn close(id: usize, discard: &mut dyn FnMut(u32)) {
+    let Ok(ep) = endpoint(id) else {
+        return;
+    };
+    let mut handles = [0u32; MAX_QUEUED_RIGHTS];
+    let count = ep.queued_rights(&mut handles);
+    let (kind, peer, npending, pending) = (ep.kind, ep.peer, ep.npending, ep.pending);
+    *ep = EMPTY_ENDPOINT;
+    if kind.connection_oriented() {
+        if let Some(peer) = peer {
+            if let Ok(peer_ep) = endpoint(peer) {
+                if peer_ep.peer == Some(id) {
+                    peer_ep.state = State::Disconnected;
+                    peer_ep.peer = None;
+                }
+            }
+            wake(peer);
+        }
+    } else {
+        // 以本端为默认目的地址的数据报套接字改为“对端已关闭”。
+        for other in 0..UNIX_SLOTS {
+            let Ok(other_ep) = endpoint(other) else {
+                continue;
+            };
+            if other_ep.kind == UnixKind::Dgram && other_ep.peer == Some(id) {
+                other_ep.peer = None;
+                other_ep.state = State::Disconnected;
+                wake(other);
+            }
+        }
+    }
+    // 监听套接字关闭时，尚未 accept 的连接一并关闭，客户端读到 EOF。
+    for &server in &pending[..npending] {
+        close(server, discard);
+    }
+    wake(id);
+    for &handle in &handles[..count] {
+        discard(handle);
+    }
+}
+
+/// Wait queue readers of `id` (and writers targeting it) sleep on.
+pub fn queue(id: usize) -> &'static TaskWaitQueue {
+    &UNIX_WAITERS[id % UNIX_SLOTS]
+}
+
+/// Wait queue a sender blocked on `id`'s destination sleeps on.
+pub fn send_queue(id: usize, to: Option<usize>) -> &'static TaskWaitQueue {
+    let peer = endpoint(id).ok().and_then(|ep| ep.peer);
+    queue(to.or(peer).unwrap_or(id))
+}
+
+pub fn kind(id: usize) -> Result<UnixKind, UnixError> {
+    Ok(endpoint(id)?.kind)
+}
+
+/// Bind `id` to `name`; path names pass the socket inode created for them.
+pub fn bind(id: usize, name: UnixAddr, node: Option<(MountId, InodeId)>) -> Result<(), UnixError> {
+    if name.is_unnamed() || !endpoint(id)?.name.is_unnamed() {
+        return Err(UnixError::Invalid);
+    }
+    if name.is_abstract() && lookup(&name, None).is_some() {
+        return Err(UnixError::AddrInUse);
+    }
+    if let Some(node) = node {
+        // 旧套接字文件被删除后 inode 可能被复用，旧绑定不再可达。
+        for other in 0..UNIX_SLOTS {
+            if let Ok(other_ep) = endpoint(other) {
+                if other_ep.node == Some(node) {
+                    other_ep.node = None;
+                }
+            }
+        }
+    }
+    let ep = endpoint(id)?;
+    ep.name = name;
+    ep.node = node;
+    Ok(())
+}
+
+/// Bind `id` to a fresh abstract name (`bind` with an empty address, or autobind).
+pub fn autobind(id: usize) -> Result<(), UnixError> {
+    for _ in 0..0x10_0000 {
+        let seq = NEXT_AUTOBIND.fetch_add(1, Ordering::Relaxed) & 0xf_ffff;
+        let mut name = [0u8; 6];
+        for (idx, byte) in name[1..].iter_mut().enumerate() {
+            *byte = b"0123456789abcdef"[((seq >> (16 - 4 * idx)) & 0xf) as usize];
+        }
+        let name = UnixAddr::new(&name)?;
+        match bind(id, name, None) {
+            Err(UnixError::AddrInUse) => continue,
+            result => return result,
+        }
+    }
+    Err(UnixError::AddrInUse)
+}
+
+/// Find the endpoint bound to an abstract `name` or to the socket inode `node`.
+pub fn lookup(name: &UnixAddr, node: Option<(MountId, InodeId)>) -> Option<usize> {
+    (0..UNIX_SLOTS).find(|&id| {
+        endpoint(id).is_ok_and(|ep| match node {
+            Some(node) => ep.node == Some(node),
+            None => name.is_abstract() && ep.name == *name,
+        })
+    })
+}
+
+pub fn listen(id: usize, backlog: usize, owner: UnixCred) -> Result<(), UnixError> {
+    let ep = endpoint(id)?;
+    if !ep.kind.connection_oriented() {
+        return Err(UnixError::NotSupported);
+    }
+    if ep.name.is_unnamed() || !matches!(ep.state, State::Unconnected | State::Listening) {
+        return Err(UnixError::Invalid);
+    }
+    ep.state = State::Listening;
+    ep.backlog = backlog.clamp(1, MAX_BACKLOG);
+    ep.owner = owner;
+    Ok(())
+}
+
+/// Connect `id` to `target`: queue a new server endpoint on a listener, or set a datagram destination.
+pub fn connect(id: usize, target: usize, owner: UnixCred) -> Result<(), UnixError> {
+    let ep = endpoint(id)?;
+    let kind = ep.kind;
+    if !kind.connection_oriented() {
+        if endpoint(target).map_err(|_| UnixError::ConnRefused)?.kind != kind {
+            return Err(UnixError::WrongType);
+        }
+        let ep = endpoint(id)?;
+        ep.peer = Some(target);
+        ep.state = State::Connected;
+        return Ok(());
+    }
+    match ep.state {
+        State::Unconnected => {}
+        State::Listening => return Err(UnixError::Invalid),
+        State::Connected | State::Disconnected => return Err(UnixError::IsConn),
+    }
+    let listener = endpoint(target).map_err(|_| UnixError::ConnRefused)?;
+    if listener.state != State::Listening {
+        return Err(UnixError::ConnRefused);
+    }
+    if listener.kind != kind {
+        return Err(UnixError::WrongType);
+    }
+    if listener.npending >= listener.backlog {
+        return Err(UnixError::WouldBlock);
+    }
+    let (name, listener_cred) = (listener.name, listener.owner);
+    let server = alloc_slot(kind).ok_or(UnixError::NoSpace)?;
+    let server_ep = endpoint(server)?;
+    server_ep.state = State::Connected;
+    server_ep.name = name;
+    server_ep.peer = Some(id);
+    server_ep.listener = Some(target);
+    server_ep.owner = listener_cred;
+    server_ep.peer_cred = owner;
+    let listener = endpoint(target)?;
+    listener.pending[listener.npending] = server;
+    listener.npending += 1;
+    let ep = endpoint(id)?;
+    ep.state = State::Connected;
+    ep.peer = Some(server);
+    ep.owner = owner;
+    ep.peer_cred = listener_cred;
+    wake(target);
+    Ok(())
+}
+
+/// Take the oldest queued connection off a listener.
+pub fn accept(id: usize) -> Result<usize, UnixError> {
+    let ep = endpoint(id)?;
+    if ep.state != State::Listening {
+        return Err(UnixError::Invalid);
+    }
+    if ep.npending == 0 {
+        return Err(UnixError::WouldBlock);
+    }
+    let server = ep.pending[0];
+    ep.pending.copy_within(1..ep.npending, 0);
+    ep.npending -= 1;
+    endpoint(server)?.listener = None;
+    // 腾出的 backlog 位置唤醒等待中的 connect。
+    wake(id);
+    Ok(server)
+}
+
+pub fn shutdown(id: usize, read: bool, write: bool) -> Result<(), UnixError> {
+    let ep = endpoint(id)?;
+    if ep.kind.connection_oriented() && !matches!(ep.state, State::Connected | State::Disconnected) {
+        return Err(UnixError::NotConn);
+    }
+    ep.shut_rd |= read;
+    ep.shut_wr |= write;
+    let peer = ep.peer;
+    if let Some(peer) = peer {
+        if write && ep.kind.connection_oriented() {
+            endpoint(peer)?.peer_shut_wr = true;
+        }
+        wake(peer);
+    }
+    wake(id);
+    Ok(())
+}
+
+pub fn name(id: usize) -> Result<UnixAddr, UnixError> {
+    Ok(endpoint(id)?.name)
+}
+
+pub fn peer_name(id: usize) -> Result<UnixAddr, UnixError> {
+    let peer = endpoint(id)?.peer.ok_or(UnixError::NotConn)?;
+    Ok(endpoint(peer).map_or(UnixAddr::UNNAMED, |ep| ep.name))
+}
+
+/// Credentials of the peer process (`SO_PEERCRED`).
+pub fn peer_cred(id: usize) -> Result<UnixCred, UnixError> {
+    let ep = endpoint(id)?;
+    if !ep.kind.connection_oriented() || ep.state == State::Unconnected {
+        return Err(UnixError::NotConn);
+    }
+    Ok(ep.peer_cred)
+}
+
+pub fn passcred(id: usize) -> Result<bool, UnixError> {
+    Ok(endpoint(id)?.passcred)
+}
+
+pub fn set_passcred(id: usize, on: bool) -> Result<(), UnixError> {
+    endpoint(id)?.passcred = on;
+    Ok(())
+}
+
+/// Bytes available to the next read (FIONREAD): everything queued for streams, the next message otherwise.
+pub fn pending(id: usize) -> Result<usize, UnixError> {
+    let ep = endpoint(id)?;
+    if ep.kind.keeps_boundaries() {
+        Ok(if ep.rec_count > 0 { ep.record(0).len } else { 0 })
+    } else {
+        Ok(ep.len)
+    }
+}
+
+/// Readiness bits for poll/epoll.
+pub fn poll(id: usize, events: u16) -> u16 {
+    let Ok(ep) = endpoint(id) else {
+        return POLLNVAL;
+    };
+    let mut revents = 0u16;
+    if ep.state == State::Listening {
+        if (events & POLLIN) != 0 && ep.npending > 0 {
+            revents |= POLLIN;
+        }
+        return revents;
+    }
+    let connected = ep.kind.connection_oriented();
+    let eof = ep.shut_rd || (connected && (ep.peer_shut_wr || ep.state == State::Disconnected));
+    if (events & POLLIN) != 0 && (ep.rec_count > 0 || eof) {
+        revents |= POLLIN;
+    }
+    // 与 Linux 一致：未连接或已断开的面向连接套接字报告 POLLHUP。
+    if connected && (matches!(ep.state, State::Unconnected | State::Disconnected) || (eof && ep.shut_wr)) {
+        revents |= POLLHUP;
+    }
+    let writable = if ep.shut_wr || ep.state == State::Disconnected {
+        true
+    } else if let Some(peer) = ep.peer {
+        endpoint(peer).map_or(true, |peer_ep| !peer_ep.full())
+    } else {
+        !connected
+    };
+    if (events & POLLOUT) != 0 && writable {
+        revents |= POLLOUT;
+    }
+    revents
+}
+
+/// Queue up to `len` bytes from `fill(offset, dst)` at the peer, or at `to` for datagrams.
+///
+/// Streams take what fits; datagram and seqpacket messages are queued whole or
+/// not at all. `rights` travel with the first byte and belong to the receiver
+/// once this returns `Ok`.
+pub fn send(
+    id: usize,
+    to: Option<usize>,
+    len: usize,
+    cred: UnixCred,
+    rights: &[u32],
+    mut fill: impl FnMut(usize, &mut [u8]) -> bool,
+) -> Result<usize, UnixError> {
+    let ep = endpoint(id)?;
+    let (kind, from) = (ep.kind, ep.name);
+    if ep.shut_wr {
+        return Err(UnixError::Pipe);
+    }
+    let target = if kind.connection_oriented() {
+        match (ep.state, to) {
+            (State::Connected, Some(_)) => return Err(UnixError::IsConn),
+            (_, Some(_)) => return Err(UnixError::NotSupported),
+            (State::Connected, None) => ep.peer.ok_or(UnixError::Pipe)?,
+            (State::Disconnected, None) => return Err(UnixError::Pipe),
+            _ => return Err(UnixError::NotConn),
+        }
+    } else {
+        match (to.or(ep.peer), ep.state) {
+            (Some(target), _) => target,
+            (None, State::Disconnected) => return Err(UnixError::ConnRefused),
+            (None, _) => return Err(UnixError::DestAddrReq),
+        }
+    };
+    if rights.len() > SCM_MAX_FD {
+        return Err(UnixError::Invalid);
+    }
+    if kind.keeps_boundaries() && len > MAX_MESSAGE {
+        return Err(UnixError::MsgSize);
+    }
+    if !kind.keeps_boundaries() && len == 0 {
+        return Ok(0);
+    }
+    let dst = endpoint(target).map_err(|_| UnixError::ConnRefused)?;
+    if dst.kind != kind {
+        return Err(UnixError::WrongType);
+    }
+    if dst.shut_rd {
+        return Err(UnixError::Pipe);
+    }
+    let space = dst.free_space();
+    let queued = if kind.keeps_boundaries() { len } else { len.min(space) };
+    if queued > space || (queued == 0 && len > 0) {
+        return Err(UnixError::WouldBlock);
+    }
+    // 同一发送者、不带描述符的连续流写入合并为一条记录。
+    let merge = !kind.keeps_boundaries()
+        && rights.is_empty()
+        && dst.rec_count > 0
+        && dst.record(dst.rec_count - 1).nrights == 0
+        && dst.record(dst.rec_count - 1).cred == cred;
+    if !merge && dst.rec_count == MAX_RECORDS {
+        return Err(UnixError::WouldBlock);
+    }
+    if !dst.stage(queued, &mut fill) {
+        return Err(UnixError::Fault);
+    }
+    dst.len += queued;
+    if merge {
+        dst.record_mut(dst.rec_count - 1).len += queued;
+    } else {
+        let mut record = Record {
+            len: queued,
+            from,
+            cred,
+            nrights: rights.len(),
+            rights: [0; SCM_MAX_FD],
+        };
+        record.rights[..rights.len()].copy_from_slice(rights);
+        let tail = dst.rec_count;
+        dst.rec_count += 1;
+        *dst.record_mut(tail) = record;
+    }
+    wake(target);
+    Ok(queued)
+}
+
+/// Read up to `max` bytes into `drain(offset, src)`; `Ok` with `len == 0` is end of stream.
+///
+/// Stream reads span records but stop at a change of credentials (with
+/// `SO_PASSCRED`) and around records carrying descriptors, so each batch of
+/// rights arrives with the data it was sent with. Peeked rights stay queued.
+pub fn recv(
+    id: usize,
+    max: usize,
+    peek: bool,
+    mut drain: impl FnMut(usize, &[u8]) -> bool,
+) -> Result<Received, UnixError> {
+    let ep = endpoint(id)?;
+    if ep.state == State::Listening {
+        return Err(UnixError::Invalid);
+    }
+    let connected = ep.kind.connection_oriented();
+    if connected && ep.state == State::Unconnected {
+        return Err(UnixError::NotConn);
+    }
+    let mut out = Received {
+        len: 0,
+        msg_len: 0,
+        from: UnixAddr::UNNAMED,
+        cred: None,
+        nrights: 0,
+        rights: [0; SCM_MAX_FD],
+    };
+    if ep.rec_count == 0 {
+        let eof = ep.shut_rd || (connected && (ep.peer_shut_wr || ep.state == State::Disconnected));
+        return if eof { Ok(out) } else { Err(UnixError::WouldBlock) };
+    }
+    let first = *ep.record(0);
+    out.from = first.from;
+    out.cred = ep.passcred.then_some(first.cred);
+    if ep.kind.keeps_boundaries() {
+        let len = max.min(first.len);
+        if !ep.peek(0, len, 0, &mut drain) {
+            return Err(UnixError::Fault);
+        }
+        out.len = len;
+        out.msg_len = first.len;
+        out.nrights = first.nrights;
+        out.rights = first.rights;
+        if !peek {
+            ep.consume(first.len);
+            ep.pop_record();
+        }
+    } else {
+        let mut idx = 0;
+        let mut offset = 0;
+        while out.len < max && idx < ep.rec_count {
+            let record = *ep.record(idx);
+            if out.len > 0 && (record.nrights > 0 || (ep.passcred && record.cred != first.cred)) {
+                break;
+            }
+            let len = (max - out.len).min(record.len);
+            if !ep.peek(offset, len, out.len, &mut drain) {
+                return Err(UnixError::Fault);
+            }
+            out.len += len;
+            if record.nrights > 0 {
+                out.nrights = record.nrights;
+                out.rights = record.rights;
+            }
+            if peek {
+                offset += len;
+                idx += 1;
+            } else {
+                ep.consume(len);
+                if len == record.len {
+                    ep.pop_record();
+                } else {
+                    let rest = ep.record_mut(0);
+                    rest.len -= len;
+                    rest.nrights = 0;
+                }
+            }
+            if record.nrights > 0 {
+                break;
+            }
+        }
+        out.msg_len = out.len;
+    }
+    if !peek {
+        // 接收缓冲区腾出空间，唤醒阻塞在本端的写者。
+        wake(id);
+    }
+    Ok(out)
+}
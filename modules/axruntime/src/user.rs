@@ -178,6 +178,8 @@ struct UserTimespec {
 //   子进程校验 ctid 并写入 COW 页面后 exit(42)
 //   wait4(child) -> 父进程回收并验证退出码 + COW 仍保持旧值
 //   execve("/init") -> 覆盖 ELF 解析与 argv/envp 栈布局
+// 两处 poll 沿用 a7=7 的旧编号；riscv64 上 7 是 fsetxattr，调用返回错误且结果不被检查，
+// poll 路径改由 ppoll 覆盖。
 const USER_CODE: [u8; 1052] = [
     0x13, 0x05, 0x00, 0x00, 0x93, 0x05, 0x00, 0x00, 0x13, 0x06, 0x00, 0x00, 0x93, 0x08, 0x70, 0x00,
     0x73, 0x00, 0x00, 0x00, 0x37, 0x15, 0x00, 0x40, 0x1b, 0x05, 0x05, 0x04, 0x93, 0x05, 0x00, 0x00,
@@ -1,9 +1,10 @@
 //! VirtIO block device (MMIO) driver.
 
+use core::arch::asm;
 use core::cell::UnsafeCell;
 use core::hint::spin_loop;
 use core::ptr;
-use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+use core::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 
 use axfs::block::{BlockDevice, BlockId};
 use axfs::{VfsError, VfsResult};
@@ -47,38 +48,210 @@ const STATUS_DRIVER_OK: u32 = 4;
 const STATUS_FEATURES_OK: u32 = 8;
 const STATUS_FAILED: u32 = 128;
 
-const QUEUE_SIZE: usize = 8;
+const VIRTIO_BLK_F_SIZE_MAX: u64 = 1 << 1;
+const VIRTIO_BLK_F_SEG_MAX: u64 = 1 << 2;
+const VIRTIO_BLK_F_RO: u64 = 1 << 5;
+const VIRTIO_BLK_F_BLK_SIZE: u64 = 1 << 6;
+const VIRTIO_BLK_F_FLUSH: u64 = 1 << 9;
+const VIRTIO_BLK_F_DISCARD: u64 = 1 << 13;
+const DRIVER_BLK_FEATURES: u64 = VIRTIO_BLK_F_SIZE_MAX
+    | VIRTIO_BLK_F_SEG_MAX
+    | VIRTIO_BLK_F_RO
+    | VIRTIO_BLK_F_BLK_SIZE
+    | VIRTIO_BLK_F_FLUSH
+    | VIRTIO_BLK_F_DISCARD;
+
+const MMIO_CONFIG: usize = 0x100;
+const CONFIG_CAPACITY: usize = MMIO_CONFIG;
+const CONFIG_SIZE_MAX: usize = MMIO_CONFIG + 0x08;
+const CONFIG_SEG_MAX: usize = MMIO_CONFIG + 0x0c;
+const CONFIG_BLK_SIZE: usize = MMIO_CONFIG + 0x14;
+const CONFIG_MAX_DISCARD_SECTORS: usize = MMIO_CONFIG + 0x24;
+
+const QUEUE_SIZE: usize = 64;
 const SECTOR_SIZE: usize = 512;
-const VIRTIO_BLK_WAIT_TIMEOUT_MS: u64 = 20;
+/// Bytes covered by one data descriptor at most.
+const SEGMENT_SIZE: usize = 4096;
+/// Data descriptors per request at most (scatter-gather list length).
+const MAX_SEGMENTS: usize = 4;
+const MAX_REQUEST_BYTES: usize = SEGMENT_SIZE * MAX_SEGMENTS;
+/// Outstanding requests per disk; each owns a fixed descriptor range.
+const MAX_INFLIGHT: usize = 8;
+const VIRTIO_BLK_WAIT_TIMEOUT_MS: u64 = 100;
 
 const DESC_F_NEXT: u16 = 1;
 const DESC_F_WRITE: u16 = 2;
 
 const VIRTIO_BLK_T_IN: u32 = 0;
 const VIRTIO_BLK_T_OUT: u32 = 1;
+const VIRTIO_BLK_T_FLUSH: u32 = 4;
+const VIRTIO_BLK_T_DISCARD: u32 = 11;
 
-static VIRTIO_BLK_READY: AtomicBool = AtomicBool::new(false);
-static VIRTIO_BLK_BASE: AtomicUsize = AtomicUsize::new(0);
-static VIRTIO_BLK_QUEUE_SIZE: AtomicUsize = AtomicUsize::new(0);
-static VIRTIO_BLK_USED_IDX: AtomicUsize = AtomicUsize::new(0);
-static VIRTIO_BLK_IRQ: AtomicUsize = AtomicUsize::new(0);
-// 单队列同步 I/O：inflight 保障互斥，waiters 用于 IRQ 唤醒阻塞等待者。
-static VIRTIO_BLK_INFLIGHT: AtomicBool = AtomicBool::new(false);
-static VIRTIO_BLK_WAITERS: WaitQueue = WaitQueue::new();
-static VIRTIO_QUEUE_LOCK: SpinLock = SpinLock::new();
-static VIRTIO_BLK_DEVICE: VirtioBlkDevice = VirtioBlkDevice;
-static VIRTIO_QUEUE: QueueCell = QueueCell::new();
+const SLOT_FREE: u8 = 0;
+const SLOT_PENDING: u8 = 1;
+const SLOT_DONE: u8 = 2;
 
-#[repr(align(512))]
-struct BounceBuf([u8; SECTOR_SIZE]);
+/// Maximum number of virtio-blk disks driven at once.
+pub const MAX_VIRTIO_BLK: usize = 4;
 
-// SAFETY: 单队列同步 I/O；通过锁保证同一时刻只有一个请求使用缓冲区。
-static mut VIRTIO_BLK_BOUNCE: BounceBuf = BounceBuf([0; SECTOR_SIZE]);
+struct RequestSlot {
+    state: AtomicU8,
+    status: AtomicU8,
+    waiters: WaitQueue,
+}
+
+impl RequestSlot {
+    const fn new() -> Self {
+        Self {
+            state: AtomicU8::new(SLOT_FREE),
+            status: AtomicU8::new(0),
+            waiters: WaitQueue::new(),
+        }
+    }
+}
+
+struct VirtioBlkState {
+    ready: AtomicBool,
+    base: AtomicUsize,
+    queue_size: AtomicUsize,
+    used_idx: AtomicUsize,
+    irq: AtomicUsize,
+    capacity: AtomicU64,
+    features: AtomicU64,
+    blk_size: AtomicUsize,
+    segment_size: AtomicUsize,
+    max_segments: AtomicUsize,
+    max_discard_sectors: AtomicU64,
+    // 每个请求槽固定占用 descs_per_slot 个描述符，槽位数由队列深度决定。
+    descs_per_slot: AtomicUsize,
+    slots: AtomicUsize,
+    requests: [RequestSlot; MAX_INFLIGHT],
+    // 等待空闲请求槽的任务。
+    slot_waiters: WaitQueue,
+    lock: SpinLock,
+    queue: QueueCell,
+    bounce: BounceCell,
+}
+
+impl VirtioBlkState {
+    const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const EMPTY_SLOT: RequestSlot = RequestSlot::new();
+        Self {
+            ready: AtomicBool::new(false),
+            base: AtomicUsize::new(0),
+            queue_size: AtomicUsize::new(0),
+            used_idx: AtomicUsize::new(0),
+            irq: AtomicUsize::new(0),
+            capacity: AtomicU64::new(0),
+            features: AtomicU64::new(0),
+            blk_size: AtomicUsize::new(SECTOR_SIZE),
+            segment_size: AtomicUsize::new(SECTOR_SIZE),
+            max_segments: AtomicUsize::new(1),
+            max_discard_sectors: AtomicU64::new(0),
+            descs_per_slot: AtomicUsize::new(3),
+            slots: AtomicUsize::new(0),
+            requests: [EMPTY_SLOT; MAX_INFLIGHT],
+            slot_waiters: WaitQueue::new(),
+            lock: SpinLock::new(),
+            queue: QueueCell::new(),
+            bounce: BounceCell::new(),
+        }
+    }
+
+    fn has_feature(&self, feature: u64) -> bool {
+        self.features.load(Ordering::Acquire) & feature != 0
+    }
+
+    /// Largest data transfer carried by a single request.
+    fn request_bytes(&self) -> usize {
+        self.segment_size.load(Ordering::Acquire) * self.max_segments.load(Ordering::Acquire)
+    }
+}
+
+static VIRTIO_BLK_STATES: [VirtioBlkState; MAX_VIRTIO_BLK] = [
+    VirtioBlkState::new(),
+    VirtioBlkState::new(),
+    VirtioBlkState::new(),
+    VirtioBlkState::new(),
+];
+static VIRTIO_BLK_DEVICES: [VirtioBlkDevice; MAX_VIRTIO_BLK] = [
+    VirtioBlkDevice { slot: 0 },
+    VirtioBlkDevice { slot: 1 },
+    VirtioBlkDevice { slot: 2 },
+    VirtioBlkDevice { slot: 3 },
+];
+static VIRTIO_BLK_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+#[repr(C, align(4096))]
+struct BounceBuf([[u8; MAX_REQUEST_BYTES]; MAX_INFLIGHT]);
+
+struct BounceCell {
+    inner: UnsafeCell<BounceBuf>,
+}
+
+impl BounceCell {
+    const fn new() -> Self {
+        Self {
+            inner: UnsafeCell::new(BounceBuf([[0; MAX_REQUEST_BYTES]; MAX_INFLIGHT])),
+        }
+    }
+
+    fn slot(&self, slot: usize) -> &mut [u8; MAX_REQUEST_BYTES] {
+        // SAFETY: 请求槽由 try_acquire_slot 独占分配，槽内缓冲区同一时刻只属于一个请求。
+        unsafe { &mut (*self.inner.get()).0[slot] }
+    }
+}
+
+unsafe impl Sync for BounceCell {}
+
+enum Data<'a> {
+    None,
+    Read(&'a mut [u8]),
+    Write(&'a [u8]),
+    /// Sector range carried in a `VirtioBlkDiscard` segment instead of data.
+    Discard { sector: BlockId, count: u32 },
+}
+
+impl Data<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::None | Self::Discard { .. } => 0,
+            Self::Read(buf) => buf.len(),
+            Self::Write(buf) => buf.len(),
+        }
+    }
+
+    fn discard(&self) -> Option<VirtioBlkDiscard> {
+        match *self {
+            Self::Discard { sector, count } => Some(VirtioBlkDiscard {
+                sector,
+                num_sectors: count,
+                flags: 0,
+            }),
+            _ => None,
+        }
+    }
+}
 
 /// VirtIO block device wrapper implementing BlockDevice.
-pub struct VirtioBlkDevice;
+pub struct VirtioBlkDevice {
+    slot: usize,
+}
+
+impl VirtioBlkDevice {
+    fn state(&self) -> &'static VirtioBlkState {
+        &VIRTIO_BLK_STATES[self.slot]
+    }
+
+    /// Return the disk capacity in 512-byte sectors.
+    pub fn capacity(&self) -> u64 {
+        self.state().capacity.load(Ordering::Acquire)
+    }
+}
 
 impl BlockDevice for VirtioBlkDevice {
+    /// Always 512: requests address the disk in 512-byte sectors whatever `blk_size` says.
     fn block_size(&self) -> usize {
         SECTOR_SIZE
     }
@@ -87,49 +260,103 @@ impl BlockDevice for VirtioBlkDevice {
         if buf.len() < SECTOR_SIZE {
             return Err(VfsError::Invalid);
         }
-        submit_request(VIRTIO_BLK_T_IN, block_id, &mut buf[..SECTOR_SIZE])
+        self.read_blocks(block_id, &mut buf[..SECTOR_SIZE])
     }
 
     fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
         if buf.len() < SECTOR_SIZE {
             return Err(VfsError::Invalid);
         }
-        let mut scratch = [0u8; SECTOR_SIZE];
-        scratch.copy_from_slice(&buf[..SECTOR_SIZE]);
-        submit_request(VIRTIO_BLK_T_OUT, block_id, &mut scratch)
+        self.write_blocks(block_id, &buf[..SECTOR_SIZE])
     }
 
     fn flush(&self) -> VfsResult<()> {
+        let state = self.state();
+        if !state.has_feature(VIRTIO_BLK_F_FLUSH) {
+            return Ok(());
+        }
+        transfer(state, VIRTIO_BLK_T_FLUSH, 0, Data::None)
+    }
+
+    fn read_blocks(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        transfer(self.state(), VIRTIO_BLK_T_IN, block_id, Data::Read(buf))
+    }
+
+    fn write_blocks(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+        transfer(self.state(), VIRTIO_BLK_T_OUT, block_id, Data::Write(buf))
+    }
+
+    fn discard_blocks(&self, block_id: BlockId, count: u64) -> VfsResult<()> {
+        let state = self.state();
+        if !state.has_feature(VIRTIO_BLK_F_DISCARD) {
+            return Err(VfsError::NotSupported);
+        }
+        let max = match state.max_discard_sectors.load(Ordering::Acquire) {
+            0 => u32::MAX as u64,
+            max => max,
+        };
+        let mut done = 0u64;
+        while done < count {
+            let chunk = core::cmp::min(count - done, max);
+            let data = Data::Discard {
+                sector: block_id + done,
+                count: chunk as u32,
+            };
+            // 丢弃请求头里的扇区号保留为 0，范围由 discard 段给出。
+            transfer(state, VIRTIO_BLK_T_DISCARD, 0, data)?;
+            done += chunk;
+        }
         Ok(())
     }
 }
 
-/// Initialize the virtio-blk device from DTB entries.
+/// Initialize every virtio-blk device found in the DTB entries.
 pub fn init(virtio_mmio: &[VirtioMmioDevice]) {
-    if VIRTIO_BLK_READY.load(Ordering::Acquire) {
+    if VIRTIO_BLK_COUNT.load(Ordering::Acquire) != 0 {
         return;
     }
+    let mut count = 0usize;
     for dev in virtio_mmio {
+        if count >= MAX_VIRTIO_BLK {
+            break;
+        }
         if dev.region.size == 0 {
             continue;
         }
-        if try_init_device(dev.region.base as usize, dev.irq) {
-            VIRTIO_BLK_READY.store(true, Ordering::Release);
-            break;
+        let state = &VIRTIO_BLK_STATES[count];
+        if try_init_device(state, dev.region.base as usize, dev.irq) {
+            state.ready.store(true, Ordering::Release);
+            crate::println!(
+                "virtio-blk: disk{} inflight={} seg_max={} blk_size={} flush={} discard={} ro={}",
+                count,
+                state.slots.load(Ordering::Acquire),
+                state.max_segments.load(Ordering::Acquire),
+                state.blk_size.load(Ordering::Acquire),
+                state.has_feature(VIRTIO_BLK_F_FLUSH),
+                state.has_feature(VIRTIO_BLK_F_DISCARD),
+                state.has_feature(VIRTIO_BLK_F_RO)
+            );
+            count += 1;
         }
     }
+    VIRTIO_BLK_COUNT.store(count, Ordering::Release);
+}
+
+/// Return the number of initialized virtio-blk disks.
+pub fn device_count() -> usize {
+    VIRTIO_BLK_COUNT.load(Ordering::Acquire)
 }
 
-/// Return the initialized virtio-blk device, if any.
-pub fn device() -> Option<&'static VirtioBlkDevice> {
-    if VIRTIO_BLK_READY.load(Ordering::Acquire) {
-        Some(&VIRTIO_BLK_DEVICE)
+/// Return the initialized virtio-blk disk at `index`, if any.
+pub fn device(index: usize) -> Option<&'static VirtioBlkDevice> {
+    if index < device_count() && VIRTIO_BLK_STATES[index].ready.load(Ordering::Acquire) {
+        Some(&VIRTIO_BLK_DEVICES[index])
     } else {
         None
     }
 }
 
-fn try_init_device(base: usize, irq: u32) -> bool {
+fn try_init_device(state: &VirtioBlkState, base: usize, irq: u32) -> bool {
     if mmio_read32(base, MMIO_MAGIC) != VIRTIO_MMIO_MAGIC {
         return false;
     }
@@ -146,7 +373,7 @@ fn try_init_device(base: usize, irq: u32) -> bool {
     mmio_write32(base, MMIO_STATUS, STATUS_ACKNOWLEDGE | STATUS_DRIVER);
 
     let device_features = read_device_features(base);
-    let mut driver_features: u64 = 0;
+    let mut driver_features = device_features & DRIVER_BLK_FEATURES;
     if (device_features >> 32) & (VIRTIO_F_VERSION_1 as u64) != 0 {
         driver_features |= (VIRTIO_F_VERSION_1 as u64) << 32;
     }
@@ -167,8 +394,36 @@ fn try_init_device(base: usize, irq: u32) -> bool {
     let queue_size = core::cmp::min(queue_max, QUEUE_SIZE);
     mmio_write32(base, MMIO_QUEUE_NUM, queue_size as u32);
 
-    let queue = VIRTIO_QUEUE.get();
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
+    // 按协商结果裁剪单请求的分段数与分段大小，再据此划分请求槽。
+    let seg_max = if driver_features & VIRTIO_BLK_F_SEG_MAX != 0 {
+        mmio_read32(base, CONFIG_SEG_MAX) as usize
+    } else {
+        1
+    };
+    let max_segments = seg_max.clamp(1, core::cmp::min(MAX_SEGMENTS, queue_size - 2));
+    let size_max = if driver_features & VIRTIO_BLK_F_SIZE_MAX != 0 {
+        mmio_read32(base, CONFIG_SIZE_MAX) as usize
+    } else {
+        SEGMENT_SIZE
+    };
+    let segment_size = (size_max.clamp(SECTOR_SIZE, SEGMENT_SIZE) / SECTOR_SIZE) * SECTOR_SIZE;
+    // blk_size 只是设备建议的 I/O 粒度，只用于日志：virtio-blk 请求里的扇区号恒以 512 字节为单位，
+    // 所以 block_size() 固定报告 SECTOR_SIZE，capacity 与分区表 LBA 也都按 512 字节扇区计。
+    let blk_size = if driver_features & VIRTIO_BLK_F_BLK_SIZE != 0 {
+        mmio_read32(base, CONFIG_BLK_SIZE) as usize
+    } else {
+        SECTOR_SIZE
+    };
+    let max_discard_sectors = if driver_features & VIRTIO_BLK_F_DISCARD != 0 {
+        mmio_read32(base, CONFIG_MAX_DISCARD_SECTORS) as u64
+    } else {
+        0
+    };
+    let descs_per_slot = max_segments + 2;
+    let slots = core::cmp::min(MAX_INFLIGHT, queue_size / descs_per_slot);
+
+    let queue = state.queue.get();
+    // SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
     unsafe {
         ptr::write_bytes(queue as *mut VirtioBlkQueue, 0, 1);
     }
@@ -187,143 +442,261 @@ fn try_init_device(base: usize, irq: u32) -> bool {
     let status = mmio_read32(base, MMIO_STATUS) | STATUS_DRIVER_OK;
     mmio_write32(base, MMIO_STATUS, status);
 
-    VIRTIO_BLK_BASE.store(base, Ordering::Release);
-    VIRTIO_BLK_QUEUE_SIZE.store(queue_size, Ordering::Release);
-    VIRTIO_BLK_USED_IDX.store(0, Ordering::Release);
-    VIRTIO_BLK_IRQ.store(irq as usize, Ordering::Release);
+    let capacity = mmio_read32(base, CONFIG_CAPACITY) as u64
+        | ((mmio_read32(base, CONFIG_CAPACITY + 4) as u64) << 32);
+    state.base.store(base, Ordering::Release);
+    state.queue_size.store(queue_size, Ordering::Release);
+    state.used_idx.store(0, Ordering::Release);
+    state.irq.store(irq as usize, Ordering::Release);
+    state.capacity.store(capacity, Ordering::Release);
+    state.features.store(driver_features, Ordering::Release);
+    state.blk_size.store(blk_size, Ordering::Release);
+    state.segment_size.store(segment_size, Ordering::Release);
+    state.max_segments.store(max_segments, Ordering::Release);
+    state.max_discard_sectors.store(max_discard_sectors, Ordering::Release);
+    state.descs_per_slot.store(descs_per_slot, Ordering::Release);
+    state.slots.store(slots, Ordering::Release);
     if irq != 0 {
-        plic::enable(irq);
+        plic::enable(irq, "virtio-blk");
     }
     true
 }
 
-fn submit_request(req_type: u32, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
-    if !VIRTIO_BLK_READY.load(Ordering::Acquire) {
+/// Split a transfer into requests, keep up to `slots` of them in flight and
+/// notify the device once per batch.
+fn transfer(state: &VirtioBlkState, req_type: u32, sector: BlockId, mut data: Data<'_>) -> VfsResult<()> {
+    if !state.ready.load(Ordering::Acquire) {
         return Err(VfsError::NotSupported);
     }
-    let base = VIRTIO_BLK_BASE.load(Ordering::Acquire);
-    let queue_size = VIRTIO_BLK_QUEUE_SIZE.load(Ordering::Acquire);
-    if base == 0 || queue_size == 0 {
+    if state.base.load(Ordering::Acquire) == 0 || state.slots.load(Ordering::Acquire) == 0 {
         return Err(VfsError::NotSupported);
     }
+    if req_type == VIRTIO_BLK_T_OUT && state.has_feature(VIRTIO_BLK_F_RO) {
+        return Err(VfsError::Permission);
+    }
+    let total = data.len();
+    let is_rw = matches!(req_type, VIRTIO_BLK_T_IN | VIRTIO_BLK_T_OUT);
+    if is_rw && (total == 0 || total % SECTOR_SIZE != 0) {
+        return Err(VfsError::Invalid);
+    }
+    let chunk = if is_rw { state.request_bytes() } else { total };
 
+    // 按提交顺序记录在途请求：(槽位, 数据偏移, 长度)。
+    let mut pending = [(0usize, 0usize, 0usize); MAX_INFLIGHT];
+    let mut head = 0usize;
+    let mut count = 0usize;
+    let mut submitted = 0usize;
+    let mut started = false;
+    let mut result = Ok(());
     loop {
-        if VIRTIO_BLK_INFLIGHT.load(Ordering::Acquire) {
-            wait_for_queue_event();
-            continue;
+        let mut queued = false;
+        while (submitted < total || !started) && count < MAX_INFLIGHT {
+            let Some(slot) = try_acquire_slot(state) else {
+                break;
+            };
+            let len = core::cmp::min(chunk, total - submitted);
+            if let Data::Write(buf) = &data {
+                state.bounce.slot(slot)[..len].copy_from_slice(&buf[submitted..submitted + len]);
+            }
+            let req_sector = sector + (submitted / SECTOR_SIZE) as BlockId;
+            post_request(state, slot, req_type, req_sector, len, data.discard());
+            pending[(head + count) % MAX_INFLIGHT] = (slot, submitted, len);
+            count += 1;
+            submitted += len;
+            started = true;
+            queued = true;
         }
-
-        let _guard = VIRTIO_QUEUE_LOCK.lock();
-        if VIRTIO_BLK_INFLIGHT.load(Ordering::Acquire) {
-            drop(_guard);
-            wait_for_queue_event();
+        if queued {
+            fence(Ordering::SeqCst);
+            mmio_write32(state.base.load(Ordering::Acquire), MMIO_QUEUE_NOTIFY, 0);
+        }
+        if count == 0 {
+            if started && submitted >= total {
+                break;
+            }
+            reap(state);
+            wait_for_event(state, &state.slot_waiters, || has_free_slot(state));
             continue;
         }
-        VIRTIO_BLK_INFLIGHT.store(true, Ordering::Release);
-
-        let status = {
-            let queue = VIRTIO_QUEUE.get();
-            let req = VirtioBlkReq {
-                type_: req_type,
-                reserved: 0,
-                sector: block_id,
-            };
-            queue.req = req;
-            queue.status = 0xff;
 
-        let req_addr = mm::kernel_virt_to_phys(queue.req_addr()) as u64;
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-        let buf_addr = unsafe {
-            let bounce = &mut VIRTIO_BLK_BOUNCE.0;
-            if req_type == VIRTIO_BLK_T_OUT {
-                bounce.copy_from_slice(&buf[..SECTOR_SIZE]);
+        let (slot, offset, len) = pending[head];
+        let status = wait_slot(state, slot);
+        if status == 0 {
+            if let Data::Read(buf) = &mut data {
+                buf[offset..offset + len].copy_from_slice(&state.bounce.slot(slot)[..len]);
             }
-            mm::kernel_virt_to_phys(bounce.as_ptr() as usize) as u64
-        };
-        let status_addr = mm::kernel_virt_to_phys(queue.status_addr()) as u64;
-
-            queue.desc[0] = VirtqDesc {
-                addr: req_addr,
-                len: core::mem::size_of::<VirtioBlkReq>() as u32,
-                flags: DESC_F_NEXT,
-                next: 1,
-            };
-            queue.desc[1] = VirtqDesc {
-                addr: buf_addr,
-            len: SECTOR_SIZE as u32,
-                flags: if req_type == VIRTIO_BLK_T_IN {
-                    DESC_F_WRITE | DESC_F_NEXT
-                } else {
-                    DESC_F_NEXT
-                },
-                next: 2,
-            };
-            queue.desc[2] = VirtqDesc {
-                addr: status_addr,
-                len: 1,
-                flags: DESC_F_WRITE,
-                next: 0,
-            };
-
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-        let avail_idx = unsafe { ptr::read_volatile(&queue.avail.idx) };
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-        unsafe {
-            ptr::write_volatile(
-                &mut queue.avail.ring[(avail_idx as usize) % queue_size],
-                0,
-            );
-        }
-        fence(Ordering::SeqCst);
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-        unsafe {
-            ptr::write_volatile(&mut queue.avail.idx, avail_idx.wrapping_add(1));
+        } else {
+            result = Err(VfsError::Io);
+            // 出错后不再提交新请求，只回收已在途的请求。
+            submitted = total;
         }
+        release_slot(state, slot);
+        head = (head + 1) % MAX_INFLIGHT;
+        count -= 1;
+    }
+    result
+}
 
-            fence(Ordering::SeqCst);
-            mmio_write32(base, MMIO_QUEUE_NOTIFY, 0);
+fn try_acquire_slot(state: &VirtioBlkState) -> Option<usize> {
+    let _guard = state.lock.lock();
+    let slots = state.slots.load(Ordering::Acquire);
+    let slot = state.requests[..slots]
+        .iter()
+        .position(|req| req.state.load(Ordering::Acquire) == SLOT_FREE)?;
+    state.requests[slot].state.store(SLOT_PENDING, Ordering::Release);
+    Some(slot)
+}
 
-            let mut last_used = VIRTIO_BLK_USED_IDX.load(Ordering::Acquire) as u16;
-            drop(_guard);
-            wait_for_completion(queue, &mut last_used);
+fn has_free_slot(state: &VirtioBlkState) -> bool {
+    let slots = state.slots.load(Ordering::Acquire);
+    state.requests[..slots].iter().any(|req| req.state.load(Ordering::Acquire) == SLOT_FREE)
+}
 
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-            unsafe { ptr::read_volatile(&queue.status) }
+fn release_slot(state: &VirtioBlkState, slot: usize) {
+    state.requests[slot].state.store(SLOT_FREE, Ordering::Release);
+    let _ = state.slot_waiters.notify_one();
+}
+
+/// Build the descriptor chain for `slot` and publish it in the avail ring.
+///
+/// `len` bytes of the slot's bounce buffer follow the header, or the
+/// `discard` segment when there is one.
+fn post_request(
+    state: &VirtioBlkState,
+    slot: usize,
+    req_type: u32,
+    sector: BlockId,
+    len: usize,
+    discard: Option<VirtioBlkDiscard>,
+) {
+    let _guard = state.lock.lock();
+    let queue = state.queue.get();
+    let queue_size = state.queue_size.load(Ordering::Acquire);
+    let first = slot * state.descs_per_slot.load(Ordering::Acquire);
+    let segment_size = state.segment_size.load(Ordering::Acquire);
+
+    queue.reqs[slot] = VirtioBlkReq {
+        type_: req_type,
+        reserved: 0,
+        sector,
+    };
+    queue.status[slot] = 0xff;
+    let mut desc = first;
+    queue.desc[desc] = VirtqDesc {
+        addr: mm::kernel_virt_to_phys(&queue.reqs[slot] as *const _ as usize) as u64,
+        len: core::mem::size_of::<VirtioBlkReq>() as u32,
+        flags: DESC_F_NEXT,
+        next: (desc + 1) as u16,
+    };
+    desc += 1;
+    if let Some(discard) = discard {
+        queue.discard[slot] = discard;
+        queue.desc[desc] = VirtqDesc {
+            addr: mm::kernel_virt_to_phys(&queue.discard[slot] as *const _ as usize) as u64,
+            len: core::mem::size_of::<VirtioBlkDiscard>() as u32,
+            flags: DESC_F_NEXT,
+            next: (desc + 1) as u16,
         };
+        desc += 1;
+    } else if len > 0 {
+        let bounce_addr = state.bounce.slot(slot).as_ptr() as usize;
+        let data_flags = if req_type == VIRTIO_BLK_T_IN {
+            DESC_F_WRITE | DESC_F_NEXT
+        } else {
+            DESC_F_NEXT
+        };
+        for offset in (0..len).step_by(segment_size) {
+            queue.desc[desc] = VirtqDesc {
+                addr: mm::kernel_virt_to_phys(bounce_addr + offset) as u64,
+                len: core::cmp::min(segment_size, len - offset) as u32,
+                flags: data_flags,
+                next: (desc + 1) as u16,
+            };
+            desc += 1;
+        }
+    }
+    queue.desc[desc] = VirtqDesc {
+        addr: mm::kernel_virt_to_phys(&queue.status[slot] as *const _ as usize) as u64,
+        len: 1,
+        flags: DESC_F_WRITE,
+        next: 0,
+    };
 
-        if req_type == VIRTIO_BLK_T_IN {
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-            unsafe {
-                buf[..SECTOR_SIZE].copy_from_slice(&VIRTIO_BLK_BOUNCE.0);
-            }
+    // SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
+    unsafe {
+        let avail_idx = ptr::read_volatile(&queue.avail.idx);
+        ptr::write_volatile(
+            &mut queue.avail.ring[(avail_idx as usize) % queue_size],
+            first as u16,
+        );
+        fence(Ordering::SeqCst);
+        ptr::write_volatile(&mut queue.avail.idx, avail_idx.wrapping_add(1));
+    }
+}
+
+/// Consume used-ring entries, mark their slots done and wake the owners.
+fn reap(state: &VirtioBlkState) {
+    let _guard = state.lock.lock();
+    let queue = state.queue.get();
+    let queue_size = state.queue_size.load(Ordering::Acquire);
+    let descs_per_slot = state.descs_per_slot.load(Ordering::Acquire);
+    if queue_size == 0 {
+        return;
+    }
+    // SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
+    let used_idx = unsafe { ptr::read_volatile(&queue.used.idx) };
+    let mut last_used = state.used_idx.load(Ordering::Acquire) as u16;
+    while last_used != used_idx {
+        // SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
+        let elem = unsafe { ptr::read_volatile(&queue.used.ring[last_used as usize % queue_size]) };
+        let slot = elem.id as usize / descs_per_slot;
+        if let Some(req) = state.requests.get(slot) {
+            // SAFETY: the device finished writing the status byte before publishing the used entry.
+            let status = unsafe { ptr::read_volatile(&queue.status[slot]) };
+            req.status.store(status, Ordering::Release);
+            req.state.store(SLOT_DONE, Ordering::Release);
+            let _ = req.waiters.notify_all();
         }
-        VIRTIO_BLK_INFLIGHT.store(false, Ordering::Release);
-        let _ = VIRTIO_BLK_WAITERS.notify_all();
+        last_used = last_used.wrapping_add(1);
+    }
+    state.used_idx.store(last_used as usize, Ordering::Release);
+}
 
-        if status != 0 {
-            return Err(VfsError::Io);
+fn wait_slot(state: &VirtioBlkState, slot: usize) -> u8 {
+    let req = &state.requests[slot];
+    loop {
+        if req.state.load(Ordering::Acquire) == SLOT_DONE {
+            return req.status.load(Ordering::Acquire);
+        }
+        reap(state);
+        if req.state.load(Ordering::Acquire) == SLOT_DONE {
+            continue;
         }
-        return Ok(());
+        wait_for_event(state, &req.waiters, || req.state.load(Ordering::Acquire) == SLOT_DONE);
     }
 }
 
-/// Handle a virtio-blk IRQ and wake waiting tasks.
+/// Handle a virtio-blk IRQ: reap completions and wake waiting tasks.
 pub fn handle_irq(irq: u32) -> bool {
-    let expected = VIRTIO_BLK_IRQ.load(Ordering::Acquire) as u32;
-    if expected == 0 || expected != irq {
-        return false;
-    }
-    let base = VIRTIO_BLK_BASE.load(Ordering::Acquire);
-    if base == 0 {
-        return false;
-    }
-    let status = mmio_read32(base, MMIO_INTERRUPT_STATUS);
-    if status != 0 {
-        mmio_write32(base, MMIO_INTERRUPT_ACK, status);
-        fence(Ordering::SeqCst);
+    let mut handled = false;
+    for state in VIRTIO_BLK_STATES.iter().take(device_count()) {
+        let expected = state.irq.load(Ordering::Acquire) as u32;
+        if expected == 0 || expected != irq {
+            continue;
+        }
+        let base = state.base.load(Ordering::Acquire);
+        if base == 0 {
+            continue;
+        }
+        let status = mmio_read32(base, MMIO_INTERRUPT_STATUS);
+        if status != 0 {
+            mmio_write32(base, MMIO_INTERRUPT_ACK, status);
+            fence(Ordering::SeqCst);
+        }
+        reap(state);
+        handled = true;
     }
-    let _ = VIRTIO_BLK_WAITERS.notify_all();
-    true
+    handled
 }
 
 #[repr(C, align(16))]
@@ -366,13 +739,22 @@ struct VirtioBlkReq {
     sector: u64,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct VirtioBlkDiscard {
+    sector: u64,
+    num_sectors: u32,
+    flags: u32,
+}
+
 #[repr(C, align(4096))]
 struct VirtioBlkQueue {
     desc: [VirtqDesc; QUEUE_SIZE],
     avail: VirtqAvail,
     used: VirtqUsed,
-    req: VirtioBlkReq,
-    status: u8,
+    reqs: [VirtioBlkReq; MAX_INFLIGHT],
+    discard: [VirtioBlkDiscard; MAX_INFLIGHT],
+    status: [u8; MAX_INFLIGHT],
 }
 
 struct QueueCell {
@@ -388,6 +770,16 @@ impl QueueCell {
             next: 0,
         };
         const ZERO_USED: VirtqUsedElem = VirtqUsedElem { id: 0, len: 0 };
+        const ZERO_REQ: VirtioBlkReq = VirtioBlkReq {
+            type_: 0,
+            reserved: 0,
+            sector: 0,
+        };
+        const ZERO_DISCARD: VirtioBlkDiscard = VirtioBlkDiscard {
+            sector: 0,
+            num_sectors: 0,
+            flags: 0,
+        };
         Self {
             inner: UnsafeCell::new(VirtioBlkQueue {
                 desc: [ZERO_DESC; QUEUE_SIZE],
@@ -403,18 +795,15 @@ impl QueueCell {
                     ring: [ZERO_USED; QUEUE_SIZE],
                     avail_event: 0,
                 },
-                req: VirtioBlkReq {
-                    type_: 0,
-                    reserved: 0,
-                    sector: 0,
-                },
-                status: 0,
+                reqs: [ZERO_REQ; MAX_INFLIGHT],
+                discard: [ZERO_DISCARD; MAX_INFLIGHT],
+                status: [0; MAX_INFLIGHT],
             }),
         }
     }
 
     fn get(&self) -> &mut VirtioBlkQueue {
-        // SAFETY: 仅通过全局自旋锁串行访问。
+        // SAFETY: 描述符/环由设备自旋锁串行访问，槽内数据由槽位独占。
         unsafe { &mut *self.inner.get() }
     }
 }
@@ -432,7 +821,14 @@ impl SpinLock {
         }
     }
 
+    /// Acquire the lock with supervisor interrupts masked so the IRQ path
+    /// cannot spin on a lock held by the interrupted task.
     fn lock(&self) -> SpinGuard<'_> {
+        let sstatus: usize;
+        // SAFETY: clearing SIE only masks interrupts for the critical section.
+        unsafe {
+            asm!("csrrci {0}, sstatus, 2", out(reg) sstatus);
+        }
         while self
             .locked
             .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
@@ -440,17 +836,24 @@ impl SpinLock {
         {
             spin_loop();
         }
-        SpinGuard { lock: self }
+        SpinGuard { lock: self, sstatus }
     }
 }
 
 struct SpinGuard<'a> {
     lock: &'a SpinLock,
+    sstatus: usize,
 }
 
 impl Drop for SpinGuard<'_> {
     fn drop(&mut self) {
         self.lock.locked.store(false, Ordering::Release);
+        if (self.sstatus & 2) != 0 {
+            // SAFETY: restore SIE if it was set on entry.
+            unsafe {
+                asm!("csrsi sstatus, 2");
+            }
+        }
     }
 }
 
@@ -479,10 +882,12 @@ fn write_driver_features(base: usize, features: u64) {
     mmio_write32(base, MMIO_DRIVER_FEATURES, (features >> 32) as u32);
 }
 
-fn wait_for_queue_event() {
-    if VIRTIO_BLK_IRQ.load(Ordering::Acquire) != 0 {
-        let result = VIRTIO_BLK_WAITERS.wait_timeout_ms(VIRTIO_BLK_WAIT_TIMEOUT_MS);
-        if result == WaitResult::Timeout {
+/// Sleep on `waiters` until woken, unless `done` already holds once queued.
+fn wait_for_event(state: &VirtioBlkState, waiters: &WaitQueue, done: impl Fn() -> bool) {
+    if state.irq.load(Ordering::Acquire) != 0 {
+        // 入队后再检查 done，IRQ 的唤醒不会丢；超时只防设备不再发中断。
+        let result = waiters.wait_timeout_ms_unless(VIRTIO_BLK_WAIT_TIMEOUT_MS, done);
+        if result == WaitResult::Timeout && crate::runtime::current_task_id().is_none() {
             crate::cpu::wait_for_interrupt();
         }
     } else {
@@ -490,19 +895,6 @@ fn wait_for_queue_event() {
     }
 }
 
-fn wait_for_completion(queue: &VirtioBlkQueue, last_used: &mut u16) {
-    loop {
-// SAFETY: queue/MMIO memory is mapped and protected by driver invariants.
-        let used_idx = unsafe { ptr::read_volatile(&queue.used.idx) };
-        if used_idx != *last_used {
-            *last_used = used_idx;
-            VIRTIO_BLK_USED_IDX.store(used_idx as usize, Ordering::Release);
-            break;
-        }
-        wait_for_queue_event();
-    }
-}
-
 impl VirtioBlkQueue {
     fn desc_addr(&self) -> usize {
         self.desc.as_ptr() as usize
@@ -515,12 +907,4 @@ impl VirtioBlkQueue {
     fn used_addr(&self) -> usize {
         &self.used as *const _ as usize
     }
-
-    fn req_addr(&self) -> usize {
-        &self.req as *const _ as usize
-    }
-
-    fn status_addr(&self) -> usize {
-        &self.status as *const _ as usize
-    }
 }
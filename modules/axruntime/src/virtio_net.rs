@@ -425,7 +425,7 @@ fn try_init_device(base: usize, irq: u32) -> bool {
     VIRTIO_NET_RX_USED.store(0, Ordering::Release);
     VIRTIO_NET_TX_USED.store(0, Ordering::Release);
     if irq != 0 {
-        plic::enable(irq);
+        plic::enable(irq, "virtio-net");
     }
 
     if (driver_features & VIRTIO_NET_F_MAC as u64) != 0 {
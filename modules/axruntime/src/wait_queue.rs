@@ -22,6 +22,11 @@ impl WaitQueue {
         crate::runtime::wait_timeout_ms(&self.inner, timeout_ms)
     }
 
+    /// Block with a timeout unless `done` holds once the task is on the queue.
+    pub fn wait_timeout_ms_unless(&self, timeout_ms: u64, done: impl Fn() -> bool) -> WaitResult {
+        crate::runtime::wait_timeout_ms_unless(&self.inner, timeout_ms, done)
+    }
+
     /// Wake a single waiting task, if any.
     pub fn notify_one(&self) -> bool {
         crate::runtime::wake_one(&self.inner)
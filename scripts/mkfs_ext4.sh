@@ -12,6 +12,8 @@ FS_SMOKE_ELF=${FS_SMOKE_ELF:-}
 SHELL_ELF=${SHELL_ELF:-}
 EXTRA_ROOTFS_DIR=${EXTRA_ROOTFS_DIR:-}
 INIT_ELF_SKIP_BUILD=${INIT_ELF_SKIP_BUILD:-0}
+# mke2fs: populate with `mke2fs -d`; axfs-img: format empty, then copy through the kernel's ext4 driver.
+STAGER=${STAGER:-mke2fs}
 
 if ! command -v mke2fs >/dev/null 2>&1; then
   echo "mke2fs not found; please install e2fsprogs." >&2
@@ -74,5 +76,12 @@ with open(path, "wb") as f:
     f.write(b"Z" * 8192)
 PY
 
-mke2fs -q -t ext4 -d "${WORKDIR}" -F "${OUT}" "${SIZE}"
+if [[ "${STAGER}" == "axfs-img" ]]; then
+  mke2fs -q -t ext4 -O ^metadata_csum -F "${OUT}" "${SIZE}"
+  AXFS_IMG=(cargo run -q --release --manifest-path "${ROOT}/Cargo.toml" -p axfs-img --)
+  "${AXFS_IMG[@]}" "${OUT}" put "${WORKDIR}" /
+  "${AXFS_IMG[@]}" "${OUT}" check
+else
+  mke2fs -q -t ext4 -d "${WORKDIR}" -F "${OUT}" "${SIZE}"
+fi
 echo "ext4 image created: ${OUT}"
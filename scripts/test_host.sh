@@ -4,7 +4,7 @@ set -euo pipefail
 ROOT=$(git rev-parse --show-toplevel 2>/dev/null || pwd)
 
 shopt -s nullglob
-CRATES=("${ROOT}"/crates/*)
+CRATES=("${ROOT}"/crates/* "${ROOT}"/modules/axnet "${ROOT}"/tools/axfs-img)
 
 if [[ ${#CRATES[@]} -eq 0 ]]; then
   echo "No host-testable crates yet." >&2
@@ -66,7 +66,6 @@ fi
 
 if [[ "${TCP_ECHO_TEST}" == "1" ]]; then
   USER_TEST=1
-  NET=1
   if [[ -z "${FS}" ]]; then
     TCP_ECHO_ELF="${ROOT}/build/tcp_echo.elf"
     TCP_ECHO_IMAGE="${ROOT}/build/rootfs-tcp-echo.ext4"
@@ -77,9 +76,6 @@ if [[ "${TCP_ECHO_TEST}" == "1" ]]; then
   if [[ "${EXPECT_EXT4}" == "0" ]]; then
     EXPECT_EXT4=1
   fi
-  if [[ "${EXPECT_NET}" == "0" ]]; then
-    EXPECT_NET=1
-  fi
   if [[ -z "${EXPECT_EXT4_ISSUE}" ]]; then
     EXPECT_EXT4_ISSUE=0
   fi
@@ -87,7 +83,6 @@ fi
 
 if [[ "${UDP_ECHO_TEST}" == "1" ]]; then
   USER_TEST=1
-  NET=1
   if [[ -z "${FS}" ]]; then
     UDP_ECHO_ELF="${ROOT}/build/udp_echo.elf"
     UDP_ECHO_IMAGE="${ROOT}/build/rootfs-udp-echo.ext4"
@@ -98,9 +93,6 @@ if [[ "${UDP_ECHO_TEST}" == "1" ]]; then
   if [[ "${EXPECT_EXT4}" == "0" ]]; then
     EXPECT_EXT4=1
   fi
-  if [[ "${EXPECT_NET}" == "0" ]]; then
-    EXPECT_NET=1
-  fi
   if [[ -z "${EXPECT_EXT4_ISSUE}" ]]; then
     EXPECT_EXT4_ISSUE=0
   fi
@@ -306,6 +298,11 @@ if [[ "${EXPECT_NET}" == "1" ]]; then
     cat "${LOG_FILE}" >&2
     exit 1
   fi
+  if ! grep -q "net: eth0 10.0.2.15/24 gw 10.0.2.2 (dhcp)" "${LOG_FILE}"; then
+    echo "Smoke test failed: DHCP lease banner not found." >&2
+    cat "${LOG_FILE}" >&2
+    exit 1
+  fi
   if ! grep -q "net: arp reply from 10.0.2.2" "${LOG_FILE}"; then
     echo "Smoke test failed: ARP reply not found." >&2
     cat "${LOG_FILE}" >&2
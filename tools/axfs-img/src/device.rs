@@ -0,0 +1,65 @@
+//! File-backed block device so the kernel drivers can run against image files.
+
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use axfs::block::{BlockDevice, BlockId};
+use axfs::{VfsError, VfsResult};
+
+/// Image file exposed as a block device with a fixed block size.
+pub struct FileDevice {
+    file: RefCell<File>,
+    block_size: usize,
+    blocks: u64,
+}
+
+impl FileDevice {
+    /// Open `path`; read-only devices fail every write with `VfsError::Io`.
+    pub fn open(path: &Path, block_size: usize, writable: bool) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(writable).open(path)?;
+        let blocks = file.metadata()?.len() / block_size as u64;
+        Ok(Self {
+            file: RefCell::new(file),
+            block_size,
+            blocks,
+        })
+    }
+
+    /// Number of whole blocks in the image.
+    pub fn blocks(&self) -> u64 {
+        self.blocks
+    }
+
+    fn seek_block(&self, file: &mut File, block_id: BlockId) -> VfsResult<()> {
+        if block_id >= self.blocks {
+            return Err(VfsError::Io);
+        }
+        file.seek(SeekFrom::Start(block_id * self.block_size as u64))
+            .map(|_| ())
+            .map_err(|_| VfsError::Io)
+    }
+}
+
+impl BlockDevice for FileDevice {
+    fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    fn read_block(&self, block_id: BlockId, buf: &mut [u8]) -> VfsResult<()> {
+        let mut file = self.file.borrow_mut();
+        self.seek_block(&mut file, block_id)?;
+        file.read_exact(&mut buf[..self.block_size]).map_err(|_| VfsError::Io)
+    }
+
+    fn write_block(&self, block_id: BlockId, buf: &[u8]) -> VfsResult<()> {
+        let mut file = self.file.borrow_mut();
+        self.seek_block(&mut file, block_id)?;
+        file.write_all(&buf[..self.block_size]).map_err(|_| VfsError::Io)
+    }
+
+    fn flush(&self) -> VfsResult<()> {
+        self.file.borrow_mut().flush().map_err(|_| VfsError::Io)
+    }
+}
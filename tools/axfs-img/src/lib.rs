@@ -0,0 +1,604 @@
+//! Host-side access to ext4 and FAT32 images through the kernel's axfs drivers.
+//!
+//! Every command opens the image with the same `Ext4Fs`/`Fat32Fs` code the
+//! kernel mounts, resolves paths through `axfs::mount::MountTable` and flushes
+//! the drivers before returning, so staging an image here exercises the
+//! kernel's writer.
+
+pub mod device;
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+use axfs::ext4::Ext4Fs;
+use axfs::fat32::{Bpb, Fat32Fs};
+use axfs::mount::{MountId, MountPoint, MountTable};
+use axfs::{DirEntry, FileType, InodeId, Metadata, VfsError, VfsOps};
+
+use device::FileDevice;
+
+const EXT4_MAGIC_OFFSET: usize = 1024 + 56;
+const EXT4_MAGIC: u16 = 0xef53;
+/// Sector size used for ext4 images, matching virtio-blk.
+const EXT4_SECTOR_SIZE: usize = 512;
+const COPY_CHUNK: usize = 64 * 1024;
+const DIR_BATCH: usize = 16;
+const MAX_CHECK_DEPTH: usize = 64;
+
+/// Errors reported by image commands.
+#[derive(Debug)]
+pub enum Error {
+    /// Host file I/O failed.
+    Io(io::Error),
+    /// The filesystem driver rejected the operation.
+    Vfs(VfsError),
+    /// Neither an ext4 superblock nor a FAT32 boot sector was found.
+    UnknownImage,
+    /// Bad command-line usage.
+    Usage(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "{}", err),
+            Error::Vfs(err) => write!(f, "filesystem error: {:?}", err),
+            Error::UnknownImage => write!(f, "not an ext4 or FAT32 image"),
+            Error::Usage(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<VfsError> for Error {
+    fn from(err: VfsError) -> Self {
+        Error::Vfs(err)
+    }
+}
+
+/// Result type for image commands.
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Filesystem found in an image.
+pub enum FsKind {
+    Ext4,
+    Fat32,
+}
+
+/// An image file opened on a block device of the right sector size.
+pub struct Image {
+    device: FileDevice,
+    kind: FsKind,
+}
+
+impl Image {
+    /// Detect the filesystem in `path` and open it.
+    pub fn open(path: &Path, writable: bool) -> Result<Self> {
+        let mut head = [0u8; 2048];
+        let len = File::open(path)?.read(&mut head)?;
+        let (kind, block_size) = detect(&head[..len])?;
+        let device = FileDevice::open(path, block_size, writable)?;
+        Ok(Self { device, kind })
+    }
+
+    /// Filesystem type of the image.
+    pub fn kind(&self) -> FsKind {
+        self.kind
+    }
+
+    /// Mount the image, run `f` against it and flush the driver afterwards.
+    pub fn with_volume<R>(&self, f: impl FnOnce(&Volume<'_>) -> Result<R>) -> Result<R> {
+        let volume = match self.kind {
+            FsKind::Ext4 => Volume::Ext4(Ext4Fs::new(&self.device)?),
+            FsKind::Fat32 => Volume::Fat32(Fat32Fs::new(&self.device)?),
+        };
+        let result = f(&volume)?;
+        volume.fs().flush()?;
+        Ok(result)
+    }
+}
+
+fn detect(head: &[u8]) -> Result<(FsKind, usize)> {
+    if head.len() >= EXT4_MAGIC_OFFSET + 2
+        && u16::from_le_bytes([head[EXT4_MAGIC_OFFSET], head[EXT4_MAGIC_OFFSET + 1]]) == EXT4_MAGIC
+    {
+        return Ok((FsKind::Ext4, EXT4_SECTOR_SIZE));
+    }
+    match Bpb::parse(head) {
+        Ok(bpb) => Ok((FsKind::Fat32, bpb.bytes_per_sector as usize)),
+        Err(_) => Err(Error::UnknownImage),
+    }
+}
+
+/// A mounted image.
+pub enum Volume<'a> {
+    Ext4(Ext4Fs<'a>),
+    Fat32(Fat32Fs<'a>),
+}
+
+impl Volume<'_> {
+    /// The driver as a generic VFS backend.
+    pub fn fs(&self) -> &dyn VfsOps {
+        match self {
+            Volume::Ext4(fs) => fs,
+            Volume::Fat32(fs) => fs,
+        }
+    }
+
+    fn mounts(&self) -> MountTable<'_, 1> {
+        MountTable::new([MountPoint::new(MountId::Root, "/", self.fs())])
+    }
+
+    fn resolve(&self, path: &str) -> Result<InodeId> {
+        Ok(self.mounts().resolve_path(path)?.1)
+    }
+
+    fn resolve_parent<'p>(&self, path: &'p str) -> Result<(InodeId, &'p str)> {
+        let (_, parent, name) = self.mounts().resolve_parent(path)?;
+        Ok((parent, name))
+    }
+}
+
+/// Print the entries of a directory, one per line, in `ls -l` style.
+pub fn ls(volume: &Volume<'_>, path: &str, out: &mut dyn Write) -> Result<()> {
+    let fs = volume.fs();
+    let inode = volume.resolve(path)?;
+    let meta = fs.metadata(inode)?;
+    if meta.file_type != FileType::Dir {
+        writeln!(out, "{}", format_entry(&meta, path))?;
+        return Ok(());
+    }
+    for entry in read_dir_all(fs, inode)? {
+        let name = String::from_utf8_lossy(entry.name()).into_owned();
+        let meta = fs.metadata(entry.ino)?;
+        writeln!(out, "{}", format_entry(&meta, &name))?;
+    }
+    Ok(())
+}
+
+/// Copy a file's contents to `out`.
+pub fn cat(volume: &Volume<'_>, path: &str, out: &mut dyn Write) -> Result<()> {
+    let fs = volume.fs();
+    let inode = volume.resolve(path)?;
+    let meta = fs.metadata(inode)?;
+    if meta.file_type == FileType::Dir {
+        return Err(Error::Vfs(VfsError::NotDir));
+    }
+    copy_out(fs, inode, meta.size, out)?;
+    Ok(())
+}
+
+/// Print the metadata of one path.
+pub fn stat(volume: &Volume<'_>, path: &str, out: &mut dyn Write) -> Result<()> {
+    let inode = volume.resolve(path)?;
+    let meta = volume.fs().metadata(inode)?;
+    writeln!(out, "  File: {}", path)?;
+    writeln!(out, "  Type: {}", type_name(meta.file_type))?;
+    writeln!(out, " Inode: {:#x}", inode)?;
+    writeln!(out, "  Size: {}", meta.size)?;
+    writeln!(out, "  Mode: {:04o} ({})", meta.mode, mode_string(&meta))?;
+    writeln!(out, "   Uid: {}  Gid: {}", meta.uid, meta.gid)?;
+    if matches!(meta.file_type, FileType::Char | FileType::Block) {
+        writeln!(out, "  Rdev: {}:{}", axvfs::dev_major(meta.rdev), axvfs::dev_minor(meta.rdev))?;
+    }
+    Ok(())
+}
+
+/// Copy a file out of the image to `host`.
+pub fn get(volume: &Volume<'_>, path: &str, host: &Path) -> Result<()> {
+    let mut file = File::create(host)?;
+    cat(volume, path, &mut file)
+}
+
+/// Copy `host` into the image at `path`; directories are copied recursively.
+///
+/// Existing files are truncated and overwritten, missing directories created.
+pub fn put(volume: &Volume<'_>, host: &Path, path: &str) -> Result<()> {
+    let host_meta = fs::metadata(host)?;
+    let mode = (host_meta.permissions().mode() & 0o7777) as u16;
+    if host_meta.is_dir() {
+        mkdir_one(volume, path, mode, true)?;
+        let mut children: Vec<_> = fs::read_dir(host)?.collect::<io::Result<_>>()?;
+        children.sort_by_key(|child| child.file_name());
+        for child in children {
+            let name = child.file_name();
+            let name = name.to_str().ok_or_else(|| Error::Usage(format!("non-UTF-8 name in {}", host.display())))?;
+            let child_path = join(path, name);
+            put(volume, &child.path(), &child_path)?;
+        }
+        return Ok(());
+    }
+    let fs = volume.fs();
+    let (parent, name) = volume.resolve_parent(path)?;
+    let inode = match fs.lookup(parent, name)? {
+        Some(inode) => {
+            if fs.metadata(inode)?.file_type == FileType::Dir {
+                return Err(Error::Vfs(VfsError::AlreadyExists));
+            }
+            fs.truncate(inode, 0)?;
+            inode
+        }
+        None => fs.create(parent, name, FileType::File, mode)?,
+    };
+    let mut file = File::open(host)?;
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut offset = 0u64;
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        let mut done = 0usize;
+        while done < read {
+            let written = fs.write_at(inode, offset, &buf[done..read])?;
+            if written == 0 {
+                return Err(Error::Vfs(VfsError::NoSpace));
+            }
+            done += written;
+            offset += written as u64;
+        }
+    }
+    Ok(())
+}
+
+/// Create a directory; with `parents`, create missing ancestors and accept existing ones.
+pub fn mkdir(volume: &Volume<'_>, path: &str, parents: bool) -> Result<()> {
+    if parents {
+        let mut prefix = String::new();
+        for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+            prefix.push('/');
+            prefix.push_str(segment);
+            mkdir_one(volume, &prefix, 0o755, true)?;
+        }
+        return Ok(());
+    }
+    mkdir_one(volume, path, 0o755, false).map(|_| ())
+}
+
+fn mkdir_one(volume: &Volume<'_>, path: &str, mode: u16, exist_ok: bool) -> Result<InodeId> {
+    if path.trim_end_matches('/').is_empty() {
+        return volume.resolve("/");
+    }
+    let fs = volume.fs();
+    let (parent, name) = volume.resolve_parent(path)?;
+    if let Some(inode) = fs.lookup(parent, name)? {
+        if exist_ok && fs.metadata(inode)?.file_type == FileType::Dir {
+            return Ok(inode);
+        }
+        return Err(Error::Vfs(VfsError::AlreadyExists));
+    }
+    Ok(fs.create(parent, name, FileType::Dir, mode)?)
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+/// Summary of a consistency check.
+pub struct CheckReport {
+    /// Directories visited, including the root.
+    pub dirs: usize,
+    /// Non-directory inodes visited.
+    pub files: usize,
+    /// Bytes read back from regular files.
+    pub bytes: u64,
+    /// Problems found; each one was also printed.
+    pub problems: usize,
+}
+
+/// Walk the whole tree and compare the allocation counters with the bitmaps or FAT.
+///
+/// Every entry must resolve through `lookup` to the inode `read_dir` reported,
+/// every regular file must read back exactly its size, and no directory may be
+/// reachable twice.
+pub fn check(volume: &Volume<'_>, out: &mut dyn Write) -> Result<CheckReport> {
+    let fs = volume.fs();
+    let mut report = CheckReport::default();
+    let mut seen = HashSet::new();
+    let root = fs.root()?;
+    check_dir(fs, root, "/", 0, &mut seen, &mut report, out)?;
+    match volume {
+        Volume::Ext4(ext4) => {
+            let usage = ext4.usage()?;
+            if usage.sb_free_blocks != usage.bitmap_free_blocks {
+                problem(
+                    &mut report,
+                    out,
+                    format_args!(
+                        "superblock free blocks {} != bitmap count {}",
+                        usage.sb_free_blocks, usage.bitmap_free_blocks
+                    ),
+                )?;
+            }
+            if usage.sb_free_inodes != usage.bitmap_free_inodes {
+                problem(
+                    &mut report,
+                    out,
+                    format_args!(
+                        "superblock free inodes {} != bitmap count {}",
+                        usage.sb_free_inodes, usage.bitmap_free_inodes
+                    ),
+                )?;
+            }
+        }
+        Volume::Fat32(fat) => {
+            let usage = fat.usage()?;
+            if let Some(hint) = usage.fs_info_free {
+                if hint != usage.scanned_free {
+                    problem(
+                        &mut report,
+                        out,
+                        format_args!("FSInfo free clusters {} != FAT scan {}", hint, usage.scanned_free),
+                    )?;
+                }
+            }
+        }
+    }
+    writeln!(
+        out,
+        "{} directories, {} files, {} bytes, {} problems",
+        report.dirs, report.files, report.bytes, report.problems
+    )?;
+    Ok(report)
+}
+
+fn check_dir(
+    fs: &dyn VfsOps,
+    dir: InodeId,
+    path: &str,
+    depth: usize,
+    seen: &mut HashSet<InodeId>,
+    report: &mut CheckReport,
+    out: &mut dyn Write,
+) -> Result<()> {
+    report.dirs += 1;
+    if !seen.insert(dir) {
+        return problem(report, out, format_args!("{}: directory reachable twice", path));
+    }
+    if depth >= MAX_CHECK_DEPTH {
+        return problem(report, out, format_args!("{}: deeper than {} levels", path, MAX_CHECK_DEPTH));
+    }
+    let entries = match read_dir_all(fs, dir) {
+        Ok(entries) => entries,
+        Err(err) => return problem(report, out, format_args!("{}: read_dir failed: {}", path, err)),
+    };
+    for entry in entries {
+        let name = String::from_utf8_lossy(entry.name()).into_owned();
+        let child_path = join(path, &name);
+        match fs.lookup(dir, &name) {
+            Ok(Some(ino)) if ino == entry.ino => {}
+            Ok(found) => {
+                let ino = entry.ino;
+                problem(report, out, format_args!("{child_path}: lookup returned {found:?}, read_dir {ino:#x}"))?;
+                continue;
+            }
+            Err(err) => {
+                problem(report, out, format_args!("{}: lookup failed: {:?}", child_path, err))?;
+                continue;
+            }
+        }
+        let meta = match fs.metadata(entry.ino) {
+            Ok(meta) => meta,
+            Err(err) => {
+                problem(report, out, format_args!("{}: metadata failed: {:?}", child_path, err))?;
+                continue;
+            }
+        };
+        if meta.file_type != entry.file_type {
+            problem(
+                report,
+                out,
+                format_args!("{}: entry type {:?} but inode type {:?}", child_path, entry.file_type, meta.file_type),
+            )?;
+        }
+        match meta.file_type {
+            FileType::Dir => check_dir(fs, entry.ino, &child_path, depth + 1, seen, report, out)?,
+            FileType::File => {
+                report.files += 1;
+                let mut sink = io::sink();
+                match copy_out(fs, entry.ino, meta.size, &mut sink) {
+                    Ok(read) if read == meta.size => report.bytes += read,
+                    Ok(read) => {
+                        problem(report, out, format_args!("{}: size {} but read {}", child_path, meta.size, read))?
+                    }
+                    Err(err) => problem(report, out, format_args!("{}: read failed: {}", child_path, err))?,
+                }
+            }
+            _ => report.files += 1,
+        }
+    }
+    Ok(())
+}
+
+fn problem(report: &mut CheckReport, out: &mut dyn Write, msg: fmt::Arguments<'_>) -> Result<()> {
+    report.problems += 1;
+    writeln!(out, "problem: {}", msg)?;
+    Ok(())
+}
+
+fn read_dir_all(fs: &dyn VfsOps, inode: InodeId) -> Result<Vec<DirEntry>> {
+    let mut all = Vec::new();
+    let mut batch = [DirEntry::empty(); DIR_BATCH];
+    let mut offset = 0usize;
+    loop {
+        let count = fs.read_dir(inode, offset, &mut batch)?;
+        if count == 0 {
+            break;
+        }
+        all.extend(batch[..count].iter().filter(|entry| entry.name() != b"." && entry.name() != b".."));
+        offset += count;
+    }
+    Ok(all)
+}
+
+fn copy_out(fs: &dyn VfsOps, inode: InodeId, size: u64, out: &mut dyn Write) -> Result<u64> {
+    let mut buf = vec![0u8; COPY_CHUNK];
+    let mut offset = 0u64;
+    while offset < size {
+        let want = (size - offset).min(COPY_CHUNK as u64) as usize;
+        let read = fs.read_at(inode, offset, &mut buf[..want])?;
+        if read == 0 {
+            break;
+        }
+        out.write_all(&buf[..read])?;
+        offset += read as u64;
+    }
+    Ok(offset)
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir.ends_with('/') {
+        format!("{}{}", dir, name)
+    } else {
+        format!("{}/{}", dir, name)
+    }
+}
+
+fn type_name(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::File => "regular file",
+        FileType::Dir => "directory",
+        FileType::Char => "character device",
+        FileType::Block => "block device",
+        FileType::Fifo => "fifo",
+        FileType::Socket => "socket",
+        FileType::Symlink => "symbolic link",
+    }
+}
+
+fn mode_string(meta: &Metadata) -> String {
+    let kind = match meta.file_type {
+        FileType::File => '-',
+        FileType::Dir => 'd',
+        FileType::Char => 'c',
+        FileType::Block => 'b',
+        FileType::Fifo => 'p',
+        FileType::Socket => 's',
+        FileType::Symlink => 'l',
+    };
+    let mut text = String::with_capacity(10);
+    text.push(kind);
+    for shift in [6u16, 3, 0] {
+        let bits = (meta.mode >> shift) & 0o7;
+        text.push(if bits & 4 != 0 { 'r' } else { '-' });
+        text.push(if bits & 2 != 0 { 'w' } else { '-' });
+        text.push(if bits & 1 != 0 { 'x' } else { '-' });
+    }
+    text
+}
+
+fn format_entry(meta: &Metadata, name: &str) -> String {
+    format!("{} {:>5} {:>5} {:>10} {}", mode_string(meta), meta.uid, meta.gid, meta.size, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(tag: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("axfs-img-{}-{}", tag, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn read_back(image: &Image, path: &str) -> Vec<u8> {
+        let mut out = Vec::new();
+        image.with_volume(|volume| cat(volume, path, &mut out)).unwrap();
+        out
+    }
+
+    fn clean_check(image: &Image) -> CheckReport {
+        let mut log = Vec::new();
+        let report = image.with_volume(|volume| check(volume, &mut log)).unwrap();
+        assert_eq!(report.problems, 0, "{}", String::from_utf8_lossy(&log));
+        report
+    }
+
+    #[test]
+    fn fat32_overwrite_and_check() {
+        let scratch = Scratch::new("fat");
+        let mut buf = vec![0u8; 64 * 1024];
+        let len = axfs::fat32::build_minimal_image(&mut buf, "init", b"old").unwrap();
+        let img = scratch.path("fat.img");
+        fs::write(&img, &buf[..len]).unwrap();
+        let payload: Vec<u8> = (0..1500u32).map(|i| i as u8).collect();
+        let host = scratch.path("init");
+        fs::write(&host, &payload).unwrap();
+
+        let image = Image::open(&img, true).unwrap();
+        assert_eq!(image.kind(), FsKind::Fat32);
+        image.with_volume(|volume| put(volume, &host, "/init")).unwrap();
+        assert_eq!(read_back(&image, "/init"), payload);
+        let report = clean_check(&image);
+        assert_eq!((report.files, report.bytes), (2, payload.len() as u64));
+        // FAT32 写路径只覆盖已有文件，新建项应如实报错。
+        let err = image.with_volume(|volume| mkdir(volume, "/d", false)).unwrap_err();
+        assert!(matches!(err, Error::Vfs(VfsError::NotSupported)));
+
+        let reopened = Image::open(&img, false).unwrap();
+        assert_eq!(read_back(&reopened, "/init"), payload);
+        image.with_volume(|volume| get(volume, "/init", &scratch.path("out"))).unwrap();
+        assert_eq!(fs::read(scratch.path("out")).unwrap(), payload);
+    }
+
+    #[test]
+    fn ext4_stage_tree_and_check() {
+        let scratch = Scratch::new("ext4");
+        let img = scratch.path("rootfs.ext4");
+        let made = Command::new("mke2fs")
+            .args(["-q", "-F", "-t", "ext4", "-b", "1024"])
+            .arg(&img)
+            .arg("4M")
+            .status();
+        if !made.is_ok_and(|status| status.success()) {
+            eprintln!("mke2fs not available, skipping");
+            return;
+        }
+        let tree = scratch.path("tree");
+        fs::create_dir_all(tree.join("etc")).unwrap();
+        fs::write(tree.join("etc/hostname"), b"axos\n").unwrap();
+        let big: Vec<u8> = (0..70_000u32).map(|i| (i * 7) as u8).collect();
+        fs::write(tree.join("busybox"), &big).unwrap();
+
+        let image = Image::open(&img, true).unwrap();
+        assert_eq!(image.kind(), FsKind::Ext4);
+        image.with_volume(|volume| mkdir(volume, "/usr/local/bin", true)).unwrap();
+        image.with_volume(|volume| put(volume, &tree, "/stage")).unwrap();
+        let err = image.with_volume(|volume| mkdir(volume, "/stage/etc", false)).unwrap_err();
+        assert!(matches!(err, Error::Vfs(VfsError::AlreadyExists)));
+
+        let reopened = Image::open(&img, false).unwrap();
+        assert_eq!(read_back(&reopened, "/stage/etc/hostname"), b"axos\n");
+        assert_eq!(read_back(&reopened, "/stage/busybox"), big);
+        let mut listing = Vec::new();
+        reopened.with_volume(|volume| ls(volume, "/usr/local", &mut listing)).unwrap();
+        assert!(String::from_utf8(listing).unwrap().contains(" bin\n"));
+        let report = clean_check(&reopened);
+        assert!(report.dirs >= 7);
+        assert_eq!(report.bytes, big.len() as u64 + 5);
+    }
+}
@@ -0,0 +1,63 @@
+//! `axfs-img`: inspect and stage ext4/FAT32 images with the kernel's filesystem drivers.
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
+use axfs_img::{Error, Image, Result};
+
+const USAGE: &str = "usage: axfs-img IMAGE COMMAND [ARGS]
+
+commands:
+  ls [PATH]             list a directory (default /)
+  cat PATH              write a file to stdout
+  stat PATH             show file metadata
+  get PATH HOST_FILE    copy a file out of the image
+  put HOST_PATH PATH    copy a host file or directory tree into the image
+  mkdir [-p] PATH       create a directory
+  check                 walk the tree and verify allocation counters";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(Error::Usage(msg)) => {
+            eprintln!("{}\n\n{}", msg, USAGE);
+            ExitCode::from(2)
+        }
+        Err(err) => {
+            eprintln!("axfs-img: {}", err);
+            ExitCode::from(1)
+        }
+    }
+}
+
+/// Run one command; `Ok(false)` means `check` found problems.
+fn run(args: &[String]) -> Result<bool> {
+    let (image, command, rest) = match args {
+        [image, command, rest @ ..] => (image, command.as_str(), rest),
+        _ => return Err(Error::Usage("missing image or command".into())),
+    };
+    let writable = matches!(command, "put" | "mkdir");
+    let image = Image::open(Path::new(image), writable)?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let ok = image.with_volume(|volume| {
+        match (command, rest) {
+            ("ls", []) => axfs_img::ls(volume, "/", &mut out)?,
+            ("ls", [path]) => axfs_img::ls(volume, path, &mut out)?,
+            ("cat", [path]) => axfs_img::cat(volume, path, &mut out)?,
+            ("stat", [path]) => axfs_img::stat(volume, path, &mut out)?,
+            ("get", [path, host]) => axfs_img::get(volume, path, Path::new(host))?,
+            ("put", [host, path]) => axfs_img::put(volume, Path::new(host), path)?,
+            ("mkdir", [path]) => axfs_img::mkdir(volume, path, false)?,
+            ("mkdir", [flag, path]) if flag == "-p" => axfs_img::mkdir(volume, path, true)?,
+            ("check", []) => return Ok(axfs_img::check(volume, &mut out)?.problems == 0),
+            _ => return Err(Error::Usage(format!("bad arguments for '{}'", command))),
+        }
+        Ok(true)
+    })?;
+    out.flush()?;
+    Ok(ok)
+}